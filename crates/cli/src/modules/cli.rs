@@ -128,6 +128,45 @@ pub enum DatabaseCommands {
 
     /// Purge expired blobs
     Purge {},
+
+    /// Create a backup of the entire server state
+    Backup {
+        /// Path on the server where the backup archive will be written
+        path: String,
+    },
+
+    /// Restore the server state from a backup archive
+    Restore {
+        /// Path on the server of the backup archive to restore
+        path: String,
+    },
+
+    /// Export all the data of an account to an archive
+    ExportAccount {
+        /// Account name to export
+        account: String,
+
+        /// Path on the server where the account archive will be written
+        path: String,
+    },
+
+    /// Import an account archive into an empty account
+    ImportAccount {
+        /// Account name to import the archive into
+        account: String,
+
+        /// Path on the server of the account archive to import
+        path: String,
+    },
+
+    /// Rebuild the full-text index of an account, or all accounts if not specified
+    Reindex {
+        /// Account name to reindex
+        account: Option<String>,
+    },
+
+    /// Show the progress of the full-text index rebuild
+    ReindexStatus {},
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
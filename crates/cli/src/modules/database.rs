@@ -27,6 +27,7 @@ use reqwest::header::AUTHORIZATION;
 use super::{cli::DatabaseCommands, is_localhost, UnwrapResult};
 
 pub async fn cmd_database(url: &str, credentials: Credentials, command: DatabaseCommands) {
+    let print_response = matches!(command, DatabaseCommands::ReindexStatus {});
     let url = match command {
         DatabaseCommands::Delete { account } => format!("{}/admin/account/delete/{}", url, account),
         DatabaseCommands::Rename {
@@ -34,6 +35,34 @@ pub async fn cmd_database(url: &str, credentials: Credentials, command: Database
             new_account,
         } => format!("{}/admin/account/rename/{}/{}", url, account, new_account),
         DatabaseCommands::Purge {} => format!("{}/admin/blob/purge", url),
+        DatabaseCommands::Backup { path } => {
+            form_urlencoded::Serializer::new(format!("{url}/admin/store/backup?"))
+                .append_pair("path", &path)
+                .finish()
+        }
+        DatabaseCommands::Restore { path } => {
+            form_urlencoded::Serializer::new(format!("{url}/admin/store/restore?"))
+                .append_pair("path", &path)
+                .finish()
+        }
+        DatabaseCommands::ExportAccount { account, path } => {
+            form_urlencoded::Serializer::new(format!("{url}/admin/account/export/{account}?"))
+                .append_pair("path", &path)
+                .finish()
+        }
+        DatabaseCommands::ImportAccount { account, path } => {
+            form_urlencoded::Serializer::new(format!("{url}/admin/account/import/{account}?"))
+                .append_pair("path", &path)
+                .finish()
+        }
+        DatabaseCommands::Reindex { account } => {
+            if let Some(account) = account {
+                format!("{}/admin/fts/reindex/{}", url, account)
+            } else {
+                format!("{}/admin/fts/reindex", url)
+            }
+        }
+        DatabaseCommands::ReindexStatus {} => format!("{}/admin/fts/status", url),
     };
 
     let response = reqwest::Client::builder()
@@ -52,7 +81,11 @@ pub async fn cmd_database(url: &str, credentials: Credentials, command: Database
         .await
         .unwrap_result("send GET request");
     if response.status().is_success() {
-        eprintln!("Success.");
+        if print_response {
+            println!("{}", response.text().await.unwrap_result("fetch text"));
+        } else {
+            eprintln!("Success.");
+        }
     } else {
         eprintln!(
             "Request Failed: {}",
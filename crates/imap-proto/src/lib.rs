@@ -87,6 +87,27 @@ pub enum Command {
     // RFC 8437
     Unauthenticate,
 
+    // RFC 5464
+    GetMetadata,
+    SetMetadata,
+
+    // RFC 5465
+    Notify,
+
+    // RFC 4978
+    Compress,
+
+    // RFC 4467
+    GenUrlAuth,
+    ResetKey,
+    UrlFetch,
+
+    // RFC 8508
+    Replace(bool),
+
+    // RFC 7377
+    Esearch,
+
     // RFC 2971
     Id,
 }
@@ -103,6 +124,7 @@ impl Command {
                 | Command::Expunge(true)
                 | Command::Sort(true)
                 | Command::Thread(true)
+                | Command::Replace(true)
         )
     }
 }
@@ -167,6 +189,35 @@ pub enum ResponseCode {
 
     // USEATTR
     UseAttr,
+
+    // METADATA
+    MetadataLongEntries {
+        size: usize,
+    },
+    MetadataMaxSize {
+        size: usize,
+    },
+    MetadataTooMany,
+    MetadataNoPrivate,
+
+    // NOTIFY
+    BadEvent,
+
+    // COMPRESS
+    CompressionActive,
+
+    // CATENATE
+    BadUrl {
+        url: String,
+    },
+    TooBig,
+
+    // INPROGRESS
+    InProgress {
+        tag: String,
+        current: Option<u32>,
+        total: Option<u32>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
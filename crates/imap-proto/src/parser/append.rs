@@ -23,7 +23,7 @@
 
 use crate::{
     protocol::{
-        append::{self, Message},
+        append::{self, CatenatePart, Message},
         Flag,
     },
     receiver::{Request, Token},
@@ -68,25 +68,67 @@ impl Request<Command> {
                         }
                         token => token,
                     };
-                    let (message, received_at) = if tokens.peek().is_some() {
-                        let token_bytes = token.unwrap_bytes();
-                        if token_bytes.len() <= 28 {
-                            if let Ok(date_time) = parse_datetime(&token_bytes) {
-                                (tokens.next().unwrap().unwrap_bytes(), Some(date_time))
-                            } else {
-                                (token_bytes, None)
+                    let received_at = match &token {
+                        Token::Argument(value) if value.len() <= 28 && tokens.peek().is_some() => {
+                            parse_datetime(value).ok()
+                        }
+                        _ => None,
+                    };
+                    let token = if received_at.is_some() {
+                        tokens.next().unwrap()
+                    } else {
+                        token
+                    };
+
+                    // RFC 4469 - CATENATE
+                    let (message, catenate) = if token.eq_ignore_ascii_case(b"CATENATE")
+                        && tokens
+                            .peek()
+                            .map_or(false, |token| token.is_parenthesis_open())
+                    {
+                        tokens.next();
+                        let mut parts = Vec::new();
+                        loop {
+                            match tokens.next() {
+                                Some(Token::ParenthesisClose) => break,
+                                Some(Token::Argument(part)) => {
+                                    let value = tokens
+                                        .next()
+                                        .ok_or((self.tag.as_str(), "Missing CATENATE value."))?;
+                                    if part.eq_ignore_ascii_case(b"TEXT") {
+                                        parts.push(CatenatePart::Text(value.unwrap_bytes()));
+                                    } else if part.eq_ignore_ascii_case(b"URL") {
+                                        parts.push(CatenatePart::Url(
+                                            value
+                                                .unwrap_string()
+                                                .map_err(|v| (self.tag.as_str(), v))?,
+                                        ));
+                                    } else {
+                                        return Err(
+                                            (self.tag.as_str(), "Invalid CATENATE part.").into()
+                                        );
+                                    }
+                                }
+                                _ => {
+                                    return Err(
+                                        (self.tag.as_str(), "Invalid CATENATE part.").into()
+                                    );
+                                }
                             }
-                        } else {
-                            (token_bytes, None)
                         }
+                        if parts.is_empty() {
+                            return Err((self.tag.as_str(), "Missing CATENATE parts.").into());
+                        }
+                        (Vec::new(), parts)
                     } else {
-                        (token.unwrap_bytes(), None)
+                        (token.unwrap_bytes(), Vec::new())
                     };
 
                     messages.push(Message {
                         message,
                         flags,
                         received_at,
+                        catenate,
                     });
                 }
 
@@ -105,7 +147,7 @@ mod tests {
 
     use crate::{
         protocol::{
-            append::{self, Message},
+            append::{self, CatenatePart, Message},
             Flag,
         },
         receiver::{Error, Receiver},
@@ -125,6 +167,7 @@ mod tests {
                         message: vec![b'a'],
                         flags: vec![Flag::Seen],
                         received_at: None,
+                        catenate: vec![],
                     }],
                 },
             ),
@@ -137,6 +180,7 @@ mod tests {
                         message: vec![b'a'],
                         flags: vec![Flag::Seen, Flag::Draft, Flag::MDNSent],
                         received_at: None,
+                        catenate: vec![],
                     }],
                 },
             ),
@@ -149,6 +193,7 @@ mod tests {
                         message: vec![b'a'],
                         flags: vec![Flag::Junk],
                         received_at: Some(760689784),
+                        catenate: vec![],
                     }],
                 },
             ),
@@ -161,6 +206,7 @@ mod tests {
                         message: vec![b'a'],
                         flags: vec![],
                         received_at: Some(1668977999),
+                        catenate: vec![],
                     }],
                 },
             ),
@@ -173,6 +219,29 @@ mod tests {
                         message: vec![b'a'],
                         flags: vec![],
                         received_at: Some(1668977999),
+                        catenate: vec![],
+                    }],
+                },
+            ),
+            (
+                concat!(
+                    "A003 APPEND Drafts (\\Seen) CATENATE (URL \"/Drafts;UIDVALIDITY=385759045/;",
+                    "UID=20/;section=HEADER\" TEXT {4+}\r\n\r\nHi URL \"/INBOX/;UID=3\")\r\n"
+                ),
+                append::Arguments {
+                    tag: "A003".to_string(),
+                    mailbox_name: "Drafts".to_string(),
+                    messages: vec![Message {
+                        message: vec![],
+                        flags: vec![Flag::Seen],
+                        received_at: None,
+                        catenate: vec![
+                            CatenatePart::Url(
+                                "/Drafts;UIDVALIDITY=385759045/;UID=20/;section=HEADER".to_string(),
+                            ),
+                            CatenatePart::Text(b"\r\nHi".to_vec()),
+                            CatenatePart::Url("/INBOX/;UID=3".to_string()),
+                        ],
                     }],
                 },
             ),
@@ -235,6 +304,7 @@ mod tests {
                                     .to_vec(),
                                     flags: vec![Flag::Seen],
                                     received_at: None,
+                                    catenate: vec![],
                                 },
                                 Message {
                                     message: concat!(
@@ -251,6 +321,7 @@ mod tests {
                                     .to_vec(),
                                     flags: vec![Flag::Seen],
                                     received_at: Some(760689784),
+                                    catenate: vec![],
                                 }
                             ],
                         },
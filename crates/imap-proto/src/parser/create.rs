@@ -67,6 +67,8 @@ impl Request<Command> {
                             "trash"
                         } else if value.eq_ignore_ascii_case(b"\\Important") {
                             "important"
+                        } else if value.eq_ignore_ascii_case(b"\\Snoozed") {
+                            "snoozed"
                         } else if value.eq_ignore_ascii_case(b"\\All") {
                             return Err((
                                 self.tag,
@@ -22,6 +22,7 @@
 */
 
 use crate::{
+    parser::metadata::parse_entry,
     protocol::{
         list::{self, ReturnOption, SelectionOption},
         status::Status,
@@ -168,6 +169,42 @@ impl Request<Command> {
                                         }
                                     }
                                 }
+                                if let ReturnOption::Metadata(entries) = &mut return_option {
+                                    match tokens.next() {
+                                        Some(Token::ParenthesisOpen) => {
+                                            while let Some(token) = tokens.next() {
+                                                match token {
+                                                    Token::ParenthesisClose => break,
+                                                    token => {
+                                                        entries.push(
+                                                            token
+                                                                .unwrap_string()
+                                                                .and_then(parse_entry)
+                                                                .map_err(|v| {
+                                                                    (self.tag.as_str(), v)
+                                                                })?,
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        Some(token) => {
+                                            entries.push(
+                                                token
+                                                    .unwrap_string()
+                                                    .and_then(parse_entry)
+                                                    .map_err(|v| (self.tag.as_str(), v))?,
+                                            );
+                                        }
+                                        None => {
+                                            return Err((
+                                                self.tag.as_str(),
+                                                "Missing METADATA return option entries.",
+                                            )
+                                                .into())
+                                        }
+                                    }
+                                }
                                 return_options.push(return_option);
                             }
                             _ => {
@@ -221,6 +258,10 @@ impl ReturnOption {
             Ok(Self::Status(Vec::with_capacity(2)))
         } else if value.eq_ignore_ascii_case(b"special-use") {
             Ok(Self::SpecialUse)
+        } else if value.eq_ignore_ascii_case(b"myrights") {
+            Ok(Self::MyRights)
+        } else if value.eq_ignore_ascii_case(b"metadata") {
+            Ok(Self::Metadata(Vec::with_capacity(2)))
         } else {
             Err(format!("Invalid return option {:?}", String::from_utf8_lossy(value)).into())
         }
@@ -393,6 +434,26 @@ mod tests {
                     ],
                 },
             ),
+            (
+                concat!(
+                    "A03 LIST \"\" % RETURN (MYRIGHTS ",
+                    "METADATA (/shared/comment /private/Comment) STATUS (APPENDLIMIT))\r\n"
+                ),
+                list::Arguments::Extended {
+                    tag: "A03".to_string(),
+                    reference_name: "".to_string(),
+                    mailbox_name: vec!["%".to_string()],
+                    selection_options: vec![],
+                    return_options: vec![
+                        ReturnOption::MyRights,
+                        ReturnOption::Metadata(vec![
+                            "/shared/comment".to_string(),
+                            "/private/comment".to_string(),
+                        ]),
+                        ReturnOption::Status(vec![Status::AppendLimit]),
+                    ],
+                },
+            ),
         ] {
             assert_eq!(
                 receiver
@@ -0,0 +1,369 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use crate::{
+    protocol::metadata::{self, Depth},
+    receiver::{Request, Token},
+    Command,
+};
+
+use super::parse_number;
+
+/*
+
+   getmetadata     = "GETMETADATA" [SP getmetadata-options]
+                     SP mailbox SP entries
+
+   getmetadata-options = "(" getmetadata-option
+                         *(SP getmetadata-option) ")"
+
+   getmetadata-option = "MAXSIZE" SP number /
+                        "DEPTH" SP ("0" / "1" / "infinity")
+
+   entries         = entry /
+                     "(" entry *(SP entry) ")"
+
+   setmetadata     = "SETMETADATA" SP mailbox
+                     SP entry-values
+
+   entry-values    = "(" entry-value *(SP entry-value) ")"
+
+   entry-value     = entry SP value
+
+   value           = nstring / literal8
+
+*/
+
+impl Request<Command> {
+    pub fn parse_get_metadata(self) -> crate::Result<metadata::GetArguments> {
+        let mut tokens = self.tokens.into_iter().peekable();
+        let mut max_size = None;
+        let mut depth = Depth::Zero;
+
+        // Parse options
+        if tokens
+            .peek()
+            .map_or(false, |token| token.is_parenthesis_open())
+        {
+            tokens.next();
+            loop {
+                match tokens.next() {
+                    Some(Token::ParenthesisClose) => break,
+                    Some(Token::Argument(value)) if value.eq_ignore_ascii_case(b"MAXSIZE") => {
+                        max_size = parse_number::<usize>(
+                            &tokens
+                                .next()
+                                .ok_or((self.tag.as_str(), "Missing MAXSIZE value."))?
+                                .unwrap_bytes(),
+                        )
+                        .map_err(|v| (self.tag.as_str(), v))?
+                        .into();
+                    }
+                    Some(Token::Argument(value)) if value.eq_ignore_ascii_case(b"DEPTH") => {
+                        let value = tokens
+                            .next()
+                            .ok_or((self.tag.as_str(), "Missing DEPTH value."))?
+                            .unwrap_bytes();
+                        depth = if value.eq(b"0") {
+                            Depth::Zero
+                        } else if value.eq(b"1") {
+                            Depth::One
+                        } else if value.eq_ignore_ascii_case(b"infinity") {
+                            Depth::Infinity
+                        } else {
+                            return Err((self.tag.as_str(), "Invalid DEPTH value.").into());
+                        };
+                    }
+                    Some(_) => {
+                        return Err((self.tag.as_str(), "Unsupported GETMETADATA option.").into());
+                    }
+                    None => {
+                        return Err((self.tag.as_str(), "Missing ')' after options.").into());
+                    }
+                }
+            }
+        }
+
+        let mailbox_name = tokens
+            .next()
+            .ok_or((self.tag.as_str(), "Missing mailbox name."))?
+            .unwrap_string()
+            .map_err(|v| (self.tag.as_str(), v))?;
+
+        // Parse entries
+        let mut entries = Vec::new();
+        match tokens.next() {
+            Some(Token::ParenthesisOpen) => loop {
+                match tokens.next() {
+                    Some(Token::ParenthesisClose) => break,
+                    Some(token) => {
+                        entries.push(
+                            parse_entry(token.unwrap_string().map_err(|v| (self.tag.as_str(), v))?)
+                                .map_err(|v| (self.tag.as_str(), v))?,
+                        );
+                    }
+                    None => {
+                        return Err((self.tag.as_str(), "Missing ')' after entries.").into());
+                    }
+                }
+            },
+            Some(token) => {
+                entries.push(
+                    parse_entry(token.unwrap_string().map_err(|v| (self.tag.as_str(), v))?)
+                        .map_err(|v| (self.tag.as_str(), v))?,
+                );
+            }
+            None => (),
+        }
+        if entries.is_empty() {
+            return Err((self.tag.as_str(), "Missing entry names.").into());
+        }
+
+        Ok(metadata::GetArguments {
+            tag: self.tag,
+            mailbox_name,
+            entries,
+            max_size,
+            depth,
+        })
+    }
+
+    pub fn parse_set_metadata(self) -> crate::Result<metadata::SetArguments> {
+        let mut tokens = self.tokens.into_iter();
+        let mailbox_name = tokens
+            .next()
+            .ok_or((self.tag.as_str(), "Missing mailbox name."))?
+            .unwrap_string()
+            .map_err(|v| (self.tag.as_str(), v))?;
+
+        if !tokens
+            .next()
+            .map_or(false, |token| token.is_parenthesis_open())
+        {
+            return Err((self.tag.as_str(), "Expected '(' before entry values.").into());
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            match tokens.next() {
+                Some(Token::ParenthesisClose) => break,
+                Some(token) => {
+                    let entry =
+                        parse_entry(token.unwrap_string().map_err(|v| (self.tag.as_str(), v))?)
+                            .map_err(|v| (self.tag.as_str(), v))?;
+                    if !entry.starts_with("/private/") && !entry.starts_with("/shared/") {
+                        return Err((
+                            self.tag.as_str(),
+                            "Only entries under /private/ or /shared/ can be set.",
+                        )
+                            .into());
+                    }
+                    let value = match tokens
+                        .next()
+                        .ok_or((self.tag.as_str(), "Missing entry value."))?
+                    {
+                        Token::Argument(value) if value.eq_ignore_ascii_case(b"NIL") => None,
+                        token => token
+                            .unwrap_string()
+                            .map_err(|v| (self.tag.as_str(), v))?
+                            .into(),
+                    };
+                    entries.push((entry, value));
+                }
+                None => {
+                    return Err((self.tag.as_str(), "Missing ')' after entry values.").into());
+                }
+            }
+        }
+        if entries.is_empty() {
+            return Err((self.tag.as_str(), "Missing entry values.").into());
+        }
+
+        Ok(metadata::SetArguments {
+            tag: self.tag,
+            mailbox_name,
+            entries,
+        })
+    }
+}
+
+pub fn parse_entry(entry: String) -> super::Result<String> {
+    let entry = entry.to_ascii_lowercase();
+    if (entry == "/private" || entry == "/shared")
+        || ((entry.starts_with("/private/") || entry.starts_with("/shared/"))
+            && !entry.ends_with('/')
+            && !entry.contains("//")
+            && !entry.contains(['*', '%'])
+            && !entry.chars().any(|ch| ch.is_ascii_control()))
+    {
+        Ok(entry)
+    } else {
+        Err(format!("Invalid entry name {entry:?}.").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        protocol::metadata::{self, Depth},
+        receiver::Receiver,
+    };
+
+    #[test]
+    fn parse_get_metadata() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "A001 GETMETADATA \"\" /shared/comment\r\n",
+                metadata::GetArguments {
+                    tag: "A001".to_string(),
+                    mailbox_name: "".to_string(),
+                    entries: vec!["/shared/comment".to_string()],
+                    max_size: None,
+                    depth: Depth::Zero,
+                },
+            ),
+            (
+                "A002 GETMETADATA INBOX (/shared/Comment /private/comment)\r\n",
+                metadata::GetArguments {
+                    tag: "A002".to_string(),
+                    mailbox_name: "INBOX".to_string(),
+                    entries: vec![
+                        "/shared/comment".to_string(),
+                        "/private/comment".to_string(),
+                    ],
+                    max_size: None,
+                    depth: Depth::Zero,
+                },
+            ),
+            (
+                "A003 GETMETADATA (MAXSIZE 1024 DEPTH infinity) INBOX (/shared)\r\n",
+                metadata::GetArguments {
+                    tag: "A003".to_string(),
+                    mailbox_name: "INBOX".to_string(),
+                    entries: vec!["/shared".to_string()],
+                    max_size: Some(1024),
+                    depth: Depth::Infinity,
+                },
+            ),
+            (
+                "A004 GETMETADATA (DEPTH 1) \"Drafts\" /private/vendor\r\n",
+                metadata::GetArguments {
+                    tag: "A004".to_string(),
+                    mailbox_name: "Drafts".to_string(),
+                    entries: vec!["/private/vendor".to_string()],
+                    max_size: None,
+                    depth: Depth::One,
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_get_metadata()
+                    .unwrap(),
+                arguments,
+                "{:?}",
+                command
+            );
+        }
+
+        for command in [
+            "A005 GETMETADATA INBOX\r\n",
+            "A006 GETMETADATA INBOX /other/comment\r\n",
+            "A007 GETMETADATA (DEPTH 2) INBOX /shared/comment\r\n",
+            "A008 GETMETADATA INBOX /shared/comment/\r\n",
+        ] {
+            assert!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_get_metadata()
+                    .is_err(),
+                "{:?}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn parse_set_metadata() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "A001 SETMETADATA INBOX (/private/comment \"My new comment\")\r\n",
+                metadata::SetArguments {
+                    tag: "A001".to_string(),
+                    mailbox_name: "INBOX".to_string(),
+                    entries: vec![(
+                        "/private/comment".to_string(),
+                        Some("My new comment".to_string()),
+                    )],
+                },
+            ),
+            (
+                "A002 SETMETADATA \"\" (/shared/comment NIL /shared/admin {7+}\r\nmailto: /private/x \"\")\r\n",
+                metadata::SetArguments {
+                    tag: "A002".to_string(),
+                    mailbox_name: "".to_string(),
+                    entries: vec![
+                        ("/shared/comment".to_string(), None),
+                        ("/shared/admin".to_string(), Some("mailto:".to_string())),
+                        ("/private/x".to_string(), Some("".to_string())),
+                    ],
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_set_metadata()
+                    .unwrap(),
+                arguments,
+                "{:?}",
+                command
+            );
+        }
+
+        for command in [
+            "A003 SETMETADATA INBOX /shared/comment \"test\"\r\n",
+            "A004 SETMETADATA INBOX (/shared \"test\")\r\n",
+            "A005 SETMETADATA INBOX (/shared/comment)\r\n",
+        ] {
+            assert!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_set_metadata()
+                    .is_err(),
+                "{:?}",
+                command
+            );
+        }
+    }
+}
@@ -24,6 +24,7 @@
 pub mod acl;
 pub mod append;
 pub mod authenticate;
+pub mod compress;
 pub mod copy_move;
 pub mod create;
 pub mod delete;
@@ -32,7 +33,10 @@ pub mod fetch;
 pub mod list;
 pub mod login;
 pub mod lsub;
+pub mod metadata;
+pub mod notify;
 pub mod rename;
+pub mod replace;
 pub mod search;
 pub mod select;
 pub mod sort;
@@ -40,13 +44,14 @@ pub mod status;
 pub mod store;
 pub mod subscribe;
 pub mod thread;
+pub mod urlauth;
 
 use std::{borrow::Cow, str::FromStr};
 
 use chrono::{DateTime, NaiveDate};
 
 use crate::{
-    protocol::{Flag, Sequence},
+    protocol::{Flag, PartialRange, Sequence},
     receiver::CommandParser,
     Command,
 };
@@ -93,6 +98,15 @@ impl CommandParser for Command {
             b"LISTRIGHTS" => Some(Command::ListRights),
             b"MYRIGHTS" => Some(Command::MyRights),
             b"UNAUTHENTICATE" => Some(Command::Unauthenticate),
+            b"GETMETADATA" => Some(Command::GetMetadata),
+            b"SETMETADATA" => Some(Command::SetMetadata),
+            b"NOTIFY" => Some(Command::Notify),
+            b"COMPRESS" => Some(Command::Compress),
+            b"GENURLAUTH" => Some(Command::GenUrlAuth),
+            b"RESETKEY" => Some(Command::ResetKey),
+            b"URLFETCH" => Some(Command::UrlFetch),
+            b"REPLACE" => Some(Command::Replace(uid)),
+            b"ESEARCH" => Some(Command::Esearch),
             b"ID" => Some(Command::Id),
             _ => None,
         }
@@ -215,6 +229,24 @@ pub fn parse_number<T: FromStr>(value: &[u8]) -> Result<T> {
         .map_err(|_| Cow::from(format!("Expected a number, found {:?}.", string)))
 }
 
+pub fn parse_partial_range(value: &[u8]) -> Result<PartialRange> {
+    if let Some((start, end)) = std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.split_once(':'))
+    {
+        if let (Ok(start), Ok(end)) = (start.parse::<i32>(), end.parse::<i32>()) {
+            if start != 0 && end != 0 && (start > 0) == (end > 0) {
+                return Ok(PartialRange { start, end });
+            }
+        }
+    }
+
+    Err(Cow::from(format!(
+        "Invalid partial range {:?}.",
+        String::from_utf8_lossy(value)
+    )))
+}
+
 pub fn parse_sequence_set(value: &[u8]) -> Result<Sequence> {
     let mut sequence_set = Vec::new();
 
@@ -408,7 +440,7 @@ impl<T: PartialEq> PushUnique<T> for Vec<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::Sequence;
+    use crate::protocol::{PartialRange, Sequence};
 
     #[test]
     fn parse_sequence_set() {
@@ -482,4 +514,40 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_partial_range() {
+        for (range, expected_result) in [
+            ("1:100", Some((1, 100))),
+            ("-1:-100", Some((-1, -100))),
+            ("500:400", Some((500, 400))),
+            ("-1:100", None),
+            ("0:10", None),
+            ("1", None),
+        ] {
+            assert_eq!(
+                super::parse_partial_range(range.as_bytes())
+                    .ok()
+                    .map(|range| (range.start, range.end)),
+                expected_result,
+                "{range}"
+            );
+        }
+
+        let items = (1..=10).collect::<Vec<u32>>();
+        for (start, end, expected_result) in [
+            (1, 3, vec![1, 2, 3]),
+            (3, 1, vec![1, 2, 3]),
+            (-1, -3, vec![8, 9, 10]),
+            (9, 20, vec![9, 10]),
+            (-9, -20, vec![1, 2]),
+            (11, 20, vec![]),
+        ] {
+            assert_eq!(
+                PartialRange { start, end }.slice(&items),
+                &expected_result[..],
+                "{start}:{end}"
+            );
+        }
+    }
 }
@@ -0,0 +1,407 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{iter::Peekable, vec::IntoIter};
+
+use crate::{
+    protocol::{
+        notify::{self, Event, EventGroup, Filter},
+        ProtocolVersion,
+    },
+    receiver::{Request, Token},
+    utf7::utf7_maybe_decode,
+    Command, ResponseCode, StatusResponse,
+};
+
+/*
+
+   notify          = "NOTIFY" SP
+                     (notify-set / notify-none)
+
+   notify-none     = "NONE"
+
+   notify-set      = "SET" [status-indicator] SP event-groups
+
+   status-indicator = SP "STATUS"
+
+   event-groups    = event-group *(SP event-group)
+
+   event-group     = "(" filter-mailboxes SP events ")"
+
+   filter-mailboxes = filter-mailboxes-selected /
+                      filter-mailboxes-other
+
+   filter-mailboxes-selected = "selected" / "selected-delayed"
+
+   filter-mailboxes-other = "inboxes" / "personal" / "subscribed" /
+                            ( "subtree" SP one-or-more-mailbox ) /
+                            ( "mailboxes" SP one-or-more-mailbox )
+
+   one-or-more-mailbox = mailbox / many-mailboxes
+
+   many-mailboxes  = "(" mailbox *(SP mailbox) ")"
+
+   events          = ( "(" event *(SP event) ")" ) / "NONE"
+
+   message-event   = ( "MessageNew" [SP
+                         "(" fetch-att *(SP fetch-att) ")" ] ) /
+                     "MessageExpunge" / "FlagChange" /
+                     "AnnotationChange"
+
+   mailbox-event   = "MailboxName" / "SubscriptionChange" /
+                     "MailboxMetadataChange"
+
+   user-event      = "ServerMetadataChange"
+
+*/
+
+impl Request<Command> {
+    pub fn parse_notify(self, version: ProtocolVersion) -> crate::Result<notify::Arguments> {
+        let mut tokens = self.tokens.into_iter().peekable();
+        let mut status = false;
+        let mut groups = Vec::new();
+
+        match tokens.next() {
+            Some(Token::Argument(value)) if value.eq_ignore_ascii_case(b"NONE") => {
+                return Ok(notify::Arguments {
+                    tag: self.tag,
+                    status,
+                    groups,
+                });
+            }
+            Some(Token::Argument(value)) if value.eq_ignore_ascii_case(b"SET") => (),
+            _ => {
+                return Err((self.tag.as_str(), "Expected SET or NONE.").into());
+            }
+        }
+
+        if tokens
+            .peek()
+            .map_or(false, |token| token.eq_ignore_ascii_case(b"STATUS"))
+        {
+            tokens.next();
+            status = true;
+        }
+
+        while let Some(token) = tokens.next() {
+            if !token.is_parenthesis_open() {
+                return Err((self.tag.as_str(), "Expected '(' before event group.").into());
+            }
+
+            // Parse filter
+            let filter = match tokens.next() {
+                Some(Token::Argument(value)) if !value.eq_ignore_ascii_case(b"subtree-one") => {
+                    parse_filter_mailboxes(&value, &mut tokens, version)
+                        .map_err(|v| (self.tag.as_str(), v))?
+                }
+                Some(Token::Argument(_)) => {
+                    return Err((self.tag.as_str(), "Invalid mailbox filter.").into());
+                }
+                _ => {
+                    return Err((self.tag.as_str(), "Missing mailbox filter.").into());
+                }
+            };
+
+            // Parse events
+            let mut events = Vec::new();
+            match tokens.next() {
+                Some(Token::Argument(value)) if value.eq_ignore_ascii_case(b"NONE") => (),
+                Some(Token::ParenthesisOpen) => loop {
+                    let event = match tokens.next() {
+                        Some(Token::ParenthesisClose) => break,
+                        Some(Token::Argument(value)) => {
+                            if value.eq_ignore_ascii_case(b"MessageNew") {
+                                // Fetch attributes are not supported, FLAGS and UID
+                                // are always returned for new messages.
+                                if tokens
+                                    .peek()
+                                    .map_or(false, |token| token.is_parenthesis_open())
+                                {
+                                    let mut depth = 0;
+                                    for token in tokens.by_ref() {
+                                        match token {
+                                            Token::ParenthesisOpen => depth += 1,
+                                            Token::ParenthesisClose => {
+                                                depth -= 1;
+                                                if depth == 0 {
+                                                    break;
+                                                }
+                                            }
+                                            _ => (),
+                                        }
+                                    }
+                                }
+                                Event::MessageNew
+                            } else if value.eq_ignore_ascii_case(b"MessageExpunge") {
+                                Event::MessageExpunge
+                            } else if value.eq_ignore_ascii_case(b"FlagChange") {
+                                Event::FlagChange
+                            } else if value.eq_ignore_ascii_case(b"MailboxName") {
+                                Event::MailboxName
+                            } else if value.eq_ignore_ascii_case(b"SubscriptionChange") {
+                                Event::SubscriptionChange
+                            } else if value.eq_ignore_ascii_case(b"AnnotationChange")
+                                || value.eq_ignore_ascii_case(b"MailboxMetadataChange")
+                                || value.eq_ignore_ascii_case(b"ServerMetadataChange")
+                            {
+                                return Err(StatusResponse::no(format!(
+                                    "Event {} is not supported.",
+                                    String::from_utf8_lossy(&value)
+                                ))
+                                .with_tag(self.tag)
+                                .with_code(ResponseCode::BadEvent));
+                            } else {
+                                return Err((self.tag.as_str(), "Invalid event.").into());
+                            }
+                        }
+                        _ => {
+                            return Err((self.tag.as_str(), "Missing ')' after events.").into());
+                        }
+                    };
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                },
+                _ => {
+                    return Err((self.tag.as_str(), "Missing events.").into());
+                }
+            }
+
+            // Validate events
+            let has_new = events.contains(&Event::MessageNew);
+            let has_expunge = events.contains(&Event::MessageExpunge);
+            if has_new != has_expunge || (events.contains(&Event::FlagChange) && !has_new) {
+                return Err((
+                    self.tag.as_str(),
+                    "MessageNew and MessageExpunge must be specified together, and both are required by FlagChange.",
+                )
+                    .into());
+            } else if filter.is_selected()
+                && events
+                    .iter()
+                    .any(|event| matches!(event, Event::MailboxName | Event::SubscriptionChange))
+            {
+                return Err((
+                    self.tag.as_str(),
+                    "Mailbox events cannot be requested for the selected mailbox.",
+                )
+                    .into());
+            }
+
+            if !tokens
+                .next()
+                .map_or(false, |token| token.is_parenthesis_close())
+            {
+                return Err((self.tag.as_str(), "Expected ')' after event group.").into());
+            }
+
+            groups.push(EventGroup { filter, events });
+        }
+
+        if !groups.is_empty() {
+            Ok(notify::Arguments {
+                tag: self.tag,
+                status,
+                groups,
+            })
+        } else {
+            Err((self.tag.as_str(), "Missing event groups.").into())
+        }
+    }
+}
+
+pub fn parse_filter_mailboxes(
+    value: &[u8],
+    tokens: &mut Peekable<IntoIter<Token>>,
+    version: ProtocolVersion,
+) -> super::Result<Filter> {
+    if value.eq_ignore_ascii_case(b"selected") {
+        Ok(Filter::Selected)
+    } else if value.eq_ignore_ascii_case(b"selected-delayed") {
+        Ok(Filter::SelectedDelayed)
+    } else if value.eq_ignore_ascii_case(b"personal") {
+        Ok(Filter::Personal)
+    } else if value.eq_ignore_ascii_case(b"inboxes") {
+        Ok(Filter::Inboxes)
+    } else if value.eq_ignore_ascii_case(b"subscribed") {
+        Ok(Filter::Subscribed)
+    } else if value.eq_ignore_ascii_case(b"subtree") {
+        parse_mailboxes(tokens, version).map(Filter::Subtree)
+    } else if value.eq_ignore_ascii_case(b"subtree-one") {
+        parse_mailboxes(tokens, version).map(Filter::SubtreeOne)
+    } else if value.eq_ignore_ascii_case(b"mailboxes") {
+        parse_mailboxes(tokens, version).map(Filter::Mailboxes)
+    } else {
+        Err("Invalid mailbox filter.".into())
+    }
+}
+
+fn parse_mailboxes(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    version: ProtocolVersion,
+) -> super::Result<Vec<String>> {
+    let mut mailboxes = Vec::new();
+    match tokens.next() {
+        Some(Token::ParenthesisOpen) => loop {
+            match tokens.next() {
+                Some(Token::ParenthesisClose) => break,
+                Some(token) => {
+                    mailboxes.push(utf7_maybe_decode(token.unwrap_string()?, version));
+                }
+                None => {
+                    return Err("Missing ')' after mailbox list.".into());
+                }
+            }
+        },
+        Some(token) => {
+            mailboxes.push(utf7_maybe_decode(token.unwrap_string()?, version));
+        }
+        None => (),
+    }
+
+    if !mailboxes.is_empty() {
+        Ok(mailboxes)
+    } else {
+        Err("Missing mailbox names.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::{
+            notify::{self, Event, EventGroup, Filter},
+            ProtocolVersion,
+        },
+        receiver::Receiver,
+        ResponseCode,
+    };
+
+    #[test]
+    fn parse_notify() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "A001 NOTIFY NONE\r\n",
+                notify::Arguments {
+                    tag: "A001".to_string(),
+                    status: false,
+                    groups: vec![],
+                },
+            ),
+            (
+                concat!(
+                    "A002 NOTIFY SET STATUS (selected (MessageNew (UID BODY.PEEK[HEADER.FIELDS (From Subject)]) ",
+                    "MessageExpunge FlagChange)) (subtree (\"Lists\" Archive) (MessageNew MessageExpunge)) ",
+                    "(personal (MailboxName SubscriptionChange))\r\n"
+                ),
+                notify::Arguments {
+                    tag: "A002".to_string(),
+                    status: true,
+                    groups: vec![
+                        EventGroup {
+                            filter: Filter::Selected,
+                            events: vec![
+                                Event::MessageNew,
+                                Event::MessageExpunge,
+                                Event::FlagChange,
+                            ],
+                        },
+                        EventGroup {
+                            filter: Filter::Subtree(vec![
+                                "Lists".to_string(),
+                                "Archive".to_string(),
+                            ]),
+                            events: vec![Event::MessageNew, Event::MessageExpunge],
+                        },
+                        EventGroup {
+                            filter: Filter::Personal,
+                            events: vec![Event::MailboxName, Event::SubscriptionChange],
+                        },
+                    ],
+                },
+            ),
+            (
+                "A003 NOTIFY SET (mailboxes INBOX NONE) (subscribed (MessageExpunge MessageNew))\r\n",
+                notify::Arguments {
+                    tag: "A003".to_string(),
+                    status: false,
+                    groups: vec![
+                        EventGroup {
+                            filter: Filter::Mailboxes(vec!["INBOX".to_string()]),
+                            events: vec![],
+                        },
+                        EventGroup {
+                            filter: Filter::Subscribed,
+                            events: vec![Event::MessageExpunge, Event::MessageNew],
+                        },
+                    ],
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_notify(ProtocolVersion::Rev2)
+                    .unwrap(),
+                arguments,
+                "{:?}",
+                command
+            );
+        }
+
+        for command in [
+            "A004 NOTIFY SET\r\n",
+            "A005 NOTIFY SET (personal (MessageNew))\r\n",
+            "A006 NOTIFY SET (inboxes (FlagChange))\r\n",
+            "A007 NOTIFY SET (selected (MailboxName))\r\n",
+            "A008 NOTIFY SET (everything (MessageNew MessageExpunge))\r\n",
+        ] {
+            assert!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_notify(ProtocolVersion::Rev2)
+                    .is_err(),
+                "{:?}",
+                command
+            );
+        }
+
+        assert_eq!(
+            receiver
+                .parse(
+                    &mut "A009 NOTIFY SET (personal (AnnotationChange))\r\n"
+                        .as_bytes()
+                        .iter()
+                )
+                .unwrap()
+                .parse_notify(ProtocolVersion::Rev2)
+                .unwrap_err()
+                .code,
+            Some(ResponseCode::BadEvent)
+        );
+    }
+}
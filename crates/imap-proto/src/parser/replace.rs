@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use crate::{
+    protocol::{append, replace},
+    receiver::Request,
+    Command,
+};
+
+use super::parse_sequence_set;
+
+impl Request<Command> {
+    pub fn parse_replace(mut self) -> crate::Result<replace::Arguments> {
+        if self.tokens.len() < 3 {
+            return Err(self.into_error("Missing arguments."));
+        }
+
+        let sequence_set = parse_sequence_set(&self.tokens.remove(0).unwrap_bytes())
+            .map_err(|v| (self.tag.as_str(), v))?;
+        let append::Arguments {
+            tag,
+            mailbox_name,
+            mut messages,
+        } = self.parse_append()?;
+
+        if messages.len() == 1 {
+            Ok(replace::Arguments {
+                tag,
+                sequence_set,
+                mailbox_name,
+                message: messages.pop().unwrap(),
+            })
+        } else {
+            Err((tag.as_str(), "Expected exactly one message.").into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::{append::Message, replace, Flag, Sequence},
+        receiver::Receiver,
+    };
+
+    #[test]
+    fn parse_replace() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                "A003 REPLACE 4 Drafts (\\Seen \\Draft) {5+}\r\nhello\r\n",
+                replace::Arguments {
+                    tag: "A003".to_string(),
+                    sequence_set: Sequence::Number { value: 4 },
+                    mailbox_name: "Drafts".to_string(),
+                    message: Message {
+                        message: b"hello".to_vec(),
+                        flags: vec![Flag::Seen, Flag::Draft],
+                        received_at: None,
+                        catenate: vec![],
+                    },
+                },
+            ),
+            (
+                "A004 UID REPLACE 25 \"Saved Drafts\" \"7-Feb-1994 22:43:04 -0800\" {3+}\r\nabc\r\n",
+                replace::Arguments {
+                    tag: "A004".to_string(),
+                    sequence_set: Sequence::Number { value: 25 },
+                    mailbox_name: "Saved Drafts".to_string(),
+                    message: Message {
+                        message: b"abc".to_vec(),
+                        flags: vec![],
+                        received_at: Some(760689784),
+                        catenate: vec![],
+                    },
+                },
+            ),
+        ] {
+            assert_eq!(
+                receiver
+                    .parse(&mut command.as_bytes().iter())
+                    .unwrap()
+                    .parse_replace()
+                    .unwrap(),
+                arguments,
+                "{command}"
+            );
+        }
+    }
+}
@@ -30,11 +30,12 @@ use mail_parser::decoders::charsets::DecoderFnc;
 
 use crate::protocol::search::{self, Filter};
 use crate::protocol::search::{ModSeqEntry, ResultOption};
-use crate::protocol::{Flag, ProtocolVersion};
+use crate::protocol::{notify, Flag, ProtocolVersion};
 use crate::receiver::{Request, Token};
 use crate::Command;
 
-use super::{parse_date, parse_number, parse_sequence_set};
+use super::notify::parse_filter_mailboxes;
+use super::{parse_date, parse_number, parse_partial_range, parse_sequence_set};
 
 impl Request<Command> {
     #[allow(clippy::while_let_on_iterator)]
@@ -82,6 +83,59 @@ impl Request<Command> {
             }),
         }
     }
+
+    pub fn parse_esearch(
+        mut self,
+        version: ProtocolVersion,
+    ) -> crate::Result<search::MultiArguments> {
+        let mut tokens = std::mem::take(&mut self.tokens).into_iter().peekable();
+        let mut sources = Vec::new();
+
+        if tokens
+            .peek()
+            .map_or(false, |token| token.eq_ignore_ascii_case(b"IN"))
+        {
+            tokens.next();
+            if tokens
+                .next()
+                .map_or(true, |token| !token.is_parenthesis_open())
+            {
+                return Err((self.tag.as_str(), "Expected '(' after IN.").into());
+            }
+            loop {
+                match tokens.next() {
+                    Some(Token::ParenthesisClose) if !sources.is_empty() => break,
+                    Some(Token::Argument(value)) => {
+                        sources.push(
+                            parse_filter_mailboxes(&value, &mut tokens, version)
+                                .map_err(|v| (self.tag.as_str(), v))?,
+                        );
+                    }
+                    _ => {
+                        return Err((self.tag.as_str(), "Invalid source mailboxes.").into());
+                    }
+                }
+            }
+        } else {
+            sources.push(notify::Filter::Selected);
+        }
+
+        self.tokens = tokens.collect();
+        let mut search = self.parse_search(version)?;
+        search.is_esearch = true;
+
+        if search.result_options.contains(&ResultOption::Save)
+            && sources.iter().any(|source| !source.is_selected())
+        {
+            return Err((
+                search.tag.as_str(),
+                "SAVE is not supported when searching multiple mailboxes.",
+            )
+                .into());
+        }
+
+        Ok(search::MultiArguments { sources, search })
+    }
 }
 
 pub fn parse_result_options(
@@ -95,9 +149,17 @@ pub fn parse_result_options(
         return Err(Cow::from("Invalid result option, expected parenthesis."));
     }
 
-    for token in tokens {
+    while let Some(token) = tokens.next() {
         match token {
             Token::ParenthesisClose => break,
+            Token::Argument(value) if value.eq_ignore_ascii_case(b"partial") => {
+                result_options.push(ResultOption::Partial(parse_partial_range(
+                    &tokens
+                        .next()
+                        .ok_or_else(|| Cow::from("Missing partial range."))?
+                        .unwrap_bytes(),
+                )?));
+            }
             Token::Argument(value) => {
                 result_options.push(ResultOption::parse(&value)?);
             }
@@ -197,6 +259,29 @@ pub fn parse_filters(
                             .ok_or_else(|| Cow::from("Expected date"))?
                             .unwrap_bytes(),
                     )?));
+                } else if value.eq_ignore_ascii_case(b"SAVEDBEFORE") {
+                    filters.push(Filter::SavedBefore(parse_date(
+                        &tokens
+                            .next()
+                            .ok_or_else(|| Cow::from("Expected date"))?
+                            .unwrap_bytes(),
+                    )?));
+                } else if value.eq_ignore_ascii_case(b"SAVEDON") {
+                    filters.push(Filter::SavedOn(parse_date(
+                        &tokens
+                            .next()
+                            .ok_or_else(|| Cow::from("Expected date"))?
+                            .unwrap_bytes(),
+                    )?));
+                } else if value.eq_ignore_ascii_case(b"SAVEDSINCE") {
+                    filters.push(Filter::SavedSince(parse_date(
+                        &tokens
+                            .next()
+                            .ok_or_else(|| Cow::from("Expected date"))?
+                            .unwrap_bytes(),
+                    )?));
+                } else if value.eq_ignore_ascii_case(b"SAVEDATESUPPORTED") {
+                    filters.push(Filter::SaveDateSupported);
                 } else if value.eq_ignore_ascii_case(b"SMALLER") {
                     filters.push(Filter::Smaller(parse_number::<u32>(
                         &tokens
@@ -343,6 +428,16 @@ pub fn parse_filters(
                     filters = Vec::with_capacity(1);
                     operator = Filter::Not;
                     continue;
+                } else if value.eq_ignore_ascii_case(b"FUZZY") {
+                    if filters_stack.len() > 10 {
+                        return Err(Cow::from("Too many nested filters"));
+                    }
+
+                    filters_stack.push((filters, operator, filters_len));
+                    filters_len = 0;
+                    filters = Vec::with_capacity(1);
+                    operator = Filter::Fuzzy;
+                    continue;
                 } else {
                     filters.push(Filter::Sequence(parse_sequence_set(&value)?, false));
                 }
@@ -373,7 +468,7 @@ pub fn parse_filters(
         if !filters_stack.is_empty()
             && (found_parenthesis
                 || (operator == Filter::Or && filters_len == 2)
-                || (operator == Filter::Not && filters_len == 1))
+                || (matches!(operator, Filter::Not | Filter::Fuzzy) && filters_len == 1))
         {
             while let Some((mut prev_filters, prev_operator, prev_filters_len)) =
                 filters_stack.pop()
@@ -430,6 +525,8 @@ impl ResultOption {
             Ok(Self::Save)
         } else if value.eq_ignore_ascii_case(b"context") {
             Ok(Self::Context)
+        } else if value.eq_ignore_ascii_case(b"relevancy") {
+            Ok(Self::Relevancy)
         } else {
             Err(format!("Invalid result option {:?}", String::from_utf8_lossy(value)).into())
         }
@@ -440,8 +537,9 @@ impl ResultOption {
 mod tests {
     use crate::{
         protocol::{
+            notify,
             search::{self, Filter, ModSeqEntry, ResultOption},
-            Flag, ProtocolVersion, Sequence,
+            Flag, PartialRange, ProtocolVersion, Sequence,
         },
         receiver::Receiver,
     };
@@ -484,6 +582,22 @@ mod tests {
                     sort: None,
                 },
             ),
+            (
+                b"A01 SEARCH RETURN (PARTIAL -1:-100 COUNT) UNDELETED\r\n".to_vec(),
+                search::Arguments {
+                    tag: "A01".to_string(),
+                    result_options: vec![
+                        ResultOption::Partial(PartialRange {
+                            start: -1,
+                            end: -100,
+                        }),
+                        ResultOption::Count,
+                    ],
+                    filter: vec![Filter::Undeleted],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
             (
                 b"A301 SEARCH $ SMALLER 4096\r\n".to_vec(),
                 search::Arguments {
@@ -725,6 +839,21 @@ mod tests {
                     sort: None,
                 },
             ),
+            (
+                b"a SEARCH SAVEDATESUPPORTED SAVEDSINCE 20-Nov-2022 SAVEDBEFORE 20-Nov-2022\r\n"
+                    .to_vec(),
+                search::Arguments {
+                    tag: "a".to_string(),
+                    result_options: vec![],
+                    filter: vec![
+                        Filter::SaveDateSupported,
+                        Filter::SavedSince(1668902400),
+                        Filter::SavedBefore(1668902400),
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
             (
                 b"t SEARCH OR NOT MODSEQ 720162338 LARGER 50000\r\n".to_vec(),
                 search::Arguments {
@@ -742,6 +871,22 @@ mod tests {
                     sort: None,
                 },
             ),
+            (
+                b"tag1 SEARCH RETURN (RELEVANCY ALL) FUZZY (SUBJECT \"IMAP break\") SEEN\r\n"
+                    .to_vec(),
+                search::Arguments {
+                    tag: "tag1".to_string(),
+                    result_options: vec![ResultOption::Relevancy, ResultOption::All],
+                    filter: vec![
+                        Filter::Fuzzy,
+                        Filter::Subject("IMAP break".to_string()),
+                        Filter::End,
+                        Filter::Seen,
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
         ] {
             let command_str = String::from_utf8_lossy(&command).into_owned();
             assert_eq!(
@@ -756,4 +901,85 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_esearch() {
+        let mut receiver = Receiver::new();
+
+        for (command, arguments) in [
+            (
+                b"tag1 ESEARCH IN (mailboxes \"folder1\" subtree-one \"folder2\") UNSEEN\r\n"
+                    .to_vec(),
+                search::MultiArguments {
+                    sources: vec![
+                        notify::Filter::Mailboxes(vec!["folder1".to_string()]),
+                        notify::Filter::SubtreeOne(vec!["folder2".to_string()]),
+                    ],
+                    search: search::Arguments {
+                        tag: "tag1".to_string(),
+                        result_options: vec![],
+                        filter: vec![Filter::Unseen],
+                        is_esearch: true,
+                        sort: None,
+                    },
+                },
+            ),
+            (
+                b"tag2 ESEARCH IN (personal subtree (Lists Archive)) RETURN (MIN COUNT) FLAGGED\r\n"
+                    .to_vec(),
+                search::MultiArguments {
+                    sources: vec![
+                        notify::Filter::Personal,
+                        notify::Filter::Subtree(vec![
+                            "Lists".to_string(),
+                            "Archive".to_string(),
+                        ]),
+                    ],
+                    search: search::Arguments {
+                        tag: "tag2".to_string(),
+                        result_options: vec![ResultOption::Min, ResultOption::Count],
+                        filter: vec![Filter::Flagged],
+                        is_esearch: true,
+                        sort: None,
+                    },
+                },
+            ),
+            (
+                b"tag3 ESEARCH SEEN\r\n".to_vec(),
+                search::MultiArguments {
+                    sources: vec![notify::Filter::Selected],
+                    search: search::Arguments {
+                        tag: "tag3".to_string(),
+                        result_options: vec![],
+                        filter: vec![Filter::Seen],
+                        is_esearch: true,
+                        sort: None,
+                    },
+                },
+            ),
+        ] {
+            let command_str = String::from_utf8_lossy(&command).into_owned();
+            assert_eq!(
+                receiver
+                    .parse(&mut command.iter())
+                    .unwrap()
+                    .parse_esearch(ProtocolVersion::Rev1)
+                    .expect(&command_str),
+                arguments,
+                "{}",
+                command_str
+            );
+        }
+
+        for command in [
+            b"tag4 ESEARCH IN (personal) RETURN (SAVE) SEEN\r\n".to_vec(),
+            b"tag5 ESEARCH IN () SEEN\r\n".to_vec(),
+        ] {
+            assert!(receiver
+                .parse(&mut command.iter())
+                .unwrap()
+                .parse_esearch(ProtocolVersion::Rev1)
+                .is_err());
+        }
+    }
 }
@@ -0,0 +1,425 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use chrono::DateTime;
+
+use crate::{
+    protocol::{
+        fetch::Section,
+        urlauth::{self, Access, ImapUrl, Mechanism, UrlAuth},
+        ProtocolVersion,
+    },
+    receiver::Request,
+    utf7::utf7_maybe_decode,
+    Command,
+};
+
+impl Request<Command> {
+    pub fn parse_genurlauth(self) -> crate::Result<urlauth::GenArguments> {
+        if self.tokens.is_empty() || self.tokens.len() % 2 != 0 {
+            return Err(self.into_error("Expected URL and mechanism pairs."));
+        }
+
+        let mut urls = Vec::with_capacity(self.tokens.len() / 2);
+        let mut tokens = self.tokens.into_iter();
+        while let (Some(url), Some(mechanism)) = (tokens.next(), tokens.next()) {
+            let url = ImapUrl::parse(&url.unwrap_string().map_err(|v| (self.tag.as_str(), v))?)
+                .map_err(|v| (self.tag.as_str(), v))?;
+            if url.host.is_none() || url.user.is_none() {
+                return Err((self.tag.as_str(), "Expected an absolute IMAP URL.").into());
+            } else if !matches!(&url.url_auth, Some(url_auth) if url_auth.mechanism.is_none()) {
+                return Err((self.tag.as_str(), "Expected an URLAUTH rump URL.").into());
+            }
+            urls.push((
+                url,
+                Mechanism::parse(&mechanism.unwrap_bytes()).map_err(|v| (self.tag.as_str(), v))?,
+            ));
+        }
+
+        Ok(urlauth::GenArguments {
+            tag: self.tag,
+            urls,
+        })
+    }
+
+    pub fn parse_resetkey(
+        self,
+        version: ProtocolVersion,
+    ) -> crate::Result<urlauth::ResetKeyArguments> {
+        let mut tokens = self.tokens.into_iter();
+        let mailbox_name = if let Some(token) = tokens.next() {
+            Some(utf7_maybe_decode(
+                token.unwrap_string().map_err(|v| (self.tag.as_str(), v))?,
+                version,
+            ))
+        } else {
+            None
+        };
+        let mut mechanisms = Vec::new();
+        for token in tokens {
+            mechanisms
+                .push(Mechanism::parse(&token.unwrap_bytes()).map_err(|v| (self.tag.as_str(), v))?);
+        }
+
+        Ok(urlauth::ResetKeyArguments {
+            tag: self.tag,
+            mailbox_name,
+            mechanisms,
+        })
+    }
+
+    pub fn parse_urlfetch(self) -> crate::Result<urlauth::FetchArguments> {
+        if self.tokens.is_empty() {
+            return Err(self.into_error("Missing URLs."));
+        }
+
+        let mut urls = Vec::with_capacity(self.tokens.len());
+        for token in self.tokens {
+            urls.push(token.unwrap_string().map_err(|v| (self.tag.as_str(), v))?);
+        }
+
+        Ok(urlauth::FetchArguments {
+            tag: self.tag,
+            urls,
+        })
+    }
+}
+
+impl Mechanism {
+    pub fn parse(value: &[u8]) -> super::Result<Self> {
+        if value.eq_ignore_ascii_case(b"INTERNAL") {
+            Ok(Mechanism::Internal)
+        } else {
+            Err(format!(
+                "Unsupported URLAUTH mechanism '{}'.",
+                String::from_utf8_lossy(value)
+            )
+            .into())
+        }
+    }
+}
+
+impl ImapUrl {
+    pub fn parse(url: &str) -> super::Result<Self> {
+        // Server and user
+        let (user, host, path) = if url
+            .get(..7)
+            .map_or(false, |scheme| scheme.eq_ignore_ascii_case("imap://"))
+        {
+            let (authority, path) = url[7..].split_once('/').ok_or("Missing URL path.")?;
+            if let Some((user_info, host)) = authority.rsplit_once('@') {
+                let user = user_info.split(';').next().unwrap_or_default();
+                (
+                    if !user.is_empty() {
+                        Some(percent_decode(user)?)
+                    } else {
+                        None
+                    },
+                    Some(host.to_string()),
+                    path,
+                )
+            } else {
+                (None, Some(authority.to_string()), path)
+            }
+        } else if let Some(path) = url.strip_prefix('/') {
+            (None, None, path)
+        } else {
+            return Err("Invalid IMAP URL.".into());
+        };
+
+        // Mailbox reference
+        let uid_pos = find_ignore_case(path, ";UID=").ok_or("Missing message UID in URL.")?;
+        let mut mailbox_ref = path[..uid_pos].trim_end_matches('/');
+        let mut uid_validity = None;
+        if let Some(pos) = find_ignore_case(mailbox_ref, ";UIDVALIDITY=") {
+            uid_validity = Some(
+                mailbox_ref[pos + 13..]
+                    .parse::<u32>()
+                    .map_err(|_| "Invalid UIDVALIDITY in URL.")?,
+            );
+            mailbox_ref = &mailbox_ref[..pos];
+        }
+        let mailbox_name = percent_decode(mailbox_ref)?;
+        if mailbox_name.is_empty() {
+            return Err("Missing mailbox name in URL.".into());
+        }
+
+        let mut imap_url = ImapUrl {
+            user,
+            host,
+            mailbox_name,
+            uid_validity,
+            uid: 0,
+            section: Vec::new(),
+            partial: None,
+            url_auth: None,
+        };
+        let mut expires = None;
+
+        for param in path[uid_pos + 1..].split(';') {
+            if imap_url.url_auth.is_some() {
+                return Err("URLAUTH must be the last URL component.".into());
+            }
+            let (name, value) = param
+                .trim_end_matches('/')
+                .split_once('=')
+                .ok_or("Invalid URL parameter.")?;
+            if name.eq_ignore_ascii_case("UID") {
+                imap_url.uid = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&uid| uid > 0)
+                    .ok_or("Invalid UID in URL.")?;
+            } else if name.eq_ignore_ascii_case("SECTION") {
+                imap_url.section = parse_section(&percent_decode(value)?)?;
+            } else if name.eq_ignore_ascii_case("PARTIAL") {
+                let (start, length) = value.split_once('.').unwrap_or((value, ""));
+                let start = start
+                    .parse::<u32>()
+                    .map_err(|_| "Invalid PARTIAL in URL.")?;
+                let length = if !length.is_empty() {
+                    length
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&length| length > 0)
+                        .ok_or("Invalid PARTIAL in URL.")?
+                } else {
+                    u32::MAX - start
+                };
+                imap_url.partial = Some((start, length));
+            } else if name.eq_ignore_ascii_case("EXPIRE") {
+                expires = DateTime::parse_from_rfc3339(&percent_decode(value)?)
+                    .map_err(|_| "Invalid EXPIRE date in URL.")?
+                    .timestamp()
+                    .into();
+            } else if name.eq_ignore_ascii_case("URLAUTH") {
+                let mut parts = value.splitn(3, ':');
+                let access = parts.next().unwrap_or_default();
+                let rump_end = find_ignore_case(url, ";URLAUTH=").unwrap() + 9 + access.len();
+                let access = if access.eq_ignore_ascii_case("anonymous") {
+                    Access::Anonymous
+                } else if access.eq_ignore_ascii_case("authuser") {
+                    Access::AuthUser
+                } else if let Some(user) = strip_prefix_ignore_case(access, "submit+") {
+                    Access::Submit(percent_decode(user)?)
+                } else if let Some(user) = strip_prefix_ignore_case(access, "user+") {
+                    Access::User(percent_decode(user)?)
+                } else {
+                    return Err("Invalid URLAUTH access identifier.".into());
+                };
+                let (mechanism, token) = match (parts.next(), parts.next()) {
+                    (Some(mechanism), Some(token)) => {
+                        if token.len() < 32 || !token.bytes().all(|ch| ch.is_ascii_hexdigit()) {
+                            return Err("Invalid URLAUTH token.".into());
+                        }
+                        (
+                            Some(Mechanism::parse(mechanism.as_bytes())?),
+                            Some(token.to_ascii_lowercase()),
+                        )
+                    }
+                    (None, None) => (None, None),
+                    _ => return Err("Invalid URLAUTH verifier.".into()),
+                };
+                imap_url.url_auth = Some(UrlAuth {
+                    rump: url[..rump_end].to_string(),
+                    expires,
+                    access,
+                    mechanism,
+                    token,
+                });
+            } else {
+                return Err("Unsupported URL parameter.".into());
+            }
+        }
+
+        if imap_url.uid == 0 {
+            Err("Missing message UID in URL.".into())
+        } else if expires.is_some() && imap_url.url_auth.is_none() {
+            Err("EXPIRE is only valid in URLAUTH URLs.".into())
+        } else {
+            Ok(imap_url)
+        }
+    }
+}
+
+fn parse_section(value: &str) -> super::Result<Vec<Section>> {
+    let mut sections = Vec::new();
+    for part in value.split('.') {
+        sections.push(if let Ok(num) = part.parse::<u32>() {
+            if num == 0 {
+                return Err("Invalid section number in URL.".into());
+            }
+            Section::Part { num }
+        } else if part.eq_ignore_ascii_case("HEADER") {
+            Section::Header
+        } else if part.eq_ignore_ascii_case("TEXT") {
+            Section::Text
+        } else if part.eq_ignore_ascii_case("MIME") {
+            Section::Mime
+        } else {
+            return Err("Unsupported section in URL.".into());
+        });
+    }
+    Ok(sections)
+}
+
+fn percent_decode(value: &str) -> super::Result<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(ch) = iter.next() {
+        if ch == b'%' {
+            match (
+                iter.next().and_then(|ch| (ch as char).to_digit(16)),
+                iter.next().and_then(|ch| (ch as char).to_digit(16)),
+            ) {
+                (Some(hi), Some(lo)) => bytes.push(((hi << 4) | lo) as u8),
+                _ => return Err("Invalid percent encoding in URL.".into()),
+            }
+        } else {
+            bytes.push(ch);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 in URL.".into())
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn strip_prefix_ignore_case<'x>(value: &'x str, prefix: &str) -> Option<&'x str> {
+    if value
+        .get(..prefix.len())
+        .map_or(false, |start| start.eq_ignore_ascii_case(prefix))
+    {
+        Some(&value[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::{
+        fetch::Section,
+        urlauth::{Access, ImapUrl, Mechanism, UrlAuth},
+    };
+
+    #[test]
+    fn parse_imap_url() {
+        for (url, expected) in [
+            (
+                "/INBOX;UIDVALIDITY=385759045/;UID=20/;SECTION=1.2.MIME",
+                ImapUrl {
+                    user: None,
+                    host: None,
+                    mailbox_name: "INBOX".to_string(),
+                    uid_validity: Some(385759045),
+                    uid: 20,
+                    section: vec![
+                        Section::Part { num: 1 },
+                        Section::Part { num: 2 },
+                        Section::Mime,
+                    ],
+                    partial: None,
+                    url_auth: None,
+                },
+            ),
+            (
+                "imap://joe@example.com/Sent%20Items/;uid=3/;partial=0.1024",
+                ImapUrl {
+                    user: Some("joe".to_string()),
+                    host: Some("example.com".to_string()),
+                    mailbox_name: "Sent Items".to_string(),
+                    uid_validity: None,
+                    uid: 3,
+                    section: vec![],
+                    partial: Some((0, 1024)),
+                    url_auth: None,
+                },
+            ),
+            (
+                concat!(
+                    "imap://joe;AUTH=*@example.com/INBOX/;uid=20/;section=1.2;",
+                    "urlauth=submit+fred:internal:91354a473744909de610943775f92038"
+                ),
+                ImapUrl {
+                    user: Some("joe".to_string()),
+                    host: Some("example.com".to_string()),
+                    mailbox_name: "INBOX".to_string(),
+                    uid_validity: None,
+                    uid: 20,
+                    section: vec![Section::Part { num: 1 }, Section::Part { num: 2 }],
+                    partial: None,
+                    url_auth: Some(UrlAuth {
+                        rump: concat!(
+                            "imap://joe;AUTH=*@example.com/INBOX/;uid=20/;section=1.2;",
+                            "urlauth=submit+fred"
+                        )
+                        .to_string(),
+                        expires: None,
+                        access: Access::Submit("fred".to_string()),
+                        mechanism: Some(Mechanism::Internal),
+                        token: Some("91354a473744909de610943775f92038".to_string()),
+                    }),
+                },
+            ),
+            (
+                "imap://joe@example.com/INBOX/;uid=20;expire=2023-01-01T00:00:00Z;urlauth=anonymous",
+                ImapUrl {
+                    user: Some("joe".to_string()),
+                    host: Some("example.com".to_string()),
+                    mailbox_name: "INBOX".to_string(),
+                    uid_validity: None,
+                    uid: 20,
+                    section: vec![],
+                    partial: None,
+                    url_auth: Some(UrlAuth {
+                        rump: "imap://joe@example.com/INBOX/;uid=20;expire=2023-01-01T00:00:00Z;urlauth=anonymous".to_string(),
+                        expires: Some(1672531200),
+                        access: Access::Anonymous,
+                        mechanism: None,
+                        token: None,
+                    }),
+                },
+            ),
+        ] {
+            assert_eq!(ImapUrl::parse(url).unwrap(), expected, "{url}");
+        }
+
+        for url in [
+            "INBOX/;UID=20",
+            "/INBOX",
+            "/INBOX/;UID=0",
+            "/;UID=20",
+            "/INBOX/;UID=20/;SECTION=HEADER.FIELDS",
+            "/INBOX/;UID=20;EXPIRE=2023-01-01T00:00:00Z",
+            "/INBOX/;UID=20;URLAUTH=anonymous;SECTION=1",
+            "/INBOX/;UID=20;URLAUTH=anonymous:internal:1234",
+        ] {
+            assert!(ImapUrl::parse(url).is_err(), "{url}");
+        }
+    }
+}
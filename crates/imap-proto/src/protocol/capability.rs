@@ -64,6 +64,21 @@ pub enum Capability {
     ObjectId,
     Preview,
     Utf8Accept,
+    Metadata,
+    MetadataServer, //METADATA-SERVER
+    Notify,
+    CompressDeflate, //COMPRESS=DEFLATE
+    Catenate,
+    UrlAuth,
+    Replace,
+    SaveDate,
+    MultiSearch,
+    Partial,
+    InProgress,
+    SearchFuzzy,
+    ListMyRights,
+    ListMetadata,
+    AppendLimit,
     Auth(Mechanism),
 }
 
@@ -109,6 +124,21 @@ impl Capability {
             Capability::CreateSpecialUse => b"CREATE-SPECIAL-USE",
             Capability::Move => b"MOVE",
             Capability::Utf8Accept => b"UTF8=ACCEPT",
+            Capability::Metadata => b"METADATA",
+            Capability::MetadataServer => b"METADATA-SERVER",
+            Capability::Notify => b"NOTIFY",
+            Capability::CompressDeflate => b"COMPRESS=DEFLATE",
+            Capability::Catenate => b"CATENATE",
+            Capability::UrlAuth => b"URLAUTH",
+            Capability::Replace => b"REPLACE",
+            Capability::SaveDate => b"SAVEDATE",
+            Capability::MultiSearch => b"MULTISEARCH",
+            Capability::Partial => b"PARTIAL",
+            Capability::InProgress => b"INPROGRESS",
+            Capability::SearchFuzzy => b"SEARCH=FUZZY",
+            Capability::ListMyRights => b"LIST-MYRIGHTS",
+            Capability::ListMetadata => b"LIST-METADATA",
+            Capability::AppendLimit => b"APPENDLIMIT",
         });
     }
 
@@ -150,6 +180,21 @@ impl Capability {
                 Capability::StatusSize,
                 Capability::ObjectId,
                 Capability::Preview,
+                Capability::Metadata,
+                Capability::MetadataServer,
+                Capability::Notify,
+                Capability::CompressDeflate,
+                Capability::Catenate,
+                Capability::UrlAuth,
+                Capability::Replace,
+                Capability::SaveDate,
+                Capability::MultiSearch,
+                Capability::Partial,
+                Capability::InProgress,
+                Capability::SearchFuzzy,
+                Capability::ListMyRights,
+                Capability::ListMetadata,
+                Capability::AppendLimit,
             ]);
         } else {
             capabilties.extend([
@@ -24,7 +24,8 @@
 use crate::utf7::utf7_encode;
 
 use super::{
-    quoted_string,
+    acl::MyRightsResponse,
+    metadata, quoted_string,
     status::{Status, StatusItem},
     ImapResponse,
 };
@@ -51,6 +52,8 @@ pub struct Response {
     pub is_lsub: bool,
     pub list_items: Vec<ListItem>,
     pub status_items: Vec<StatusItem>,
+    pub rights_items: Vec<MyRightsResponse>,
+    pub metadata_items: Vec<metadata::Response>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -67,6 +70,8 @@ pub enum ReturnOption {
     Children,
     Status(Vec<Status>),
     SpecialUse,
+    MyRights,
+    Metadata(Vec<String>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -88,6 +93,8 @@ pub enum Attribute {
     Sent,
     Trash,
     Important,
+    Snoozed,
+    Virtual,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -159,6 +166,8 @@ impl Attribute {
             Attribute::Sent => b"\\Sent",
             Attribute::Trash => b"\\Trash",
             Attribute::Important => b"\\Important",
+            Attribute::Snoozed => b"\\Snoozed",
+            Attribute::Virtual => b"\\Virtual",
         });
     }
 }
@@ -174,6 +183,7 @@ impl TryFrom<&str> for Attribute {
             "sent" => Ok(Attribute::Sent),
             "trash" => Ok(Attribute::Trash),
             "important" => Ok(Attribute::Important),
+            "snoozed" => Ok(Attribute::Snoozed),
             _ => Err(()),
         }
     }
@@ -273,6 +283,14 @@ impl ImapResponse for Response {
         for status_item in &self.status_items {
             status_item.serialize(&mut buf, self.is_rev2);
         }
+
+        for rights_item in self.rights_items {
+            buf.extend(rights_item.into_bytes(self.is_rev2));
+        }
+
+        for metadata_item in self.metadata_items {
+            buf.extend(metadata_item.into_bytes(self.is_rev2));
+        }
         buf
     }
 }
@@ -280,6 +298,8 @@ impl ImapResponse for Response {
 #[cfg(test)]
 mod tests {
     use crate::protocol::{
+        acl::{MyRightsResponse, Rights},
+        metadata,
         status::{Status, StatusItem, StatusItemType},
         ImapResponse,
     };
@@ -377,6 +397,17 @@ mod tests {
                     ],
                 },
             ],
+            rights_items: vec![MyRightsResponse {
+                mailbox_name: "foo".to_string(),
+                rights: vec![Rights::Lookup, Rights::Read],
+            }],
+            metadata_items: vec![metadata::Response {
+                mailbox_name: "INBOX".to_string(),
+                entries: vec![(
+                    "/shared/comment".to_string(),
+                    Some("Inbox comment".to_string()),
+                )],
+            }],
             is_lsub: false,
             is_rev2: true,
         };
@@ -385,6 +416,8 @@ mod tests {
             "* LIST () \"/\" \"foo\" (\"CHILDINFO\" (\"SUBSCRIBED\"))\r\n",
             "* STATUS \"INBOX\" (MESSAGES 17)\r\n",
             "* STATUS \"foo\" (MESSAGES 30 UNSEEN 29)\r\n",
+            "* MYRIGHTS \"foo\" lr\r\n",
+            "* METADATA \"INBOX\" (\"/shared/comment\" \"Inbox comment\")\r\n",
         );
         let expected_v1 = concat!(
             "* LSUB (\\Subscribed) \"/\" \"INBOX\"\r\n",
@@ -395,6 +428,8 @@ mod tests {
         response.is_rev2 = false;
         response.is_lsub = true;
         response.status_items.clear();
+        response.rights_items.clear();
+        response.metadata_items.clear();
         let response_v1 = String::from_utf8(response.serialize()).unwrap();
 
         assert_eq!(response_v2, expected_v2);
@@ -0,0 +1,183 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use crate::utf7::utf7_encode;
+
+use super::{literal_string, quoted_string};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetArguments {
+    pub tag: String,
+    pub mailbox_name: String,
+    pub entries: Vec<String>,
+    pub max_size: Option<usize>,
+    pub depth: Depth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetArguments {
+    pub tag: String,
+    pub mailbox_name: String,
+    pub entries: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Depth {
+    #[default]
+    Zero,
+    One,
+    Infinity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub mailbox_name: String,
+    pub entries: Vec<(String, Option<String>)>,
+}
+
+impl Response {
+    pub fn into_bytes(self, is_rev2: bool) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            self.mailbox_name.len()
+                + 16
+                + self
+                    .entries
+                    .iter()
+                    .map(|(entry, value)| entry.len() + value.as_ref().map_or(3, |v| v.len()) + 4)
+                    .sum::<usize>(),
+        );
+        buf.extend_from_slice(b"* METADATA ");
+        if is_rev2 {
+            quoted_string(&mut buf, &self.mailbox_name);
+        } else {
+            quoted_string(&mut buf, &utf7_encode(&self.mailbox_name));
+        }
+        buf.extend_from_slice(b" (");
+        for (pos, (entry, value)) in self.entries.into_iter().enumerate() {
+            if pos > 0 {
+                buf.push(b' ');
+            }
+            quoted_string(&mut buf, &entry);
+            buf.push(b' ');
+            match value {
+                Some(value)
+                    if value
+                        .as_bytes()
+                        .iter()
+                        .any(|&ch| ch == b'\r' || ch == b'\n' || ch == 0 || ch > 0x7f) =>
+                {
+                    literal_string(&mut buf, &value);
+                }
+                Some(value) => {
+                    quoted_string(&mut buf, &value);
+                }
+                None => {
+                    buf.extend_from_slice(b"NIL");
+                }
+            }
+        }
+        buf.extend_from_slice(b")\r\n");
+        buf
+    }
+}
+
+impl Depth {
+    pub fn matches(&self, entry: &str, requested: &str) -> bool {
+        if entry == requested {
+            true
+        } else if let Some(child) = entry
+            .strip_prefix(requested)
+            .and_then(|child| child.strip_prefix('/'))
+        {
+            match self {
+                Depth::Zero => false,
+                Depth::One => !child.contains('/'),
+                Depth::Infinity => true,
+            }
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::metadata::{Depth, Response};
+
+    #[test]
+    fn serialize_metadata() {
+        assert_eq!(
+            String::from_utf8(
+                Response {
+                    mailbox_name: "INBOX".to_string(),
+                    entries: vec![
+                        (
+                            "/shared/comment".to_string(),
+                            Some("Shared comment".to_string())
+                        ),
+                        (
+                            "/private/comment".to_string(),
+                            Some("My own\r\ncomment".to_string())
+                        ),
+                        ("/shared/vendor/foo".to_string(), None),
+                    ]
+                }
+                .into_bytes(true)
+            )
+            .unwrap(),
+            concat!(
+                "* METADATA \"INBOX\" (\"/shared/comment\" \"Shared comment\" ",
+                "\"/private/comment\" {15}\r\nMy own\r\ncomment ",
+                "\"/shared/vendor/foo\" NIL)\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn metadata_depth() {
+        for (depth, entry, requested, expected) in [
+            (Depth::Zero, "/shared/comment", "/shared/comment", true),
+            (Depth::Zero, "/shared/comment/a", "/shared/comment", false),
+            (Depth::One, "/shared/comment/a", "/shared/comment", true),
+            (Depth::One, "/shared/comment/a/b", "/shared/comment", false),
+            (
+                Depth::Infinity,
+                "/shared/comment/a/b",
+                "/shared/comment",
+                true,
+            ),
+            (
+                Depth::Infinity,
+                "/shared/comments",
+                "/shared/comment",
+                false,
+            ),
+        ] {
+            assert_eq!(
+                depth.matches(entry, requested),
+                expected,
+                "{depth:?} {entry} {requested}"
+            );
+        }
+    }
+}
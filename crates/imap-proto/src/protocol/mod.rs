@@ -33,6 +33,7 @@ pub mod acl;
 pub mod append;
 pub mod authenticate;
 pub mod capability;
+pub mod compress;
 pub mod copy_move;
 pub mod create;
 pub mod delete;
@@ -41,14 +42,18 @@ pub mod expunge;
 pub mod fetch;
 pub mod list;
 pub mod login;
+pub mod metadata;
 pub mod namespace;
+pub mod notify;
 pub mod rename;
+pub mod replace;
 pub mod search;
 pub mod select;
 pub mod status;
 pub mod store;
 pub mod subscribe;
 pub mod thread;
+pub mod urlauth;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProtocolVersion {
@@ -71,6 +76,12 @@ pub enum Sequence {
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialRange {
+    pub start: i32,
+    pub end: i32,
+}
+
 impl Sequence {
     pub fn number(value: u32) -> Sequence {
         Sequence::Number { value }
@@ -377,6 +388,44 @@ impl ResponseCode {
                 return;
             }
             ResponseCode::UseAttr => b"USEATTR",
+            ResponseCode::MetadataLongEntries { size } => {
+                buf.extend_from_slice(b"METADATA LONGENTRIES ");
+                buf.extend_from_slice(size.to_string().as_bytes());
+                return;
+            }
+            ResponseCode::MetadataMaxSize { size } => {
+                buf.extend_from_slice(b"METADATA MAXSIZE ");
+                buf.extend_from_slice(size.to_string().as_bytes());
+                return;
+            }
+            ResponseCode::MetadataTooMany => b"METADATA TOOMANY",
+            ResponseCode::MetadataNoPrivate => b"METADATA NOPRIVATE",
+            ResponseCode::BadEvent => b"BADEVENT",
+            ResponseCode::CompressionActive => b"COMPRESSIONACTIVE",
+            ResponseCode::TooBig => b"TOOBIG",
+            ResponseCode::BadUrl { url } => {
+                buf.extend_from_slice(b"BADURL ");
+                buf.extend_from_slice(url.as_bytes());
+                return;
+            }
+            ResponseCode::InProgress {
+                tag,
+                current,
+                total,
+            } => {
+                buf.extend_from_slice(b"INPROGRESS (");
+                quoted_string(buf, tag);
+                for value in [current, total] {
+                    buf.push(b' ');
+                    if let Some(value) = value {
+                        buf.extend_from_slice(value.to_string().as_bytes());
+                    } else {
+                        buf.extend_from_slice(b"NIL");
+                    }
+                }
+                buf.push(b')');
+                return;
+            }
         });
     }
 }
@@ -418,6 +467,36 @@ impl StatusResponse {
     }
 }
 
+impl PartialRange {
+    pub fn slice<'x, T>(&self, items: &'x [T]) -> &'x [T] {
+        let (from, to) = if self.start.unsigned_abs() <= self.end.unsigned_abs() {
+            (
+                self.start.unsigned_abs() as usize,
+                self.end.unsigned_abs() as usize,
+            )
+        } else {
+            (
+                self.end.unsigned_abs() as usize,
+                self.start.unsigned_abs() as usize,
+            )
+        };
+        let len = items.len();
+        if from == 0 || from > len {
+            &[]
+        } else if self.start > 0 {
+            &items[from - 1..to.min(len)]
+        } else {
+            &items[len.saturating_sub(to)..len - from + 1]
+        }
+    }
+
+    pub fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.start.to_string().as_bytes());
+        buf.push(b':');
+        buf.extend_from_slice(self.end.to_string().as_bytes());
+    }
+}
+
 impl ProtocolVersion {
     #[inline(always)]
     pub fn is_rev2(&self) -> bool {
@@ -504,6 +583,16 @@ impl Display for Command {
             Command::ListRights => write!(f, "LISTRIGHTS"),
             Command::MyRights => write!(f, "MYRIGHTS"),
             Command::Unauthenticate => write!(f, "UNAUTHENTICATE"),
+            Command::GetMetadata => write!(f, "GETMETADATA"),
+            Command::SetMetadata => write!(f, "SETMETADATA"),
+            Command::Notify => write!(f, "NOTIFY"),
+            Command::Compress => write!(f, "COMPRESS"),
+            Command::GenUrlAuth => write!(f, "GENURLAUTH"),
+            Command::ResetKey => write!(f, "RESETKEY"),
+            Command::UrlFetch => write!(f, "URLFETCH"),
+            Command::Replace(false) => write!(f, "REPLACE"),
+            Command::Replace(true) => write!(f, "UID REPLACE"),
+            Command::Esearch => write!(f, "ESEARCH"),
             Command::Id => write!(f, "ID"),
         }
     }
@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arguments {
+    pub tag: String,
+    pub status: bool,
+    pub groups: Vec<EventGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventGroup {
+    pub filter: Filter,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Selected,
+    SelectedDelayed,
+    Personal,
+    Inboxes,
+    Subscribed,
+    Subtree(Vec<String>),
+    SubtreeOne(Vec<String>),
+    Mailboxes(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    MessageNew,
+    MessageExpunge,
+    FlagChange,
+    AnnotationChange,
+    MailboxName,
+    SubscriptionChange,
+    MailboxMetadataChange,
+    ServerMetadataChange,
+}
+
+impl Filter {
+    pub fn is_selected(&self) -> bool {
+        matches!(self, Filter::Selected | Filter::SelectedDelayed)
+    }
+
+    pub fn matches(&self, mailbox_name: &str, is_personal: bool, is_subscribed: bool) -> bool {
+        match self {
+            Filter::Selected | Filter::SelectedDelayed => false,
+            Filter::Personal => is_personal,
+            Filter::Inboxes => is_personal && mailbox_name.eq_ignore_ascii_case("INBOX"),
+            Filter::Subscribed => is_subscribed,
+            Filter::Subtree(names) => names.iter().any(|name| {
+                mailbox_name == name
+                    || mailbox_name
+                        .strip_prefix(name.as_str())
+                        .map_or(false, |child| child.starts_with('/'))
+            }),
+            Filter::SubtreeOne(names) => names.iter().any(|name| {
+                mailbox_name == name
+                    || mailbox_name
+                        .strip_prefix(name.as_str())
+                        .and_then(|child| child.strip_prefix('/'))
+                        .map_or(false, |child| !child.is_empty() && !child.contains('/'))
+            }),
+            Filter::Mailboxes(names) => names.iter().any(|name| mailbox_name == name),
+        }
+    }
+}
+
+impl EventGroup {
+    pub fn has_message_events(&self) -> bool {
+        self.events.iter().any(|event| {
+            matches!(
+                event,
+                Event::MessageNew | Event::MessageExpunge | Event::FlagChange
+            )
+        })
+    }
+
+    pub fn has_event(&self, event: Event) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::notify::Filter;
+
+    #[test]
+    fn notify_filter() {
+        for (filter, mailbox_name, is_personal, is_subscribed, expected) in [
+            (Filter::Personal, "Drafts", true, false, true),
+            (
+                Filter::Personal,
+                "Shared Folders/jane/INBOX",
+                false,
+                false,
+                false,
+            ),
+            (Filter::Inboxes, "INBOX", true, false, true),
+            (Filter::Inboxes, "Drafts", true, true, false),
+            (Filter::Subscribed, "Drafts", true, true, true),
+            (Filter::Subscribed, "Drafts", true, false, false),
+            (
+                Filter::Subtree(vec!["Lists".to_string()]),
+                "Lists/rust",
+                true,
+                false,
+                true,
+            ),
+            (
+                Filter::Subtree(vec!["Lists".to_string()]),
+                "Listserv",
+                true,
+                false,
+                false,
+            ),
+            (
+                Filter::SubtreeOne(vec!["Lists".to_string()]),
+                "Lists/rust",
+                true,
+                false,
+                true,
+            ),
+            (
+                Filter::SubtreeOne(vec!["Lists".to_string()]),
+                "Lists/rust/tokio",
+                true,
+                false,
+                false,
+            ),
+            (
+                Filter::Mailboxes(vec!["Lists".to_string()]),
+                "Lists/rust",
+                true,
+                false,
+                false,
+            ),
+            (Filter::Selected, "INBOX", true, true, false),
+        ] {
+            assert_eq!(
+                filter.matches(mailbox_name, is_personal, is_subscribed),
+                expected,
+                "{filter:?} {mailbox_name}"
+            );
+        }
+    }
+}
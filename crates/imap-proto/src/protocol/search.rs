@@ -21,7 +21,9 @@
  * for more details.
 */
 
-use super::{quoted_string, serialize_sequence, Flag, Sequence};
+use crate::utf7::utf7_encode;
+
+use super::{notify, quoted_string, serialize_sequence, Flag, PartialRange, Sequence};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Arguments {
@@ -32,6 +34,12 @@ pub struct Arguments {
     pub filter: Vec<Filter>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiArguments {
+    pub sources: Vec<notify::Filter>,
+    pub search: Arguments,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Sort {
     Arrival,
@@ -43,6 +51,7 @@ pub enum Sort {
     Subject,
     To,
     DisplayTo,
+    Relevancy,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,9 +69,18 @@ pub struct Response {
     pub min: Option<u32>,
     pub max: Option<u32>,
     pub count: Option<u32>,
+    pub partial: Option<(PartialRange, Vec<u32>)>,
+    pub relevancy: Vec<u32>,
     pub highest_modseq: Option<u64>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiResponse {
+    pub mailbox_name: String,
+    pub uid_validity: u32,
+    pub response: Response,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ResultOption {
     Min,
@@ -71,6 +89,8 @@ pub enum ResultOption {
     Count,
     Save,
     Context,
+    Partial(PartialRange),
+    Relevancy,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -112,6 +132,9 @@ pub enum Filter {
     Not,
     End,
 
+    // RFC 6203 - SEARCH=FUZZY
+    Fuzzy,
+
     // Imap4rev1
     Recent,
     New,
@@ -127,6 +150,12 @@ pub enum Filter {
     // RFC 8474 - ObjectID
     EmailId(String),
     ThreadId(String),
+
+    // RFC 8514 - SAVEDATE
+    SavedBefore(i64),
+    SavedOn(i64),
+    SavedSince(i64),
+    SaveDateSupported,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -154,29 +183,7 @@ impl Response {
             buf.extend_from_slice(b"* ESEARCH (TAG ");
             quoted_string(&mut buf, tag);
             buf.extend_from_slice(b")");
-            if self.is_uid {
-                buf.extend_from_slice(b" UID");
-            }
-            if let Some(count) = &self.count {
-                buf.extend_from_slice(b" COUNT ");
-                buf.extend_from_slice(count.to_string().as_bytes());
-            }
-            if let Some(min) = &self.min {
-                buf.extend_from_slice(b" MIN ");
-                buf.extend_from_slice(min.to_string().as_bytes());
-            }
-            if let Some(max) = &self.max {
-                buf.extend_from_slice(b" MAX ");
-                buf.extend_from_slice(max.to_string().as_bytes());
-            }
-            if !self.ids.is_empty() {
-                buf.extend_from_slice(b" ALL ");
-                serialize_sequence(&mut buf, &self.ids);
-            }
-            if let Some(highest_modseq) = self.highest_modseq {
-                buf.extend_from_slice(b" MODSEQ ");
-                buf.extend_from_slice(highest_modseq.to_string().as_bytes());
-            }
+            self.serialize_esearch(&mut buf);
         } else {
             if !self.is_sort {
                 buf.extend_from_slice(b"* SEARCH");
@@ -198,6 +205,82 @@ impl Response {
         buf.extend_from_slice(b"\r\n");
         buf
     }
+
+    fn serialize_esearch(&self, buf: &mut Vec<u8>) {
+        if self.is_uid {
+            buf.extend_from_slice(b" UID");
+        }
+        if let Some(count) = &self.count {
+            buf.extend_from_slice(b" COUNT ");
+            buf.extend_from_slice(count.to_string().as_bytes());
+        }
+        if let Some(min) = &self.min {
+            buf.extend_from_slice(b" MIN ");
+            buf.extend_from_slice(min.to_string().as_bytes());
+        }
+        if let Some(max) = &self.max {
+            buf.extend_from_slice(b" MAX ");
+            buf.extend_from_slice(max.to_string().as_bytes());
+        }
+        if !self.ids.is_empty() {
+            buf.extend_from_slice(b" ALL ");
+            serialize_sequence(buf, &self.ids);
+        }
+        if let Some((range, ids)) = &self.partial {
+            buf.extend_from_slice(b" PARTIAL (");
+            range.serialize(buf);
+            if !ids.is_empty() {
+                buf.push(b' ');
+                serialize_sequence(buf, ids);
+            } else {
+                buf.extend_from_slice(b" NIL");
+            }
+            buf.push(b')');
+        }
+        if !self.relevancy.is_empty() {
+            buf.extend_from_slice(b" RELEVANCY (");
+            for (pos, score) in self.relevancy.iter().enumerate() {
+                if pos > 0 {
+                    buf.push(b' ');
+                }
+                buf.extend_from_slice(score.to_string().as_bytes());
+            }
+            buf.push(b')');
+        }
+        if let Some(highest_modseq) = self.highest_modseq {
+            buf.extend_from_slice(b" MODSEQ ");
+            buf.extend_from_slice(highest_modseq.to_string().as_bytes());
+        }
+    }
+
+    pub fn has_results(&self) -> bool {
+        !self.ids.is_empty()
+            || self.min.is_some()
+            || self.max.is_some()
+            || self.count.map_or(false, |count| count > 0)
+            || self
+                .partial
+                .as_ref()
+                .map_or(false, |(_, ids)| !ids.is_empty())
+    }
+}
+
+impl MultiResponse {
+    pub fn serialize(&self, buf: &mut Vec<u8>, tag: &str, is_rev2: bool) {
+        buf.extend_from_slice(b"* ESEARCH (TAG ");
+        quoted_string(buf, tag);
+        buf.extend_from_slice(b" MAILBOX ");
+        if is_rev2 {
+            quoted_string(buf, &self.mailbox_name);
+        } else {
+            quoted_string(buf, &utf7_encode(&self.mailbox_name));
+        }
+        buf.extend_from_slice(b" UIDVALIDITY ");
+        buf.extend_from_slice(self.uid_validity.to_string().as_bytes());
+        buf.extend_from_slice(b")");
+        self.response.serialize_esearch(buf);
+        buf.extend_from_slice(b"\r\n");
+    }
 }
 
 #[cfg(test)]
@@ -215,6 +298,8 @@ mod tests {
                     min: 2.into(),
                     max: 11.into(),
                     count: 3.into(),
+                    partial: None,
+                    relevancy: vec![],
                     highest_modseq: None,
                 },
                 "A283",
@@ -232,6 +317,8 @@ mod tests {
                     min: None,
                     max: None,
                     count: None,
+                    partial: None,
+                    relevancy: vec![],
                     highest_modseq: None,
                 },
                 "A283",
@@ -247,6 +334,8 @@ mod tests {
                     min: None,
                     max: None,
                     count: None,
+                    partial: None,
+                    relevancy: vec![],
                     highest_modseq: None,
                 },
                 "A283",
@@ -262,12 +351,51 @@ mod tests {
                     min: None,
                     max: None,
                     count: None,
+                    partial: None,
+                    relevancy: vec![],
                     highest_modseq: 12345.into(),
                 },
                 "A283",
                 concat!("* ESEARCH (TAG \"A283\") ALL 10:13,21 MODSEQ 12345\r\n",),
                 concat!("* SEARCH 10 11 12 13 21 (MODSEQ 12345)\r\n",),
             ),
+            (
+                super::Response {
+                    is_uid: true,
+                    is_esearch: true,
+                    is_sort: false,
+                    ids: vec![],
+                    min: None,
+                    max: None,
+                    count: None,
+                    partial: Some((
+                        super::PartialRange { start: 1, end: 5 },
+                        vec![106, 147, 159, 163, 164],
+                    )),
+                    relevancy: vec![],
+                    highest_modseq: None,
+                },
+                "A01",
+                concat!("* ESEARCH (TAG \"A01\") UID PARTIAL (1:5 106,147,159,163:164)\r\n",),
+                concat!("* SEARCH\r\n"),
+            ),
+            (
+                super::Response {
+                    is_uid: true,
+                    is_esearch: true,
+                    is_sort: false,
+                    ids: vec![2, 5, 9],
+                    min: None,
+                    max: None,
+                    count: None,
+                    partial: None,
+                    relevancy: vec![100, 76, 25],
+                    highest_modseq: None,
+                },
+                "A02",
+                concat!("* ESEARCH (TAG \"A02\") UID ALL 2,5,9 RELEVANCY (100 76 25)\r\n",),
+                concat!("* SEARCH 2 5 9\r\n"),
+            ),
         ] {
             let response_v2 = String::from_utf8(response.clone().serialize(tag)).unwrap();
             response.is_esearch = false;
@@ -277,4 +405,34 @@ mod tests {
             assert_eq!(response_v1, expected_v1);
         }
     }
+
+    #[test]
+    fn serialize_multisearch() {
+        let mut buf = Vec::new();
+        super::MultiResponse {
+            mailbox_name: "folder1".to_string(),
+            uid_validity: 1,
+            response: super::Response {
+                is_uid: true,
+                is_esearch: true,
+                is_sort: false,
+                ids: vec![3001, 3002, 3003, 3004, 3005, 3006],
+                min: None,
+                max: None,
+                count: None,
+                partial: None,
+                relevancy: vec![],
+                highest_modseq: None,
+            },
+        }
+        .serialize(&mut buf, "tag1", true);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            concat!(
+                "* ESEARCH (TAG \"tag1\" MAILBOX \"folder1\" UIDVALIDITY 1) ",
+                "UID ALL 3001:3006\r\n"
+            )
+        );
+    }
 }
@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use super::{fetch::Section, quoted_string, ImapResponse};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenArguments {
+    pub tag: String,
+    pub urls: Vec<(ImapUrl, Mechanism)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResetKeyArguments {
+    pub tag: String,
+    pub mailbox_name: Option<String>,
+    pub mechanisms: Vec<Mechanism>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchArguments {
+    pub tag: String,
+    pub urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mechanism {
+    Internal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapUrl {
+    pub user: Option<String>,
+    pub host: Option<String>,
+    pub mailbox_name: String,
+    pub uid_validity: Option<u32>,
+    pub uid: u32,
+    pub section: Vec<Section>,
+    pub partial: Option<(u32, u32)>,
+    pub url_auth: Option<UrlAuth>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlAuth {
+    pub rump: String,
+    pub expires: Option<i64>,
+    pub access: Access,
+    pub mechanism: Option<Mechanism>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Access {
+    Anonymous,
+    AuthUser,
+    User(String),
+    Submit(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenResponse {
+    pub urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchResponse {
+    pub urls: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl Mechanism {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mechanism::Internal => "INTERNAL",
+        }
+    }
+}
+
+impl Access {
+    pub fn allows(&self, user: &str, is_submission: bool) -> bool {
+        match self {
+            Access::Anonymous => true,
+            Access::AuthUser => !user.is_empty(),
+            Access::User(name) => !is_submission && name == user,
+            Access::Submit(name) => is_submission && name == user,
+        }
+    }
+}
+
+impl UrlAuth {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+}
+
+impl ImapResponse for GenResponse {
+    fn serialize(self) -> Vec<u8> {
+        let mut buf =
+            Vec::with_capacity(16 + self.urls.iter().map(|url| url.len() + 3).sum::<usize>());
+        buf.extend_from_slice(b"* GENURLAUTH");
+        for url in &self.urls {
+            buf.push(b' ');
+            quoted_string(&mut buf, url);
+        }
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+}
+
+impl ImapResponse for FetchResponse {
+    fn serialize(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            16 + self
+                .urls
+                .iter()
+                .map(|(url, data)| url.len() + data.as_ref().map_or(3, |d| d.len() + 12))
+                .sum::<usize>(),
+        );
+        buf.extend_from_slice(b"* URLFETCH");
+        for (url, data) in &self.urls {
+            buf.push(b' ');
+            quoted_string(&mut buf, url);
+            buf.push(b' ');
+            if let Some(data) = data {
+                buf.push(b'{');
+                buf.extend_from_slice(data.len().to_string().as_bytes());
+                buf.extend_from_slice(b"}\r\n");
+                buf.extend_from_slice(data);
+            } else {
+                buf.extend_from_slice(b"NIL");
+            }
+        }
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::ImapResponse;
+
+    #[test]
+    fn serialize_urlauth() {
+        assert_eq!(
+            String::from_utf8(
+                super::GenResponse {
+                    urls: vec!["imap://joe@example.com/INBOX/;uid=20/;section=1.2;urlauth=submit+fred:internal:91354a473744909de610943775f92038".to_string()],
+                }
+                .serialize()
+            )
+            .unwrap(),
+            concat!(
+                "* GENURLAUTH \"imap://joe@example.com/INBOX/;uid=20/;section=1.2;",
+                "urlauth=submit+fred:internal:91354a473744909de610943775f92038\"\r\n"
+            )
+        );
+
+        assert_eq!(
+            String::from_utf8(
+                super::FetchResponse {
+                    urls: vec![
+                        ("imap://a/INBOX/;uid=1".to_string(), Some(b"Hello".to_vec())),
+                        ("imap://a/INBOX/;uid=2".to_string(), None),
+                    ],
+                }
+                .serialize()
+            )
+            .unwrap(),
+            concat!(
+                "* URLFETCH \"imap://a/INBOX/;uid=1\" {5}\r\nHello ",
+                "\"imap://a/INBOX/;uid=2\" NIL\r\n"
+            )
+        );
+    }
+}
@@ -75,6 +75,24 @@ impl<T: AsyncRead> Session<T> {
 
         let mut requests = requests.into_iter().peekable();
         while let Some(request) = requests.next() {
+            // Deliver queued NOTIFY changes to the selected mailbox, unless
+            // the command refers to messages by sequence number (RFC 5465, 5)
+            if let State::Selected { data, mailbox, .. } = &self.state {
+                if !matches!(
+                    request.command,
+                    Command::Fetch(false)
+                        | Command::Store(false)
+                        | Command::Search(false)
+                        | Command::Sort(false)
+                        | Command::Thread(false)
+                        | Command::Copy(false)
+                        | Command::Move(false)
+                ) {
+                    data.flush_notify_changes(mailbox, self.is_qresync, self.version.is_rev2())
+                        .await;
+                }
+            }
+
             match request.command {
                 Command::List | Command::Lsub => {
                     self.handle_list(request).await?;
@@ -190,6 +208,33 @@ impl<T: AsyncRead> Session<T> {
                 Command::Unauthenticate => {
                     self.handle_unauthenticate(request).await?;
                 }
+                Command::GetMetadata => {
+                    self.handle_get_metadata(request).await?;
+                }
+                Command::SetMetadata => {
+                    self.handle_set_metadata(request).await?;
+                }
+                Command::Notify => {
+                    self.handle_notify(request).await?;
+                }
+                Command::Esearch => {
+                    self.handle_esearch(request).await?;
+                }
+                Command::Compress => {
+                    self.handle_compress(request).await?;
+                }
+                Command::GenUrlAuth => {
+                    self.handle_genurlauth(request).await?;
+                }
+                Command::ResetKey => {
+                    self.handle_resetkey(request).await?;
+                }
+                Command::UrlFetch => {
+                    self.handle_urlfetch(request).await?;
+                }
+                Command::Replace(is_uid) => {
+                    self.handle_replace(request, is_uid).await?;
+                }
                 Command::Id => {
                     self.handle_id(request).await?;
                 }
@@ -288,6 +333,14 @@ impl<T: AsyncRead> Session<T> {
             | Command::GetAcl
             | Command::ListRights
             | Command::MyRights
+            | Command::GetMetadata
+            | Command::SetMetadata
+            | Command::Notify
+            | Command::Esearch
+            | Command::Compress
+            | Command::GenUrlAuth
+            | Command::ResetKey
+            | Command::UrlFetch
             | Command::Unauthenticate => {
                 if let State::Authenticated { .. } | State::Selected { .. } = state {
                     Ok(request)
@@ -305,12 +358,16 @@ impl<T: AsyncRead> Session<T> {
             | Command::Move(_)
             | Command::Check
             | Command::Sort(_)
-            | Command::Thread(_) => match state {
+            | Command::Thread(_)
+            | Command::Replace(_) => match state {
                 State::Selected { mailbox, .. } => {
                     if mailbox.is_select
                         || !matches!(
                             request.command,
-                            Command::Store(_) | Command::Expunge(_) | Command::Move(_),
+                            Command::Store(_)
+                                | Command::Expunge(_)
+                                | Command::Move(_)
+                                | Command::Replace(_),
                         )
                     {
                         Ok(request)
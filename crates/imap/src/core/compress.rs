@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+const CHUNK_SIZE: usize = 8192;
+
+// Raw DEFLATE streams without zlib headers, as required by RFC 4978.
+
+pub struct Deflater {
+    compress: Compress,
+}
+
+pub struct Inflater {
+    decompress: Decompress,
+}
+
+impl Deflater {
+    pub fn new() -> Self {
+        Deflater {
+            compress: Compress::new(Compression::default(), false),
+        }
+    }
+
+    pub fn deflate(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut output = Vec::with_capacity(bytes.len() / 2 + 64);
+        let mut input = bytes;
+
+        loop {
+            if output.capacity() - output.len() < 64 {
+                output.reserve(CHUNK_SIZE);
+            }
+            let total_in = self.compress.total_in();
+            self.compress
+                .compress_vec(input, &mut output, FlushCompress::Sync)
+                .ok()?;
+            input = &input[(self.compress.total_in() - total_in) as usize..];
+
+            // The sync flush is complete once all input has been consumed
+            // and the compressor did not fill the output buffer.
+            if input.is_empty() && output.len() < output.capacity() {
+                break;
+            }
+        }
+
+        Some(output)
+    }
+}
+
+impl Inflater {
+    pub fn new() -> Self {
+        Inflater {
+            decompress: Decompress::new(false),
+        }
+    }
+
+    pub fn inflate(&mut self, bytes: &[u8], max_size: usize) -> Option<Vec<u8>> {
+        let mut output = Vec::with_capacity(bytes.len() * 2 + 64);
+        let mut input = bytes;
+
+        loop {
+            if output.capacity() - output.len() < 64 {
+                output.reserve(CHUNK_SIZE);
+            }
+            let total_in = self.decompress.total_in();
+            let total_out = self.decompress.total_out();
+            let status = self
+                .decompress
+                .decompress_vec(input, &mut output, FlushDecompress::Sync)
+                .ok()?;
+            let consumed = (self.decompress.total_in() - total_in) as usize;
+            input = &input[consumed..];
+
+            if output.len() > max_size {
+                return None;
+            } else if status == Status::StreamEnd
+                || (input.is_empty() && output.len() < output.capacity())
+                || (consumed == 0 && self.decompress.total_out() == total_out)
+            {
+                break;
+            }
+        }
+
+        Some(output)
+    }
+}
+
+impl Default for Deflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Inflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -13,7 +13,10 @@ use jmap_proto::{
 use parking_lot::Mutex;
 use store::query::log::{Change, Query};
 use tokio::io::AsyncRead;
-use utils::{listener::limiter::InFlight, map::mutex_map::MutexMap};
+use utils::{
+    listener::limiter::InFlight,
+    map::{bitmap::Bitmap, mutex_map::MutexMap},
+};
 
 use super::{Account, Mailbox, MailboxId, MailboxSync, Session, SessionData};
 
@@ -33,6 +36,7 @@ impl SessionData {
             state: access_token.state().into(),
             mailbox_locks: MutexMap::with_capacity(5),
             in_flight,
+            notify: Mutex::new(None),
         };
 
         // Fetch mailboxes for the main account
@@ -205,14 +209,13 @@ impl SessionData {
                                     _ => None,
                                 },
                             ),
+                            is_virtual: matches!(
+                                mailbox.properties.get(&Property::Filter),
+                                Some(Value::Object(_))
+                            ),
                             total_messages: self
                                 .jmap
-                                .get_tag(
-                                    account_id,
-                                    Collection::Email,
-                                    Property::MailboxIds,
-                                    *mailbox_id,
-                                )
+                                .mailbox_message_ids(access_token, account_id, *mailbox_id)
                                 .await
                                 .map_err(|_| {})?
                                 .map(|v| v.len() as u32)
@@ -220,7 +223,12 @@ impl SessionData {
                                 .into(),
                             total_unseen: self
                                 .jmap
-                                .mailbox_unread_tags(account_id, *mailbox_id, &message_ids)
+                                .mailbox_unread_tags(
+                                    access_token,
+                                    account_id,
+                                    *mailbox_id,
+                                    &message_ids,
+                                )
                                 .await
                                 .map_err(|_| {})?
                                 .map(|v| v.len() as u32)
@@ -527,6 +535,18 @@ impl SessionData {
         }
     }
 
+    pub fn is_virtual_mailbox(&self, mailbox: &MailboxId) -> bool {
+        mailbox.mailbox_id.map_or(false, |mailbox_id| {
+            self.mailboxes.lock().iter().any(|account| {
+                account.account_id == mailbox.account_id
+                    && account
+                        .mailbox_state
+                        .get(&mailbox_id)
+                        .map_or(false, |mailbox| mailbox.is_virtual)
+            })
+        })
+    }
+
     pub fn is_all_mailbox(&self, mailbox_name: &str) -> bool {
         self.imap.name_all == mailbox_name
     }
@@ -537,10 +557,21 @@ impl SessionData {
         document_id: u32,
         item: Acl,
     ) -> crate::op::Result<bool> {
+        self.get_mailbox_acl(account_id, document_id)
+            .await
+            .map(|acl| acl.contains(item))
+    }
+
+    pub async fn get_mailbox_acl(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> crate::op::Result<Bitmap<Acl>> {
         let access_token = self.get_access_token().await?;
-        Ok(access_token.is_member(account_id)
-            || self
-                .jmap
+        if access_token.is_member(account_id) {
+            Ok(Bitmap::all())
+        } else {
+            self.jmap
                 .get_property::<Object<Value>>(
                     account_id,
                     Collection::Mailbox,
@@ -548,7 +579,8 @@ impl SessionData {
                     Property::Value,
                 )
                 .await?
-                .map(|mailbox| mailbox.effective_acl(&access_token).contains(item))
-                .ok_or_else(|| StatusResponse::no("Mailbox no longer exists."))?)
+                .map(|mailbox| mailbox.effective_acl(&access_token))
+                .ok_or_else(|| StatusResponse::no("Mailbox no longer exists."))
+        }
     }
 }
@@ -28,6 +28,7 @@ use imap_proto::{
     protocol::{expunge, select::Exists, Sequence},
     StatusResponse,
 };
+use jmap::JMAP;
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
     roaring::RoaringBitmap,
@@ -65,6 +66,7 @@ struct UidMapBuilder {
 
 impl SessionData {
     pub async fn fetch_messages(&self, mailbox: &MailboxId) -> crate::op::Result<MailboxState> {
+        let access_token = self.get_access_token().await?;
         let mut try_count = 0;
 
         loop {
@@ -101,12 +103,7 @@ impl SessionData {
             // Obtain message ids
             let message_ids = if let Some(mailbox_id) = mailbox.mailbox_id {
                 self.jmap
-                    .get_tag(
-                        mailbox.account_id,
-                        Collection::Email,
-                        Property::MailboxIds,
-                        mailbox_id,
-                    )
+                    .mailbox_message_ids(&access_token, mailbox.account_id, mailbox_id)
                     .await?
                     .unwrap_or_default()
             } else {
@@ -409,6 +406,31 @@ impl SessionData {
     }
 }
 
+// Resolves a UID to a document id using the last stored UID map, which
+// always contains any UID a client could have seen.
+pub async fn uid_to_document_id(
+    jmap: &JMAP,
+    account_id: u32,
+    mailbox_id: u32,
+    uid: u32,
+) -> crate::op::Result<Option<(u32, u32)>> {
+    Ok(jmap
+        .get_property::<UidMap>(
+            account_id,
+            Collection::Mailbox,
+            mailbox_id,
+            Property::EmailIds,
+        )
+        .await?
+        .and_then(|uid_map| {
+            uid_map
+                .items
+                .iter()
+                .find(|item| item.uid == uid)
+                .map(|item| (uid_map.uid_validity, item.id))
+        }))
+}
+
 impl SelectedMailbox {
     pub async fn sequence_to_ids(
         &self,
@@ -30,7 +30,7 @@ use std::{
 use ahash::AHashMap;
 use dashmap::DashMap;
 use imap_proto::{
-    protocol::{list::Attribute, ProtocolVersion},
+    protocol::{list::Attribute, notify::EventGroup, ProtocolVersion},
     receiver::Receiver,
     Command, ResponseCode, StatusResponse,
 };
@@ -53,6 +53,7 @@ use utils::{
 };
 
 pub mod client;
+pub mod compress;
 pub mod mailbox;
 pub mod message;
 pub mod session;
@@ -82,6 +83,10 @@ pub struct IMAP {
     pub timeout_unauth: Duration,
     pub timeout_idle: Duration,
 
+    pub metadata_max_size: usize,
+    pub metadata_max_entries: usize,
+    pub metadata_max_depth: usize,
+
     pub greeting_plain: Vec<u8>,
     pub greeting_tls: Vec<u8>,
 
@@ -100,6 +105,7 @@ pub struct Session<T: AsyncRead> {
     pub is_tls: bool,
     pub is_condstore: bool,
     pub is_qresync: bool,
+    pub inflater: Option<compress::Inflater>,
     pub writer: mpsc::Sender<writer::Event>,
     pub stream_rx: ReadHalf<T>,
     pub in_flight: InFlight,
@@ -117,6 +123,15 @@ pub struct SessionData {
     pub writer: mpsc::Sender<writer::Event>,
     pub state: AtomicU32,
     pub in_flight: InFlight,
+    pub notify: parking_lot::Mutex<Option<Notify>>,
+}
+
+pub struct Notify {
+    pub groups: Arc<Vec<EventGroup>>,
+    pub selected: Option<Arc<SelectedMailbox>>,
+    pub selected_changed: bool,
+    pub selected_wake: Arc<tokio::sync::Notify>,
+    pub task: tokio::task::JoinHandle<()>,
 }
 
 #[derive(Debug, Default)]
@@ -124,6 +139,7 @@ pub struct Mailbox {
     pub has_children: bool,
     pub is_subscribed: bool,
     pub special_use: Option<Attribute>,
+    pub is_virtual: bool,
     pub total_messages: Option<u32>,
     pub total_unseen: Option<u32>,
     pub total_deleted: Option<u32>,
@@ -21,6 +21,8 @@
  * for more details.
 */
 
+use std::borrow::Cow;
+
 use imap_proto::{protocol::ProtocolVersion, receiver::Receiver};
 use jmap::auth::rate_limit::RemoteAddress;
 use tokio::{
@@ -70,7 +72,13 @@ impl<T: AsyncRead> Session<T> {
                     match result {
                         Ok(Ok(bytes_read)) => {
                             if bytes_read > 0 {
-                                match self.ingest(&buf[..bytes_read]).await {
+                                let result = if let Some(bytes) = self.inflate(&buf[..bytes_read]) {
+                                    self.ingest(&bytes).await
+                                } else {
+                                    tracing::debug!(parent: &self.span, event = "error", "Failed to decompress data.");
+                                    Err(())
+                                };
+                                match result {
                                     Ok(false) => (),
                                     Ok(true) => {
                                         return true;
@@ -106,6 +114,16 @@ impl<T: AsyncRead> Session<T> {
 
         false
     }
+
+    pub fn inflate<'x>(&mut self, bytes: &'x [u8]) -> Option<Cow<'x, [u8]>> {
+        if let Some(inflater) = &mut self.inflater {
+            inflater
+                .inflate(bytes, self.imap.max_request_size)
+                .map(Cow::Owned)
+        } else {
+            Some(Cow::Borrowed(bytes))
+        }
+    }
 }
 
 impl Session<TcpStream> {
@@ -130,6 +148,7 @@ impl Session<TcpStream> {
             is_tls: false,
             is_condstore: false,
             is_qresync: false,
+            inflater: None,
             imap: manager.imap,
             jmap: manager.jmap,
             instance: session.instance,
@@ -180,6 +199,7 @@ impl Session<TcpStream> {
             is_tls: true,
             is_condstore: self.is_condstore,
             is_qresync: self.is_qresync,
+            inflater: self.inflater,
             writer: self.writer,
             span: self.span,
             in_flight: self.in_flight,
@@ -218,6 +238,7 @@ impl Session<TlsStream<TcpStream>> {
             is_tls: true,
             is_condstore: false,
             is_qresync: false,
+            inflater: None,
             imap: manager.imap,
             jmap: manager.jmap,
             instance: session.instance,
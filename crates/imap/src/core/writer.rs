@@ -31,7 +31,7 @@ use tokio::{
 use tokio_rustls::server::TlsStream;
 use tracing::debug;
 
-use super::{Session, SessionData};
+use super::{compress::Deflater, Session, SessionData};
 
 const IPC_CHANNEL_BUFFER: usize = 128;
 
@@ -40,11 +40,14 @@ pub enum Event {
     StreamTls(WriteHalf<TlsStream<TcpStream>>),
     Bytes(Cow<'static, [u8]>),
     Upgrade(oneshot::Sender<WriteHalf<TcpStream>>),
+    Deflate,
 }
 
 pub fn spawn_writer(mut stream: Event, span: tracing::Span) -> mpsc::Sender<Event> {
     let (tx, mut rx) = mpsc::channel::<Event>(IPC_CHANNEL_BUFFER);
     tokio::spawn(async move {
+        let mut deflater: Option<Deflater> = None;
+
         'outer: loop {
             match stream {
                 Event::Stream(mut stream_tx) => {
@@ -65,6 +68,17 @@ pub fn spawn_writer(mut stream: Event, span: tracing::Span) -> mpsc::Sender<Even
                                     )
                                 );*/
 
+                                let bytes = if let Some(deflater) = &mut deflater {
+                                    if let Some(bytes) = deflater.deflate(bytes.as_ref()) {
+                                        bytes.into()
+                                    } else {
+                                        debug!("Failed to compress bytes.");
+                                        break 'outer;
+                                    }
+                                } else {
+                                    bytes
+                                };
+
                                 if let Err(err) = stream_tx.write_all(bytes.as_ref()).await {
                                     debug!("Failed to write to stream: {}", err);
                                     break 'outer;
@@ -82,6 +96,9 @@ pub fn spawn_writer(mut stream: Event, span: tracing::Span) -> mpsc::Sender<Even
                                     break 'outer;
                                 }
                             }
+                            Event::Deflate => {
+                                deflater = Some(Deflater::new());
+                            }
                             _ => {
                                 stream = event;
                                 continue 'outer;
@@ -94,11 +111,25 @@ pub fn spawn_writer(mut stream: Event, span: tracing::Span) -> mpsc::Sender<Even
                     while let Some(event) = rx.recv().await {
                         match event {
                             Event::Bytes(bytes) => {
+                                let bytes = if let Some(deflater) = &mut deflater {
+                                    if let Some(bytes) = deflater.deflate(bytes.as_ref()) {
+                                        bytes.into()
+                                    } else {
+                                        debug!("Failed to compress bytes.");
+                                        break 'outer;
+                                    }
+                                } else {
+                                    bytes
+                                };
+
                                 if let Err(err) = stream_tx.write_all(bytes.as_ref()).await {
                                     debug!("Failed to write to stream: {}", err);
                                     break 'outer;
                                 }
                             }
+                            Event::Deflate => {
+                                deflater = Some(Deflater::new());
+                            }
                             _ => {
                                 stream = event;
                                 continue 'outer;
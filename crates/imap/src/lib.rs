@@ -54,6 +54,9 @@ impl IMAP {
             timeout_auth: config.property_or_static("imap.timeout.authenticated", "30m")?,
             timeout_unauth: config.property_or_static("imap.timeout.anonymous", "1m")?,
             timeout_idle: config.property_or_static("imap.timeout.idle", "30m")?,
+            metadata_max_size: config.property_or_static("imap.metadata.max-size", "4096")?,
+            metadata_max_entries: config.property_or_static("imap.metadata.max-entries", "64")?,
+            metadata_max_depth: config.property_or_static("imap.metadata.max-depth", "8")?,
             greeting_plain: StatusResponse::ok(SERVER_GREETING)
                 .with_code(ResponseCode::Capability {
                     capabilities: Capability::all_capabilities(false, false),
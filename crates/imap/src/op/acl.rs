@@ -34,6 +34,7 @@ use imap_proto::{
 use jmap::{
     auth::{acl::EffectiveAcl, AccessToken},
     mailbox::set::SCHEMA,
+    share_notification::ShareChange,
 };
 use jmap_proto::{
     error::method::MethodError,
@@ -165,46 +166,11 @@ impl<T: AsyncRead> Session<T> {
                                         MyRightsResponse {
                                             mailbox_name: arguments.mailbox_name,
                                             rights: if access_token.is_shared(mailbox.account_id) {
-                                                let acl = values.inner.effective_acl(&access_token);
-                                                let mut rights = Vec::with_capacity(5);
-                                                if acl.contains(Acl::ReadItems) {
-                                                    rights.push(Rights::Read);
-                                                    rights.push(Rights::Lookup);
-                                                }
-                                                if acl.contains(Acl::AddItems) {
-                                                    rights.push(Rights::Insert);
-                                                }
-                                                if acl.contains(Acl::RemoveItems) {
-                                                    rights.push(Rights::DeleteMessages);
-                                                    rights.push(Rights::Expunge);
-                                                }
-                                                if acl.contains(Acl::ModifyItems) {
-                                                    rights.push(Rights::Seen);
-                                                    rights.push(Rights::Write);
-                                                }
-                                                if acl.contains(Acl::CreateChild) {
-                                                    rights.push(Rights::CreateMailbox);
-                                                }
-                                                if acl.contains(Acl::Delete) {
-                                                    rights.push(Rights::DeleteMailbox);
-                                                }
-                                                if acl.contains(Acl::Submit) {
-                                                    rights.push(Rights::Post);
-                                                }
-                                                rights
+                                                acl_to_rights(
+                                                    values.inner.effective_acl(&access_token),
+                                                )
                                             } else {
-                                                vec![
-                                                    Rights::Read,
-                                                    Rights::Lookup,
-                                                    Rights::Insert,
-                                                    Rights::DeleteMessages,
-                                                    Rights::Expunge,
-                                                    Rights::Seen,
-                                                    Rights::Write,
-                                                    Rights::CreateMailbox,
-                                                    Rights::DeleteMailbox,
-                                                    Rights::Post,
-                                                ]
+                                                acl_to_rights(Bitmap::all())
                                             },
                                         }
                                         .into_bytes(is_rev2),
@@ -232,14 +198,15 @@ impl<T: AsyncRead> Session<T> {
 
                 tokio::spawn(async move {
                     // Validate mailbox
-                    let (mailbox, values, _) = match data.get_acl_mailbox(&arguments, true).await {
-                        Ok(result) => result,
-                        Err(response) => {
-                            data.write_bytes(response.with_tag(arguments.tag).into_bytes())
-                                .await;
-                            return;
-                        }
-                    };
+                    let (mailbox, values, access_token) =
+                        match data.get_acl_mailbox(&arguments, true).await {
+                            Ok(result) => result,
+                            Err(response) => {
+                                data.write_bytes(response.with_tag(arguments.tag).into_bytes())
+                                    .await;
+                                return;
+                            }
+                        };
 
                     // Obtain principal id
                     let (acl_account_id, id) = match data
@@ -364,6 +331,14 @@ impl<T: AsyncRead> Session<T> {
 
                     // Write changes
                     let mailbox_id = mailbox.mailbox_id.unwrap();
+                    let share_change = ShareChange::new(
+                        Collection::Mailbox,
+                        mailbox.account_id,
+                        mailbox_id,
+                        values.inner.get(&Property::Name),
+                        values.inner.get(&Property::Acl),
+                        changes.get(&Property::Acl),
+                    );
                     let mut batch = BatchBuilder::new();
                     batch
                         .with_account_id(mailbox.account_id)
@@ -425,6 +400,13 @@ impl<T: AsyncRead> Session<T> {
                     // Invalidate ACLs
                     data.jmap.access_tokens.remove(&acl_account_id);
 
+                    // Notify sharees
+                    if let Some(share_change) = share_change {
+                        data.jmap
+                            .share_notification_send(&access_token, share_change)
+                            .await;
+                    }
+
                     data.write_bytes(
                         StatusResponse::completed(command)
                             .with_tag(arguments.tag)
@@ -518,3 +500,32 @@ impl SessionData {
         }
     }
 }
+
+pub fn acl_to_rights(acl: Bitmap<Acl>) -> Vec<Rights> {
+    let mut rights = Vec::with_capacity(5);
+    if acl.contains(Acl::ReadItems) {
+        rights.push(Rights::Read);
+        rights.push(Rights::Lookup);
+    }
+    if acl.contains(Acl::AddItems) {
+        rights.push(Rights::Insert);
+    }
+    if acl.contains(Acl::RemoveItems) {
+        rights.push(Rights::DeleteMessages);
+        rights.push(Rights::Expunge);
+    }
+    if acl.contains(Acl::ModifyItems) {
+        rights.push(Rights::Seen);
+        rights.push(Rights::Write);
+    }
+    if acl.contains(Acl::CreateChild) {
+        rights.push(Rights::CreateMailbox);
+    }
+    if acl.contains(Acl::Delete) {
+        rights.push(Rights::DeleteMailbox);
+    }
+    if acl.contains(Acl::Submit) {
+        rights.push(Rights::Post);
+    }
+    rights
+}
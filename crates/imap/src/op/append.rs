@@ -24,15 +24,30 @@
 use std::sync::Arc;
 
 use imap_proto::{
-    protocol::append::Arguments, receiver::Request, Command, ResponseCode, StatusResponse,
+    protocol::{
+        append::{Arguments, CatenatePart},
+        replace,
+    },
+    receiver::Request,
+    Command, ResponseCode, ResponseType, StatusResponse,
 };
 
-use jmap::email::ingest::IngestEmail;
-use jmap_proto::types::{acl::Acl, keyword::Keyword, state::StateChange, type_state::TypeState};
+use jmap::email::{
+    index::{IndexSaveDate, SaveDates},
+    ingest::{IngestEmail, ReplaceEmail},
+};
+use jmap_proto::{
+    error::method::MethodError,
+    types::{
+        acl::Acl, collection::Collection, id::Id, keyword::Keyword, property::Property,
+        state::StateChange, type_state::TypeState,
+    },
+};
 use mail_parser::Message;
+use store::write::{log::ChangeLogBuilder, now, BatchBuilder, F_VALUE};
 use tokio::io::AsyncRead;
 
-use crate::core::{MailboxId, SelectedMailbox, Session, SessionData};
+use crate::core::{MailboxId, SavedSearch, SelectedMailbox, Session, SessionData};
 
 impl<T: AsyncRead> Session<T> {
     pub async fn handle_append(&mut self, request: Request<Command>) -> crate::OpResult {
@@ -48,38 +63,66 @@ impl<T: AsyncRead> Session<T> {
                 }
 
                 // Obtain mailbox
-                let mailbox =
-                    if let Some(mailbox) = data.get_mailbox_by_name(&arguments.mailbox_name) {
-                        if mailbox.mailbox_id.is_some() {
-                            mailbox
-                        } else {
-                            return self
-                                .write_bytes(
-                                    StatusResponse::no(
-                                        "Appending messages to this mailbox is not allowed.",
-                                    )
-                                    .with_tag(arguments.tag)
-                                    .with_code(ResponseCode::Cannot)
-                                    .into_bytes(),
-                                )
-                                .await;
+                let mailbox = match data.get_append_mailbox(&arguments.mailbox_name) {
+                    Ok(mailbox) => mailbox,
+                    Err(response) => {
+                        return self
+                            .write_bytes(response.with_tag(arguments.tag).into_bytes())
+                            .await;
+                    }
+                };
+                let is_qresync = self.is_qresync;
+
+                tokio::spawn(async move {
+                    data.write_bytes(
+                        match data
+                            .append_messages(arguments, selected_mailbox, mailbox, is_qresync, None)
+                            .await
+                        {
+                            Ok(response) => response,
+                            Err(response) => response,
                         }
-                    } else {
+                        .into_bytes(),
+                    )
+                    .await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+
+    pub async fn handle_replace(
+        &mut self,
+        request: Request<Command>,
+        is_uid: bool,
+    ) -> crate::OpResult {
+        match request.parse_replace() {
+            Ok(arguments) => {
+                let (data, src_mailbox) = self.state.select_data();
+
+                // Refresh mailboxes
+                if let Err(err) = data.synchronize_mailboxes(false).await {
+                    return self
+                        .write_bytes(err.with_tag(arguments.tag).into_bytes())
+                        .await;
+                }
+
+                // Obtain mailbox
+                let mailbox = match data.get_append_mailbox(&arguments.mailbox_name) {
+                    Ok(mailbox) => mailbox,
+                    Err(response) => {
                         return self
-                            .write_bytes(
-                                StatusResponse::no("Mailbox does not exist.")
-                                    .with_tag(arguments.tag)
-                                    .with_code(ResponseCode::TryCreate)
-                                    .into_bytes(),
-                            )
+                            .write_bytes(response.with_tag(arguments.tag).into_bytes())
                             .await;
-                    };
+                    }
+                };
                 let is_qresync = self.is_qresync;
 
                 tokio::spawn(async move {
                     data.write_bytes(
                         match data
-                            .append_messages(arguments, selected_mailbox, mailbox, is_qresync)
+                            .replace_message(arguments, src_mailbox, mailbox, is_uid, is_qresync)
                             .await
                         {
                             Ok(response) => response,
@@ -97,12 +140,43 @@ impl<T: AsyncRead> Session<T> {
 }
 
 impl SessionData {
+    fn get_append_mailbox(&self, mailbox_name: &str) -> crate::op::Result<MailboxId> {
+        if let Some(mailbox) = self.get_mailbox_by_name(mailbox_name) {
+            if mailbox.mailbox_id.is_some() && !self.is_virtual_mailbox(&mailbox) {
+                Ok(mailbox)
+            } else {
+                Err(
+                    StatusResponse::no("Appending messages to this mailbox is not allowed.")
+                        .with_code(ResponseCode::Cannot),
+                )
+            }
+        } else {
+            Err(StatusResponse::no("Mailbox does not exist.").with_code(ResponseCode::TryCreate))
+        }
+    }
+
+    pub async fn append_limit(&self, account_id: u32) -> crate::op::Result<u64> {
+        let max_size = self.jmap.config.mail_max_size as u64;
+        let access_token = self.get_access_token().await?;
+        let quota = self.jmap.get_quota(&access_token, account_id).await?;
+        if quota > 0 {
+            let used_quota = self.jmap.get_used_quota(account_id).await?;
+            Ok(std::cmp::min(
+                max_size,
+                std::cmp::max(quota - used_quota, 0) as u64,
+            ))
+        } else {
+            Ok(max_size)
+        }
+    }
+
     async fn append_messages(
         &self,
         arguments: Arguments,
         selected_mailbox: Option<Arc<SelectedMailbox>>,
         mailbox: MailboxId,
         is_qresync: bool,
+        mut replace: Option<ReplaceEmail>,
     ) -> crate::op::Result<StatusResponse> {
         // Verify ACLs
         let account_id = mailbox.account_id;
@@ -126,11 +200,40 @@ impl SessionData {
             .map_err(|r| r.with_tag(&arguments.tag))?
             .quota as i64;
 
+        // Assemble CATENATE messages
+        let mut messages = arguments.messages;
+        for message in &mut messages {
+            for part in std::mem::take(&mut message.catenate) {
+                match part {
+                    CatenatePart::Text(text) => {
+                        message.message.extend_from_slice(&text);
+                    }
+                    CatenatePart::Url(url) => {
+                        message.message.extend_from_slice(
+                            &self
+                                .catenate_url(&url)
+                                .await
+                                .map_err(|r| r.with_tag(&arguments.tag))?,
+                        );
+                    }
+                }
+            }
+        }
+
         // Append messages
         let mut response = StatusResponse::completed(Command::Append);
-        let mut created_ids = Vec::with_capacity(arguments.messages.len());
+        let mut created_ids = Vec::with_capacity(messages.len());
         let mut last_change_id = None;
-        for message in arguments.messages {
+        for message in messages {
+            if message.message.len() > self.jmap.config.mail_max_size {
+                response = StatusResponse::no(format!(
+                    "Message exceeds the maximum size of {} bytes.",
+                    self.jmap.config.mail_max_size
+                ))
+                .with_code(ResponseCode::TooBig);
+                break;
+            }
+
             match self
                 .jmap
                 .email_ingest(IngestEmail {
@@ -143,6 +246,7 @@ impl SessionData {
                     received_at: message.received_at.map(|d| d as u64),
                     skip_duplicates: false,
                     encrypt: self.jmap.config.encrypt && self.jmap.config.encrypt_append,
+                    replace: replace.take(),
                 })
                 .await
             {
@@ -217,4 +321,185 @@ impl SessionData {
 
         Ok(response.with_tag(arguments.tag))
     }
+
+    async fn replace_message(
+        &self,
+        arguments: replace::Arguments,
+        src_mailbox: Arc<SelectedMailbox>,
+        mailbox: MailboxId,
+        is_uid: bool,
+        is_qresync: bool,
+    ) -> crate::op::Result<StatusResponse> {
+        // Virtual mailboxes hold no messages of their own
+        let src_mailbox_id = match src_mailbox.id.mailbox_id {
+            Some(mailbox_id) if !self.is_virtual_mailbox(&src_mailbox.id) => mailbox_id,
+            _ => {
+                return Ok(
+                    StatusResponse::no("Messages cannot be replaced in a virtual mailbox.")
+                        .with_tag(arguments.tag)
+                        .with_code(ResponseCode::Cannot),
+                );
+            }
+        };
+
+        // Obtain message to replace
+        let ids = src_mailbox
+            .sequence_to_ids(&arguments.sequence_set, is_uid)
+            .await
+            .map_err(|r| r.with_tag(&arguments.tag))?;
+        let document_id = match ids.len() {
+            1 => *ids.keys().next().unwrap(),
+            0 => {
+                return Ok(StatusResponse::no("The message to replace does not exist.")
+                    .with_tag(arguments.tag));
+            }
+            _ => {
+                return Ok(StatusResponse::bad("Only one message can be replaced.")
+                    .with_tag(arguments.tag));
+            }
+        };
+
+        // Verify ACLs
+        let src_account_id = src_mailbox.id.account_id;
+        if !self
+            .check_mailbox_acl(src_account_id, src_mailbox_id, Acl::RemoveItems)
+            .await
+            .map_err(|r| r.with_tag(&arguments.tag))?
+        {
+            return Ok(StatusResponse::no(
+                "You do not have the required permissions to remove messages from this mailbox.",
+            )
+            .with_tag(arguments.tag)
+            .with_code(ResponseCode::NoPerm));
+        }
+
+        // Append the new message, expunging the replaced one in the same
+        // write batch when both mailboxes belong to the same account
+        let replace = if src_account_id == mailbox.account_id {
+            Some(ReplaceEmail {
+                document_id,
+                mailbox_id: src_mailbox_id,
+            })
+        } else {
+            None
+        };
+        let is_atomic = replace.is_some();
+        let tag = arguments.tag;
+        let mut response = self
+            .append_messages(
+                Arguments {
+                    tag: tag.clone(),
+                    mailbox_name: arguments.mailbox_name,
+                    messages: vec![arguments.message],
+                },
+                src_mailbox.clone().into(),
+                mailbox,
+                is_qresync,
+                replace,
+            )
+            .await?;
+        if response.rtype != ResponseType::Ok {
+            return Ok(response);
+        }
+
+        // Remove the replaced message from other accounts
+        if !is_atomic {
+            self.expunge_replaced(src_account_id, src_mailbox_id, document_id)
+                .await
+                .map_err(|r| r.with_tag(&tag))?;
+        }
+        *src_mailbox.saved_search.lock() = SavedSearch::None;
+
+        // Send APPENDUID and synchronize messages
+        response.tag = None;
+        response.message = "Replacement message ready.".into();
+        self.write_bytes(response.into_bytes()).await;
+        self.write_mailbox_changes(&src_mailbox, is_qresync)
+            .await
+            .map_err(|r| r.with_tag(&tag))?;
+
+        Ok(StatusResponse::completed(Command::Replace(is_uid)).with_tag(tag))
+    }
+
+    async fn expunge_replaced(
+        &self,
+        account_id: u32,
+        mailbox_id: u32,
+        document_id: u32,
+    ) -> crate::op::Result<()> {
+        let (mut mailboxes, thread_id) =
+            if let Some(result) = self.get_mailbox_tags(account_id, document_id).await? {
+                result
+            } else {
+                return Ok(());
+            };
+
+        let mut changelog = ChangeLogBuilder::new();
+        let delete = if mailboxes.current().contains(&mailbox_id) {
+            if mailboxes.current().len() > 1 {
+                // Untag message from this mailbox
+                mailboxes.update(mailbox_id, false);
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Email)
+                    .update_document(document_id)
+                    .update_save_dates(
+                        self.jmap
+                            .get_property::<SaveDates>(
+                                account_id,
+                                Collection::Email,
+                                document_id,
+                                Property::SaveDate,
+                            )
+                            .await?,
+                        mailboxes.current(),
+                        mailboxes.added(),
+                        now(),
+                    );
+                mailboxes.update_batch(&mut batch, Property::MailboxIds);
+                changelog.change_id = self.jmap.assign_change_id(account_id).await?;
+                batch.value(Property::Cid, changelog.change_id, F_VALUE);
+                match self.jmap.write_batch(batch).await {
+                    Ok(_) => {
+                        changelog
+                            .log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+                        changelog.log_child_update(Collection::Mailbox, mailbox_id);
+                    }
+                    Err(MethodError::ServerUnavailable) => {}
+                    Err(_) => {
+                        return Err(StatusResponse::database_failure());
+                    }
+                }
+                false
+            } else {
+                true
+            }
+        } else {
+            // The message was removed from the mailbox in the meantime
+            false
+        };
+
+        // Delete message if it no longer belongs to any mailbox
+        if delete {
+            if let Ok(changes) = self.jmap.email_delete(account_id, document_id).await? {
+                changelog.merge(changes);
+            }
+        }
+
+        // Write changes
+        if !changelog.is_empty() {
+            let change_id = self.jmap.commit_changes(account_id, changelog).await?;
+            self.jmap
+                .broadcast_state_change(
+                    StateChange::new(account_id)
+                        .with_change(TypeState::Email, change_id)
+                        .with_change(TypeState::Mailbox, change_id)
+                        .with_change(TypeState::Thread, change_id),
+                )
+                .await;
+        }
+
+        Ok(())
+    }
 }
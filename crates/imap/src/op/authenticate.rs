@@ -207,6 +207,9 @@ impl<T: AsyncRead> Session<T> {
     }
 
     pub async fn handle_unauthenticate(&mut self, request: Request<Command>) -> crate::OpResult {
+        if let State::Authenticated { data } | State::Selected { data, .. } = &self.state {
+            data.notify.lock().take();
+        }
         self.state = State::NotAuthenticated { auth_failures: 0 };
 
         self.write_bytes(
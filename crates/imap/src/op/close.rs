@@ -31,9 +31,10 @@ impl<T: AsyncRead> Session<T> {
     pub async fn handle_close(&mut self, request: Request<Command>) -> crate::OpResult {
         let (data, mailbox) = self.state.select_data();
         if mailbox.is_select {
-            data.expunge(mailbox, None).await.ok();
+            data.expunge(mailbox, None, None).await.ok();
         }
 
+        data.set_notify_selected(None);
         self.state = State::Authenticated { data };
         self.write_bytes(
             StatusResponse::completed(Command::Close)
@@ -0,0 +1,71 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use imap_proto::{
+    protocol::compress::Algorithm, receiver::Request, Command, ResponseCode, StatusResponse,
+};
+use tokio::io::AsyncRead;
+
+use crate::core::{compress::Inflater, writer::Event, Session};
+
+impl<T: AsyncRead> Session<T> {
+    pub async fn handle_compress(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_compress() {
+            Ok(arguments) => {
+                if self.inflater.is_some() {
+                    return self
+                        .write_bytes(
+                            StatusResponse::no("Compression is already active.")
+                                .with_tag(arguments.tag)
+                                .with_code(ResponseCode::CompressionActive)
+                                .into_bytes(),
+                        )
+                        .await;
+                }
+
+                // The tagged response is sent uncompressed, all data
+                // exchanged afterwards is compressed in both directions.
+                match arguments.algorithm {
+                    Algorithm::Deflate => {
+                        self.write_bytes(
+                            StatusResponse::ok("DEFLATE active")
+                                .with_tag(arguments.tag)
+                                .into_bytes(),
+                        )
+                        .await?;
+                        if let Err(err) = self.writer.send(Event::Deflate).await {
+                            tracing::debug!(parent: &self.span, "Failed to send event: {}", err);
+                            return Err(());
+                        }
+                        self.inflater = Some(Inflater::new());
+                    }
+                }
+
+                tracing::debug!(parent: &self.span, event = "compress", "DEFLATE compression enabled.");
+
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+}
@@ -28,7 +28,10 @@ use imap_proto::{
     StatusResponse,
 };
 
-use jmap::email::set::TagManager;
+use jmap::email::{
+    index::{IndexSaveDate, SaveDates},
+    set::TagManager,
+};
 use jmap_proto::{
     error::{method::MethodError, set::SetErrorType},
     types::{
@@ -36,11 +39,13 @@ use jmap_proto::{
         type_state::TypeState,
     },
 };
-use store::write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, F_VALUE};
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, F_VALUE};
 use tokio::io::AsyncRead;
 
 use crate::core::{MailboxId, SelectedMailbox, Session, SessionData};
 
+use super::Progress;
+
 impl<T: AsyncRead> Session<T> {
     pub async fn handle_copy_move(
         &mut self,
@@ -64,7 +69,7 @@ impl<T: AsyncRead> Session<T> {
                     // Make sure the mailbox exists.
                     let dest_mailbox =
                         if let Some(mailbox) = data.get_mailbox_by_name(&arguments.mailbox_name) {
-                            if mailbox.mailbox_id.is_some() {
+                            if mailbox.mailbox_id.is_some() && !data.is_virtual_mailbox(&mailbox) {
                                 mailbox
                             } else {
                                 return data
@@ -105,8 +110,11 @@ impl<T: AsyncRead> Session<T> {
                             .await;
                     }
 
-                    // Messages cannot me moved out of all folders.
-                    if is_move && src_mailbox.id.mailbox_id.is_none() {
+                    // Messages cannot me moved out of all folders or saved searches.
+                    if is_move
+                        && (src_mailbox.id.mailbox_id.is_none()
+                            || data.is_virtual_mailbox(&src_mailbox.id))
+                    {
                         return data
                             .write_bytes(
                                 StatusResponse::no(
@@ -208,10 +216,14 @@ impl SessionData {
         let mut changelog = ChangeLogBuilder::new();
         let mut did_move = false;
         let mut copied_ids = Vec::with_capacity(ids.len());
+        let mut progress = Progress::new(&arguments.tag, ids.len());
         if src_mailbox.id.account_id == dest_mailbox.account_id {
             // Mailboxes are in the same account
             let account_id = src_mailbox.id.account_id;
             for (id, imap_id) in ids {
+                if let Some(bytes) = progress.advance() {
+                    self.write_bytes(bytes).await;
+                }
                 // Obtain mailbox tags
                 let (mut mailboxes, thread_id) = if let Some(result) = self
                     .get_mailbox_tags(account_id, id)
@@ -248,7 +260,23 @@ impl SessionData {
                 batch
                     .with_account_id(account_id)
                     .with_collection(Collection::Email)
-                    .update_document(id);
+                    .update_document(id)
+                    .update_save_dates(
+                        self.jmap
+                            .get_property::<SaveDates>(
+                                account_id,
+                                Collection::Email,
+                                id,
+                                Property::SaveDate,
+                            )
+                            .await
+                            .map_err(|_| {
+                                StatusResponse::database_failure().with_tag(&arguments.tag)
+                            })?,
+                        mailboxes.current(),
+                        mailboxes.added(),
+                        now(),
+                    );
                 mailboxes.update_batch(&mut batch, Property::MailboxIds);
                 if changelog.change_id == u64::MAX {
                     changelog.change_id =
@@ -294,6 +322,9 @@ impl SessionData {
                 })?
                 .quota as i64;
             for (id, imap_id) in ids {
+                if let Some(bytes) = progress.advance() {
+                    self.write_bytes(bytes).await;
+                }
                 match self
                     .jmap
                     .copy_message(
@@ -357,12 +388,28 @@ impl SessionData {
                         }
                     } else {
                         // Remove mailbox tag from message
+                        mailboxes.update(src_mailbox_id, false);
                         let mut batch = BatchBuilder::new();
                         batch
                             .with_account_id(src_account_id)
                             .with_collection(Collection::Email)
-                            .update_document(id);
-                        mailboxes.update(src_mailbox_id, false);
+                            .update_document(id)
+                            .update_save_dates(
+                                self.jmap
+                                    .get_property::<SaveDates>(
+                                        src_account_id,
+                                        Collection::Email,
+                                        id,
+                                        Property::SaveDate,
+                                    )
+                                    .await
+                                    .map_err(|_| {
+                                        StatusResponse::database_failure().with_tag(&arguments.tag)
+                                    })?,
+                                mailboxes.current(),
+                                mailboxes.added(),
+                                now(),
+                            );
                         mailboxes.update_batch(&mut batch, Property::MailboxIds);
                         if changelog.change_id == u64::MAX {
                             changelog.change_id = self
@@ -202,6 +202,7 @@ impl SessionData {
                 Mailbox {
                     has_children: pos < params.path.len() - 1 || params.is_rename,
                     is_subscribed: false,
+                    is_virtual: false,
                     total_messages: 0.into(),
                     total_unseen: 0.into(),
                     total_deleted: 0.into(),
@@ -30,7 +30,10 @@ use imap_proto::{
     Command, ResponseCode, StatusResponse,
 };
 
-use jmap::email::set::TagManager;
+use jmap::email::{
+    index::{IndexSaveDate, SaveDates},
+    set::TagManager,
+};
 use jmap_proto::{
     error::method::MethodError,
     types::{
@@ -38,11 +41,13 @@ use jmap_proto::{
         state::StateChange, type_state::TypeState,
     },
 };
-use store::write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, F_VALUE};
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, F_VALUE};
 use tokio::io::AsyncRead;
 
 use crate::core::{ImapId, SavedSearch, SelectedMailbox, Session, SessionData};
 
+use super::Progress;
+
 impl<T: AsyncRead> Session<T> {
     pub async fn handle_expunge(
         &mut self,
@@ -97,7 +102,10 @@ impl<T: AsyncRead> Session<T> {
             _ => None,
         };
 
-        if let Err(response) = data.expunge(mailbox.clone(), sequence).await {
+        if let Err(response) = data
+            .expunge(mailbox.clone(), sequence, Some(&request.tag))
+            .await
+        {
             return self
                 .write_bytes(response.with_tag(request.tag).into_bytes())
                 .await;
@@ -129,7 +137,16 @@ impl SessionData {
         &self,
         mailbox: Arc<SelectedMailbox>,
         sequence: Option<AHashMap<u32, ImapId>>,
+        tag: Option<&str>,
     ) -> crate::op::Result<()> {
+        // Saved searches hold no messages of their own
+        if self.is_virtual_mailbox(&mailbox.id) {
+            return Err(StatusResponse::no(
+                "Messages cannot be expunged from a saved search mailbox.",
+            )
+            .with_code(ResponseCode::Cannot));
+        }
+
         // Obtain message ids
         let account_id = mailbox.id.account_id;
         let deleted_ids = if let Some(mailbox_id) = mailbox.id.mailbox_id {
@@ -166,7 +183,11 @@ impl SessionData {
 
         // Delete ids
         let mut changelog = ChangeLogBuilder::new();
+        let mut progress = tag.map(|tag| Progress::new(tag, deleted_ids.len() as usize));
         for id in deleted_ids {
+            if let Some(bytes) = progress.as_mut().and_then(|progress| progress.advance()) {
+                self.write_bytes(bytes).await;
+            }
             if sequence
                 .as_ref()
                 .map_or(false, |ids| !ids.contains_key(&id))
@@ -210,7 +231,20 @@ impl SessionData {
                     batch
                         .with_account_id(account_id)
                         .with_collection(Collection::Email)
-                        .update_document(id);
+                        .update_document(id)
+                        .update_save_dates(
+                            self.jmap
+                                .get_property::<SaveDates>(
+                                    account_id,
+                                    Collection::Email,
+                                    id,
+                                    Property::SaveDate,
+                                )
+                                .await?,
+                            mailboxes.current(),
+                            mailboxes.added(),
+                            now(),
+                        );
                     mailboxes.update_batch(&mut batch, Property::MailboxIds);
                     keywords.update_batch(&mut batch, Property::Keywords);
                     if changelog.change_id == u64::MAX {
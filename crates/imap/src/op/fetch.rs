@@ -37,6 +37,7 @@ use imap_proto::{
     receiver::Request,
     Command, ResponseCode, StatusResponse,
 };
+use jmap::email::index::SaveDates;
 use jmap_proto::{
     error::method::MethodError,
     object::Object,
@@ -201,6 +202,13 @@ impl SessionData {
             arguments.attributes.push_unique(Attribute::ModSeq);
         }
 
+        // Apply PARTIAL range
+        if let Some(range) = arguments.partial {
+            let mut sorted_ids = ids.into_iter().collect::<Vec<_>>();
+            sorted_ids.sort_unstable_by_key(|(_, imap_id)| imap_id.uid);
+            ids = range.slice(&sorted_ids).iter().copied().collect();
+        }
+
         // Build properties list
         let mut set_seen_flags = false;
         let mut needs_thread_id = false;
@@ -499,6 +507,29 @@ impl SessionData {
                             thread_id: Id::from_parts(account_id, thread_id).to_string(),
                         });
                     }
+                    Attribute::SaveDate => {
+                        items.push(DataItem::SaveDate {
+                            date: self
+                                .jmap
+                                .get_property::<SaveDates>(
+                                    account_id,
+                                    Collection::Email,
+                                    id,
+                                    Property::SaveDate,
+                                )
+                                .await
+                                .ok()
+                                .flatten()
+                                .and_then(|save_dates| {
+                                    if let Some(mailbox_id) = mailbox.id.mailbox_id {
+                                        save_dates.get(mailbox_id)
+                                    } else {
+                                        save_dates.last()
+                                    }
+                                })
+                                .map(|date| date as i64),
+                        });
+                    }
                 }
             }
 
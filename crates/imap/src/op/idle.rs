@@ -62,13 +62,21 @@ impl<T: AsyncRead> Session<T> {
         let is_rev2 = self.version.is_rev2();
         let is_qresync = self.is_qresync;
 
-        // Register with state manager
-        let mut change_rx = if let Some(change_rx) = self
+        // Register with state manager, unless changes are already being
+        // delivered by NOTIFY
+        let notify_wake = data
+            .notify
+            .lock()
+            .as_ref()
+            .map(|notify| notify.selected_wake.clone());
+        let mut change_rx = if notify_wake.is_some() {
+            None
+        } else if let Some(change_rx) = self
             .jmap
             .subscribe_state_manager(data.account_id, data.account_id, types)
             .await
         {
-            change_rx
+            Some(change_rx)
         } else {
             return self
                 .write_bytes(
@@ -84,6 +92,9 @@ impl<T: AsyncRead> Session<T> {
         self.write_bytes(b"+ Idling, send 'DONE' to stop.\r\n".to_vec())
             .await?;
         tracing::debug!(parent: &self.span, event = "stat", context = "idle", "Starting IDLE.");
+        if let Some(mailbox) = &mailbox {
+            data.flush_notify_changes(mailbox, is_qresync, is_rev2).await;
+        }
         let mut buf = vec![0; 1024];
         loop {
             tokio::select! {
@@ -91,7 +102,13 @@ impl<T: AsyncRead> Session<T> {
                     match result {
                         Ok(Ok(bytes_read)) => {
                             if bytes_read > 0 {
-                                if (buf[..bytes_read]).windows(4).any(|w| w == b"DONE") {
+                                let bytes = if let Some(bytes) = self.inflate(&buf[..bytes_read]) {
+                                    bytes
+                                } else {
+                                    tracing::debug!(parent: &self.span, event = "error", "Failed to decompress data.");
+                                    return Err(());
+                                };
+                                if bytes.windows(4).any(|w| w == b"DONE") {
                                     tracing::debug!(parent: &self.span, event = "stop", context = "idle", "Stopping IDLE.");
                                     return self.write_bytes(StatusResponse::completed(Command::Idle)
                                                                     .with_tag(request.tag)
@@ -113,7 +130,23 @@ impl<T: AsyncRead> Session<T> {
                         }
                     }
                 }
-                state_change = change_rx.recv() => {
+                _ = async {
+                    match &notify_wake {
+                        Some(notify_wake) => notify_wake.notified().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    // Deliver selected mailbox changes queued by NOTIFY
+                    if let Some(mailbox) = &mailbox {
+                        data.flush_notify_changes(mailbox, is_qresync, is_rev2).await;
+                    }
+                }
+                state_change = async {
+                    match &mut change_rx {
+                        Some(change_rx) => change_rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
                     if let Some(state_change) = state_change {
                         let mut has_mailbox_changes = false;
                         let mut has_email_changes = false;
@@ -268,6 +301,7 @@ impl SessionData {
                             attributes: vec![fetch::Attribute::Flags, fetch::Attribute::Uid],
                             changed_since: None,
                             include_vanished: false,
+                            partial: None,
                         },
                         mailbox.clone(),
                         true,
@@ -23,9 +23,11 @@
 
 use imap_proto::{
     protocol::{
+        acl::MyRightsResponse,
         list::{
             self, Arguments, Attribute, ChildInfo, ListItem, ReturnOption, SelectionOption, Tag,
         },
+        metadata::{Depth, GetArguments},
         ImapResponse, ProtocolVersion,
     },
     receiver::Request,
@@ -36,6 +38,8 @@ use tokio::io::AsyncRead;
 
 use crate::core::{Session, SessionData};
 
+use super::acl::acl_to_rights;
+
 impl<T: AsyncRead> Session<T> {
     pub async fn handle_list(&mut self, request: Request<Command>) -> crate::OpResult {
         let command = request.command;
@@ -67,6 +71,8 @@ impl<T: AsyncRead> Session<T> {
                                         tags: vec![],
                                     }],
                                     status_items: Vec::new(),
+                                    rights_items: Vec::new(),
+                                    metadata_items: Vec::new(),
                                 }
                                 .serialize(),
                             ),
@@ -123,6 +129,8 @@ impl SessionData {
         let mut include_subscribed = false;
         let mut include_children = false;
         let mut include_status = None;
+        let mut include_rights = false;
+        let mut include_metadata = None;
         for selection_option in &selection_options {
             match selection_option {
                 SelectionOption::Subscribed => {
@@ -153,6 +161,12 @@ impl SessionData {
                 ReturnOption::SpecialUse => {
                     include_special_use = true;
                 }
+                ReturnOption::MyRights => {
+                    include_rights = true;
+                }
+                ReturnOption::Metadata(entries) => {
+                    include_metadata = entries.into();
+                }
             }
         }
         if recursive_match && !filter_subscribed {
@@ -241,6 +255,9 @@ impl SessionData {
                         if include_subscribed && mailbox.is_subscribed {
                             attributes.push(Attribute::Subscribed);
                         }
+                        if mailbox.is_virtual {
+                            attributes.push(Attribute::Virtual);
+                        }
                         if include_special_use {
                             if let Some(special_use) = &mailbox.special_use {
                                 attributes.push(*special_use);
@@ -280,6 +297,56 @@ impl SessionData {
             }
         }
 
+        // Add rights and metadata responses
+        let mut rights_items = Vec::new();
+        let mut metadata_items = Vec::new();
+        if include_rights || include_metadata.is_some() {
+            for list_item in &list_items {
+                let mailbox =
+                    if let Some(mailbox) = self.get_mailbox_by_name(&list_item.mailbox_name) {
+                        mailbox
+                    } else {
+                        continue;
+                    };
+                if let (true, Some(mailbox_id)) = (include_rights, mailbox.mailbox_id) {
+                    match self.get_mailbox_acl(mailbox.account_id, mailbox_id).await {
+                        Ok(acl) => {
+                            rights_items.push(MyRightsResponse {
+                                mailbox_name: list_item.mailbox_name.to_string(),
+                                rights: acl_to_rights(acl),
+                            });
+                        }
+                        Err(_) => {
+                            tracing::debug!(parent: &self.span, "Failed to get mailbox rights.");
+                        }
+                    }
+                }
+                if let Some(entries) = include_metadata {
+                    match self
+                        .get_metadata(GetArguments {
+                            tag: String::new(),
+                            mailbox_name: list_item.mailbox_name.to_string(),
+                            entries: entries.clone(),
+                            max_size: None,
+                            depth: Depth::Zero,
+                        })
+                        .await
+                    {
+                        Ok((Some(mut metadata), _)) => {
+                            metadata.entries.retain(|(_, value)| value.is_some());
+                            if !metadata.entries.is_empty() {
+                                metadata_items.push(metadata);
+                            }
+                        }
+                        Ok((None, _)) => (),
+                        Err(_) => {
+                            tracing::debug!(parent: &self.span, "Failed to get mailbox metadata.");
+                        }
+                    }
+                }
+            }
+        }
+
         // Write response
         self.write_bytes(
             StatusResponse::completed(if !is_lsub {
@@ -294,6 +361,8 @@ impl SessionData {
                     is_lsub,
                     list_items,
                     status_items,
+                    rights_items,
+                    metadata_items,
                 }
                 .serialize(),
             ),
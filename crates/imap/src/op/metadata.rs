@@ -0,0 +1,331 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use imap_proto::{
+    protocol::metadata::{GetArguments, Response, SetArguments},
+    receiver::Request,
+    Command, ResponseCode, StatusResponse,
+};
+use jmap::mailbox::set::SCHEMA;
+use jmap_proto::{
+    error::method::MethodError,
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        acl::Acl, collection::Collection, property::Property, state::StateChange,
+        type_state::TypeState, value::Value,
+    },
+};
+use store::write::{assert::HashedValue, BatchBuilder, F_CLEAR, F_VALUE};
+use tokio::io::AsyncRead;
+
+use crate::core::{Session, SessionData};
+
+struct MetadataEntries {
+    account_id: u32,
+    mailbox_id: Option<u32>,
+    current: Option<HashedValue<Object<Value>>>,
+    entries: Object<Value>,
+    is_owner: bool,
+}
+
+impl<T: AsyncRead> Session<T> {
+    pub async fn handle_get_metadata(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_get_metadata() {
+            Ok(mut arguments) => {
+                let data = self.state.session_data();
+                let is_rev2 = self.version.is_rev2();
+
+                tokio::spawn(async move {
+                    let tag = std::mem::take(&mut arguments.tag);
+                    let bytes = match data.get_metadata(arguments).await {
+                        Ok((response, long_entries)) => {
+                            let mut status =
+                                StatusResponse::completed(Command::GetMetadata).with_tag(tag);
+                            if long_entries > 0 {
+                                status = status.with_code(ResponseCode::MetadataLongEntries {
+                                    size: long_entries,
+                                });
+                            }
+                            status.serialize(
+                                response
+                                    .map(|response| response.into_bytes(is_rev2))
+                                    .unwrap_or_default(),
+                            )
+                        }
+                        Err(response) => response.with_tag(tag).into_bytes(),
+                    };
+                    data.write_bytes(bytes).await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+
+    pub async fn handle_set_metadata(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_set_metadata() {
+            Ok(mut arguments) => {
+                let data = self.state.session_data();
+
+                tokio::spawn(async move {
+                    let tag = std::mem::take(&mut arguments.tag);
+                    data.write_bytes(
+                        match data.set_metadata(arguments).await {
+                            Ok(_) => StatusResponse::completed(Command::SetMetadata),
+                            Err(response) => response,
+                        }
+                        .with_tag(tag)
+                        .into_bytes(),
+                    )
+                    .await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+}
+
+impl SessionData {
+    pub async fn get_metadata(
+        &self,
+        arguments: GetArguments,
+    ) -> crate::op::Result<(Option<Response>, usize)> {
+        let metadata = self
+            .get_metadata_entries(&arguments.mailbox_name, Acl::ReadItems)
+            .await?;
+
+        // Obtain matching entries
+        let mut entries: Vec<(String, Option<String>)> = Vec::new();
+        let mut long_entries = 0;
+        for requested in &arguments.entries {
+            let mut found = false;
+            for (entry, value) in metadata.entries.properties.iter() {
+                if let (Property::_T(entry), Value::Text(value)) = (entry, value) {
+                    if !arguments.depth.matches(entry, requested)
+                        || (!metadata.is_owner && entry.starts_with("/private/"))
+                    {
+                        continue;
+                    }
+                    found |= entry == requested;
+                    if arguments
+                        .max_size
+                        .map_or(false, |max_size| value.len() > max_size)
+                    {
+                        long_entries = std::cmp::max(long_entries, value.len());
+                    } else if !entries.iter().any(|(e, _)| e == entry) {
+                        entries.push((entry.clone(), Some(value.clone())));
+                    }
+                }
+            }
+            if !found
+                && requested != "/private"
+                && requested != "/shared"
+                && !entries.iter().any(|(e, _)| e == requested)
+            {
+                entries.push((requested.clone(), None));
+            }
+        }
+
+        Ok((
+            if !entries.is_empty() {
+                Some(Response {
+                    mailbox_name: arguments.mailbox_name,
+                    entries,
+                })
+            } else {
+                None
+            },
+            long_entries,
+        ))
+    }
+
+    async fn set_metadata(&self, arguments: SetArguments) -> crate::op::Result<()> {
+        let metadata = self
+            .get_metadata_entries(&arguments.mailbox_name, Acl::Modify)
+            .await?;
+        let mut entries = metadata.entries;
+
+        // Apply changes
+        for (entry, value) in arguments.entries {
+            if !metadata.is_owner && entry.starts_with("/private/") {
+                return Err(StatusResponse::no(
+                    "Private annotations are not supported on shared mailboxes.",
+                )
+                .with_code(ResponseCode::MetadataNoPrivate));
+            } else if entry.split('/').count() - 2 > self.imap.metadata_max_depth {
+                return Err(StatusResponse::no(format!(
+                    "Entry names cannot be nested more than {} levels deep.",
+                    self.imap.metadata_max_depth
+                ))
+                .with_code(ResponseCode::Limit));
+            }
+
+            if let Some(value) = value {
+                if value.len() > self.imap.metadata_max_size {
+                    return Err(
+                        StatusResponse::no("Annotation value is too large.").with_code(
+                            ResponseCode::MetadataMaxSize {
+                                size: self.imap.metadata_max_size,
+                            },
+                        ),
+                    );
+                }
+                entries.set(Property::_T(entry), Value::Text(value));
+            } else {
+                entries.remove(&Property::_T(entry));
+            }
+        }
+        if entries.properties.len() > self.imap.metadata_max_entries {
+            return Err(StatusResponse::no("Too many annotations.")
+                .with_code(ResponseCode::MetadataTooMany));
+        }
+
+        // Build batch
+        let account_id = metadata.account_id;
+        let mut batch = BatchBuilder::new();
+        batch.with_account_id(account_id);
+        let change_id = if let Some(mailbox_id) = metadata.mailbox_id {
+            let mut changes = self.jmap.begin_changes(account_id).await?;
+            batch
+                .with_collection(Collection::Mailbox)
+                .update_document(mailbox_id)
+                .custom(
+                    ObjectIndexBuilder::new(SCHEMA)
+                        .with_current(metadata.current.unwrap())
+                        .with_changes(Object::with_capacity(1).with_property(
+                            Property::Metadata,
+                            if !entries.properties.is_empty() {
+                                Value::Object(entries)
+                            } else {
+                                Value::Null
+                            },
+                        )),
+                );
+            changes.log_update(Collection::Mailbox, mailbox_id);
+            let change_id = changes.change_id;
+            batch.custom(changes);
+            Some(change_id)
+        } else {
+            batch
+                .with_collection(Collection::Principal)
+                .update_document(0);
+            if let Some(current) = &metadata.current {
+                batch.assert_value(Property::Metadata, current);
+            } else {
+                batch.assert_value(Property::Metadata, ());
+            }
+            if !entries.properties.is_empty() {
+                batch.value(Property::Metadata, &entries, F_VALUE);
+            } else {
+                batch.value(Property::Metadata, (), F_VALUE | F_CLEAR);
+            }
+            None
+        };
+
+        match self.jmap.write_batch(batch).await {
+            Ok(_) => {
+                if let Some(change_id) = change_id {
+                    self.jmap
+                        .broadcast_state_change(
+                            StateChange::new(account_id).with_change(TypeState::Mailbox, change_id),
+                        )
+                        .await;
+                }
+                Ok(())
+            }
+            Err(MethodError::ServerUnavailable) => Err(StatusResponse::no(
+                "Another process modified these annotations, please try again.",
+            )),
+            Err(_) => Err(StatusResponse::database_failure()),
+        }
+    }
+
+    async fn get_metadata_entries(
+        &self,
+        mailbox_name: &str,
+        acl: Acl,
+    ) -> crate::op::Result<MetadataEntries> {
+        if mailbox_name.is_empty() {
+            // Server annotations are stored per account
+            let current = self
+                .jmap
+                .get_property::<HashedValue<Object<Value>>>(
+                    self.account_id,
+                    Collection::Principal,
+                    0,
+                    Property::Metadata,
+                )
+                .await?;
+            Ok(MetadataEntries {
+                account_id: self.account_id,
+                mailbox_id: None,
+                entries: current
+                    .as_ref()
+                    .map(|current| current.inner.clone())
+                    .unwrap_or_else(|| Object::with_capacity(0)),
+                current,
+                is_owner: true,
+            })
+        } else if let Some(mailbox) = self.get_mailbox_by_name(mailbox_name) {
+            let mailbox_id = mailbox.mailbox_id.ok_or_else(|| {
+                StatusResponse::no("Annotations are not supported on this mailbox.")
+                    .with_code(ResponseCode::Cannot)
+            })?;
+            if !self
+                .check_mailbox_acl(mailbox.account_id, mailbox_id, acl)
+                .await?
+            {
+                return Err(StatusResponse::no(
+                    "You do not have enough permissions to perform this operation.",
+                )
+                .with_code(ResponseCode::NoPerm));
+            }
+            let current = self
+                .jmap
+                .get_property::<HashedValue<Object<Value>>>(
+                    mailbox.account_id,
+                    Collection::Mailbox,
+                    mailbox_id,
+                    Property::Value,
+                )
+                .await?
+                .ok_or_else(|| StatusResponse::no("Mailbox no longer exists."))?;
+            Ok(MetadataEntries {
+                account_id: mailbox.account_id,
+                mailbox_id: mailbox_id.into(),
+                entries: current
+                    .inner
+                    .properties
+                    .get(&Property::Metadata)
+                    .and_then(|value| value.as_obj())
+                    .cloned()
+                    .unwrap_or_else(|| Object::with_capacity(0)),
+                current: current.into(),
+                is_owner: self.get_access_token().await?.is_member(mailbox.account_id),
+            })
+        } else {
+            Err(StatusResponse::no("Mailbox does not exist.").with_code(ResponseCode::NonExistent))
+        }
+    }
+}
@@ -21,14 +21,22 @@
  * for more details.
 */
 
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
 use ::store::query::log::Query;
-use imap_proto::StatusResponse;
+use imap_proto::{ResponseCode, StatusResponse};
+
+use crate::core::SessionData;
 
 pub mod acl;
 pub mod append;
 pub mod authenticate;
 pub mod capability;
 pub mod close;
+pub mod compress;
 pub mod copy_move;
 pub mod create;
 pub mod delete;
@@ -39,8 +47,10 @@ pub mod idle;
 pub mod list;
 pub mod login;
 pub mod logout;
+pub mod metadata;
 pub mod namespace;
 pub mod noop;
+pub mod notify;
 pub mod rename;
 pub mod search;
 pub mod select;
@@ -48,6 +58,7 @@ pub mod status;
 pub mod store;
 pub mod subscribe;
 pub mod thread;
+pub mod urlauth;
 
 trait FromModSeq {
     fn from_modseq(modseq: u64) -> Self;
@@ -74,3 +85,61 @@ impl ToModSeq for Option<u64> {
 }
 
 pub type Result<T> = std::result::Result<T, StatusResponse>;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct Progress {
+    tag: String,
+    current: u32,
+    total: Option<u32>,
+    last_update: Instant,
+}
+
+impl Progress {
+    pub fn new(tag: &str, total: usize) -> Self {
+        Progress {
+            tag: tag.to_string(),
+            current: 0,
+            total: Some(total as u32),
+            last_update: Instant::now(),
+        }
+    }
+
+    // Returns an INPROGRESS response if the update interval has elapsed
+    pub fn advance(&mut self) -> Option<Vec<u8>> {
+        let response = if self.last_update.elapsed() >= PROGRESS_INTERVAL {
+            self.last_update = Instant::now();
+            in_progress(&self.tag, self.current.into(), self.total).into()
+        } else {
+            None
+        };
+        self.current += 1;
+        response
+    }
+}
+
+impl SessionData {
+    pub async fn with_progress<F: Future>(&self, tag: &str, future: F) -> F::Output {
+        tokio::pin!(future);
+        loop {
+            tokio::select! {
+                result = &mut future => {
+                    return result;
+                }
+                _ = tokio::time::sleep(PROGRESS_INTERVAL) => {
+                    self.write_bytes(in_progress(tag, None, None)).await;
+                }
+            }
+        }
+    }
+}
+
+fn in_progress(tag: &str, current: Option<u32>, total: Option<u32>) -> Vec<u8> {
+    StatusResponse::ok("Still working...")
+        .with_code(ResponseCode::InProgress {
+            tag: tag.to_string(),
+            current,
+            total,
+        })
+        .into_bytes()
+}
@@ -0,0 +1,389 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::{Arc, Weak};
+
+use ahash::AHashMap;
+use imap_proto::{
+    protocol::{
+        list::{Attribute, ListItem},
+        notify::{Arguments, Event, EventGroup},
+        status::Status,
+    },
+    receiver::Request,
+    Command, ResponseCode, StatusResponse,
+};
+use jmap_proto::types::{state::StateChange, type_state::TypeState};
+use tokio::{io::AsyncRead, sync::mpsc};
+use utils::map::bitmap::Bitmap;
+
+use crate::core::{Notify, SelectedMailbox, Session, SessionData, State};
+
+const STATUS_ITEMS: &[Status] = &[
+    Status::Messages,
+    Status::Unseen,
+    Status::UidNext,
+    Status::UidValidity,
+];
+
+struct NotifyMailbox {
+    account_id: u32,
+    mailbox_id: u32,
+    is_subscribed: bool,
+}
+
+impl<T: AsyncRead> Session<T> {
+    pub async fn handle_notify(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_notify(self.version) {
+            Ok(arguments) => {
+                let (data, selected) = match &self.state {
+                    State::Authenticated { data } => (data.clone(), None),
+                    State::Selected { data, mailbox } => (data.clone(), mailbox.clone().into()),
+                    _ => unreachable!(),
+                };
+
+                // Stop any active notifications
+                data.notify.lock().take();
+
+                // Refresh mailboxes so that shared accounts are included
+                if data.synchronize_mailboxes(false).await.is_err() {
+                    tracing::debug!(parent: &data.span, "Failed to refresh mailboxes.");
+                }
+
+                if arguments.groups.is_empty() {
+                    return self
+                        .write_bytes(
+                            StatusResponse::completed(Command::Notify)
+                                .with_tag(arguments.tag)
+                                .into_bytes(),
+                        )
+                        .await;
+                }
+
+                // Register with state manager
+                let change_rx = if let Some(change_rx) = data.subscribe_notify().await {
+                    change_rx
+                } else {
+                    return self
+                        .write_bytes(
+                            StatusResponse::no("It was not possible to enable notifications.")
+                                .with_tag(arguments.tag)
+                                .with_code(ResponseCode::ContactAdmin)
+                                .into_bytes(),
+                        )
+                        .await;
+                };
+
+                let Arguments {
+                    tag,
+                    status,
+                    groups,
+                } = arguments;
+                let groups = Arc::new(groups);
+                let is_rev2 = self.version.is_rev2();
+                let is_qresync = self.is_qresync;
+                let task = tokio::spawn(notify_task(
+                    Arc::downgrade(&data),
+                    change_rx,
+                    is_qresync,
+                    is_rev2,
+                ));
+                *data.notify.lock() = Some(Notify {
+                    groups: groups.clone(),
+                    selected: selected.clone(),
+                    selected_changed: false,
+                    selected_wake: Arc::new(tokio::sync::Notify::new()),
+                    task,
+                });
+
+                // Send the status of all matching mailboxes
+                let mut buf = Vec::new();
+                if status {
+                    for (mailbox_name, mailbox) in data.notify_mailboxes() {
+                        if selected
+                            .as_ref()
+                            .map_or(true, |selected| !selected.is_mailbox(&mailbox))
+                            && data
+                                .notify_group(&groups, &mailbox_name, &mailbox)
+                                .map_or(false, |group| group.has_message_events())
+                        {
+                            if let Ok(status) = data.status(mailbox_name, STATUS_ITEMS).await {
+                                status.serialize(&mut buf, is_rev2);
+                            }
+                        }
+                    }
+                }
+
+                self.write_bytes(
+                    StatusResponse::completed(Command::Notify)
+                        .with_tag(tag)
+                        .serialize(buf),
+                )
+                .await
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+}
+
+async fn notify_task(
+    data: Weak<SessionData>,
+    mut change_rx: mpsc::Receiver<StateChange>,
+    is_qresync: bool,
+    is_rev2: bool,
+) {
+    while let Some(state_change) = change_rx.recv().await {
+        let data = if let Some(data) = data.upgrade() {
+            data
+        } else {
+            break;
+        };
+        let account_ids = data.account_ids();
+
+        let mut has_email_changes = false;
+        for (type_state, _) in state_change.types {
+            if matches!(type_state, TypeState::Email | TypeState::EmailDelivery) {
+                has_email_changes = true;
+            }
+        }
+
+        if !data
+            .write_notify_changes(has_email_changes, is_qresync, is_rev2)
+            .await
+        {
+            break;
+        }
+
+        // Sharing changes require updating the subscription so that changes
+        // to newly shared accounts are also received
+        if data.account_ids() != account_ids {
+            if let Some(new_change_rx) = data.subscribe_notify().await {
+                change_rx = new_change_rx;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl SessionData {
+    async fn subscribe_notify(&self) -> Option<mpsc::Receiver<StateChange>> {
+        self.jmap
+            .subscribe_state_manager(
+                self.account_id,
+                self.account_id,
+                Bitmap::from_iter([
+                    TypeState::Email,
+                    TypeState::Mailbox,
+                    TypeState::EmailDelivery,
+                ]),
+            )
+            .await
+    }
+
+    fn account_ids(&self) -> Vec<u32> {
+        self.mailboxes
+            .lock()
+            .iter()
+            .map(|account| account.account_id)
+            .collect()
+    }
+
+    async fn write_notify_changes(
+        &self,
+        check_emails: bool,
+        is_qresync: bool,
+        is_rev2: bool,
+    ) -> bool {
+        let (groups, selected) = if let Some(notify) = self.notify.lock().as_ref() {
+            (notify.groups.clone(), notify.selected.clone())
+        } else {
+            return true;
+        };
+
+        // Fetch changed mailboxes
+        let mailboxes_before = self.notify_mailboxes();
+        let changes = match self.synchronize_mailboxes(true).await {
+            Ok(Some(changes)) => changes,
+            Ok(None) => return true,
+            Err(_) => {
+                tracing::debug!(parent: &self.span, "Failed to refresh mailboxes.");
+                return true;
+            }
+        };
+        let mailboxes = self.notify_mailboxes();
+        let mut buf = Vec::with_capacity(64);
+
+        // List deleted mailboxes
+        for mailbox_name in changes.deleted {
+            if let Some(mailbox) = mailboxes_before.get(&mailbox_name) {
+                if self
+                    .notify_group(&groups, &mailbox_name, mailbox)
+                    .map_or(false, |group| group.has_event(Event::MailboxName))
+                {
+                    ListItem {
+                        mailbox_name,
+                        attributes: vec![Attribute::NonExistent],
+                        tags: vec![],
+                    }
+                    .serialize(&mut buf, is_rev2, false);
+                }
+            }
+        }
+
+        // List added mailboxes and subscription changes
+        for (mailbox_name, mailbox) in &mailboxes {
+            let group = self.notify_group(&groups, mailbox_name, mailbox);
+            let event = match mailboxes_before.get(mailbox_name) {
+                None => Event::MailboxName,
+                Some(mailbox_before) if mailbox_before.is_subscribed != mailbox.is_subscribed => {
+                    Event::SubscriptionChange
+                }
+                _ => continue,
+            };
+            if group.map_or(false, |group| group.has_event(event)) {
+                ListItem {
+                    mailbox_name: mailbox_name.to_string(),
+                    attributes: if mailbox.is_subscribed {
+                        vec![Attribute::Subscribed]
+                    } else {
+                        vec![]
+                    },
+                    tags: vec![],
+                }
+                .serialize(&mut buf, is_rev2, false);
+            }
+        }
+
+        // Obtain status of changed mailboxes
+        for mailbox_name in changes.changed {
+            if let Some(mailbox) = mailboxes.get(&mailbox_name) {
+                if selected
+                    .as_ref()
+                    .map_or(true, |selected| !selected.is_mailbox(mailbox))
+                    && self
+                        .notify_group(&groups, &mailbox_name, mailbox)
+                        .map_or(false, |group| group.has_message_events())
+                {
+                    if let Ok(status) = self.status(mailbox_name, STATUS_ITEMS).await {
+                        status.serialize(&mut buf, is_rev2);
+                    }
+                }
+            }
+        }
+
+        if !buf.is_empty() && !self.write_bytes(buf).await {
+            return false;
+        }
+
+        // Changes to the selected mailbox are queued until the next command
+        // boundary or IDLE, as untagged EXPUNGE responses are not allowed while
+        // a command using message sequence numbers is in progress
+        if check_emails
+            && selected.is_some()
+            && groups
+                .iter()
+                .any(|group| group.filter.is_selected() && group.has_message_events())
+        {
+            if let Some(notify) = self.notify.lock().as_mut() {
+                notify.selected_changed = true;
+                notify.selected_wake.notify_one();
+            }
+        }
+
+        true
+    }
+
+    pub async fn flush_notify_changes(
+        &self,
+        mailbox: &Arc<SelectedMailbox>,
+        is_qresync: bool,
+        is_rev2: bool,
+    ) {
+        let has_changes = self
+            .notify
+            .lock()
+            .as_mut()
+            .map_or(false, |notify| std::mem::take(&mut notify.selected_changed));
+        if has_changes {
+            self.write_changes(&Some(mailbox.clone()), false, true, is_qresync, is_rev2)
+                .await;
+        }
+    }
+
+    fn notify_group<'x>(
+        &self,
+        groups: &'x [EventGroup],
+        mailbox_name: &str,
+        mailbox: &NotifyMailbox,
+    ) -> Option<&'x EventGroup> {
+        // Events on the selected mailbox are reported through FETCH and EXPUNGE
+        groups.iter().find(|group| {
+            !group.filter.is_selected()
+                && group.filter.matches(
+                    mailbox_name,
+                    mailbox.account_id == self.account_id,
+                    mailbox.is_subscribed,
+                )
+        })
+    }
+
+    fn notify_mailboxes(&self) -> AHashMap<String, NotifyMailbox> {
+        let mut mailboxes = AHashMap::new();
+        for account in self.mailboxes.lock().iter() {
+            for (mailbox_name, mailbox_id) in &account.mailbox_names {
+                mailboxes.insert(
+                    mailbox_name.to_string(),
+                    NotifyMailbox {
+                        account_id: account.account_id,
+                        mailbox_id: *mailbox_id,
+                        is_subscribed: account
+                            .mailbox_state
+                            .get(mailbox_id)
+                            .map_or(false, |mailbox| mailbox.is_subscribed),
+                    },
+                );
+            }
+        }
+        mailboxes
+    }
+
+    pub fn set_notify_selected(&self, mailbox: Option<Arc<SelectedMailbox>>) {
+        if let Some(notify) = self.notify.lock().as_mut() {
+            notify.selected = mailbox;
+            notify.selected_changed = false;
+        }
+    }
+}
+
+impl SelectedMailbox {
+    fn is_mailbox(&self, mailbox: &NotifyMailbox) -> bool {
+        self.id.account_id == mailbox.account_id && self.id.mailbox_id == Some(mailbox.mailbox_id)
+    }
+}
+
+impl Drop for Notify {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -23,29 +23,39 @@
 
 use std::sync::Arc;
 
+use ahash::{AHashMap, AHashSet};
+
 use imap_proto::{
     protocol::{
-        search::{self, Arguments, Filter, Response, ResultOption},
+        search::{self, Arguments, Filter, MultiArguments, MultiResponse, Response, ResultOption},
         Sequence,
     },
     receiver::Request,
     Command, StatusResponse,
 };
 
-use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, property::Property};
+use jmap::email::index::SaveDateKey;
+use jmap_proto::types::{
+    acl::Acl, collection::Collection, id::Id, keyword::Keyword, property::Property,
+};
 use mail_parser::{HeaderName, RfcHeader};
 use store::{
-    fts::{builder::MAX_TOKEN_LENGTH, Language},
+    fts::{builder::MAX_TOKEN_LENGTH, stemmer::Stemmer, term_index::TermIndex, Language},
     query::{self, log::Query, sort::Pagination, ResultSet},
     roaring::RoaringBitmap,
     write::now,
+    ValueKey,
 };
 use tokio::{io::AsyncRead, sync::watch};
 
-use crate::core::{ImapId, MailboxState, SavedSearch, SelectedMailbox, Session, SessionData};
+use crate::core::{
+    ImapId, MailboxId, MailboxState, SavedSearch, SelectedMailbox, Session, SessionData,
+};
 
 use super::{FromModSeq, ToModSeq};
 
+const RELEVANCY_BATCH_SIZE: usize = 100;
+
 impl<T: AsyncRead> Session<T> {
     pub async fn handle_search(
         &mut self,
@@ -75,12 +85,15 @@ impl<T: AsyncRead> Session<T> {
                 tokio::spawn(async move {
                     let tag = std::mem::take(&mut arguments.tag);
                     let bytes = match data
-                        .search(
-                            arguments,
-                            mailbox.clone(),
-                            results_tx,
-                            prev_saved_search.clone(),
-                            is_uid,
+                        .with_progress(
+                            &tag,
+                            data.search(
+                                arguments,
+                                mailbox.clone(),
+                                results_tx,
+                                prev_saved_search.clone(),
+                                is_uid,
+                            ),
                         )
                         .await
                     {
@@ -111,22 +124,292 @@ impl<T: AsyncRead> Session<T> {
             Err(response) => self.write_bytes(response.into_bytes()).await,
         }
     }
+
+    pub async fn handle_esearch(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_esearch(self.version) {
+            Ok(mut arguments) => {
+                let (data, selected) = self.state.session_mailbox_state();
+                let is_rev2 = self.version.is_rev2();
+
+                tokio::spawn(async move {
+                    let tag = std::mem::take(&mut arguments.search.tag);
+                    let bytes = match data
+                        .with_progress(&tag, data.multi_search(arguments, selected))
+                        .await
+                    {
+                        Ok(responses) => {
+                            let mut buf = Vec::with_capacity(64 * responses.len());
+                            for response in responses {
+                                response.serialize(&mut buf, &tag, is_rev2);
+                            }
+                            StatusResponse::completed(Command::Esearch)
+                                .with_tag(tag)
+                                .serialize(buf)
+                        }
+                        Err(response) => response.with_tag(tag).into_bytes(),
+                    };
+                    data.write_bytes(bytes).await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
 }
 
 impl SessionData {
+    pub async fn multi_search(
+        &self,
+        arguments: MultiArguments,
+        selected: Option<Arc<SelectedMailbox>>,
+    ) -> crate::op::Result<Vec<MultiResponse>> {
+        // Refresh mailboxes
+        self.synchronize_mailboxes(false).await?;
+
+        // Obtain source mailboxes
+        let include_selected = arguments.sources.iter().any(|source| source.is_selected());
+        let mut mailboxes = Vec::new();
+        if let Some(selected) = selected
+            .as_ref()
+            .filter(|selected| include_selected && selected.id.mailbox_id.is_none())
+        {
+            mailboxes.push((
+                self.imap.name_all.clone(),
+                MailboxId {
+                    account_id: selected.id.account_id,
+                    mailbox_id: None,
+                },
+                true,
+            ));
+        }
+        for account in self.mailboxes.lock().iter() {
+            let is_personal = account.account_id == self.account_id;
+            for (mailbox_name, mailbox_id) in &account.mailbox_names {
+                let is_selected = selected.as_ref().map_or(false, |selected| {
+                    selected.id.account_id == account.account_id
+                        && selected.id.mailbox_id == Some(*mailbox_id)
+                });
+                let is_subscribed = account
+                    .mailbox_state
+                    .get(mailbox_id)
+                    .map_or(false, |mailbox| mailbox.is_subscribed);
+                if (is_selected && include_selected)
+                    || arguments
+                        .sources
+                        .iter()
+                        .any(|source| source.matches(mailbox_name, is_personal, is_subscribed))
+                {
+                    mailboxes.push((
+                        mailbox_name.to_string(),
+                        MailboxId {
+                            account_id: account.account_id,
+                            mailbox_id: Some(*mailbox_id),
+                        },
+                        is_selected,
+                    ));
+                }
+            }
+        }
+
+        // Obtain the mailboxes to search, grouped by account
+        let mut accounts: Vec<(u32, Vec<(String, Arc<SelectedMailbox>)>)> = Vec::new();
+        for (mailbox_name, mailbox_id, is_selected) in mailboxes {
+            if mailbox_id.account_id != self.account_id
+                && !self
+                    .check_mailbox_acl(
+                        mailbox_id.account_id,
+                        mailbox_id.mailbox_id.unwrap_or_default(),
+                        Acl::ReadItems,
+                    )
+                    .await?
+            {
+                continue;
+            }
+
+            let mailbox = match &selected {
+                Some(selected) if is_selected => selected.clone(),
+                _ => Arc::new(SelectedMailbox {
+                    state: parking_lot::Mutex::new(self.fetch_messages(&mailbox_id).await?),
+                    id: mailbox_id,
+                    saved_search: parking_lot::Mutex::new(SavedSearch::None),
+                    is_select: false,
+                    is_condstore: false,
+                }),
+            };
+            if let Some((_, account_mailboxes)) = accounts
+                .iter_mut()
+                .find(|(account_id, _)| *account_id == mailbox_id.account_id)
+            {
+                account_mailboxes.push((mailbox_name, mailbox));
+            } else {
+                accounts.push((mailbox_id.account_id, vec![(mailbox_name, mailbox)]));
+            }
+        }
+
+        // Sequence sets and save dates are relative to each mailbox,
+        // these have to be searched separately
+        let is_mailbox_filter = arguments.search.filter.iter().any(|filter| {
+            matches!(
+                filter,
+                Filter::Sequence(..)
+                    | Filter::SavedBefore(_)
+                    | Filter::SavedOn(_)
+                    | Filter::SavedSince(_)
+            )
+        });
+
+        // Search each account
+        let mut responses = Vec::new();
+        for (account_id, mailboxes) in accounts {
+            if is_mailbox_filter || mailboxes.len() == 1 {
+                for (mailbox_name, mailbox) in mailboxes {
+                    let response = self
+                        .search(arguments.search.clone(), mailbox.clone(), None, None, true)
+                        .await?;
+                    if response.has_results() {
+                        responses.push(MultiResponse {
+                            mailbox_name,
+                            uid_validity: mailbox.state.lock().uid_validity,
+                            response,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            // Build a single filter over the messages of all mailboxes
+            let mut mailbox_ids = Vec::with_capacity(mailboxes.len());
+            let mut message_ids = RoaringBitmap::new();
+            for (_, mailbox) in &mailboxes {
+                let ids = self.search_message_ids(&mailbox.id).await?;
+                message_ids |= &ids;
+                mailbox_ids.push(ids);
+            }
+            let mut filters = vec![query::Filter::is_in_set(message_ids.clone())];
+            let include_highest_modseq = self
+                .query_filters(
+                    arguments.search.filter.clone(),
+                    &mailboxes[0].1,
+                    &message_ids,
+                    &None,
+                    true,
+                    &mut filters,
+                )
+                .await?;
+            let result_set = self
+                .jmap
+                .filter(account_id, Collection::Email, filters)
+                .await?;
+            let scores = if let Some(terms) = self.search_relevancy_terms(&arguments.search) {
+                self.relevancy_scores(account_id, &result_set.results, terms)
+                    .await?
+            } else {
+                AHashMap::new()
+            };
+
+            // Split the results by mailbox
+            for ((mailbox_name, mailbox), ids) in mailboxes.into_iter().zip(mailbox_ids) {
+                let response = self
+                    .search_results(
+                        arguments.search.clone(),
+                        mailbox.clone(),
+                        ResultSet {
+                            account_id,
+                            collection: Collection::Email.into(),
+                            results: ids & &result_set.results,
+                        },
+                        scores.clone(),
+                        include_highest_modseq,
+                        None,
+                        true,
+                    )
+                    .await?;
+                if response.has_results() {
+                    responses.push(MultiResponse {
+                        mailbox_name,
+                        uid_validity: mailbox.state.lock().uid_validity,
+                        response,
+                    });
+                }
+            }
+        }
+
+        Ok(responses)
+    }
+
     pub async fn search(
         &self,
-        arguments: Arguments,
+        mut arguments: Arguments,
         mailbox: Arc<SelectedMailbox>,
         results_tx: Option<watch::Sender<Arc<Vec<ImapId>>>>,
         prev_saved_search: Option<Option<Arc<Vec<ImapId>>>>,
         is_uid: bool,
     ) -> Result<search::Response, StatusResponse> {
+        // Obtain relevancy terms
+        let relevancy_terms = self.search_relevancy_terms(&arguments);
+
         // Run query
         let (result_set, include_highest_modseq) = self
-            .query(arguments.filter, &mailbox, &prev_saved_search, is_uid)
+            .query(
+                std::mem::take(&mut arguments.filter),
+                &mailbox,
+                &prev_saved_search,
+                is_uid,
+            )
             .await?;
 
+        // Calculate relevancy scores
+        let scores = if let Some(terms) = relevancy_terms {
+            self.relevancy_scores(mailbox.id.account_id, &result_set.results, terms)
+                .await?
+        } else {
+            AHashMap::new()
+        };
+
+        self.search_results(
+            arguments,
+            mailbox,
+            result_set,
+            scores,
+            include_highest_modseq,
+            results_tx,
+            is_uid,
+        )
+        .await
+    }
+
+    fn search_relevancy_terms(
+        &self,
+        arguments: &Arguments,
+    ) -> Option<Vec<(String, Option<String>)>> {
+        if arguments.result_options.contains(&ResultOption::Relevancy)
+            || arguments.sort.as_ref().map_or(false, |sort| {
+                sort.iter()
+                    .any(|item| matches!(item.sort, search::Sort::Relevancy))
+            })
+        {
+            Some(relevancy_terms(
+                &arguments.filter,
+                self.jmap.config.default_language,
+            ))
+        } else {
+            None
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn search_results(
+        &self,
+        arguments: Arguments,
+        mailbox: Arc<SelectedMailbox>,
+        result_set: ResultSet,
+        scores: AHashMap<u32, u32>,
+        include_highest_modseq: bool,
+        results_tx: Option<watch::Sender<Arc<Vec<ImapId>>>>,
+        is_uid: bool,
+    ) -> Result<search::Response, StatusResponse> {
+        let include_relevancy = arguments.result_options.contains(&ResultOption::Relevancy);
+
         // Obtain modseq
         let highest_modseq = if include_highest_modseq {
             self.synchronize_messages(&mailbox)
@@ -148,6 +431,15 @@ impl SessionData {
             None
         };
         let mut imap_ids = Vec::with_capacity(results_len);
+        let partial = arguments
+            .result_options
+            .iter()
+            .find_map(|option| match option {
+                ResultOption::Partial(range) => Some(*range),
+                _ => None,
+            });
+        let find_min = partial.is_none() && arguments.result_options.contains(&ResultOption::Min);
+        let find_max = partial.is_none() && arguments.result_options.contains(&ResultOption::Max);
         let is_sort = if let Some(sort) = arguments.sort {
             mailbox.map_search_results(
                 self.jmap
@@ -177,6 +469,10 @@ impl SessionData {
                                 search::Sort::To | search::Sort::DisplayTo => {
                                     query::Comparator::field(Property::To, item.ascending)
                                 }
+                                search::Sort::Relevancy => {
+                                    // Most relevant messages are returned first
+                                    query::Comparator::score(scores.clone(), !item.ascending)
+                                }
                             })
                             .collect::<Vec<_>>(),
                         Pagination::new(results_len, 0, None, 0),
@@ -187,8 +483,8 @@ impl SessionData {
                     .into_iter()
                     .map(|id| id as u32),
                 is_uid,
-                arguments.result_options.contains(&ResultOption::Min),
-                arguments.result_options.contains(&ResultOption::Max),
+                find_min,
+                find_max,
                 &mut min,
                 &mut max,
                 &mut total,
@@ -200,8 +496,8 @@ impl SessionData {
             mailbox.map_search_results(
                 result_set.results.into_iter(),
                 is_uid,
-                arguments.result_options.contains(&ResultOption::Min),
-                arguments.result_options.contains(&ResultOption::Max),
+                find_min,
+                find_max,
                 &mut min,
                 &mut max,
                 &mut total,
@@ -212,6 +508,59 @@ impl SessionData {
             false
         };
 
+        // Obtain partial results
+        let (min, max) = if partial.is_some() {
+            (
+                if arguments.result_options.contains(&ResultOption::Min) {
+                    imap_ids.iter().min().copied()
+                } else {
+                    None
+                },
+                if arguments.result_options.contains(&ResultOption::Max) {
+                    imap_ids.iter().max().copied()
+                } else {
+                    None
+                },
+            )
+        } else {
+            (min.map(|(id, _)| id), max.map(|(id, _)| id))
+        };
+        let partial = partial.map(|range| {
+            let ids = range.slice(&imap_ids).to_vec();
+            if let Some(saved_results) = &mut saved_results {
+                let partial_ids = ids.iter().copied().collect::<AHashSet<_>>();
+                saved_results.retain(|imap_id| {
+                    partial_ids.contains(if is_uid {
+                        &imap_id.uid
+                    } else {
+                        &imap_id.seqnum
+                    })
+                });
+            }
+            (range, ids)
+        });
+
+        // Map relevancy scores to the returned ids
+        let relevancy = if include_relevancy {
+            let state = mailbox.state.lock();
+            let scores = scores
+                .iter()
+                .filter_map(|(document_id, score)| {
+                    state
+                        .map_result_id(*document_id, is_uid)
+                        .map(|(id, _)| (id, *score))
+                })
+                .collect::<AHashMap<_, _>>();
+            partial
+                .as_ref()
+                .map_or(&imap_ids, |(_, ids)| ids)
+                .iter()
+                .map(|id| scores.get(id).copied().unwrap_or(1))
+                .collect()
+        } else {
+            vec![]
+        };
+
         // Save results
         if let (Some(results_tx), Some(saved_results)) = (results_tx, saved_results) {
             let saved_results = Arc::new(saved_results);
@@ -224,8 +573,8 @@ impl SessionData {
         // Build response
         Ok(Response {
             is_uid,
-            min: min.map(|(id, _)| id),
-            max: max.map(|(id, _)| id),
+            min,
+            max,
             count: if arguments.result_options.contains(&ResultOption::Count) {
                 Some(total)
             } else {
@@ -233,17 +582,86 @@ impl SessionData {
             },
             ids: if arguments.result_options.is_empty()
                 || arguments.result_options.contains(&ResultOption::All)
+                || (include_relevancy && partial.is_none())
             {
                 imap_ids
             } else {
                 vec![]
             },
+            partial,
+            relevancy,
             is_sort,
             is_esearch: arguments.is_esearch,
             highest_modseq,
         })
     }
 
+    pub async fn relevancy_scores(
+        &self,
+        account_id: u32,
+        document_ids: &RoaringBitmap,
+        terms: Vec<(String, Option<String>)>,
+    ) -> crate::op::Result<AHashMap<u32, u32>> {
+        // Obtain term frequencies
+        let mut frequencies = Vec::with_capacity(document_ids.len() as usize);
+        let mut max_frequency = 0;
+        if !terms.is_empty() {
+            let document_ids = document_ids.iter().collect::<Vec<_>>();
+            for document_ids in document_ids.chunks(RELEVANCY_BATCH_SIZE) {
+                let term_indexes = self
+                    .jmap
+                    .store
+                    .get_values::<TermIndex>(
+                        document_ids
+                            .iter()
+                            .map(|document_id| {
+                                ValueKey::term_index(account_id, Collection::Email, *document_id)
+                            })
+                            .collect(),
+                    )
+                    .await
+                    .map_err(|_| StatusResponse::database_failure())?;
+
+                for (document_id, term_index) in document_ids.iter().zip(term_indexes) {
+                    let frequency = term_index.map_or(0, |term_index| {
+                        let match_terms = terms
+                            .iter()
+                            .map(|(word, stemmed_word)| {
+                                term_index.get_match_term(word, stemmed_word.as_deref())
+                            })
+                            .collect::<Vec<_>>();
+                        term_index
+                            .match_terms(&match_terms, None, false, true, false)
+                            .ok()
+                            .flatten()
+                            .map_or(0, |groups| {
+                                groups.iter().map(|group| group.terms.len() as u32).sum()
+                            })
+                    });
+                    max_frequency = std::cmp::max(max_frequency, frequency);
+                    frequencies.push((*document_id, frequency));
+                }
+            }
+        } else {
+            frequencies.extend(document_ids.iter().map(|document_id| (document_id, 0)));
+        }
+
+        // Normalize scores to the 1-100 range
+        Ok(frequencies
+            .into_iter()
+            .map(|(document_id, frequency)| {
+                (
+                    document_id,
+                    if max_frequency > 0 {
+                        std::cmp::max(1, frequency * 100 / max_frequency)
+                    } else {
+                        100
+                    },
+                )
+            })
+            .collect())
+    }
+
     pub async fn query(
         &self,
         imap_filter: Vec<Filter>,
@@ -253,28 +671,63 @@ impl SessionData {
     ) -> Result<(ResultSet, bool), StatusResponse> {
         // Obtain message ids
         let mut filters = Vec::with_capacity(imap_filter.len() + 1);
-        let message_ids = if let Some(mailbox_id) = mailbox.id.mailbox_id {
-            let ids = self
-                .jmap
-                .get_tag(
-                    mailbox.id.account_id,
-                    Collection::Email,
-                    Property::MailboxIds,
-                    mailbox_id,
+        let message_ids = self.search_message_ids(&mailbox.id).await?;
+        if mailbox.id.mailbox_id.is_some() {
+            filters.push(query::Filter::is_in_set(message_ids.clone()));
+        }
+
+        // Convert query
+        let include_highest_modseq = self
+            .query_filters(
+                imap_filter,
+                mailbox,
+                &message_ids,
+                prev_saved_search,
+                is_uid,
+                &mut filters,
+            )
+            .await?;
+
+        // Run query
+        self.jmap
+            .filter(mailbox.id.account_id, Collection::Email, filters)
+            .await
+            .map(|res| (res, include_highest_modseq))
+            .map_err(|err| err.into())
+    }
+
+    async fn search_message_ids(&self, mailbox_id: &MailboxId) -> crate::op::Result<RoaringBitmap> {
+        Ok(if let Some(document_id) = mailbox_id.mailbox_id {
+            self.jmap
+                .mailbox_message_ids(
+                    &*self.get_access_token().await?,
+                    mailbox_id.account_id,
+                    document_id,
                 )
                 .await?
-                .unwrap_or_default();
-            filters.push(query::Filter::is_in_set(ids.clone()));
-            ids
+                .unwrap_or_default()
         } else {
             self.jmap
-                .get_document_ids(mailbox.id.account_id, Collection::Email)
+                .get_document_ids(mailbox_id.account_id, Collection::Email)
                 .await?
                 .unwrap_or_default()
-        };
+        })
+    }
 
+    async fn query_filters(
+        &self,
+        imap_filter: Vec<Filter>,
+        mailbox: &SelectedMailbox,
+        message_ids: &RoaringBitmap,
+        prev_saved_search: &Option<Option<Arc<Vec<ImapId>>>>,
+        is_uid: bool,
+        filters: &mut Vec<query::Filter>,
+    ) -> crate::op::Result<bool> {
         // Convert query
         let mut include_highest_modseq = false;
+        let mut fuzzy_stack = Vec::new();
+        let mut is_fuzzy = false;
+        let default_language = self.jmap.config.default_language;
         for filter in imap_filter {
             match filter {
                 search::Filter::Sequence(sequence, uid_filter) => {
@@ -303,7 +756,7 @@ impl SessionData {
                     }
                     filters.push(query::Filter::is_in_set(set));
                 }
-                search::Filter::All => {
+                search::Filter::All | search::Filter::SaveDateSupported => {
                     filters.push(query::Filter::is_in_set(message_ids.clone()));
                 }
                 search::Filter::Answered => {
@@ -319,10 +772,11 @@ impl SessionData {
                     filters.push(query::Filter::lt(Property::ReceivedAt, date as u64));
                 }
                 search::Filter::Body(text) => {
-                    filters.push(query::Filter::has_text_detect(
+                    filters.push(text_filter(
                         Property::TextBody,
                         text,
-                        self.jmap.config.default_language,
+                        default_language,
+                        is_fuzzy,
                     ));
                 }
                 search::Filter::Cc(text) => {
@@ -455,10 +909,11 @@ impl SessionData {
                     filters.push(query::Filter::lt(Property::Size, size));
                 }
                 search::Filter::Subject(text) => {
-                    filters.push(query::Filter::has_text_detect(
+                    filters.push(text_filter(
                         Property::Subject,
                         text,
-                        self.jmap.config.default_language,
+                        default_language,
+                        is_fuzzy,
                     ));
                 }
                 search::Filter::Text(text) => {
@@ -475,20 +930,23 @@ impl SessionData {
                         &text,
                         Language::None,
                     ));
-                    filters.push(query::Filter::has_text_detect(
+                    filters.push(text_filter(
                         Property::Subject,
                         &text,
-                        self.jmap.config.default_language,
+                        default_language,
+                        is_fuzzy,
                     ));
-                    filters.push(query::Filter::has_text_detect(
+                    filters.push(text_filter(
                         Property::TextBody,
                         &text,
-                        self.jmap.config.default_language,
+                        default_language,
+                        is_fuzzy,
                     ));
-                    filters.push(query::Filter::has_text_detect(
+                    filters.push(text_filter(
                         Property::Attachments,
                         text,
-                        self.jmap.config.default_language,
+                        default_language,
+                        is_fuzzy,
                     ));
                     filters.push(query::Filter::End);
                 }
@@ -544,15 +1002,24 @@ impl SessionData {
                     filters.push(query::Filter::End);
                 }
                 search::Filter::And => {
+                    fuzzy_stack.push(is_fuzzy);
                     filters.push(query::Filter::And);
                 }
                 search::Filter::Or => {
+                    fuzzy_stack.push(is_fuzzy);
                     filters.push(query::Filter::Or);
                 }
                 search::Filter::Not => {
+                    fuzzy_stack.push(is_fuzzy);
                     filters.push(query::Filter::Not);
                 }
+                search::Filter::Fuzzy => {
+                    fuzzy_stack.push(is_fuzzy);
+                    is_fuzzy = true;
+                    filters.push(query::Filter::And);
+                }
                 search::Filter::End => {
+                    is_fuzzy = fuzzy_stack.pop().unwrap_or(false);
                     filters.push(query::Filter::End);
                 }
                 search::Filter::Recent => {
@@ -626,6 +1093,30 @@ impl SessionData {
                         )));
                     }
                 }
+                search::Filter::SavedBefore(date) => {
+                    save_date_filter(
+                        &self.save_date_mailboxes(&mailbox.id).await?,
+                        0,
+                        date as u64,
+                        filters,
+                    );
+                }
+                search::Filter::SavedOn(date) => {
+                    save_date_filter(
+                        &self.save_date_mailboxes(&mailbox.id).await?,
+                        date as u64,
+                        (date + 86400) as u64,
+                        filters,
+                    );
+                }
+                search::Filter::SavedSince(date) => {
+                    save_date_filter(
+                        &self.save_date_mailboxes(&mailbox.id).await?,
+                        date as u64,
+                        u64::MAX,
+                        filters,
+                    );
+                }
                 search::Filter::ThreadId(id) => {
                     if let Some(id) = Id::from_bytes(id.as_bytes()) {
                         filters.push(query::Filter::is_in_bitmap(
@@ -641,12 +1132,21 @@ impl SessionData {
             }
         }
 
-        // Run query
-        self.jmap
-            .filter(mailbox.id.account_id, Collection::Email, filters)
-            .await
-            .map(|res| (res, include_highest_modseq))
-            .map_err(|err| err.into())
+        Ok(include_highest_modseq)
+    }
+
+    async fn save_date_mailboxes(&self, mailbox_id: &MailboxId) -> crate::op::Result<Vec<u32>> {
+        if let Some(mailbox_id) = mailbox_id.mailbox_id {
+            Ok(vec![mailbox_id])
+        } else {
+            Ok(self
+                .jmap
+                .get_document_ids(mailbox_id.account_id, Collection::Mailbox)
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .collect())
+        }
     }
 }
 
@@ -722,6 +1222,89 @@ impl SelectedMailbox {
     }
 }
 
+fn text_filter(
+    field: Property,
+    text: impl Into<String>,
+    default_language: Language,
+    is_fuzzy: bool,
+) -> query::Filter {
+    if is_fuzzy {
+        let (text, language) = Language::detect(text.into(), default_language);
+        query::Filter::has_fuzzy_text(field, text, language)
+    } else {
+        query::Filter::has_text_detect(field, text, default_language)
+    }
+}
+
+// Save dates are indexed by mailbox, the virtual mailboxes match a save date in any mailbox
+fn save_date_filter(mailbox_ids: &[u32], from: u64, to: u64, filters: &mut Vec<query::Filter>) {
+    filters.push(query::Filter::Or);
+    for mailbox_id in mailbox_ids {
+        filters.push(query::Filter::And);
+        filters.push(query::Filter::ge(
+            Property::SaveDate,
+            SaveDateKey {
+                mailbox_id: *mailbox_id,
+                save_date: from,
+            },
+        ));
+        filters.push(query::Filter::lt(
+            Property::SaveDate,
+            SaveDateKey {
+                mailbox_id: *mailbox_id,
+                save_date: to,
+            },
+        ));
+        filters.push(query::Filter::End);
+    }
+    filters.push(query::Filter::End);
+}
+
+fn relevancy_terms(
+    filters: &[Filter],
+    default_language: Language,
+) -> Vec<(String, Option<String>)> {
+    let mut terms = Vec::new();
+    let mut filter_stack = Vec::new();
+    let mut include_term = true;
+
+    for filter in filters {
+        match filter {
+            Filter::Subject(text) | Filter::Body(text) | Filter::Text(text) => {
+                if include_term {
+                    let (text, language) = Language::detect(text.to_string(), default_language);
+                    for token in Stemmer::new(&text, language, MAX_TOKEN_LENGTH) {
+                        let term = (
+                            token.word.into_owned(),
+                            token.stemmed_word.map(|w| w.into_owned()),
+                        );
+                        if !terms.contains(&term) {
+                            terms.push(term);
+                        }
+                    }
+                }
+            }
+            Filter::And | Filter::Or | Filter::Fuzzy => {
+                filter_stack.push(false);
+            }
+            Filter::Not => {
+                filter_stack.push(true);
+                include_term = !include_term;
+            }
+            Filter::End => {
+                if filter_stack.pop().unwrap_or(false) {
+                    include_term = !include_term;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // The term index does not support matching more than 64 terms
+    terms.truncate(64);
+    terms
+}
+
 impl MailboxState {
     pub fn map_result_id(&self, document_id: u32, is_uid: bool) -> Option<(u32, ImapId)> {
         if let Some(imap_id) = self.id_to_imap.get(&document_id) {
@@ -97,6 +97,7 @@ impl<T: AsyncRead> Session<T> {
                                             attributes: vec![fetch::Attribute::Flags],
                                             changed_since: qresync.modseq.into(),
                                             include_vanished: true,
+                                            partial: None,
                                         },
                                         mailbox.clone(),
                                         true,
@@ -126,6 +127,7 @@ impl<T: AsyncRead> Session<T> {
                             };
 
                             // Update state
+                            data.set_notify_selected(mailbox.clone().into());
                             self.state = State::Selected { data, mailbox };
 
                             self.write_bytes(
@@ -160,9 +162,9 @@ impl<T: AsyncRead> Session<T> {
     }
 
     pub async fn handle_unselect(&mut self, request: Request<Command>) -> crate::OpResult {
-        self.state = State::Authenticated {
-            data: self.state.session_data(),
-        };
+        let data = self.state.session_data();
+        data.set_notify_selected(None);
+        self.state = State::Authenticated { data };
         self.write_bytes(
             StatusResponse::completed(Command::Unselect)
                 .with_tag(request.tag)
@@ -92,6 +92,13 @@ impl SessionData {
             );
         };
 
+        // Obtain the maximum message size that can be appended
+        let append_limit = if items.contains(&Status::AppendLimit) {
+            self.append_limit(mailbox.account_id).await?.into()
+        } else {
+            None
+        };
+
         // Make sure all requested fields are up to date
         let mut items_update = Vec::with_capacity(items.len());
         let mut items_response = Vec::with_capacity(items.len());
@@ -170,6 +177,12 @@ impl SessionData {
                         Status::Recent => {
                             items_response.push((*item, StatusItemType::Number(0)));
                         }
+                        Status::AppendLimit => {
+                            items_response.push((
+                                *item,
+                                StatusItemType::Number(append_limit.unwrap_or_default()),
+                            ));
+                        }
                     }
                 }
                 break;
@@ -188,10 +201,9 @@ impl SessionData {
             if let Some(mailbox_id) = mailbox.mailbox_id {
                 let mailbox_message_ids = self
                     .jmap
-                    .get_tag(
+                    .mailbox_message_ids(
+                        &*self.get_access_token().await?,
                         mailbox.account_id,
-                        Collection::Email,
-                        Property::MailboxIds,
                         mailbox_id,
                     )
                     .await?
@@ -258,7 +270,10 @@ impl SessionData {
                                 0
                             }
                         }
-                        Status::HighestModSeq | Status::MailboxId | Status::Recent => {
+                        Status::HighestModSeq
+                        | Status::MailboxId
+                        | Status::Recent
+                        | Status::AppendLimit => {
                             unreachable!()
                         }
                     };
@@ -311,7 +326,10 @@ impl SessionData {
                                 0
                             }
                         }
-                        Status::HighestModSeq | Status::MailboxId | Status::Recent => {
+                        Status::HighestModSeq
+                        | Status::MailboxId
+                        | Status::Recent
+                        | Status::AppendLimit => {
                             unreachable!()
                         }
                     };
@@ -337,7 +355,10 @@ impl SessionData {
                             Status::Unseen => mailbox_state.total_unseen = value.into(),
                             Status::Deleted => mailbox_state.total_deleted = value.into(),
                             Status::Size => mailbox_state.size = value.into(),
-                            Status::HighestModSeq | Status::MailboxId | Status::Recent => {
+                            Status::HighestModSeq
+                            | Status::MailboxId
+                            | Status::Recent
+                            | Status::AppendLimit => {
                                 unreachable!()
                             }
                         }
@@ -0,0 +1,475 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use imap_proto::{
+    protocol::{
+        urlauth::{FetchResponse, GenArguments, GenResponse, ImapUrl, ResetKeyArguments},
+        ImapResponse,
+    },
+    receiver::Request,
+    Command, ResponseCode, StatusResponse,
+};
+use jmap::{mailbox::INBOX_ID, JMAP};
+use jmap_proto::{
+    error::method::MethodError,
+    object::Object,
+    types::{acl::Acl, blob::BlobId, collection::Collection, property::Property, value::Value},
+};
+use mail_parser::Message;
+use store::{
+    blake3,
+    rand::{thread_rng, Rng},
+    write::{assert::HashedValue, now, BatchBuilder, F_CLEAR, F_VALUE},
+};
+use tokio::{io::AsyncRead, sync::mpsc};
+use utils::ipc::UrlFetchRequest;
+
+use crate::core::{message::uid_to_document_id, Session, SessionData};
+
+use super::fetch::AsImapDataItem;
+
+impl<T: AsyncRead> Session<T> {
+    pub async fn handle_genurlauth(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_genurlauth() {
+            Ok(mut arguments) => {
+                let data = self.state.session_data();
+
+                tokio::spawn(async move {
+                    let tag = std::mem::take(&mut arguments.tag);
+                    let bytes = match data.generate_urls(arguments).await {
+                        Ok(response) => StatusResponse::completed(Command::GenUrlAuth)
+                            .with_tag(tag)
+                            .serialize(response.serialize()),
+                        Err(response) => response.with_tag(tag).into_bytes(),
+                    };
+                    data.write_bytes(bytes).await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+
+    pub async fn handle_resetkey(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_resetkey(self.version) {
+            Ok(mut arguments) => {
+                let data = self.state.session_data();
+
+                tokio::spawn(async move {
+                    let tag = std::mem::take(&mut arguments.tag);
+                    data.write_bytes(
+                        match data.reset_keys(arguments).await {
+                            Ok(_) => StatusResponse::completed(Command::ResetKey),
+                            Err(response) => response,
+                        }
+                        .with_tag(tag)
+                        .into_bytes(),
+                    )
+                    .await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+
+    pub async fn handle_urlfetch(&mut self, request: Request<Command>) -> crate::OpResult {
+        match request.parse_urlfetch() {
+            Ok(arguments) => {
+                let data = self.state.session_data();
+
+                tokio::spawn(async move {
+                    let bytes = match data.get_access_token().await {
+                        Ok(access_token) => {
+                            let mut response = FetchResponse {
+                                urls: Vec::with_capacity(arguments.urls.len()),
+                            };
+                            let mut result = Ok(());
+                            for url in arguments.urls {
+                                let contents = match ImapUrl::parse(&url) {
+                                    Ok(imap_url) => {
+                                        match fetch_url(
+                                            &data.jmap,
+                                            &imap_url,
+                                            &access_token.name,
+                                            false,
+                                        )
+                                        .await
+                                        {
+                                            Ok(contents) => contents,
+                                            Err(err) => {
+                                                result = Err(err);
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    Err(_) => None,
+                                };
+                                response.urls.push((url, contents));
+                            }
+                            match result {
+                                Ok(_) => StatusResponse::completed(Command::UrlFetch)
+                                    .with_tag(arguments.tag)
+                                    .serialize(response.serialize()),
+                                Err(response) => response.with_tag(arguments.tag).into_bytes(),
+                            }
+                        }
+                        Err(response) => response.with_tag(arguments.tag).into_bytes(),
+                    };
+                    data.write_bytes(bytes).await;
+                });
+                Ok(())
+            }
+            Err(response) => self.write_bytes(response.into_bytes()).await,
+        }
+    }
+}
+
+impl SessionData {
+    async fn generate_urls(&self, arguments: GenArguments) -> crate::op::Result<GenResponse> {
+        let access_token = self.get_access_token().await?;
+        let mut response = GenResponse {
+            urls: Vec::with_capacity(arguments.urls.len()),
+        };
+
+        for (url, mechanism) in arguments.urls {
+            // URLs can only be generated for mailboxes owned by the user
+            if url.user.as_deref() != Some(access_token.name.as_str()) {
+                return Err(StatusResponse::no(
+                    "URLAUTH can only be generated for your own mailboxes.",
+                )
+                .with_code(ResponseCode::NoPerm));
+            }
+            let mailbox_id = self
+                .get_mailbox_by_name(&url.mailbox_name)
+                .filter(|mailbox| mailbox.account_id == self.account_id)
+                .and_then(|mailbox| mailbox.mailbox_id)
+                .ok_or_else(|| {
+                    StatusResponse::no("Mailbox does not exist.")
+                        .with_code(ResponseCode::NonExistent)
+                })?;
+            let rump = url
+                .url_auth
+                .ok_or_else(|| StatusResponse::bad("URL is missing the URLAUTH component."))?
+                .rump;
+            let key = get_access_key(&self.jmap, self.account_id, mailbox_id, true)
+                .await?
+                .ok_or_else(StatusResponse::database_failure)?;
+            let token = blake3::keyed_hash(&key, rump.as_bytes());
+            response.urls.push(format!(
+                "{}:{}:{}",
+                rump,
+                mechanism.as_str().to_ascii_lowercase(),
+                token.to_hex()
+            ));
+        }
+
+        Ok(response)
+    }
+
+    async fn reset_keys(&self, arguments: ResetKeyArguments) -> crate::op::Result<()> {
+        let mailbox_id = if let Some(mailbox_name) = &arguments.mailbox_name {
+            Some(
+                self.get_mailbox_by_name(mailbox_name)
+                    .filter(|mailbox| mailbox.account_id == self.account_id)
+                    .and_then(|mailbox| mailbox.mailbox_id)
+                    .ok_or_else(|| {
+                        StatusResponse::no("Mailbox does not exist.")
+                            .with_code(ResponseCode::NonExistent)
+                    })?,
+            )
+        } else {
+            None
+        };
+
+        reset_access_keys(&self.jmap, self.account_id, mailbox_id).await
+    }
+
+    pub async fn catenate_url(&self, url: &str) -> crate::op::Result<Vec<u8>> {
+        let bad_url = || {
+            StatusResponse::no("Invalid or inaccessible URL.").with_code(ResponseCode::BadUrl {
+                url: url.to_string(),
+            })
+        };
+        let imap_url = ImapUrl::parse(url).map_err(|_| bad_url())?;
+
+        if imap_url.url_auth.is_some() {
+            // URLAUTH authorized URLs are fetched with the rights of their owner
+            let access_token = self.get_access_token().await?;
+            return fetch_url(&self.jmap, &imap_url, &access_token.name, false)
+                .await?
+                .ok_or_else(bad_url);
+        } else if imap_url.user.is_some() {
+            let access_token = self.get_access_token().await?;
+            if imap_url.user.as_deref() != Some(access_token.name.as_str()) {
+                return Err(bad_url());
+            }
+        }
+
+        // Plain URLs are fetched with the rights of the current user
+        let mailbox = self
+            .get_mailbox_by_name(&imap_url.mailbox_name)
+            .filter(|mailbox| mailbox.mailbox_id.is_some())
+            .ok_or_else(bad_url)?;
+        if !self
+            .check_mailbox_acl(
+                mailbox.account_id,
+                mailbox.mailbox_id.unwrap(),
+                Acl::ReadItems,
+            )
+            .await?
+        {
+            return Err(bad_url());
+        }
+
+        fetch_message_section(
+            &self.jmap,
+            mailbox.account_id,
+            mailbox.mailbox_id.unwrap(),
+            &imap_url,
+        )
+        .await?
+        .ok_or_else(bad_url)
+    }
+}
+
+// Fetches the contents of an URLAUTH authorized URL on behalf of the
+// specified user, returns None if the URL is invalid or access is denied.
+pub async fn fetch_url(
+    jmap: &JMAP,
+    url: &ImapUrl,
+    user: &str,
+    is_submission: bool,
+) -> crate::op::Result<Option<Vec<u8>>> {
+    let (url_auth, owner) = match (&url.url_auth, &url.user) {
+        (Some(url_auth), Some(owner))
+            if url_auth.token.is_some()
+                && !url_auth.is_expired(now() as i64)
+                && url_auth.access.allows(user, is_submission) =>
+        {
+            (url_auth, owner)
+        }
+        _ => return Ok(None),
+    };
+
+    // Obtain owner's mailbox
+    let account_id = if let Some(account_id) = jmap.try_get_account_id(owner).await? {
+        account_id
+    } else {
+        return Ok(None);
+    };
+    let mailbox_id = if url.mailbox_name.eq_ignore_ascii_case("INBOX") {
+        INBOX_ID
+    } else if let Some(mailbox_id) = jmap
+        .mailbox_get_by_name(account_id, &url.mailbox_name)
+        .await?
+    {
+        mailbox_id
+    } else {
+        return Ok(None);
+    };
+
+    // Verify token
+    let token = match url_auth
+        .token
+        .as_deref()
+        .and_then(|token| blake3::Hash::from_hex(token).ok())
+    {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+    match get_access_key(jmap, account_id, mailbox_id, false).await? {
+        Some(key) if blake3::keyed_hash(&key, url_auth.rump.as_bytes()) == token => {}
+        _ => return Ok(None),
+    }
+
+    fetch_message_section(jmap, account_id, mailbox_id, url).await
+}
+
+pub fn spawn_url_resolver(jmap: Arc<JMAP>, mut url_rx: mpsc::Receiver<UrlFetchRequest>) {
+    tokio::spawn(async move {
+        while let Some(request) = url_rx.recv().await {
+            let jmap = jmap.clone();
+            tokio::spawn(async move {
+                let result = match ImapUrl::parse(&request.url) {
+                    Ok(url) => fetch_url(&jmap, &url, &request.user, true).await,
+                    Err(_) => Ok(None),
+                };
+
+                // Dropping the sender on failure signals a temporary error
+                if let Ok(result) = result {
+                    request.result_tx.send(result).ok();
+                }
+            });
+        }
+    });
+}
+
+async fn fetch_message_section(
+    jmap: &JMAP,
+    account_id: u32,
+    mailbox_id: u32,
+    url: &ImapUrl,
+) -> crate::op::Result<Option<Vec<u8>>> {
+    // Obtain message id
+    let document_id = match uid_to_document_id(jmap, account_id, mailbox_id, url.uid).await? {
+        Some((uid_validity, document_id))
+            if url
+                .uid_validity
+                .map_or(true, |url_uid_validity| url_uid_validity == uid_validity) =>
+        {
+            document_id
+        }
+        _ => return Ok(None),
+    };
+
+    // Fetch message
+    let raw_message = match jmap
+        .get_blob(&BlobId::maildir(account_id, document_id).kind, 0..u32::MAX)
+        .await
+    {
+        Ok(Some(raw_message)) => raw_message,
+        Ok(None) => return Ok(None),
+        Err(_) => return Err(StatusResponse::database_failure()),
+    };
+
+    if url.section.is_empty() {
+        Ok(match url.partial {
+            Some((start, length)) => raw_message
+                .get(
+                    start as usize
+                        ..std::cmp::min(start as usize + length as usize, raw_message.len()),
+                )
+                .map(|bytes| bytes.to_vec()),
+            None => Some(raw_message),
+        })
+    } else {
+        Ok(Message::parse(&raw_message).and_then(|message| {
+            message
+                .body_section(&url.section, url.partial)
+                .map(|section| section.into_owned().into_bytes())
+        }))
+    }
+}
+
+// Mailbox access keys are stored per account, indexed by mailbox id.
+async fn get_access_key(
+    jmap: &JMAP,
+    account_id: u32,
+    mailbox_id: u32,
+    create: bool,
+) -> crate::op::Result<Option<[u8; 32]>> {
+    let property = Property::_T(mailbox_id.to_string());
+    let current = jmap
+        .get_property::<HashedValue<Object<Value>>>(
+            account_id,
+            Collection::Principal,
+            0,
+            Property::AccessKeys,
+        )
+        .await?;
+    if let Some(Value::Blob(key)) = current
+        .as_ref()
+        .and_then(|current| current.inner.properties.get(&property))
+    {
+        if let Ok(key) = <[u8; 32]>::try_from(key.as_slice()) {
+            return Ok(Some(key));
+        }
+    }
+    if !create {
+        return Ok(None);
+    }
+
+    // Generate a new key
+    let key: [u8; 32] = thread_rng().gen();
+    let mut keys = current
+        .as_ref()
+        .map(|current| current.inner.clone())
+        .unwrap_or_else(|| Object::with_capacity(1));
+    keys.set(property, Value::Blob(key.to_vec()));
+    write_access_keys(jmap, account_id, current, keys).await?;
+
+    Ok(Some(key))
+}
+
+async fn reset_access_keys(
+    jmap: &JMAP,
+    account_id: u32,
+    mailbox_id: Option<u32>,
+) -> crate::op::Result<()> {
+    let current = if let Some(current) = jmap
+        .get_property::<HashedValue<Object<Value>>>(
+            account_id,
+            Collection::Principal,
+            0,
+            Property::AccessKeys,
+        )
+        .await?
+    {
+        current
+    } else {
+        return Ok(());
+    };
+
+    let mut keys = current.inner.clone();
+    if let Some(mailbox_id) = mailbox_id {
+        keys.remove(&Property::_T(mailbox_id.to_string()));
+    } else {
+        keys.properties.clear();
+    }
+
+    write_access_keys(jmap, account_id, Some(current), keys).await
+}
+
+async fn write_access_keys(
+    jmap: &JMAP,
+    account_id: u32,
+    current: Option<HashedValue<Object<Value>>>,
+    keys: Object<Value>,
+) -> crate::op::Result<()> {
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Principal)
+        .update_document(0);
+    if let Some(current) = &current {
+        batch.assert_value(Property::AccessKeys, current);
+    } else {
+        batch.assert_value(Property::AccessKeys, ());
+    }
+    if !keys.properties.is_empty() {
+        batch.value(Property::AccessKeys, &keys, F_VALUE);
+    } else {
+        batch.value(Property::AccessKeys, (), F_VALUE | F_CLEAR);
+    }
+
+    match jmap.write_batch(batch).await {
+        Ok(_) => Ok(()),
+        Err(MethodError::ServerUnavailable) => Err(StatusResponse::no(
+            "Another process modified the access keys, please try again.",
+        )),
+        Err(_) => Err(StatusResponse::database_failure()),
+    }
+}
@@ -36,6 +36,7 @@ pub enum MethodError {
     UnsupportedSort(String),
     ServerFail(String),
     UnknownMethod(String),
+    UnknownDataType(String),
     ServerUnavailable,
     ServerPartialFail,
     InvalidResultReference(String),
@@ -57,6 +58,7 @@ impl Display for MethodError {
             MethodError::UnsupportedSort(err) => write!(f, "Unsupported sort: {}", err),
             MethodError::ServerFail(err) => write!(f, "Server error: {}", err),
             MethodError::UnknownMethod(err) => write!(f, "Unknown method: {}", err),
+            MethodError::UnknownDataType(err) => write!(f, "Unknown data type: {}", err),
             MethodError::ServerUnavailable => write!(f, "Server unavailable"),
             MethodError::ServerPartialFail => write!(f, "Server partial fail"),
             MethodError::InvalidResultReference(err) => {
@@ -122,6 +124,7 @@ impl Serialize for MethodError {
                 )
             }),
             MethodError::UnknownMethod(description) => ("unknownMethod", description.as_str()),
+            MethodError::UnknownDataType(description) => ("unknownDataType", description.as_str()),
             MethodError::ServerUnavailable => (
                 "serverUnavailable",
                 concat!(
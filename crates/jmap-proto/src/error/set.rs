@@ -99,6 +99,10 @@ pub enum SetErrorType {
     InvalidScript,
     #[serde(rename = "scriptIsActive")]
     ScriptIsActive,
+    #[serde(rename = "mdnAlreadySent")]
+    MdnAlreadySent,
+    #[serde(rename = "calendarHasEvent")]
+    CalendarHasEvent,
 }
 
 impl SetErrorType {
@@ -129,6 +133,8 @@ impl SetErrorType {
             SetErrorType::AlreadyExists => "alreadyExists",
             SetErrorType::InvalidScript => "invalidScript",
             SetErrorType::ScriptIsActive => "scriptIsActive",
+            SetErrorType::MdnAlreadySent => "mdnAlreadySent",
+            SetErrorType::CalendarHasEvent => "calendarHasEvent",
         }
     }
 }
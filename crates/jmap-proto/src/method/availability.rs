@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use serde::Serialize;
+
+use crate::{
+    object::Object,
+    parser::{json::Parser, JsonObjectParser, Token},
+    request::RequestProperty,
+    types::{date::UTCDate, id::Id, property::Property, value::Value},
+};
+
+#[derive(Debug, Clone)]
+pub struct GetAvailabilityRequest {
+    pub account_id: Id,
+    pub id: Id,
+    pub utc_start: UTCDate,
+    pub utc_end: UTCDate,
+    pub show_details: bool,
+    pub event_properties: Option<Vec<Property>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GetAvailabilityResponse {
+    pub list: Vec<BusyPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BusyPeriod {
+    #[serde(rename = "utcStart")]
+    pub utc_start: UTCDate,
+
+    #[serde(rename = "utcEnd")]
+    pub utc_end: UTCDate,
+
+    #[serde(rename = "busyStatus")]
+    pub busy_status: BusyStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Object<Value>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BusyStatus {
+    #[serde(rename = "confirmed")]
+    Confirmed,
+    #[serde(rename = "tentative")]
+    Tentative,
+    #[serde(rename = "unavailable")]
+    Unavailable,
+}
+
+impl JsonObjectParser for GetAvailabilityRequest {
+    fn parse(parser: &mut Parser) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = GetAvailabilityRequest {
+            account_id: Id::default(),
+            id: Id::default(),
+            utc_start: UTCDate::default(),
+            utc_end: UTCDate::default(),
+            show_details: false,
+            event_properties: None,
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x6469 => {
+                    request.id = parser.next_token::<Id>()?.unwrap_string("id")?;
+                }
+                0x7472_6174_5363_7475 => {
+                    request.utc_start =
+                        parser.next_token::<UTCDate>()?.unwrap_string("utcStart")?;
+                }
+                0x646e_4563_7475 => {
+                    request.utc_end = parser.next_token::<UTCDate>()?.unwrap_string("utcEnd")?;
+                }
+                0x0073_6c69_6174_6544_776f_6873 => {
+                    request.show_details = parser
+                        .next_token::<String>()?
+                        .unwrap_bool_or_null("showDetails")?
+                        .unwrap_or(false);
+                }
+                0x0073_6569_7472_6570_6f72_5074_6e65_7665 => {
+                    request.event_properties = <Option<Vec<Property>>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
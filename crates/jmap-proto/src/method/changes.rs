@@ -68,6 +68,10 @@ pub enum RequestArguments {
     Thread,
     Identity,
     EmailSubmission,
+    Calendar,
+    CalendarEvent,
+    ShareNotification,
+    Principal,
 }
 
 impl JsonObjectParser for ChangesRequest {
@@ -82,6 +86,10 @@ impl JsonObjectParser for ChangesRequest {
                 MethodObject::Thread => RequestArguments::Thread,
                 MethodObject::Identity => RequestArguments::Identity,
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
+                MethodObject::Principal => RequestArguments::Principal,
                 _ => {
                     return Err(Error::Method(MethodError::UnknownMethod(format!(
                         "{}/changes",
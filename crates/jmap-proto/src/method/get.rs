@@ -21,16 +21,18 @@
  * for more details.
 */
 
+use utils::map::vec_map::VecMap;
+
 use crate::{
     error::method::MethodError,
-    object::{email, Object},
-    parser::{json::Parser, Error, JsonObjectParser, Token},
+    object::{blob::BlobProperty, email, Object},
+    parser::{json::Parser, Error, Ignore, JsonObjectParser, Token},
     request::{
         method::MethodObject,
         reference::{MaybeReference, ResultReference},
         RequestProperty, RequestPropertyParser,
     },
-    types::{id::Id, property::Property, state::State, value::Value},
+    types::{blob::BlobId, id::Id, property::Property, state::State, value::Value},
 };
 
 #[derive(Debug, Clone)]
@@ -52,6 +54,9 @@ pub enum RequestArguments {
     SieveScript,
     VacationResponse,
     Principal,
+    Calendar,
+    CalendarEvent,
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -69,6 +74,26 @@ pub struct GetResponse {
     pub not_found: Vec<Id>,
 }
 
+#[derive(Debug, Clone)]
+pub struct BlobGetRequest {
+    pub account_id: Id,
+    pub ids: Option<Vec<BlobId>>,
+    pub properties: Option<Vec<BlobProperty>>,
+    pub offset: Option<usize>,
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobGetResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    pub list: Vec<VecMap<BlobProperty, Value>>,
+
+    #[serde(rename = "notFound")]
+    pub not_found: Vec<BlobId>,
+}
+
 impl JsonObjectParser for GetRequest<RequestArguments> {
     fn parse(parser: &mut Parser<'_>) -> crate::parser::Result<Self>
     where
@@ -85,6 +110,9 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
                 MethodObject::Principal => RequestArguments::Principal,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(Error::Method(MethodError::UnknownMethod(format!(
                         "{}/get",
@@ -132,6 +160,54 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
     }
 }
 
+impl JsonObjectParser for BlobGetRequest {
+    fn parse(parser: &mut Parser<'_>) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = BlobGetRequest {
+            account_id: Id::default(),
+            ids: None,
+            properties: None,
+            offset: None,
+            length: None,
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x0073_6469 => {
+                    request.ids = <Option<Vec<BlobId>>>::parse(parser)?;
+                }
+                0x7365_6974_7265_706f_7270 => {
+                    request.properties = <Option<Vec<BlobProperty>>>::parse(parser)?;
+                }
+                0x7465_7366_666f => {
+                    request.offset = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("offset")?;
+                }
+                0x6874_676e_656c => {
+                    request.length = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("length")?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
 impl RequestPropertyParser for RequestArguments {
     fn parse(
         &mut self,
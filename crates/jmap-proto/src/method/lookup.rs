@@ -0,0 +1,106 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use serde::Serialize;
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::method::MethodError,
+    parser::{json::Parser, Error, JsonObjectParser, Token},
+    request::{method::MethodObject, RequestProperty},
+    types::{blob::BlobId, id::Id},
+};
+
+#[derive(Debug, Clone)]
+pub struct BlobLookupRequest {
+    pub account_id: Id,
+    pub type_names: Vec<MethodObject>,
+    pub ids: Vec<BlobId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobLookupResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    pub list: Vec<BlobInfo>,
+
+    #[serde(rename = "notFound")]
+    pub not_found: Vec<BlobId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobInfo {
+    pub id: BlobId,
+
+    #[serde(rename = "matchedIds")]
+    pub matched_ids: VecMap<String, Vec<Id>>,
+}
+
+impl JsonObjectParser for BlobLookupRequest {
+    fn parse(parser: &mut Parser) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = BlobLookupRequest {
+            account_id: Id::default(),
+            type_names: Vec::new(),
+            ids: Vec::new(),
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x0073_656d_614e_6570_7974 => {
+                    for type_name in <Vec<String>>::parse(parser)? {
+                        request.type_names.push(match type_name.as_str() {
+                            "Email" => MethodObject::Email,
+                            "Mailbox" => MethodObject::Mailbox,
+                            "Thread" => MethodObject::Thread,
+                            "SieveScript" => MethodObject::SieveScript,
+                            _ => {
+                                return Err(Error::Method(MethodError::UnknownDataType(format!(
+                                    "Unknown data type {type_name:?}."
+                                ))))
+                            }
+                        });
+                    }
+                }
+                0x0073_6469 => {
+                    request.ids = <Vec<BlobId>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
@@ -25,7 +25,7 @@ use std::fmt::Display;
 
 use crate::{
     error::method::MethodError,
-    object::{email, mailbox},
+    object::{calendar, email, mailbox},
     parser::{json::Parser, Error, Ignore, JsonObjectParser, Token},
     request::{method::MethodObject, RequestProperty, RequestPropertyParser},
     types::{date::UTCDate, id::Id, keyword::Keyword, state::State},
@@ -91,12 +91,19 @@ pub enum Filter {
     InMailboxOtherThan(Vec<Id>),
     MinSize(u32),
     MaxSize(u32),
+    ThreadMinSize(u32),
+    ThreadMaxSize(u32),
+    ThreadReceivedBefore(UTCDate),
+    ThreadReceivedAfter(UTCDate),
     AllInThreadHaveKeyword(Keyword),
     SomeInThreadHaveKeyword(Keyword),
     NoneInThreadHaveKeyword(Keyword),
     HasKeyword(Keyword),
     NotKeyword(Keyword),
     HasAttachment(bool),
+    HasSmime(bool),
+    HasVerifiedSmime(bool),
+    HasVerifiedSmimeAtDelivery(bool),
     From(String),
     To(String),
     Cc(String),
@@ -113,6 +120,12 @@ pub enum Filter {
     HasAnyRole(bool),
     IsSubscribed(bool),
     IsActive(bool),
+    InCalendars(Vec<Id>),
+    Uid(String),
+    Title(String),
+    Description(String),
+    ObjectType(String),
+    ObjectAccountId(Id),
     _T(String),
 
     And,
@@ -149,6 +162,12 @@ pub enum SortProperty {
     HasKeyword,
     AllInThreadHaveKeyword,
     SomeInThreadHaveKeyword,
+    Start,
+    Uid,
+    Created,
+    ThreadReceivedAt,
+    ThreadSize,
+    ThreadHasUnread,
     _T(String),
 }
 
@@ -159,6 +178,8 @@ pub enum RequestArguments {
     EmailSubmission,
     SieveScript,
     Principal,
+    CalendarEvent(calendar::QueryArguments),
+    ShareNotification,
 }
 
 impl JsonObjectParser for QueryRequest<RequestArguments> {
@@ -173,6 +194,8 @@ impl JsonObjectParser for QueryRequest<RequestArguments> {
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::Principal => RequestArguments::Principal,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent(Default::default()),
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(Error::Method(MethodError::UnknownMethod(format!(
                         "{}/query",
@@ -342,6 +365,32 @@ pub fn parse_filter(parser: &mut Parser) -> crate::parser::Result<Vec<Filter>> {
                                 .unwrap_uint_or_null("maxSize")?
                                 .unwrap_or_default() as u32,
                         ),
+                        (0x0065_7a69_536e_694d_6461_6572_6874, _) => Filter::ThreadMinSize(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_uint_or_null("threadMinSize")?
+                                .unwrap_or_default() as u32,
+                        ),
+                        (0x0065_7a69_5378_614d_6461_6572_6874, _) => Filter::ThreadMaxSize(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_uint_or_null("threadMaxSize")?
+                                .unwrap_or_default() as u32,
+                        ),
+                        (0x6542_6465_7669_6563_6552_6461_6572_6874, 0x6572_6f66) => {
+                            Filter::ThreadReceivedBefore(
+                                parser
+                                    .next_token::<UTCDate>()?
+                                    .unwrap_string("threadReceivedBefore")?,
+                            )
+                        }
+                        (0x6641_6465_7669_6563_6552_6461_6572_6874, 0x0072_6574) => {
+                            Filter::ThreadReceivedAfter(
+                                parser
+                                    .next_token::<UTCDate>()?
+                                    .unwrap_string("threadReceivedAfter")?,
+                            )
+                        }
                         (0x4b65_7661_4864_6165_7268_546e_496c_6c61, 0x6472_6f77_7965) => {
                             Filter::AllInThreadHaveKeyword(
                                 parser
@@ -378,6 +427,21 @@ pub fn parse_filter(parser: &mut Parser) -> crate::parser::Result<Vec<Filter>> {
                                 .next_token::<String>()?
                                 .unwrap_bool("hasAttachment")?,
                         ),
+                        (0x656d_696d_5373_6168, _) => Filter::HasSmime(
+                            parser.next_token::<String>()?.unwrap_bool("hasSmime")?,
+                        ),
+                        (0x656d_696d_5364_6569_6669_7265_5673_6168, 0) => Filter::HasVerifiedSmime(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_bool("hasVerifiedSmime")?,
+                        ),
+                        (0x656d_696d_5364_6569_6669_7265_5673_6168, 0x7972_6576_696c_6544_7441) => {
+                            Filter::HasVerifiedSmimeAtDelivery(
+                                parser
+                                    .next_token::<String>()?
+                                    .unwrap_bool("hasVerifiedSmimeAtDelivery")?,
+                            )
+                        }
                         (0x6d6f_7266, _) => {
                             Filter::From(parser.next_token::<String>()?.unwrap_string("from")?)
                         }
@@ -428,6 +492,28 @@ pub fn parse_filter(parser: &mut Parser) -> crate::parser::Result<Vec<Filter>> {
                         (0x6576_6974_6341_7369, _) => Filter::IsActive(
                             parser.next_token::<String>()?.unwrap_bool("isActive")?,
                         ),
+                        (0x0073_7261_646e_656c_6143_6e69, _) => {
+                            Filter::InCalendars(<Vec<Id>>::parse(parser)?)
+                        }
+                        (0x0064_6975, _) => {
+                            Filter::Uid(parser.next_token::<String>()?.unwrap_string("uid")?)
+                        }
+                        (0x0065_6c74_6974, _) => {
+                            Filter::Title(parser.next_token::<String>()?.unwrap_string("title")?)
+                        }
+                        (0x006e_6f69_7470_6972_6373_6564, _) => Filter::Description(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_string("description")?,
+                        ),
+                        (0x6570_7954_7463_656a_626f, _) => Filter::ObjectType(
+                            parser.next_token::<String>()?.unwrap_string("objectType")?,
+                        ),
+                        (0x0064_4974_6e75_6f63_6341_7463_656a_626f, _) => Filter::ObjectAccountId(
+                            parser
+                                .next_token::<Id>()?
+                                .unwrap_string("objectAccountId")?,
+                        ),
                         _ => {
                             if parser.is_eof || parser.skip_string() {
                                 let filter = Filter::_T(
@@ -569,6 +655,12 @@ impl JsonObjectParser for SortProperty {
             0x6472_6f77_7965_4b73_6168 => Ok(SortProperty::HasKeyword),
             0x4b65_7661_4864_6165_7268_546e_496c_6c61 => Ok(SortProperty::AllInThreadHaveKeyword),
             0x6576_6148_6461_6572_6854_6e49_656d_6f73 => Ok(SortProperty::SomeInThreadHaveKeyword),
+            0x0074_7261_7473 => Ok(SortProperty::Start),
+            0x0064_6975 => Ok(SortProperty::Uid),
+            0x0064_6574_6165_7263 => Ok(SortProperty::Created),
+            0x7441_6465_7669_6563_6552_6461_6572_6874 => Ok(SortProperty::ThreadReceivedAt),
+            0x657a_6953_6461_6572_6874 => Ok(SortProperty::ThreadSize),
+            0x0064_6165_726e_5573_6148_6461_6572_6874 => Ok(SortProperty::ThreadHasUnread),
             _ => {
                 if parser.is_eof || parser.skip_string() {
                     Ok(SortProperty::_T(
@@ -607,12 +699,19 @@ impl Display for Filter {
             Filter::InMailboxOtherThan(_) => "inMailboxOtherThan",
             Filter::MinSize(_) => "minSize",
             Filter::MaxSize(_) => "maxSize",
+            Filter::ThreadMinSize(_) => "threadMinSize",
+            Filter::ThreadMaxSize(_) => "threadMaxSize",
+            Filter::ThreadReceivedBefore(_) => "threadReceivedBefore",
+            Filter::ThreadReceivedAfter(_) => "threadReceivedAfter",
             Filter::AllInThreadHaveKeyword(_) => "allInThreadHaveKeyword",
             Filter::SomeInThreadHaveKeyword(_) => "someInThreadHaveKeyword",
             Filter::NoneInThreadHaveKeyword(_) => "noneInThreadHaveKeyword",
             Filter::HasKeyword(_) => "hasKeyword",
             Filter::NotKeyword(_) => "notKeyword",
             Filter::HasAttachment(_) => "hasAttachment",
+            Filter::HasSmime(_) => "hasSmime",
+            Filter::HasVerifiedSmime(_) => "hasVerifiedSmime",
+            Filter::HasVerifiedSmimeAtDelivery(_) => "hasVerifiedSmimeAtDelivery",
             Filter::From(_) => "from",
             Filter::To(_) => "to",
             Filter::Cc(_) => "cc",
@@ -629,6 +728,12 @@ impl Display for Filter {
             Filter::HasAnyRole(_) => "hasAnyRole",
             Filter::IsSubscribed(_) => "isSubscribed",
             Filter::IsActive(_) => "isActive",
+            Filter::InCalendars(_) => "inCalendars",
+            Filter::Uid(_) => "uid",
+            Filter::Title(_) => "title",
+            Filter::Description(_) => "description",
+            Filter::ObjectType(_) => "objectType",
+            Filter::ObjectAccountId(_) => "objectAccountId",
             Filter::_T(v) => v.as_str(),
             Filter::And => "and",
             Filter::Or => "or",
@@ -659,6 +764,12 @@ impl Display for SortProperty {
             SortProperty::HasKeyword => "hasKeyword",
             SortProperty::AllInThreadHaveKeyword => "allInThreadHaveKeyword",
             SortProperty::SomeInThreadHaveKeyword => "someInThreadHaveKeyword",
+            SortProperty::Start => "start",
+            SortProperty::Uid => "uid",
+            SortProperty::Created => "created",
+            SortProperty::ThreadReceivedAt => "threadReceivedAt",
+            SortProperty::ThreadSize => "threadSize",
+            SortProperty::ThreadHasUnread => "threadHasUnread",
             SortProperty::_T(s) => s,
         })
     }
@@ -673,6 +784,7 @@ impl RequestPropertyParser for RequestArguments {
         match self {
             RequestArguments::Email(args) => args.parse(parser, property),
             RequestArguments::Mailbox(args) => args.parse(parser, property),
+            RequestArguments::CalendarEvent(args) => args.parse(parser, property),
             _ => Ok(false),
         }
     }
@@ -688,6 +800,8 @@ impl Filter {
                 | Filter::MaxSize(_)
                 | Filter::Text(_)
                 | Filter::HasAttachment(_)
+                | Filter::HasSmime(_)
+                | Filter::HasVerifiedSmimeAtDelivery(_)
                 | Filter::From(_)
                 | Filter::To(_)
                 | Filter::Cc(_)
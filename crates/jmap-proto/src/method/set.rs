@@ -29,7 +29,7 @@ use crate::{
         method::MethodError,
         set::{InvalidProperty, SetError},
     },
-    object::{email_submission, mailbox, sieve, Object},
+    object::{calendar, email_submission, mailbox, sieve, Object},
     parser::{json::Parser, Error, JsonObjectParser, Token},
     request::{
         method::MethodObject,
@@ -70,6 +70,10 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript(sieve::SetArguments),
     VacationResponse,
+    Calendar(calendar::SetArguments),
+    CalendarEvent(calendar::EventSetArguments),
+    Principal,
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -130,6 +134,10 @@ impl JsonObjectParser for SetRequest<RequestArguments> {
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
                 MethodObject::SieveScript => RequestArguments::SieveScript(Default::default()),
+                MethodObject::Calendar => RequestArguments::Calendar(Default::default()),
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent(Default::default()),
+                MethodObject::Principal => RequestArguments::Principal,
+                MethodObject::ShareNotification => RequestArguments::ShareNotification,
                 _ => {
                     return Err(Error::Method(MethodError::UnknownMethod(format!(
                         "{}/set",
@@ -213,7 +221,9 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::ReceivedAt
                     | Property::Expires
                     | Property::FromDate
-                    | Property::ToDate => parser
+                    | Property::ToDate
+                    | Property::Created
+                    | Property::Updated => parser
                         .next_token::<UTCDate>()?
                         .unwrap_string_or_null("")?
                         .map(|date| SetValue::Value(Value::Date(date)))
@@ -237,7 +247,19 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::Location
                     | Property::Cid
                     | Property::Role
-                    | Property::PartId => parser
+                    | Property::PartId
+                    | Property::Uid
+                    | Property::Title
+                    | Property::Start
+                    | Property::Duration
+                    | Property::TimeZone
+                    | Property::RecurrenceId
+                    | Property::Status
+                    | Property::FreeBusyStatus
+                    | Property::Privacy
+                    | Property::Method
+                    | Property::ProdId
+                    | Property::Color => parser
                         .next_token::<String>()?
                         .unwrap_string_or_null("")?
                         .map(|text| SetValue::Value(Value::Text(text)))
@@ -259,12 +281,19 @@ impl JsonObjectParser for Object<SetValue> {
                     Property::HasAttachment
                     | Property::IsSubscribed
                     | Property::IsEnabled
-                    | Property::IsActive => parser
+                    | Property::IsActive
+                    | Property::IsVisible
+                    | Property::IsDefault
+                    | Property::ShowWithoutTime => parser
                         .next_token::<String>()?
                         .unwrap_bool_or_null("")?
                         .map(|bool| SetValue::Value(Value::Bool(bool)))
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::Size | Property::SortOrder | Property::Quota => parser
+                    Property::Size
+                    | Property::SortOrder
+                    | Property::Quota
+                    | Property::Sequence
+                    | Property::Priority => parser
                         .next_token::<String>()?
                         .unwrap_uint_or_null("")?
                         .map(|uint| SetValue::Value(Value::UnsignedInt(uint)))
@@ -274,7 +303,7 @@ impl JsonObjectParser for Object<SetValue> {
                         .unwrap_string_or_null("")?
                         .map(SetValue::IdReference)
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::MailboxIds => {
+                    Property::MailboxIds | Property::CalendarIds => {
                         if key.patch.is_empty() {
                             SetValue::IdReferences(
                                 <SetValueMap<MaybeReference<Id, String>>>::parse(parser)?.values,
@@ -341,7 +370,14 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::SubParts
                     | Property::To
                     | Property::UndoStatus
-                    | Property::Types => SetValue::Value(Value::parse::<ObjectProperty, String>(
+                    | Property::Types
+                    | Property::RecurrenceRules
+                    | Property::RecurrenceOverrides
+                    | Property::Participants
+                    | Property::Locations
+                    | Property::VirtualLocations
+                    | Property::Alerts
+                    | Property::Snoozed => SetValue::Value(Value::parse::<ObjectProperty, String>(
                         parser.next_token()?,
                         parser,
                     )?),
@@ -349,6 +385,9 @@ impl JsonObjectParser for Object<SetValue> {
                         parser.next_token()?,
                         parser,
                     )?),
+                    Property::Filter => {
+                        SetValue::Value(Value::parse_json(parser.next_token()?, parser)?)
+                    }
                     Property::Members => SetValue::Value(Value::parse::<ObjectProperty, Id>(
                         parser.next_token()?,
                         parser,
@@ -385,6 +424,8 @@ impl RequestPropertyParser for RequestArguments {
             RequestArguments::Mailbox(args) => args.parse(parser, property),
             RequestArguments::EmailSubmission(args) => args.parse(parser, property),
             RequestArguments::SieveScript(args) => args.parse(parser, property),
+            RequestArguments::Calendar(args) => args.parse(parser, property),
+            RequestArguments::CalendarEvent(args) => args.parse(parser, property),
             _ => Ok(false),
         }
     }
@@ -0,0 +1,220 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use mail_parser::decoders::base64::base64_decode;
+use serde::Serialize;
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::{method::MethodError, set::SetError},
+    parser::{json::Parser, Error, Ignore, JsonObjectParser, Token},
+    request::{reference::MaybeReference, RequestProperty},
+    types::{blob::BlobId, id::Id},
+};
+
+#[derive(Debug, Clone)]
+pub struct BlobUploadRequest {
+    pub account_id: Id,
+    pub create: VecMap<String, UploadObject>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadObject {
+    pub type_: Option<String>,
+    pub data: Vec<DataSourceObject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSourceObject {
+    Id {
+        id: MaybeReference<BlobId, String>,
+        length: Option<usize>,
+        offset: Option<usize>,
+    },
+    Value(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobUploadResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "created")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub created: VecMap<String, BlobUploadResponseObject>,
+
+    #[serde(rename = "notCreated")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_created: VecMap<String, SetError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobUploadResponseObject {
+    #[serde(rename = "id")]
+    pub id: BlobId,
+
+    #[serde(rename = "type")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    #[serde(rename = "size")]
+    pub size: usize,
+}
+
+impl JsonObjectParser for BlobUploadRequest {
+    fn parse(parser: &mut Parser) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = BlobUploadRequest {
+            account_id: Id::default(),
+            create: VecMap::new(),
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x6574_6165_7263 => {
+                    request.create = <VecMap<String, UploadObject>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl JsonObjectParser for UploadObject {
+    fn parse(parser: &mut Parser) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = UploadObject::default();
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x6570_7974 => {
+                    request.type_ = parser
+                        .next_token::<String>()?
+                        .unwrap_string_or_null("type")?;
+                }
+                0x6174_6164 => {
+                    parser.next_token::<Ignore>()?.assert(Token::ArrayStart)?;
+                    loop {
+                        match parser.next_token::<Ignore>()? {
+                            Token::DictStart => {
+                                request.data.push(DataSourceObject::parse(parser)?);
+                            }
+                            Token::Comma => (),
+                            Token::ArrayEnd => break,
+                            token => return Err(token.error("data", "DataSourceObject")),
+                        }
+                    }
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl JsonObjectParser for DataSourceObject {
+    fn parse(parser: &mut Parser) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut data = None;
+        let mut id = None;
+        let mut length = None;
+        let mut offset = None;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0074_7865_5473_613a_6174_6164 => {
+                    data = parser
+                        .next_token::<String>()?
+                        .unwrap_string("data:asText")?
+                        .into_bytes()
+                        .into();
+                }
+                0x0034_3665_7361_4273_613a_6174_6164 => {
+                    data = base64_decode(
+                        parser
+                            .next_token::<String>()?
+                            .unwrap_string("data:asBase64")?
+                            .as_bytes(),
+                    )
+                    .ok_or_else(|| {
+                        Error::Method(MethodError::InvalidArguments(
+                            "Failed to decode data:asBase64.".to_string(),
+                        ))
+                    })?
+                    .into();
+                }
+                0x6449_626f_6c62 => {
+                    id = parser
+                        .next_token::<MaybeReference<BlobId, String>>()?
+                        .unwrap_string("blobId")?
+                        .into();
+                }
+                0x6874_676e_656c => {
+                    length = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("length")?;
+                }
+                0x7465_7366_666f => {
+                    offset = parser
+                        .next_token::<Ignore>()?
+                        .unwrap_usize_or_null("offset")?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        match (data, id) {
+            (Some(data), None) => Ok(DataSourceObject::Value(data)),
+            (None, Some(id)) => Ok(DataSourceObject::Id { id, length, offset }),
+            _ => Err(Error::Method(MethodError::InvalidArguments(
+                "Expected either data or blobId in DataSourceObject.".to_string(),
+            ))),
+        }
+    }
+}
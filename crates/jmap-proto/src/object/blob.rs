@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::parser::{json::Parser, JsonObjectParser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlobProperty {
+    Id,
+    Data,
+    DataAsText,
+    DataAsBase64,
+    DigestSha,
+    DigestSha256,
+    Size,
+    IsEncodingProblem,
+    IsTruncated,
+}
+
+impl JsonObjectParser for BlobProperty {
+    fn parse(parser: &mut Parser<'_>) -> crate::parser::Result<Self>
+    where
+        Self: Sized,
+    {
+        match u128::parse(parser)? {
+            0x6469 => Ok(BlobProperty::Id),
+            0x6174_6164 => Ok(BlobProperty::Data),
+            0x0074_7865_5473_613a_6174_6164 => Ok(BlobProperty::DataAsText),
+            0x0034_3665_7361_4273_613a_6174_6164 => Ok(BlobProperty::DataAsBase64),
+            0x6168_733a_7473_6567_6964 => Ok(BlobProperty::DigestSha),
+            0x3635_322d_6168_733a_7473_6567_6964 => Ok(BlobProperty::DigestSha256),
+            0x657a_6973 => Ok(BlobProperty::Size),
+            _ => Err(parser.error_value()),
+        }
+    }
+}
+
+impl BlobProperty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlobProperty::Id => "id",
+            BlobProperty::Data => "data",
+            BlobProperty::DataAsText => "data:asText",
+            BlobProperty::DataAsBase64 => "data:asBase64",
+            BlobProperty::DigestSha => "digest:sha",
+            BlobProperty::DigestSha256 => "digest:sha-256",
+            BlobProperty::Size => "size",
+            BlobProperty::IsEncodingProblem => "isEncodingProblem",
+            BlobProperty::IsTruncated => "isTruncated",
+        }
+    }
+}
+
+impl Display for BlobProperty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for BlobProperty {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
@@ -21,10 +21,13 @@
  * for more details.
 */
 
+pub mod blob;
+pub mod calendar;
 pub mod email;
 pub mod email_submission;
 pub mod index;
 pub mod mailbox;
+pub mod mdn;
 pub mod sieve;
 
 use std::slice::Iter;
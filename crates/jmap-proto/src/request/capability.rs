@@ -44,6 +44,16 @@ pub enum Capability {
     WebSocket = 1 << 6,
     #[serde(rename(serialize = "urn:ietf:params:jmap:sieve"))]
     Sieve = 1 << 7,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:mdn"))]
+    Mdn = 1 << 8,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:blob"))]
+    Blob = 1 << 9,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:smimeverify"))]
+    SmimeVerify = 1 << 10,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:principals"))]
+    Principals = 1 << 11,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:webpush-vapid"))]
+    WebPushVapid = 1 << 12,
 }
 
 impl JsonObjectParser for Capability {
@@ -71,6 +81,11 @@ impl JsonObjectParser for Capability {
                 0x0073_7261_646e_656c_6163 => Ok(Capability::Calendars),
                 0x0074_656b_636f_7362_6577 => Ok(Capability::WebSocket),
                 0x0065_7665_6973 => Ok(Capability::Sieve),
+                0x006e_646d => Ok(Capability::Mdn),
+                0x626f_6c62 => Ok(Capability::Blob),
+                0x0079_6669_7265_7665_6d69_6d73 => Ok(Capability::SmimeVerify),
+                0x736c_6170_6963_6e69_7270 => Ok(Capability::Principals),
+                0x0064_6970_6176_2d68_7375_7062_6577 => Ok(Capability::WebPushVapid),
                 _ => Err(parser.error_capability()),
             },
             Err(Error::Method(_)) => Err(parser.error_capability()),
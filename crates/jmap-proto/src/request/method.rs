@@ -45,6 +45,10 @@ pub enum MethodObject {
     VacationResponse,
     SieveScript,
     Principal,
+    Mdn,
+    Calendar,
+    CalendarEvent,
+    ShareNotification,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +62,10 @@ pub enum MethodFunction {
     Import,
     Parse,
     Validate,
+    Send,
+    Upload,
+    Lookup,
+    GetAvailability,
     Echo,
 }
 
@@ -68,6 +76,7 @@ impl JsonObjectParser for MethodName {
     {
         let mut shift = 0;
         let mut obj_hash: u128 = 0;
+        let mut obj_hash_ext: u128 = 0;
         let mut fnc_hash: u128 = 0;
 
         loop {
@@ -78,6 +87,9 @@ impl JsonObjectParser for MethodName {
                 if shift < 128 {
                     obj_hash |= (ch as u128) << shift;
                     shift += 8;
+                } else if shift < 256 {
+                    obj_hash_ext |= (ch as u128) << (shift - 128);
+                    shift += 8;
                 } else {
                     return Err(parser.error_value());
                 }
@@ -98,6 +110,10 @@ impl JsonObjectParser for MethodName {
 
         Ok(MethodName {
             obj: match obj_hash {
+                0x6f69_7461_6369_6669_746f_4e65_7261_6853 if obj_hash_ext == 0x006e => {
+                    MethodObject::ShareNotification
+                }
+                _ if obj_hash_ext != 0 => return Err(parser.error_value()),
                 0x006c_6961_6d45 => MethodObject::Email,
                 0x0078_6f62_6c69_614d => MethodObject::Mailbox,
                 0x6461_6572_6854 => MethodObject::Thread,
@@ -109,6 +125,9 @@ impl JsonObjectParser for MethodName {
                 0x6e6f_6974_7069_7263_7362_7553_6873_7550 => MethodObject::PushSubscription,
                 0x0074_7069_7263_5365_7665_6953 => MethodObject::SieveScript,
                 0x006c_6170_6963_6e69_7250 => MethodObject::Principal,
+                0x004e_444d => MethodObject::Mdn,
+                0x7261_646e_656c_6143 => MethodObject::Calendar,
+                0x0074_6e65_7645_7261_646e_656c_6143 => MethodObject::CalendarEvent,
                 0x6572_6f43 => MethodObject::Core,
                 _ => return Err(parser.error_value()),
             },
@@ -122,6 +141,10 @@ impl JsonObjectParser for MethodName {
                 0x7472_6f70_6d69 => MethodFunction::Import,
                 0x0065_7372_6170 => MethodFunction::Parse,
                 0x6574_6164_696c_6176 => MethodFunction::Validate,
+                0x646e_6573 => MethodFunction::Send,
+                0x6461_6f6c_7075 => MethodFunction::Upload,
+                0x7075_6b6f_6f6c => MethodFunction::Lookup,
+                0x0079_7469_6c69_6261_6c69_6176_4174_6567 => MethodFunction::GetAvailability,
                 0x6f68_6365 => MethodFunction::Echo,
                 _ => return Err(parser.error_value()),
             },
@@ -151,6 +174,9 @@ impl MethodName {
         match (self.fnc, self.obj) {
             (MethodFunction::Echo, MethodObject::Core) => "Core/echo",
             (MethodFunction::Copy, MethodObject::Blob) => "Blob/copy",
+            (MethodFunction::Upload, MethodObject::Blob) => "Blob/upload",
+            (MethodFunction::Get, MethodObject::Blob) => "Blob/get",
+            (MethodFunction::Lookup, MethodObject::Blob) => "Blob/lookup",
             (MethodFunction::Get, MethodObject::PushSubscription) => "PushSubscription/get",
             (MethodFunction::Set, MethodObject::PushSubscription) => "PushSubscription/set",
             (MethodFunction::Get, MethodObject::Mailbox) => "Mailbox/get",
@@ -186,8 +212,33 @@ impl MethodName {
             (MethodFunction::Query, MethodObject::SieveScript) => "SieveScript/query",
             (MethodFunction::Validate, MethodObject::SieveScript) => "SieveScript/validate",
             (MethodFunction::Get, MethodObject::Principal) => "Principal/get",
+            (MethodFunction::Changes, MethodObject::Principal) => "Principal/changes",
             (MethodFunction::Set, MethodObject::Principal) => "Principal/set",
             (MethodFunction::Query, MethodObject::Principal) => "Principal/query",
+            (MethodFunction::GetAvailability, MethodObject::Principal) => {
+                "Principal/getAvailability"
+            }
+            (MethodFunction::Send, MethodObject::Mdn) => "MDN/send",
+            (MethodFunction::Parse, MethodObject::Mdn) => "MDN/parse",
+            (MethodFunction::Get, MethodObject::Calendar) => "Calendar/get",
+            (MethodFunction::Changes, MethodObject::Calendar) => "Calendar/changes",
+            (MethodFunction::Set, MethodObject::Calendar) => "Calendar/set",
+            (MethodFunction::Get, MethodObject::CalendarEvent) => "CalendarEvent/get",
+            (MethodFunction::Changes, MethodObject::CalendarEvent) => "CalendarEvent/changes",
+            (MethodFunction::Query, MethodObject::CalendarEvent) => "CalendarEvent/query",
+            (MethodFunction::QueryChanges, MethodObject::CalendarEvent) => {
+                "CalendarEvent/queryChanges"
+            }
+            (MethodFunction::Set, MethodObject::CalendarEvent) => "CalendarEvent/set",
+            (MethodFunction::Get, MethodObject::ShareNotification) => "ShareNotification/get",
+            (MethodFunction::Changes, MethodObject::ShareNotification) => {
+                "ShareNotification/changes"
+            }
+            (MethodFunction::Query, MethodObject::ShareNotification) => "ShareNotification/query",
+            (MethodFunction::QueryChanges, MethodObject::ShareNotification) => {
+                "ShareNotification/queryChanges"
+            }
+            (MethodFunction::Set, MethodObject::ShareNotification) => "ShareNotification/set",
             _ => "error",
         }
     }
@@ -204,6 +255,10 @@ impl Display for MethodObject {
             MethodObject::PushSubscription => "PushSubscription",
             MethodObject::SieveScript => "SieveScript",
             MethodObject::Principal => "Principal",
+            MethodObject::Mdn => "MDN",
+            MethodObject::Calendar => "Calendar",
+            MethodObject::CalendarEvent => "CalendarEvent",
+            MethodObject::ShareNotification => "ShareNotification",
             MethodObject::Core => "Core",
             MethodObject::Mailbox => "Mailbox",
             MethodObject::Thread => "Thread",
@@ -36,15 +36,19 @@ use std::{
 use crate::{
     error::method::MethodError,
     method::{
+        availability::GetAvailabilityRequest,
         changes::ChangesRequest,
         copy::{self, CopyBlobRequest, CopyRequest},
-        get::{self, GetRequest},
+        get::{self, BlobGetRequest, GetRequest},
         import::ImportEmailRequest,
+        lookup::BlobLookupRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
         parse::ParseEmailRequest,
         query::{self, QueryRequest},
         query_changes::QueryChangesRequest,
         search_snippet::GetSearchSnippetRequest,
         set::{self, SetRequest},
+        upload::BlobUploadRequest,
         validate::ValidateSieveScriptRequest,
     },
     parser::{json::Parser, JsonObjectParser},
@@ -80,12 +84,18 @@ pub enum RequestMethod {
     Changes(ChangesRequest),
     Copy(CopyRequest<copy::RequestArguments>),
     CopyBlob(CopyBlobRequest),
+    UploadBlob(BlobUploadRequest),
+    GetBlob(BlobGetRequest),
+    LookupBlob(BlobLookupRequest),
     ImportEmail(ImportEmailRequest),
     ParseEmail(ParseEmailRequest),
     QueryChanges(QueryChangesRequest),
     Query(QueryRequest<query::RequestArguments>),
     SearchSnippet(GetSearchSnippetRequest),
     ValidateScript(ValidateSieveScriptRequest),
+    SendMdn(MdnSendRequest),
+    ParseMdn(MdnParseRequest),
+    GetAvailability(GetAvailabilityRequest),
     Echo(Echo),
     Error(MethodError),
 }
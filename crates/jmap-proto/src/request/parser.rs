@@ -29,15 +29,19 @@ use crate::{
         request::{RequestError, RequestLimitError},
     },
     method::{
+        availability::GetAvailabilityRequest,
         changes::ChangesRequest,
         copy::{CopyBlobRequest, CopyRequest},
-        get::GetRequest,
+        get::{BlobGetRequest, GetRequest},
         import::ImportEmailRequest,
+        lookup::BlobLookupRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
         parse::ParseEmailRequest,
         query::QueryRequest,
         query_changes::QueryChangesRequest,
         search_snippet::GetSearchSnippetRequest,
         set::SetRequest,
+        upload::BlobUploadRequest,
         validate::ValidateSieveScriptRequest,
     },
     parser::{json::Parser, Error, Ignore, JsonObjectParser, Token},
@@ -129,14 +133,16 @@ impl Request {
                         let start_depth_dict = parser.depth_dict;
 
                         let method = match (&method_name.fnc, &method_name.obj) {
-                            (MethodFunction::Get, _) => {
-                                if method_name.obj != MethodObject::SearchSnippet {
-                                    GetRequest::parse(parser).map(RequestMethod::Get)
-                                } else {
+                            (MethodFunction::Get, _) => match method_name.obj {
+                                MethodObject::SearchSnippet => {
                                     GetSearchSnippetRequest::parse(parser)
                                         .map(RequestMethod::SearchSnippet)
                                 }
-                            }
+                                MethodObject::Blob => {
+                                    BlobGetRequest::parse(parser).map(RequestMethod::GetBlob)
+                                }
+                                _ => GetRequest::parse(parser).map(RequestMethod::Get),
+                            },
                             (MethodFunction::Query, _) => {
                                 QueryRequest::parse(parser).map(RequestMethod::Query)
                             }
@@ -155,6 +161,12 @@ impl Request {
                             (MethodFunction::Copy, MethodObject::Blob) => {
                                 CopyBlobRequest::parse(parser).map(RequestMethod::CopyBlob)
                             }
+                            (MethodFunction::Upload, MethodObject::Blob) => {
+                                BlobUploadRequest::parse(parser).map(RequestMethod::UploadBlob)
+                            }
+                            (MethodFunction::Lookup, MethodObject::Blob) => {
+                                BlobLookupRequest::parse(parser).map(RequestMethod::LookupBlob)
+                            }
                             (MethodFunction::Import, MethodObject::Email) => {
                                 ImportEmailRequest::parse(parser).map(RequestMethod::ImportEmail)
                             }
@@ -165,6 +177,16 @@ impl Request {
                                 ValidateSieveScriptRequest::parse(parser)
                                     .map(RequestMethod::ValidateScript)
                             }
+                            (MethodFunction::Send, MethodObject::Mdn) => {
+                                MdnSendRequest::parse(parser).map(RequestMethod::SendMdn)
+                            }
+                            (MethodFunction::Parse, MethodObject::Mdn) => {
+                                MdnParseRequest::parse(parser).map(RequestMethod::ParseMdn)
+                            }
+                            (MethodFunction::GetAvailability, MethodObject::Principal) => {
+                                GetAvailabilityRequest::parse(parser)
+                                    .map(RequestMethod::GetAvailability)
+                            }
                             (MethodFunction::Echo, MethodObject::Core) => {
                                 Echo::parse(parser).map(RequestMethod::Echo)
                             }
@@ -229,7 +251,21 @@ impl From<Error> for RequestError {
 
 #[cfg(test)]
 mod tests {
-    use crate::request::Request;
+    use crate::{
+        method::{
+            query::{self, Comparator, Filter, SortProperty},
+            set,
+            upload::DataSourceObject,
+        },
+        object::{blob::BlobProperty, mdn::Disposition, Object},
+        request::{method::MethodObject, reference::MaybeReference, Request, RequestMethod},
+        types::{
+            date::UTCDate,
+            id::Id,
+            property::Property,
+            value::{SetValue, Value},
+        },
+    };
 
     const TEST: &str = r#"
     {
@@ -344,9 +380,683 @@ mod tests {
       }
     "##;
 
+    const TEST3: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:mail", "urn:ietf:params:jmap:mdn" ],
+        "methodCalls": [
+          [ "MDN/send", {
+            "accountId": "c",
+            "identityId": "a",
+            "send": {
+              "k1546": {
+                "forEmailId": "b",
+                "subject": "Read receipt for: World domination",
+                "textBody": "This receipt shows that the email has been displayed.",
+                "reportingUA": "joes-pc.cs.example.com; Foomail 97.1",
+                "disposition": {
+                  "actionMode": "manual-action",
+                  "sendingMode": "mdn-sent-manually",
+                  "type": "displayed"
+                },
+                "extensionFields": {
+                  "X-EXTENSION-EXAMPLE": "example.com"
+                }
+              }
+            },
+            "onSuccessUpdateEmail": {
+              "#k1546": {
+                "keywords/$mdnsent": true
+              }
+            }
+          }, "0" ],
+          [ "MDN/parse", {
+            "accountId": "c",
+            "blobIds": []
+          }, "1" ]
+        ]
+      }
+    "##;
+
+    const TEST4: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:blob" ],
+        "methodCalls": [
+          [ "Blob/upload", {
+            "accountId": "c",
+            "create": {
+              "b1": {
+                "data": [
+                  { "data:asText": "The quick brown fox" },
+                  { "data:asBase64": "IGp1bXBzIG92ZXI=" }
+                ],
+                "type": "text/plain"
+              },
+              "b2": {
+                "data": [ { "blobId": "#b1", "offset": 4, "length": 5 } ]
+              }
+            }
+          }, "0" ],
+          [ "Blob/get", {
+            "accountId": "c",
+            "ids": [],
+            "properties": [ "data:asText", "digest:sha", "digest:sha-256", "size" ],
+            "offset": 4,
+            "length": 9
+          }, "1" ],
+          [ "Blob/lookup", {
+            "accountId": "c",
+            "typeNames": [ "Mailbox", "Thread", "Email" ],
+            "ids": []
+          }, "2" ]
+        ]
+      }
+    "##;
+
+    const TEST5: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:mail", "urn:ietf:params:jmap:smimeverify" ],
+        "methodCalls": [
+          [ "Email/query", {
+            "accountId": "c",
+            "filter": {
+              "operator": "AND",
+              "conditions": [
+                { "hasSmime": true },
+                { "hasVerifiedSmime": false },
+                { "hasVerifiedSmimeAtDelivery": true }
+              ]
+            }
+          }, "0" ],
+          [ "Email/get", {
+            "accountId": "c",
+            "#ids": {
+              "resultOf": "0",
+              "name": "Email/query",
+              "path": "/ids"
+            },
+            "properties": [ "smimeStatus", "smimeErrors", "smimeVerifiedAt",
+                            "smimeStatusAtDelivery" ]
+          }, "1" ]
+        ]
+      }
+    "##;
+
+    const TEST6: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:calendars" ],
+        "methodCalls": [
+          [ "CalendarEvent/set", {
+            "accountId": "c",
+            "create": {
+              "e1": {
+                "@type": "Event",
+                "calendarIds": { "b": true },
+                "uid": "a8df6573-0474-496d-8496-033ad45d7fea",
+                "title": "Team sync",
+                "start": "2023-06-01T10:00:00",
+                "timeZone": "Europe/Berlin",
+                "duration": "PT1H",
+                "recurrenceRules": [ {
+                  "@type": "RecurrenceRule",
+                  "frequency": "weekly",
+                  "byDay": [ { "@type": "NDay", "day": "th" } ],
+                  "count": 10
+                } ],
+                "recurrenceOverrides": {
+                  "2023-06-08T10:00:00": { "excluded": true }
+                },
+                "participants": {
+                  "p1": {
+                    "@type": "Participant",
+                    "email": "jane@example.com",
+                    "sendTo": { "imip": "mailto:jane@example.com" },
+                    "roles": { "owner": true, "attendee": true },
+                    "participationStatus": "accepted"
+                  }
+                }
+              }
+            },
+            "sendSchedulingMessages": true
+          }, "0" ],
+          [ "CalendarEvent/query", {
+            "accountId": "c",
+            "filter": {
+              "operator": "AND",
+              "conditions": [
+                { "inCalendars": [ "b" ] },
+                { "after": "2023-06-01T00:00:00Z" },
+                { "before": "2023-07-01T00:00:00Z" },
+                { "title": "sync" }
+              ]
+            },
+            "sort": [ { "property": "start" } ],
+            "expandRecurrences": true,
+            "timeZone": "Europe/Berlin"
+          }, "1" ],
+          [ "Calendar/set", {
+            "accountId": "c",
+            "destroy": [ "b" ],
+            "onDestroyRemoveEvents": true
+          }, "2" ]
+        ]
+      }
+    "##;
+
+    const TEST7: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:principals" ],
+        "methodCalls": [
+          [ "ShareNotification/query", {
+            "accountId": "a",
+            "filter": {
+              "operator": "AND",
+              "conditions": [
+                { "objectType": "Mailbox" },
+                { "after": "2023-06-01T00:00:00Z" }
+              ]
+            },
+            "sort": [ { "property": "created", "isAscending": false } ]
+          }, "0" ],
+          [ "ShareNotification/set", {
+            "accountId": "a",
+            "destroy": [ "b" ]
+          }, "1" ],
+          [ "Principal/set", {
+            "accountId": "a",
+            "update": {
+              "c": {
+                "description": "Meeting room",
+                "timeZone": "Europe/Berlin"
+              }
+            }
+          }, "2" ],
+          [ "Principal/getAvailability", {
+            "accountId": "a",
+            "id": "c",
+            "utcStart": "2023-06-01T00:00:00Z",
+            "utcEnd": "2023-06-08T00:00:00Z",
+            "showDetails": true,
+            "eventProperties": [ "title", "start" ]
+          }, "3" ]
+        ]
+      }
+    "##;
+
+    const TEST8: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail" ],
+        "methodCalls": [
+          [ "Mailbox/set", {
+            "accountId": "a",
+            "create": {
+              "s1": {
+                "name": "Unread from Joe",
+                "filter": {
+                  "operator": "AND",
+                  "conditions": [
+                    { "from": "joe@example.com" },
+                    { "notKeyword": "$seen" }
+                  ]
+                }
+              }
+            }
+          }, "0" ],
+          [ "Email/query", {
+            "accountId": "a",
+            "filter": { "inMailbox": "b" }
+          }, "1" ]
+        ]
+      }
+    "##;
+
+    const TEST9: &str = r##"
+    {
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail" ],
+        "methodCalls": [
+          [ "Email/set", {
+            "accountId": "a",
+            "update": {
+              "b": {
+                "snoozed": {
+                  "until": "2023-06-01T09:00:00Z",
+                  "moveToMailboxId": "c"
+                }
+              },
+              "d": {
+                "snoozed": null
+              }
+            }
+          }, "0" ]
+        ]
+      }
+    "##;
+
     #[test]
     fn parse_request() {
         println!("{:?}", Request::parse(TEST.as_bytes(), 10, 10240));
         println!("{:?}", Request::parse(TEST2.as_bytes(), 10, 10240));
     }
+
+    #[test]
+    fn parse_mdn_request() {
+        let request = Request::parse(TEST3.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 2);
+
+        let send = match &request.method_calls[0].method {
+            RequestMethod::SendMdn(send) => send,
+            method => panic!("Unexpected method {method:?}"),
+        };
+        assert_eq!(send.account_id.to_string(), "c");
+        assert_eq!(send.identity_id.to_string(), "a");
+        let mdn = send.send.get("k1546").unwrap();
+        assert_eq!(mdn.for_email_id.unwrap().to_string(), "b");
+        assert_eq!(
+            mdn.subject.as_deref(),
+            Some("Read receipt for: World domination")
+        );
+        assert_eq!(
+            mdn.text_body.as_deref(),
+            Some("This receipt shows that the email has been displayed.")
+        );
+        assert_eq!(
+            mdn.reporting_ua.as_deref(),
+            Some("joes-pc.cs.example.com; Foomail 97.1")
+        );
+        assert_eq!(
+            mdn.disposition,
+            Some(Disposition {
+                action_mode: "manual-action".to_string(),
+                sending_mode: "mdn-sent-manually".to_string(),
+                type_: "displayed".to_string(),
+            })
+        );
+        assert_eq!(
+            mdn.extension_fields
+                .as_ref()
+                .and_then(|fields| fields.get("X-EXTENSION-EXAMPLE"))
+                .map(|value| value.as_str()),
+            Some("example.com")
+        );
+        assert!(!mdn.include_original_message);
+        let update = send.on_success_update_email.as_ref().unwrap();
+        assert_eq!(
+            update.keys().collect::<Vec<_>>(),
+            [&MaybeReference::Reference("k1546".to_string())]
+        );
+
+        match &request.method_calls[1].method {
+            RequestMethod::ParseMdn(parse) => {
+                assert_eq!(parse.account_id.to_string(), "c");
+                assert!(parse.blob_ids.is_empty());
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_blob_request() {
+        let request = Request::parse(TEST4.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 3);
+
+        let upload = match &request.method_calls[0].method {
+            RequestMethod::UploadBlob(upload) => upload,
+            method => panic!("Unexpected method {method:?}"),
+        };
+        let b1 = upload.create.get("b1").unwrap();
+        assert_eq!(b1.type_.as_deref(), Some("text/plain"));
+        assert_eq!(
+            b1.data,
+            vec![
+                DataSourceObject::Value(b"The quick brown fox".to_vec()),
+                DataSourceObject::Value(b" jumps over".to_vec()),
+            ]
+        );
+        assert_eq!(
+            upload.create.get("b2").unwrap().data,
+            vec![DataSourceObject::Id {
+                id: MaybeReference::Reference("b1".to_string()),
+                length: Some(5),
+                offset: Some(4),
+            }]
+        );
+
+        match &request.method_calls[1].method {
+            RequestMethod::GetBlob(get) => {
+                assert_eq!(get.ids.as_deref(), Some(&[][..]));
+                assert_eq!(
+                    get.properties.as_deref(),
+                    Some(
+                        &[
+                            BlobProperty::DataAsText,
+                            BlobProperty::DigestSha,
+                            BlobProperty::DigestSha256,
+                            BlobProperty::Size
+                        ][..]
+                    )
+                );
+                assert_eq!((get.offset, get.length), (Some(4), Some(9)));
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[2].method {
+            RequestMethod::LookupBlob(lookup) => {
+                assert_eq!(
+                    lookup.type_names,
+                    vec![
+                        MethodObject::Mailbox,
+                        MethodObject::Thread,
+                        MethodObject::Email
+                    ]
+                );
+                assert!(lookup.ids.is_empty());
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_smime_request() {
+        let request = Request::parse(TEST5.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 2);
+
+        match &request.method_calls[0].method {
+            RequestMethod::Query(query) => {
+                assert!(
+                    matches!(
+                        query.filter.as_slice(),
+                        [
+                            Filter::And,
+                            Filter::HasSmime(true),
+                            Filter::HasVerifiedSmime(false),
+                            Filter::HasVerifiedSmimeAtDelivery(true),
+                            Filter::Close
+                        ]
+                    ),
+                    "{:?}",
+                    query.filter
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[1].method {
+            RequestMethod::Get(get) => {
+                match &get.ids {
+                    Some(MaybeReference::Reference(reference)) => {
+                        assert_eq!(reference.result_of, "0");
+                        assert_eq!(reference.name.to_string(), "Email/query");
+                    }
+                    ids => panic!("Unexpected ids {ids:?}"),
+                }
+                assert_eq!(
+                    get.properties,
+                    Some(MaybeReference::Value(vec![
+                        Property::SmimeStatus,
+                        Property::SmimeErrors,
+                        Property::SmimeVerifiedAt,
+                        Property::SmimeStatusAtDelivery
+                    ]))
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_calendar_request() {
+        let request = Request::parse(TEST6.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 3);
+        let calendar_id = Id::from_bytes(b"b").unwrap();
+
+        match &request.method_calls[0].method {
+            RequestMethod::Set(set) => {
+                let event = set.create.as_ref().unwrap().get("e1").unwrap();
+                assert_eq!(
+                    event.properties.get(&Property::Title),
+                    Some(&SetValue::Value(Value::Text("Team sync".to_string())))
+                );
+                assert_eq!(
+                    event.properties.get(&Property::Uid),
+                    Some(&SetValue::Value(Value::Text(
+                        "a8df6573-0474-496d-8496-033ad45d7fea".to_string()
+                    )))
+                );
+                assert_eq!(
+                    event.properties.get(&Property::CalendarIds),
+                    Some(&SetValue::IdReferences(vec![MaybeReference::Value(
+                        calendar_id
+                    )]))
+                );
+                match &set.arguments {
+                    set::RequestArguments::CalendarEvent(arguments) => {
+                        assert_eq!(arguments.send_scheduling_messages, Some(true));
+                    }
+                    arguments => panic!("Unexpected arguments {arguments:?}"),
+                }
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[1].method {
+            RequestMethod::Query(query) => {
+                assert!(
+                    matches!(
+                        query.filter.as_slice(),
+                        [
+                            Filter::And,
+                            Filter::InCalendars(ids),
+                            Filter::After(_),
+                            Filter::Before(_),
+                            Filter::Title(title),
+                            Filter::Close
+                        ] if ids == &[calendar_id] && title == "sync"
+                    ),
+                    "{:?}",
+                    query.filter
+                );
+                assert_eq!(query.sort.as_ref().map(|sort| sort.len()), Some(1));
+                match &query.arguments {
+                    query::RequestArguments::CalendarEvent(arguments) => {
+                        assert_eq!(arguments.expand_recurrences, Some(true));
+                        assert_eq!(arguments.time_zone.as_deref(), Some("Europe/Berlin"));
+                    }
+                    arguments => panic!("Unexpected arguments {arguments:?}"),
+                }
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[2].method {
+            RequestMethod::Set(set) => {
+                assert_eq!(set.destroy, Some(MaybeReference::Value(vec![calendar_id])));
+                match &set.arguments {
+                    set::RequestArguments::Calendar(arguments) => {
+                        assert_eq!(arguments.on_destroy_remove_events, Some(true));
+                    }
+                    arguments => panic!("Unexpected arguments {arguments:?}"),
+                }
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_share_notification_request() {
+        let request = Request::parse(TEST7.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 4);
+
+        match &request.method_calls[0].method {
+            RequestMethod::Query(query) => {
+                assert!(
+                    matches!(
+                        query.filter.as_slice(),
+                        [
+                            Filter::And,
+                            Filter::ObjectType(object_type),
+                            Filter::After(after),
+                            Filter::Close
+                        ] if object_type == "Mailbox" && after.timestamp() == 1685577600
+                    ),
+                    "{:?}",
+                    query.filter
+                );
+                assert_eq!(
+                    query.sort,
+                    Some(vec![Comparator {
+                        is_ascending: false,
+                        collation: None,
+                        property: SortProperty::Created,
+                        keyword: None,
+                    }])
+                );
+                assert!(matches!(
+                    query.arguments,
+                    query::RequestArguments::ShareNotification
+                ));
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[1].method {
+            RequestMethod::Set(set) => {
+                assert_eq!(
+                    set.destroy,
+                    Some(MaybeReference::Value(vec![Id::from_bytes(b"b").unwrap()]))
+                );
+                assert!(matches!(
+                    set.arguments,
+                    set::RequestArguments::ShareNotification
+                ));
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[2].method {
+            RequestMethod::Set(set) => {
+                let principal = set
+                    .update
+                    .as_ref()
+                    .unwrap()
+                    .get(&Id::from_bytes(b"c").unwrap())
+                    .unwrap();
+                assert_eq!(
+                    principal.properties.get(&Property::Description),
+                    Some(&SetValue::Value(Value::Text("Meeting room".to_string())))
+                );
+                assert_eq!(
+                    principal.properties.get(&Property::TimeZone),
+                    Some(&SetValue::Value(Value::Text("Europe/Berlin".to_string())))
+                );
+                assert!(matches!(set.arguments, set::RequestArguments::Principal));
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[3].method {
+            RequestMethod::GetAvailability(availability) => {
+                assert_eq!(availability.id, Id::from_bytes(b"c").unwrap());
+                assert_eq!(
+                    (
+                        availability.utc_start.timestamp(),
+                        availability.utc_end.timestamp()
+                    ),
+                    (1685577600, 1686182400)
+                );
+                assert!(availability.show_details);
+                assert_eq!(
+                    availability.event_properties,
+                    Some(vec![Property::Title, Property::Start])
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_saved_search_request() {
+        let request = Request::parse(TEST8.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 2);
+
+        match &request.method_calls[0].method {
+            RequestMethod::Set(set) => {
+                let mailbox = set.create.as_ref().unwrap().get("s1").unwrap();
+                assert_eq!(
+                    mailbox.properties.get(&Property::Name),
+                    Some(&SetValue::Value(Value::Text("Unread from Joe".to_string())))
+                );
+                assert_eq!(
+                    mailbox.properties.get(&Property::Filter),
+                    Some(&SetValue::Value(Value::Object(
+                        Object::with_capacity(2)
+                            .with_property(Property::_T("operator".to_string()), "AND")
+                            .with_property(
+                                Property::_T("conditions".to_string()),
+                                vec![
+                                    Object::with_capacity(1).with_property(
+                                        Property::_T("from".to_string()),
+                                        "joe@example.com"
+                                    ),
+                                    Object::with_capacity(1).with_property(
+                                        Property::_T("notKeyword".to_string()),
+                                        "$seen"
+                                    ),
+                                ]
+                            )
+                    )))
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+
+        match &request.method_calls[1].method {
+            RequestMethod::Query(query) => {
+                assert!(
+                    matches!(
+                        query.filter.as_slice(),
+                        [Filter::InMailbox(id)] if id == &Id::from_bytes(b"b").unwrap()
+                    ),
+                    "{:?}",
+                    query.filter
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_snooze_request() {
+        let request = Request::parse(TEST9.as_bytes(), 10, 10240).unwrap();
+        assert_eq!(request.method_calls.len(), 1);
+
+        match &request.method_calls[0].method {
+            RequestMethod::Set(set) => {
+                let update = set.update.as_ref().unwrap();
+                assert_eq!(
+                    update
+                        .get(&Id::from_bytes(b"b").unwrap())
+                        .unwrap()
+                        .properties
+                        .get(&Property::Snoozed),
+                    Some(&SetValue::Value(Value::Object(
+                        Object::with_capacity(2)
+                            .with_property(Property::Until, UTCDate::from_timestamp(1685610000))
+                            .with_property(
+                                Property::MoveToMailboxId,
+                                Id::from_bytes(b"c").unwrap()
+                            )
+                    )))
+                );
+                assert_eq!(
+                    update
+                        .get(&Id::from_bytes(b"d").unwrap())
+                        .unwrap()
+                        .properties
+                        .get(&Property::Snoozed),
+                    Some(&SetValue::Value(Value::Null))
+                );
+            }
+            method => panic!("Unexpected method {method:?}"),
+        }
+    }
 }
@@ -29,15 +29,19 @@ use std::collections::HashMap;
 use crate::{
     error::method::MethodError,
     method::{
+        availability::GetAvailabilityResponse,
         changes::ChangesResponse,
         copy::{CopyBlobResponse, CopyResponse},
-        get::GetResponse,
+        get::{BlobGetResponse, GetResponse},
         import::ImportEmailResponse,
+        lookup::BlobLookupResponse,
+        mdn::{MdnParseResponse, MdnSendResponse},
         parse::ParseEmailResponse,
         query::QueryResponse,
         query_changes::QueryChangesResponse,
         search_snippet::GetSearchSnippetResponse,
         set::SetResponse,
+        upload::BlobUploadResponse,
         validate::ValidateSieveScriptResponse,
     },
     request::{echo::Echo, method::MethodName, Call},
@@ -54,12 +58,18 @@ pub enum ResponseMethod {
     Changes(ChangesResponse),
     Copy(CopyResponse),
     CopyBlob(CopyBlobResponse),
+    UploadBlob(BlobUploadResponse),
+    GetBlob(BlobGetResponse),
+    LookupBlob(BlobLookupResponse),
     ImportEmail(ImportEmailResponse),
     ParseEmail(ParseEmailResponse),
     QueryChanges(QueryChangesResponse),
     Query(QueryResponse),
     SearchSnippet(GetSearchSnippetResponse),
     ValidateScript(ValidateSieveScriptResponse),
+    SendMdn(MdnSendResponse),
+    ParseMdn(MdnParseResponse),
+    GetAvailability(GetAvailabilityResponse),
     Echo(Echo),
     Error(MethodError),
 }
@@ -191,6 +201,42 @@ impl From<ValidateSieveScriptResponse> for ResponseMethod {
     }
 }
 
+impl From<BlobUploadResponse> for ResponseMethod {
+    fn from(upload_blob: BlobUploadResponse) -> Self {
+        ResponseMethod::UploadBlob(upload_blob)
+    }
+}
+
+impl From<BlobGetResponse> for ResponseMethod {
+    fn from(get_blob: BlobGetResponse) -> Self {
+        ResponseMethod::GetBlob(get_blob)
+    }
+}
+
+impl From<BlobLookupResponse> for ResponseMethod {
+    fn from(lookup_blob: BlobLookupResponse) -> Self {
+        ResponseMethod::LookupBlob(lookup_blob)
+    }
+}
+
+impl From<GetAvailabilityResponse> for ResponseMethod {
+    fn from(get_availability: GetAvailabilityResponse) -> Self {
+        ResponseMethod::GetAvailability(get_availability)
+    }
+}
+
+impl From<MdnSendResponse> for ResponseMethod {
+    fn from(send_mdn: MdnSendResponse) -> Self {
+        ResponseMethod::SendMdn(send_mdn)
+    }
+}
+
+impl From<MdnParseResponse> for ResponseMethod {
+    fn from(parse_mdn: MdnParseResponse) -> Self {
+        ResponseMethod::ParseMdn(parse_mdn)
+    }
+}
+
 impl<T: Into<ResponseMethod>> From<Result<T, MethodError>> for ResponseMethod {
     fn from(result: Result<T, MethodError>) -> Self {
         match result {
@@ -38,7 +38,12 @@ pub enum Collection {
     SieveScript = 5,
     PushSubscription = 6,
     Principal = 7,
-    None = 8,
+    Calendar = 8,
+    CalendarEvent = 9,
+    ShareNotification = 10,
+    // Never persisted, only used as the upper bound of bitmaps so it has to
+    // remain the last variant when new ones are added.
+    None = 11,
 }
 
 impl From<u8> for Collection {
@@ -52,6 +57,9 @@ impl From<u8> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::Calendar,
+            9 => Collection::CalendarEvent,
+            10 => Collection::ShareNotification,
             _ => Collection::None,
         }
     }
@@ -68,6 +76,9 @@ impl From<u64> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::Calendar,
+            9 => Collection::CalendarEvent,
+            10 => Collection::ShareNotification,
             _ => Collection::None,
         }
     }
@@ -95,6 +106,9 @@ impl TryFrom<Collection> for TypeState {
             Collection::Thread => Ok(TypeState::Thread),
             Collection::Identity => Ok(TypeState::Identity),
             Collection::EmailSubmission => Ok(TypeState::EmailSubmission),
+            Collection::Calendar => Ok(TypeState::Calendar),
+            Collection::CalendarEvent => Ok(TypeState::CalendarEvent),
+            Collection::ShareNotification => Ok(TypeState::ShareNotification),
             _ => Err(()),
         }
     }
@@ -111,6 +125,9 @@ impl Display for Collection {
             Collection::EmailSubmission => write!(f, "emailSubmission"),
             Collection::SieveScript => write!(f, "sieveScript"),
             Collection::Principal => write!(f, "principal"),
+            Collection::Calendar => write!(f, "calendar"),
+            Collection::CalendarEvent => write!(f, "calendarEvent"),
+            Collection::ShareNotification => write!(f, "shareNotification"),
             Collection::None => write!(f, ""),
         }
     }
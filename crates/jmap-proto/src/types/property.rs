@@ -130,6 +130,58 @@ pub enum Property {
     MayCreateChild,
     MayRename,
     MaySubmit,
+    Metadata,
+    AccessKeys,
+    SaveDate,
+    SmimeStatus,
+    SmimeErrors,
+    SmimeVerifiedAt,
+    SmimeStatusAtDelivery,
+    CalendarIds,
+    Uid,
+    Title,
+    Start,
+    Duration,
+    TimeZone,
+    ShowWithoutTime,
+    RecurrenceRules,
+    RecurrenceOverrides,
+    RecurrenceId,
+    Status,
+    FreeBusyStatus,
+    Privacy,
+    Participants,
+    Locations,
+    VirtualLocations,
+    Alerts,
+    Sequence,
+    Method,
+    Created,
+    Updated,
+    ProdId,
+    Priority,
+    Color,
+    IsVisible,
+    IsDefault,
+    UtcStart,
+    UtcEnd,
+    MayReadFreeBusy,
+    MayWriteAll,
+    MayWriteOwn,
+    MayUpdatePrivate,
+    MayRsvp,
+    MayAdmin,
+    ChangedBy,
+    ObjectType,
+    ObjectAccountId,
+    ObjectId,
+    OldRights,
+    NewRights,
+    PrincipalId,
+    Filter,
+    Snoozed,
+    Until,
+    MoveToMailboxId,
     _T(String),
 }
 
@@ -238,17 +290,19 @@ impl JsonObjectParser for SetProperty {
 
         if is_patch {
             match &property {
-                Property::MailboxIds | Property::Members => match Id::parse(parser) {
-                    Ok(id) => {
-                        patch.push(Value::Id(id));
-                    }
-                    Err(Error::Method(_)) => {
-                        property = parser.invalid_property()?;
-                    }
-                    Err(err) => {
-                        return Err(err);
+                Property::MailboxIds | Property::CalendarIds | Property::Members => {
+                    match Id::parse(parser) {
+                        Ok(id) => {
+                            patch.push(Value::Id(id));
+                        }
+                        Err(Error::Method(_)) => {
+                            property = parser.invalid_property()?;
+                        }
+                        Err(err) => {
+                            return Err(err);
+                        }
                     }
-                },
+                }
                 Property::Keywords => match Keyword::parse(parser) {
                     Ok(keyword) => {
                         patch.push(Value::Keyword(keyword));
@@ -326,6 +380,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6c63 => Property::Acl,
             0x7365_7361_696c => Property::Aliases,
             0x7374_6e65_6d68_6361_7474 => Property::Attachments,
+            0x0073_7472_656c => Property::Alerts,
             _ => return None,
         },
         b'b' => match hash {
@@ -337,9 +392,13 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         },
         b'c' => match hash {
             0x0073_6569_7469_6c69_6261_7061 => Property::Capabilities,
+            0x7364_4972_6164_6e65_6c61 => Property::CalendarIds,
             0x63 => Property::Cc,
             0x7465_7372_6168 => Property::Charset,
             0x6469 => Property::Cid,
+            0x726f_6c6f => Property::Color,
+            0x6465_7461_6572 => Property::Created,
+            0x0079_4264_6567_6e61_6863 => Property::ChangedBy,
             _ => return None,
         },
         b'd' => match hash {
@@ -348,6 +407,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4974_6e65_696c_4365_6369_7665 => Property::DeviceClientId,
             0x6e6f_6974_6973_6f70_7369 => Property::Disposition,
             0x0073_6449_626f_6c42_6e73 => Property::DsnBlobIds,
+            0x006e_6f69_7461_7275 => Property::Duration,
             _ => return None,
         },
         b'e' => match hash {
@@ -361,6 +421,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         b'f' => match hash {
             0x006d_6f72 => Property::From,
             0x0065_7461_446d_6f72 => Property::FromDate,
+            0x0073_7574_6174_5379_7375_4265_6572 => Property::FreeBusyStatus,
+            0x0072_6574_6c69 => Property::Filter,
             _ => return None,
         },
         b'h' => match hash {
@@ -375,8 +437,10 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4979_7469_746e_6564 => Property::IdentityId,
             0x6f54_796c_7065_526e => Property::InReplyTo,
             0x0065_7669_7463_4173 => Property::IsActive,
+            0x746c_7561_6665_4473 => Property::IsDefault,
             0x6465_6c62_616e_4573 => Property::IsEnabled,
             0x0064_6562_6972_6373_6275_5373 => Property::IsSubscribed,
+            0x656c_6269_7369_5673 => Property::IsVisible,
             _ => return None,
         },
         b'k' => match hash {
@@ -387,19 +451,31 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         b'l' => match hash {
             0x0065_6761_7567_6e61 => Property::Language,
             0x006e_6f69_7461_636f => Property::Location,
+            0x736e_6f69_7461_636f => Property::Locations,
             _ => return None,
         },
         b'm' => match hash {
             0x0073_6449_786f_626c_6961 => Property::MailboxIds,
             0x6574_656c_6544_7961 => Property::MayDelete,
+            0x6449_786f_626c_6961_4d6f_5465_766f => Property::MoveToMailboxId,
             0x0073_6449_626f_6c42_6e64 => Property::MdnBlobIds,
             0x7372_6562_6d65 => Property::Members,
             0x6449_6567_6173_7365 => Property::MessageId,
+            0x0061_7461_6461_7465 => Property::Metadata,
+            0x0064_6f68_7465 => Property::Method,
             0x0073_7468_6769_5279 => Property::MyRights,
             _ => return None,
         },
         b'n' => match hash {
             0x0065_6d61 => Property::Name,
+            0x7374_6867_6952_7765 => Property::NewRights,
+            _ => return None,
+        },
+        b'o' => match hash {
+            0x0065_7079_5474_6365_6a62 => Property::ObjectType,
+            0x6449_746e_756f_6363_4174_6365_6a62 => Property::ObjectAccountId,
+            0x0064_4974_6365_6a62 => Property::ObjectId,
+            0x7374_6867_6952_646c => Property::OldRights,
             _ => return None,
         },
         b'p' => match hash {
@@ -407,6 +483,11 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4974_7261 => Property::PartId,
             0x6572_7574_6369 => Property::Picture,
             0x7765_6976_6572 => Property::Preview,
+            0x0073_746e_6170_6963_6974_7261 => Property::Participants,
+            0x0079_7469_726f_6972 => Property::Priority,
+            0x7963_6176_6972 => Property::Privacy,
+            0x0064_4964_6f72 => Property::ProdId,
+            0x6449_6c61_7069_636e_6972 => Property::PrincipalId,
             _ => return None,
         },
         b'q' => match hash {
@@ -415,6 +496,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
         },
         b'r' => match hash {
             0x0074_4164_6576_6965_6365 => Property::ReceivedAt,
+            0x0064_4965_636e_6572_7275_6365 => Property::RecurrenceId,
+            0x7365_6c75_5265_636e_6572_7275_6365 => Property::RecurrenceRules,
             0x0073_6563_6e65_7265_6665 => Property::References,
             0x6f54_796c_7065 => Property::ReplyTo,
             0x0065_6c6f => Property::Role,
@@ -424,11 +507,19 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0074_6572_6365 => Property::Secret,
             0x0074_4164_6e65 => Property::SendAt,
             0x0072_6564_6e65 => Property::Sender,
+            0x0065_636e_6575_7165 => Property::Sequence,
+            0x656d_6954_7475_6f68_7469_5777_6f68 => Property::ShowWithoutTime,
             0x0074_4174_6e65 => Property::SentAt,
             0x0065_7a69 => Property::Size,
+            0x7375_7461_7453_656d_696d => Property::SmimeStatus,
+            0x7372_6f72_7245_656d_696d => Property::SmimeErrors,
+            0x7441_6465_6966_6972_6556_656d_696d => Property::SmimeVerifiedAt,
             0x7265_6472_4f74_726f => Property::SortOrder,
+            0x7472_6174 => Property::Start,
+            0x0073_7574_6174 => Property::Status,
             0x7463_656a_6275 => Property::Subject,
             0x7374_7261_5062_7573 => Property::SubParts,
+            0x6465_7a6f_6f6e => Property::Snoozed,
             _ => return None,
         },
         b't' => match hash {
@@ -436,6 +527,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6572_7574_616e_6769_5374_7865 => Property::TextSignature,
             0x0064_4964_6165_7268 => Property::ThreadId,
             0x0065_6e6f_7a65_6d69 => Property::Timezone,
+            0x0065_6e6f_5a65_6d69 => Property::TimeZone,
+            0x656c_7469 => Property::Title,
             0x6f => Property::To,
             0x0065_7461_446f => Property::ToDate,
             0x736c_6961_6d45_6c61_746f => Property::TotalEmails,
@@ -445,14 +538,20 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             _ => return None,
         },
         b'u' => match hash {
+            0x6469 => Property::Uid,
             0x0073_7574_6174_536f_646e => Property::UndoStatus,
             0x0073_6c69_616d_4564_6165_726e => Property::UnreadEmails,
             0x7364_6165_7268_5464_6165_726e => Property::UnreadThreads,
+            0x6465_7461_6470 => Property::Updated,
             0x6c72 => Property::Url,
+            0x0064_6e45_6374 => Property::UtcEnd,
+            0x0074_7261_7453_6374 => Property::UtcStart,
+            0x6c69_746e => Property::Until,
             _ => return None,
         },
         b'v' => match hash {
             0x0065_646f_436e_6f69_7461_6369_6669_7265 => Property::VerificationCode,
+            0x0073_6e6f_6974_6163_6f4c_6c61_7574_7269 => Property::VirtualLocations,
             _ => return None,
         },
         _ => return None,
@@ -614,6 +713,7 @@ impl JsonObjectParser for ObjectProperty {
                 0x656d_616e_6552_7961 => Property::MayRename,
                 0x6574_656c_6544_7961 => Property::MayDelete,
                 0x7469_6d62_7553_7961 => Property::MaySubmit,
+                0x6449_786f_626c_6961_4d6f_5465_766f => Property::MoveToMailboxId,
                 _ => parser.invalid_property()?,
             },
             b'n' => match hash {
@@ -640,6 +740,10 @@ impl JsonObjectParser for ObjectProperty {
                 0x0065_7079 => Property::Type,
                 _ => parser.invalid_property()?,
             },
+            b'u' => match hash {
+                0x6c69_746e => Property::Until,
+                _ => parser.invalid_property()?,
+            },
             b'v' => match hash {
                 0x6575_6c61 => Property::Value,
                 _ => parser.invalid_property()?,
@@ -652,7 +756,7 @@ impl JsonObjectParser for ObjectProperty {
 impl<'x> Parser<'x> {
     fn invalid_property(&mut self) -> crate::parser::Result<Property> {
         if self.is_eof || self.skip_string() {
-            Ok(Property::_T(
+            Ok(Property::from_long_name(
                 String::from_utf8_lossy(self.bytes[self.pos_marker..self.pos - 1].as_ref())
                     .into_owned(),
             ))
@@ -675,7 +779,7 @@ impl Property {
                         hash |= (ch as u128) << shift;
                         shift += 8;
                     } else {
-                        return Property::_T(value.to_string());
+                        return Property::from_long_name(value.to_string());
                     }
                 } else {
                     first_char = ch;
@@ -692,6 +796,15 @@ impl Property {
         }
     }
 
+    // Names that do not fit in the 128-bit hash
+    fn from_long_name(name: String) -> Property {
+        match name.as_str() {
+            "smimeStatusAtDelivery" => Property::SmimeStatusAtDelivery,
+            "recurrenceOverrides" => Property::RecurrenceOverrides,
+            _ => Property::_T(name),
+        }
+    }
+
     pub fn as_rfc_header(&self) -> RfcHeader {
         match self {
             Property::MessageId => RfcHeader::MessageId,
@@ -810,6 +923,58 @@ impl Display for Property {
             Property::MayCreateChild => write!(f, "mayCreateChild"),
             Property::MayRename => write!(f, "mayRename"),
             Property::MaySubmit => write!(f, "maySubmit"),
+            Property::Metadata => write!(f, "metadata"),
+            Property::AccessKeys => write!(f, "accessKeys"),
+            Property::SaveDate => write!(f, "saveDate"),
+            Property::SmimeStatus => write!(f, "smimeStatus"),
+            Property::SmimeErrors => write!(f, "smimeErrors"),
+            Property::SmimeVerifiedAt => write!(f, "smimeVerifiedAt"),
+            Property::SmimeStatusAtDelivery => write!(f, "smimeStatusAtDelivery"),
+            Property::CalendarIds => write!(f, "calendarIds"),
+            Property::Uid => write!(f, "uid"),
+            Property::Title => write!(f, "title"),
+            Property::Start => write!(f, "start"),
+            Property::Duration => write!(f, "duration"),
+            Property::TimeZone => write!(f, "timeZone"),
+            Property::ShowWithoutTime => write!(f, "showWithoutTime"),
+            Property::RecurrenceRules => write!(f, "recurrenceRules"),
+            Property::RecurrenceOverrides => write!(f, "recurrenceOverrides"),
+            Property::RecurrenceId => write!(f, "recurrenceId"),
+            Property::Status => write!(f, "status"),
+            Property::FreeBusyStatus => write!(f, "freeBusyStatus"),
+            Property::Privacy => write!(f, "privacy"),
+            Property::Participants => write!(f, "participants"),
+            Property::Locations => write!(f, "locations"),
+            Property::VirtualLocations => write!(f, "virtualLocations"),
+            Property::Alerts => write!(f, "alerts"),
+            Property::Sequence => write!(f, "sequence"),
+            Property::Method => write!(f, "method"),
+            Property::Created => write!(f, "created"),
+            Property::Updated => write!(f, "updated"),
+            Property::ProdId => write!(f, "prodId"),
+            Property::Priority => write!(f, "priority"),
+            Property::Color => write!(f, "color"),
+            Property::IsVisible => write!(f, "isVisible"),
+            Property::IsDefault => write!(f, "isDefault"),
+            Property::UtcStart => write!(f, "utcStart"),
+            Property::UtcEnd => write!(f, "utcEnd"),
+            Property::MayReadFreeBusy => write!(f, "mayReadFreeBusy"),
+            Property::MayWriteAll => write!(f, "mayWriteAll"),
+            Property::MayWriteOwn => write!(f, "mayWriteOwn"),
+            Property::MayUpdatePrivate => write!(f, "mayUpdatePrivate"),
+            Property::MayRsvp => write!(f, "mayRSVP"),
+            Property::MayAdmin => write!(f, "mayAdmin"),
+            Property::ChangedBy => write!(f, "changedBy"),
+            Property::ObjectType => write!(f, "objectType"),
+            Property::ObjectAccountId => write!(f, "objectAccountId"),
+            Property::ObjectId => write!(f, "objectId"),
+            Property::OldRights => write!(f, "oldRights"),
+            Property::NewRights => write!(f, "newRights"),
+            Property::PrincipalId => write!(f, "principalId"),
+            Property::Filter => write!(f, "filter"),
+            Property::Snoozed => write!(f, "snoozed"),
+            Property::Until => write!(f, "until"),
+            Property::MoveToMailboxId => write!(f, "moveToMailboxId"),
             Property::_T(s) => write!(f, "{s}"),
         }
     }
@@ -983,6 +1148,58 @@ impl From<&Property> for u8 {
             Property::Id => 94,
             Property::IdentityId => 95,
             Property::InReplyTo => 96,
+            Property::Metadata => 98,
+            Property::AccessKeys => 99,
+            Property::SaveDate => 100,
+            Property::SmimeStatus => 101,
+            Property::SmimeErrors => 102,
+            Property::SmimeVerifiedAt => 103,
+            Property::SmimeStatusAtDelivery => 104,
+            Property::CalendarIds => 105,
+            Property::Uid => 106,
+            Property::Title => 107,
+            Property::Start => 108,
+            Property::Duration => 109,
+            Property::TimeZone => 110,
+            Property::ShowWithoutTime => 111,
+            Property::RecurrenceRules => 112,
+            Property::RecurrenceOverrides => 113,
+            Property::RecurrenceId => 114,
+            Property::Status => 115,
+            Property::FreeBusyStatus => 116,
+            Property::Privacy => 117,
+            Property::Participants => 118,
+            Property::Locations => 119,
+            Property::VirtualLocations => 120,
+            Property::Alerts => 121,
+            Property::Sequence => 122,
+            Property::Method => 123,
+            Property::Created => 124,
+            Property::Updated => 125,
+            Property::ProdId => 126,
+            Property::Priority => 127,
+            Property::Color => 128,
+            Property::IsVisible => 129,
+            Property::IsDefault => 130,
+            Property::UtcStart => 131,
+            Property::UtcEnd => 132,
+            Property::MayReadFreeBusy => 133,
+            Property::MayWriteAll => 134,
+            Property::MayWriteOwn => 135,
+            Property::MayUpdatePrivate => 136,
+            Property::MayRsvp => 137,
+            Property::MayAdmin => 138,
+            Property::ChangedBy => 139,
+            Property::ObjectType => 140,
+            Property::ObjectAccountId => 141,
+            Property::ObjectId => 142,
+            Property::OldRights => 143,
+            Property::NewRights => 144,
+            Property::PrincipalId => 145,
+            Property::Filter => 146,
+            Property::Snoozed => 147,
+            Property::Until => 148,
+            Property::MoveToMailboxId => 149,
             Property::_T(_) => 97,
         }
     }
@@ -1114,6 +1331,58 @@ impl SerializeInto for Property {
             Property::Id => 94,
             Property::IdentityId => 95,
             Property::InReplyTo => 96,
+            Property::Metadata => 98,
+            Property::AccessKeys => 99,
+            Property::SaveDate => 100,
+            Property::SmimeStatus => 101,
+            Property::SmimeErrors => 102,
+            Property::SmimeVerifiedAt => 103,
+            Property::SmimeStatusAtDelivery => 104,
+            Property::CalendarIds => 105,
+            Property::Uid => 106,
+            Property::Title => 107,
+            Property::Start => 108,
+            Property::Duration => 109,
+            Property::TimeZone => 110,
+            Property::ShowWithoutTime => 111,
+            Property::RecurrenceRules => 112,
+            Property::RecurrenceOverrides => 113,
+            Property::RecurrenceId => 114,
+            Property::Status => 115,
+            Property::FreeBusyStatus => 116,
+            Property::Privacy => 117,
+            Property::Participants => 118,
+            Property::Locations => 119,
+            Property::VirtualLocations => 120,
+            Property::Alerts => 121,
+            Property::Sequence => 122,
+            Property::Method => 123,
+            Property::Created => 124,
+            Property::Updated => 125,
+            Property::ProdId => 126,
+            Property::Priority => 127,
+            Property::Color => 128,
+            Property::IsVisible => 129,
+            Property::IsDefault => 130,
+            Property::UtcStart => 131,
+            Property::UtcEnd => 132,
+            Property::MayReadFreeBusy => 133,
+            Property::MayWriteAll => 134,
+            Property::MayWriteOwn => 135,
+            Property::MayUpdatePrivate => 136,
+            Property::MayRsvp => 137,
+            Property::MayAdmin => 138,
+            Property::ChangedBy => 139,
+            Property::ObjectType => 140,
+            Property::ObjectAccountId => 141,
+            Property::ObjectId => 142,
+            Property::OldRights => 143,
+            Property::NewRights => 144,
+            Property::PrincipalId => 145,
+            Property::Filter => 146,
+            Property::Snoozed => 147,
+            Property::Until => 148,
+            Property::MoveToMailboxId => 149,
             Property::_T(value) => {
                 buf.push(97);
                 value.serialize_into(buf);
@@ -1228,6 +1497,58 @@ impl DeserializeFrom for Property {
             95 => Some(Property::IdentityId),
             96 => Some(Property::InReplyTo),
             97 => String::deserialize_from(bytes).map(Property::_T),
+            98 => Some(Property::Metadata),
+            99 => Some(Property::AccessKeys),
+            100 => Some(Property::SaveDate),
+            101 => Some(Property::SmimeStatus),
+            102 => Some(Property::SmimeErrors),
+            103 => Some(Property::SmimeVerifiedAt),
+            104 => Some(Property::SmimeStatusAtDelivery),
+            105 => Some(Property::CalendarIds),
+            106 => Some(Property::Uid),
+            107 => Some(Property::Title),
+            108 => Some(Property::Start),
+            109 => Some(Property::Duration),
+            110 => Some(Property::TimeZone),
+            111 => Some(Property::ShowWithoutTime),
+            112 => Some(Property::RecurrenceRules),
+            113 => Some(Property::RecurrenceOverrides),
+            114 => Some(Property::RecurrenceId),
+            115 => Some(Property::Status),
+            116 => Some(Property::FreeBusyStatus),
+            117 => Some(Property::Privacy),
+            118 => Some(Property::Participants),
+            119 => Some(Property::Locations),
+            120 => Some(Property::VirtualLocations),
+            121 => Some(Property::Alerts),
+            122 => Some(Property::Sequence),
+            123 => Some(Property::Method),
+            124 => Some(Property::Created),
+            125 => Some(Property::Updated),
+            126 => Some(Property::ProdId),
+            127 => Some(Property::Priority),
+            128 => Some(Property::Color),
+            129 => Some(Property::IsVisible),
+            130 => Some(Property::IsDefault),
+            131 => Some(Property::UtcStart),
+            132 => Some(Property::UtcEnd),
+            133 => Some(Property::MayReadFreeBusy),
+            134 => Some(Property::MayWriteAll),
+            135 => Some(Property::MayWriteOwn),
+            136 => Some(Property::MayUpdatePrivate),
+            137 => Some(Property::MayRsvp),
+            138 => Some(Property::MayAdmin),
+            139 => Some(Property::ChangedBy),
+            140 => Some(Property::ObjectType),
+            141 => Some(Property::ObjectAccountId),
+            142 => Some(Property::ObjectId),
+            143 => Some(Property::OldRights),
+            144 => Some(Property::NewRights),
+            145 => Some(Property::PrincipalId),
+            146 => Some(Property::Filter),
+            147 => Some(Property::Snoozed),
+            148 => Some(Property::Until),
+            149 => Some(Property::MoveToMailboxId),
             _ => None,
         }
     }
@@ -44,7 +44,15 @@ pub enum TypeState {
     Thread = 4,
     #[serde(rename = "Identity")]
     Identity = 5,
-    None = 6,
+    #[serde(rename = "Calendar")]
+    Calendar = 6,
+    #[serde(rename = "CalendarEvent")]
+    CalendarEvent = 7,
+    #[serde(rename = "ShareNotification")]
+    ShareNotification = 8,
+    // Never persisted, only used as the upper bound of bitmaps so it has to
+    // remain the last variant when new ones are added.
+    None = 9,
 }
 
 impl BitmapItem for TypeState {
@@ -66,6 +74,9 @@ impl From<u64> for TypeState {
             3 => TypeState::Mailbox,
             4 => TypeState::Thread,
             5 => TypeState::Identity,
+            6 => TypeState::Calendar,
+            7 => TypeState::CalendarEvent,
+            8 => TypeState::ShareNotification,
             _ => {
                 debug_assert!(false, "Invalid type_state value: {}", value);
                 TypeState::None
@@ -86,24 +97,34 @@ impl JsonObjectParser for TypeState {
         Self: Sized,
     {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         while let Some(ch) = parser.next_unescaped()? {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
                 shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
+                shift += 8;
             } else {
                 return Err(parser.error_value());
             }
         }
 
         match hash {
+            0x6f69_7461_6369_6669_746f_4e65_7261_6853 if hash_ext == 0x006e => {
+                Ok(TypeState::ShareNotification)
+            }
+            _ if hash_ext != 0 => Err(parser.error_value()),
             0x006c_6961_6d45 => Ok(TypeState::Email),
             0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(TypeState::EmailDelivery),
             0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(TypeState::EmailSubmission),
             0x0078_6f62_6c69_614d => Ok(TypeState::Mailbox),
             0x6461_6572_6854 => Ok(TypeState::Thread),
             0x7974_6974_6e65_6449 => Ok(TypeState::Identity),
+            0x7261_646e_656c_6143 => Ok(TypeState::Calendar),
+            0x0074_6e65_7645_7261_646e_656c_6143 => Ok(TypeState::CalendarEvent),
             _ => Err(parser.error_value()),
         }
     }
@@ -114,24 +135,34 @@ impl TryFrom<&str> for TypeState {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let mut hash = 0;
+        let mut hash_ext = 0;
         let mut shift = 0;
 
         for &ch in value.as_bytes() {
             if shift < 128 {
                 hash |= (ch as u128) << shift;
                 shift += 8;
+            } else if shift < 256 {
+                hash_ext |= (ch as u128) << (shift - 128);
+                shift += 8;
             } else {
                 return Err(());
             }
         }
 
         match hash {
+            0x6f69_7461_6369_6669_746f_4e65_7261_6853 if hash_ext == 0x006e => {
+                Ok(TypeState::ShareNotification)
+            }
+            _ if hash_ext != 0 => Err(()),
             0x006c_6961_6d45 => Ok(TypeState::Email),
             0x0079_7265_7669_6c65_446c_6961_6d45 => Ok(TypeState::EmailDelivery),
             0x006e_6f69_7373_696d_6275_536c_6961_6d45 => Ok(TypeState::EmailSubmission),
             0x0078_6f62_6c69_614d => Ok(TypeState::Mailbox),
             0x6461_6572_6854 => Ok(TypeState::Thread),
             0x7974_6974_6e65_6449 => Ok(TypeState::Identity),
+            0x7261_646e_656c_6143 => Ok(TypeState::Calendar),
+            0x0074_6e65_7645_7261_646e_656c_6143 => Ok(TypeState::CalendarEvent),
             _ => Err(()),
         }
     }
@@ -146,6 +177,9 @@ impl TypeState {
             TypeState::Mailbox => "Mailbox",
             TypeState::Thread => "Thread",
             TypeState::Identity => "Identity",
+            TypeState::Calendar => "Calendar",
+            TypeState::CalendarEvent => "CalendarEvent",
+            TypeState::ShareNotification => "ShareNotification",
             TypeState::None => "",
         }
     }
@@ -172,6 +206,9 @@ impl DeserializeFrom for TypeState {
             3 => Some(TypeState::Mailbox),
             4 => Some(TypeState::Thread),
             5 => Some(TypeState::Identity),
+            6 => Some(TypeState::Calendar),
+            7 => Some(TypeState::CalendarEvent),
+            8 => Some(TypeState::ShareNotification),
             _ => None,
         }
     }
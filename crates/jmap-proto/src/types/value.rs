@@ -119,6 +119,43 @@ impl Value {
         })
     }
 
+    /// Parses a JSON value as is, without interpreting the properties of
+    /// nested objects.
+    pub fn parse_json(
+        token: Token<String>,
+        parser: &mut Parser<'_>,
+    ) -> crate::parser::Result<Self> {
+        Ok(match token {
+            Token::String(v) => Value::Text(v),
+            Token::DictStart => {
+                let mut properties = Object::with_capacity(4);
+                while let Some(key) = parser.next_dict_key::<String>()? {
+                    let value = Value::parse_json(parser.next_token()?, parser)?;
+                    properties.append(Property::_T(key), value);
+                }
+                Value::Object(properties)
+            }
+            Token::ArrayStart => {
+                let mut values = Vec::with_capacity(4);
+                loop {
+                    match parser.next_token::<String>()? {
+                        Token::Comma => (),
+                        Token::ArrayEnd => break,
+                        token => {
+                            values.push(Value::parse_json(token, parser)?);
+                        }
+                    }
+                }
+                Value::List(values)
+            }
+            Token::Integer(v) => Value::UnsignedInt(std::cmp::max(v, 0) as u64),
+            Token::Float(v) => Value::UnsignedInt(if v > 0.0 { v as u64 } else { 0 }),
+            Token::Boolean(v) => Value::Bool(v),
+            Token::Null => Value::Null,
+            token => return Err(token.error("", "value")),
+        })
+    }
+
     pub fn from_property(
         parser: &mut Parser<'_>,
         property: &Property,
@@ -168,6 +205,16 @@ impl Value {
             | Property::SubParts => {
                 Value::parse::<ObjectProperty, String>(parser.next_token()?, parser)
             }
+            Property::Until => Ok(parser
+                .next_token::<UTCDate>()?
+                .unwrap_string_or_null("")?
+                .map(Value::Date)
+                .unwrap_or(Value::Null)),
+            Property::MoveToMailboxId => Ok(parser
+                .next_token::<Id>()?
+                .unwrap_string_or_null("")?
+                .map(Value::Id)
+                .unwrap_or(Value::Null)),
             Property::Language | Property::Parameters => {
                 Value::parse::<String, String>(parser.next_token()?, parser)
             }
@@ -322,6 +369,7 @@ impl Value {
             Value::UnsignedInt(u) => Some(*u),
             Value::Id(id) => Some(id.id()),
             Value::Bool(b) => Some(*b as u64),
+            Value::Date(d) => Some(d.timestamp() as u64),
             _ => None,
         }
     }
@@ -23,11 +23,12 @@
 
 use jmap_proto::{
     object::{index::ObjectIndexBuilder, Object},
-    types::{collection::Collection, property::Property, value::Value},
+    types::{collection::Collection, id::Id, property::Property, value::Value},
 };
 use store::{
+    ahash::AHashMap,
     write::{assert::HashedValue, BatchBuilder, Operation, ValueClass},
-    BitmapKey, Serialize, ValueKey,
+    BitmapKey, CustomValueKey, Serialize, ValueKey,
 };
 
 use crate::{auth::authenticate::AccountKey, mailbox::set::SCHEMA, JMAP};
@@ -123,4 +124,129 @@ impl JMAP {
         self.store.write(batch.build()).await?;
         Ok(())
     }
+
+    pub async fn export_account(&self, account_id: u32, path: &str) -> store::Result<()> {
+        // Obtain the names of the principals the mailboxes are shared with
+        let mut principals = Vec::new();
+        for mailbox_id in self
+            .store
+            .get_bitmap(BitmapKey::document_ids(account_id, Collection::Mailbox))
+            .await?
+            .unwrap_or_default()
+        {
+            let mailbox = self
+                .store
+                .get_value::<Object<Value>>(ValueKey::new(
+                    account_id,
+                    Collection::Mailbox,
+                    mailbox_id,
+                    Property::Value,
+                ))
+                .await?
+                .ok_or_else(|| {
+                    store::Error::InternalError(format!("Mailbox {} not found", mailbox_id))
+                })?;
+            if let Some(Value::List(acls)) = mailbox.properties.get(&Property::Acl) {
+                for item in acls.chunks_exact(2) {
+                    if let Some(Value::Id(id)) = item.first() {
+                        let principal_id = id.document_id();
+                        if principals.iter().any(|(id, _)| *id == principal_id) {
+                            continue;
+                        }
+                        if let Some(name) = self
+                            .store
+                            .get_value::<String>(CustomValueKey {
+                                value: AccountKey::id_to_name(principal_id),
+                            })
+                            .await?
+                        {
+                            principals.push((principal_id, name));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.store
+            .export_account(account_id, path, &principals)
+            .await
+    }
+
+    pub async fn import_account(&self, account_id: u32, path: &str) -> store::Result<()> {
+        // Make sure the account is empty
+        let mailbox_ids = self
+            .store
+            .get_bitmap(BitmapKey::document_ids(account_id, Collection::Mailbox))
+            .await?
+            .unwrap_or_default();
+        if !mailbox_ids.is_empty() {
+            return Err(store::Error::InternalError(
+                "Account is not empty.".to_string(),
+            ));
+        }
+
+        // Import account and map principals to local account ids
+        let mut principal_ids = AHashMap::new();
+        for (principal_id, name) in self.store.import_account(account_id, path).await? {
+            if let Some(local_id) = self
+                .store
+                .get_value::<u32>(CustomValueKey {
+                    value: AccountKey::name_to_id(&name),
+                })
+                .await?
+            {
+                principal_ids.insert(principal_id, local_id);
+            }
+        }
+
+        // Rebuild mailbox ACLs using the local account ids, grants to
+        // unknown principals are dropped
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Mailbox);
+        for mailbox_id in self
+            .store
+            .get_bitmap(BitmapKey::document_ids(account_id, Collection::Mailbox))
+            .await?
+            .unwrap_or_default()
+        {
+            let mut mailbox = self
+                .store
+                .get_value::<HashedValue<Object<Value>>>(ValueKey::new(
+                    account_id,
+                    Collection::Mailbox,
+                    mailbox_id,
+                    Property::Value,
+                ))
+                .await?
+                .ok_or_else(|| {
+                    store::Error::InternalError(format!("Mailbox {} not found", mailbox_id))
+                })?;
+            if let Some(Value::List(acls)) = mailbox.inner.properties.remove(&Property::Acl) {
+                let mut local_acls = Vec::with_capacity(acls.len());
+                for item in acls.chunks_exact(2) {
+                    if let (Some(Value::Id(id)), Some(acl)) = (item.first(), item.last()) {
+                        if let Some(local_id) = principal_ids.get(&id.document_id()) {
+                            local_acls.push(Value::Id(Id::from(*local_id)));
+                            local_acls.push(acl.clone());
+                        }
+                    }
+                }
+                batch.update_document(mailbox_id).custom(
+                    ObjectIndexBuilder::new(SCHEMA)
+                        .with_current(mailbox)
+                        .with_changes(
+                            Object::with_capacity(1)
+                                .with_property(Property::Acl, Value::List(local_acls)),
+                        ),
+                );
+            }
+        }
+        if !batch.is_empty() {
+            self.store.write(batch.build()).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -28,6 +28,8 @@ use store::{
     rand::{distributions::Alphanumeric, thread_rng, Rng},
 };
 
+use crate::email::smime::parse_trust_store;
+
 use super::session::BaseCapabilities;
 
 impl crate::Config {
@@ -37,6 +39,11 @@ impl crate::Config {
                 settings.value("jmap.fts.default-language").unwrap_or("en"),
             )
             .unwrap_or(Language::English),
+            fts_reindex_batch_size: settings
+                .property("jmap.fts.reindex.batch-size")?
+                .unwrap_or(100),
+            fts_reindex_throttle: settings
+                .property_or_static::<Duration>("jmap.fts.reindex.throttle", "100ms")?,
             query_max_results: settings
                 .property("jmap.protocol.query.max-results")?
                 .unwrap_or(5000),
@@ -138,8 +145,27 @@ impl crate::Config {
             principal_allow_lookups: settings
                 .property("jmap.principal.allow-lookups")?
                 .unwrap_or(true),
+            principal_description_max_len: settings
+                .property("jmap.principal.max-description-length")?
+                .unwrap_or(1024),
+            calendar_name_max_len: settings
+                .property("jmap.calendar.max-name-length")?
+                .unwrap_or(255),
+            calendar_max_participants: settings
+                .property("jmap.calendar.max-participants")?
+                .unwrap_or(250),
+            calendar_max_instances: settings
+                .property("jmap.calendar.max-instances")?
+                .unwrap_or(1000),
+            calendar_max_expand_duration: settings
+                .property_or_static::<Duration>("jmap.calendar.max-expand-duration", "366d")?,
+            calendar_itip_enable: settings
+                .property("jmap.calendar.itip.enable")?
+                .unwrap_or(true),
             encrypt: settings.property_or_static("jmap.encryption.enable", "true")?,
             encrypt_append: settings.property_or_static("jmap.encryption.append", "false")?,
+            smime_trust_store: parse_trust_store(settings)?,
+            push_vapid_key: None,
         };
         config.add_capabilites(settings);
         Ok(config)
@@ -48,7 +48,7 @@ use utils::listener::{ServerInstance, SessionData, SessionManager};
 use crate::{
     auth::{oauth::OAuthMetadata, AccessToken},
     blob::{DownloadResponse, UploadResponse},
-    services::state,
+    services::{housekeeper, state},
     websocket::upgrade::upgrade_websocket_connection,
     JMAP,
 };
@@ -339,6 +339,101 @@ pub async fn parse_jmap_request(
                         .into_http_response()
                     };
                 }
+                ("account", action @ ("export" | "import"), &Method::GET) => {
+                    let archive_path = req.uri().query().and_then(|q| {
+                        form_urlencoded::parse(q.as_bytes())
+                            .find(|(k, _)| k == "path")
+                            .map(|(_, v)| v.into_owned())
+                    });
+                    return if let (Some(account_name), Some(archive_path)) =
+                        (path.next(), archive_path)
+                    {
+                        // Accounts being imported might not have been assigned an id yet
+                        let account_id = if action == "export" {
+                            jmap.try_get_account_id(account_name).await
+                        } else {
+                            jmap.get_account_id(account_name).await.map(Some)
+                        };
+                        match account_id {
+                            Ok(Some(account_id)) => {
+                                let result = if action == "export" {
+                                    jmap.export_account(account_id, &archive_path).await
+                                } else {
+                                    jmap.import_account(account_id, &archive_path).await
+                                };
+                                match result {
+                                    Ok(_) => JsonResponse::new(Value::String("success".into()))
+                                        .into_http_response(),
+                                    Err(err) => RequestError::blank(
+                                        StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                                        if action == "export" {
+                                            "Account export failed"
+                                        } else {
+                                            "Account import failed"
+                                        },
+                                        err.to_string(),
+                                    )
+                                    .into_http_response(),
+                                }
+                            }
+                            Ok(None) => RequestError::blank(
+                                StatusCode::NOT_FOUND.as_u16(),
+                                "Not found",
+                                "Account not found.",
+                            )
+                            .into_http_response(),
+                            Err(_) => RequestError::internal_server_error().into_http_response(),
+                        }
+                    } else {
+                        RequestError::blank(
+                            StatusCode::BAD_REQUEST.as_u16(),
+                            "Invalid parameters",
+                            "Expected account name and archive path",
+                        )
+                        .into_http_response()
+                    };
+                }
+                ("fts", "reindex", &Method::GET) => {
+                    let account_id = if let Some(account_name) = path.next() {
+                        match jmap.try_get_account_id(account_name).await {
+                            Ok(Some(account_id)) => Some(account_id),
+                            Ok(None) => {
+                                return RequestError::blank(
+                                    StatusCode::NOT_FOUND.as_u16(),
+                                    "Not found",
+                                    "Account not found.",
+                                )
+                                .into_http_response();
+                            }
+                            Err(_) => {
+                                return RequestError::internal_server_error().into_http_response()
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    return match jmap
+                        .housekeeper_tx
+                        .send(housekeeper::Event::ReindexFts { account_id })
+                        .await
+                    {
+                        Ok(_) => {
+                            JsonResponse::new(Value::String("success".into())).into_http_response()
+                        }
+                        Err(_) => RequestError::internal_server_error().into_http_response(),
+                    };
+                }
+                ("fts", "status", &Method::GET) => {
+                    return match jmap.fts_reindex_state().await {
+                        Ok(state) => JsonResponse::new(state).into_http_response(),
+                        Err(err) => RequestError::blank(
+                            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                            "Failed to obtain reindex status",
+                            err.to_string(),
+                        )
+                        .into_http_response(),
+                    };
+                }
                 ("blob", "purge", &Method::GET) => {
                     return match jmap.store.purge_tmp_blobs(jmap.config.upload_tmp_ttl).await {
                         Ok(_) => {
@@ -352,6 +447,41 @@ pub async fn parse_jmap_request(
                         .into_http_response(),
                     };
                 }
+                ("store", action @ ("backup" | "restore"), &Method::GET) => {
+                    let path = req.uri().query().and_then(|q| {
+                        form_urlencoded::parse(q.as_bytes())
+                            .find(|(k, _)| k == "path")
+                            .map(|(_, v)| v.into_owned())
+                    });
+                    return if let Some(path) = path {
+                        let result = if action == "backup" {
+                            jmap.store.backup(&path).await
+                        } else {
+                            jmap.store.restore(&path).await
+                        };
+                        match result {
+                            Ok(_) => JsonResponse::new(Value::String("success".into()))
+                                .into_http_response(),
+                            Err(err) => RequestError::blank(
+                                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                                if action == "backup" {
+                                    "Backup failed"
+                                } else {
+                                    "Restore failed"
+                                },
+                                err.to_string(),
+                            )
+                            .into_http_response(),
+                        }
+                    } else {
+                        RequestError::blank(
+                            StatusCode::BAD_REQUEST.as_u16(),
+                            "Invalid parameters",
+                            "Expected archive path",
+                        )
+                        .into_http_response()
+                    };
+                }
                 (path_1 @ ("queue" | "report"), path_2, &Method::GET) => {
                     return jmap
                         .smtp
@@ -168,6 +168,16 @@ impl JMAP {
 
                     self.vacation_response_get(req).await?.into()
                 }
+                get::RequestArguments::Calendar => {
+                    access_token.assert_has_access(req.account_id, Collection::Calendar)?;
+
+                    self.calendar_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::CalendarEvent => {
+                    access_token.assert_has_access(req.account_id, Collection::CalendarEvent)?;
+
+                    self.calendar_event_get(req, access_token).await?.into()
+                }
                 get::RequestArguments::Principal => {
                     if self.config.principal_allow_lookups || access_token.is_super_user() {
                         self.principal_get(req).await?.into()
@@ -177,6 +187,11 @@ impl JMAP {
                         ));
                     }
                 }
+                get::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_get(req).await?.into()
+                }
             },
             RequestMethod::Query(mut req) => match req.take_arguments() {
                 query::RequestArguments::Email(arguments) => {
@@ -203,6 +218,13 @@ impl JMAP {
 
                     self.sieve_script_query(req).await?.into()
                 }
+                query::RequestArguments::CalendarEvent(arguments) => {
+                    access_token.assert_has_access(req.account_id, Collection::CalendarEvent)?;
+
+                    self.calendar_event_query(req.with_arguments(arguments), access_token)
+                        .await?
+                        .into()
+                }
                 query::RequestArguments::Principal => {
                     if self.config.principal_allow_lookups || access_token.is_super_user() {
                         self.principal_query(req).await?.into()
@@ -212,6 +234,11 @@ impl JMAP {
                         ));
                     }
                 }
+                query::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_query(req).await?.into()
+                }
             },
             RequestMethod::Set(mut req) => match req.take_arguments() {
                 set::RequestArguments::Email => {
@@ -253,6 +280,28 @@ impl JMAP {
 
                     self.vacation_response_set(req).await?.into()
                 }
+                set::RequestArguments::Calendar(arguments) => {
+                    access_token.assert_has_access(req.account_id, Collection::Calendar)?;
+
+                    self.calendar_set(req.with_arguments(arguments), access_token)
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::CalendarEvent(arguments) => {
+                    access_token.assert_has_access(req.account_id, Collection::CalendarEvent)?;
+
+                    self.calendar_event_set(req.with_arguments(arguments), access_token, instance)
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::Principal => {
+                    self.principal_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::ShareNotification => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.share_notification_set(req).await?.into()
+                }
             },
             RequestMethod::Changes(req) => self.changes(req, access_token).await?.into(),
             RequestMethod::Copy(req) => {
@@ -263,6 +312,21 @@ impl JMAP {
                 self.email_copy(req, access_token, next_call).await?.into()
             }
             RequestMethod::CopyBlob(req) => self.blob_copy(req, access_token).await?.into(),
+            RequestMethod::UploadBlob(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.blob_upload_many(req, access_token).await?.into()
+            }
+            RequestMethod::GetBlob(req) => {
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.blob_get(req, access_token).await?.into()
+            }
+            RequestMethod::LookupBlob(req) => {
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.blob_lookup(req, access_token).await?.into()
+            }
             RequestMethod::ImportEmail(req) => {
                 access_token.assert_has_access(req.account_id, Collection::Email)?;
 
@@ -273,6 +337,16 @@ impl JMAP {
 
                 self.email_parse(req, access_token).await?.into()
             }
+            RequestMethod::SendMdn(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.mdn_send(req, instance, next_call).await?.into()
+            }
+            RequestMethod::ParseMdn(req) => {
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.mdn_parse(req, access_token).await?.into()
+            }
             RequestMethod::QueryChanges(req) => self.query_changes(req, access_token).await?.into(),
             RequestMethod::SearchSnippet(req) => {
                 access_token.assert_has_access(req.account_id, Collection::Email)?;
@@ -284,6 +358,17 @@ impl JMAP {
 
                 self.sieve_script_validate(req, access_token).await?.into()
             }
+            RequestMethod::GetAvailability(req) => {
+                if self.config.principal_allow_lookups || access_token.is_super_user() {
+                    self.principal_get_availability(req, access_token)
+                        .await?
+                        .into()
+                } else {
+                    return Err(MethodError::Forbidden(
+                        "Principal lookups are disabled".to_string(),
+                    ));
+                }
+            }
             RequestMethod::Echo(req) => req.into(),
             RequestMethod::Error(error) => return Err(error),
         })
@@ -32,7 +32,7 @@ use jmap_proto::{
 use store::ahash::AHashSet;
 use utils::{listener::ServerInstance, map::vec_map::VecMap, UnwrapFailure};
 
-use crate::{auth::AccessToken, JMAP};
+use crate::{auth::AccessToken, push::vapid::VapidKey, JMAP};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Session {
@@ -81,6 +81,12 @@ pub enum Capabilities {
     VacationResponse(VacationResponseCapabilities),
     WebSocket(WebSocketCapabilities),
     Sieve(SieveCapabilities),
+    Mdn(MdnCapabilities),
+    Blob(BlobCapabilities),
+    SmimeVerify(SmimeVerifyCapabilities),
+    Calendars(CalendarsCapabilities),
+    Principals(PrincipalsCapabilities),
+    WebPushVapid(WebPushVapidCapabilities),
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -158,6 +164,49 @@ pub struct SubmissionCapabilities {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct VacationResponseCapabilities {}
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MdnCapabilities {}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SmimeVerifyCapabilities {}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PrincipalsCapabilities {}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WebPushVapidCapabilities {
+    #[serde(rename(serialize = "applicationServerKey"))]
+    application_server_key: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobCapabilities {
+    #[serde(rename(serialize = "maxSizeBlobSet"))]
+    max_size_blob_set: usize,
+    #[serde(rename(serialize = "maxDataSources"))]
+    max_data_sources: usize,
+    #[serde(rename(serialize = "supportedTypeNames"))]
+    supported_type_names: Vec<&'static str>,
+    #[serde(rename(serialize = "supportedDigestAlgorithms"))]
+    supported_digest_algorithms: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CalendarsCapabilities {
+    #[serde(rename(serialize = "maxCalendarsPerEvent"))]
+    max_calendars_per_event: Option<usize>,
+    #[serde(rename(serialize = "minDateTime"))]
+    min_date_time: &'static str,
+    #[serde(rename(serialize = "maxDateTime"))]
+    max_date_time: &'static str,
+    #[serde(rename(serialize = "maxExpandedQueryDuration"))]
+    max_expanded_query_duration: String,
+    #[serde(rename(serialize = "maxParticipantsPerEvent"))]
+    max_participants_per_event: Option<usize>,
+    #[serde(rename(serialize = "mayCreateCalendar"))]
+    may_create_calendar: bool,
+}
+
 #[derive(Default)]
 pub struct BaseCapabilities {
     pub capabilities: VecMap<Capability, Capabilities>,
@@ -198,7 +247,13 @@ impl JMAP {
                     .unwrap_or_else(|| Id::from(*id).to_string()),
                 is_personal,
                 is_readonly,
-                Some(&[Capability::Core, Capability::Mail, Capability::WebSocket]),
+                Some(&[
+                    Capability::Core,
+                    Capability::Mail,
+                    Capability::Calendars,
+                    Capability::Principals,
+                    Capability::WebSocket,
+                ]),
             );
         }
 
@@ -233,6 +288,35 @@ impl crate::Config {
             Capability::Sieve,
             Capabilities::Sieve(SieveCapabilities::new(self, settings)),
         );
+        self.capabilities
+            .capabilities
+            .append(Capability::Mdn, Capabilities::Mdn(MdnCapabilities {}));
+        self.capabilities.capabilities.append(
+            Capability::Blob,
+            Capabilities::Blob(BlobCapabilities::new(self)),
+        );
+        self.capabilities.capabilities.append(
+            Capability::SmimeVerify,
+            Capabilities::SmimeVerify(SmimeVerifyCapabilities {}),
+        );
+        self.capabilities.capabilities.append(
+            Capability::Calendars,
+            Capabilities::Calendars(CalendarsCapabilities::new(self)),
+        );
+        self.capabilities.capabilities.append(
+            Capability::Principals,
+            Capabilities::Principals(PrincipalsCapabilities {}),
+        );
+    }
+
+    pub fn set_push_vapid_key(&mut self, vapid_key: VapidKey) {
+        self.capabilities.capabilities.append(
+            Capability::WebPushVapid,
+            Capabilities::WebPushVapid(WebPushVapidCapabilities {
+                application_server_key: vapid_key.public_key().to_string(),
+            }),
+        );
+        self.push_vapid_key = Some(Arc::new(vapid_key));
     }
 }
 
@@ -421,6 +505,33 @@ impl SieveCapabilities {
     }
 }
 
+impl CalendarsCapabilities {
+    pub fn new(config: &crate::Config) -> Self {
+        CalendarsCapabilities {
+            max_calendars_per_event: None,
+            min_date_time: "1970-01-01T00:00:00Z",
+            max_date_time: "9999-12-31T23:59:59Z",
+            max_expanded_query_duration: format!(
+                "P{}D",
+                config.calendar_max_expand_duration.as_secs() / 86400
+            ),
+            max_participants_per_event: config.calendar_max_participants.into(),
+            may_create_calendar: true,
+        }
+    }
+}
+
+impl BlobCapabilities {
+    pub fn new(config: &crate::Config) -> Self {
+        BlobCapabilities {
+            max_size_blob_set: config.upload_max_size,
+            max_data_sources: config.set_max_objects,
+            supported_type_names: vec!["Email", "Mailbox", "Thread", "SieveScript"],
+            supported_digest_algorithms: vec!["sha", "sha-256"],
+        }
+    }
+}
+
 impl MailCapabilities {
     pub fn new(config: &crate::Config) -> Self {
         MailCapabilities {
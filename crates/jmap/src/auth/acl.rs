@@ -32,6 +32,7 @@ use jmap_proto::{
     },
 };
 use store::{
+    query::Filter,
     roaring::RoaringBitmap,
     write::{assert::HashedValue, key::DeserializeBigEndian},
     AclKey, Deserialize, Error,
@@ -90,6 +91,10 @@ impl JMAP {
                             && (acl.contains(Acl::ReadItems) || acl.contains(Acl::Administer))
                         {
                             collections.insert(Collection::Email);
+                        } else if collection == Collection::Calendar
+                            && (acl.contains(Acl::ReadItems) || acl.contains(Acl::Administer))
+                        {
+                            collections.insert(Collection::CalendarEvent);
                         }
 
                         if !collections.is_empty() {
@@ -221,6 +226,37 @@ impl JMAP {
         Ok(shared_messages)
     }
 
+    pub async fn shared_events(
+        &self,
+        access_token: &AccessToken,
+        to_account_id: u32,
+        check_acls: impl Into<Bitmap<Acl>>,
+    ) -> Result<RoaringBitmap, MethodError> {
+        let check_acls = check_acls.into();
+        let shared_calendars = self
+            .shared_documents(
+                access_token,
+                to_account_id,
+                Collection::Calendar,
+                check_acls,
+            )
+            .await?;
+        if shared_calendars.is_empty() {
+            return Ok(shared_calendars);
+        }
+        let mut filter = Vec::with_capacity(shared_calendars.len() as usize + 2);
+        filter.push(Filter::Or);
+        for calendar_id in shared_calendars {
+            filter.push(Filter::eq(Property::CalendarIds, calendar_id));
+        }
+        filter.push(Filter::End);
+
+        Ok(self
+            .filter(to_account_id, Collection::CalendarEvent, filter)
+            .await?
+            .results)
+    }
+
     pub async fn owned_or_shared_documents(
         &self,
         access_token: &AccessToken,
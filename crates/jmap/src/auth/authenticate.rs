@@ -315,20 +315,28 @@ impl JMAP {
     }
 }
 
+// Tags of the custom keys stored under the reserved account id `u32::MAX`,
+// every key type must use a distinct tag.
+pub const KEY_ACCOUNT_NAME: u8 = 0;
+pub const KEY_ACCOUNT_ID: u8 = 1;
+pub const KEY_FTS_REINDEX: u8 = 2;
+pub const KEY_VAPID: u8 = 3;
+pub const KEY_SNOOZE_QUEUE: u8 = 4;
+
 pub struct AccountKey();
 
 impl AccountKey {
     pub fn name_to_id(name: &str) -> Vec<u8> {
         KeySerializer::new(name.len() + std::mem::size_of::<u32>() + 1)
             .write(u32::MAX)
-            .write(0u8)
+            .write(KEY_ACCOUNT_NAME)
             .write(name)
             .finalize()
     }
     pub fn id_to_name(id: u32) -> Vec<u8> {
         KeySerializer::new(std::mem::size_of::<u32>() * 2 + 1)
             .write(u32::MAX)
-            .write(1u8)
+            .write(KEY_ACCOUNT_ID)
             .write(id)
             .finalize()
     }
@@ -0,0 +1,264 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use base64::{engine::general_purpose, Engine};
+use jmap_proto::{
+    error::method::MethodError,
+    method::{
+        get::{BlobGetRequest, BlobGetResponse},
+        lookup::{BlobInfo, BlobLookupRequest, BlobLookupResponse},
+    },
+    object::blob::BlobProperty,
+    request::method::MethodObject,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use store::{query::Filter, BlobKind};
+use utils::map::vec_map::VecMap;
+
+use crate::{auth::AccessToken, JMAP};
+
+impl JMAP {
+    pub async fn blob_get(
+        &self,
+        request: BlobGetRequest,
+        access_token: &AccessToken,
+    ) -> Result<BlobGetResponse, MethodError> {
+        let ids = request.ids.unwrap_or_default();
+        if ids.len() > self.config.get_max_objects {
+            return Err(MethodError::RequestTooLarge);
+        }
+        let properties = request
+            .properties
+            .unwrap_or_else(|| vec![BlobProperty::Data, BlobProperty::Size]);
+        let mut response = BlobGetResponse {
+            account_id: request.account_id,
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for blob_id in ids {
+            let bytes = if let Some(bytes) = self.blob_download(&blob_id, access_token).await? {
+                bytes
+            } else {
+                response.not_found.push(blob_id);
+                continue;
+            };
+
+            // Obtain requested range
+            let offset = request.offset.unwrap_or(0);
+            let length = request
+                .length
+                .unwrap_or(usize::MAX)
+                .min(bytes.len().saturating_sub(offset));
+            let is_truncated = offset > bytes.len()
+                || request
+                    .length
+                    .map_or(false, |length| offset.saturating_add(length) > bytes.len());
+            let data = bytes.get(offset..offset + length).unwrap_or_default();
+
+            let mut blob = VecMap::with_capacity(properties.len() + 1);
+            blob.append(BlobProperty::Id, Value::BlobId(blob_id));
+            for property in &properties {
+                match property {
+                    BlobProperty::Data => match std::str::from_utf8(data) {
+                        Ok(text) => {
+                            blob.append(BlobProperty::DataAsText, Value::Text(text.to_string()));
+                        }
+                        Err(_) => {
+                            blob.append(
+                                BlobProperty::DataAsBase64,
+                                Value::Text(general_purpose::STANDARD.encode(data)),
+                            );
+                        }
+                    },
+                    BlobProperty::DataAsText => match std::str::from_utf8(data) {
+                        Ok(text) => {
+                            blob.append(BlobProperty::DataAsText, Value::Text(text.to_string()));
+                        }
+                        Err(_) => {
+                            blob.append(BlobProperty::DataAsText, Value::Null);
+                            blob.set(BlobProperty::IsEncodingProblem, Value::Bool(true));
+                        }
+                    },
+                    BlobProperty::DataAsBase64 => {
+                        blob.append(
+                            BlobProperty::DataAsBase64,
+                            Value::Text(general_purpose::STANDARD.encode(data)),
+                        );
+                    }
+                    BlobProperty::DigestSha => {
+                        let mut hasher = Sha1::new();
+                        hasher.update(data);
+                        blob.append(
+                            BlobProperty::DigestSha,
+                            Value::Text(general_purpose::STANDARD.encode(hasher.finalize())),
+                        );
+                    }
+                    BlobProperty::DigestSha256 => {
+                        let mut hasher = Sha256::new();
+                        hasher.update(data);
+                        blob.append(
+                            BlobProperty::DigestSha256,
+                            Value::Text(general_purpose::STANDARD.encode(hasher.finalize())),
+                        );
+                    }
+                    BlobProperty::Size => {
+                        blob.append(BlobProperty::Size, Value::UnsignedInt(bytes.len() as u64));
+                    }
+                    BlobProperty::Id
+                    | BlobProperty::IsEncodingProblem
+                    | BlobProperty::IsTruncated => (),
+                }
+            }
+            if is_truncated {
+                blob.append(BlobProperty::IsTruncated, Value::Bool(true));
+            }
+
+            response.list.push(blob);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn blob_lookup(
+        &self,
+        request: BlobLookupRequest,
+        access_token: &AccessToken,
+    ) -> Result<BlobLookupResponse, MethodError> {
+        if request.ids.len() > self.config.get_max_objects {
+            return Err(MethodError::RequestTooLarge);
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = BlobLookupResponse {
+            account_id: request.account_id,
+            list: Vec::with_capacity(request.ids.len()),
+            not_found: vec![],
+        };
+
+        for blob_id in request.ids {
+            if blob_id.account_id() != account_id
+                || !self.has_access_blob(&blob_id, access_token).await?
+            {
+                response.not_found.push(blob_id);
+                continue;
+            }
+
+            let mut matched_ids = VecMap::with_capacity(request.type_names.len());
+            match &blob_id.kind {
+                BlobKind::LinkedMaildir { document_id, .. } => {
+                    let thread_id = if let Some(thread_id) = self
+                        .get_property::<u32>(
+                            account_id,
+                            Collection::Email,
+                            *document_id,
+                            Property::ThreadId,
+                        )
+                        .await?
+                    {
+                        thread_id
+                    } else {
+                        response.not_found.push(blob_id);
+                        continue;
+                    };
+
+                    let email_id = Id::from_parts(thread_id, *document_id);
+                    for type_name in &request.type_names {
+                        let ids = match type_name {
+                            MethodObject::Email => vec![email_id],
+                            MethodObject::Thread => vec![Id::from(thread_id)],
+                            MethodObject::Mailbox => self
+                                .get_property::<Vec<u32>>(
+                                    account_id,
+                                    Collection::Email,
+                                    *document_id,
+                                    Property::MailboxIds,
+                                )
+                                .await?
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(Id::from)
+                                .collect(),
+                            MethodObject::EmailSubmission => self
+                                .filter(
+                                    account_id,
+                                    Collection::EmailSubmission,
+                                    vec![Filter::eq(Property::EmailId, email_id.id())],
+                                )
+                                .await?
+                                .results
+                                .into_iter()
+                                .map(Id::from)
+                                .collect(),
+                            _ => vec![],
+                        };
+                        matched_ids.append(type_name.to_string(), ids);
+                    }
+                }
+                BlobKind::Linked {
+                    collection,
+                    document_id,
+                    ..
+                } if Collection::from(*collection) == Collection::SieveScript => {
+                    if !self
+                        .get_document_ids(account_id, Collection::SieveScript)
+                        .await?
+                        .map_or(false, |ids| ids.contains(*document_id))
+                    {
+                        response.not_found.push(blob_id);
+                        continue;
+                    }
+
+                    for type_name in &request.type_names {
+                        matched_ids.append(
+                            type_name.to_string(),
+                            if type_name == &MethodObject::SieveScript {
+                                vec![Id::from(*document_id)]
+                            } else {
+                                vec![]
+                            },
+                        );
+                    }
+                }
+                _ => {
+                    if self.get_blob(&blob_id.kind, 0..1).await?.is_none() {
+                        response.not_found.push(blob_id);
+                        continue;
+                    }
+
+                    for type_name in &request.type_names {
+                        matched_ids.append(type_name.to_string(), vec![]);
+                    }
+                }
+            }
+
+            response.list.push(BlobInfo {
+                id: blob_id,
+                matched_ids,
+            });
+        }
+
+        Ok(response)
+    }
+}
@@ -24,10 +24,19 @@
 use std::sync::Arc;
 
 use jmap_proto::{
-    error::{method::MethodError, request::RequestError},
+    error::{
+        method::MethodError,
+        request::RequestError,
+        set::{SetError, SetErrorType},
+    },
+    method::upload::{
+        BlobUploadRequest, BlobUploadResponse, BlobUploadResponseObject, DataSourceObject,
+    },
+    request::reference::MaybeReference,
     types::{blob::BlobId, id::Id},
 };
 use store::BlobKind;
+use utils::map::vec_map::VecMap;
 
 use crate::{auth::AccessToken, JMAP};
 
@@ -112,6 +121,142 @@ impl JMAP {
         }
     }
 
+    pub async fn blob_upload_many(
+        &self,
+        request: BlobUploadRequest,
+        access_token: &AccessToken,
+    ) -> Result<BlobUploadResponse, MethodError> {
+        if request.create.len() > self.config.set_max_objects {
+            return Err(MethodError::RequestTooLarge);
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = BlobUploadResponse {
+            account_id: request.account_id,
+            created: VecMap::with_capacity(request.create.len()),
+            not_created: VecMap::new(),
+        };
+
+        'outer: for (create_id, upload_object) in request.create {
+            if upload_object.data.len() > self.config.set_max_objects {
+                response.not_created.append(
+                    create_id,
+                    SetError::new(SetErrorType::TooLarge).with_description(format!(
+                        "Too many data sources, maximum is {}.",
+                        self.config.set_max_objects
+                    )),
+                );
+                continue;
+            }
+            let mut data = Vec::new();
+
+            for data_source in upload_object.data {
+                let bytes = match data_source {
+                    DataSourceObject::Value(bytes) => bytes,
+                    DataSourceObject::Id { id, length, offset } => {
+                        let blob_id = match id {
+                            MaybeReference::Value(blob_id) => blob_id,
+                            MaybeReference::Reference(reference) => {
+                                if let Some(created) = response.created.get(&reference) {
+                                    created.id.clone()
+                                } else {
+                                    response.not_created.append(
+                                        create_id,
+                                        SetError::invalid_properties().with_description(format!(
+                                            "Creation id {reference:?} not found."
+                                        )),
+                                    );
+                                    continue 'outer;
+                                }
+                            }
+                        };
+
+                        let bytes = if let Some(bytes) =
+                            self.blob_download(&blob_id, access_token).await?
+                        {
+                            bytes
+                        } else {
+                            response.not_created.append(
+                                create_id,
+                                SetError::new(SetErrorType::BlobNotFound)
+                                    .with_description(format!("BlobId {blob_id} not found.")),
+                            );
+                            continue 'outer;
+                        };
+
+                        let offset = offset.unwrap_or(0);
+                        let length = length.unwrap_or(bytes.len().saturating_sub(offset));
+                        if let Some(bytes) = bytes.get(offset..offset.saturating_add(length)) {
+                            bytes.to_vec()
+                        } else {
+                            response.not_created.append(
+                                create_id,
+                                SetError::invalid_properties().with_description(format!(
+                                    "Invalid offset or length for blobId {blob_id}."
+                                )),
+                            );
+                            continue 'outer;
+                        }
+                    }
+                };
+
+                if data.len() + bytes.len() > self.config.upload_max_size {
+                    response.not_created.append(
+                        create_id,
+                        SetError::new(SetErrorType::TooLarge).with_description(format!(
+                            "Upload size exceeds maximum of {} bytes.",
+                            self.config.upload_max_size
+                        )),
+                    );
+                    continue 'outer;
+                }
+                data.extend(bytes);
+            }
+
+            // Enforce quota
+            let (total_files, total_bytes) = self
+                .store
+                .get_tmp_blob_usage(account_id, self.config.upload_tmp_ttl)
+                .await
+                .map_err(|err| {
+                    tracing::error!(event = "error",
+                    context = "blob_store",
+                    account_id = account_id,
+                    error = ?err,
+                    "Failed to obtain blob quota");
+                    MethodError::ServerPartialFail
+                })?;
+            if ((self.config.upload_tmp_quota_size > 0
+                && total_bytes + data.len() > self.config.upload_tmp_quota_size)
+                || (self.config.upload_tmp_quota_amount > 0
+                    && total_files + 1 > self.config.upload_tmp_quota_amount))
+                && !access_token.is_super_user()
+            {
+                response.not_created.append(
+                    create_id,
+                    SetError::over_quota().with_description(format!(
+                        "You have exceeded the blob upload quota of {} files or {} bytes.",
+                        self.config.upload_tmp_quota_amount, self.config.upload_tmp_quota_size
+                    )),
+                );
+                continue;
+            }
+
+            // Write blob
+            let blob_id = BlobId::temporary(account_id);
+            self.put_blob(&blob_id.kind, &data).await?;
+            response.created.append(
+                create_id,
+                BlobUploadResponseObject {
+                    id: blob_id,
+                    type_: upload_object.type_,
+                    size: data.len(),
+                },
+            );
+        }
+
+        Ok(response)
+    }
+
     pub async fn put_blob(&self, kind: &BlobKind, data: &[u8]) -> Result<(), MethodError> {
         self.store.put_blob(kind, data).await.map_err(|err| {
             tracing::error!(
@@ -0,0 +1,619 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::{
+        method::MethodError,
+        set::{SetError, SetErrorType},
+    },
+    method::set::{SetRequest, SetResponse},
+    object::{
+        calendar::SetArguments,
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        acl::Acl,
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::TypeState,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::{
+    auth::{acl::EffectiveAcl, AccessToken},
+    calendar_event::{recurrence::parse_time_zone, set::SCHEMA as EVENT_SCHEMA},
+    mailbox::set::MailboxSubscribe,
+    share_notification::ShareChange,
+    JMAP,
+};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Name)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .required(),
+    IndexProperty::new(Property::SortOrder).index_as(IndexAs::Integer),
+    IndexProperty::new(Property::IsDefault).index_as(IndexAs::HasProperty),
+    IndexProperty::new(Property::IsSubscribed).index_as(IndexAs::IntegerList),
+    IndexProperty::new(Property::Acl).index_as(IndexAs::Acl),
+];
+
+impl JMAP {
+    pub async fn calendar_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> Result<SetResponse, MethodError> {
+        let account_id = request.account_id.document_id();
+        let on_destroy_remove_events = request.arguments.on_destroy_remove_events.unwrap_or(false);
+        let is_shared = access_token.is_shared(account_id);
+        let mut calendar_ids = self.calendar_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::Calendar)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            if is_shared {
+                response.not_created.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to create calendars."),
+                );
+                continue;
+            }
+
+            match self
+                .calendar_set_item(object, None, &response, access_token)
+                .await?
+            {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    let document_id = self
+                        .assign_document_id(account_id, Collection::Calendar)
+                        .await?;
+                    let share_change = ShareChange::new(
+                        Collection::Calendar,
+                        account_id,
+                        document_id,
+                        builder.get(&Property::Name),
+                        &Value::Null,
+                        builder.get(&Property::Acl),
+                    );
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Calendar)
+                        .create_document(document_id)
+                        .custom(builder);
+                    changes.log_insert(Collection::Calendar, document_id);
+                    calendar_ids.insert(document_id);
+                    self.write_batch(batch).await?;
+                    response.created(id, document_id);
+
+                    // Notify sharees
+                    if let Some(share_change) = share_change {
+                        self.share_notification_send(access_token, share_change)
+                            .await;
+                    }
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain calendar
+            let document_id = id.document_id();
+            let calendar = if let Some(calendar) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::Calendar,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                calendar
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            // Validate ACL
+            if is_shared {
+                let acl = calendar.inner.effective_acl(access_token);
+                if !acl.contains(Acl::Modify) {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to modify this calendar."),
+                    );
+                    continue 'update;
+                } else if object.properties.contains_key(&Property::Acl)
+                    && !acl.contains(Acl::Administer)
+                {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden().with_description(
+                            "You are not allowed to change the permissions of this calendar.",
+                        ),
+                    );
+                    continue 'update;
+                }
+            }
+
+            let current_acl = calendar
+                .inner
+                .properties
+                .get(&Property::Acl)
+                .cloned()
+                .unwrap_or(Value::Null);
+            match self
+                .calendar_set_item(object, calendar.into(), &response, access_token)
+                .await?
+            {
+                Ok(builder) => {
+                    let share_change = ShareChange::new(
+                        Collection::Calendar,
+                        account_id,
+                        document_id,
+                        builder.get(&Property::Name),
+                        &current_acl,
+                        builder.get(&Property::Acl),
+                    );
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Calendar)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.store.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::Calendar, document_id);
+
+                                // Notify sharees
+                                if let Some(share_change) = share_change {
+                                    self.share_notification_send(access_token, share_change)
+                                        .await;
+                                }
+                            }
+                            Err(store::Error::AssertValueFailed) => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this calendar, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                tracing::error!(
+                                    event = "error",
+                                    context = "calendar_set",
+                                    account_id = account_id,
+                                    error = ?err,
+                                    "Failed to update calendar.");
+                                return Err(MethodError::ServerPartialFail);
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        let mut did_remove_events = false;
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if !calendar_ids.contains(document_id) {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            }
+
+            match self
+                .calendar_destroy(
+                    account_id,
+                    document_id,
+                    &mut changes,
+                    access_token,
+                    on_destroy_remove_events,
+                )
+                .await?
+            {
+                Ok(removed_events) => {
+                    did_remove_events |= removed_events;
+                    response.destroyed.push(id);
+                }
+                Err(err) => {
+                    response.not_destroyed.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let state_change =
+                StateChange::new(account_id).with_change(TypeState::Calendar, changes.change_id);
+            response.state_change = if did_remove_events {
+                state_change.with_change(TypeState::CalendarEvent, changes.change_id)
+            } else {
+                state_change
+            }
+            .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+
+    async fn calendar_destroy(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        changes: &mut ChangeLogBuilder,
+        access_token: &AccessToken,
+        remove_events: bool,
+    ) -> Result<Result<bool, SetError>, MethodError> {
+        // Obtain calendar
+        let calendar = if let Some(calendar) = self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                Collection::Calendar,
+                document_id,
+                Property::Value,
+            )
+            .await?
+        {
+            calendar
+        } else {
+            return Ok(Err(SetError::not_found()));
+        };
+
+        // The default calendar cannot be deleted
+        if calendar.inner.get(&Property::IsDefault) == &Value::Bool(true)
+            && !access_token.is_super_user()
+        {
+            return Ok(Err(SetError::forbidden().with_description(
+                "You are not allowed to delete the default calendar.",
+            )));
+        }
+
+        // Validate ACLs
+        if access_token.is_shared(account_id) {
+            let acl = calendar.inner.effective_acl(access_token);
+            if !acl.contains(Acl::Administer) {
+                if !acl.contains(Acl::Delete) {
+                    return Ok(Err(SetError::forbidden()
+                        .with_description("You are not allowed to delete this calendar.")));
+                } else if remove_events && !acl.contains(Acl::RemoveItems) {
+                    return Ok(Err(SetError::forbidden().with_description(
+                        "You are not allowed to delete events from this calendar.",
+                    )));
+                }
+            }
+        }
+
+        // Verify that the calendar is empty
+        let event_ids = self
+            .filter(
+                account_id,
+                Collection::CalendarEvent,
+                vec![Filter::eq(Property::CalendarIds, document_id)],
+            )
+            .await?
+            .results;
+        let did_remove_events = !event_ids.is_empty();
+        if did_remove_events {
+            if !remove_events {
+                return Ok(Err(SetError::new(SetErrorType::CalendarHasEvent)
+                    .with_description("Calendar is not empty.")));
+            }
+
+            // If the event belongs to multiple calendars, remove it from the current calendar,
+            // otherwise delete it.
+            for event_id in event_ids {
+                let event = if let Some(event) = self
+                    .get_property::<HashedValue<Object<Value>>>(
+                        account_id,
+                        Collection::CalendarEvent,
+                        event_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    event
+                } else {
+                    continue;
+                };
+                let calendar_ids = event
+                    .inner
+                    .get(&Property::CalendarIds)
+                    .as_list()
+                    .map(|ids| {
+                        ids.iter()
+                            .filter(|id| {
+                                !matches!(id, Value::Id(id) if id.document_id() == document_id)
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::CalendarEvent);
+                if !calendar_ids.is_empty() {
+                    batch.update_document(event_id).custom(
+                        ObjectIndexBuilder::new(EVENT_SCHEMA)
+                            .with_current(event)
+                            .with_changes(
+                                Object::with_capacity(1).with_property(
+                                    Property::CalendarIds,
+                                    Value::List(calendar_ids),
+                                ),
+                            ),
+                    );
+                    changes.log_update(Collection::CalendarEvent, event_id);
+                } else {
+                    batch
+                        .delete_document(event_id)
+                        .custom(ObjectIndexBuilder::new(EVENT_SCHEMA).with_current(event));
+                    changes.log_delete(Collection::CalendarEvent, event_id);
+                }
+                match self.store.write(batch.build()).await {
+                    Ok(_) => (),
+                    Err(store::Error::AssertValueFailed) => {
+                        return Ok(Err(SetError::forbidden().with_description(concat!(
+                            "Another process modified an event in this calendar ",
+                            "while deleting it, please try again."
+                        ))));
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            event = "error",
+                            context = "calendar_set",
+                            account_id = account_id,
+                            calendar_id = document_id,
+                            event_id = event_id,
+                            error = ?err,
+                            "Failed to update event while deleting calendar.");
+                        return Err(MethodError::ServerPartialFail);
+                    }
+                }
+            }
+        }
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Calendar)
+            .delete_document(document_id)
+            .custom(ObjectIndexBuilder::new(SCHEMA).with_current(calendar));
+
+        match self.store.write(batch.build()).await {
+            Ok(_) => {
+                changes.log_delete(Collection::Calendar, document_id);
+                Ok(Ok(did_remove_events))
+            }
+            Err(store::Error::AssertValueFailed) => Ok(Err(SetError::forbidden()
+                .with_description(concat!(
+                    "Another process modified this calendar ",
+                    "while deleting it, please try again."
+                )))),
+            Err(err) => {
+                tracing::error!(
+                    event = "error",
+                    context = "calendar_set",
+                    account_id = account_id,
+                    document_id = document_id,
+                    error = ?err,
+                    "Failed to delete calendar.");
+                Err(MethodError::ServerPartialFail)
+            }
+        }
+    }
+
+    async fn calendar_set_item(
+        &self,
+        changes_: Object<SetValue>,
+        current: Option<HashedValue<Object<Value>>>,
+        response: &SetResponse,
+        access_token: &AccessToken,
+    ) -> Result<Result<ObjectIndexBuilder, SetError>, MethodError> {
+        // Parse properties
+        let mut changes = Object::with_capacity(changes_.properties.len());
+        for (property, value) in changes_.properties {
+            let value = match response.eval_object_references(value) {
+                Ok(value) => value,
+                Err(err) => {
+                    return Ok(Err(err));
+                }
+            };
+            let value = match (&property, value) {
+                (Property::Name, MaybePatchValue::Value(Value::Text(value))) => {
+                    let value = value.trim();
+                    if !value.is_empty() && value.len() < self.config.calendar_name_max_len {
+                        Value::Text(value.to_string())
+                    } else {
+                        return Ok(Err(SetError::invalid_properties()
+                            .with_property(Property::Name)
+                            .with_description(
+                                if !value.is_empty() {
+                                    "Calendar name is too long."
+                                } else {
+                                    "Calendar name cannot be empty."
+                                }
+                                .to_string(),
+                            )));
+                    }
+                }
+                (
+                    Property::Description | Property::Color,
+                    MaybePatchValue::Value(value @ (Value::Text(_) | Value::Null)),
+                ) => value,
+                (Property::TimeZone, MaybePatchValue::Value(Value::Text(value))) => {
+                    if parse_time_zone(&value).is_some() {
+                        Value::Text(value)
+                    } else {
+                        return Ok(Err(SetError::invalid_properties()
+                            .with_property(Property::TimeZone)
+                            .with_description(format!("Unknown time zone {value:?}."))));
+                    }
+                }
+                (Property::TimeZone, MaybePatchValue::Value(Value::Null)) => Value::Null,
+                (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                    Value::UnsignedInt(value)
+                }
+                (Property::IsVisible, MaybePatchValue::Value(Value::Bool(value))) => {
+                    Value::Bool(value)
+                }
+                (Property::IsSubscribed, MaybePatchValue::Value(Value::Bool(subscribe))) => {
+                    if let Some(current) = current.as_ref() {
+                        if let Some(value) = current
+                            .inner
+                            .mailbox_subscribe(access_token.primary_id(), subscribe)
+                        {
+                            value
+                        } else {
+                            continue;
+                        }
+                    } else if subscribe {
+                        Value::List(vec![Value::Id(access_token.primary_id().into())])
+                    } else {
+                        continue;
+                    }
+                }
+                (Property::Acl, value) => {
+                    match self.acl_set(&mut changes, current.as_ref(), value).await {
+                        Ok(_) => continue,
+                        Err(err) => {
+                            return Ok(Err(err));
+                        }
+                    }
+                }
+                _ => {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string())))
+                }
+            };
+
+            changes.append(property, value);
+        }
+
+        // Refresh ACLs
+        if changes.properties.contains_key(&Property::Acl) {
+            self.refresh_acls(&changes, &current);
+        }
+
+        // Validate
+        Ok(ObjectIndexBuilder::new(SCHEMA)
+            .with_changes(changes)
+            .with_current_opt(current)
+            .validate())
+    }
+
+    pub async fn calendar_get_or_create(
+        &self,
+        account_id: u32,
+    ) -> Result<RoaringBitmap, MethodError> {
+        let mut calendar_ids = self
+            .get_document_ids(account_id, Collection::Calendar)
+            .await?
+            .unwrap_or_default();
+        if !calendar_ids.is_empty() {
+            return Ok(calendar_ids);
+        }
+
+        // Create the default calendar
+        let document_id = self
+            .assign_document_id(account_id, Collection::Calendar)
+            .await?;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Calendar)
+            .create_document(document_id)
+            .custom(
+                ObjectIndexBuilder::new(SCHEMA).with_changes(
+                    Object::with_capacity(3)
+                        .with_property(Property::Name, "Calendar")
+                        .with_property(Property::IsDefault, true)
+                        .with_property(Property::IsVisible, true),
+                ),
+            );
+        self.store.write(batch.build()).await.map_err(|err| {
+            tracing::error!(
+                event = "error",
+                context = "calendar_get_or_create",
+                error = ?err,
+                "Failed to create default calendar.");
+            MethodError::ServerPartialFail
+        })?;
+        calendar_ids.insert(document_id);
+
+        Ok(calendar_ids)
+    }
+
+    pub async fn calendar_get_default(&self, account_id: u32) -> Result<u32, MethodError> {
+        let calendar_ids = self.calendar_get_or_create(account_id).await?;
+        Ok(self
+            .filter(
+                account_id,
+                Collection::Calendar,
+                vec![Filter::is_in_bitmap(Property::IsDefault, ())],
+            )
+            .await?
+            .results
+            .min()
+            .or_else(|| calendar_ids.min())
+            .unwrap_or_default())
+    }
+}
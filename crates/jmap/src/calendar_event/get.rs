@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use chrono::{Duration, NaiveDateTime};
+use jmap_proto::{
+    error::method::MethodError,
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{
+        acl::Acl, collection::Collection, date::UTCDate, id::Id, property::Property, value::Value,
+    },
+};
+
+use crate::{auth::AccessToken, JMAP};
+
+use super::recurrence::{format_local_date_time, Recurrence};
+
+impl JMAP {
+    pub async fn calendar_event_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> Result<GetResponse, MethodError> {
+        let ids = request.unwrap_ids(self.config.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::CalendarIds,
+            Property::Uid,
+            Property::Title,
+            Property::Description,
+            Property::Start,
+            Property::Duration,
+            Property::TimeZone,
+            Property::ShowWithoutTime,
+            Property::RecurrenceId,
+            Property::RecurrenceRules,
+            Property::RecurrenceOverrides,
+            Property::Status,
+            Property::FreeBusyStatus,
+            Property::Privacy,
+            Property::Priority,
+            Property::Color,
+            Property::Locations,
+            Property::VirtualLocations,
+            Property::Participants,
+            Property::ReplyTo,
+            Property::Alerts,
+            Property::Sequence,
+            Property::Created,
+            Property::Updated,
+        ]);
+        let account_id = request.account_id.document_id();
+        let mut event_ids = self
+            .get_document_ids(account_id, Collection::CalendarEvent)
+            .await?
+            .unwrap_or_default();
+        if access_token.is_shared(account_id) {
+            event_ids &= self
+                .shared_events(access_token, account_id, Acl::ReadItems)
+                .await?;
+        }
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            event_ids
+                .iter()
+                .take(self.config.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::CalendarEvent)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the event object
+            let document_id = id.document_id();
+            if !event_ids.contains(document_id) {
+                response.not_found.push(id);
+                continue;
+            }
+            let values = if let Some(values) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    &Property::Value,
+                )
+                .await?
+            {
+                values
+            } else {
+                response.not_found.push(id);
+                continue;
+            };
+
+            // Expanded recurrence instances are encoded in the id prefix
+            let mut values = if id.prefix_id() != 0 {
+                if let Some(values) =
+                    self.calendar_event_instance(values, id.prefix_id() as i64 - 1)
+                {
+                    values
+                } else {
+                    response.not_found.push(id);
+                    continue;
+                }
+            } else {
+                values
+            };
+
+            let mut event = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::CalendarIds => {
+                        if let Value::List(ids) = values.remove(property) {
+                            let mut obj = Object::with_capacity(ids.len());
+                            for id in ids {
+                                if let Value::Id(id) = id {
+                                    obj.append(Property::_T(id.to_string()), true);
+                                }
+                            }
+                            Value::Object(obj)
+                        } else {
+                            Value::Null
+                        }
+                    }
+                    Property::UtcStart | Property::UtcEnd => match values.remove(property) {
+                        Value::UnsignedInt(timestamp) if timestamp != u64::MAX => {
+                            Value::Date(UTCDate::from_timestamp(timestamp as i64))
+                        }
+                        _ => Value::Null,
+                    },
+                    Property::Sequence => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    Property::Priority => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    Property::ShowWithoutTime => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::Status => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("confirmed".to_string())),
+                    Property::FreeBusyStatus => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("busy".to_string())),
+                    Property::Privacy => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("public".to_string())),
+                    Property::Duration => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("PT0S".to_string())),
+                    Property::Uid
+                    | Property::Title
+                    | Property::Description
+                    | Property::Start
+                    | Property::TimeZone
+                    | Property::RecurrenceId
+                    | Property::RecurrenceRules
+                    | Property::RecurrenceOverrides
+                    | Property::Color
+                    | Property::Locations
+                    | Property::VirtualLocations
+                    | Property::Participants
+                    | Property::ReplyTo
+                    | Property::Alerts
+                    | Property::Method
+                    | Property::ProdId
+                    | Property::Created
+                    | Property::Updated => values.remove(property),
+                    _ => Value::Null,
+                };
+
+                event.append(property.clone(), value);
+            }
+
+            // Add result to response
+            response.list.push(event);
+        }
+
+        Ok(response)
+    }
+
+    /// Builds an instance of a recurring event, which is identified by the number
+    /// of seconds between the start of the event and its recurrence id.
+    fn calendar_event_instance(
+        &self,
+        mut event: Object<Value>,
+        offset: i64,
+    ) -> Option<Object<Value>> {
+        let recurrence = Recurrence::from_event(&event, None)?;
+        let recurrence_id = recurrence.start + Duration::seconds(offset);
+        let utc_start = recurrence.to_timestamp(&recurrence_id);
+        let instance = recurrence
+            .instances(utc_start, utc_start + 1, self.config.calendar_max_instances)
+            .into_iter()
+            .find(|instance| instance.recurrence_id == recurrence_id)?;
+
+        // Apply the override patch, if any
+        let recurrence_id = format_local_date_time(&instance.recurrence_id);
+        let patch =
+            if let Value::Object(mut overrides) = event.remove(&Property::RecurrenceOverrides) {
+                overrides
+                    .properties
+                    .remove(&Property::_T(recurrence_id.clone()))
+            } else {
+                None
+            };
+        event.remove(&Property::RecurrenceRules);
+        event.set(Property::Start, recurrence_id.clone());
+        event.set(Property::RecurrenceId, recurrence_id);
+        if let Some(Value::Object(patch)) = patch {
+            for (property, value) in patch.properties {
+                match &property {
+                    Property::_T(name) if name.contains('/') || name == "excluded" => (),
+                    Property::Id | Property::CalendarIds | Property::Uid => (),
+                    _ => {
+                        event.set(property, value);
+                    }
+                }
+            }
+        }
+        event.set(
+            Property::UtcStart,
+            Value::UnsignedInt(instance.utc_start.max(0) as u64),
+        );
+        event.set(
+            Property::UtcEnd,
+            Value::UnsignedInt(instance.utc_end.max(0) as u64),
+        );
+
+        Some(event)
+    }
+}
+
+/// Returns the id of an instance of a recurring event.
+pub fn instance_id(
+    document_id: u32,
+    recurrence: &Recurrence,
+    recurrence_id: &NaiveDateTime,
+) -> Option<Id> {
+    u32::try_from((*recurrence_id - recurrence.start).num_seconds() + 1)
+        .ok()
+        .filter(|offset| *offset != 0)
+        .map(|offset| Id::from_parts(offset, document_id))
+}
@@ -0,0 +1,778 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::fmt::Write;
+
+use chrono::{Duration, NaiveDateTime};
+use jmap_proto::{
+    object::Object,
+    types::{date::UTCDate, property::Property, value::Value},
+};
+
+use super::{
+    mailto_address, participant_address, participant_has_role,
+    recurrence::{
+        format_duration, format_local_date_time, parse_duration, parse_ical_date_time,
+        parse_local_date_time, parse_time_zone, to_timestamp, RecurrenceRule,
+    },
+    JSCalendarObject,
+};
+
+/// A minimal iCalendar (RFC 5545) component, only the subset needed for
+/// iTIP scheduling is interpreted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<ContentLine>,
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentLine {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Component {
+    pub fn new(name: impl Into<String>) -> Self {
+        Component {
+            name: name.into(),
+            properties: vec![],
+            components: vec![],
+        }
+    }
+
+    /// Parses an iCalendar stream, returning its first VCALENDAR component.
+    pub fn parse(data: &str) -> Option<Self> {
+        let data = data
+            .replace("\r\n ", "")
+            .replace("\r\n\t", "")
+            .replace("\n ", "")
+            .replace("\n\t", "");
+        let mut stack: Vec<Component> = Vec::new();
+
+        for line in data.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let content_line = ContentLine::parse(line)?;
+            match content_line.name.as_str() {
+                "BEGIN" => {
+                    stack.push(Component::new(
+                        content_line.value.trim().to_ascii_uppercase(),
+                    ));
+                }
+                "END" => {
+                    let component = stack.pop()?;
+                    if !component
+                        .name
+                        .eq_ignore_ascii_case(content_line.value.trim())
+                    {
+                        return None;
+                    }
+                    if let Some(parent) = stack.last_mut() {
+                        parent.components.push(component);
+                    } else if component.name == "VCALENDAR" {
+                        return Some(component);
+                    }
+                }
+                _ => {
+                    stack.last_mut()?.properties.push(content_line);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn property(&self, name: &str) -> Option<&ContentLine> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    pub fn properties<'x>(&'x self, name: &'x str) -> impl Iterator<Item = &'x ContentLine> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name).map(|p| p.text())
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &Component> {
+        self.components.iter().filter(|c| c.name == "VEVENT")
+    }
+
+    pub fn method(&self) -> Option<String> {
+        self.property("METHOD")
+            .map(|p| p.value.trim().to_ascii_uppercase())
+    }
+
+    pub fn add(&mut self, line: ContentLine) {
+        self.properties.push(line);
+    }
+
+    pub fn write(&self, buf: &mut String) {
+        write_line(buf, &format!("BEGIN:{}", self.name));
+        for property in &self.properties {
+            property.write(buf);
+        }
+        for component in &self.components {
+            component.write(buf);
+        }
+        write_line(buf, &format!("END:{}", self.name));
+    }
+}
+
+impl ContentLine {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        ContentLine {
+            name: name.into(),
+            params: vec![],
+            value: value.into(),
+        }
+    }
+
+    pub fn text_value(name: impl Into<String>, value: &str) -> Self {
+        ContentLine::new(name, escape_text(value))
+    }
+
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut name = String::new();
+        let mut params = Vec::new();
+        let mut chars = line.char_indices();
+
+        // Parse name
+        let mut next = None;
+        for (pos, ch) in chars.by_ref() {
+            if ch == ';' || ch == ':' {
+                next = Some((pos, ch));
+                break;
+            }
+            name.push(ch);
+        }
+        let (mut pos, mut ch) = next?;
+
+        // Parse parameters
+        while ch == ';' {
+            let mut param_name = String::new();
+            let mut param_value = String::new();
+            let mut in_value = false;
+            let mut in_quotes = false;
+            let mut next = None;
+            for (pos_, ch_) in chars.by_ref() {
+                match ch_ {
+                    '"' if in_value => in_quotes = !in_quotes,
+                    '=' if !in_value => in_value = true,
+                    ';' | ':' if !in_quotes => {
+                        next = Some((pos_, ch_));
+                        break;
+                    }
+                    _ if in_value => param_value.push(ch_),
+                    _ => param_name.push(ch_),
+                }
+            }
+            (pos, ch) = next?;
+            params.push((param_name.trim().to_ascii_uppercase(), param_value));
+        }
+
+        Some(ContentLine {
+            name: name.trim().to_ascii_uppercase(),
+            params,
+            value: line.get(pos + 1..)?.to_string(),
+        })
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\\' {
+                match chars.next() {
+                    Some('n' | 'N') => text.push('\n'),
+                    Some(ch) => text.push(ch),
+                    None => (),
+                }
+            } else {
+                text.push(ch);
+            }
+        }
+        text
+    }
+
+    pub fn write(&self, buf: &mut String) {
+        let mut line = self.name.clone();
+        for (name, value) in &self.params {
+            if value.contains([';', ':', ',']) {
+                let _ = write!(line, ";{name}=\"{value}\"");
+            } else {
+                let _ = write!(line, ";{name}={value}");
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        write_line(buf, &line);
+    }
+}
+
+fn write_line(buf: &mut String, line: &str) {
+    // Fold lines longer than 75 octets
+    let mut line_len = 0;
+    for ch in line.chars() {
+        let ch_len = ch.len_utf8();
+        if line_len + ch_len > 75 {
+            buf.push_str("\r\n ");
+            line_len = 1;
+        }
+        buf.push(ch);
+        line_len += ch_len;
+    }
+    buf.push_str("\r\n");
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Converts a VEVENT to a JSCalendar event.
+pub fn vevent_to_jscalendar(vevent: &Component, calendar: &Component) -> Option<Object<Value>> {
+    let mut event = Object::with_capacity(16);
+    event.append(Property::Uid, vevent.text("UID")?);
+    if let Some(method) = calendar.method() {
+        event.append(Property::Method, method.to_ascii_lowercase());
+    }
+    if let Some(prod_id) = calendar.text("PRODID") {
+        event.append(Property::ProdId, prod_id);
+    }
+    if let Some(title) = vevent.text("SUMMARY") {
+        event.append(Property::Title, title);
+    }
+    if let Some(description) = vevent.text("DESCRIPTION") {
+        event.append(Property::Description, description);
+    }
+
+    // Start, duration and time zone
+    let dtstart = vevent.property("DTSTART")?;
+    let (start, is_utc) = parse_ical_date_time(&dtstart.value)?;
+    let time_zone = if is_utc {
+        Some("Etc/UTC".to_string())
+    } else {
+        dtstart
+            .param("TZID")
+            .filter(|tz| parse_time_zone(tz).is_some())
+            .map(|tz| tz.to_string())
+    };
+    let show_without_time = dtstart.param("VALUE") == Some("DATE");
+    event.append(Property::Start, format_local_date_time(&start));
+    if let Some(time_zone) = &time_zone {
+        event.append(Property::TimeZone, time_zone.as_str());
+    }
+    if show_without_time {
+        event.append(Property::ShowWithoutTime, true);
+    }
+    let duration = if let Some(duration) = vevent
+        .property("DURATION")
+        .and_then(|p| parse_duration(&p.value))
+    {
+        Some(duration)
+    } else if let Some(dtend) = vevent.property("DTEND") {
+        let (end, end_is_utc) = parse_ical_date_time(&dtend.value)?;
+        if end_is_utc == is_utc
+            && dtend.param("TZID").unwrap_or_default() == dtstart.param("TZID").unwrap_or_default()
+        {
+            Some(end - start)
+        } else {
+            let tz = time_zone.as_deref().and_then(parse_time_zone);
+            let end_tz = if end_is_utc {
+                parse_time_zone("Etc/UTC")
+            } else {
+                dtend.param("TZID").and_then(parse_time_zone)
+            };
+            Some(Duration::seconds(
+                to_timestamp(&end, end_tz.as_ref()) - to_timestamp(&start, tz.as_ref()),
+            ))
+        }
+    } else if show_without_time {
+        Some(Duration::days(1))
+    } else {
+        None
+    };
+    if let Some(duration) = duration.filter(|d| d.num_seconds() > 0) {
+        event.append(Property::Duration, format_duration(&duration));
+    }
+
+    // Recurrences
+    let rules = vevent
+        .properties("RRULE")
+        .filter_map(|p| RecurrenceRule::from_rrule(&p.value))
+        .map(|rule| Value::Object(rule.to_object()))
+        .collect::<Vec<_>>();
+    if !rules.is_empty() {
+        event.append(Property::RecurrenceRules, Value::List(rules));
+    }
+    let mut overrides = Object::with_capacity(0);
+    for exdate in vevent.properties("EXDATE") {
+        for value in exdate.value.split(',') {
+            if let Some((exdate, _)) = parse_ical_date_time(value) {
+                let mut patch = Object::with_capacity(1);
+                patch.set_key("excluded", true);
+                overrides.append(
+                    Property::_T(format_local_date_time(
+                        &exdate.date().and_time(start.time()),
+                    )),
+                    Value::Object(patch),
+                );
+            }
+        }
+    }
+    for rdate in vevent.properties("RDATE") {
+        for value in rdate.value.split(',') {
+            if let Some((rdate, _)) = parse_ical_date_time(value) {
+                overrides.append(
+                    Property::_T(format_local_date_time(&rdate)),
+                    Value::Object(Object::with_capacity(0)),
+                );
+            }
+        }
+    }
+    if !overrides.properties.is_empty() {
+        event.append(Property::RecurrenceOverrides, Value::Object(overrides));
+    }
+    if let Some(recurrence_id) = vevent
+        .property("RECURRENCE-ID")
+        .and_then(|p| parse_ical_date_time(&p.value))
+    {
+        event.append(
+            Property::RecurrenceId,
+            format_local_date_time(&recurrence_id.0),
+        );
+    }
+
+    // Scheduling properties
+    if let Some(sequence) = vevent
+        .property("SEQUENCE")
+        .and_then(|p| p.value.trim().parse::<u64>().ok())
+    {
+        event.append(Property::Sequence, Value::UnsignedInt(sequence));
+    }
+    if let Some(priority) = vevent
+        .property("PRIORITY")
+        .and_then(|p| p.value.trim().parse::<u64>().ok())
+    {
+        event.append(Property::Priority, Value::UnsignedInt(priority));
+    }
+    if let Some(status) = vevent.property("STATUS") {
+        let status = status.value.trim().to_ascii_lowercase();
+        if ["confirmed", "cancelled", "tentative"].contains(&status.as_str()) {
+            event.append(Property::Status, status);
+        }
+    }
+    if let Some(transp) = vevent.property("TRANSP") {
+        event.append(
+            Property::FreeBusyStatus,
+            if transp.value.trim().eq_ignore_ascii_case("TRANSPARENT") {
+                "free"
+            } else {
+                "busy"
+            },
+        );
+    }
+    if let Some(class) = vevent.property("CLASS") {
+        event.append(
+            Property::Privacy,
+            match class.value.trim().to_ascii_uppercase().as_str() {
+                "PRIVATE" => "private",
+                "CONFIDENTIAL" => "secret",
+                _ => "public",
+            },
+        );
+    }
+    for (name, property) in [
+        ("CREATED", Property::Created),
+        ("LAST-MODIFIED", Property::Updated),
+    ] {
+        if let Some((date, _)) = vevent
+            .property(name)
+            .and_then(|p| parse_ical_date_time(&p.value))
+        {
+            event.append(
+                property,
+                Value::Date(UTCDate::from_timestamp(date.timestamp())),
+            );
+        }
+    }
+    if let Some(location) = vevent.text("LOCATION").filter(|l| !l.is_empty()) {
+        let mut obj = Object::with_capacity(2);
+        obj.set_key("@type", "Location");
+        obj.set_key("name", location);
+        let mut locations = Object::with_capacity(1);
+        locations.append(Property::_T("1".to_string()), Value::Object(obj));
+        event.append(Property::Locations, Value::Object(locations));
+    }
+
+    // Participants
+    let mut participants: Vec<(String, Object<Value>)> = Vec::new();
+    if let Some(organizer) = vevent.property("ORGANIZER") {
+        if let Some(address) = mailto_address(&organizer.value) {
+            let mut reply_to = Object::with_capacity(1);
+            reply_to.set_key("imip", format!("mailto:{address}"));
+            event.append(Property::ReplyTo, Value::Object(reply_to));
+
+            let mut participant = new_participant(organizer, &address);
+            let mut roles = Object::with_capacity(1);
+            roles.set_key("owner", true);
+            participant.set_key("roles", Value::Object(roles));
+            participants.push((address, participant));
+        }
+    }
+    for attendee in vevent.properties("ATTENDEE") {
+        if let Some(address) = mailto_address(&attendee.value) {
+            let idx = if let Some(idx) = participants.iter().position(|(a, _)| a == &address) {
+                idx
+            } else {
+                participants.push((address.clone(), new_participant(attendee, &address)));
+                participants.len() - 1
+            };
+            let participant = &mut participants[idx].1;
+            let mut roles = participant
+                .get_key("roles")
+                .and_then(|roles| roles.as_obj())
+                .cloned()
+                .unwrap_or_else(|| Object::with_capacity(2));
+            roles.set_key("attendee", true);
+            match attendee
+                .param("ROLE")
+                .map(|r| r.to_ascii_uppercase())
+                .as_deref()
+            {
+                Some("CHAIR") => roles.set_key("chair", true),
+                Some("OPT-PARTICIPANT") => roles.set_key("optional", true),
+                Some("NON-PARTICIPANT") => roles.set_key("informational", true),
+                _ => (),
+            }
+            participant.set_key("roles", Value::Object(roles));
+            participant.set_key(
+                "participationStatus",
+                attendee
+                    .param("PARTSTAT")
+                    .unwrap_or("NEEDS-ACTION")
+                    .to_ascii_lowercase(),
+            );
+            if attendee
+                .param("RSVP")
+                .map_or(false, |rsvp| rsvp.eq_ignore_ascii_case("TRUE"))
+            {
+                participant.set_key("expectReply", true);
+            }
+            if let Some(kind) = attendee.param("CUTYPE") {
+                let kind = kind.to_ascii_lowercase();
+                if ["individual", "group", "resource", "location"].contains(&kind.as_str()) {
+                    participant.set_key("kind", kind);
+                }
+            }
+        }
+    }
+    if !participants.is_empty() {
+        let mut obj = Object::with_capacity(participants.len());
+        for (pos, (_, participant)) in participants.into_iter().enumerate() {
+            obj.append(
+                Property::_T(format!("p{}", pos + 1)),
+                Value::Object(participant),
+            );
+        }
+        event.append(Property::Participants, Value::Object(obj));
+    }
+
+    Some(event)
+}
+
+fn new_participant(line: &ContentLine, address: &str) -> Object<Value> {
+    let mut participant = Object::with_capacity(6);
+    participant.set_key("@type", "Participant");
+    if let Some(name) = line.param("CN").filter(|name| !name.is_empty()) {
+        participant.set_key("name", name);
+    }
+    participant.set_key("email", address);
+    let mut send_to = Object::with_capacity(1);
+    send_to.set_key("imip", format!("mailto:{address}"));
+    participant.set_key("sendTo", Value::Object(send_to));
+    participant
+}
+
+/// Converts a JSCalendar event to an iTIP message with the given method.
+/// When `only_participant` is set, only that attendee is included (used for replies).
+pub fn jscalendar_to_ical(
+    event: &Object<Value>,
+    method: &str,
+    only_participant: Option<&str>,
+    dtstamp: i64,
+) -> String {
+    let mut vevent = Component::new("VEVENT");
+    let time_zone = event.get(&Property::TimeZone).as_string();
+    let tz = time_zone.and_then(parse_time_zone);
+    let show_without_time = event.get(&Property::ShowWithoutTime) == &Value::Bool(true);
+
+    if let Some(uid) = event.get(&Property::Uid).as_string() {
+        vevent.add(ContentLine::text_value("UID", uid));
+    }
+    if let Some(dtstamp) = NaiveDateTime::from_timestamp_opt(dtstamp, 0) {
+        vevent.add(ContentLine::new(
+            "DTSTAMP",
+            dtstamp.format("%Y%m%dT%H%M%SZ").to_string(),
+        ));
+    }
+    vevent.add(ContentLine::new(
+        "SEQUENCE",
+        event
+            .get(&Property::Sequence)
+            .as_uint()
+            .unwrap_or(0)
+            .to_string(),
+    ));
+    if let Some(start) = event
+        .get(&Property::Start)
+        .as_string()
+        .and_then(parse_local_date_time)
+    {
+        vevent.add(local_date_time_line(
+            "DTSTART",
+            &start,
+            time_zone,
+            show_without_time,
+        ));
+    }
+    if let Some(duration) = event.get(&Property::Duration).as_string() {
+        vevent.add(ContentLine::new("DURATION", duration));
+    }
+    if let Some(recurrence_id) = event
+        .get(&Property::RecurrenceId)
+        .as_string()
+        .and_then(parse_local_date_time)
+    {
+        vevent.add(local_date_time_line(
+            "RECURRENCE-ID",
+            &recurrence_id,
+            time_zone,
+            show_without_time,
+        ));
+    }
+    if let Some(title) = event.get(&Property::Title).as_string() {
+        vevent.add(ContentLine::text_value("SUMMARY", title));
+    }
+    if let Some(description) = event.get(&Property::Description).as_string() {
+        vevent.add(ContentLine::text_value("DESCRIPTION", description));
+    }
+    if let Some(location) = event
+        .get(&Property::Locations)
+        .as_obj()
+        .and_then(|locations| locations.properties.values().next())
+        .and_then(|location| location.as_obj()?.get_key_str("name"))
+    {
+        vevent.add(ContentLine::text_value("LOCATION", location));
+    }
+    for rule in event
+        .get(&Property::RecurrenceRules)
+        .as_list()
+        .map(|rules| rules.as_slice())
+        .unwrap_or_default()
+    {
+        if let Some(rule) = rule.as_obj().and_then(RecurrenceRule::from_object) {
+            vevent.add(ContentLine::new("RRULE", rule.to_rrule(tz.as_ref())));
+        }
+    }
+    if let Some(overrides) = event.get(&Property::RecurrenceOverrides).as_obj() {
+        for (recurrence_id, patch) in overrides.properties.iter() {
+            if let Some(recurrence_id) = match recurrence_id {
+                Property::_T(recurrence_id) => parse_local_date_time(recurrence_id),
+                _ => None,
+            } {
+                let is_excluded = patch
+                    .as_obj()
+                    .and_then(|patch| patch.get_key("excluded"))
+                    .map_or(false, |excluded| excluded == &Value::Bool(true));
+                vevent.add(local_date_time_line(
+                    if is_excluded { "EXDATE" } else { "RDATE" },
+                    &recurrence_id,
+                    time_zone,
+                    show_without_time,
+                ));
+            }
+        }
+    }
+    if let Some(status) = event.get(&Property::Status).as_string() {
+        vevent.add(ContentLine::new("STATUS", status.to_ascii_uppercase()));
+    } else if method == "CANCEL" {
+        vevent.add(ContentLine::new("STATUS", "CANCELLED"));
+    }
+    if let Some(free_busy) = event.get(&Property::FreeBusyStatus).as_string() {
+        vevent.add(ContentLine::new(
+            "TRANSP",
+            if free_busy == "free" {
+                "TRANSPARENT"
+            } else {
+                "OPAQUE"
+            },
+        ));
+    }
+    if let Some(privacy) = event.get(&Property::Privacy).as_string() {
+        vevent.add(ContentLine::new(
+            "CLASS",
+            match privacy {
+                "private" => "PRIVATE",
+                "secret" => "CONFIDENTIAL",
+                _ => "PUBLIC",
+            },
+        ));
+    }
+    if let Some(priority) = event.get(&Property::Priority).as_uint() {
+        vevent.add(ContentLine::new("PRIORITY", priority.to_string()));
+    }
+
+    // Participants
+    let participants = event
+        .get(&Property::Participants)
+        .as_obj()
+        .map(|participants| {
+            participants
+                .properties
+                .values()
+                .filter_map(|participant| participant.as_obj())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let organizer = super::organizer_address(event);
+    if let Some(organizer) = &organizer {
+        let mut line = ContentLine::new("ORGANIZER", format!("mailto:{organizer}"));
+        if let Some(name) = participants
+            .iter()
+            .find(|p| participant_address(p).as_deref() == Some(organizer.as_str()))
+            .and_then(|p| p.get_key_str("name"))
+        {
+            line = line.with_param("CN", name);
+        }
+        vevent.add(line);
+    }
+    for participant in participants {
+        let address = if let Some(address) = participant_address(participant) {
+            address
+        } else {
+            continue;
+        };
+        if !participant_has_role(participant, "attendee")
+            || matches!(only_participant, Some(only) if only != address)
+        {
+            continue;
+        }
+        let mut line = ContentLine::new("ATTENDEE", format!("mailto:{address}"));
+        if let Some(name) = participant.get_key_str("name") {
+            line = line.with_param("CN", name);
+        }
+        line = line.with_param(
+            "ROLE",
+            if participant_has_role(participant, "chair") {
+                "CHAIR"
+            } else if participant_has_role(participant, "optional") {
+                "OPT-PARTICIPANT"
+            } else if participant_has_role(participant, "informational") {
+                "NON-PARTICIPANT"
+            } else {
+                "REQ-PARTICIPANT"
+            },
+        );
+        line = line.with_param(
+            "PARTSTAT",
+            participant
+                .get_key_str("participationStatus")
+                .unwrap_or("needs-action")
+                .to_ascii_uppercase(),
+        );
+        if participant.get_key("expectReply") == Some(&Value::Bool(true)) {
+            line = line.with_param("RSVP", "TRUE");
+        }
+        if let Some(kind) = participant.get_key_str("kind") {
+            line = line.with_param("CUTYPE", kind.to_ascii_uppercase());
+        }
+        vevent.add(line);
+    }
+
+    let mut calendar = Component::new("VCALENDAR");
+    calendar.add(ContentLine::new("VERSION", "2.0"));
+    calendar.add(ContentLine::new(
+        "PRODID",
+        concat!(
+            "-//Stalwart Labs Ltd.//Stalwart JMAP v",
+            env!("CARGO_PKG_VERSION"),
+            "//EN"
+        ),
+    ));
+    calendar.add(ContentLine::new("METHOD", method));
+    calendar.components.push(vevent);
+
+    let mut buf = String::with_capacity(1024);
+    calendar.write(&mut buf);
+    buf
+}
+
+fn local_date_time_line(
+    name: &str,
+    value: &NaiveDateTime,
+    time_zone: Option<&str>,
+    show_without_time: bool,
+) -> ContentLine {
+    if show_without_time {
+        ContentLine::new(name, value.format("%Y%m%d").to_string()).with_param("VALUE", "DATE")
+    } else {
+        match time_zone {
+            Some("Etc/UTC" | "UTC") => {
+                ContentLine::new(name, value.format("%Y%m%dT%H%M%SZ").to_string())
+            }
+            Some(time_zone) => ContentLine::new(name, value.format("%Y%m%dT%H%M%S").to_string())
+                .with_param("TZID", time_zone),
+            None => ContentLine::new(name, value.format("%Y%m%dT%H%M%S").to_string()),
+        }
+    }
+}
@@ -0,0 +1,590 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{borrow::Cow, sync::Arc};
+
+use jmap_proto::{
+    error::{
+        method::MethodError,
+        set::{SetError, SetErrorType},
+    },
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
+use mail_builder::{
+    headers::{address::Address, content_type::ContentType},
+    mime::{BodyPart, MimePart},
+    MessageBuilder,
+};
+use mail_parser::{HeaderValue, Message, MimeHeaders, PartType};
+use smtp::core::{NullIo, Session, SessionData, State};
+use smtp_proto::{MailFrom, RcptTo};
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, now},
+};
+use utils::listener::ServerInstance;
+
+use crate::JMAP;
+
+use super::{
+    ical::{jscalendar_to_ical, vevent_to_jscalendar, Component},
+    mailto_address, organizer_address, participant_address, participant_has_role, JSCalendarObject,
+};
+
+/// An iMIP message received by email.
+pub struct ItipMessage {
+    pub method: String,
+    pub calendar: Component,
+    pub from: Option<String>,
+}
+
+/// An iTIP message to be sent after an event is created, updated or destroyed.
+pub struct ItipOutgoing {
+    pub from: String,
+    pub recipients: Vec<String>,
+    pub method: &'static str,
+    pub event: Object<Value>,
+    pub only_participant: Option<String>,
+}
+
+impl ItipMessage {
+    pub fn parse(message: &Message<'_>) -> Option<Self> {
+        for part in &message.parts {
+            if !part.is_content_type("text", "calendar") {
+                continue;
+            }
+            let contents = match &part.body {
+                PartType::Text(text) => Cow::Borrowed(text.as_ref()),
+                PartType::Binary(bytes) | PartType::InlineBinary(bytes) => {
+                    String::from_utf8_lossy(bytes)
+                }
+                _ => continue,
+            };
+            let calendar = if let Some(calendar) = Component::parse(&contents) {
+                calendar
+            } else {
+                continue;
+            };
+            let method = if let Some(method) = calendar.method().or_else(|| {
+                part.content_type()
+                    .and_then(|ct| ct.attribute("method"))
+                    .map(|method| method.to_ascii_uppercase())
+            }) {
+                method
+            } else {
+                continue;
+            };
+
+            if ["REQUEST", "REPLY", "CANCEL"].contains(&method.as_str())
+                && calendar.events().next().is_some()
+            {
+                let from = match message.from() {
+                    HeaderValue::Address(addr) => addr.address.as_ref(),
+                    HeaderValue::AddressList(addrs) => {
+                        addrs.first().and_then(|addr| addr.address.as_ref())
+                    }
+                    _ => None,
+                }
+                .map(|address| address.to_lowercase());
+
+                return Some(ItipMessage {
+                    method,
+                    calendar,
+                    from,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+impl ItipOutgoing {
+    /// Determines which scheduling messages have to be sent after an event owned by
+    /// one of the given addresses changed. A `None` event means it was destroyed.
+    pub fn build(
+        addresses: &[String],
+        current: Option<&Object<Value>>,
+        event: Option<&Object<Value>>,
+    ) -> Vec<ItipOutgoing> {
+        let mut messages = Vec::new();
+        let organizer = if let Some(organizer) = event.or(current).and_then(organizer_address) {
+            organizer
+        } else {
+            return messages;
+        };
+
+        if addresses.contains(&organizer) {
+            let current_attendees = current
+                .map(|current| attendee_addresses(current, &organizer))
+                .unwrap_or_default();
+            if let Some(event) = event {
+                let attendees = attendee_addresses(event, &organizer);
+                let removed = current_attendees
+                    .into_iter()
+                    .filter(|address| !attendees.contains(address))
+                    .collect::<Vec<_>>();
+                if !attendees.is_empty() {
+                    messages.push(ItipOutgoing {
+                        from: organizer.clone(),
+                        recipients: attendees,
+                        method: if event.get(&Property::Status).as_string() == Some("cancelled") {
+                            "CANCEL"
+                        } else {
+                            "REQUEST"
+                        },
+                        event: event.clone(),
+                        only_participant: None,
+                    });
+                }
+                if let Some(current) = current.filter(|_| !removed.is_empty()) {
+                    messages.push(ItipOutgoing {
+                        from: organizer,
+                        recipients: removed,
+                        method: "CANCEL",
+                        event: current.clone(),
+                        only_participant: None,
+                    });
+                }
+            } else if let Some(current) = current.filter(|_| !current_attendees.is_empty()) {
+                messages.push(ItipOutgoing {
+                    from: organizer,
+                    recipients: current_attendees,
+                    method: "CANCEL",
+                    event: current.clone(),
+                    only_participant: None,
+                });
+            }
+        } else if let (Some(current), Some(event)) = (current, event) {
+            // Notify the organizer when one of our participation statuses changed
+            for address in addresses {
+                let status = participation_status(event, address);
+                if status.is_some() && status != participation_status(current, address) {
+                    messages.push(ItipOutgoing {
+                        from: address.clone(),
+                        recipients: vec![organizer],
+                        method: "REPLY",
+                        event: event.clone(),
+                        only_participant: address.clone().into(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+impl JMAP {
+    pub async fn calendar_account_addresses(
+        &self,
+        account_id: u32,
+    ) -> Result<Vec<String>, MethodError> {
+        let account_name = self.get_account_name(account_id).await?.unwrap_or_default();
+        Ok(self
+            .directory
+            .emails_by_name(&account_name)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|address| address.to_lowercase())
+            .collect())
+    }
+
+    pub async fn calendar_itip_send(
+        &self,
+        message: ItipOutgoing,
+        instance: &Arc<ServerInstance>,
+    ) -> Result<(), SetError> {
+        let title = message
+            .event
+            .get(&Property::Title)
+            .as_string()
+            .unwrap_or("Untitled event");
+        let subject = match message.method {
+            "REQUEST"
+                if message
+                    .event
+                    .get(&Property::Sequence)
+                    .as_uint()
+                    .unwrap_or(0)
+                    > 0 =>
+            {
+                format!("Updated invitation: {title}")
+            }
+            "REQUEST" => format!("Invitation: {title}"),
+            "CANCEL" => format!("Cancelled: {title}"),
+            _ => {
+                let status = message
+                    .only_participant
+                    .as_deref()
+                    .and_then(|address| participation_status(&message.event, address))
+                    .unwrap_or("needs-action");
+                format!(
+                    "{}: {title}",
+                    match status {
+                        "accepted" => "Accepted",
+                        "declined" => "Declined",
+                        "tentative" => "Tentatively accepted",
+                        _ => "Reply",
+                    }
+                )
+            }
+        };
+        let mut text_body = format!("{subject}\r\n");
+        if let Some(start) = message.event.get(&Property::Start).as_string() {
+            text_body.push_str(&format!(
+                "\r\nWhen: {} ({})\r\n",
+                start.replace('T', " "),
+                message
+                    .event
+                    .get(&Property::TimeZone)
+                    .as_string()
+                    .unwrap_or("floating time")
+            ));
+        }
+        if let Some(description) = message.event.get(&Property::Description).as_string() {
+            text_body.push_str(&format!("\r\n{description}\r\n"));
+        }
+        let ical = jscalendar_to_ical(
+            &message.event,
+            message.method,
+            message.only_participant.as_deref(),
+            now() as i64,
+        );
+
+        let itip_message = MessageBuilder::new()
+            .from(message.from.as_str())
+            .to(Address::new_list(
+                message
+                    .recipients
+                    .iter()
+                    .map(|rcpt| Address::from(rcpt.as_str()))
+                    .collect(),
+            ))
+            .subject(subject)
+            .body(MimePart::new(
+                ContentType::new("multipart/alternative"),
+                BodyPart::Multipart(vec![
+                    MimePart::new(
+                        ContentType::new("text/plain"),
+                        BodyPart::Text(text_body.into()),
+                    ),
+                    MimePart::new(
+                        ContentType::new("text/calendar")
+                            .attribute("method", message.method)
+                            .attribute("charset", "utf-8"),
+                        BodyPart::Text(ical.into()),
+                    ),
+                ]),
+            ))
+            .write_to_vec()
+            .unwrap_or_default();
+
+        // Submit message
+        let mut session =
+            Session::<NullIo>::local(self.smtp.clone(), instance.clone(), SessionData::default());
+        let _ = session
+            .handle_mail_from(MailFrom {
+                address: message.from,
+                ..Default::default()
+            })
+            .await;
+        if let Some(error) = session.has_failed() {
+            return Err(SetError::new(SetErrorType::ForbiddenMailFrom)
+                .with_description(format!("Server rejected MAIL-FROM: {}", error.trim())));
+        }
+        for rcpt in message.recipients {
+            let _ = session
+                .handle_rcpt_to(RcptTo {
+                    address: rcpt,
+                    ..Default::default()
+                })
+                .await;
+            if let Some(error) = session.has_failed() {
+                return Err(SetError::new(SetErrorType::InvalidRecipients)
+                    .with_description(format!("Server rejected RCPT-TO: {}", error.trim())));
+            }
+        }
+        session.data.message = itip_message;
+        let response = session.queue_message().await;
+        if let State::Accepted(_) = session.state {
+            Ok(())
+        } else {
+            Err(
+                SetError::new(SetErrorType::ForbiddenToSend).with_description(format!(
+                    "Server rejected DATA: {}",
+                    std::str::from_utf8(&response).unwrap_or_default().trim()
+                )),
+            )
+        }
+    }
+
+    /// Applies an incoming iMIP message to the calendars of an account, returning
+    /// the change id if any event was created or modified. Only messages whose
+    /// sender was authenticated, either by a local submission or by a DMARC
+    /// aligned SPF or DKIM pass, are processed.
+    pub async fn calendar_itip_ingest(
+        &self,
+        account_id: u32,
+        itip: &ItipMessage,
+        sender_address: &str,
+        sender_authenticated: bool,
+    ) -> Result<Option<u64>, MethodError> {
+        if !sender_authenticated {
+            tracing::debug!(
+                context = "calendar_itip",
+                event = "skip",
+                account_id = account_id,
+                sender = sender_address,
+                "Ignoring scheduling message from unauthenticated sender."
+            );
+            return Ok(None);
+        }
+
+        // DMARC authenticates the From header, the envelope sender is only used
+        // when it is missing
+        let sender =
+            if let Some(sender) = mailto_address(itip.from.as_deref().unwrap_or(sender_address)) {
+                sender
+            } else {
+                return Ok(None);
+            };
+        let mut changes = ChangeLogBuilder::new();
+
+        for vevent in itip.calendar.events() {
+            let mut itip_event =
+                if let Some(itip_event) = vevent_to_jscalendar(vevent, &itip.calendar) {
+                    itip_event
+                } else {
+                    continue;
+                };
+            itip_event.remove(&Property::Method);
+            let uid = itip_event
+                .get(&Property::Uid)
+                .as_string()
+                .unwrap_or_default()
+                .to_string();
+
+            // Obtain the stored event
+            let current = if let Some(document_id) = self
+                .filter(
+                    account_id,
+                    Collection::CalendarEvent,
+                    vec![Filter::eq(Property::Uid, uid.as_str())],
+                )
+                .await?
+                .results
+                .min()
+            {
+                self.get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+                .map(|current| (document_id, current))
+            } else {
+                None
+            };
+
+            let (document_id, current) = match (itip.method.as_str(), current) {
+                ("REQUEST", None) => {
+                    // New invitation, add it to the default calendar
+                    if organizer_address(&itip_event).as_ref() != Some(&sender) {
+                        continue;
+                    }
+                    let calendar_id = self.calendar_get_default(account_id).await?;
+                    itip_event.append(
+                        Property::CalendarIds,
+                        Value::List(vec![Value::Id(Id::from(calendar_id))]),
+                    );
+                    if let Some(document_id) = self
+                        .calendar_event_write(account_id, None, itip_event)
+                        .await?
+                    {
+                        changes.log_insert(Collection::CalendarEvent, document_id);
+                    }
+                    continue;
+                }
+                (_, Some(current)) => current,
+                _ => continue,
+            };
+
+            // Updates and cancellations are only accepted from the organizer of the
+            // stored event
+            let is_from_organizer = organizer_address(&current.inner).as_ref() == Some(&sender);
+            let mut event = current.inner.clone();
+            let recurrence_id = itip_event
+                .get(&Property::RecurrenceId)
+                .as_string()
+                .map(|recurrence_id| recurrence_id.to_string());
+            match itip.method.as_str() {
+                "REQUEST" if is_from_organizer => {
+                    if let Some(recurrence_id) = recurrence_id {
+                        // Updated instance of a recurring event
+                        let mut patch = Object::with_capacity(4);
+                        for property in [
+                            Property::Start,
+                            Property::Duration,
+                            Property::Title,
+                            Property::Status,
+                        ] {
+                            let value = itip_event.remove(&property);
+                            if value != Value::Null && &value != event.get(&property) {
+                                patch.append(property, value);
+                            }
+                        }
+                        override_mut(&mut event, recurrence_id).properties = patch.properties;
+                    } else {
+                        let sequence = itip_event.get(&Property::Sequence).as_uint().unwrap_or(0);
+                        let current_sequence =
+                            event.get(&Property::Sequence).as_uint().unwrap_or(0);
+                        if sequence < current_sequence {
+                            continue;
+                        }
+
+                        // Keep our own participation status unless the event was rescheduled
+                        if sequence == current_sequence {
+                            let addresses = self.calendar_account_addresses(account_id).await?;
+                            for address in &addresses {
+                                if let Some(status) = participation_status(&event, address) {
+                                    set_participation_status(&mut itip_event, address, status);
+                                }
+                            }
+                        }
+                        itip_event.set(
+                            Property::CalendarIds,
+                            event.get(&Property::CalendarIds).clone(),
+                        );
+                        event = itip_event;
+                    }
+                }
+                "REPLY" => {
+                    if let Some(status) = participation_status(&itip_event, &sender) {
+                        set_participation_status(&mut event, &sender, status);
+                    }
+                }
+                "CANCEL" if is_from_organizer => {
+                    if let Some(recurrence_id) = recurrence_id {
+                        let patch = override_mut(&mut event, recurrence_id);
+                        patch.properties.clear();
+                        patch.set_key("excluded", true);
+                    } else {
+                        event.set(Property::Status, "cancelled");
+                    }
+                }
+                _ => continue,
+            }
+
+            if event != current.inner
+                && self
+                    .calendar_event_write(account_id, Some((document_id, current)), event)
+                    .await?
+                    .is_some()
+            {
+                changes.log_update(Collection::CalendarEvent, document_id);
+            }
+        }
+
+        if !changes.is_empty() {
+            self.commit_changes(account_id, changes).await.map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn attendee_addresses(event: &Object<Value>, organizer: &str) -> Vec<String> {
+    event
+        .get(&Property::Participants)
+        .as_obj()
+        .map(|participants| {
+            participants
+                .properties
+                .values()
+                .filter_map(|participant| {
+                    let participant = participant.as_obj()?;
+                    if participant_has_role(participant, "attendee") {
+                        participant_address(participant).filter(|address| address != organizer)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn participation_status<'x>(event: &'x Object<Value>, address: &str) -> Option<&'x str> {
+    event
+        .get(&Property::Participants)
+        .as_obj()?
+        .properties
+        .values()
+        .filter_map(|participant| participant.as_obj())
+        .find(|participant| participant_address(participant).as_deref() == Some(address))?
+        .get_key_str("participationStatus")
+}
+
+fn set_participation_status(event: &mut Object<Value>, address: &str, status: &str) {
+    let status = status.to_string();
+    if let Some(participants) = event
+        .properties
+        .get_mut(&Property::Participants)
+        .and_then(|participants| participants.as_obj_mut())
+    {
+        for participant in participants.properties.values_mut() {
+            if let Some(participant) = participant.as_obj_mut() {
+                if participant_address(participant).as_deref() == Some(address) {
+                    participant.set_key("participationStatus", status);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn override_mut(event: &mut Object<Value>, recurrence_id: String) -> &mut Object<Value> {
+    let overrides = event
+        .properties
+        .get_mut_or_insert_with(Property::RecurrenceOverrides, || {
+            Value::Object(Object::with_capacity(1))
+        });
+    if !matches!(overrides, Value::Object(_)) {
+        *overrides = Value::Object(Object::with_capacity(1));
+    }
+    let overrides = overrides.as_obj_mut().unwrap();
+    let property = Property::_T(recurrence_id);
+    if !matches!(overrides.properties.get(&property), Some(Value::Object(_))) {
+        overrides.set(property.clone(), Value::Object(Object::with_capacity(1)));
+    }
+    overrides
+        .properties
+        .get_mut(&property)
+        .and_then(|patch| patch.as_obj_mut())
+        .unwrap()
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+pub mod get;
+pub mod ical;
+pub mod itip;
+pub mod query;
+pub mod recurrence;
+pub mod set;
+
+/// Nested JSCalendar objects (participants, recurrence rules, etc.) are parsed
+/// as generic objects, so their keys may be either a known property or a
+/// free-form name. These helpers look them up by their JSON name.
+pub trait JSCalendarObject {
+    fn get_key(&self, name: &str) -> Option<&Value>;
+    fn get_key_mut(&mut self, name: &str) -> Option<&mut Value>;
+    fn set_key(&mut self, name: &str, value: impl Into<Value>);
+    fn get_key_str(&self, name: &str) -> Option<&str>;
+}
+
+impl JSCalendarObject for Object<Value> {
+    fn get_key(&self, name: &str) -> Option<&Value> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property_is(property, name))
+            .map(|(_, value)| value)
+    }
+
+    fn get_key_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.properties
+            .iter_mut()
+            .find(|(property, _)| property_is(property, name))
+            .map(|(_, value)| value)
+    }
+
+    fn set_key(&mut self, name: &str, value: impl Into<Value>) {
+        let value = value.into();
+        if let Some(current) = self.get_key_mut(name) {
+            *current = value;
+        } else {
+            self.append(Property::_T(name.to_string()), value);
+        }
+    }
+
+    fn get_key_str(&self, name: &str) -> Option<&str> {
+        self.get_key(name).and_then(|value| value.as_string())
+    }
+}
+
+fn property_is(property: &Property, name: &str) -> bool {
+    match property {
+        Property::_T(property) => property == name,
+        property => property.to_string() == name,
+    }
+}
+
+/// Returns the lowercased email address of a "mailto:" URI.
+pub fn mailto_address(uri: &str) -> Option<String> {
+    let address = uri
+        .strip_prefix("mailto:")
+        .or_else(|| uri.strip_prefix("MAILTO:"))
+        .unwrap_or(uri)
+        .trim();
+    if address.contains('@') {
+        Some(address.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Returns the email address used to contact a participant over iMIP.
+pub fn participant_address(participant: &Object<Value>) -> Option<String> {
+    participant
+        .get_key("sendTo")
+        .and_then(|send_to| send_to.as_obj())
+        .and_then(|send_to| send_to.get_key_str("imip"))
+        .and_then(mailto_address)
+        .or_else(|| participant.get_key_str("email").and_then(mailto_address))
+}
+
+/// Returns the email address of the organizer of an event.
+pub fn organizer_address(event: &Object<Value>) -> Option<String> {
+    event
+        .get_key("replyTo")
+        .and_then(|reply_to| reply_to.as_obj())
+        .and_then(|reply_to| reply_to.get_key_str("imip"))
+        .and_then(mailto_address)
+        .or_else(|| {
+            event
+                .get(&Property::Participants)
+                .as_obj()?
+                .properties
+                .values()
+                .filter_map(|participant| participant.as_obj())
+                .find(|participant| participant_has_role(participant, "owner"))
+                .and_then(participant_address)
+        })
+}
+
+pub fn participant_has_role(participant: &Object<Value>, role: &str) -> bool {
+    participant
+        .get_key("roles")
+        .and_then(|roles| roles.as_obj())
+        .and_then(|roles| roles.get_key(role))
+        .map_or(false, |value| value == &Value::Bool(true))
+}
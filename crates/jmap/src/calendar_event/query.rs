@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    method::query::{Comparator, Filter, QueryRequest, QueryResponse, SortProperty},
+    object::{calendar::QueryArguments, Object},
+    types::{acl::Acl, collection::Collection, property::Property, value::Value},
+};
+use store::{
+    fts::Language,
+    query::{self, sort::Pagination},
+};
+
+use crate::{auth::AccessToken, UpdateResults, JMAP};
+
+use super::{
+    get::instance_id,
+    recurrence::{parse_time_zone, Recurrence},
+};
+
+impl JMAP {
+    pub async fn calendar_event_query(
+        &self,
+        mut request: QueryRequest<QueryArguments>,
+        access_token: &AccessToken,
+    ) -> Result<QueryResponse, MethodError> {
+        let account_id = request.account_id.document_id();
+        let mut filters = Vec::with_capacity(request.filter.len());
+        let mut range_after = None;
+        let mut range_before = None;
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InCalendars(calendar_ids) => {
+                    filters.push(query::Filter::Or);
+                    for calendar_id in calendar_ids {
+                        filters.push(query::Filter::eq(
+                            Property::CalendarIds,
+                            calendar_id.document_id(),
+                        ));
+                    }
+                    filters.push(query::Filter::End);
+                }
+                Filter::After(date) => {
+                    range_after = Some(date.timestamp());
+                    filters.push(query::Filter::gt(
+                        Property::UtcEnd,
+                        date.timestamp().max(0) as u64,
+                    ));
+                }
+                Filter::Before(date) => {
+                    range_before = Some(date.timestamp());
+                    filters.push(query::Filter::lt(
+                        Property::UtcStart,
+                        date.timestamp().max(0) as u64,
+                    ));
+                }
+                Filter::Text(text) => {
+                    filters.push(query::Filter::Or);
+                    filters.push(query::Filter::has_text(
+                        Property::Title,
+                        &text,
+                        Language::None,
+                    ));
+                    filters.push(query::Filter::has_text(
+                        Property::Description,
+                        &text,
+                        Language::None,
+                    ));
+                    filters.push(query::Filter::End);
+                }
+                Filter::Title(text) => filters.push(query::Filter::has_text(
+                    Property::Title,
+                    &text,
+                    Language::None,
+                )),
+                Filter::Description(text) => filters.push(query::Filter::has_text(
+                    Property::Description,
+                    &text,
+                    Language::None,
+                )),
+                Filter::Uid(uid) => filters.push(query::Filter::eq(Property::Uid, uid)),
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+
+                other => return Err(MethodError::UnsupportedFilter(other.to_string())),
+            }
+        }
+
+        let mut result_set = self
+            .filter(account_id, Collection::CalendarEvent, filters)
+            .await?;
+        if access_token.is_shared(account_id) {
+            result_set.apply_mask(
+                self.shared_events(access_token, account_id, Acl::ReadItems)
+                    .await?,
+            );
+        }
+        let (mut response, paginate) = self.build_query_response(&result_set, &request).await?;
+
+        // Expand recurrences
+        if request.arguments.expand_recurrences.unwrap_or(false) {
+            let (after, before) = match (range_after, range_before) {
+                (Some(after), Some(before))
+                    if before > after
+                        && before - after
+                            <= self.config.calendar_max_expand_duration.as_secs() as i64 =>
+                {
+                    (after, before)
+                }
+                (Some(_), Some(_)) => {
+                    return Err(MethodError::InvalidArguments(format!(
+                        "The time range to expand cannot exceed {} days.",
+                        self.config.calendar_max_expand_duration.as_secs() / 86400
+                    )));
+                }
+                _ => {
+                    return Err(MethodError::InvalidArguments(
+                        "Both 'after' and 'before' filters are required to expand recurrences."
+                            .to_string(),
+                    ));
+                }
+            };
+            let time_zone = if let Some(time_zone) = &request.arguments.time_zone {
+                Some(parse_time_zone(time_zone).ok_or_else(|| {
+                    MethodError::InvalidArguments(format!("Unknown time zone {time_zone:?}."))
+                })?)
+            } else {
+                None
+            };
+            let is_ascending = match request.sort.as_ref().and_then(|sort| sort.first()) {
+                Some(Comparator {
+                    property: SortProperty::Start,
+                    is_ascending,
+                    ..
+                }) => *is_ascending,
+                Some(comparator) => {
+                    return Err(MethodError::UnsupportedSort(
+                        comparator.property.to_string(),
+                    ))
+                }
+                None => true,
+            };
+
+            // Obtain the instances of each event within the range
+            let mut instances = Vec::new();
+            for document_id in &result_set.results {
+                let event = if let Some(event) = self
+                    .get_property::<Object<Value>>(
+                        account_id,
+                        Collection::CalendarEvent,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    event
+                } else {
+                    continue;
+                };
+                let recurrence = if let Some(recurrence) = Recurrence::from_event(&event, time_zone)
+                {
+                    recurrence
+                } else {
+                    continue;
+                };
+
+                if recurrence.is_recurrent() {
+                    let max_instances = self
+                        .config
+                        .calendar_max_instances
+                        .saturating_sub(instances.len());
+                    for instance in recurrence.instances(after, before, max_instances) {
+                        if let Some(id) =
+                            instance_id(document_id, &recurrence, &instance.recurrence_id)
+                        {
+                            instances.push((instance.utc_start, document_id, id.prefix_id()));
+                        }
+                    }
+                    if instances.len() >= self.config.calendar_max_instances {
+                        return Err(MethodError::InvalidArguments(format!(
+                            "The query expands to more than {} instances.",
+                            self.config.calendar_max_instances
+                        )));
+                    }
+                } else {
+                    instances.push((recurrence.to_timestamp(&recurrence.start), document_id, 0));
+                }
+            }
+            instances.sort_unstable_by(|a, b| if is_ascending { a.cmp(b) } else { b.cmp(a) });
+
+            // Paginate results
+            response.can_calculate_changes = false;
+            if response.total.is_some() {
+                response.total = Some(instances.len());
+            }
+            let limit = std::cmp::min(
+                request.limit.unwrap_or(self.config.query_max_results),
+                self.config.query_max_results,
+            );
+            response.limit = if instances.len() > limit {
+                Some(limit)
+            } else {
+                None
+            };
+            if limit > 0 && !instances.is_empty() {
+                let mut paginate = Pagination::new(
+                    std::cmp::min(limit, instances.len()),
+                    request.position.unwrap_or(0),
+                    request.anchor.map(|anchor| anchor.document_id()),
+                    request.anchor_offset.unwrap_or(0),
+                );
+                for (_, document_id, prefix_id) in instances {
+                    if !paginate.add(prefix_id, document_id) {
+                        break;
+                    }
+                }
+                response.update_results(paginate.build())?;
+            }
+
+            return Ok(response);
+        }
+
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Start)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Start => {
+                        query::Comparator::field(Property::UtcStart, comparator.is_ascending)
+                    }
+                    SortProperty::Uid => {
+                        query::Comparator::field(Property::Uid, comparator.is_ascending)
+                    }
+
+                    other => return Err(MethodError::UnsupportedSort(other.to_string())),
+                });
+            }
+
+            // Sort results
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
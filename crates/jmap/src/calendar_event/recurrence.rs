@@ -0,0 +1,916 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::fmt::Write;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+use super::JSCalendarObject;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+    Minutely,
+    Secondly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<(Weekday, Option<i32>)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instance {
+    pub recurrence_id: NaiveDateTime,
+    pub utc_start: i64,
+    pub utc_end: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recurrence {
+    pub start: NaiveDateTime,
+    pub duration: Duration,
+    pub time_zone: Option<Tz>,
+    pub rules: Vec<RecurrenceRule>,
+    pub excluded: Vec<NaiveDateTime>,
+    pub included: Vec<NaiveDateTime>,
+}
+
+// Maximum number of consecutive periods without matches before giving up
+const MAX_EMPTY_PERIODS: u32 = 1000;
+
+impl Recurrence {
+    pub fn from_event(event: &Object<Value>, default_time_zone: Option<Tz>) -> Option<Self> {
+        let start = parse_local_date_time(event.get(&Property::Start).as_string()?)?;
+        let duration = event
+            .get(&Property::Duration)
+            .as_string()
+            .and_then(parse_duration)
+            .unwrap_or_else(Duration::zero);
+        let time_zone = event
+            .get(&Property::TimeZone)
+            .as_string()
+            .and_then(parse_time_zone)
+            .or(default_time_zone);
+        let rules = event
+            .get(&Property::RecurrenceRules)
+            .as_list()
+            .map(|rules| {
+                rules
+                    .iter()
+                    .filter_map(|rule| RecurrenceRule::from_object(rule.as_obj()?))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut excluded = Vec::new();
+        let mut included = Vec::new();
+        if let Some(overrides) = event.get(&Property::RecurrenceOverrides).as_obj() {
+            for (recurrence_id, patch) in overrides.properties.iter() {
+                let recurrence_id = if let Property::_T(recurrence_id) = recurrence_id {
+                    if let Some(recurrence_id) = parse_local_date_time(recurrence_id) {
+                        recurrence_id
+                    } else {
+                        continue;
+                    }
+                } else {
+                    continue;
+                };
+                if patch
+                    .as_obj()
+                    .and_then(|patch| patch.get_key("excluded"))
+                    .map_or(false, |excluded| excluded == &Value::Bool(true))
+                {
+                    excluded.push(recurrence_id);
+                } else {
+                    included.push(recurrence_id);
+                }
+            }
+        }
+
+        Some(Recurrence {
+            start,
+            duration,
+            time_zone,
+            rules,
+            excluded,
+            included,
+        })
+    }
+
+    pub fn is_recurrent(&self) -> bool {
+        !self.rules.is_empty() || !self.included.is_empty()
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.count.is_none() && rule.until.is_none())
+    }
+
+    /// Returns the UTC start of the first instance and the UTC end of the
+    /// last instance, or `None` as the end if the event recurs forever.
+    pub fn utc_range(&self, max_instances: usize) -> (i64, Option<i64>) {
+        let utc_start = self.to_timestamp(&self.start);
+        if !self.is_recurrent() {
+            (utc_start, Some(utc_start + self.duration.num_seconds()))
+        } else if self.is_infinite() {
+            (utc_start, None)
+        } else {
+            let instances = self.instances(i64::MIN, i64::MAX, max_instances);
+            (
+                instances
+                    .first()
+                    .map_or(utc_start, |instance| instance.utc_start),
+                if instances.len() < max_instances {
+                    Some(
+                        instances
+                            .iter()
+                            .map(|instance| instance.utc_end)
+                            .max()
+                            .unwrap_or(utc_start + self.duration.num_seconds()),
+                    )
+                } else {
+                    None
+                },
+            )
+        }
+    }
+
+    /// Expands the instances overlapping the [after, before) UTC range.
+    pub fn instances(&self, after: i64, before: i64, max_instances: usize) -> Vec<Instance> {
+        // Any instance starting after this local time cannot fall within the range
+        let limit = if before != i64::MAX {
+            NaiveDateTime::from_timestamp_opt(before, 0).map(|dt| dt + Duration::days(2))
+        } else {
+            None
+        };
+        let duration = self.duration.num_seconds();
+
+        let mut recurrence_ids = vec![self.start];
+        for rule in &self.rules {
+            recurrence_ids.extend(rule.expand(self.start, limit, max_instances + 1));
+        }
+        recurrence_ids.extend(self.included.iter().copied());
+        recurrence_ids.sort_unstable();
+        recurrence_ids.dedup();
+
+        let mut instances = Vec::new();
+        for recurrence_id in recurrence_ids {
+            if self.excluded.contains(&recurrence_id) {
+                continue;
+            }
+            let utc_start = self.to_timestamp(&recurrence_id);
+            let utc_end = utc_start + duration;
+            if utc_start < before && (utc_end > after || (duration == 0 && utc_start >= after)) {
+                instances.push(Instance {
+                    recurrence_id,
+                    utc_start,
+                    utc_end,
+                });
+                if instances.len() >= max_instances {
+                    break;
+                }
+            }
+        }
+
+        instances
+    }
+
+    pub fn to_timestamp(&self, local: &NaiveDateTime) -> i64 {
+        to_timestamp(local, self.time_zone.as_ref())
+    }
+}
+
+impl RecurrenceRule {
+    pub fn from_object(rule: &Object<Value>) -> Option<Self> {
+        let frequency = Frequency::parse(rule.get_key_str("frequency")?)?;
+        let interval = rule
+            .get_key("interval")
+            .and_then(|interval| interval.as_uint())
+            .map_or(1, |interval| interval.clamp(1, u32::MAX as u64) as u32);
+        let by_day = rule
+            .get_key("byDay")
+            .and_then(|days| days.as_list())
+            .map(|days| {
+                days.iter()
+                    .filter_map(|day| {
+                        let day = day.as_obj()?;
+                        Some((
+                            parse_weekday(day.get_key_str("day")?)?,
+                            day.get_key("nthOfPeriod")
+                                .and_then(|nth| nth.as_uint())
+                                .filter(|nth| *nth > 0 && *nth <= 53)
+                                .map(|nth| nth as i32),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let by_month_day = rule
+            .get_key("byMonthDay")
+            .and_then(|days| days.as_list())
+            .map(|days| {
+                days.iter()
+                    .filter_map(|day| day.as_uint())
+                    .filter(|day| (1..=31).contains(day))
+                    .map(|day| day as i32)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let by_month = rule
+            .get_key("byMonth")
+            .and_then(|months| months.as_list())
+            .map(|months| {
+                months
+                    .iter()
+                    .filter_map(|month| match month {
+                        Value::Text(month) => month.parse::<u32>().ok(),
+                        Value::UnsignedInt(month) => Some(*month as u32),
+                        _ => None,
+                    })
+                    .filter(|month| (1..=12).contains(month))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let count = rule
+            .get_key("count")
+            .and_then(|count| count.as_uint())
+            .map(|count| count.clamp(1, u32::MAX as u64) as u32);
+        let until = rule.get_key_str("until").and_then(parse_local_date_time);
+
+        Some(RecurrenceRule {
+            frequency,
+            interval,
+            by_day,
+            by_month_day,
+            by_month,
+            count,
+            until,
+        })
+    }
+
+    pub fn to_object(&self) -> Object<Value> {
+        let mut rule = Object::with_capacity(7);
+        rule.set_key("@type", "RecurrenceRule");
+        rule.set_key("frequency", self.frequency.as_str());
+        if self.interval > 1 {
+            rule.set_key("interval", Value::UnsignedInt(self.interval as u64));
+        }
+        if !self.by_day.is_empty() {
+            rule.set_key(
+                "byDay",
+                Value::List(
+                    self.by_day
+                        .iter()
+                        .map(|(day, nth)| {
+                            let mut nday = Object::with_capacity(3);
+                            nday.set_key("@type", "NDay");
+                            nday.set_key("day", weekday_as_str(*day));
+                            if let Some(nth) = nth.filter(|nth| *nth > 0) {
+                                nday.set_key("nthOfPeriod", Value::UnsignedInt(nth as u64));
+                            }
+                            Value::Object(nday)
+                        })
+                        .collect(),
+                ),
+            );
+        }
+        if !self.by_month_day.is_empty() {
+            rule.set_key(
+                "byMonthDay",
+                Value::List(
+                    self.by_month_day
+                        .iter()
+                        .filter(|day| **day > 0)
+                        .map(|day| Value::UnsignedInt(*day as u64))
+                        .collect(),
+                ),
+            );
+        }
+        if !self.by_month.is_empty() {
+            rule.set_key(
+                "byMonth",
+                Value::List(
+                    self.by_month
+                        .iter()
+                        .map(|month| Value::Text(month.to_string()))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(count) = self.count {
+            rule.set_key("count", Value::UnsignedInt(count as u64));
+        }
+        if let Some(until) = &self.until {
+            rule.set_key("until", format_local_date_time(until));
+        }
+        rule
+    }
+
+    /// Parses an iCalendar RRULE value.
+    pub fn from_rrule(value: &str) -> Option<Self> {
+        let mut frequency = None;
+        let mut rule = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+            count: None,
+            until: None,
+        };
+
+        for part in value.split(';') {
+            let (name, value) = part.split_once('=')?;
+            match name.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Frequency::parse(&value.to_ascii_lowercase());
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse::<u32>().ok()?.max(1);
+                }
+                "COUNT" => {
+                    rule.count = value.parse::<u32>().ok()?.max(1).into();
+                }
+                "UNTIL" => {
+                    rule.until = parse_ical_date_time(value).map(|(dt, _)| dt);
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let day = day.trim();
+                        if day.len() < 2 || !day.is_ascii() {
+                            return None;
+                        }
+                        let (nth, day) = day.split_at(day.len() - 2);
+                        rule.by_day.push((
+                            parse_weekday(&day.to_ascii_lowercase())?,
+                            if !nth.is_empty() {
+                                nth.trim_start_matches('+').parse::<i32>().ok()
+                            } else {
+                                None
+                            },
+                        ));
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        let day = day.trim().parse::<i32>().ok()?;
+                        if day != 0 && (-31..=31).contains(&day) {
+                            rule.by_month_day.push(day);
+                        }
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        let month = month.trim().parse::<u32>().ok()?;
+                        if (1..=12).contains(&month) {
+                            rule.by_month.push(month);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        rule.frequency = frequency?;
+        Some(rule)
+    }
+
+    /// Writes the rule as an iCalendar RRULE value.
+    pub fn to_rrule(&self, time_zone: Option<&Tz>) -> String {
+        let mut rrule = format!("FREQ={}", self.frequency.as_str().to_ascii_uppercase());
+        if self.interval > 1 {
+            let _ = write!(rrule, ";INTERVAL={}", self.interval);
+        }
+        if let Some(count) = self.count {
+            let _ = write!(rrule, ";COUNT={count}");
+        }
+        if let Some(until) = &self.until {
+            // UNTIL has to be specified in UTC when the start has a time zone
+            if time_zone.is_some() {
+                if let Some(until) =
+                    NaiveDateTime::from_timestamp_opt(to_timestamp(until, time_zone), 0)
+                {
+                    let _ = write!(rrule, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"));
+                }
+            } else {
+                let _ = write!(rrule, ";UNTIL={}", until.format("%Y%m%dT%H%M%S"));
+            }
+        }
+        if !self.by_day.is_empty() {
+            rrule.push_str(";BYDAY=");
+            for (pos, (day, nth)) in self.by_day.iter().enumerate() {
+                if pos > 0 {
+                    rrule.push(',');
+                }
+                if let Some(nth) = nth {
+                    let _ = write!(rrule, "{nth}");
+                }
+                rrule.push_str(&weekday_as_str(*day).to_ascii_uppercase());
+            }
+        }
+        for (name, values) in [
+            (
+                "BYMONTHDAY",
+                self.by_month_day
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "BYMONTH",
+                self.by_month
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+        ] {
+            if !values.is_empty() {
+                let _ = write!(rrule, ";{name}={}", values.join(","));
+            }
+        }
+        rrule
+    }
+
+    /// Expands the rule in local time, returning at most `max` occurrences
+    /// starting no later than `limit`.
+    pub fn expand(
+        &self,
+        start: NaiveDateTime,
+        limit: Option<NaiveDateTime>,
+        max: usize,
+    ) -> Vec<NaiveDateTime> {
+        let mut occurrences = Vec::new();
+        let mut count = 0;
+        let mut empty_periods = 0;
+        let interval = self.interval as i64;
+        let time = start.time();
+
+        for period in 0.. {
+            let period = period as i64 * interval;
+            let mut candidates = match self.frequency {
+                Frequency::Yearly => {
+                    let year = start.year() + period as i32;
+                    if !self.by_month.is_empty() {
+                        self.by_month
+                            .iter()
+                            .flat_map(|month| self.month_days(year, *month, start))
+                            .collect::<Vec<_>>()
+                    } else if !self.by_month_day.is_empty() {
+                        (1..=12)
+                            .flat_map(|month| self.month_days(year, month, start))
+                            .collect::<Vec<_>>()
+                    } else if !self.by_day.is_empty() {
+                        match (
+                            NaiveDate::from_ymd_opt(year, 1, 1),
+                            NaiveDate::from_ymd_opt(year + 1, 1, 1),
+                        ) {
+                            (Some(from), Some(to)) => self.range_days(from, to),
+                            _ => break,
+                        }
+                    } else {
+                        NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                            .into_iter()
+                            .collect()
+                    }
+                }
+                Frequency::Monthly => {
+                    let month = start.month0() as i64 + period;
+                    let year = start.year() as i64 + month.div_euclid(12);
+                    let month = (month.rem_euclid(12) + 1) as u32;
+                    if year > i32::MAX as i64 {
+                        break;
+                    }
+                    if self.by_month.is_empty() || self.by_month.contains(&month) {
+                        self.month_days(year as i32, month, start)
+                    } else {
+                        vec![]
+                    }
+                }
+                Frequency::Weekly => {
+                    let week_start = start.date()
+                        - Duration::days(start.weekday().num_days_from_monday() as i64)
+                        + Duration::weeks(period);
+                    if self.by_day.is_empty() {
+                        vec![
+                            week_start
+                                + Duration::days(start.weekday().num_days_from_monday() as i64),
+                        ]
+                    } else {
+                        self.by_day
+                            .iter()
+                            .map(|(day, _)| {
+                                week_start + Duration::days(day.num_days_from_monday() as i64)
+                            })
+                            .filter(|date| {
+                                self.by_month.is_empty() || self.by_month.contains(&date.month())
+                            })
+                            .collect()
+                    }
+                }
+                Frequency::Daily => {
+                    let date = start.date() + Duration::days(period);
+                    if self.matches_date(&date) {
+                        vec![date]
+                    } else {
+                        vec![]
+                    }
+                }
+                Frequency::Hourly | Frequency::Minutely | Frequency::Secondly => {
+                    let candidate = start
+                        + match self.frequency {
+                            Frequency::Hourly => Duration::hours(period),
+                            Frequency::Minutely => Duration::minutes(period),
+                            _ => Duration::seconds(period),
+                        };
+                    if limit.map_or(false, |limit| candidate > limit)
+                        || self.until.map_or(false, |until| candidate > until)
+                    {
+                        break;
+                    }
+                    if self.matches_date(&candidate.date()) {
+                        count += 1;
+                        occurrences.push(candidate);
+                        if occurrences.len() >= max
+                            || self.count.map_or(false, |max_count| count >= max_count)
+                        {
+                            break;
+                        }
+                        empty_periods = 0;
+                    } else {
+                        empty_periods += 1;
+                        if empty_periods > MAX_EMPTY_PERIODS * 24 * 60 {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            candidates.sort_unstable();
+            candidates.dedup();
+            if candidates.is_empty() {
+                empty_periods += 1;
+                if empty_periods > MAX_EMPTY_PERIODS {
+                    break;
+                }
+                continue;
+            } else {
+                empty_periods = 0;
+            }
+
+            for date in candidates {
+                let candidate = date.and_time(time);
+                if candidate < start {
+                    continue;
+                } else if limit.map_or(false, |limit| candidate > limit)
+                    || self.until.map_or(false, |until| candidate > until)
+                {
+                    return occurrences;
+                }
+                count += 1;
+                occurrences.push(candidate);
+                if occurrences.len() >= max
+                    || self.count.map_or(false, |max_count| count >= max_count)
+                {
+                    return occurrences;
+                }
+            }
+        }
+
+        occurrences
+    }
+
+    fn month_days(&self, year: i32, month: u32, start: NaiveDateTime) -> Vec<NaiveDate> {
+        let (first, next) = match (
+            NaiveDate::from_ymd_opt(year, month, 1),
+            if month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)
+            },
+        ) {
+            (Some(first), Some(next)) => (first, next),
+            _ => return vec![],
+        };
+        let last_day = (next - first).num_days() as i32;
+
+        if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .filter_map(|day| {
+                    let day = if *day > 0 { *day } else { last_day + 1 + *day };
+                    if (1..=last_day).contains(&day) {
+                        NaiveDate::from_ymd_opt(year, month, day as u32)
+                    } else {
+                        None
+                    }
+                })
+                .filter(|date| {
+                    self.by_day.is_empty()
+                        || self.by_day.iter().any(|(day, _)| *day == date.weekday())
+                })
+                .collect()
+        } else if !self.by_day.is_empty() {
+            self.range_days(first, next)
+        } else {
+            NaiveDate::from_ymd_opt(year, month, start.day())
+                .into_iter()
+                .collect()
+        }
+    }
+
+    fn range_days(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        for (day, nth) in &self.by_day {
+            let mut matches = Vec::new();
+            let mut date = from
+                + Duration::days(
+                    (day.num_days_from_monday() as i64
+                        - from.weekday().num_days_from_monday() as i64)
+                        .rem_euclid(7),
+                );
+            while date < to {
+                matches.push(date);
+                date += Duration::weeks(1);
+            }
+            match nth {
+                Some(nth) if *nth > 0 => {
+                    if let Some(date) = matches.get(*nth as usize - 1) {
+                        dates.push(*date);
+                    }
+                }
+                Some(nth) if *nth < 0 => {
+                    if let Some(date) = matches
+                        .len()
+                        .checked_sub(nth.unsigned_abs() as usize)
+                        .and_then(|pos| matches.get(pos))
+                    {
+                        dates.push(*date);
+                    }
+                }
+                _ => {
+                    dates.extend(matches);
+                }
+            }
+        }
+        dates
+    }
+
+    fn matches_date(&self, date: &NaiveDate) -> bool {
+        (self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            && (self.by_day.is_empty() || self.by_day.iter().any(|(day, _)| *day == date.weekday()))
+            && (self.by_month_day.is_empty()
+                || self.by_month_day.iter().any(|day| {
+                    *day == date.day() as i32
+                        || (*day < 0 && days_in_month(date) + 1 + *day == date.day() as i32)
+                }))
+    }
+}
+
+impl Frequency {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "yearly" => Some(Frequency::Yearly),
+            "monthly" => Some(Frequency::Monthly),
+            "weekly" => Some(Frequency::Weekly),
+            "daily" => Some(Frequency::Daily),
+            "hourly" => Some(Frequency::Hourly),
+            "minutely" => Some(Frequency::Minutely),
+            "secondly" => Some(Frequency::Secondly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Yearly => "yearly",
+            Frequency::Monthly => "monthly",
+            Frequency::Weekly => "weekly",
+            Frequency::Daily => "daily",
+            Frequency::Hourly => "hourly",
+            Frequency::Minutely => "minutely",
+            Frequency::Secondly => "secondly",
+        }
+    }
+}
+
+fn days_in_month(date: &NaiveDate) -> i32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day() as i32)
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "mo" => Some(Weekday::Mon),
+        "tu" => Some(Weekday::Tue),
+        "we" => Some(Weekday::Wed),
+        "th" => Some(Weekday::Thu),
+        "fr" => Some(Weekday::Fri),
+        "sa" => Some(Weekday::Sat),
+        "su" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_as_str(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "mo",
+        Weekday::Tue => "tu",
+        Weekday::Wed => "we",
+        Weekday::Thu => "th",
+        Weekday::Fri => "fr",
+        Weekday::Sat => "sa",
+        Weekday::Sun => "su",
+    }
+}
+
+pub fn parse_time_zone(value: &str) -> Option<Tz> {
+    value.parse::<Tz>().ok()
+}
+
+/// Converts a local date-time to a UTC timestamp. Floating times are
+/// interpreted as UTC.
+pub fn to_timestamp(local: &NaiveDateTime, time_zone: Option<&Tz>) -> i64 {
+    if let Some(time_zone) = time_zone {
+        match time_zone.from_local_datetime(local).earliest() {
+            Some(dt) => dt.timestamp(),
+            None => {
+                // Local time falls within a DST gap
+                time_zone
+                    .from_local_datetime(&(*local + Duration::hours(1)))
+                    .earliest()
+                    .map_or_else(|| local.timestamp(), |dt| dt.timestamp())
+            }
+        }
+    } else {
+        local.timestamp()
+    }
+}
+
+/// Converts a UTC timestamp to the local date-time of a time zone.
+pub fn from_timestamp(timestamp: i64, time_zone: Option<&Tz>) -> Option<NaiveDateTime> {
+    let utc = NaiveDateTime::from_timestamp_opt(timestamp, 0)?;
+    Some(if let Some(time_zone) = time_zone {
+        time_zone.from_utc_datetime(&utc).naive_local()
+    } else {
+        utc
+    })
+}
+
+/// Parses a JSCalendar LocalDateTime such as "2023-06-08T10:00:00".
+pub fn parse_local_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+pub fn format_local_date_time(value: &NaiveDateTime) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Parses an iCalendar DATE or DATE-TIME value, returning whether it is in UTC.
+pub fn parse_ical_date_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    if let Some(value) = value.strip_suffix('Z').or_else(|| value.strip_suffix('z')) {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|dt| (dt, true))
+    } else if value.len() == 8 {
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|dt| (dt, false))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|dt| (dt, false))
+    }
+}
+
+/// Parses an ISO 8601 duration as used by JSCalendar and iCalendar,
+/// such as "P1DT2H30M" or "-PT15M".
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (is_negative, value) = if let Some(value) = value.strip_prefix('-') {
+        (true, value)
+    } else {
+        (false, value.strip_prefix('+').unwrap_or(value))
+    };
+    let value = value
+        .strip_prefix('P')
+        .or_else(|| value.strip_prefix('p'))?;
+
+    let mut duration = Duration::zero();
+    let mut is_time = false;
+    let mut number = String::new();
+    let mut has_items = false;
+    for ch in value.chars() {
+        match ch {
+            '0'..='9' | '.' => {
+                number.push(ch);
+                continue;
+            }
+            'T' | 't' => {
+                if is_time || !number.is_empty() {
+                    return None;
+                }
+                is_time = true;
+                continue;
+            }
+            _ => (),
+        }
+        let amount = number.parse::<f64>().ok()?;
+        number.clear();
+        has_items = true;
+        let seconds = match (ch.to_ascii_uppercase(), is_time) {
+            ('W', false) => amount * 604800.0,
+            ('D', false) => amount * 86400.0,
+            ('H', true) => amount * 3600.0,
+            ('M', true) => amount * 60.0,
+            ('S', true) => amount,
+            _ => return None,
+        };
+        duration = duration + Duration::milliseconds((seconds * 1000.0) as i64);
+    }
+
+    if number.is_empty() && has_items {
+        Some(if is_negative { -duration } else { duration })
+    } else {
+        None
+    }
+}
+
+pub fn format_duration(duration: &Duration) -> String {
+    let mut result = String::with_capacity(12);
+    let mut seconds = duration.num_seconds();
+    if seconds < 0 {
+        result.push('-');
+        seconds = -seconds;
+    }
+    result.push('P');
+    let days = seconds / 86400;
+    seconds %= 86400;
+    if days > 0 {
+        if days % 7 == 0 && seconds == 0 {
+            let _ = write!(result, "{}W", days / 7);
+            return result;
+        }
+        let _ = write!(result, "{days}D");
+    }
+    if seconds > 0 || days == 0 {
+        result.push('T');
+        let hours = seconds / 3600;
+        let minutes = (seconds % 3600) / 60;
+        seconds %= 60;
+        if hours > 0 {
+            let _ = write!(result, "{hours}H");
+        }
+        if minutes > 0 {
+            let _ = write!(result, "{minutes}M");
+        }
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            let _ = write!(result, "{seconds}S");
+        }
+    }
+    result
+}
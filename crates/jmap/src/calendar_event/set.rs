@@ -0,0 +1,756 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap_proto::{
+    error::{method::MethodError, set::SetError},
+    method::set::{SetRequest, SetResponse},
+    object::{
+        calendar::EventSetArguments,
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        acl::Acl,
+        collection::Collection,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        type_state::TypeState,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    rand::{thread_rng, Rng},
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+use utils::listener::ServerInstance;
+
+use crate::{auth::AccessToken, JMAP};
+
+use super::{
+    itip::ItipOutgoing,
+    organizer_address,
+    recurrence::{
+        parse_duration, parse_local_date_time, parse_time_zone, Recurrence, RecurrenceRule,
+    },
+};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::CalendarIds)
+        .index_as(IndexAs::IntegerList)
+        .required(),
+    IndexProperty::new(Property::Uid)
+        .index_as(IndexAs::Text {
+            tokenize: false,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::Title).index_as(IndexAs::Text {
+        tokenize: true,
+        index: true,
+    }),
+    IndexProperty::new(Property::Description).index_as(IndexAs::Text {
+        tokenize: true,
+        index: false,
+    }),
+    IndexProperty::new(Property::UtcStart).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::UtcEnd).index_as(IndexAs::LongInteger),
+];
+
+impl JMAP {
+    pub async fn calendar_event_set(
+        &self,
+        mut request: SetRequest<EventSetArguments>,
+        access_token: &AccessToken,
+        instance: &Arc<ServerInstance>,
+    ) -> Result<SetResponse, MethodError> {
+        let account_id = request.account_id.document_id();
+        let calendar_ids = self.calendar_get_or_create(account_id).await?;
+        let event_ids = self
+            .get_document_ids(account_id, Collection::CalendarEvent)
+            .await?
+            .unwrap_or_default();
+        let mut response = self
+            .prepare_set_response(&request, Collection::CalendarEvent)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Obtain the addresses used for scheduling
+        let addresses = if request.arguments.send_scheduling_messages.unwrap_or(false)
+            && self.config.calendar_itip_enable
+        {
+            self.calendar_account_addresses(account_id).await?
+        } else {
+            vec![]
+        };
+        let mut itip_messages = Vec::new();
+
+        // Obtain ACLs
+        let (can_add_calendar_ids, can_remove_calendar_ids, can_modify_event_ids) =
+            if access_token.is_shared(account_id) {
+                (
+                    self.shared_documents(
+                        access_token,
+                        account_id,
+                        Collection::Calendar,
+                        Acl::AddItems,
+                    )
+                    .await?
+                    .into(),
+                    self.shared_documents(
+                        access_token,
+                        account_id,
+                        Collection::Calendar,
+                        Acl::RemoveItems,
+                    )
+                    .await?
+                    .into(),
+                    self.shared_events(access_token, account_id, Acl::ModifyItems)
+                        .await?
+                        .into(),
+                )
+            } else {
+                (None, None, None)
+            };
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        'create: for (id, object) in request.unwrap_create() {
+            let (builder, event) = match self
+                .calendar_event_set_item(object, None, &calendar_ids, &addresses, &response)
+                .await?
+            {
+                Ok(result) => result,
+                Err(err) => {
+                    response.not_created.append(id, err);
+                    continue 'create;
+                }
+            };
+
+            // Validate ACLs
+            if let Some(can_add_calendar_ids) = &can_add_calendar_ids {
+                if event_calendar_ids(&event).any(|id| !can_add_calendar_ids.contains(id)) {
+                    response.not_created.append(
+                        id,
+                        SetError::forbidden().with_description(
+                            "You are not allowed to add events to one or more calendars.",
+                        ),
+                    );
+                    continue 'create;
+                }
+            }
+
+            let document_id = self
+                .assign_document_id(account_id, Collection::CalendarEvent)
+                .await?;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::CalendarEvent)
+                .create_document(document_id)
+                .custom(builder);
+            self.write_batch(batch).await?;
+            changes.log_insert(Collection::CalendarEvent, document_id);
+            if !addresses.is_empty() {
+                itip_messages.extend(ItipOutgoing::build(&addresses, None, Some(&event)));
+            }
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Expanded instances are read-only
+            let document_id = id.document_id();
+            if id.prefix_id() != 0 {
+                response.not_updated.append(
+                    id,
+                    SetError::forbidden().with_description(concat!(
+                        "Recurrence instances cannot be modified, ",
+                        "update the recurrenceOverrides of the main event instead."
+                    )),
+                );
+                continue 'update;
+            } else if matches!(&can_modify_event_ids, Some(ids) if !ids.contains(document_id)) {
+                response.not_updated.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to modify this event."),
+                );
+                continue 'update;
+            }
+
+            // Obtain event
+            let current = if let Some(current) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                current
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let current_event = current.inner.clone();
+
+            let (builder, event) = match self
+                .calendar_event_set_item(
+                    object,
+                    current.into(),
+                    &calendar_ids,
+                    &addresses,
+                    &response,
+                )
+                .await?
+            {
+                Ok(result) => result,
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                    continue 'update;
+                }
+            };
+
+            // Validate calendar ACLs
+            if let (Some(can_add_calendar_ids), Some(can_remove_calendar_ids)) =
+                (&can_add_calendar_ids, &can_remove_calendar_ids)
+            {
+                let current_calendar_ids = event_calendar_ids(&current_event).collect::<Vec<_>>();
+                let calendar_ids = event_calendar_ids(&event).collect::<Vec<_>>();
+                if calendar_ids.iter().any(|id| {
+                    !current_calendar_ids.contains(id) && !can_add_calendar_ids.contains(*id)
+                }) || current_calendar_ids
+                    .iter()
+                    .any(|id| !calendar_ids.contains(id) && !can_remove_calendar_ids.contains(*id))
+                {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden().with_description(
+                            "You are not allowed to move events between these calendars.",
+                        ),
+                    );
+                    continue 'update;
+                }
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::CalendarEvent)
+                .update_document(document_id)
+                .custom(builder);
+            if !batch.is_empty() {
+                match self.store.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_update(Collection::CalendarEvent, document_id);
+                        if !addresses.is_empty() {
+                            itip_messages.extend(ItipOutgoing::build(
+                                &addresses,
+                                Some(&current_event),
+                                Some(&event),
+                            ));
+                        }
+                    }
+                    Err(store::Error::AssertValueFailed) => {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this event, please try again.",
+                            ),
+                        );
+                        continue 'update;
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            event = "error",
+                            context = "calendar_event_set",
+                            account_id = account_id,
+                            error = ?err,
+                            "Failed to update event.");
+                        return Err(MethodError::ServerPartialFail);
+                    }
+                }
+            }
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        if !will_destroy.is_empty() {
+            let can_destroy_event_ids = if access_token.is_shared(account_id) {
+                self.shared_events(access_token, account_id, Acl::RemoveItems)
+                    .await?
+                    .into()
+            } else {
+                None
+            };
+
+            for id in will_destroy {
+                let document_id = id.document_id();
+                if id.prefix_id() != 0 || !event_ids.contains(document_id) {
+                    response.not_destroyed.append(id, SetError::not_found());
+                    continue;
+                } else if matches!(&can_destroy_event_ids, Some(ids) if !ids.contains(document_id))
+                {
+                    response.not_destroyed.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to delete this event."),
+                    );
+                    continue;
+                }
+
+                let current = if let Some(current) = self
+                    .get_property::<HashedValue<Object<Value>>>(
+                        account_id,
+                        Collection::CalendarEvent,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    current
+                } else {
+                    response.not_destroyed.append(id, SetError::not_found());
+                    continue;
+                };
+                let current_event = current.inner.clone();
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::CalendarEvent)
+                    .delete_document(document_id)
+                    .custom(ObjectIndexBuilder::new(SCHEMA).with_current(current));
+                match self.store.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_delete(Collection::CalendarEvent, document_id);
+                        if !addresses.is_empty() {
+                            itip_messages.extend(ItipOutgoing::build(
+                                &addresses,
+                                Some(&current_event),
+                                None,
+                            ));
+                        }
+                        response.destroyed.push(id);
+                    }
+                    Err(store::Error::AssertValueFailed) => {
+                        response.not_destroyed.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this event, please try again.",
+                            ),
+                        );
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            event = "error",
+                            context = "calendar_event_set",
+                            account_id = account_id,
+                            document_id = document_id,
+                            error = ?err,
+                            "Failed to delete event.");
+                        return Err(MethodError::ServerPartialFail);
+                    }
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let new_state = self.commit_changes(account_id, changes).await?;
+            response.state_change = StateChange::new(account_id)
+                .with_change(TypeState::CalendarEvent, new_state)
+                .into();
+            response.new_state = Some(new_state.into());
+        }
+
+        // Send scheduling messages
+        for message in itip_messages {
+            let method = message.method;
+            if let Err(err) = self.calendar_itip_send(message, instance).await {
+                tracing::debug!(
+                    context = "calendar_event_set",
+                    event = "itip",
+                    account_id = account_id,
+                    method = method,
+                    reason = ?err,
+                    "Failed to send scheduling message.");
+            }
+        }
+
+        Ok(response)
+    }
+
+    async fn calendar_event_set_item(
+        &self,
+        changes_: Object<SetValue>,
+        current: Option<HashedValue<Object<Value>>>,
+        calendar_ids: &RoaringBitmap,
+        addresses: &[String],
+        response: &SetResponse,
+    ) -> Result<Result<(ObjectIndexBuilder, Object<Value>), SetError>, MethodError> {
+        let mut new_calendar_ids = current
+            .as_ref()
+            .map(|current| event_calendar_ids(&current.inner).collect::<Vec<_>>());
+        let mut changes = Object::with_capacity(changes_.properties.len());
+
+        for (property, value) in changes_.properties {
+            let value = match response.eval_object_references(value) {
+                Ok(value) => value,
+                Err(err) => {
+                    return Ok(Err(err));
+                }
+            };
+            let (is_valid, value) = match (&property, value) {
+                (Property::CalendarIds, MaybePatchValue::Value(Value::List(ids))) => {
+                    new_calendar_ids = ids
+                        .into_iter()
+                        .map(|id| id.unwrap_id().document_id())
+                        .collect::<Vec<_>>()
+                        .into();
+                    continue;
+                }
+                (Property::CalendarIds, MaybePatchValue::Patch(patch)) => {
+                    let mut patch = patch.into_iter();
+                    let document_id = patch.next().unwrap().unwrap_id().document_id();
+                    let ids = new_calendar_ids.get_or_insert_with(Vec::new);
+                    if patch.next().unwrap().unwrap_bool() {
+                        if !ids.contains(&document_id) {
+                            ids.push(document_id);
+                        }
+                    } else {
+                        ids.retain(|id| id != &document_id);
+                    }
+                    continue;
+                }
+                (Property::Uid, MaybePatchValue::Value(Value::Text(uid))) => {
+                    if current.is_none() {
+                        changes.append(Property::Uid, uid);
+                        continue;
+                    } else if current.as_ref().map_or(false, |current| {
+                        current.inner.get(&Property::Uid).as_string() == Some(uid.as_str())
+                    }) {
+                        continue;
+                    } else {
+                        return Ok(Err(SetError::invalid_properties()
+                            .with_property(Property::Uid)
+                            .with_description("The uid of an event cannot be changed.")));
+                    }
+                }
+                (
+                    Property::Start | Property::RecurrenceId,
+                    MaybePatchValue::Value(value @ Value::Text(_)),
+                ) => {
+                    let is_valid = value.as_string().and_then(parse_local_date_time).is_some();
+                    (is_valid, value)
+                }
+                (Property::Duration, MaybePatchValue::Value(value @ Value::Text(_))) => {
+                    let is_valid = value.as_string().and_then(parse_duration).is_some();
+                    (is_valid, value)
+                }
+                (Property::TimeZone, MaybePatchValue::Value(value @ Value::Text(_))) => {
+                    let is_valid = value.as_string().and_then(parse_time_zone).is_some();
+                    (is_valid, value)
+                }
+                (Property::Status, MaybePatchValue::Value(value @ Value::Text(_))) => (
+                    matches!(
+                        value.as_string(),
+                        Some("confirmed" | "cancelled" | "tentative")
+                    ),
+                    value,
+                ),
+                (Property::FreeBusyStatus, MaybePatchValue::Value(value @ Value::Text(_))) => {
+                    (matches!(value.as_string(), Some("free" | "busy")), value)
+                }
+                (Property::Privacy, MaybePatchValue::Value(value @ Value::Text(_))) => (
+                    matches!(value.as_string(), Some("public" | "private" | "secret")),
+                    value,
+                ),
+                (Property::Priority, MaybePatchValue::Value(Value::UnsignedInt(priority))) => {
+                    (priority <= 9, Value::UnsignedInt(priority))
+                }
+                (Property::Participants, MaybePatchValue::Value(value @ Value::Object(_))) => (
+                    value.as_obj().map_or(0, |p| p.properties.len())
+                        <= self.config.calendar_max_participants,
+                    value,
+                ),
+                (Property::RecurrenceRules, MaybePatchValue::Value(value @ Value::List(_))) => (
+                    value.as_list().map_or(false, |rules| {
+                        rules.iter().all(|rule| {
+                            rule.as_obj()
+                                .and_then(RecurrenceRule::from_object)
+                                .is_some()
+                        })
+                    }),
+                    value,
+                ),
+                (
+                    Property::RecurrenceOverrides,
+                    MaybePatchValue::Value(value @ Value::Object(_)),
+                ) => (
+                    value.as_obj().map_or(false, |overrides| {
+                        overrides.properties.iter().all(|(recurrence_id, patch)| {
+                            matches!(recurrence_id, Property::_T(recurrence_id)
+                                if parse_local_date_time(recurrence_id).is_some())
+                                && matches!(patch, Value::Object(_))
+                        })
+                    }),
+                    value,
+                ),
+                (
+                    Property::Title
+                    | Property::Description
+                    | Property::Color
+                    | Property::Method
+                    | Property::ProdId,
+                    MaybePatchValue::Value(value @ Value::Text(_)),
+                )
+                | (Property::ShowWithoutTime, MaybePatchValue::Value(value @ Value::Bool(_)))
+                | (Property::Sequence, MaybePatchValue::Value(value @ Value::UnsignedInt(_)))
+                | (
+                    Property::Created | Property::Updated,
+                    MaybePatchValue::Value(value @ Value::Date(_)),
+                )
+                | (
+                    Property::Locations
+                    | Property::VirtualLocations
+                    | Property::Alerts
+                    | Property::ReplyTo,
+                    MaybePatchValue::Value(value @ Value::Object(_)),
+                )
+                | (
+                    Property::Title
+                    | Property::Description
+                    | Property::Color
+                    | Property::Method
+                    | Property::ProdId
+                    | Property::RecurrenceId
+                    | Property::Duration
+                    | Property::TimeZone
+                    | Property::Status
+                    | Property::FreeBusyStatus
+                    | Property::Privacy
+                    | Property::Priority
+                    | Property::ShowWithoutTime
+                    | Property::Participants
+                    | Property::RecurrenceRules
+                    | Property::RecurrenceOverrides
+                    | Property::Locations
+                    | Property::VirtualLocations
+                    | Property::Alerts
+                    | Property::ReplyTo
+                    | Property::Created
+                    | Property::Updated,
+                    MaybePatchValue::Value(value @ Value::Null),
+                ) => (true, value),
+                _ => (false, Value::Null),
+            };
+
+            if is_valid {
+                changes.append(property, value);
+            } else {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string())));
+            }
+        }
+
+        // Validate calendarIds
+        if let Some(ids) = new_calendar_ids {
+            if ids.is_empty() {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::CalendarIds)
+                    .with_description(
+                        "Event has to belong to at least one calendar.",
+                    )));
+            }
+            for id in &ids {
+                if !calendar_ids.contains(*id) {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(Property::CalendarIds)
+                        .with_description(format!(
+                            "calendarId {} does not exist.",
+                            Id::from(*id)
+                        ))));
+                }
+            }
+            changes.append(
+                Property::CalendarIds,
+                Value::List(ids.into_iter().map(|id| Value::Id(id.into())).collect()),
+            );
+        }
+
+        // Assign a uid to new events
+        if current.is_none() && !changes.properties.contains_key(&Property::Uid) {
+            changes.append(
+                Property::Uid,
+                format!("{:032x}", thread_rng().gen::<u128>()),
+            );
+        }
+
+        // Build the resulting event
+        let mut event = current
+            .as_ref()
+            .map(|current| current.inner.clone())
+            .unwrap_or_else(|| Object::with_capacity(changes.properties.len()));
+        for (property, value) in changes.properties.iter() {
+            if value != &Value::Null {
+                event.set(property.clone(), value.clone());
+            } else {
+                event.remove(property);
+            }
+        }
+        if event.get(&Property::Start) == &Value::Null {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::Start)
+                .with_description("Property cannot be empty.")));
+        }
+
+        // The organizer increments the sequence number on every update
+        if let Some(current) = &current {
+            if !changes.properties.contains_key(&Property::Sequence)
+                && organizer_address(&event)
+                    .map_or(false, |organizer| addresses.contains(&organizer))
+            {
+                let sequence = current
+                    .inner
+                    .get(&Property::Sequence)
+                    .as_uint()
+                    .unwrap_or(0)
+                    + 1;
+                changes.append(Property::Sequence, Value::UnsignedInt(sequence));
+                event.set(Property::Sequence, Value::UnsignedInt(sequence));
+            }
+        }
+
+        // Update the indexed time range
+        for (property, value) in self.calendar_event_utc_range(&event) {
+            changes.set(property.clone(), value.clone());
+            event.set(property, value);
+        }
+
+        // Validate
+        Ok(ObjectIndexBuilder::new(SCHEMA)
+            .with_changes(changes)
+            .with_current_opt(current)
+            .validate()
+            .map(|builder| (builder, event)))
+    }
+
+    /// Returns the UTC start of the first instance and the UTC end of the last
+    /// instance of an event. Events that recur forever end at `u64::MAX`.
+    pub fn calendar_event_utc_range(&self, event: &Object<Value>) -> [(Property, Value); 2] {
+        let (utc_start, utc_end) = Recurrence::from_event(event, None)
+            .map(|recurrence| recurrence.utc_range(self.config.calendar_max_instances))
+            .unwrap_or((0, Some(0)));
+        [
+            (
+                Property::UtcStart,
+                Value::UnsignedInt(utc_start.max(0) as u64),
+            ),
+            (
+                Property::UtcEnd,
+                Value::UnsignedInt(utc_end.map_or(u64::MAX, |utc_end| utc_end.max(0) as u64)),
+            ),
+        ]
+    }
+
+    /// Writes an event modified outside of CalendarEvent/set, such as by an
+    /// incoming iTIP message. Returns the document id if the event was written.
+    pub async fn calendar_event_write(
+        &self,
+        account_id: u32,
+        current: Option<(u32, HashedValue<Object<Value>>)>,
+        mut event: Object<Value>,
+    ) -> Result<Option<u32>, MethodError> {
+        for (property, value) in self.calendar_event_utc_range(&event) {
+            event.set(property, value);
+        }
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::CalendarEvent);
+        let document_id = if let Some((document_id, current)) = current {
+            // Remove properties that are no longer present
+            let mut changes = event;
+            for property in current.inner.properties.keys() {
+                if !changes.properties.contains_key(property) {
+                    changes.append(property.clone(), Value::Null);
+                }
+            }
+            batch.update_document(document_id).custom(
+                ObjectIndexBuilder::new(SCHEMA)
+                    .with_current(current)
+                    .with_changes(changes),
+            );
+            document_id
+        } else {
+            let document_id = self
+                .assign_document_id(account_id, Collection::CalendarEvent)
+                .await?;
+            batch
+                .create_document(document_id)
+                .custom(ObjectIndexBuilder::new(SCHEMA).with_changes(event));
+            document_id
+        };
+
+        match self.store.write(batch.build()).await {
+            Ok(_) => Ok(Some(document_id)),
+            Err(store::Error::AssertValueFailed) => Ok(None),
+            Err(err) => {
+                tracing::error!(
+                    event = "error",
+                    context = "calendar_event_write",
+                    account_id = account_id,
+                    document_id = document_id,
+                    error = ?err,
+                    "Failed to write event.");
+                Err(MethodError::ServerPartialFail)
+            }
+        }
+    }
+}
+
+fn event_calendar_ids(event: &Object<Value>) -> impl Iterator<Item = u32> + '_ {
+    event
+        .get(&Property::CalendarIds)
+        .as_list()
+        .map(|ids| ids.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|id| id.as_id().map(|id| id.document_id()))
+}
@@ -62,6 +62,30 @@ impl JMAP {
 
                 Collection::EmailSubmission
             }
+            RequestArguments::Calendar => {
+                access_token.assert_has_access(request.account_id, Collection::Calendar)?;
+
+                Collection::Calendar
+            }
+            RequestArguments::CalendarEvent => {
+                access_token.assert_has_access(request.account_id, Collection::CalendarEvent)?;
+
+                Collection::CalendarEvent
+            }
+            RequestArguments::ShareNotification => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::ShareNotification
+            }
+            RequestArguments::Principal => {
+                if !self.config.principal_allow_lookups && !access_token.is_super_user() {
+                    return Err(MethodError::Forbidden(
+                        "Principal lookups are disabled".to_string(),
+                    ));
+                }
+
+                Collection::Principal
+            }
         };
 
         let max_changes = if self.config.changes_max_results > 0
@@ -81,7 +105,11 @@ impl JMAP {
             destroyed: vec![],
             updated_properties: None,
         };
-        let account_id = request.account_id.document_id();
+        let account_id = if collection != Collection::Principal {
+            request.account_id.document_id()
+        } else {
+            u32::MAX
+        };
 
         let (items_sent, mut changelog) = match &request.since_state {
             State::Initial => {
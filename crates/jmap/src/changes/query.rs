@@ -51,6 +51,18 @@ impl JMAP {
                         query::RequestArguments::EmailSubmission => {
                             changes::RequestArguments::EmailSubmission
                         }
+                        query::RequestArguments::CalendarEvent(arguments) => {
+                            if arguments.expand_recurrences.unwrap_or(false) {
+                                return Err(MethodError::InvalidArguments(
+                                    "Changes cannot be calculated for expanded recurrences."
+                                        .to_string(),
+                                ));
+                            }
+                            changes::RequestArguments::CalendarEvent
+                        }
+                        query::RequestArguments::ShareNotification => {
+                            changes::RequestArguments::ShareNotification
+                        }
                         _ => return Err(MethodError::UnknownMethod("Unknown method".to_string())),
                     },
                 },
@@ -80,7 +92,9 @@ impl JMAP {
                 calculate_total: request.calculate_total,
                 arguments: query::RequestArguments::EmailSubmission,
             };
-            let is_mutable = query.filter.iter().any(|f| !f.is_immutable())
+            // Event times can be modified, so calendar event queries are always mutable
+            let is_mutable = matches!(request.arguments, query::RequestArguments::CalendarEvent(_))
+                || query.filter.iter().any(|f| !f.is_immutable())
                 || query
                     .sort
                     .as_ref()
@@ -97,6 +111,13 @@ impl JMAP {
                 query::RequestArguments::EmailSubmission => {
                     self.email_submission_query(query).await?
                 }
+                query::RequestArguments::CalendarEvent(arguments) => {
+                    self.calendar_event_query(query.with_arguments(arguments), access_token)
+                        .await?
+                }
+                query::RequestArguments::ShareNotification => {
+                    self.share_notification_query(query).await?
+                }
                 _ => unreachable!(),
             };
 
@@ -56,6 +56,8 @@ impl JMAP {
             changes.change_id = self.assign_change_id(account_id).await?;
         }
         let state = changes.change_id;
+        self.mailbox_saved_search_log_changes(account_id, &mut changes)
+            .await?;
 
         let mut builder = BatchBuilder::new();
         builder.with_account_id(account_id).custom(changes);
@@ -51,15 +51,15 @@ use mail_parser::parsers::fields::thread::thread_name;
 use store::{
     fts::term_index::TokenIndex,
     query::RawValue,
-    write::{BatchBuilder, F_BITMAP, F_VALUE},
+    write::{now, BatchBuilder, F_BITMAP, F_VALUE},
     BlobKind,
 };
 use utils::map::vec_map::VecMap;
 
-use crate::{auth::AccessToken, JMAP};
+use crate::{auth::AccessToken, thread::summary::ThreadSummaryChange, JMAP};
 
 use super::{
-    index::{EmailIndexBuilder, TrimTextValue, MAX_SORT_FIELD_LENGTH},
+    index::{EmailIndexBuilder, IndexSaveDate, TrimTextValue, MAX_SORT_FIELD_LENGTH},
     ingest::IngestedEmail,
 };
 
@@ -94,7 +94,9 @@ impl JMAP {
         let from_message_ids = self
             .owned_or_shared_messages(access_token, from_account_id, Acl::ReadItems)
             .await?;
-        let mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        // Saved searches cannot contain messages
+        let mut mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        mailbox_ids -= self.mailbox_saved_search_ids(account_id).await?;
         let can_add_mailbox_ids = if access_token.is_shared(account_id) {
             self.shared_documents(access_token, account_id, Collection::Mailbox, Acl::AddItems)
                 .await?
@@ -423,10 +425,15 @@ impl JMAP {
         }
 
         // Build batch
+        let received_at = metadata
+            .get(&Property::ReceivedAt)
+            .as_date()
+            .map_or_else(now, |date| date.timestamp() as u64);
         batch
             .with_collection(Collection::Email)
             .create_document(message_id)
             .value(Property::ThreadId, thread_id, F_VALUE | F_BITMAP)
+            .set_save_dates(&mailboxes, now())
             .value(Property::MailboxIds, mailboxes, F_VALUE | F_BITMAP)
             .value(Property::Keywords, keywords, F_VALUE | F_BITMAP)
             .value(Property::Cid, changes.change_id, F_VALUE)
@@ -434,7 +441,14 @@ impl JMAP {
             .custom(token_index)
             .custom(changes);
 
-        self.store.write(batch.build()).await.map_err(|err| {
+        self.write_batch_with_thread_summary(
+            account_id,
+            thread_id,
+            batch,
+            ThreadSummaryChange::Add { received_at },
+        )
+        .await
+        .map_err(|err| {
             tracing::error!(
                     event = "error",
                     context = "email_copy",
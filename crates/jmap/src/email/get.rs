@@ -31,10 +31,15 @@ use jmap_proto::{
     },
 };
 use mail_parser::Message;
+use store::write::now;
 
-use crate::{auth::AccessToken, email::headers::HeaderToValue, JMAP};
+use crate::{auth::AccessToken, email::headers::HeaderToValue, Bincode, JMAP};
 
-use super::body::{ToBodyPart, TruncateBody};
+use super::{
+    body::{ToBodyPart, TruncateBody},
+    smime::SmimeStatus,
+    snooze::Snooze,
+};
 
 impl JMAP {
     pub async fn email_get(
@@ -207,6 +212,12 @@ impl JMAP {
                 None
             };
 
+            // Signatures verified at delivery fail once a certificate in the chain expires
+            let smime_expired = matches!(
+                values.get(&Property::SmimeStatus),
+                Value::Date(valid_until) if valid_until.timestamp() < now() as i64
+            );
+
             // Prepare response
             let mut email = Object::with_capacity(properties.len());
             for property in &properties {
@@ -276,6 +287,19 @@ impl JMAP {
                             continue 'outer;
                         }
                     }
+                    Property::Snoozed => {
+                        email.append(
+                            property.clone(),
+                            self.get_property::<Bincode<Snooze>>(
+                                account_id,
+                                Collection::Email,
+                                id.document_id(),
+                                &Property::Snoozed,
+                            )
+                            .await?
+                            .map_or(Value::Null, |snooze| snooze.inner.into_value()),
+                        );
+                    }
                     Property::Size
                     | Property::ReceivedAt
                     | Property::MessageId
@@ -293,6 +317,31 @@ impl JMAP {
                     | Property::Preview => {
                         email.append(property.clone(), values.remove(property));
                     }
+                    Property::SmimeStatusAtDelivery | Property::SmimeVerifiedAt => {
+                        email.append(property.clone(), values.get(property).clone());
+                    }
+                    Property::SmimeStatus => {
+                        email.append(
+                            property.clone(),
+                            if smime_expired {
+                                Value::Text(SmimeStatus::Failed.as_str().to_string())
+                            } else {
+                                values.get(&Property::SmimeStatusAtDelivery).clone()
+                            },
+                        );
+                    }
+                    Property::SmimeErrors => {
+                        email.append(
+                            property.clone(),
+                            if smime_expired {
+                                Value::List(vec![Value::Text(
+                                    "Certificate has expired".to_string(),
+                                )])
+                            } else {
+                                values.get(property).clone()
+                            },
+                        );
+                    }
                     Property::Header(_) => {
                         if let Some(message) = &message {
                             email.append(
@@ -55,7 +55,9 @@ impl JMAP {
             .assert_state(account_id, Collection::Email, &request.if_in_state)
             .await?;
 
-        let valid_mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        // Saved searches cannot contain messages
+        let mut valid_mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        valid_mailbox_ids -= self.mailbox_saved_search_ids(account_id).await?;
         let can_add_mailbox_ids = if access_token.is_shared(account_id) {
             self.shared_documents(access_token, account_id, Collection::Mailbox, Acl::AddItems)
                 .await?
@@ -142,6 +144,7 @@ impl JMAP {
                     received_at: email.received_at.map(|r| r.into()),
                     skip_duplicates: false,
                     encrypt: self.config.encrypt && self.config.encrypt_append,
+                    replace: None,
                 })
                 .await
             {
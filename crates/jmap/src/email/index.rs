@@ -42,10 +42,14 @@ use store::{
         builder::{FtsIndexBuilder, MAX_TOKEN_LENGTH},
         Language,
     },
-    write::{BatchBuilder, IntoOperations, F_BITMAP, F_CLEAR, F_INDEX, F_VALUE},
+    write::{now, BatchBuilder, IntoOperations, ToBitmaps, F_BITMAP, F_CLEAR, F_INDEX, F_VALUE},
+    Deserialize, Serialize,
 };
 
-use crate::email::headers::IntoForm;
+use crate::email::{
+    headers::IntoForm,
+    smime::{SmimeStatus, SmimeVerification},
+};
 
 pub const MAX_MESSAGE_PARTS: usize = 1000;
 pub const MAX_ID_LENGTH: usize = 100;
@@ -65,8 +69,19 @@ pub(super) trait IndexMessage {
         keywords: Vec<Keyword>,
         mailbox_ids: Vec<u32>,
         received_at: u64,
+        smime: Option<SmimeVerification>,
         default_language: Language,
     ) -> store::Result<&mut Self>;
+
+    fn index_message_properties<'x>(
+        &mut self,
+        message: Message<'x>,
+        keywords: Vec<Keyword>,
+        mailbox_ids: Vec<u32>,
+        received_at: u64,
+        smime: Option<SmimeVerification>,
+        default_language: Language,
+    ) -> FtsIndexBuilder<'x>;
 }
 
 impl IndexMessage for BatchBuilder {
@@ -76,8 +91,33 @@ impl IndexMessage for BatchBuilder {
         keywords: Vec<Keyword>,
         mailbox_ids: Vec<u32>,
         received_at: u64,
+        smime: Option<SmimeVerification>,
         default_language: Language,
     ) -> store::Result<&mut Self> {
+        let fts = self.index_message_properties(
+            message,
+            keywords,
+            mailbox_ids,
+            received_at,
+            smime,
+            default_language,
+        );
+
+        // Store full text index
+        self.custom(fts);
+
+        Ok(self)
+    }
+
+    fn index_message_properties<'x>(
+        &mut self,
+        message: Message<'x>,
+        keywords: Vec<Keyword>,
+        mailbox_ids: Vec<u32>,
+        received_at: u64,
+        smime: Option<SmimeVerification>,
+        default_language: Language,
+    ) -> FtsIndexBuilder<'x> {
         let mut metadata = Object::with_capacity(15);
 
         // Index keywords
@@ -383,13 +423,41 @@ impl IndexMessage for BatchBuilder {
             self.bitmap(Property::HasAttachment, (), 0);
         }
 
+        // Store and index S/MIME status at delivery
+        if let Some(smime) = smime {
+            metadata.append(Property::SmimeStatusAtDelivery, smime.status.as_str());
+            self.bitmap(Property::SmimeStatus, (), 0);
+            if smime.status != SmimeStatus::Encrypted {
+                metadata.append(
+                    Property::SmimeVerifiedAt,
+                    Value::Date(UTCDate::from_timestamp(now() as i64)),
+                );
+            }
+            if smime.status == SmimeStatus::Failed {
+                metadata.append(
+                    Property::SmimeErrors,
+                    Value::List(smime.errors.into_iter().map(Value::Text).collect()),
+                );
+            }
+
+            // Verified signatures remain valid until the first certificate in the chain expires
+            if let Some(valid_until) = smime.valid_until {
+                metadata.append(
+                    Property::SmimeStatus,
+                    Value::Date(UTCDate::from_timestamp(valid_until as i64)),
+                );
+                self.bitmap(Property::SmimeStatusAtDelivery, (), 0).value(
+                    Property::SmimeStatus,
+                    valid_until,
+                    F_INDEX,
+                );
+            }
+        }
+
         // Store properties
         self.value(Property::BodyStructure, metadata, F_VALUE);
 
-        // Store full text index
-        self.custom(fts);
-
-        Ok(self)
+        fts
     }
 }
 
@@ -487,6 +555,19 @@ impl IntoOperations for EmailIndexBuilder {
                 (Property::HasAttachment, Value::Bool(true)) => {
                     batch.bitmap(Property::HasAttachment, (), options);
                 }
+                (Property::SmimeStatusAtDelivery, Value::Text(status)) => {
+                    batch.bitmap(Property::SmimeStatus, (), options);
+                    if status == SmimeStatus::Verified.as_str() {
+                        batch.bitmap(Property::SmimeStatusAtDelivery, (), options);
+                    }
+                }
+                (Property::SmimeStatus, Value::Date(valid_until)) => {
+                    batch.value(
+                        Property::SmimeStatus,
+                        valid_until.timestamp() as u64,
+                        F_INDEX | options,
+                    );
+                }
                 _ => {}
             }
         }
@@ -659,6 +740,185 @@ impl VisitValues for HeaderValue<'_> {
     }
 }
 
+/// Time at which the message was assigned to each of its mailboxes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SaveDates(pub Vec<(u32, u64)>);
+
+/// Index key of a save date. Keys are sorted by mailbox first so that
+/// date ranges can be matched within a single mailbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveDateKey {
+    pub mailbox_id: u32,
+    pub save_date: u64,
+}
+
+impl SaveDates {
+    pub fn get(&self, mailbox_id: u32) -> Option<u64> {
+        self.0
+            .iter()
+            .find_map(|(id, save_date)| (*id == mailbox_id).then_some(*save_date))
+    }
+
+    pub fn last(&self) -> Option<u64> {
+        self.0.iter().map(|(_, save_date)| *save_date).max()
+    }
+}
+
+impl Serialize for SaveDateKey {
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<u32>() + std::mem::size_of::<u64>());
+        bytes.extend_from_slice(&self.mailbox_id.to_be_bytes());
+        bytes.extend_from_slice(&self.save_date.to_be_bytes());
+        bytes
+    }
+}
+
+impl ToBitmaps for SaveDateKey {
+    fn to_bitmaps(&self, _: &mut Vec<store::write::Operation>, _: u8, _: bool) {
+        unreachable!()
+    }
+}
+
+impl Serialize for SaveDates {
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * 12);
+        for (mailbox_id, save_date) in self.0 {
+            bytes.extend(
+                SaveDateKey {
+                    mailbox_id,
+                    save_date,
+                }
+                .serialize(),
+            );
+        }
+        bytes
+    }
+}
+
+impl Deserialize for SaveDates {
+    fn deserialize(bytes: &[u8]) -> store::Result<Self> {
+        if bytes.len() % 12 == 0 {
+            Ok(SaveDates(
+                bytes
+                    .chunks_exact(12)
+                    .map(|chunk| {
+                        (
+                            u32::from_be_bytes(chunk[..4].try_into().unwrap()),
+                            u64::from_be_bytes(chunk[4..].try_into().unwrap()),
+                        )
+                    })
+                    .collect(),
+            ))
+        } else {
+            Err(store::Error::InternalError(
+                "Failed to deserialize save dates".to_string(),
+            ))
+        }
+    }
+}
+
+impl ToBitmaps for SaveDates {
+    fn to_bitmaps(&self, _: &mut Vec<store::write::Operation>, _: u8, _: bool) {
+        unreachable!()
+    }
+}
+
+pub trait IndexSaveDate {
+    fn set_save_dates(&mut self, mailbox_ids: &[u32], save_date: u64) -> &mut Self;
+    fn update_save_dates(
+        &mut self,
+        current: Option<SaveDates>,
+        mailbox_ids: &[u32],
+        added_ids: &[u32],
+        save_date: u64,
+    ) -> &mut Self;
+    fn clear_save_dates(&mut self, current: SaveDates) -> &mut Self;
+}
+
+// The save date is the time at which the message was last assigned to a mailbox
+impl IndexSaveDate for BatchBuilder {
+    fn set_save_dates(&mut self, mailbox_ids: &[u32], save_date: u64) -> &mut Self {
+        for mailbox_id in mailbox_ids {
+            self.value(
+                Property::SaveDate,
+                SaveDateKey {
+                    mailbox_id: *mailbox_id,
+                    save_date,
+                },
+                F_INDEX,
+            );
+        }
+        self.value(
+            Property::SaveDate,
+            SaveDates(
+                mailbox_ids
+                    .iter()
+                    .map(|mailbox_id| (*mailbox_id, save_date))
+                    .collect(),
+            ),
+            F_VALUE,
+        )
+    }
+
+    fn update_save_dates(
+        &mut self,
+        current: Option<SaveDates>,
+        mailbox_ids: &[u32],
+        added_ids: &[u32],
+        save_date: u64,
+    ) -> &mut Self {
+        // Keep the save dates of the mailboxes the message remains in
+        let mut save_dates = Vec::with_capacity(mailbox_ids.len());
+        for (mailbox_id, current_date) in current.unwrap_or_default().0 {
+            if mailbox_ids.contains(&mailbox_id) && !added_ids.contains(&mailbox_id) {
+                save_dates.push((mailbox_id, current_date));
+            } else {
+                self.value(
+                    Property::SaveDate,
+                    SaveDateKey {
+                        mailbox_id,
+                        save_date: current_date,
+                    },
+                    F_INDEX | F_CLEAR,
+                );
+            }
+        }
+
+        // Set the save date of the mailboxes the message was added to
+        for mailbox_id in added_ids {
+            self.value(
+                Property::SaveDate,
+                SaveDateKey {
+                    mailbox_id: *mailbox_id,
+                    save_date,
+                },
+                F_INDEX,
+            );
+            save_dates.push((*mailbox_id, save_date));
+        }
+
+        if !save_dates.is_empty() {
+            self.value(Property::SaveDate, SaveDates(save_dates), F_VALUE)
+        } else {
+            self.value(Property::SaveDate, (), F_VALUE | F_CLEAR)
+        }
+    }
+
+    fn clear_save_dates(&mut self, current: SaveDates) -> &mut Self {
+        for (mailbox_id, save_date) in current.0 {
+            self.value(
+                Property::SaveDate,
+                SaveDateKey {
+                    mailbox_id,
+                    save_date,
+                },
+                F_INDEX | F_CLEAR,
+            );
+        }
+        self.value(Property::SaveDate, (), F_VALUE | F_CLEAR)
+    }
+}
+
 pub trait TrimTextValue {
     fn trim_text(self, length: usize) -> Self;
 }
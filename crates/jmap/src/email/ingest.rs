@@ -36,19 +36,25 @@ use mail_parser::{
 use store::{
     ahash::AHashSet,
     query::Filter,
-    write::{log::ChangeLogBuilder, now, BatchBuilder, F_BITMAP, F_CLEAR, F_VALUE},
-    BitmapKey, ValueKey,
+    roaring::RoaringBitmap,
+    write::{
+        assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, F_BITMAP, F_CLEAR, F_VALUE,
+    },
+    BitmapKey, BlobKind, ValueKey,
 };
 use utils::map::vec_map::VecMap;
 
 use crate::{
     email::index::{IndexMessage, MAX_ID_LENGTH},
+    thread::summary::ThreadSummaryChange,
     IngestError, JMAP,
 };
 
 use super::{
     crypto::{EncryptMessage, EncryptMessageError, EncryptionParams},
-    index::{TrimTextValue, MAX_SORT_FIELD_LENGTH},
+    index::{IndexSaveDate, SaveDates, TrimTextValue, MAX_SORT_FIELD_LENGTH},
+    set::TagManager,
+    smime::VerifySmime,
 };
 
 #[derive(Default)]
@@ -69,6 +75,13 @@ pub struct IngestEmail<'x> {
     pub received_at: Option<u64>,
     pub skip_duplicates: bool,
     pub encrypt: bool,
+    pub replace: Option<ReplaceEmail>,
+}
+
+/// Message expunged from `mailbox_id` in the same batch the new message is written with.
+pub struct ReplaceEmail {
+    pub document_id: u32,
+    pub mailbox_id: u32,
 }
 
 impl JMAP {
@@ -176,6 +189,9 @@ impl JMAP {
             }
         };
 
+        // Verify S/MIME signature at delivery time
+        let smime = message.verify_smime(&self.config.smime_trust_store, now() as i64);
+
         // Encrypt message
         if params.encrypt && !message.is_encrypted() {
             if let Some(encrypt_params) = self
@@ -222,6 +238,43 @@ impl JMAP {
             }
         }
 
+        // Obtain message to replace
+        let replace = if let Some(replace) = params.replace {
+            let mailboxes = self
+                .get_property::<HashedValue<Vec<u32>>>(
+                    params.account_id,
+                    Collection::Email,
+                    replace.document_id,
+                    Property::MailboxIds,
+                )
+                .await
+                .map_err(|_| IngestError::Temporary)?;
+            let thread_id = self
+                .get_property::<u32>(
+                    params.account_id,
+                    Collection::Email,
+                    replace.document_id,
+                    Property::ThreadId,
+                )
+                .await
+                .map_err(|_| IngestError::Temporary)?;
+            match (mailboxes, thread_id) {
+                (Some(mailboxes), Some(thread_id))
+                    if mailboxes.inner.contains(&replace.mailbox_id) =>
+                {
+                    Some((replace, TagManager::new(mailboxes), thread_id))
+                }
+                _ => {
+                    return Err(IngestError::Permanent {
+                        code: [5, 5, 0],
+                        reason: "Message to replace not found.".to_string(),
+                    });
+                }
+            }
+        } else {
+            None
+        };
+
         // Obtain a documentId and changeId
         let document_id = self
             .store
@@ -295,16 +348,22 @@ impl JMAP {
         for mailbox_id in &params.mailbox_ids {
             changes.log_child_update(Collection::Mailbox, *mailbox_id);
         }
+        self.mailbox_saved_search_log_changes(params.account_id, &mut changes)
+            .await
+            .map_err(|_| IngestError::Temporary)?;
 
         // Build write batch
+        let received_at = params.received_at.unwrap_or_else(now);
         batch
             .with_collection(Collection::Email)
             .create_document(document_id)
+            .set_save_dates(&params.mailbox_ids, now())
             .index_message(
                 message,
                 params.keywords,
                 params.mailbox_ids,
-                params.received_at.unwrap_or_else(now),
+                received_at,
+                smime,
                 self.config.default_language,
             )
             .map_err(|err| {
@@ -316,16 +375,106 @@ impl JMAP {
                 IngestError::Temporary
             })?
             .value(Property::Cid, change_id, F_VALUE)
-            .value(Property::ThreadId, thread_id, F_VALUE | F_BITMAP)
-            .custom(changes);
-        self.store.write(batch.build()).await.map_err(|err| {
-            tracing::error!(
-                event = "error",
-                context = "email_ingest",
-                error = ?err,
-                "Failed to write message to database.");
-            IngestError::Temporary
-        })?;
+            .value(Property::ThreadId, thread_id, F_VALUE | F_BITMAP);
+
+        // Expunge the replaced message from its mailbox in the same batch
+        let mut thread_changes = vec![(thread_id, ThreadSummaryChange::Add { received_at })];
+        let mut deleted_id = None;
+        if let Some((replace, mut mailboxes, replace_thread_id)) = replace {
+            mailboxes.update(replace.mailbox_id, false);
+            if mailboxes.has_tags() {
+                // Untag the message from the mailbox
+                let save_dates = self
+                    .get_property::<SaveDates>(
+                        params.account_id,
+                        Collection::Email,
+                        replace.document_id,
+                        Property::SaveDate,
+                    )
+                    .await
+                    .map_err(|_| IngestError::Temporary)?;
+                batch
+                    .update_document(replace.document_id)
+                    .update_save_dates(save_dates, mailboxes.current(), mailboxes.added(), now());
+                mailboxes.update_batch(&mut batch, Property::MailboxIds);
+                batch.value(Property::Cid, change_id, F_VALUE);
+                changes.log_update(
+                    Collection::Email,
+                    Id::from_parts(replace_thread_id, replace.document_id),
+                );
+                changes.log_child_update(Collection::Mailbox, replace.mailbox_id);
+            } else {
+                // Delete the message when it no longer belongs to any mailbox
+                let deletion = match self
+                    .email_delete_batch(params.account_id, replace.document_id, &mut batch)
+                    .await
+                {
+                    Ok(Ok(deletion)) => deletion,
+                    Ok(Err(_)) => {
+                        return Err(IngestError::Permanent {
+                            code: [5, 5, 0],
+                            reason: "Message to replace not found.".to_string(),
+                        });
+                    }
+                    Err(_) => return Err(IngestError::Temporary),
+                };
+                changes.merge(deletion.changes);
+                if deletion.thread_id == thread_id {
+                    thread_changes = vec![(
+                        thread_id,
+                        ThreadSummaryChange::Replace {
+                            document_id: replace.document_id,
+                            received_at,
+                        },
+                    )];
+                } else if deletion.is_last_in_thread {
+                    changes.log_delete(Collection::Thread, deletion.thread_id);
+                    batch
+                        .with_collection(Collection::Thread)
+                        .delete_document(deletion.thread_id)
+                        .value(Property::Value, (), F_VALUE | F_CLEAR);
+                } else {
+                    changes.log_child_update(Collection::Thread, deletion.thread_id);
+                    thread_changes.push((
+                        deletion.thread_id,
+                        ThreadSummaryChange::Remove {
+                            document_id: replace.document_id,
+                            received_at: deletion.received_at,
+                        },
+                    ));
+                }
+                deleted_id = Some(replace.document_id);
+            }
+        }
+        batch.custom(changes);
+        self.write_batch_with_thread_summaries(params.account_id, batch, thread_changes)
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                    event = "error",
+                    context = "email_ingest",
+                    error = ?err,
+                    "Failed to write message to database.");
+                IngestError::Temporary
+            })?;
+
+        // Delete the blob of the replaced message
+        if let Some(document_id) = deleted_id {
+            if let Err(err) = self
+                .store
+                .delete_blob(&BlobKind::LinkedMaildir {
+                    account_id: params.account_id,
+                    document_id,
+                })
+                .await
+            {
+                tracing::error!(
+                    event = "error",
+                    context = "email_ingest",
+                    error = ?err,
+                    "Failed to delete blob of replaced message.");
+            }
+        }
 
         Ok(IngestedEmail {
             id,
@@ -446,34 +595,40 @@ impl JMAP {
                 .with_collection(Collection::Thread);
             for &delete_thread_id in thread_counts.keys() {
                 if delete_thread_id != thread_id {
-                    batch.delete_document(delete_thread_id);
+                    batch.delete_document(delete_thread_id).value(
+                        Property::Value,
+                        (),
+                        F_VALUE | F_CLEAR,
+                    );
                     changes.log_delete(Collection::Thread, delete_thread_id);
                 }
             }
 
             // Move messages to the new threadId
             batch.with_collection(Collection::Email);
+            let mut merged_ids = RoaringBitmap::new();
             for old_thread_id in thread_ids.into_iter().flatten().collect::<AHashSet<_>>() {
+                let document_ids = self
+                    .store
+                    .get_bitmap(BitmapKey::value(
+                        account_id,
+                        Collection::Email,
+                        Property::ThreadId,
+                        old_thread_id,
+                    ))
+                    .await
+                    .map_err(|err| {
+                        tracing::error!(
+                        event = "error",
+                        context = "find_or_merge_thread",
+                        error = ?err,
+                        "Failed to obtain threadId bitmap.");
+                        IngestError::Temporary
+                    })?
+                    .unwrap_or_default();
+                merged_ids |= &document_ids;
                 if thread_id != old_thread_id {
-                    for document_id in self
-                        .store
-                        .get_bitmap(BitmapKey::value(
-                            account_id,
-                            Collection::Email,
-                            Property::ThreadId,
-                            old_thread_id,
-                        ))
-                        .await
-                        .map_err(|err| {
-                            tracing::error!(
-                            event = "error",
-                            context = "find_or_merge_thread",
-                            error = ?err,
-                            "Failed to obtain threadId bitmap.");
-                            IngestError::Temporary
-                        })?
-                        .unwrap_or_default()
-                    {
+                    for document_id in document_ids {
                         batch
                             .update_document(document_id)
                             .assert_value(Property::ThreadId, old_thread_id)
@@ -489,8 +644,29 @@ impl JMAP {
             }
             batch.custom(changes);
 
+            // Rebuild the summary of the merged thread
+            self.thread_summary_prepare(
+                account_id,
+                thread_id,
+                &ThreadSummaryChange::Rebuild {
+                    document_ids: merged_ids,
+                },
+                &mut batch,
+            )
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                    event = "error",
+                    context = "find_or_merge_thread",
+                    error = ?err,
+                    "Failed to obtain thread summary.");
+                IngestError::Temporary
+            })?;
+
             match self.store.write(batch.build()).await {
-                Ok(_) => return Ok(Some(thread_id)),
+                Ok(_) => {
+                    return Ok(Some(thread_id));
+                }
                 Err(store::Error::AssertValueFailed) if try_count < 3 => {
                     try_count += 1;
                 }
@@ -31,5 +31,8 @@ pub mod index;
 pub mod ingest;
 pub mod parse;
 pub mod query;
+pub mod reindex;
 pub mod set;
+pub mod smime;
 pub mod snippet;
+pub mod snooze;
@@ -29,13 +29,15 @@ use jmap_proto::{
 };
 use mail_parser::{HeaderName, RfcHeader};
 use store::{
+    ahash::{AHashMap, AHashSet},
     fts::{builder::MAX_TOKEN_LENGTH, Language},
     query::{self},
     roaring::RoaringBitmap,
+    write::now,
     ValueKey,
 };
 
-use crate::{auth::AccessToken, JMAP};
+use crate::{auth::AccessToken, thread::summary::ThreadSummary, JMAP};
 
 impl JMAP {
     pub async fn email_query(
@@ -44,9 +46,141 @@ impl JMAP {
         access_token: &AccessToken,
     ) -> Result<QueryResponse, MethodError> {
         let account_id = request.account_id.document_id();
-        let mut filters = Vec::with_capacity(request.filter.len());
+        let filter = self
+            .mailbox_saved_search_expand(account_id, std::mem::take(&mut request.filter))
+            .await?;
+        let mut result_set = self
+            .filter(
+                account_id,
+                Collection::Email,
+                self.email_query_filters(account_id, filter).await?,
+            )
+            .await?;
+        if access_token.is_shared(account_id) {
+            result_set.apply_mask(
+                self.shared_messages(access_token, account_id, Acl::ReadItems)
+                    .await?,
+            );
+        }
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
 
-        for cond in std::mem::take(&mut request.filter) {
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::descending(SortProperty::ReceivedAt)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::ReceivedAt => {
+                        query::Comparator::field(Property::ReceivedAt, comparator.is_ascending)
+                    }
+                    SortProperty::Size => {
+                        query::Comparator::field(Property::Size, comparator.is_ascending)
+                    }
+                    SortProperty::From => {
+                        query::Comparator::field(Property::From, comparator.is_ascending)
+                    }
+                    SortProperty::To => {
+                        query::Comparator::field(Property::To, comparator.is_ascending)
+                    }
+                    SortProperty::Subject => {
+                        query::Comparator::field(Property::Subject, comparator.is_ascending)
+                    }
+                    SortProperty::SentAt => {
+                        query::Comparator::field(Property::SentAt, comparator.is_ascending)
+                    }
+                    SortProperty::HasKeyword => query::Comparator::set(
+                        self.get_tag(
+                            account_id,
+                            Collection::Email,
+                            Property::Keywords,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                        )
+                        .await?
+                        .unwrap_or_default(),
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::AllInThreadHaveKeyword => query::Comparator::set(
+                        self.thread_keywords(
+                            account_id,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                            true,
+                        )
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::SomeInThreadHaveKeyword => query::Comparator::set(
+                        self.thread_keywords(
+                            account_id,
+                            comparator.keyword.unwrap_or(Keyword::Seen),
+                            false,
+                        )
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    // Non-standard
+                    SortProperty::Cc => {
+                        query::Comparator::field(Property::Cc, comparator.is_ascending)
+                    }
+                    SortProperty::ThreadReceivedAt => query::Comparator::score(
+                        self.thread_scores(account_id, &result_set.results, |summary| {
+                            summary.received_at
+                        })
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::ThreadSize => query::Comparator::score(
+                        self.thread_scores(account_id, &result_set.results, |summary| {
+                            summary.size as u64
+                        })
+                        .await?,
+                        comparator.is_ascending,
+                    ),
+                    SortProperty::ThreadHasUnread => {
+                        let mut unread_ids = self
+                            .get_document_ids(account_id, Collection::Email)
+                            .await?
+                            .unwrap_or_default();
+                        unread_ids -= self
+                            .thread_keywords(account_id, Keyword::Seen, true)
+                            .await?;
+                        query::Comparator::set(unread_ids, comparator.is_ascending)
+                    }
+
+                    other => return Err(MethodError::UnsupportedSort(other.to_string())),
+                });
+            }
+
+            // Sort results
+            self.sort(
+                result_set,
+                comparators,
+                paginate
+                    .with_prefix_key(ValueKey::new(
+                        account_id,
+                        Collection::Email,
+                        0,
+                        Property::ThreadId,
+                    ))
+                    .with_prefix_unique(request.arguments.collapse_threads.unwrap_or(false)),
+                response,
+            )
+            .await
+        } else {
+            Ok(response)
+        }
+    }
+
+    pub async fn email_query_filters(
+        &self,
+        account_id: u32,
+        filter: Vec<Filter>,
+    ) -> Result<Vec<query::Filter>, MethodError> {
+        let mut filters = Vec::with_capacity(filter.len());
+
+        for cond in filter {
             match cond {
                 Filter::InMailbox(mailbox) => filters.push(query::Filter::is_in_bitmap(
                     Property::MailboxIds,
@@ -68,6 +202,26 @@ impl JMAP {
                 Filter::After(date) => filters.push(query::Filter::gt(Property::ReceivedAt, date)),
                 Filter::MinSize(size) => filters.push(query::Filter::ge(Property::Size, size)),
                 Filter::MaxSize(size) => filters.push(query::Filter::lt(Property::Size, size)),
+                Filter::ThreadMinSize(size) => filters.push(query::Filter::is_in_set(
+                    self.thread_summary_filter(account_id, |summary| summary.size >= size)
+                        .await?,
+                )),
+                Filter::ThreadMaxSize(size) => filters.push(query::Filter::is_in_set(
+                    self.thread_summary_filter(account_id, |summary| summary.size < size)
+                        .await?,
+                )),
+                Filter::ThreadReceivedBefore(date) => filters.push(query::Filter::is_in_set(
+                    self.thread_summary_filter(account_id, |summary| {
+                        (summary.received_at as i64) < date.timestamp()
+                    })
+                    .await?,
+                )),
+                Filter::ThreadReceivedAfter(date) => filters.push(query::Filter::is_in_set(
+                    self.thread_summary_filter(account_id, |summary| {
+                        (summary.received_at as i64) > date.timestamp()
+                    })
+                    .await?,
+                )),
                 Filter::AllInThreadHaveKeyword(keyword) => filters.push(query::Filter::is_in_set(
                     self.thread_keywords(account_id, keyword, true).await?,
                 )),
@@ -98,6 +252,37 @@ impl JMAP {
                         filters.push(query::Filter::End);
                     }
                 }
+                Filter::HasSmime(has_smime) => {
+                    if !has_smime {
+                        filters.push(query::Filter::Not);
+                    }
+                    filters.push(query::Filter::is_in_bitmap(Property::SmimeStatus, ()));
+                    if !has_smime {
+                        filters.push(query::Filter::End);
+                    }
+                }
+                Filter::HasVerifiedSmime(is_verified) => {
+                    if !is_verified {
+                        filters.push(query::Filter::Not);
+                    }
+                    // Verified signatures are indexed by the expiration date of their chain
+                    filters.push(query::Filter::ge(Property::SmimeStatus, now()));
+                    if !is_verified {
+                        filters.push(query::Filter::End);
+                    }
+                }
+                Filter::HasVerifiedSmimeAtDelivery(is_verified) => {
+                    if !is_verified {
+                        filters.push(query::Filter::Not);
+                    }
+                    filters.push(query::Filter::is_in_bitmap(
+                        Property::SmimeStatusAtDelivery,
+                        (),
+                    ));
+                    if !is_verified {
+                        filters.push(query::Filter::End);
+                    }
+                }
                 Filter::Text(text) => {
                     filters.push(query::Filter::Or);
                     filters.push(query::Filter::has_text(
@@ -239,98 +424,7 @@ impl JMAP {
             }
         }
 
-        let mut result_set = self.filter(account_id, Collection::Email, filters).await?;
-        if access_token.is_shared(account_id) {
-            result_set.apply_mask(
-                self.shared_messages(access_token, account_id, Acl::ReadItems)
-                    .await?,
-            );
-        }
-        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
-
-        if let Some(paginate) = paginate {
-            // Parse sort criteria
-            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
-            for comparator in request
-                .sort
-                .and_then(|s| if !s.is_empty() { s.into() } else { None })
-                .unwrap_or_else(|| vec![Comparator::descending(SortProperty::ReceivedAt)])
-            {
-                comparators.push(match comparator.property {
-                    SortProperty::ReceivedAt => {
-                        query::Comparator::field(Property::ReceivedAt, comparator.is_ascending)
-                    }
-                    SortProperty::Size => {
-                        query::Comparator::field(Property::Size, comparator.is_ascending)
-                    }
-                    SortProperty::From => {
-                        query::Comparator::field(Property::From, comparator.is_ascending)
-                    }
-                    SortProperty::To => {
-                        query::Comparator::field(Property::To, comparator.is_ascending)
-                    }
-                    SortProperty::Subject => {
-                        query::Comparator::field(Property::Subject, comparator.is_ascending)
-                    }
-                    SortProperty::SentAt => {
-                        query::Comparator::field(Property::SentAt, comparator.is_ascending)
-                    }
-                    SortProperty::HasKeyword => query::Comparator::set(
-                        self.get_tag(
-                            account_id,
-                            Collection::Email,
-                            Property::Keywords,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                        )
-                        .await?
-                        .unwrap_or_default(),
-                        comparator.is_ascending,
-                    ),
-                    SortProperty::AllInThreadHaveKeyword => query::Comparator::set(
-                        self.thread_keywords(
-                            account_id,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                            true,
-                        )
-                        .await?,
-                        comparator.is_ascending,
-                    ),
-                    SortProperty::SomeInThreadHaveKeyword => query::Comparator::set(
-                        self.thread_keywords(
-                            account_id,
-                            comparator.keyword.unwrap_or(Keyword::Seen),
-                            false,
-                        )
-                        .await?,
-                        comparator.is_ascending,
-                    ),
-                    // Non-standard
-                    SortProperty::Cc => {
-                        query::Comparator::field(Property::Cc, comparator.is_ascending)
-                    }
-
-                    other => return Err(MethodError::UnsupportedSort(other.to_string())),
-                });
-            }
-
-            // Sort results
-            self.sort(
-                result_set,
-                comparators,
-                paginate
-                    .with_prefix_key(ValueKey::new(
-                        account_id,
-                        Collection::Email,
-                        0,
-                        Property::ThreadId,
-                    ))
-                    .with_prefix_unique(request.arguments.collapse_threads.unwrap_or(false)),
-                response,
-            )
-            .await
-        } else {
-            Ok(response)
-        }
+        Ok(filters)
     }
 
     async fn thread_keywords(
@@ -380,4 +474,123 @@ impl JMAP {
 
         Ok(matched_ids)
     }
+
+    async fn thread_summary_filter(
+        &self,
+        account_id: u32,
+        filter: impl Fn(&ThreadSummary) -> bool,
+    ) -> Result<RoaringBitmap, MethodError> {
+        let thread_ids = self
+            .get_document_ids(account_id, Collection::Thread)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let summaries = self
+            .thread_summaries(account_id, &thread_ids)
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                    event = "error",
+                    context = "email_query",
+                    account_id = account_id,
+                    error = ?err,
+                    "Failed to retrieve thread summaries");
+                MethodError::ServerPartialFail
+            })?;
+
+        let mut matched_ids = RoaringBitmap::new();
+        for (thread_id, summary) in thread_ids.into_iter().zip(summaries) {
+            if filter(&summary) {
+                if let Some(thread_doc_ids) = self
+                    .get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
+                    .await?
+                {
+                    matched_ids |= thread_doc_ids;
+                }
+            }
+        }
+
+        Ok(matched_ids)
+    }
+
+    async fn thread_scores(
+        &self,
+        account_id: u32,
+        document_ids: &RoaringBitmap,
+        score: impl Fn(&ThreadSummary) -> u64,
+    ) -> Result<AHashMap<u32, u32>, MethodError> {
+        let thread_ids = self
+            .store
+            .get_values::<u32>(
+                document_ids
+                    .iter()
+                    .map(|document_id| {
+                        ValueKey::new(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::ThreadId,
+                        )
+                    })
+                    .collect(),
+            )
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                    event = "error",
+                    context = "email_query",
+                    account_id = account_id,
+                    error = ?err,
+                    "Failed to retrieve threadIds");
+                MethodError::ServerPartialFail
+            })?;
+
+        // Obtain the summary of each thread
+        let unique_ids = thread_ids
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let thread_values = self
+            .thread_summaries(account_id, &unique_ids)
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                    event = "error",
+                    context = "email_query",
+                    account_id = account_id,
+                    error = ?err,
+                    "Failed to retrieve thread summaries");
+                MethodError::ServerPartialFail
+            })?
+            .iter()
+            .map(score)
+            .collect::<Vec<_>>();
+
+        // Scores are 32-bit, so sort by the rank of each value rather than the value itself
+        let mut ranks = thread_values.clone();
+        ranks.sort_unstable();
+        ranks.dedup();
+        let thread_scores = unique_ids
+            .into_iter()
+            .zip(thread_values)
+            .map(|(thread_id, value)| {
+                (
+                    thread_id,
+                    ranks.binary_search(&value).unwrap_or_default() as u32 + 1,
+                )
+            })
+            .collect::<AHashMap<_, _>>();
+
+        Ok(document_ids
+            .iter()
+            .zip(thread_ids)
+            .filter_map(|(document_id, thread_id)| {
+                Some((document_id, *thread_scores.get(&thread_id?)?))
+            })
+            .collect())
+    }
 }
@@ -0,0 +1,365 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::time::Duration;
+
+use jmap_proto::types::collection::Collection;
+use mail_parser::Message;
+use store::{
+    fts::term_index::TokenIndex,
+    write::{assert::HashedValue, key::KeySerializer, BatchBuilder, Operation, ValueClass},
+    BitmapKey, BlobKind, CustomValueKey, Serialize, ValueKey,
+};
+
+use crate::{auth::authenticate::KEY_FTS_REINDEX, Bincode, JMAP};
+
+use super::index::IndexMessage;
+
+const MAX_REINDEX_RETRIES: u32 = 5;
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ReindexState {
+    /// Accounts pending reindexing, the first one is the account being reindexed.
+    pub accounts: Vec<u32>,
+    /// Last document reindexed in the current account.
+    pub last_document_id: Option<u32>,
+    pub indexed: u64,
+    pub failed: u64,
+}
+
+impl JMAP {
+    /// Schedules the full-text index of an account, or all accounts, to be rebuilt.
+    pub async fn fts_reindex_schedule(&self, account_id: Option<u32>) -> store::Result<()> {
+        let account_ids = if let Some(account_id) = account_id {
+            vec![account_id]
+        } else {
+            self.store
+                .get_bitmap(BitmapKey::document_ids(u32::MAX, Collection::Principal))
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .collect()
+        };
+
+        let mut try_count = 0;
+        loop {
+            let current = self
+                .store
+                .get_value::<HashedValue<Bincode<ReindexState>>>(CustomValueKey {
+                    value: reindex_key(),
+                })
+                .await?;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(u32::MAX)
+                .with_collection(Collection::Principal);
+            let mut state = if let Some(current) = current {
+                batch.assert_value(
+                    ValueClass::Custom {
+                        bytes: reindex_key(),
+                    },
+                    &current,
+                );
+                current.inner
+            } else {
+                batch.assert_value(
+                    ValueClass::Custom {
+                        bytes: reindex_key(),
+                    },
+                    (),
+                );
+                Bincode::new(ReindexState::default())
+            };
+            for account_id in &account_ids {
+                if !state.inner.accounts.contains(account_id) {
+                    state.inner.accounts.push(*account_id);
+                }
+            }
+            batch.op(Operation::Value {
+                class: ValueClass::Custom {
+                    bytes: reindex_key(),
+                },
+                set: state.serialize().into(),
+            });
+
+            match self.store.write(batch.build()).await {
+                Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                    try_count += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the progress of the current reindexing job, if any.
+    pub async fn fts_reindex_state(&self) -> store::Result<Option<ReindexState>> {
+        self.store
+            .get_value::<Bincode<ReindexState>>(CustomValueKey {
+                value: reindex_key(),
+            })
+            .await
+            .map(|state| state.map(|state| state.inner))
+    }
+
+    /// Rebuilds the full-text index of all scheduled accounts. Progress is stored after
+    /// each batch, allowing the job to resume where it was left if interrupted.
+    pub async fn fts_reindex_run(&self) {
+        let mut try_count = 0;
+        loop {
+            match self.fts_reindex_batch().await {
+                Ok(true) => {
+                    try_count = 0;
+                    tokio::time::sleep(self.config.fts_reindex_throttle).await;
+                }
+                Ok(false) => {
+                    break;
+                }
+                Err(store::Error::AssertValueFailed) => {
+                    // The job was rescheduled or a message changed, retry after a backoff
+                    try_count += 1;
+                    if let Some(backoff) = fts_reindex_backoff(try_count) {
+                        tokio::time::sleep(backoff).await;
+                    } else {
+                        tracing::error!(
+                            event = "error",
+                            context = "fts_reindex",
+                            "Failed to rebuild full-text index after {} attempts.",
+                            MAX_REINDEX_RETRIES
+                        );
+                        break;
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(
+                        event = "error",
+                        context = "fts_reindex",
+                        error = ?err,
+                        "Failed to rebuild full-text index.");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Reindexes the next batch of messages and stores the progress, returns
+    /// `false` once there is nothing left to reindex.
+    pub async fn fts_reindex_batch(&self) -> store::Result<bool> {
+        let mut state = if let Some(state) = self
+            .store
+            .get_value::<HashedValue<Bincode<ReindexState>>>(CustomValueKey {
+                value: reindex_key(),
+            })
+            .await?
+        {
+            state
+        } else {
+            return Ok(false);
+        };
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(u32::MAX)
+            .with_collection(Collection::Principal)
+            .assert_value(
+                ValueClass::Custom {
+                    bytes: reindex_key(),
+                },
+                &state,
+            );
+
+        if let Some(account_id) = state.inner.inner.accounts.first().copied() {
+            let last_document_id = state.inner.inner.last_document_id;
+            let mut num_documents = 0;
+
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Email);
+            for document_id in self
+                .store
+                .get_bitmap(BitmapKey::document_ids(account_id, Collection::Email))
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|document_id| last_document_id.map_or(true, |last| *document_id > last))
+                .take(self.config.fts_reindex_batch_size)
+            {
+                if self
+                    .fts_reindex_message(&mut batch, account_id, document_id)
+                    .await?
+                {
+                    state.inner.inner.indexed += 1;
+                } else {
+                    state.inner.inner.failed += 1;
+                }
+                state.inner.inner.last_document_id = document_id.into();
+                num_documents += 1;
+            }
+
+            // Move to the next account once all messages have been reindexed
+            if num_documents < self.config.fts_reindex_batch_size {
+                tracing::debug!(
+                    context = "fts_reindex",
+                    event = "account-finished",
+                    account_id = account_id,
+                    "Finished rebuilding full-text index."
+                );
+                state.inner.inner.accounts.remove(0);
+                state.inner.inner.last_document_id = None;
+            }
+        }
+
+        // Update progress
+        let is_done = state.inner.inner.accounts.is_empty();
+        batch
+            .with_account_id(u32::MAX)
+            .with_collection(Collection::Principal)
+            .op(Operation::Value {
+                class: ValueClass::Custom {
+                    bytes: reindex_key(),
+                },
+                set: if !is_done {
+                    (&state.inner).serialize().into()
+                } else {
+                    None
+                },
+            });
+        self.store.write(batch.build()).await?;
+
+        if is_done {
+            tracing::info!(
+                context = "fts_reindex",
+                event = "finished",
+                indexed = state.inner.inner.indexed,
+                failed = state.inner.inner.failed,
+                "Full-text index rebuild completed."
+            );
+        }
+
+        Ok(!is_done)
+    }
+
+    pub async fn fts_reindex_message(
+        &self,
+        batch: &mut BatchBuilder,
+        account_id: u32,
+        document_id: u32,
+    ) -> store::Result<bool> {
+        let raw_message = if let Some(raw_message) = self
+            .store
+            .get_blob(
+                &BlobKind::LinkedMaildir {
+                    account_id,
+                    document_id,
+                },
+                0..u32::MAX,
+            )
+            .await?
+        {
+            raw_message
+        } else {
+            tracing::debug!(
+                context = "fts_reindex",
+                event = "error",
+                account_id = account_id,
+                document_id = document_id,
+                "Message blob not found."
+            );
+            return Ok(false);
+        };
+        let message = if let Some(message) = Message::parse(&raw_message) {
+            message
+        } else {
+            tracing::debug!(
+                context = "fts_reindex",
+                event = "error",
+                account_id = account_id,
+                document_id = document_id,
+                "Failed to parse message."
+            );
+            return Ok(false);
+        };
+
+        // Remove the current index, making sure that the message was not modified or
+        // deleted meanwhile. A missing index means that the message is being deleted,
+        // as its blob is removed only after the deletion is committed.
+        let token_index = if let Some(token_index) = self
+            .store
+            .get_value::<HashedValue<TokenIndex>>(ValueKey::term_index(
+                account_id,
+                Collection::Email,
+                document_id,
+            ))
+            .await?
+        {
+            token_index
+        } else {
+            tracing::debug!(
+                context = "fts_reindex",
+                event = "error",
+                account_id = account_id,
+                document_id = document_id,
+                "Term index not found."
+            );
+            return Ok(false);
+        };
+        batch
+            .update_document(document_id)
+            .assert_value(
+                ValueClass::Property {
+                    field: u8::MAX,
+                    family: u8::MAX,
+                },
+                &token_index,
+            )
+            .custom(token_index.inner);
+
+        // Build the new index, only the full-text index is kept
+        let fts = BatchBuilder::new().index_message_properties(
+            message,
+            vec![],
+            vec![],
+            0,
+            None,
+            self.config.default_language,
+        );
+        batch.custom(fts);
+
+        Ok(true)
+    }
+}
+
+/// Returns how long to wait before retrying a failed batch, or `None` once
+/// all retries have been exhausted.
+pub fn fts_reindex_backoff(try_count: u32) -> Option<Duration> {
+    if try_count <= MAX_REINDEX_RETRIES {
+        Some(Duration::from_millis(100 << try_count))
+    } else {
+        None
+    }
+}
+
+fn reindex_key() -> Vec<u8> {
+    KeySerializer::new(std::mem::size_of::<u32>() + 1)
+        .write(u32::MAX)
+        .write(KEY_FTS_REINDEX)
+        .finalize()
+}
@@ -55,18 +55,22 @@ use store::{
     ahash::AHashSet,
     fts::term_index::TokenIndex,
     write::{
-        assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, DeserializeFrom, SerializeInto,
-        ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
+        assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, DeserializeFrom,
+        SerializeInto, ToBitmaps, ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
     },
     BlobKind, Serialize, ValueKey,
 };
 
-use crate::{auth::AccessToken, IngestError, JMAP};
+use crate::{
+    auth::AccessToken, mailbox::INBOX_ID, thread::summary::ThreadSummaryChange, Bincode,
+    IngestError, JMAP,
+};
 
 use super::{
     headers::{BuildHeader, ValueToHeader},
-    index::EmailIndexBuilder,
+    index::{EmailIndexBuilder, IndexSaveDate, SaveDates},
     ingest::IngestEmail,
+    snooze::{Snooze, SnoozeSchedule, SNOOZED_KEYWORD},
 };
 
 impl JMAP {
@@ -81,8 +85,9 @@ impl JMAP {
             .prepare_set_response(&request, Collection::Email)
             .await?;
 
-        // Obtain mailboxIds
-        let mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        // Obtain mailboxIds, saved searches cannot contain messages
+        let mut mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        mailbox_ids -= self.mailbox_saved_search_ids(account_id).await?;
         let (can_add_mailbox_ids, can_delete_mailbox_ids, can_modify_message_ids) = if access_token
             .is_shared(account_id)
         {
@@ -738,6 +743,7 @@ impl JMAP {
                     received_at,
                     skip_duplicates: false,
                     encrypt: self.config.encrypt && self.config.encrypt_append,
+                    replace: None,
                 })
                 .await
             {
@@ -757,6 +763,8 @@ impl JMAP {
 
         // Process updates
         let mut changes = ChangeLogBuilder::new();
+        let mut snoozed_mailbox_id = None;
+        let mut scheduled = Vec::new();
         'update: for (id, object) in request.unwrap_update() {
             // Make sure id won't be destroyed
             if will_destroy.contains(&id) {
@@ -793,6 +801,8 @@ impl JMAP {
             batch
                 .with_account_id(account_id)
                 .with_collection(Collection::Email);
+            let mut snooze = None;
+            let mut current_snooze = None;
 
             for (property, value) in object.properties {
                 let value = match response.eval_object_references(value) {
@@ -832,6 +842,38 @@ impl JMAP {
                             patch.next().unwrap().unwrap_bool(),
                         );
                     }
+                    (Property::Snoozed, MaybePatchValue::Value(Value::Object(mut value))) => {
+                        let until = if let Value::Date(until) = value.remove(&Property::Until) {
+                            until.timestamp() as u64
+                        } else {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::Snoozed)
+                                    .with_description("Missing snooze until date."),
+                            );
+                            continue 'update;
+                        };
+                        let mailbox_id = match value.remove(&Property::MoveToMailboxId) {
+                            Value::Id(mailbox_id) => mailbox_id.document_id(),
+                            _ => INBOX_ID,
+                        };
+                        if !mailbox_ids.contains(mailbox_id) {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::Snoozed)
+                                    .with_description(format!(
+                                        "mailboxId {mailbox_id} does not exist."
+                                    )),
+                            );
+                            continue 'update;
+                        }
+                        snooze = Some(Some(Snooze { until, mailbox_id }));
+                    }
+                    (Property::Snoozed, MaybePatchValue::Value(Value::Null)) => {
+                        snooze = Some(None);
+                    }
                     (property, _) => {
                         response.invalid_property_update(id, property);
                         continue 'update;
@@ -839,7 +881,71 @@ impl JMAP {
                 }
             }
 
-            if !mailboxes.has_changes() && !keywords.has_changes() {
+            // Snoozed emails are moved to the Snoozed mailbox until they are woken up
+            if let Some(snooze) = &snooze {
+                if access_token.is_shared(account_id) {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("Messages in shared accounts cannot be snoozed."),
+                    );
+                    continue 'update;
+                }
+
+                let current = self
+                    .get_property::<HashedValue<Bincode<Snooze>>>(
+                        account_id,
+                        Collection::Email,
+                        document_id,
+                        Property::Snoozed,
+                    )
+                    .await?;
+                let snoozed_id = if let Some(snoozed_id) = snoozed_mailbox_id {
+                    snoozed_id
+                } else if snooze.is_some() {
+                    let snoozed_id = self.mailbox_get_or_create_snoozed(account_id).await?;
+                    mailbox_ids.insert(snoozed_id);
+                    snoozed_mailbox_id = snoozed_id.into();
+                    snoozed_id
+                } else {
+                    self.mailbox_get_by_role(account_id, "snoozed")
+                        .await?
+                        .unwrap_or(u32::MAX)
+                };
+                let snoozed_keyword = Keyword::Other(SNOOZED_KEYWORD.to_string());
+
+                if snooze.is_some() {
+                    for mailbox_id in mailboxes.current().to_vec() {
+                        if mailbox_id != snoozed_id {
+                            mailboxes.force_update(mailbox_id, false);
+                        }
+                    }
+                    mailboxes.force_update(snoozed_id, true);
+                    keywords.force_update(snoozed_keyword, true);
+                } else if let Some(current) = &current {
+                    // Cancelled snoozes return the email to its target mailbox
+                    if !mailboxes.has_changes() && mailboxes.current().contains(&snoozed_id) {
+                        let target_id = current.inner.inner.mailbox_id;
+                        mailboxes.force_update(snoozed_id, false);
+                        mailboxes.force_update(
+                            if mailbox_ids.contains(target_id) {
+                                target_id
+                            } else {
+                                INBOX_ID
+                            },
+                            true,
+                        );
+                    }
+                    keywords.force_update(snoozed_keyword, false);
+                }
+
+                if let Some(current) = &current {
+                    batch.assert_value(Property::Snoozed, current);
+                    current_snooze = Some(current.inner.inner);
+                }
+            }
+
+            if !mailboxes.has_changes() && !keywords.has_changes() && snooze.is_none() {
                 response.not_updated.append(
                     id,
                     SetError::invalid_properties()
@@ -943,6 +1049,22 @@ impl JMAP {
                     }
                 }
 
+                // Update the save date of the mailboxes the message was assigned to or removed from
+                if mailboxes.has_changes() {
+                    batch.update_save_dates(
+                        self.get_property::<SaveDates>(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::SaveDate,
+                        )
+                        .await?,
+                        mailboxes.current(),
+                        mailboxes.added(),
+                        now(),
+                    );
+                }
+
                 // Update mailboxIds property
                 mailboxes.update_batch(&mut batch, Property::MailboxIds);
             }
@@ -952,12 +1074,26 @@ impl JMAP {
                 changes.log_child_update(Collection::Mailbox, mailbox_id);
             }
 
+            // Update snooze and its wake-up
+            match (&snooze, &current_snooze) {
+                (Some(Some(snooze)), current) => {
+                    batch.set_snooze(account_id, document_id, current.as_ref(), *snooze);
+                }
+                (Some(None), Some(current)) => {
+                    batch.clear_snooze(account_id, document_id, current);
+                }
+                _ => (),
+            }
+
             // Write changes
             if !batch.is_empty() {
                 match self.store.write(batch.build()).await {
                     Ok(_) => {
                         // Add to updated list
                         response.updated.append(id, None);
+                        if let Some(Some(snooze)) = snooze {
+                            scheduled.push(snooze.until);
+                        }
                     }
                     Err(store::Error::AssertValueFailed) => {
                         response.not_updated.append(
@@ -1022,6 +1158,11 @@ impl JMAP {
             }
         }
 
+        // Notify the scheduler of new snooze wake-ups
+        if let Some(due) = scheduled.into_iter().min() {
+            self.snooze_notify(due).await;
+        }
+
         // Update state
         if !changes.is_empty() || !response.created.is_empty() {
             let new_state = if !changes.is_empty() {
@@ -1050,6 +1191,83 @@ impl JMAP {
     ) -> Result<Result<ChangeLogBuilder, SetError>, MethodError> {
         // Create batch
         let mut batch = BatchBuilder::new();
+        let mut deletion = match self
+            .email_delete_batch(account_id, document_id, &mut batch)
+            .await?
+        {
+            Ok(deletion) => deletion,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        // Commit batch, deleting the thread if it is empty or updating its summary otherwise
+        let result = if deletion.is_last_in_thread {
+            deletion
+                .changes
+                .log_delete(Collection::Thread, deletion.thread_id);
+            batch
+                .with_collection(Collection::Thread)
+                .delete_document(deletion.thread_id)
+                .value(Property::Value, (), F_VALUE | F_CLEAR);
+            self.store.write(batch.build()).await
+        } else {
+            deletion
+                .changes
+                .log_child_update(Collection::Thread, deletion.thread_id);
+            self.write_batch_with_thread_summary(
+                account_id,
+                deletion.thread_id,
+                batch,
+                ThreadSummaryChange::Remove {
+                    document_id,
+                    received_at: deletion.received_at,
+                },
+            )
+            .await
+        };
+        match result {
+            Ok(_) => (),
+            Err(store::Error::AssertValueFailed) => {
+                return Ok(Err(SetError::forbidden().with_description(
+                    "Another process modified this message, please try again.",
+                )));
+            }
+            Err(err) => {
+                tracing::error!(
+                    event = "error",
+                    context = "email_delete",
+                    error = ?err,
+                    "Failed to commit batch.");
+                return Err(MethodError::ServerPartialFail);
+            }
+        }
+
+        // Delete blob
+        self.store
+            .delete_blob(&BlobKind::LinkedMaildir {
+                account_id,
+                document_id,
+            })
+            .await
+            .map_err(|err| {
+                tracing::error!(
+                event = "error",
+                context = "email_delete",
+                error = ?err,
+                "Failed to delete blob.");
+                MethodError::ServerPartialFail
+            })?;
+
+        Ok(Ok(deletion.changes))
+    }
+
+    /// Adds the deletion of an email to `batch`, except for the changes to its
+    /// thread which depend on how the batch is committed.
+    pub async fn email_delete_batch(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        batch: &mut BatchBuilder,
+    ) -> Result<Result<EmailDeletion, SetError>, MethodError> {
         let mut changes = ChangeLogBuilder::with_change_id(0);
 
         // Delete document
@@ -1058,8 +1276,32 @@ impl JMAP {
             .with_collection(Collection::Email)
             .delete_document(document_id);
 
-        // Remove last changeId
+        // Remove last changeId and snooze
         batch.value(Property::Cid, (), F_VALUE | F_CLEAR);
+        if let Some(snooze) = self
+            .get_property::<Bincode<Snooze>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Snoozed,
+            )
+            .await?
+        {
+            batch.clear_snooze(account_id, document_id, &snooze.inner);
+        }
+
+        // Remove save dates
+        if let Some(save_dates) = self
+            .get_property::<SaveDates>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::SaveDate,
+            )
+            .await?
+        {
+            batch.clear_save_dates(save_dates);
+        }
 
         // Remove mailboxes
         let mailboxes = if let Some(mailboxes) = self
@@ -1118,8 +1360,7 @@ impl JMAP {
         };
 
         // Remove threadIds
-        let mut delete_thread_id = None;
-        if let Some(thread_id) = self
+        let (thread_id, is_last_in_thread) = if let Some(thread_id) = self
             .get_property::<u32>(
                 account_id,
                 Collection::Email,
@@ -1133,15 +1374,6 @@ impl JMAP {
                 .get_tag(account_id, Collection::Email, Property::ThreadId, thread_id)
                 .await?
             {
-                if thread_tags.len() > 1 {
-                    // Thread has other document ids, remove this one
-                    changes.log_child_update(Collection::Thread, thread_id);
-                } else {
-                    // Thread is empty, delete it
-                    delete_thread_id = thread_id.into();
-                    changes.log_delete(Collection::Thread, thread_id);
-                }
-
                 // Remove threadId value and tag
                 batch.assert_value(Property::ThreadId, thread_id).value(
                     Property::ThreadId,
@@ -1151,6 +1383,7 @@ impl JMAP {
 
                 // Log message deletion
                 changes.log_delete(Collection::Email, Id::from_parts(thread_id, document_id));
+                (thread_id, thread_tags.len() <= 1)
             } else {
                 tracing::debug!(
                     event = "error",
@@ -1171,9 +1404,10 @@ impl JMAP {
                 "Failed to fetch threadId.",
             );
             return Ok(Err(SetError::not_found()));
-        }
+        };
 
         // Remove message metadata
+        let mut received_at = 0;
         if let Some(metadata) = self
             .get_property::<Object<Value>>(
                 account_id,
@@ -1183,6 +1417,10 @@ impl JMAP {
             )
             .await?
         {
+            received_at = metadata
+                .get(&Property::ReceivedAt)
+                .as_date()
+                .map_or(0, |date| date.timestamp() as u64);
             batch.custom(EmailIndexBuilder::clear(metadata));
         } else {
             tracing::debug!(
@@ -1225,51 +1463,24 @@ impl JMAP {
             return Ok(Err(SetError::not_found()));
         }
 
-        // Delete threadId
-        if let Some(thread_id) = delete_thread_id {
-            batch
-                .with_collection(Collection::Thread)
-                .delete_document(thread_id);
-        }
-
-        // Commit batch
-        match self.store.write(batch.build()).await {
-            Ok(_) => (),
-            Err(store::Error::AssertValueFailed) => {
-                return Ok(Err(SetError::forbidden().with_description(
-                    "Another process modified this message, please try again.",
-                )));
-            }
-            Err(err) => {
-                tracing::error!(
-                    event = "error",
-                    context = "email_delete",
-                    error = ?err,
-                    "Failed to commit batch.");
-                return Err(MethodError::ServerPartialFail);
-            }
-        }
-
-        // Delete blob
-        self.store
-            .delete_blob(&BlobKind::LinkedMaildir {
-                account_id,
-                document_id,
-            })
-            .await
-            .map_err(|err| {
-                tracing::error!(
-                event = "error",
-                context = "email_delete",
-                error = ?err,
-                "Failed to delete blob.");
-                MethodError::ServerPartialFail
-            })?;
-
-        Ok(Ok(changes))
+        Ok(Ok(EmailDeletion {
+            changes,
+            thread_id,
+            received_at,
+            is_last_in_thread,
+        }))
     }
 }
 
+/// Deletion of an email added to a batch by `email_delete_batch`.
+pub struct EmailDeletion {
+    pub changes: ChangeLogBuilder,
+    pub thread_id: u32,
+    pub received_at: u64,
+    /// Whether the thread of the email is left empty.
+    pub is_last_in_thread: bool,
+}
+
 pub struct TagManager<
     T: PartialEq + Clone + ToBitmaps + SerializeInto + Serialize + DeserializeFrom + Sync + Send,
 > {
@@ -1335,6 +1546,27 @@ impl<
         }
     }
 
+    /// Adds or removes a tag regardless of how the tags were previously modified.
+    pub fn force_update(&mut self, tag: T, add: bool) {
+        if add {
+            if !self.current.inner.contains(&tag) {
+                if let Some(index) = self.removed.iter().position(|t| t == &tag) {
+                    self.removed.swap_remove(index);
+                } else {
+                    self.added.push(tag.clone());
+                }
+                self.current.inner.push(tag);
+            }
+        } else if let Some(index) = self.current.inner.iter().position(|t| t == &tag) {
+            let tag = self.current.inner.swap_remove(index);
+            if let Some(index) = self.added.iter().position(|t| t == &tag) {
+                self.added.swap_remove(index);
+            } else {
+                self.removed.push(tag);
+            }
+        }
+    }
+
     pub fn added(&self) -> &[T] {
         &self.added
     }
@@ -0,0 +1,735 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::borrow::Cow;
+
+use mail_parser::{HeaderValue, Message, MessagePart, MimeHeaders, PartType};
+use p256::ecdsa::{signature::hazmat::PrehashVerifier, Signature as EcdsaSignature, VerifyingKey};
+use rasn::types::{ObjectIdentifier, OctetString};
+use rasn_cms::{
+    pkcs7_compat::EncapsulatedContentInfo, CertificateChoices, SignedData, SignerIdentifier,
+    SignerInfo, CONTENT_ENVELOPED_DATA, CONTENT_SIGNED_DATA,
+};
+use rasn_pkix::{Certificate, Name, Time};
+use rsa::{pkcs1::DecodeRsaPublicKey, Pkcs1v15Sign, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use super::crypto::{try_parse_certs, EncryptionMethod};
+
+const MAX_CHAIN_LENGTH: usize = 10;
+
+const OID_SHA1: &[u32] = &[1, 3, 14, 3, 2, 26];
+const OID_SHA256: &[u32] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+const OID_SHA384: &[u32] = &[2, 16, 840, 1, 101, 3, 4, 2, 2];
+const OID_SHA512: &[u32] = &[2, 16, 840, 1, 101, 3, 4, 2, 3];
+const OID_SHA1_WITH_RSA: &[u32] = &[1, 2, 840, 113549, 1, 1, 5];
+const OID_SHA256_WITH_RSA: &[u32] = &[1, 2, 840, 113549, 1, 1, 11];
+const OID_SHA384_WITH_RSA: &[u32] = &[1, 2, 840, 113549, 1, 1, 12];
+const OID_SHA512_WITH_RSA: &[u32] = &[1, 2, 840, 113549, 1, 1, 13];
+const OID_RSA_ENCRYPTION: &[u32] = &[1, 2, 840, 113549, 1, 1, 1];
+const OID_ECDSA_WITH_SHA1: &[u32] = &[1, 2, 840, 10045, 4, 1];
+const OID_ECDSA_WITH_SHA256: &[u32] = &[1, 2, 840, 10045, 4, 3, 2];
+const OID_ECDSA_WITH_SHA384: &[u32] = &[1, 2, 840, 10045, 4, 3, 3];
+const OID_ECDSA_WITH_SHA512: &[u32] = &[1, 2, 840, 10045, 4, 3, 4];
+const OID_EC_PUBLIC_KEY: &[u32] = &[1, 2, 840, 10045, 2, 1];
+const OID_MESSAGE_DIGEST: &[u32] = &[1, 2, 840, 113549, 1, 9, 4];
+const OID_EMAIL_ADDRESS: &[u32] = &[1, 2, 840, 113549, 1, 9, 1];
+const OID_SUBJECT_KEY_ID: &[u32] = &[2, 5, 29, 14];
+const OID_SUBJECT_ALT_NAME: &[u32] = &[2, 5, 29, 17];
+const OID_KEY_USAGE: &[u32] = &[2, 5, 29, 15];
+const OID_BASIC_CONSTRAINTS: &[u32] = &[2, 5, 29, 19];
+
+// keyCertSign is bit 5 of the KeyUsage bit string
+const KEY_USAGE_KEY_CERT_SIGN: u8 = 0x80 >> 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmimeStatus {
+    Unknown,
+    Verified,
+    Failed,
+    Encrypted,
+}
+
+#[derive(Debug)]
+pub struct SmimeVerification {
+    pub status: SmimeStatus,
+    pub errors: Vec<String>,
+    /// Time after which a verified signature stops being valid, the earliest
+    /// expiration date of the certificates involved.
+    pub valid_until: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureAlgorithm {
+    RsaPkcs1v15,
+    EcdsaP256,
+}
+
+pub trait VerifySmime {
+    fn verify_smime(&self, trust_store: &[Certificate], now: i64) -> Option<SmimeVerification>;
+}
+
+impl VerifySmime for Message<'_> {
+    fn verify_smime(&self, trust_store: &[Certificate], now: i64) -> Option<SmimeVerification> {
+        let root = self.parts.first()?;
+
+        // Obtain the signed content and its signature
+        let (content, pkcs7) = if root.is_content_type("multipart", "signed") {
+            if !root
+                .content_type()
+                .and_then(|ct| ct.attribute("protocol"))
+                .map_or(false, |protocol| {
+                    let protocol = protocol.to_ascii_lowercase();
+                    protocol == "application/pkcs7-signature"
+                        || protocol == "application/x-pkcs7-signature"
+                })
+            {
+                return None;
+            }
+
+            match &root.body {
+                PartType::Multipart(part_ids) if part_ids.len() >= 2 => {
+                    let signed_part = &self.parts[part_ids[0]];
+                    match (
+                        self.raw_message
+                            .get(signed_part.offset_header..signed_part.offset_end),
+                        part_contents(&self.parts[part_ids[1]]),
+                    ) {
+                        (Some(content), Some(signature)) => {
+                            (Some(canonicalize(content)), signature)
+                        }
+                        _ => {
+                            return Some(SmimeVerification::failed(
+                                "Malformed multipart/signed message",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Some(SmimeVerification::failed(
+                        "Malformed multipart/signed message",
+                    ))
+                }
+            }
+        } else if root.is_content_type("application", "pkcs7-mime")
+            || root.is_content_type("application", "x-pkcs7-mime")
+        {
+            (None, part_contents(root).unwrap_or_default())
+        } else {
+            return None;
+        };
+
+        // Decode PKCS#7 structure
+        let content_info = match rasn::der::decode::<EncapsulatedContentInfo>(pkcs7) {
+            Ok(content_info) => content_info,
+            Err(err) => {
+                return Some(SmimeVerification::failed(format!(
+                    "Failed to decode PKCS#7 structure: {err}"
+                )))
+            }
+        };
+        if content.is_none() && oid_eq(&content_info.content_type, CONTENT_ENVELOPED_DATA) {
+            return Some(SmimeVerification {
+                status: SmimeStatus::Encrypted,
+                errors: vec![],
+                valid_until: None,
+            });
+        } else if !oid_eq(&content_info.content_type, CONTENT_SIGNED_DATA) {
+            return Some(SmimeVerification {
+                status: SmimeStatus::Unknown,
+                errors: vec![],
+                valid_until: None,
+            });
+        }
+        let signed_data = match content_info
+            .content
+            .as_ref()
+            .map(|content| rasn::der::decode::<SignedData>(content.as_bytes()))
+        {
+            Some(Ok(signed_data)) => signed_data,
+            Some(Err(err)) => {
+                return Some(SmimeVerification::failed(format!(
+                    "Failed to decode SignedData: {err}"
+                )))
+            }
+            None => return Some(SmimeVerification::failed("Missing SignedData")),
+        };
+        let content = match content
+            .as_deref()
+            .or(signed_data.encap_content_info.content.as_deref())
+        {
+            Some(content) => content,
+            None => return Some(SmimeVerification::failed("Missing signed content")),
+        };
+
+        // Verify each signer
+        let certs = signed_data
+            .certificates
+            .iter()
+            .flatten()
+            .filter_map(|cert| match cert {
+                CertificateChoices::Certificate(cert) => Some(cert.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let from = from_addresses(self);
+        let mut errors = Vec::new();
+        let mut valid_until = u64::MAX;
+        if signed_data.signer_infos.is_empty() {
+            errors.push("No signers found".to_string());
+        }
+        for signer in &signed_data.signer_infos {
+            match verify_signer(signer, content, &certs, trust_store, &from, now) {
+                Ok(signer_valid_until) => {
+                    valid_until = std::cmp::min(valid_until, signer_valid_until);
+                }
+                Err(err) => {
+                    errors.push(err);
+                }
+            }
+        }
+
+        Some(if errors.is_empty() {
+            SmimeVerification {
+                status: SmimeStatus::Verified,
+                errors,
+                valid_until: valid_until.into(),
+            }
+        } else {
+            SmimeVerification {
+                status: SmimeStatus::Failed,
+                errors,
+                valid_until: None,
+            }
+        })
+    }
+}
+
+fn verify_signer(
+    signer: &SignerInfo,
+    content: &[u8],
+    certs: &[&Certificate],
+    trust_store: &[Certificate],
+    from: &[String],
+    now: i64,
+) -> Result<u64, String> {
+    // Find signer certificate
+    let cert = certs
+        .iter()
+        .copied()
+        .find(|cert| match &signer.sid {
+            SignerIdentifier::IssuerAndSerialNumber(sid) => {
+                cert.tbs_certificate.issuer == sid.issuer
+                    && cert.tbs_certificate.serial_number == sid.serial_number
+            }
+            SignerIdentifier::SubjectKeyIdentifier(sid) => {
+                subject_key_id(cert).map_or(false, |id| id == *sid)
+            }
+        })
+        .ok_or_else(|| "Signer certificate not found".to_string())?;
+
+    // Verify message digest and signature
+    let algo = DigestAlgorithm::from_oid(&signer.digest_algorithm.algorithm)
+        .ok_or_else(|| "Unsupported digest algorithm".to_string())?;
+    let signature_algo = SignatureAlgorithm::from_oid(&signer.signature_algorithm.algorithm)
+        .ok_or_else(|| "Unsupported signature algorithm".to_string())?;
+    if let Some(signed_attrs) = &signer.signed_attrs {
+        let digest = signed_attrs
+            .iter()
+            .find(|attr| oid_eq(&attr.r#type, OID_MESSAGE_DIGEST))
+            .and_then(|attr| attr.values.iter().next())
+            .and_then(|value| rasn::der::decode::<OctetString>(value.as_bytes()).ok())
+            .ok_or_else(|| "Missing message digest attribute".to_string())?;
+        if digest.as_ref() != algo.digest(content).as_slice() {
+            return Err("Message digest mismatch".to_string());
+        }
+
+        // Signed attributes are signed using their DER encoding as a SET OF
+        let signed_attrs = rasn::der::encode(signed_attrs)
+            .map_err(|err| format!("Failed to encode signed attributes: {err}"))?;
+        verify_signature(cert, signature_algo, algo, &signed_attrs, &signer.signature)?;
+    } else {
+        verify_signature(cert, signature_algo, algo, content, &signer.signature)?;
+    }
+
+    // Verify certificate chain and sender address
+    let valid_until = verify_chain(cert, certs, trust_store, now)?;
+    let cert_addresses = certificate_addresses(cert);
+    if from.is_empty() || !from.iter().all(|addr| cert_addresses.contains(addr)) {
+        return Err("Sender address does not match the signer certificate".to_string());
+    }
+
+    Ok(valid_until)
+}
+
+// Walks the chain up to a trust anchor, returning the earliest expiration date
+// of the certificates in the path.
+fn verify_chain(
+    cert: &Certificate,
+    certs: &[&Certificate],
+    trust_store: &[Certificate],
+    now: i64,
+) -> Result<u64, String> {
+    let mut cert = cert;
+    let mut valid_until = u64::MAX;
+    let mut num_intermediates = 0;
+
+    for _ in 0..MAX_CHAIN_LENGTH {
+        valid_until = std::cmp::min(valid_until, check_validity(cert, now)?);
+
+        if trust_store.iter().any(|anchor| anchor == cert) {
+            return Ok(valid_until);
+        } else if let Some(anchor) = trust_store.iter().find(|anchor| {
+            anchor.tbs_certificate.subject == cert.tbs_certificate.issuer
+                && verify_certificate(cert, anchor).is_ok()
+        }) {
+            check_path_len(anchor, num_intermediates)?;
+            return Ok(std::cmp::min(valid_until, check_validity(anchor, now)?));
+        }
+
+        // Intermediates have to be CAs allowed to sign certificates
+        let issuer = certs
+            .iter()
+            .copied()
+            .filter(|issuer| {
+                issuer.tbs_certificate.subject == cert.tbs_certificate.issuer
+                    && issuer.tbs_certificate.subject != issuer.tbs_certificate.issuer
+            })
+            .find(|issuer| check_ca(issuer).is_ok() && verify_certificate(cert, issuer).is_ok())
+            .ok_or_else(|| "Certificate was not issued by a trusted authority".to_string())?;
+        check_path_len(issuer, num_intermediates)?;
+        num_intermediates += 1;
+        cert = issuer;
+    }
+
+    Err("Certificate chain is too long".to_string())
+}
+
+fn check_ca(cert: &Certificate) -> Result<(), String> {
+    if !basic_constraints(cert).map_or(false, |(is_ca, _)| is_ca) {
+        Err("Issuer certificate is not a CA".to_string())
+    } else if !key_usage(cert).map_or(false, |usage| usage & KEY_USAGE_KEY_CERT_SIGN != 0) {
+        Err("Issuer certificate is not allowed to sign certificates".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Checks that no more than pathLenConstraint intermediates follow the issuer
+fn check_path_len(issuer: &Certificate, num_intermediates: usize) -> Result<(), String> {
+    match basic_constraints(issuer) {
+        Some((_, Some(path_len))) if num_intermediates > path_len => {
+            Err("Certificate path length constraint exceeded".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn verify_certificate(cert: &Certificate, issuer: &Certificate) -> Result<(), String> {
+    let algo = DigestAlgorithm::from_oid(&cert.signature_algorithm.algorithm)
+        .ok_or_else(|| "Unsupported certificate signature algorithm".to_string())?;
+    let signature_algo = SignatureAlgorithm::from_oid(&cert.signature_algorithm.algorithm)
+        .ok_or_else(|| "Unsupported certificate signature algorithm".to_string())?;
+    let tbs_certificate = rasn::der::encode(&cert.tbs_certificate)
+        .map_err(|err| format!("Failed to encode certificate: {err}"))?;
+    verify_signature(
+        issuer,
+        signature_algo,
+        algo,
+        &tbs_certificate,
+        cert.signature_value.as_raw_slice(),
+    )
+    .map_err(|_| "Invalid certificate signature".to_string())
+}
+
+fn verify_signature(
+    cert: &Certificate,
+    signature_algo: SignatureAlgorithm,
+    algo: DigestAlgorithm,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    let public_key_info = &cert.tbs_certificate.subject_public_key_info;
+    let public_key = public_key_info.subject_public_key.as_raw_slice();
+
+    match signature_algo {
+        SignatureAlgorithm::RsaPkcs1v15
+            if oid_eq(&public_key_info.algorithm.algorithm, OID_RSA_ENCRYPTION) =>
+        {
+            let public_key = RsaPublicKey::from_pkcs1_der(public_key)
+                .map_err(|err| format!("Failed to parse public key: {err}"))?;
+
+            let mut hashed = algo.digest_info_prefix().to_vec();
+            hashed.extend_from_slice(&algo.digest(data));
+            public_key
+                .verify(Pkcs1v15Sign::new_unprefixed(), &hashed, signature)
+                .map_err(|_| "Invalid signature".to_string())
+        }
+        SignatureAlgorithm::EcdsaP256
+            if oid_eq(&public_key_info.algorithm.algorithm, OID_EC_PUBLIC_KEY) =>
+        {
+            let public_key = VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|err| format!("Failed to parse public key: {err}"))?;
+            let signature =
+                EcdsaSignature::from_der(signature).map_err(|_| "Invalid signature".to_string())?;
+            public_key
+                .verify_prehash(&algo.digest(data), &signature)
+                .map_err(|_| "Invalid signature".to_string())
+        }
+        _ => Err("Public key does not match the signature algorithm".to_string()),
+    }
+}
+
+// Returns the expiration date of a certificate that is valid at the given time
+fn check_validity(cert: &Certificate, now: i64) -> Result<u64, String> {
+    let validity = &cert.tbs_certificate.validity;
+    let not_after = timestamp(&validity.not_after);
+    if now < timestamp(&validity.not_before) {
+        Err("Certificate is not yet valid".to_string())
+    } else if now > not_after {
+        Err("Certificate has expired".to_string())
+    } else {
+        Ok(not_after as u64)
+    }
+}
+
+fn timestamp(time: &Time) -> i64 {
+    match time {
+        Time::Utc(time) => time.timestamp(),
+        Time::General(time) => time.timestamp(),
+    }
+}
+
+fn extension<'x>(cert: &'x Certificate, oid: &[u32]) -> Option<&'x [u8]> {
+    cert.tbs_certificate
+        .extensions
+        .as_ref()?
+        .iter()
+        .find(|ext| oid_eq(&ext.extn_id, oid))
+        .map(|ext| ext.extn_value.as_ref())
+}
+
+fn subject_key_id(cert: &Certificate) -> Option<OctetString> {
+    extension(cert, OID_SUBJECT_KEY_ID)
+        .and_then(|value| rasn::der::decode::<OctetString>(value).ok())
+}
+
+// Returns the cA flag and pathLenConstraint of the basicConstraints extension
+fn basic_constraints(cert: &Certificate) -> Option<(bool, Option<usize>)> {
+    let (tag, mut value, _) = der_next(extension(cert, OID_BASIC_CONSTRAINTS)?)?;
+    if tag != 0x30 {
+        return None;
+    }
+
+    let mut is_ca = false;
+    let mut path_len = None;
+    if let Some((0x01, flag, rest)) = der_next(value) {
+        is_ca = flag.first().map_or(false, |flag| *flag != 0);
+        value = rest;
+    }
+    if let Some((0x02, len, _)) = der_next(value) {
+        path_len = len
+            .iter()
+            .fold(0usize, |acc, byte| {
+                acc.saturating_mul(256).saturating_add(*byte as usize)
+            })
+            .into();
+    }
+
+    Some((is_ca, path_len))
+}
+
+// Returns the first byte of the keyUsage bit string
+fn key_usage(cert: &Certificate) -> Option<u8> {
+    match der_next(extension(cert, OID_KEY_USAGE)?)? {
+        (0x03, bits, _) => bits.get(1).copied(),
+        _ => None,
+    }
+}
+
+fn certificate_addresses(cert: &Certificate) -> Vec<String> {
+    let mut addresses = Vec::new();
+
+    // Add rfc822Name entries from the subjectAltName extension
+    if let Some(value) = extension(cert, OID_SUBJECT_ALT_NAME) {
+        if let Some((0x30, mut names, _)) = der_next(value) {
+            while let Some((tag, value, rest)) = der_next(names) {
+                if tag == 0x81 {
+                    addresses.push(String::from_utf8_lossy(value).to_lowercase());
+                }
+                names = rest;
+            }
+        }
+    }
+
+    // Add emailAddress attributes from the subject
+    let Name::RdnSequence(rdns) = &cert.tbs_certificate.subject;
+    for attr in rdns.iter().flatten() {
+        if oid_eq(&attr.r#type, OID_EMAIL_ADDRESS) {
+            if let Some((_, value, _)) = der_next(attr.value.as_bytes()) {
+                addresses.push(String::from_utf8_lossy(value).to_lowercase());
+            }
+        }
+    }
+
+    addresses
+}
+
+fn from_addresses(message: &Message<'_>) -> Vec<String> {
+    let mut addresses = Vec::new();
+    let mut add_address = |address: &Option<Cow<'_, str>>| {
+        if let Some(address) = address {
+            addresses.push(address.to_lowercase());
+        }
+    };
+
+    match message.from() {
+        HeaderValue::Address(addr) => add_address(&addr.address),
+        HeaderValue::AddressList(addrs) => {
+            for addr in addrs {
+                add_address(&addr.address);
+            }
+        }
+        HeaderValue::Group(group) => {
+            for addr in &group.addresses {
+                add_address(&addr.address);
+            }
+        }
+        HeaderValue::GroupList(groups) => {
+            for addr in groups.iter().flat_map(|group| group.addresses.iter()) {
+                add_address(&addr.address);
+            }
+        }
+        _ => (),
+    }
+
+    addresses
+}
+
+fn part_contents<'x>(part: &'x MessagePart<'_>) -> Option<&'x [u8]> {
+    match &part.body {
+        PartType::Binary(bytes) | PartType::InlineBinary(bytes) => Some(bytes.as_ref()),
+        PartType::Text(text) => Some(text.as_bytes()),
+        _ => None,
+    }
+}
+
+// Signed MIME entities are verified in canonical form, with CRLF line endings
+fn canonicalize(content: &[u8]) -> Cow<'_, [u8]> {
+    if content
+        .iter()
+        .enumerate()
+        .any(|(pos, &ch)| ch == b'\n' && (pos == 0 || content[pos - 1] != b'\r'))
+    {
+        let mut result = Vec::with_capacity(content.len() + 64);
+        let mut last_ch = 0;
+        for &ch in content {
+            if ch == b'\n' && last_ch != b'\r' {
+                result.push(b'\r');
+            }
+            result.push(ch);
+            last_ch = ch;
+        }
+        Cow::Owned(result)
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+// Returns the tag, contents and remaining bytes of a DER encoded value
+fn der_next(bytes: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, bytes) = bytes.split_first()?;
+    let (&len, mut bytes) = bytes.split_first()?;
+    let len = if len & 0x80 == 0 {
+        len as usize
+    } else {
+        let num_bytes = (len & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > std::mem::size_of::<usize>() || bytes.len() < num_bytes {
+            return None;
+        }
+        let mut len = 0usize;
+        for &byte in &bytes[..num_bytes] {
+            len = (len << 8) | byte as usize;
+        }
+        bytes = &bytes[num_bytes..];
+        len
+    };
+
+    if bytes.len() >= len {
+        Some((tag, &bytes[..len], &bytes[len..]))
+    } else {
+        None
+    }
+}
+
+fn oid_eq(oid: &ObjectIdentifier, arcs: &[u32]) -> bool {
+    oid.iter().copied().eq(arcs.iter().copied())
+}
+
+pub fn parse_trust_store(settings: &utils::config::Config) -> Result<Vec<Certificate>, String> {
+    let mut trust_store = Vec::new();
+
+    for (key, _) in settings.values("jmap.email.smime.trust-store") {
+        match try_parse_certs(settings.file_contents(key)?) {
+            Ok((EncryptionMethod::SMIME, certs)) => {
+                for cert in certs {
+                    trust_store.push(rasn::der::decode::<Certificate>(&cert).map_err(|err| {
+                        format!("Failed to decode certificate for property {key:?}: {err}")
+                    })?);
+                }
+            }
+            Ok(_) => {
+                return Err(format!(
+                    "Property {key:?} does not contain X.509 certificates"
+                ));
+            }
+            Err(err) => {
+                return Err(format!("Failed to parse property {key:?}: {err}"));
+            }
+        }
+    }
+
+    Ok(trust_store)
+}
+
+impl SmimeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmimeStatus::Unknown => "unknown",
+            SmimeStatus::Verified => "signed/verified",
+            SmimeStatus::Failed => "signed/failed",
+            SmimeStatus::Encrypted => "encrypted",
+        }
+    }
+
+    pub fn is_verified(&self) -> bool {
+        matches!(self, SmimeStatus::Verified)
+    }
+}
+
+impl SmimeVerification {
+    fn failed(error: impl Into<String>) -> Self {
+        SmimeVerification {
+            status: SmimeStatus::Failed,
+            errors: vec![error.into()],
+            valid_until: None,
+        }
+    }
+}
+
+impl DigestAlgorithm {
+    fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        if [OID_SHA1, OID_SHA1_WITH_RSA, OID_ECDSA_WITH_SHA1]
+            .iter()
+            .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(DigestAlgorithm::Sha1)
+        } else if [OID_SHA256, OID_SHA256_WITH_RSA, OID_ECDSA_WITH_SHA256]
+            .iter()
+            .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(DigestAlgorithm::Sha256)
+        } else if [OID_SHA384, OID_SHA384_WITH_RSA, OID_ECDSA_WITH_SHA384]
+            .iter()
+            .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(DigestAlgorithm::Sha384)
+        } else if [OID_SHA512, OID_SHA512_WITH_RSA, OID_ECDSA_WITH_SHA512]
+            .iter()
+            .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(DigestAlgorithm::Sha512)
+        } else {
+            None
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            DigestAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            DigestAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    // DER encoded DigestInfo header that precedes the hash in PKCS#1 v1.5 signatures
+    fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            DigestAlgorithm::Sha1 => &[
+                0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04,
+                0x14,
+            ],
+            DigestAlgorithm::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            DigestAlgorithm::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            DigestAlgorithm::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+}
+
+impl SignatureAlgorithm {
+    fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        if [
+            OID_RSA_ENCRYPTION,
+            OID_SHA1_WITH_RSA,
+            OID_SHA256_WITH_RSA,
+            OID_SHA384_WITH_RSA,
+            OID_SHA512_WITH_RSA,
+        ]
+        .iter()
+        .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(SignatureAlgorithm::RsaPkcs1v15)
+        } else if [
+            OID_ECDSA_WITH_SHA1,
+            OID_ECDSA_WITH_SHA256,
+            OID_ECDSA_WITH_SHA384,
+            OID_ECDSA_WITH_SHA512,
+        ]
+        .iter()
+        .any(|arcs| oid_eq(oid, arcs))
+        {
+            Some(SignatureAlgorithm::EcdsaP256)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,413 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    object::Object,
+    types::{
+        collection::Collection, date::UTCDate, id::Id, keyword::Keyword, property::Property,
+        state::StateChange, type_state::TypeState, value::Value,
+    },
+};
+use store::{
+    write::{
+        assert::HashedValue,
+        key::{DeserializeBigEndian, KeySerializer},
+        now, BatchBuilder, Operation, ValueClass, F_CLEAR, F_VALUE,
+    },
+    CustomValueKey,
+};
+
+use crate::{
+    auth::authenticate::KEY_SNOOZE_QUEUE, mailbox::INBOX_ID, services::snooze, Bincode, JMAP,
+};
+
+use super::{
+    index::{IndexSaveDate, SaveDates},
+    set::TagManager,
+};
+
+pub const SNOOZED_KEYWORD: &str = "$snoozed";
+
+/// Seconds to wait before retrying a wake-up that could not be completed.
+const RETRY_INTERVAL: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Snooze {
+    /// Time at which the email is woken up.
+    pub until: u64,
+    /// Mailbox the email is moved to once woken up.
+    pub mailbox_id: u32,
+}
+
+/// Maintains the snooze of an email together with its entry in the wake-up
+/// queue, so both are written in the same transaction.
+pub trait SnoozeSchedule {
+    fn set_snooze(
+        &mut self,
+        account_id: u32,
+        document_id: u32,
+        current: Option<&Snooze>,
+        snooze: Snooze,
+    ) -> &mut Self;
+    fn clear_snooze(&mut self, account_id: u32, document_id: u32, current: &Snooze) -> &mut Self;
+    fn snooze_queue(
+        &mut self,
+        until: u64,
+        account_id: u32,
+        document_id: u32,
+        set: bool,
+    ) -> &mut Self;
+}
+
+impl Snooze {
+    pub fn into_value(self) -> Value {
+        Value::Object(
+            Object::with_capacity(2)
+                .with_property(
+                    Property::Until,
+                    Value::Date(UTCDate::from_timestamp(self.until as i64)),
+                )
+                .with_property(
+                    Property::MoveToMailboxId,
+                    Value::Id(Id::from(self.mailbox_id)),
+                ),
+        )
+    }
+}
+
+impl JMAP {
+    /// Notifies the scheduler of a new wake-up.
+    pub async fn snooze_notify(&self, due: u64) {
+        if self
+            .snooze_tx
+            .send(snooze::Event::Schedule { due })
+            .await
+            .is_err()
+        {
+            tracing::warn!("Failed to send snooze event to scheduler.");
+        }
+    }
+
+    /// Returns the time of the earliest pending wake-up.
+    pub async fn snooze_next_due(&self) -> store::Result<Option<u64>> {
+        self.store
+            .iterate(
+                None,
+                CustomValueKey {
+                    value: snooze_queue_key(0, 0, 0),
+                },
+                CustomValueKey {
+                    value: snooze_queue_key(u64::MAX, u32::MAX, u32::MAX),
+                },
+                true,
+                true,
+                |due: &mut Option<u64>, key, _| {
+                    *due = Some(key.deserialize_be_u64(std::mem::size_of::<u32>() + 1)?);
+                    Ok(false)
+                },
+            )
+            .await
+    }
+
+    /// Wakes up all emails whose snooze has expired. Wake-ups that fail are
+    /// retried later.
+    pub async fn snooze_wake_due(&self) -> store::Result<()> {
+        let now = now();
+        let due = self
+            .store
+            .iterate(
+                Vec::new(),
+                CustomValueKey {
+                    value: snooze_queue_key(0, 0, 0),
+                },
+                CustomValueKey {
+                    value: snooze_queue_key(now, u32::MAX, u32::MAX),
+                },
+                false,
+                true,
+                |due: &mut Vec<(u64, u32, u32)>, key, _| {
+                    due.push((
+                        key.deserialize_be_u64(std::mem::size_of::<u32>() + 1)?,
+                        key.deserialize_be_u32(
+                            std::mem::size_of::<u32>() + std::mem::size_of::<u64>() + 1,
+                        )?,
+                        key.deserialize_be_u32(
+                            (std::mem::size_of::<u32>() * 2) + std::mem::size_of::<u64>() + 1,
+                        )?,
+                    ));
+                    Ok(true)
+                },
+            )
+            .await?;
+
+        for (until, account_id, document_id) in due {
+            if let Err(err) = self.snooze_wake(account_id, document_id, until).await {
+                tracing::warn!(
+                    event = "error",
+                    context = "snooze",
+                    account_id = account_id,
+                    document_id = document_id,
+                    error = ?err,
+                    "Failed to wake up snoozed email, retrying later.");
+
+                // Entries are only removed once the wake-up is committed
+                let mut batch = BatchBuilder::new();
+                batch
+                    .snooze_queue(until, account_id, document_id, false)
+                    .snooze_queue(
+                        std::cmp::max(until, now) + RETRY_INTERVAL,
+                        account_id,
+                        document_id,
+                        true,
+                    );
+                self.store.write(batch.build()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves a snoozed email back to its target mailbox and marks it as unread.
+    /// The queue entry that triggered the wake-up is removed in the same
+    /// transaction, entries left behind by rescheduled or cancelled snoozes are
+    /// discarded.
+    pub async fn snooze_wake(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        due: u64,
+    ) -> Result<(), MethodError> {
+        let mut batch = BatchBuilder::new();
+        batch.snooze_queue(due, account_id, document_id, false);
+
+        // Obtain snooze, mailboxes and keywords
+        let snooze = match self
+            .get_property::<HashedValue<Bincode<Snooze>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Snoozed,
+            )
+            .await?
+        {
+            Some(snooze) if snooze.inner.inner.until <= now() => snooze,
+            _ => return self.write_batch(batch).await,
+        };
+        let (mut mailboxes, mut keywords) = if let (Some(mailboxes), Some(keywords)) = (
+            self.get_property::<HashedValue<Vec<u32>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::MailboxIds,
+            )
+            .await?,
+            self.get_property::<HashedValue<Vec<Keyword>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await?,
+        ) {
+            (TagManager::new(mailboxes), TagManager::new(keywords))
+        } else {
+            return self.write_batch(batch).await;
+        };
+        let thread_id = if let Some(thread_id) = self
+            .get_property::<u32>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::ThreadId,
+            )
+            .await?
+        {
+            thread_id
+        } else {
+            return self.write_batch(batch).await;
+        };
+
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email)
+            .update_document(document_id)
+            .assert_value(Property::Snoozed, &snooze)
+            .clear_snooze(account_id, document_id, &snooze.inner.inner);
+
+        // Snoozes removed by an IMAP client, by deleting the keyword, are discarded
+        let snoozed_keyword = Keyword::Other(SNOOZED_KEYWORD.to_string());
+        if !keywords.current().contains(&snoozed_keyword) {
+            return self
+                .snooze_write(account_id, document_id, batch)
+                .await
+                .map(|_| ());
+        }
+
+        // Mark as unread and move to the target mailbox
+        let mut changes = self.begin_changes(account_id).await?;
+        let snooze = snooze.inner.inner;
+        let target_id = if self
+            .mailbox_get_or_create(account_id)
+            .await?
+            .contains(snooze.mailbox_id)
+            && !self
+                .mailbox_saved_search_ids(account_id)
+                .await?
+                .contains(snooze.mailbox_id)
+        {
+            snooze.mailbox_id
+        } else {
+            INBOX_ID
+        };
+        keywords.force_update(snoozed_keyword, false);
+        keywords.force_update(Keyword::Seen, false);
+        if let Some(snoozed_id) = self.mailbox_get_by_role(account_id, "snoozed").await? {
+            if snoozed_id != target_id {
+                mailboxes.force_update(snoozed_id, false);
+            }
+        }
+        mailboxes.force_update(target_id, true);
+        if !mailboxes.has_tags() {
+            mailboxes.force_update(INBOX_ID, true);
+        }
+
+        // Log changes
+        let seen_changed = keywords
+            .changed_tags()
+            .any(|keyword| keyword == &Keyword::Seen);
+        for mailbox_id in mailboxes
+            .changed_tags()
+            .chain(mailboxes.current().iter().filter(|_| seen_changed))
+        {
+            changes.log_child_update(Collection::Mailbox, *mailbox_id);
+        }
+        changes.log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+        if mailboxes.has_changes() {
+            batch.update_save_dates(
+                self.get_property::<SaveDates>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::SaveDate,
+                )
+                .await?,
+                mailboxes.current(),
+                mailboxes.added(),
+                now(),
+            );
+        }
+        keywords.update_batch(&mut batch, Property::Keywords);
+        mailboxes.update_batch(&mut batch, Property::MailboxIds);
+        batch.value(Property::Cid, changes.change_id, F_VALUE);
+        if !self.snooze_write(account_id, document_id, batch).await? {
+            return Ok(());
+        }
+
+        // Notify push and IDLE subscribers
+        let change_id = self.commit_changes(account_id, changes).await?;
+        self.broadcast_state_change(
+            StateChange::new(account_id)
+                .with_change(TypeState::Email, change_id)
+                .with_change(TypeState::Mailbox, change_id)
+                .with_change(TypeState::Thread, change_id),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn snooze_write(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        batch: BatchBuilder,
+    ) -> Result<bool, MethodError> {
+        match self.store.write(batch.build()).await {
+            Ok(_) => Ok(true),
+            Err(store::Error::AssertValueFailed) => {
+                // The email was modified concurrently, its queue entry was either
+                // removed by that change or is picked up again on the next run.
+                Ok(false)
+            }
+            Err(err) => {
+                tracing::error!(
+                    event = "error",
+                    context = "snooze",
+                    account_id = account_id,
+                    document_id = document_id,
+                    error = ?err,
+                    "Failed to wake up snoozed email.");
+                Err(MethodError::ServerPartialFail)
+            }
+        }
+    }
+}
+
+// Pending wake-ups are keyed by (until, accountId, documentId) so the ones that
+// are due can be obtained with a range scan
+impl SnoozeSchedule for BatchBuilder {
+    fn set_snooze(
+        &mut self,
+        account_id: u32,
+        document_id: u32,
+        current: Option<&Snooze>,
+        snooze: Snooze,
+    ) -> &mut Self {
+        if let Some(current) = current {
+            self.snooze_queue(current.until, account_id, document_id, false);
+        }
+        self.value(Property::Snoozed, Bincode::new(snooze), F_VALUE)
+            .snooze_queue(snooze.until, account_id, document_id, true)
+    }
+
+    fn clear_snooze(&mut self, account_id: u32, document_id: u32, current: &Snooze) -> &mut Self {
+        self.value(Property::Snoozed, (), F_VALUE | F_CLEAR)
+            .snooze_queue(current.until, account_id, document_id, false)
+    }
+
+    fn snooze_queue(
+        &mut self,
+        until: u64,
+        account_id: u32,
+        document_id: u32,
+        set: bool,
+    ) -> &mut Self {
+        self.op(Operation::Value {
+            class: ValueClass::Custom {
+                bytes: snooze_queue_key(until, account_id, document_id),
+            },
+            set: if set { Some(Vec::new()) } else { None },
+        })
+    }
+}
+
+fn snooze_queue_key(until: u64, account_id: u32, document_id: u32) -> Vec<u8> {
+    KeySerializer::new((std::mem::size_of::<u32>() * 3) + std::mem::size_of::<u64>() + 1)
+        .write(u32::MAX)
+        .write(KEY_SNOOZE_QUEUE)
+        .write(until)
+        .write(account_id)
+        .write(document_id)
+        .finalize()
+}
@@ -40,9 +40,11 @@ use jmap_proto::{
     },
     types::{collection::Collection, property::Property},
 };
+use push::vapid::VapidKey;
 use services::{
     delivery::spawn_delivery_manager,
     housekeeper::{self, init_housekeeper, spawn_housekeeper},
+    snooze::{self, init_snooze_scheduler, spawn_snooze_scheduler},
     state::{self, init_state_manager, spawn_state_manager},
 };
 use smtp::core::SMTP;
@@ -65,13 +67,17 @@ use utils::{
 pub mod api;
 pub mod auth;
 pub mod blob;
+pub mod calendar;
+pub mod calendar_event;
 pub mod changes;
 pub mod email;
 pub mod identity;
 pub mod mailbox;
+pub mod mdn;
 pub mod principal;
 pub mod push;
 pub mod services;
+pub mod share_notification;
 pub mod sieve;
 pub mod submission;
 pub mod thread;
@@ -95,6 +101,7 @@ pub struct JMAP {
 
     pub state_tx: mpsc::Sender<state::Event>,
     pub housekeeper_tx: mpsc::Sender<housekeeper::Event>,
+    pub snooze_tx: mpsc::Sender<snooze::Event>,
     pub smtp: Arc<SMTP>,
 
     pub sieve_compiler: Compiler,
@@ -103,6 +110,8 @@ pub struct JMAP {
 
 pub struct Config {
     pub default_language: Language,
+    pub fts_reindex_batch_size: usize,
+    pub fts_reindex_throttle: Duration,
     pub query_max_results: usize,
     pub changes_max_results: usize,
 
@@ -137,6 +146,7 @@ pub struct Config {
 
     pub event_source_throttle: Duration,
     pub push_max_total: usize,
+    pub push_vapid_key: Option<Arc<VapidKey>>,
 
     pub web_socket_throttle: Duration,
     pub web_socket_timeout: Duration,
@@ -153,7 +163,16 @@ pub struct Config {
     pub encrypt: bool,
     pub encrypt_append: bool,
 
+    pub smime_trust_store: Vec<rasn_pkix::Certificate>,
+
     pub principal_allow_lookups: bool,
+    pub principal_description_max_len: usize,
+
+    pub calendar_name_max_len: usize,
+    pub calendar_max_participants: usize,
+    pub calendar_max_instances: usize,
+    pub calendar_max_expand_duration: Duration,
+    pub calendar_itip_enable: bool,
 
     pub capabilities: BaseCapabilities,
 }
@@ -175,14 +194,22 @@ impl JMAP {
         delivery_rx: mpsc::Receiver<DeliveryEvent>,
         smtp: Arc<SMTP>,
     ) -> Result<Arc<Self>, String> {
-        // Init state manager and housekeeper
+        // Init state manager, housekeeper and snooze scheduler
         let (state_tx, state_rx) = init_state_manager();
         let (housekeeper_tx, housekeeper_rx) = init_housekeeper();
+        let (snooze_tx, snooze_rx) = init_snooze_scheduler();
         let shard_amount = config
             .property::<u64>("global.shared-map.shard")?
             .unwrap_or(32)
             .next_power_of_two() as usize;
 
+        // Open store and load VAPID key
+        let store = Store::open(config).await.failed("Unable to open database");
+        let mut jmap_config = Config::new(config).failed("Invalid configuration file");
+        if config.property_or_static("jmap.push.vapid.enable", "true")? {
+            jmap_config.set_push_vapid_key(VapidKey::load(&store, config).await?);
+        }
+
         let jmap_server = Arc::new(JMAP {
             directory: directory_config
                 .directories
@@ -192,8 +219,8 @@ impl JMAP {
                     config.value_require("jmap.directory")?
                 ))
                 .clone(),
-            store: Store::open(config).await.failed("Unable to open database"),
-            config: Config::new(config).failed("Invalid configuration file"),
+            store,
+            config: jmap_config,
             sessions: TtlDashMap::with_capacity(
                 config.property("jmap.session.cache.size")?.unwrap_or(100),
                 shard_amount,
@@ -222,6 +249,7 @@ impl JMAP {
             ),
             state_tx,
             housekeeper_tx,
+            snooze_tx,
             smtp,
             sieve_compiler: Compiler::new()
                 .with_max_script_size(
@@ -369,6 +397,9 @@ impl JMAP {
         // Spawn housekeeper
         spawn_housekeeper(jmap_server.clone(), config, housekeeper_rx);
 
+        // Spawn snooze scheduler
+        spawn_snooze_scheduler(jmap_server.clone(), snooze_rx);
+
         Ok(jmap_server)
     }
 
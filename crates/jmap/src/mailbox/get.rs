@@ -80,6 +80,8 @@ impl JMAP {
                     | Property::SortOrder
                     | Property::Acl
                     | Property::MyRights
+                    | Property::Metadata
+                    | Property::Filter
             )
         });
         let mut response = GetResponse {
@@ -125,7 +127,7 @@ impl JMAP {
             for property in &properties {
                 let value = match property {
                     Property::Id => Value::Id(id),
-                    Property::Name | Property::Role => values.remove(property),
+                    Property::Name | Property::Role | Property::Filter => values.remove(property),
                     Property::SortOrder => values
                         .properties
                         .remove(property)
@@ -141,40 +143,40 @@ impl JMAP {
                         })
                         .unwrap_or_default(),
                     Property::TotalEmails => Value::UnsignedInt(
-                        self.get_tag(
+                        self.mailbox_message_ids(access_token, account_id, document_id)
+                            .await?
+                            .map(|v| v.len())
+                            .unwrap_or(0),
+                    ),
+                    Property::UnreadEmails => Value::UnsignedInt(
+                        self.mailbox_unread_tags(
+                            access_token,
                             account_id,
-                            Collection::Email,
-                            Property::MailboxIds,
                             document_id,
+                            &message_ids,
                         )
                         .await?
                         .map(|v| v.len())
                         .unwrap_or(0),
                     ),
-                    Property::UnreadEmails => Value::UnsignedInt(
-                        self.mailbox_unread_tags(account_id, document_id, &message_ids)
-                            .await?
-                            .map(|v| v.len())
-                            .unwrap_or(0),
-                    ),
                     Property::TotalThreads => Value::UnsignedInt(
                         self.mailbox_count_threads(
                             account_id,
-                            self.get_tag(
-                                account_id,
-                                Collection::Email,
-                                Property::MailboxIds,
-                                document_id,
-                            )
-                            .await?,
+                            self.mailbox_message_ids(access_token, account_id, document_id)
+                                .await?,
                         )
                         .await? as u64,
                     ),
                     Property::UnreadThreads => Value::UnsignedInt(
                         self.mailbox_count_threads(
                             account_id,
-                            self.mailbox_unread_tags(account_id, document_id, &message_ids)
-                                .await?,
+                            self.mailbox_unread_tags(
+                                access_token,
+                                account_id,
+                                document_id,
+                                &message_ids,
+                            )
+                            .await?,
                         )
                         .await? as u64,
                     ),
@@ -241,6 +243,24 @@ impl JMAP {
                         )
                         .await
                     }
+                    Property::Metadata => {
+                        // Private annotations are only visible to the mailbox owner
+                        let mut metadata = values
+                            .properties
+                            .remove(property)
+                            .and_then(|v| v.try_unwrap_object())
+                            .unwrap_or_else(|| Object::with_capacity(0));
+                        if access_token.is_shared(account_id) {
+                            metadata.properties = metadata
+                                .properties
+                                .into_iter()
+                                .filter(|(entry, _)| {
+                                    matches!(entry, Property::_T(entry) if entry.starts_with("/shared/"))
+                                })
+                                .collect();
+                        }
+                        Value::Object(metadata)
+                    }
 
                     _ => Value::Null,
                 };
@@ -281,19 +301,15 @@ impl JMAP {
 
     pub async fn mailbox_unread_tags(
         &self,
+        access_token: &AccessToken,
         account_id: u32,
         document_id: u32,
         message_ids: &Option<RoaringBitmap>,
     ) -> Result<Option<RoaringBitmap>, MethodError> {
         if let (Some(message_ids), Some(mailbox_message_ids)) = (
             message_ids,
-            self.get_tag(
-                account_id,
-                Collection::Email,
-                Property::MailboxIds,
-                document_id,
-            )
-            .await?,
+            self.mailbox_message_ids(access_token, account_id, document_id)
+                .await?,
         ) {
             if let Some(mut seen) = self
                 .get_tag(
@@ -0,0 +1,298 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    method::query::Filter,
+    object::Object,
+    parser::json::Parser,
+    types::{
+        acl::Acl, collection::Collection, date::UTCDate, id::Id, keyword::Keyword,
+        property::Property, value::Value,
+    },
+};
+use store::{roaring::RoaringBitmap, write::log::ChangeLogBuilder};
+
+use crate::{auth::AccessToken, JMAP};
+
+const MAX_FILTER_DEPTH: usize = 10;
+
+impl JMAP {
+    pub async fn mailbox_saved_search_ids(
+        &self,
+        account_id: u32,
+    ) -> Result<RoaringBitmap, MethodError> {
+        self.get_tag(account_id, Collection::Mailbox, Property::Filter, ())
+            .await
+            .map(|ids| ids.unwrap_or_default())
+    }
+
+    pub async fn mailbox_saved_search(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> Result<Option<Vec<Filter>>, MethodError> {
+        Ok(self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::Mailbox,
+                document_id,
+                &Property::Value,
+            )
+            .await?
+            .and_then(|mailbox| {
+                mailbox
+                    .properties
+                    .get(&Property::Filter)
+                    .and_then(parse_saved_search)
+            }))
+    }
+
+    // Returns the messages contained in a mailbox. Saved searches hold no
+    // messages of their own, their contents are obtained by evaluating the
+    // stored filter.
+    pub async fn mailbox_message_ids(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        document_id: u32,
+    ) -> Result<Option<RoaringBitmap>, MethodError> {
+        if self
+            .mailbox_saved_search_ids(account_id)
+            .await?
+            .contains(document_id)
+        {
+            if let Some(filter) = self.mailbox_saved_search(account_id, document_id).await? {
+                let mut message_ids = self
+                    .filter(
+                        account_id,
+                        Collection::Email,
+                        self.email_query_filters(account_id, filter).await?,
+                    )
+                    .await?
+                    .results;
+
+                // Searches in shared accounts only see the messages in shared mailboxes
+                if access_token.is_shared(account_id) {
+                    message_ids &= self
+                        .shared_messages(access_token, account_id, Acl::ReadItems)
+                        .await?;
+                }
+                if !message_ids.is_empty() {
+                    return Ok(Some(message_ids));
+                }
+            }
+            Ok(None)
+        } else {
+            self.get_tag(
+                account_id,
+                Collection::Email,
+                Property::MailboxIds,
+                document_id,
+            )
+            .await
+        }
+    }
+
+    // Replaces inMailbox conditions that point to a saved search with the
+    // filter it stores. Saved searches nested inside other saved searches are
+    // not expanded, which also prevents circular references.
+    pub async fn mailbox_saved_search_expand(
+        &self,
+        account_id: u32,
+        filter: Vec<Filter>,
+    ) -> Result<Vec<Filter>, MethodError> {
+        if !filter
+            .iter()
+            .any(|cond| matches!(cond, Filter::InMailbox(_)))
+        {
+            return Ok(filter);
+        }
+        let saved_search_ids = self.mailbox_saved_search_ids(account_id).await?;
+        if saved_search_ids.is_empty() {
+            return Ok(filter);
+        }
+
+        let mut expanded_filter = Vec::with_capacity(filter.len());
+        for cond in filter {
+            match cond {
+                Filter::InMailbox(id) if saved_search_ids.contains(id.document_id()) => {
+                    if let Some(saved_filter) = self
+                        .mailbox_saved_search(account_id, id.document_id())
+                        .await?
+                    {
+                        expanded_filter.push(Filter::And);
+                        expanded_filter.extend(saved_filter);
+                        expanded_filter.push(Filter::Close);
+                    } else {
+                        expanded_filter.push(Filter::InMailbox(id));
+                    }
+                }
+                cond => expanded_filter.push(cond),
+            }
+        }
+
+        Ok(expanded_filter)
+    }
+
+    // Saved searches are evaluated on demand, so any change to the account's
+    // emails is logged as a potential change to their contents. The saved
+    // searches are only looked up when the log contains email changes.
+    pub async fn mailbox_saved_search_log_changes(
+        &self,
+        account_id: u32,
+        changes: &mut ChangeLogBuilder,
+    ) -> Result<(), MethodError> {
+        if changes
+            .changes
+            .get(&u8::from(Collection::Email))
+            .map_or(false, |changes| {
+                !changes.inserts.is_empty()
+                    || !changes.updates.is_empty()
+                    || !changes.deletes.is_empty()
+            })
+        {
+            for document_id in self.mailbox_saved_search_ids(account_id).await? {
+                changes.log_child_update(Collection::Mailbox, document_id);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts a stored saved search into a query filter. Only email conditions
+/// are accepted, any other property makes the whole filter invalid.
+pub fn parse_saved_search(value: &Value) -> Option<Vec<Filter>> {
+    let mut filter = Vec::new();
+    saved_search_condition(value, &mut filter, 0)?;
+    Some(filter)
+}
+
+fn saved_search_condition(value: &Value, filter: &mut Vec<Filter>, depth: usize) -> Option<()> {
+    let condition = value.as_obj()?;
+    if depth > MAX_FILTER_DEPTH {
+        return None;
+    }
+
+    if let Some((_, operator)) = condition
+        .properties
+        .iter()
+        .find(|(property, _)| property.to_string() == "operator")
+    {
+        filter.push(match operator.as_string()? {
+            "AND" => Filter::And,
+            "OR" => Filter::Or,
+            "NOT" => Filter::Not,
+            _ => return None,
+        });
+        for (property, value) in condition.properties.iter() {
+            match property.to_string().as_str() {
+                "operator" => (),
+                "conditions" => {
+                    for value in value.as_list()? {
+                        saved_search_condition(value, filter, depth + 1)?;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        filter.push(Filter::Close);
+    } else {
+        // Properties of a single condition must all match
+        let is_compound = condition.properties.len() > 1;
+        if is_compound {
+            filter.push(Filter::And);
+        }
+        for (property, value) in condition.properties.iter() {
+            filter.push(saved_search_property(&property.to_string(), value)?);
+        }
+        if is_compound {
+            filter.push(Filter::Close);
+        }
+    }
+
+    Some(())
+}
+
+fn saved_search_property(property: &str, value: &Value) -> Option<Filter> {
+    let text = || value.as_string().map(|text| text.to_string());
+    let id = || {
+        value
+            .as_string()
+            .and_then(|id| Id::from_bytes(id.as_bytes()))
+    };
+    let keyword = || text().map(Keyword::from);
+    let size = || value.as_uint().and_then(|size| u32::try_from(size).ok());
+    let date = || {
+        let date = format!("\"{}\"", value.as_string()?);
+        Parser::new(date.as_bytes())
+            .next_token::<UTCDate>()
+            .ok()?
+            .unwrap_string("")
+            .ok()
+    };
+
+    Some(match property {
+        "inMailbox" => Filter::InMailbox(id()?),
+        "inMailboxOtherThan" => Filter::InMailboxOtherThan(
+            value
+                .as_list()?
+                .iter()
+                .map(|id| id.as_string().and_then(|id| Id::from_bytes(id.as_bytes())))
+                .collect::<Option<Vec<_>>>()?,
+        ),
+        "before" => Filter::Before(date()?),
+        "after" => Filter::After(date()?),
+        "minSize" => Filter::MinSize(size()?),
+        "maxSize" => Filter::MaxSize(size()?),
+        "allInThreadHaveKeyword" => Filter::AllInThreadHaveKeyword(keyword()?),
+        "someInThreadHaveKeyword" => Filter::SomeInThreadHaveKeyword(keyword()?),
+        "noneInThreadHaveKeyword" => Filter::NoneInThreadHaveKeyword(keyword()?),
+        "hasKeyword" => Filter::HasKeyword(keyword()?),
+        "notKeyword" => Filter::NotKeyword(keyword()?),
+        "hasAttachment" => Filter::HasAttachment(value.as_bool()?),
+        "text" => Filter::Text(text()?),
+        "from" => Filter::From(text()?),
+        "to" => Filter::To(text()?),
+        "cc" => Filter::Cc(text()?),
+        "bcc" => Filter::Bcc(text()?),
+        "subject" => Filter::Subject(text()?),
+        "body" => Filter::Body(text()?),
+        "header" => Filter::Header(
+            value
+                .as_list()?
+                .iter()
+                .map(|value| value.as_string().map(|value| value.to_string()))
+                .collect::<Option<Vec<_>>>()
+                .filter(|header| matches!(header.len(), 1 | 2))?,
+        ),
+        "sentBefore" => Filter::SentBefore(date()?),
+        "sentAfter" => Filter::SentAfter(date()?),
+        "threadMinSize" => Filter::ThreadMinSize(size()?),
+        "threadMaxSize" => Filter::ThreadMaxSize(size()?),
+        "threadReceivedBefore" => Filter::ThreadReceivedBefore(date()?),
+        "threadReceivedAfter" => Filter::ThreadReceivedAfter(date()?),
+        _ => return None,
+    })
+}
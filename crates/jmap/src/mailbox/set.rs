@@ -46,15 +46,19 @@ use jmap_proto::{
 use store::{
     query::Filter,
     roaring::RoaringBitmap,
-    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, F_BITMAP, F_CLEAR, F_VALUE},
+    write::{
+        assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, F_BITMAP, F_CLEAR, F_VALUE,
+    },
 };
 
 use crate::{
     auth::{acl::EffectiveAcl, AccessToken},
+    email::index::{IndexSaveDate, SaveDates},
+    share_notification::ShareChange,
     JMAP,
 };
 
-use super::{INBOX_ID, TRASH_ID};
+use super::{saved_search::parse_saved_search, INBOX_ID, TRASH_ID};
 
 struct SetContext<'x> {
     account_id: u32,
@@ -81,6 +85,7 @@ pub static SCHEMA: &[IndexProperty] = &[
     IndexProperty::new(Property::SortOrder).index_as(IndexAs::Integer),
     IndexProperty::new(Property::IsSubscribed).index_as(IndexAs::IntegerList),
     IndexProperty::new(Property::Acl).index_as(IndexAs::Acl),
+    IndexProperty::new(Property::Filter).index_as(IndexAs::HasProperty),
 ];
 
 impl JMAP {
@@ -113,6 +118,14 @@ impl JMAP {
                     let document_id = self
                         .assign_document_id(account_id, Collection::Mailbox)
                         .await?;
+                    let share_change = ShareChange::new(
+                        Collection::Mailbox,
+                        account_id,
+                        document_id,
+                        builder.get(&Property::Name),
+                        &Value::Null,
+                        builder.get(&Property::Acl),
+                    );
                     batch
                         .with_account_id(account_id)
                         .with_collection(Collection::Mailbox)
@@ -122,6 +135,12 @@ impl JMAP {
                     ctx.mailbox_ids.insert(document_id);
                     self.write_batch(batch).await?;
                     ctx.response.created(id, document_id);
+
+                    // Notify sharees
+                    if let Some(share_change) = share_change {
+                        self.share_notification_send(access_token, share_change)
+                            .await;
+                    }
                 }
                 Err(err) => {
                     ctx.response.not_created.append(id, err);
@@ -174,11 +193,25 @@ impl JMAP {
                     }
                 }
 
+                let current_acl = mailbox
+                    .inner
+                    .properties
+                    .get(&Property::Acl)
+                    .cloned()
+                    .unwrap_or(Value::Null);
                 match self
                     .mailbox_set_item(object, (document_id, mailbox).into(), &ctx)
                     .await?
                 {
                     Ok(builder) => {
+                        let share_change = ShareChange::new(
+                            Collection::Mailbox,
+                            account_id,
+                            document_id,
+                            builder.get(&Property::Name),
+                            &current_acl,
+                            builder.get(&Property::Acl),
+                        );
                         let mut batch = BatchBuilder::new();
                         batch
                             .with_account_id(account_id)
@@ -189,6 +222,12 @@ impl JMAP {
                             match self.store.write(batch.build()).await {
                                 Ok(_) => {
                                     changes.log_update(Collection::Mailbox, document_id);
+
+                                    // Notify sharees
+                                    if let Some(share_change) = share_change {
+                                        self.share_notification_send(access_token, share_change)
+                                            .await;
+                                    }
                                 }
                                 Err(store::Error::AssertValueFailed) => {
                                     ctx.response.not_updated.append(id, SetError::forbidden().with_description(
@@ -340,6 +379,18 @@ impl JMAP {
                                     .with_account_id(account_id)
                                     .with_collection(Collection::Email)
                                     .update_document(message_id)
+                                    .update_save_dates(
+                                        self.get_property::<SaveDates>(
+                                            account_id,
+                                            Collection::Email,
+                                            message_id,
+                                            Property::SaveDate,
+                                        )
+                                        .await?,
+                                        &mailbox_ids.inner,
+                                        &[],
+                                        now(),
+                                    )
                                     .assert_value(Property::MailboxIds, &mailbox_ids)
                                     .value(Property::MailboxIds, mailbox_ids.inner, F_VALUE)
                                     .value(Property::MailboxIds, document_id, F_BITMAP | F_CLEAR);
@@ -432,7 +483,19 @@ impl JMAP {
             let mut batch = BatchBuilder::new();
             batch
                 .with_account_id(account_id)
-                .with_collection(Collection::Mailbox)
+                .with_collection(Collection::Mailbox);
+
+            // Release the Snoozed mailbox creation marker
+            if matches!(
+                mailbox.inner.get(&Property::Role),
+                Value::Text(role) if role == "snoozed"
+            ) {
+                batch
+                    .update_document(u32::MAX)
+                    .value(Property::Role, (), F_VALUE | F_CLEAR);
+            }
+
+            batch
                 .delete_document(document_id)
                 .value(Property::EmailIds, (), F_VALUE | F_CLEAR)
                 .custom(ObjectIndexBuilder::new(SCHEMA).with_current(mailbox));
@@ -529,7 +592,7 @@ impl JMAP {
                 (Property::Role, MaybePatchValue::Value(Value::Text(value))) => {
                     let role = value.trim().to_lowercase();
                     if [
-                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent",
+                        "inbox", "trash", "spam", "junk", "drafts", "archive", "sent", "snoozed",
                     ]
                     .contains(&role.as_str())
                     {
@@ -544,6 +607,16 @@ impl JMAP {
                 (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
                     Value::UnsignedInt(value)
                 }
+                (Property::Filter, MaybePatchValue::Value(value @ Value::Object(_))) => {
+                    if parse_saved_search(&value).is_some() {
+                        value
+                    } else {
+                        return Ok(Err(SetError::invalid_properties()
+                            .with_property(Property::Filter)
+                            .with_description("Invalid filter.")));
+                    }
+                }
+                (Property::Filter, MaybePatchValue::Value(Value::Null)) => Value::Null,
                 (Property::Acl, value) => {
                     match self
                         .acl_set(&mut changes, update.as_ref().map(|(_, obj)| obj), value)
@@ -676,6 +749,49 @@ impl JMAP {
             }
         }
 
+        // Saved searches cannot have a role or contain messages.
+        let current_value = |property: &Property| {
+            update
+                .as_ref()
+                .and_then(|(_, current)| current.inner.properties.get(property))
+        };
+        if matches!(
+            changes
+                .properties
+                .get(&Property::Filter)
+                .or_else(|| current_value(&Property::Filter)),
+            Some(Value::Object(_))
+        ) {
+            if matches!(
+                changes
+                    .properties
+                    .get(&Property::Role)
+                    .or_else(|| current_value(&Property::Role)),
+                Some(Value::Text(_))
+            ) {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_properties([Property::Filter, Property::Role])
+                    .with_description("Saved searches cannot have a role.")));
+            }
+
+            if let Some((document_id, _)) = &update {
+                if !self
+                    .get_tag(
+                        ctx.account_id,
+                        Collection::Email,
+                        Property::MailboxIds,
+                        *document_id,
+                    )
+                    .await?
+                    .unwrap_or_default()
+                    .is_empty()
+                {
+                    return Ok(Err(SetError::new(SetErrorType::MailboxHasEmail)
+                        .with_description("Mailbox is not empty.")));
+                }
+            }
+        }
+
         // Verify that the mailbox name is unique.
         if let Value::Text(mailbox_name) = changes.get(&Property::Name) {
             // Obtain parent mailbox id
@@ -797,6 +913,97 @@ impl JMAP {
         Ok(mailbox_ids)
     }
 
+    pub async fn mailbox_get_or_create_snoozed(&self, account_id: u32) -> Result<u32, MethodError> {
+        let mut try_count = 0;
+
+        loop {
+            if let Some(document_id) = self.mailbox_get_by_role(account_id, "snoozed").await? {
+                return Ok(document_id);
+            }
+
+            // Concurrent creations are detected by asserting the id of the last
+            // Snoozed mailbox created in the account
+            let last_id = self
+                .get_property::<HashedValue<u32>>(
+                    account_id,
+                    Collection::Mailbox,
+                    u32::MAX,
+                    Property::Role,
+                )
+                .await?;
+
+            // Avoid clashing with an existing top-level mailbox named "Snoozed"
+            let mut name = "Snoozed".to_string();
+            let mut num = 1;
+            while !self
+                .filter(
+                    account_id,
+                    Collection::Mailbox,
+                    vec![
+                        Filter::eq(Property::Name, name.as_str()),
+                        Filter::eq(Property::ParentId, 0u32),
+                    ],
+                )
+                .await?
+                .results
+                .is_empty()
+            {
+                num += 1;
+                name = format!("Snoozed {num}");
+            }
+
+            let document_id = self
+                .assign_document_id(account_id, Collection::Mailbox)
+                .await?;
+            let mut changes = self.begin_changes(account_id).await?;
+            let change_id = changes.change_id;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Mailbox)
+                .update_document(u32::MAX);
+            if let Some(last_id) = &last_id {
+                batch.assert_value(Property::Role, last_id);
+            } else {
+                batch.assert_value(Property::Role, ());
+            }
+            batch
+                .value(Property::Role, document_id, F_VALUE)
+                .create_document(document_id)
+                .custom(
+                    ObjectIndexBuilder::new(SCHEMA).with_changes(
+                        Object::with_capacity(3)
+                            .with_property(Property::Name, name)
+                            .with_property(Property::Role, "snoozed")
+                            .with_property(Property::ParentId, Value::Id(0u64.into())),
+                    ),
+                );
+            changes.log_insert(Collection::Mailbox, document_id);
+            batch.custom(changes);
+
+            match self.store.write(batch.build()).await {
+                Ok(_) => {
+                    self.broadcast_state_change(
+                        StateChange::new(account_id).with_change(TypeState::Mailbox, change_id),
+                    )
+                    .await;
+                    return Ok(document_id);
+                }
+                Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                    try_count += 1;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        event = "error",
+                        context = "mailbox_get_or_create_snoozed",
+                        error = ?err,
+                        "Failed to create Snoozed mailbox.");
+                    return Err(MethodError::ServerPartialFail);
+                }
+            }
+        }
+    }
+
     pub async fn mailbox_create_path(
         &self,
         account_id: u32,
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    method::mdn::{MdnParseRequest, MdnParseResponse},
+    object::mdn::{Disposition, Mdn},
+    types::{collection::Collection, id::Id, property::Property},
+};
+use mail_parser::{Message, MimeHeaders, PartType};
+use store::query::Filter;
+use utils::map::vec_map::VecMap;
+
+use crate::{auth::AccessToken, submission::status::parse_fields, JMAP};
+
+impl JMAP {
+    pub async fn mdn_parse(
+        &self,
+        request: MdnParseRequest,
+        access_token: &AccessToken,
+    ) -> Result<MdnParseResponse, MethodError> {
+        if request.blob_ids.len() > self.config.mail_parse_max_items {
+            return Err(MethodError::RequestTooLarge);
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = MdnParseResponse {
+            account_id: request.account_id,
+            parsed: VecMap::with_capacity(request.blob_ids.len()),
+            not_parsable: vec![],
+            not_found: vec![],
+        };
+
+        for blob_id in request.blob_ids {
+            // Fetch raw message to parse
+            let raw_message = match self.blob_download(&blob_id, access_token).await? {
+                Some(raw_message) => raw_message,
+                None => {
+                    response.not_found.push(blob_id);
+                    continue;
+                }
+            };
+            let mut mdn = if let Some(mdn) = Message::parse(&raw_message).and_then(|message| {
+                let mut mdn = parse_mdn(&message)?;
+                mdn.subject = message.subject().map(|subject| subject.to_string());
+                mdn.text_body = message.body_text(0).map(|text| text.into_owned());
+                Some(mdn)
+            }) {
+                mdn
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+
+            // Find the original message
+            if let Some(message_id) = &mdn.original_message_id {
+                if let Some(document_id) = self
+                    .filter(
+                        account_id,
+                        Collection::Email,
+                        vec![Filter::eq(Property::MessageId, message_id.as_str())],
+                    )
+                    .await?
+                    .results
+                    .min()
+                {
+                    if let Some(thread_id) = self
+                        .get_property::<u32>(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::ThreadId,
+                        )
+                        .await?
+                    {
+                        mdn.for_email_id = Id::from_parts(thread_id, document_id).into();
+                    }
+                }
+            }
+
+            response.parsed.append(blob_id, mdn);
+        }
+
+        Ok(response)
+    }
+}
+
+fn parse_mdn(message: &Message<'_>) -> Option<Mdn> {
+    if !message
+        .parts
+        .first()?
+        .is_content_type("multipart", "report")
+    {
+        return None;
+    }
+
+    let mut mdn = Mdn::default();
+    let mut has_report = false;
+    for part in &message.parts {
+        if part.is_content_type("message", "rfc822") {
+            mdn.include_original_message = true;
+        } else if part.is_content_type("message", "disposition-notification")
+            || part.is_content_type("message", "global-disposition-notification")
+        {
+            let contents = match &part.body {
+                PartType::Text(text) => text.as_ref().into(),
+                PartType::Binary(bytes) | PartType::InlineBinary(bytes) => {
+                    String::from_utf8_lossy(bytes.as_ref())
+                }
+                _ => continue,
+            };
+
+            for (name, value) in parse_fields(&contents).into_iter().flatten() {
+                match name.as_str() {
+                    "reporting-ua" => {
+                        mdn.reporting_ua = value.into();
+                    }
+                    "mdn-gateway" => {
+                        mdn.mdn_gateway = value.into();
+                    }
+                    "original-recipient" => {
+                        mdn.original_recipient = value.into();
+                    }
+                    "final-recipient" => {
+                        mdn.final_recipient = value.into();
+                    }
+                    "original-message-id" => {
+                        mdn.original_message_id = value
+                            .trim()
+                            .trim_start_matches('<')
+                            .trim_end_matches('>')
+                            .to_string()
+                            .into();
+                    }
+                    "disposition" => {
+                        mdn.disposition = parse_disposition(&value);
+                    }
+                    "error" => {
+                        mdn.error.get_or_insert_with(Vec::new).push(value);
+                    }
+                    _ => {
+                        mdn.extension_fields
+                            .get_or_insert_with(VecMap::new)
+                            .append(name, value);
+                    }
+                }
+            }
+            has_report = true;
+        }
+    }
+
+    if has_report && mdn.disposition.is_some() {
+        Some(mdn)
+    } else {
+        None
+    }
+}
+
+fn parse_disposition(value: &str) -> Option<Disposition> {
+    let (modes, type_) = value.split_once(';')?;
+    let (action_mode, sending_mode) = modes.split_once('/')?;
+    let type_ = type_
+        .split_once('/')
+        .map_or(type_, |(type_, _)| type_)
+        .trim()
+        .to_ascii_lowercase();
+
+    Some(Disposition {
+        action_mode: action_mode.trim().to_ascii_lowercase(),
+        sending_mode: sending_mode.trim().to_ascii_lowercase(),
+        type_,
+    })
+}
@@ -0,0 +1,520 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{collections::HashMap, fmt::Write, sync::Arc};
+
+use jmap_proto::{
+    error::{
+        method::MethodError,
+        set::{SetError, SetErrorType},
+    },
+    method::{
+        mdn::{MdnSendRequest, MdnSendResponse},
+        set::{self, SetRequest},
+    },
+    object::{mdn::Mdn, Object},
+    request::{
+        method::{MethodFunction, MethodName, MethodObject},
+        reference::MaybeReference,
+        Call, RequestMethod,
+    },
+    types::{
+        collection::Collection, id::Id, keyword::Keyword, property::Property, state::StateChange,
+        type_state::TypeState, value::Value,
+    },
+};
+use mail_builder::{
+    headers::content_type::ContentType,
+    mime::{BodyPart, MimePart},
+    MessageBuilder,
+};
+use mail_parser::{parsers::MessageStream, HeaderValue, Message, MessagePart};
+use smtp::core::{NullIo, Session, SessionData, State};
+use smtp_proto::{MailFrom, RcptTo};
+use store::{
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, F_VALUE},
+    BlobKind,
+};
+use utils::{listener::ServerInstance, map::vec_map::VecMap};
+
+use crate::{email::set::TagManager, JMAP};
+
+impl JMAP {
+    pub async fn mdn_send(
+        &self,
+        request: MdnSendRequest,
+        instance: &Arc<ServerInstance>,
+        next_call: &mut Option<Call<RequestMethod>>,
+    ) -> Result<MdnSendResponse, MethodError> {
+        if request.send.len() > self.config.set_max_objects {
+            return Err(MethodError::RequestTooLarge);
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = MdnSendResponse {
+            account_id: request.account_id,
+            sent: VecMap::with_capacity(request.send.len()),
+            not_sent: VecMap::new(),
+        };
+
+        // Fetch identity
+        let (from_name, from_email) = if let Some(mut identity) = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::Identity,
+                request.identity_id.document_id(),
+                Property::Value,
+            )
+            .await?
+        {
+            (
+                identity
+                    .properties
+                    .remove(&Property::Name)
+                    .and_then(|value| value.try_unwrap_string())
+                    .unwrap_or_default(),
+                identity
+                    .properties
+                    .remove(&Property::Email)
+                    .and_then(|value| value.try_unwrap_string())
+                    .unwrap_or_default(),
+            )
+        } else {
+            return Err(MethodError::InvalidArguments(format!(
+                "Identity {} not found.",
+                request.identity_id
+            )));
+        };
+
+        // Send MDNs
+        let mut changes = ChangeLogBuilder::new();
+        let mut sent_email_ids = HashMap::new();
+        for (create_id, mdn) in request.send {
+            match self
+                .send_mdn(
+                    account_id,
+                    &from_name,
+                    &from_email,
+                    mdn,
+                    instance,
+                    &mut changes,
+                )
+                .await?
+            {
+                Ok((email_id, mdn)) => {
+                    sent_email_ids.insert(create_id.clone(), email_id);
+                    response.sent.append(create_id, mdn);
+                }
+                Err(err) => {
+                    response.not_sent.append(create_id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id)
+                    .with_change(TypeState::Email, change_id)
+                    .with_change(TypeState::Mailbox, change_id)
+                    .with_change(TypeState::Thread, change_id),
+            )
+            .await;
+        }
+
+        // On success
+        if let Some(update) = request.on_success_update_email {
+            if !sent_email_ids.is_empty() {
+                *next_call = Call {
+                    id: String::new(),
+                    name: MethodName::new(MethodObject::Email, MethodFunction::Set),
+                    method: RequestMethod::Set(SetRequest {
+                        account_id: request.account_id,
+                        if_in_state: None,
+                        create: None,
+                        update: update
+                            .into_iter()
+                            .filter_map(|(id, value)| {
+                                (
+                                    match id {
+                                        MaybeReference::Value(id) => id,
+                                        MaybeReference::Reference(id_ref) => {
+                                            *(sent_email_ids.get(&id_ref)?)
+                                        }
+                                    },
+                                    value,
+                                )
+                                    .into()
+                            })
+                            .collect::<VecMap<_, _>>()
+                            .into(),
+                        destroy: None,
+                        arguments: set::RequestArguments::Email,
+                    }),
+                }
+                .into();
+            }
+        }
+
+        Ok(response)
+    }
+
+    async fn send_mdn(
+        &self,
+        account_id: u32,
+        from_name: &str,
+        from_email: &str,
+        mdn: Mdn,
+        instance: &Arc<ServerInstance>,
+        changes: &mut ChangeLogBuilder,
+    ) -> Result<Result<(Id, Mdn), SetError>, MethodError> {
+        // Validate request
+        let email_id = if let Some(email_id) = mdn.for_email_id {
+            email_id
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::EmailId)
+                .with_description("Missing forEmailId property.")));
+        };
+        let disposition = match &mdn.disposition {
+            Some(disposition)
+                if ["manual-action", "automatic-action"]
+                    .contains(&disposition.action_mode.as_str())
+                    && ["mdn-sent-manually", "mdn-sent-automatically"]
+                        .contains(&disposition.sending_mode.as_str())
+                    && ["deleted", "dispatched", "displayed", "processed"]
+                        .contains(&disposition.type_.as_str()) =>
+            {
+                disposition
+            }
+            _ => {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::Disposition)
+                    .with_description("Invalid or missing disposition.")));
+            }
+        };
+        if mdn
+            .reporting_ua
+            .iter()
+            .chain(mdn.error.iter().flatten())
+            .chain(
+                mdn.extension_fields
+                    .iter()
+                    .flat_map(|fields| fields.iter().flat_map(|(name, value)| [name, value])),
+            )
+            .any(|value| value.contains(['\r', '\n']))
+            || mdn.extension_fields.as_ref().map_or(false, |fields| {
+                fields
+                    .keys()
+                    .any(|name| name.is_empty() || name.contains([':', ' ']))
+            })
+        {
+            return Ok(Err(SetError::invalid_properties()
+                .with_description("Invalid characters found in report fields.")));
+        }
+
+        // Obtain the current keywords
+        let document_id = email_id.document_id();
+        let mut keywords = if let Some(keywords) = self
+            .get_property::<HashedValue<Vec<Keyword>>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await?
+        {
+            TagManager::new(keywords)
+        } else {
+            return Ok(Err(
+                SetError::not_found().with_description(format!("Email {email_id} not found."))
+            ));
+        };
+        if keywords.current().contains(&Keyword::MdnSent) {
+            return Ok(Err(SetError::new(SetErrorType::MdnAlreadySent)
+                .with_description("An MDN was already sent for this message.")));
+        }
+
+        // Obtain the original message
+        let raw_message = if let Some(raw_message) = self
+            .get_blob(
+                &BlobKind::LinkedMaildir {
+                    account_id,
+                    document_id,
+                },
+                0..u32::MAX,
+            )
+            .await?
+        {
+            raw_message
+        } else {
+            return Ok(Err(SetError::not_found().with_description(format!(
+                "Blob for email {email_id} not found."
+            ))));
+        };
+        let message = if let Some(message) = Message::parse(&raw_message) {
+            message
+        } else {
+            return Ok(Err(SetError::new(SetErrorType::InvalidEmail)
+                .with_description("Failed to parse original message.")));
+        };
+        let rcpt_to = if let Some(rcpt_to) = header_address(
+            &message.parts[0],
+            &raw_message,
+            "Disposition-Notification-To",
+        ) {
+            rcpt_to
+        } else {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
+                .with_description(
+                    "The message does not request a disposition notification.",
+                )));
+        };
+
+        // Build report
+        let original_recipient = header_text(&message.parts[0], &raw_message, "Original-Recipient");
+        let final_recipient = format!("rfc822; {from_email}");
+        let original_message_id = message.message_id().map(|id| id.to_string());
+        let mut report = String::with_capacity(256);
+        if let Some(reporting_ua) = &mdn.reporting_ua {
+            let _ = write!(report, "Reporting-UA: {reporting_ua}\r\n");
+        }
+        if let Some(original_recipient) = &original_recipient {
+            let _ = write!(report, "Original-Recipient: {original_recipient}\r\n");
+        }
+        let _ = write!(report, "Final-Recipient: {final_recipient}\r\n");
+        if let Some(original_message_id) = &original_message_id {
+            let _ = write!(report, "Original-Message-ID: <{original_message_id}>\r\n");
+        }
+        let _ = write!(
+            report,
+            "Disposition: {}/{}; {}\r\n",
+            disposition.action_mode, disposition.sending_mode, disposition.type_
+        );
+        for error in mdn.error.iter().flatten() {
+            let _ = write!(report, "Error: {error}\r\n");
+        }
+        for (name, value) in mdn.extension_fields.iter().flatten() {
+            let _ = write!(report, "{name}: {value}\r\n");
+        }
+
+        // Build message
+        let original_subject = message.subject().unwrap_or_default();
+        let subject = mdn.subject.clone().unwrap_or_else(|| {
+            format!(
+                "Disposition notification: {} ({})",
+                original_subject, disposition.type_
+            )
+        });
+        let text_body = mdn.text_body.clone().unwrap_or_else(|| {
+            format!(
+                "The message with subject \"{}\" sent to {} has been {}.",
+                original_subject, from_email, disposition.type_
+            )
+        });
+        let mut parts = vec![
+            MimePart::new(
+                ContentType::new("text/plain"),
+                BodyPart::Text(text_body.into()),
+            ),
+            MimePart::new(
+                ContentType::new("message/disposition-notification"),
+                BodyPart::Text(report.into()),
+            ),
+        ];
+        if mdn.include_original_message {
+            parts.push(MimePart::new(
+                ContentType::new("message/rfc822"),
+                BodyPart::Text(String::from_utf8_lossy(&raw_message).into_owned().into()),
+            ));
+        }
+        let mut builder = MessageBuilder::new();
+        builder = if !from_name.is_empty() {
+            builder.from((from_name, from_email))
+        } else {
+            builder.from(from_email)
+        };
+        if let Some(original_message_id) = &original_message_id {
+            builder = builder.in_reply_to(original_message_id.as_str());
+        }
+        let mdn_message = builder
+            .to(rcpt_to.as_str())
+            .subject(subject)
+            .body(MimePart::new(
+                ContentType::new("multipart/report")
+                    .attribute("report-type", "disposition-notification"),
+                BodyPart::Multipart(parts),
+            ))
+            .write_to_vec()
+            .unwrap_or_default();
+        if mdn_message.len() > self.config.mail_max_size {
+            return Ok(Err(SetError::new(SetErrorType::TooLarge).with_description(
+                format!(
+                    "Message exceeds maximum size of {} bytes.",
+                    self.config.mail_max_size
+                ),
+            )));
+        }
+
+        // Submit MDN using a null return path
+        let mut session =
+            Session::<NullIo>::local(self.smtp.clone(), instance.clone(), SessionData::default());
+        let _ = session
+            .handle_mail_from(MailFrom {
+                address: String::new(),
+                ..Default::default()
+            })
+            .await;
+        if let Some(error) = session.has_failed() {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenMailFrom)
+                .with_description(format!(
+                    "Server rejected MAIL-FROM: {}",
+                    error.trim()
+                ))));
+        }
+        let _ = session
+            .handle_rcpt_to(RcptTo {
+                address: rcpt_to,
+                ..Default::default()
+            })
+            .await;
+        if let Some(error) = session.has_failed() {
+            return Ok(Err(SetError::new(SetErrorType::InvalidRecipients)
+                .with_description(format!(
+                    "Server rejected RCPT-TO: {}",
+                    error.trim()
+                ))));
+        }
+        session.data.message = mdn_message;
+        let response = session.queue_message().await;
+        if !matches!(session.state, State::Accepted(_)) {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
+                .with_description(format!(
+                    "Server rejected DATA: {}",
+                    std::str::from_utf8(&response).unwrap_or_default().trim()
+                ))));
+        }
+
+        // Set the $mdnsent keyword now that the MDN was queued
+        if changes.change_id == u64::MAX {
+            changes.change_id = self.assign_change_id(account_id).await?;
+        }
+        let mut try_count = 0;
+        loop {
+            keywords.update(Keyword::MdnSent, true);
+            if !keywords.has_changes() {
+                break;
+            }
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Email)
+                .update_document(document_id);
+            keywords.update_batch(&mut batch, Property::Keywords);
+            batch.value(Property::Cid, changes.change_id, F_VALUE);
+            match self.store.write(batch.build()).await {
+                Ok(_) => {
+                    changes.log_update(Collection::Email, email_id);
+                    break;
+                }
+                Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                    // Another process modified the keywords, reload and retry
+                    try_count += 1;
+                    if let Some(current) = self
+                        .get_property::<HashedValue<Vec<Keyword>>>(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::Keywords,
+                        )
+                        .await?
+                    {
+                        keywords = TagManager::new(current);
+                    } else {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(
+                        event = "error",
+                        context = "mdn_send",
+                        error = ?err,
+                        "Failed to write message changes to database.");
+                    return Err(MethodError::ServerPartialFail);
+                }
+            }
+        }
+
+        Ok(Ok((
+            email_id,
+            Mdn {
+                include_original_message: mdn.include_original_message,
+                original_recipient,
+                final_recipient: final_recipient.into(),
+                original_message_id,
+                ..Default::default()
+            },
+        )))
+    }
+}
+
+fn header_text(part: &MessagePart<'_>, raw_message: &[u8], name: &str) -> Option<String> {
+    part.headers
+        .iter()
+        .rev()
+        .find(|header| header.name.as_str().eq_ignore_ascii_case(name))
+        .and_then(|header| raw_message.get(header.offset_start..header.offset_end))
+        .map(|value| String::from_utf8_lossy(value).trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn header_address(part: &MessagePart<'_>, raw_message: &[u8], name: &str) -> Option<String> {
+    let header = part
+        .headers
+        .iter()
+        .rev()
+        .find(|header| header.name.as_str().eq_ignore_ascii_case(name))?;
+    let address = match MessageStream::new(raw_message.get(header.offset_start..header.offset_end)?)
+        .parse_address()
+    {
+        HeaderValue::Address(addr) => addr.address,
+        HeaderValue::AddressList(addrs) => addrs.into_iter().next()?.address,
+        HeaderValue::Group(group) => group.addresses.into_iter().next()?.address,
+        HeaderValue::GroupList(groups) => {
+            groups
+                .into_iter()
+                .next()?
+                .addresses
+                .into_iter()
+                .next()?
+                .address
+        }
+        _ => None,
+    }?;
+
+    if address.contains('@') {
+        Some(address.into_owned())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,178 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    method::availability::{
+        BusyPeriod, BusyStatus, GetAvailabilityRequest, GetAvailabilityResponse,
+    },
+    object::Object,
+    types::{acl::Acl, collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+use store::query;
+
+use crate::{
+    auth::AccessToken,
+    calendar_event::{get::instance_id, recurrence::Recurrence},
+    JMAP,
+};
+
+impl JMAP {
+    pub async fn principal_get_availability(
+        &self,
+        request: GetAvailabilityRequest,
+        access_token: &AccessToken,
+    ) -> Result<GetAvailabilityResponse, MethodError> {
+        let account_id = request.id.document_id();
+        let after = request.utc_start.timestamp();
+        let before = request.utc_end.timestamp();
+        if before <= after
+            || before - after > self.config.calendar_max_expand_duration.as_secs() as i64
+        {
+            return Err(MethodError::InvalidArguments(format!(
+                "The requested time range must be positive and cannot exceed {} days.",
+                self.config.calendar_max_expand_duration.as_secs() / 86400
+            )));
+        } else if self.get_account_name(account_id).await?.is_none() {
+            return Err(MethodError::NotFound);
+        }
+
+        // Obtain the events the user is allowed to see the free/busy status of
+        let mut filters = vec![
+            query::Filter::lt(Property::UtcStart, before.max(0) as u64),
+            query::Filter::gt(Property::UtcEnd, after.max(0) as u64),
+        ];
+        let mut readable_ids = None;
+        if !access_token.is_member(account_id) {
+            let calendar_ids = self
+                .shared_documents(
+                    access_token,
+                    account_id,
+                    Collection::Calendar,
+                    vec![Acl::Read, Acl::ReadItems],
+                )
+                .await?;
+            if calendar_ids.is_empty() {
+                return Err(MethodError::Forbidden(
+                    "You are not allowed to read the availability of this principal.".to_string(),
+                ));
+            }
+            filters.push(query::Filter::Or);
+            for calendar_id in calendar_ids {
+                filters.push(query::Filter::eq(Property::CalendarIds, calendar_id));
+            }
+            filters.push(query::Filter::End);
+
+            if request.show_details {
+                readable_ids = self
+                    .shared_events(access_token, account_id, Acl::ReadItems)
+                    .await?
+                    .into();
+            }
+        }
+        let event_ids = self
+            .filter(account_id, Collection::CalendarEvent, filters)
+            .await?
+            .results;
+
+        let mut list = Vec::new();
+        for document_id in event_ids {
+            let event = if let Some(event) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                event
+            } else {
+                continue;
+            };
+
+            // Free and cancelled events do not block time
+            let busy_status = match (
+                event.get(&Property::FreeBusyStatus).as_string(),
+                event.get(&Property::Status).as_string(),
+            ) {
+                (Some("free"), _) | (_, Some("cancelled")) => continue,
+                (_, Some("tentative")) => BusyStatus::Tentative,
+                _ => BusyStatus::Confirmed,
+            };
+            let recurrence = if let Some(recurrence) = Recurrence::from_event(&event, None) {
+                recurrence
+            } else {
+                continue;
+            };
+            let show_details = request.show_details
+                && readable_ids
+                    .as_ref()
+                    .map_or(true, |ids| ids.contains(document_id));
+
+            let max_instances = self
+                .config
+                .calendar_max_instances
+                .saturating_sub(list.len());
+            for instance in recurrence.instances(after, before, max_instances) {
+                let event = if show_details {
+                    let id = if recurrence.is_recurrent() {
+                        instance_id(document_id, &recurrence, &instance.recurrence_id)
+                    } else {
+                        Some(document_id.into())
+                    };
+                    let mut result = Object::with_capacity(8);
+                    result.append(Property::Id, id.map(Value::Id).unwrap_or(Value::Null));
+                    for (property, value) in &event.properties {
+                        if !matches!(
+                            property,
+                            Property::Id | Property::UtcStart | Property::UtcEnd
+                        ) && request
+                            .event_properties
+                            .as_ref()
+                            .map_or(true, |properties| properties.contains(property))
+                        {
+                            result.append(property.clone(), value.clone());
+                        }
+                    }
+                    Some(result)
+                } else {
+                    None
+                };
+
+                list.push(BusyPeriod {
+                    utc_start: UTCDate::from_timestamp(instance.utc_start),
+                    utc_end: UTCDate::from_timestamp(instance.utc_end),
+                    busy_status,
+                    event,
+                });
+            }
+            if list.len() >= self.config.calendar_max_instances {
+                break;
+            }
+        }
+        list.sort_unstable_by_key(|period| period.utc_start.timestamp());
+
+        Ok(GetAvailabilityResponse { list })
+    }
+}
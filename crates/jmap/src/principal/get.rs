@@ -25,7 +25,7 @@ use jmap_proto::{
     error::method::MethodError,
     method::get::{GetRequest, GetResponse, RequestArguments},
     object::Object,
-    types::{collection::Collection, property::Property, state::State, value::Value},
+    types::{collection::Collection, property::Property, value::Value},
 };
 
 use crate::JMAP;
@@ -42,8 +42,8 @@ impl JMAP {
             Property::Name,
             Property::Description,
             Property::Email,
-            //Property::Timezone,
-            //Property::Capabilities,
+            Property::TimeZone,
+            Property::Capabilities,
         ]);
         let email_submission_ids = self
             .get_document_ids(u32::MAX, Collection::EmailSubmission)
@@ -60,7 +60,10 @@ impl JMAP {
         };
         let mut response = GetResponse {
             account_id: request.account_id.into(),
-            state: State::Initial.into(),
+            state: self
+                .get_state(u32::MAX, Collection::Principal)
+                .await?
+                .into(),
             list: Vec::with_capacity(ids.len()),
             not_found: vec![],
         };
@@ -87,17 +90,36 @@ impl JMAP {
                 continue;
             };
 
+            // Obtain the properties set by the user
+            let mut preferences = self
+                .get_property::<Object<Value>>(
+                    u32::MAX,
+                    Collection::Principal,
+                    id.document_id(),
+                    Property::Value,
+                )
+                .await?
+                .unwrap_or_default();
+
             let mut result = Object::with_capacity(properties.len());
             for property in &properties {
                 let value = match property {
                     Property::Id => Value::Id(id),
                     Property::Type => Value::Text(principal.typ.to_jmap().to_string()),
                     Property::Name => Value::Text(principal.name.clone()),
-                    Property::Description => principal
-                        .description
-                        .clone()
-                        .map(Value::Text)
-                        .unwrap_or(Value::Null),
+                    Property::Description => match preferences.remove(property) {
+                        Value::Null => principal
+                            .description
+                            .clone()
+                            .map(Value::Text)
+                            .unwrap_or(Value::Null),
+                        description => description,
+                    },
+                    Property::TimeZone => preferences.remove(property),
+                    Property::Capabilities => match preferences.remove(property) {
+                        Value::Null => Value::Object(Object::with_capacity(0)),
+                        capabilities => capabilities,
+                    },
                     Property::Email => self
                         .directory
                         .emails_by_name(&name)
@@ -21,5 +21,7 @@
  * for more details.
 */
 
+pub mod availability;
 pub mod get;
 pub mod query;
+pub mod set;
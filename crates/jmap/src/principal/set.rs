@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::{method::MethodError, set::SetError},
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::Object,
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        value::{MaybePatchValue, Value},
+    },
+};
+use store::write::{BatchBuilder, F_VALUE};
+
+use crate::{auth::AccessToken, calendar_event::recurrence::parse_time_zone, JMAP};
+
+impl JMAP {
+    pub async fn principal_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> Result<SetResponse, MethodError> {
+        // Principals are shared by all accounts, their changes are logged under u32::MAX
+        let mut response = SetResponse::from_request(&request, self.config.set_max_objects)?
+            .with_state(
+                self.assert_state(u32::MAX, Collection::Principal, &request.if_in_state)
+                    .await?,
+            );
+
+        // Principals are managed by the directory
+        for (id, _) in request.unwrap_create() {
+            response.not_created.append(
+                id,
+                SetError::forbidden().with_description("Principals cannot be created."),
+            );
+        }
+        for id in request.unwrap_destroy() {
+            response.not_destroyed.append(
+                id,
+                SetError::forbidden().with_description("Principals cannot be destroyed."),
+            );
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Users may only update their own principal
+            let document_id = id.document_id();
+            if document_id != access_token.primary_id() && !access_token.is_super_user() {
+                response.not_updated.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to modify this principal."),
+                );
+                continue 'update;
+            } else if self.get_account_name(document_id).await?.is_none() {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            }
+
+            let mut principal = self
+                .get_property::<Object<Value>>(
+                    u32::MAX,
+                    Collection::Principal,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+                .unwrap_or_else(|| Object::with_capacity(3));
+
+            for (property, value) in object.properties {
+                let value = match response.eval_object_references(value) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+                let value = match (&property, value) {
+                    (Property::Description, MaybePatchValue::Value(Value::Text(value))) => {
+                        if value.len() <= self.config.principal_description_max_len {
+                            Value::Text(value)
+                        } else {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::Description)
+                                    .with_description("Description is too long."),
+                            );
+                            continue 'update;
+                        }
+                    }
+                    (Property::TimeZone, MaybePatchValue::Value(Value::Text(value))) => {
+                        if parse_time_zone(&value).is_some() {
+                            Value::Text(value)
+                        } else {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::TimeZone)
+                                    .with_description(format!("Unknown time zone {value:?}.")),
+                            );
+                            continue 'update;
+                        }
+                    }
+                    (Property::Capabilities, MaybePatchValue::Value(value @ Value::Object(_))) => {
+                        value
+                    }
+                    (
+                        Property::Description | Property::TimeZone | Property::Capabilities,
+                        MaybePatchValue::Value(Value::Null),
+                    ) => Value::Null,
+                    (Property::Id | Property::Type | Property::Name | Property::Email, _) => {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden()
+                                .with_property(property)
+                                .with_description("This property is managed by the directory."),
+                        );
+                        continue 'update;
+                    }
+                    _ => {
+                        response.not_updated.append(
+                            id,
+                            SetError::invalid_properties()
+                                .with_property(property)
+                                .with_description("Invalid property or value."),
+                        );
+                        continue 'update;
+                    }
+                };
+
+                if let Value::Null = value {
+                    principal.remove(&property);
+                } else {
+                    principal.set(property, value);
+                }
+            }
+
+            // Update record
+            let mut changes = self.begin_changes(u32::MAX).await?;
+            changes.log_update(Collection::Principal, document_id);
+            let change_id = changes.change_id;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(u32::MAX)
+                .with_collection(Collection::Principal)
+                .update_document(document_id)
+                .value(Property::Value, principal, F_VALUE)
+                .custom(changes);
+            self.write_batch(batch).await?;
+            response.new_state = Some(change_id.into());
+            response.updated.append(id, None);
+        }
+
+        Ok(response)
+    }
+}
@@ -27,19 +27,30 @@ use store::ahash::{AHashMap, AHashSet};
 use tokio::sync::mpsc;
 use utils::{config::Config, UnwrapFailure};
 
-use crate::{api::StateChangeResponse, services::IPC_CHANNEL_BUFFER, LONG_SLUMBER};
+use crate::{api::StateChangeResponse, services::IPC_CHANNEL_BUFFER, JMAP, LONG_SLUMBER};
 
-use super::{ece::ece_encrypt, EncryptionKeys, Event, PushServer, PushUpdate};
+use super::{ece::ece_encrypt, vapid::VapidKey, EncryptionKeys, Event, PushServer, PushUpdate};
 
-use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE},
+    StatusCode,
+};
 use std::{
     collections::hash_map::Entry,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
+enum DeliveryStatus {
+    Success,
+    Failure,
+    Expired,
+}
+
+pub fn spawn_push_manager(core: Arc<JMAP>, settings: &Config) -> mpsc::Sender<Event> {
     let (push_tx_, mut push_rx) = mpsc::channel::<Event>(IPC_CHANNEL_BUFFER);
     let push_tx = push_tx_.clone();
+    let vapid_key = core.config.push_vapid_key.clone();
 
     let push_attempt_interval: Duration = settings
         .property_or_static("jmap.push.attempts.interval", "1m")
@@ -98,6 +109,7 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
                                         })
                                         .unwrap_or(true)
                                     {
+                                        let vapid_key = vapid_key.clone();
                                         tokio::spawn(async move {
                                             http_request(
                                                 url,
@@ -111,6 +123,7 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
                                                     code
                                                 ),
                                                 keys,
+                                                vapid_key,
                                                 push_timeout,
                                             )
                                             .await;
@@ -158,9 +171,18 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
                                         && last_request > push_throttle)
                                         || ((1..push_attempts_max)
                                             .contains(&subscription.num_attempts)
-                                            && last_request > push_attempt_interval))
+                                            && last_request
+                                                > backoff(
+                                                    push_attempt_interval,
+                                                    subscription.num_attempts,
+                                                )))
                                 {
-                                    subscription.send(id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        id,
+                                        push_tx.clone(),
+                                        vapid_key.clone(),
+                                        push_timeout,
+                                    );
                                     retry_ids.remove(&id);
                                 } else {
                                     retry_ids.insert(id);
@@ -189,6 +211,27 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
                             retry_ids.insert(id);
                         }
                     }
+                    Event::DeliveryExpired { id } => {
+                        if let Some(subscription) = subscriptions.remove(&id) {
+                            tracing::debug!(
+                                "Removing expired push subscription for url {}.",
+                                subscription.url
+                            );
+                            retry_ids.remove(&id);
+
+                            let core = core.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = core.push_subscription_expire(id).await {
+                                    tracing::error!(
+                                        context = "push_manager",
+                                        event = "error",
+                                        reason = %err,
+                                        "Failed to remove expired push subscription."
+                                    );
+                                }
+                            });
+                        }
+                    }
                 },
                 Ok(None) => {
                     break;
@@ -210,10 +253,19 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
                                 && ((subscription.num_attempts == 0
                                     && last_request >= push_throttle)
                                     || (subscription.num_attempts > 0
-                                        && last_request >= push_attempt_interval))
+                                        && last_request
+                                            >= backoff(
+                                                push_attempt_interval,
+                                                subscription.num_attempts,
+                                            )))
                             {
                                 if subscription.num_attempts < push_attempts_max {
-                                    subscription.send(*retry_id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        *retry_id,
+                                        push_tx.clone(),
+                                        vapid_key.clone(),
+                                        push_timeout,
+                                    );
                                 } else {
                                     tracing::debug!(
                                         concat!(
@@ -255,7 +307,13 @@ pub fn spawn_push_manager(settings: &Config) -> mpsc::Sender<Event> {
 }
 
 impl PushServer {
-    fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, push_timeout: Duration) {
+    fn send(
+        &mut self,
+        id: Id,
+        push_tx: mpsc::Sender<Event>,
+        vapid_key: Option<Arc<VapidKey>>,
+        push_timeout: Duration,
+    ) {
         let url = self.url.clone();
         let keys = self.keys.clone();
         let state_changes = std::mem::take(&mut self.state_changes);
@@ -276,17 +334,18 @@ impl PushServer {
 
             push_tx
                 .send(
-                    if http_request(
+                    match http_request(
                         url,
                         serde_json::to_string(&response).unwrap(),
                         keys,
+                        vapid_key,
                         push_timeout,
                     )
                     .await
                     {
-                        Event::DeliverySuccess { id }
-                    } else {
-                        Event::DeliveryFailure { id, state_changes }
+                        DeliveryStatus::Success => Event::DeliverySuccess { id },
+                        DeliveryStatus::Failure => Event::DeliveryFailure { id, state_changes },
+                        DeliveryStatus::Expired => Event::DeliveryExpired { id },
                     },
                 )
                 .await
@@ -299,8 +358,9 @@ async fn http_request(
     url: String,
     mut body: String,
     keys: Option<EncryptionKeys>,
+    vapid_key: Option<Arc<VapidKey>>,
     push_timeout: Duration,
-) -> bool {
+) -> DeliveryStatus {
     let client_builder = reqwest::Client::builder().timeout(push_timeout);
 
     #[cfg(feature = "test_mode")]
@@ -324,16 +384,35 @@ async fn http_request(
             Err(err) => {
                 // Do not reattempt if encryption fails.
                 tracing::debug!("Failed to encrypt push subscription to {}: {}", url, err);
-                return true;
+                return DeliveryStatus::Success;
             }
         }
     }
 
+    if let Some(authorization) = vapid_key.and_then(|vapid_key| vapid_key.authorization(&url)) {
+        client = client.header(AUTHORIZATION, authorization);
+    }
+
     match client.body(body).send().await {
-        Ok(response) => response.status().is_success(),
+        Ok(response) => match response.status() {
+            status if status.is_success() => DeliveryStatus::Success,
+            StatusCode::NOT_FOUND | StatusCode::GONE => {
+                // The push service no longer accepts messages for this endpoint.
+                DeliveryStatus::Expired
+            }
+            status => {
+                tracing::debug!("HTTP post to {} failed with status: {}", url, status);
+                DeliveryStatus::Failure
+            }
+        },
         Err(err) => {
             tracing::debug!("HTTP post to {} failed with: {}", url, err);
-            false
+            DeliveryStatus::Failure
         }
     }
 }
+
+/// Doubles the wait between consecutive delivery attempts.
+fn backoff(interval: Duration, num_attempts: u32) -> Duration {
+    interval.saturating_mul(1 << num_attempts.saturating_sub(1).min(16))
+}
@@ -30,6 +30,7 @@ use jmap_proto::{
     types::{
         collection::Collection,
         date::UTCDate,
+        id::Id,
         property::Property,
         type_state::TypeState,
         value::{MaybePatchValue, Value},
@@ -212,6 +213,22 @@ impl JMAP {
 
         Ok(response)
     }
+
+    /// Removes a push subscription after its push service reported that the
+    /// endpoint no longer exists.
+    pub async fn push_subscription_expire(&self, id: Id) -> store::Result<()> {
+        let account_id = id.prefix_id();
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::PushSubscription)
+            .delete_document(id.document_id())
+            .value(Property::Value, (), F_VALUE | F_CLEAR);
+        self.store.write(batch.build()).await?;
+        self.update_push_subscriptions(account_id).await;
+
+        Ok(())
+    }
 }
 
 fn validate_push_value(
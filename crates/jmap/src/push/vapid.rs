@@ -0,0 +1,155 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use base64::{engine::general_purpose, Engine};
+use jmap_proto::types::collection::Collection;
+use p256::{
+    ecdsa::{signature::Signer, Signature, SigningKey},
+    elliptic_curve::rand_core::OsRng,
+};
+use store::{
+    write::{key::KeySerializer, now, BatchBuilder, Operation, ValueClass},
+    CustomValueKey, Serialize, Store,
+};
+use utils::config::Config;
+
+use crate::auth::authenticate::KEY_VAPID;
+
+/// Application server key used to identify this server to push services (RFC 8292).
+pub struct VapidKey {
+    signing_key: SigningKey,
+    public_key: String,
+    subject: String,
+    expiry: u64,
+}
+
+impl VapidKey {
+    /// Loads the VAPID key from the configuration or, if not configured, from the
+    /// store. A new key is generated and persisted the first time the server starts.
+    pub async fn load(store: &Store, settings: &Config) -> Result<Self, String> {
+        let signing_key = if let Some(key) = settings.text_file_contents("jmap.push.vapid.key")? {
+            decode_signing_key(key.trim())
+                .ok_or_else(|| "Invalid VAPID key in 'jmap.push.vapid.key'.".to_string())?
+        } else {
+            let mut try_count = 0;
+            loop {
+                if let Some(key) = store
+                    .get_value::<String>(CustomValueKey { value: vapid_key() })
+                    .await
+                    .map_err(|err| format!("Failed to obtain VAPID key: {err}"))?
+                {
+                    break decode_signing_key(&key)
+                        .ok_or_else(|| "Invalid VAPID key found in store.".to_string())?;
+                }
+
+                let signing_key = SigningKey::random(&mut OsRng);
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(u32::MAX)
+                    .with_collection(Collection::Principal)
+                    .assert_value(ValueClass::Custom { bytes: vapid_key() }, ())
+                    .op(Operation::Value {
+                        class: ValueClass::Custom { bytes: vapid_key() },
+                        set: general_purpose::URL_SAFE_NO_PAD
+                            .encode(signing_key.to_bytes())
+                            .serialize()
+                            .into(),
+                    });
+
+                match store.write(batch.build()).await {
+                    Ok(_) => break signing_key,
+                    Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                        // Another node generated the key first
+                        try_count += 1;
+                    }
+                    Err(err) => return Err(format!("Failed to store VAPID key: {err}")),
+                }
+            }
+        };
+
+        Ok(VapidKey {
+            public_key: general_purpose::URL_SAFE_NO_PAD.encode(
+                signing_key
+                    .verifying_key()
+                    .to_encoded_point(false)
+                    .as_bytes(),
+            ),
+            signing_key,
+            subject: settings
+                .value("jmap.push.vapid.subject")
+                .map(|subject| subject.to_string())
+                .or_else(|| {
+                    settings
+                        .value("server.hostname")
+                        .map(|hostname| format!("mailto:postmaster@{hostname}"))
+                })
+                .unwrap_or_else(|| "mailto:postmaster@localhost".to_string()),
+            expiry: settings
+                .property_or_static::<std::time::Duration>("jmap.push.vapid.expiry", "12h")?
+                .as_secs()
+                .clamp(60, 86400),
+        })
+    }
+
+    /// Returns the uncompressed public key, base64url encoded.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Builds the value of the `Authorization` header for a request to the
+    /// specified push endpoint.
+    pub fn authorization(&self, url: &str) -> Option<String> {
+        let audience = reqwest::Url::parse(url)
+            .ok()?
+            .origin()
+            .ascii_serialization();
+        let header = general_purpose::URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = general_purpose::URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": now() + self.expiry,
+                "sub": self.subject,
+            })
+            .to_string(),
+        );
+        let token = format!("{header}.{claims}");
+        let signature: Signature = self.signing_key.sign(token.as_bytes());
+
+        Some(format!(
+            "vapid t={token}.{}, k={}",
+            general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key
+        ))
+    }
+}
+
+fn decode_signing_key(key: &str) -> Option<SigningKey> {
+    SigningKey::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(key).ok()?).ok()
+}
+
+fn vapid_key() -> Vec<u8> {
+    KeySerializer::new(std::mem::size_of::<u32>() + 1)
+        .write(u32::MAX)
+        .write(KEY_VAPID)
+        .finalize()
+}
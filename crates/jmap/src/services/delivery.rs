@@ -35,6 +35,9 @@ pub fn spawn_delivery_manager(core: Arc<JMAP>, mut delivery_rx: mpsc::Receiver<D
                 DeliveryEvent::Ingest { message, result_tx } => {
                     result_tx.send(core.deliver_message(message).await).ok();
                 }
+                DeliveryEvent::Report(report) => {
+                    core.update_delivery_status(report).await;
+                }
                 DeliveryEvent::Stop => break,
             }
         }
@@ -22,7 +22,10 @@
 */
 
 use std::{
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -38,6 +41,7 @@ pub enum Event {
     PurgeDb,
     PurgeBlobs,
     PurgeSessions,
+    ReindexFts { account_id: Option<u32> },
     Exit,
 }
 
@@ -67,6 +71,11 @@ pub fn spawn_housekeeper(core: Arc<JMAP>, settings: &Config, mut rx: mpsc::Recei
 
     tokio::spawn(async move {
         tracing::debug!("Housekeeper task started.");
+
+        // Resume any interrupted full-text index rebuild
+        let reindex_running = Arc::new(AtomicBool::new(false));
+        spawn_fts_reindex(core.clone(), reindex_running.clone());
+
         loop {
             let time_to_next = [
                 purge_db_at.time_to_next(),
@@ -82,6 +91,14 @@ pub fn spawn_housekeeper(core: Arc<JMAP>, settings: &Config, mut rx: mpsc::Recei
                     Event::PurgeDb => tasks_to_run[TASK_PURGE_DB] = true,
                     Event::PurgeBlobs => tasks_to_run[TASK_PURGE_BLOBS] = true,
                     Event::PurgeSessions => tasks_to_run[TASK_PURGE_SESSIONS] = true,
+                    Event::ReindexFts { account_id } => {
+                        match core.fts_reindex_schedule(account_id).await {
+                            Ok(_) => spawn_fts_reindex(core.clone(), reindex_running.clone()),
+                            Err(err) => {
+                                tracing::error!("Error while scheduling reindex: {}", err);
+                            }
+                        }
+                    }
                     Event::Exit => {
                         tracing::debug!("Housekeeper task exiting.");
                         return;
@@ -144,6 +161,15 @@ pub fn spawn_housekeeper(core: Arc<JMAP>, settings: &Config, mut rx: mpsc::Recei
     });
 }
 
+fn spawn_fts_reindex(core: Arc<JMAP>, is_running: Arc<AtomicBool>) {
+    if !is_running.swap(true, Ordering::Relaxed) {
+        tokio::spawn(async move {
+            core.fts_reindex_run().await;
+            is_running.store(false, Ordering::Relaxed);
+        });
+    }
+}
+
 pub fn init_housekeeper() -> (mpsc::Sender<Event>, mpsc::Receiver<Event>) {
     mpsc::channel::<Event>(IPC_CHANNEL_BUFFER)
 }
@@ -26,7 +26,10 @@ use mail_parser::Message;
 use store::ahash::AHashMap;
 use utils::ipc::{DeliveryResult, IngestMessage};
 
-use crate::{email::ingest::IngestEmail, mailbox::INBOX_ID, IngestError, JMAP};
+use crate::{
+    calendar_event::itip::ItipMessage, email::ingest::IngestEmail, mailbox::INBOX_ID,
+    submission::status::ReceivedReport, IngestError, JMAP,
+};
 
 impl JMAP {
     pub async fn deliver_message(&self, message: IngestMessage) -> Vec<DeliveryResult> {
@@ -42,6 +45,17 @@ impl JMAP {
             }
         };
 
+        // Check whether the message is a DSN or MDN
+        let report =
+            Message::parse(&raw_message).and_then(|message| ReceivedReport::parse(&message));
+
+        // Check whether the message contains an iMIP scheduling message
+        let itip = if self.config.calendar_itip_enable {
+            Message::parse(&raw_message).and_then(|message| ItipMessage::parse(&message))
+        } else {
+            None
+        };
+
         // Obtain the UIDs for each recipient
         let mut recipients = Vec::with_capacity(message.recipients.len());
         let mut deliver_names = AHashMap::with_capacity(message.recipients.len());
@@ -105,6 +119,7 @@ impl JMAP {
                         received_at: None,
                         skip_duplicates: true,
                         encrypt: self.config.encrypt,
+                        replace: None,
                     })
                     .await
                 }
@@ -120,14 +135,40 @@ impl JMAP {
                 Ok(ingested_message) => {
                     // Notify state change
                     if ingested_message.change_id != u64::MAX {
-                        self.broadcast_state_change(
-                            StateChange::new(uid)
-                                .with_change(TypeState::EmailDelivery, ingested_message.change_id)
-                                .with_change(TypeState::Email, ingested_message.change_id)
-                                .with_change(TypeState::Mailbox, ingested_message.change_id)
-                                .with_change(TypeState::Thread, ingested_message.change_id),
-                        )
-                        .await;
+                        let mut state_change = StateChange::new(uid)
+                            .with_change(TypeState::EmailDelivery, ingested_message.change_id)
+                            .with_change(TypeState::Email, ingested_message.change_id)
+                            .with_change(TypeState::Mailbox, ingested_message.change_id)
+                            .with_change(TypeState::Thread, ingested_message.change_id);
+
+                        // Link delivery reports to their email submissions
+                        if let Some(report) = &report {
+                            if let Ok(Some(change_id)) = self
+                                .link_received_report(uid, report, ingested_message.blob_id)
+                                .await
+                            {
+                                state_change =
+                                    state_change.with_change(TypeState::EmailSubmission, change_id);
+                            }
+                        }
+
+                        // Apply scheduling messages to the recipient's calendars
+                        if let Some(itip) = &itip {
+                            if let Ok(Some(change_id)) = self
+                                .calendar_itip_ingest(
+                                    uid,
+                                    itip,
+                                    &message.sender_address,
+                                    message.sender_authenticated,
+                                )
+                                .await
+                            {
+                                state_change =
+                                    state_change.with_change(TypeState::CalendarEvent, change_id);
+                            }
+                        }
+
+                        self.broadcast_state_change(state_change).await;
                     }
                 }
                 Err(err) => match err {
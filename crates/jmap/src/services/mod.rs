@@ -24,6 +24,7 @@
 pub mod delivery;
 pub mod housekeeper;
 pub mod ingest;
+pub mod snooze;
 pub mod state;
 
 pub const IPC_CHANNEL_BUFFER: usize = 1024;
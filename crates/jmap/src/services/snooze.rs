@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use store::write::now;
+use tokio::sync::mpsc;
+
+use crate::JMAP;
+
+use super::IPC_CHANNEL_BUFFER;
+
+pub enum Event {
+    Schedule { due: u64 },
+    Exit,
+}
+
+// Wake-ups scheduled by other nodes sharing the store are only picked up on polls
+const MAX_SLEEP: u64 = 5 * 60;
+
+pub fn spawn_snooze_scheduler(core: Arc<JMAP>, mut rx: mpsc::Receiver<Event>) {
+    tokio::spawn(async move {
+        tracing::debug!("Snooze scheduler task started.");
+
+        let mut next_due = match core.snooze_next_due().await {
+            Ok(next_due) => next_due,
+            Err(err) => {
+                tracing::error!("Error while obtaining snoozed emails: {}", err);
+                None
+            }
+        };
+
+        loop {
+            let sleep = next_due.map_or(MAX_SLEEP, |due| {
+                std::cmp::min(due.saturating_sub(now()), MAX_SLEEP)
+            });
+
+            match tokio::time::timeout(Duration::from_secs(sleep), rx.recv()).await {
+                Ok(Some(Event::Schedule { due })) => {
+                    if next_due.map_or(true, |next_due| due < next_due) {
+                        next_due = due.into();
+                    }
+                    continue;
+                }
+                Ok(Some(Event::Exit)) | Ok(None) => {
+                    tracing::debug!("Snooze scheduler task exiting.");
+                    return;
+                }
+                Err(_) => (),
+            }
+
+            // Wake up due emails, the store is polled as other nodes might have added entries
+            if next_due.map_or(false, |due| due <= now()) {
+                if let Err(err) = core.snooze_wake_due().await {
+                    tracing::error!("Error while waking up snoozed emails: {}", err);
+                }
+            }
+            match core.snooze_next_due().await {
+                Ok(due) => next_due = due,
+                Err(err) => {
+                    tracing::error!("Error while obtaining snoozed emails: {}", err);
+                }
+            }
+        }
+    });
+}
+
+pub fn init_snooze_scheduler() -> (mpsc::Sender<Event>, mpsc::Receiver<Event>) {
+    mpsc::channel::<Event>(IPC_CHANNEL_BUFFER)
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        acl::Acl, collection::Collection, date::UTCDate, id::Id, property::Property,
+        state::StateChange, type_state::TypeState, value::Value,
+    },
+};
+use store::write::{now, BatchBuilder};
+use utils::map::bitmap::Bitmap;
+
+use crate::{auth::AccessToken, JMAP};
+
+use self::set::SCHEMA;
+
+pub mod get;
+pub mod query;
+pub mod set;
+
+/// Rights granted to each account on a shared object before and after an update.
+#[derive(Debug)]
+pub struct ShareChange {
+    object_type: Collection,
+    account_id: u32,
+    document_id: u32,
+    name: Option<String>,
+    grants: Vec<(u32, Bitmap<Acl>, Bitmap<Acl>)>,
+}
+
+impl ShareChange {
+    pub fn new(
+        object_type: Collection,
+        account_id: u32,
+        document_id: u32,
+        name: &Value,
+        old_acl: &Value,
+        new_acl: &Value,
+    ) -> Option<Self> {
+        let old_acl = acl_list(old_acl);
+        let new_acl = acl_list(new_acl);
+        let mut grants = Vec::new();
+
+        for &(grant_id, old_rights) in &old_acl {
+            let new_rights = new_acl
+                .iter()
+                .find(|(id, _)| *id == grant_id)
+                .map(|(_, rights)| *rights)
+                .unwrap_or_default();
+            if old_rights != new_rights {
+                grants.push((grant_id, old_rights, new_rights));
+            }
+        }
+        for &(grant_id, new_rights) in &new_acl {
+            if !old_acl.iter().any(|(id, _)| *id == grant_id) && !new_rights.is_empty() {
+                grants.push((grant_id, Bitmap::default(), new_rights));
+            }
+        }
+
+        if !grants.is_empty() {
+            Some(ShareChange {
+                object_type,
+                account_id,
+                document_id,
+                name: name.as_string().map(|name| name.to_string()),
+                grants,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl JMAP {
+    /// Notifies the accounts whose rights changed. The change has already been
+    /// committed at this point, so failures are logged rather than returned.
+    pub async fn share_notification_send(&self, access_token: &AccessToken, change: ShareChange) {
+        let email = self
+            .directory
+            .emails_by_name(&access_token.name)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .next();
+        let changed_by = Object::with_capacity(3)
+            .with_property(Property::Name, access_token.name.clone())
+            .with_property(
+                Property::Email,
+                email.map(Value::Text).unwrap_or(Value::Null),
+            )
+            .with_property(
+                Property::PrincipalId,
+                Value::Id(access_token.primary_id().into()),
+            );
+        let created = UTCDate::from_timestamp(now() as i64);
+
+        for (grant_id, old_rights, new_rights) in change.grants {
+            // Users do not get notified about changes they made themselves
+            if access_token.is_primary_id(grant_id) {
+                continue;
+            }
+
+            let notification = Object::with_capacity(8)
+                .with_property(Property::Created, created.clone())
+                .with_property(Property::ChangedBy, changed_by.clone())
+                .with_property(
+                    Property::ObjectType,
+                    match change.object_type {
+                        Collection::Mailbox => "Mailbox",
+                        Collection::Calendar => "Calendar",
+                        _ => "",
+                    },
+                )
+                .with_property(Property::ObjectAccountId, Id::from(change.account_id))
+                .with_property(Property::ObjectId, Id::from(change.document_id))
+                .with_property(
+                    Property::OldRights,
+                    rights_object(change.object_type, old_rights),
+                )
+                .with_property(
+                    Property::NewRights,
+                    rights_object(change.object_type, new_rights),
+                )
+                .with_property(
+                    Property::Name,
+                    change.name.clone().map(Value::Text).unwrap_or(Value::Null),
+                );
+
+            if let Err(err) = self.share_notification_create(grant_id, notification).await {
+                tracing::warn!(
+                    event = "error",
+                    context = "share_notification",
+                    account_id = grant_id,
+                    error = ?err,
+                    "Failed to create share notification.");
+            }
+        }
+    }
+
+    async fn share_notification_create(
+        &self,
+        account_id: u32,
+        notification: Object<Value>,
+    ) -> Result<(), MethodError> {
+        let document_id = self
+            .assign_document_id(account_id, Collection::ShareNotification)
+            .await?;
+        let mut changes = self.begin_changes(account_id).await?;
+        changes.log_insert(Collection::ShareNotification, document_id);
+        let change_id = changes.change_id;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::ShareNotification)
+            .create_document(document_id)
+            .custom(ObjectIndexBuilder::new(SCHEMA).with_changes(notification))
+            .custom(changes);
+        self.write_batch(batch).await?;
+        self.broadcast_state_change(
+            StateChange::new(account_id).with_change(TypeState::ShareNotification, change_id),
+        )
+        .await;
+
+        Ok(())
+    }
+}
+
+fn acl_list(value: &Value) -> Vec<(u32, Bitmap<Acl>)> {
+    value
+        .as_list()
+        .map(|list| {
+            list.chunks_exact(2)
+                .filter_map(|item| match (item.first(), item.last()) {
+                    (Some(Value::Id(id)), Some(Value::UnsignedInt(acl_bits))) => {
+                        Some((id.document_id(), Bitmap::from(*acl_bits)))
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn rights_object(object_type: Collection, acl: Bitmap<Acl>) -> Value {
+    if acl.is_empty() {
+        return Value::Null;
+    }
+
+    match object_type {
+        Collection::Mailbox => Object::with_capacity(9)
+            .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+            .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
+            .with_property(Property::MayRemoveItems, acl.contains(Acl::RemoveItems))
+            .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MaySetKeywords, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MayCreateChild, acl.contains(Acl::CreateChild))
+            .with_property(Property::MayRename, acl.contains(Acl::Modify))
+            .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+            .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
+            .into(),
+        Collection::Calendar => Object::with_capacity(8)
+            .with_property(
+                Property::MayReadFreeBusy,
+                acl.contains_any([Acl::Read, Acl::ReadItems].into_iter()),
+            )
+            .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+            .with_property(Property::MayWriteAll, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MayWriteOwn, acl.contains(Acl::AddItems))
+            .with_property(Property::MayUpdatePrivate, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MayRsvp, acl.contains(Acl::ReadItems))
+            .with_property(Property::MayAdmin, acl.contains(Acl::Administer))
+            .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+            .into(),
+        _ => Value::Null,
+    }
+}
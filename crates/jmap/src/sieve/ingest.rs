@@ -73,6 +73,10 @@ impl JMAP {
             .mailbox_get_or_create(account_id)
             .await
             .map_err(|_| IngestError::Temporary)?;
+        let saved_search_ids = self
+            .mailbox_saved_search_ids(account_id)
+            .await
+            .map_err(|_| IngestError::Temporary)?;
 
         // Create Sieve instance
         let mut instance = self.sieve_runtime.filter_parsed(message);
@@ -310,8 +314,8 @@ impl JMAP {
                             }
                         }
 
-                        // Default to Inbox
-                        if target_id == u32::MAX {
+                        // Default to Inbox, saved searches cannot contain messages
+                        if target_id == u32::MAX || saved_search_ids.contains(target_id) {
                             target_id = INBOX_ID;
                         }
 
@@ -451,6 +455,7 @@ impl JMAP {
                         received_at: None,
                         skip_duplicates: true,
                         encrypt: self.config.encrypt,
+                        replace: None,
                     })
                     .await
                 {
@@ -506,6 +511,7 @@ pub fn is_valid_role(role: &str) -> bool {
         "archive",
         "sent",
         "important",
+        "snoozed",
     ]
     .contains(&role)
 }
@@ -129,6 +129,15 @@ impl JMAP {
                                     .iter()
                                     .flat_map(|rcpts| rcpts.recipients.iter())
                                 {
+                                    let displayed = status
+                                        .properties
+                                        .get(&Property::_T(rcpt.address.clone()))
+                                        .and_then(|status| status.as_obj())
+                                        .and_then(|status| {
+                                            status.get(&Property::Displayed).as_string()
+                                        })
+                                        .unwrap_or("unknown")
+                                        .to_string();
                                     status.set(
                                         Property::_T(rcpt.address.clone()),
                                         Object::with_capacity(3)
@@ -155,7 +164,7 @@ impl JMAP {
                                                 }
                                                 .replace('\n', " "),
                                             )
-                                            .with_property(Property::Displayed, "unknown"),
+                                            .with_property(Property::Displayed, displayed),
                                     );
                                 }
 
@@ -176,7 +185,10 @@ impl JMAP {
                     | Property::ThreadId
                     | Property::Envelope
                     | Property::SendAt => push.remove(property),
-                    Property::MdnBlobIds | Property::DsnBlobIds => Value::List(vec![]),
+                    Property::MdnBlobIds | Property::DsnBlobIds => match push.remove(property) {
+                        Value::Null => Value::List(vec![]),
+                        value => value,
+                    },
                     _ => Value::Null,
                 };
 
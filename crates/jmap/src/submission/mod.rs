@@ -24,3 +24,4 @@
 pub mod get;
 pub mod query;
 pub mod set;
+pub mod status;
@@ -70,6 +70,7 @@ pub static SCHEMA: &[IndexProperty] = &[
     IndexProperty::new(Property::IdentityId).index_as(IndexAs::Integer),
     IndexProperty::new(Property::ThreadId).index_as(IndexAs::Integer),
     IndexProperty::new(Property::SendAt).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::MessageId).index_as(IndexAs::LongInteger),
 ];
 
 impl JMAP {
@@ -476,7 +477,7 @@ impl JMAP {
         };
 
         // Make sure the envelope address matches the identity email address
-        let mail_from = if let Some(mail_from) = mail_from {
+        let mut mail_from = if let Some(mail_from) = mail_from {
             if !mail_from.address.eq_ignore_ascii_case(&identity_mail_from) {
                 return Ok(Err(SetError::new(SetErrorType::ForbiddenFrom)
                     .with_description(
@@ -601,6 +602,7 @@ impl JMAP {
             Session::<NullIo>::local(self.smtp.clone(), instance.clone(), SessionData::default());
 
         // MAIL FROM
+        mail_from.flags |= queue::MAIL_TRACK_DELIVERY;
         let _ = session.handle_mail_from(mail_from).await;
         if let Some(error) = session.has_failed() {
             return Ok(Err(SetError::new(SetErrorType::ForbiddenMailFrom)
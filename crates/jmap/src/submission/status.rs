@@ -0,0 +1,526 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    error::method::MethodError,
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        blob::BlobId, collection::Collection, id::Id, property::Property, state::StateChange,
+        type_state::TypeState, value::Value,
+    },
+};
+use mail_parser::{Message, MimeHeaders, PartType};
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+use utils::ipc::DeliveryReport;
+
+use crate::JMAP;
+
+use super::set::SCHEMA;
+
+#[derive(Debug, Default)]
+pub struct ReceivedReport {
+    pub is_mdn: bool,
+    pub envelope_id: Option<String>,
+    pub message_id: Option<String>,
+    pub recipients: Vec<ReportRecipient>,
+}
+
+#[derive(Debug)]
+pub struct ReportRecipient {
+    pub address: String,
+    pub status: &'static str,
+    pub smtp_reply: Option<String>,
+}
+
+impl JMAP {
+    pub async fn update_delivery_status(&self, report: DeliveryReport) {
+        for name in self
+            .directory
+            .names_by_email(&report.sender_address)
+            .await
+            .unwrap_or_default()
+        {
+            let account_id = match self.get_account_id(&name).await {
+                Ok(account_id) => account_id,
+                Err(_) => continue,
+            };
+            let document_ids = match self
+                .filter(
+                    account_id,
+                    Collection::EmailSubmission,
+                    vec![Filter::eq(Property::MessageId, report.queue_id)],
+                )
+                .await
+            {
+                Ok(result) => result.results,
+                Err(_) => continue,
+            };
+
+            let mut changes = ChangeLogBuilder::new();
+            for document_id in document_ids {
+                match self
+                    .update_submission(account_id, document_id, |submission| {
+                        let status = delivery_status_mut(submission);
+                        for rcpt in &report.recipients {
+                            let status = recipient_status_mut(status, &rcpt.address);
+                            status.set(
+                                Property::Delivered,
+                                if rcpt.delivered { "yes" } else { "no" },
+                            );
+                            status.set(Property::SmtpReply, rcpt.smtp_reply.replace('\n', " "));
+                        }
+                    })
+                    .await
+                {
+                    Ok(true) => {
+                        changes.log_update(Collection::EmailSubmission, document_id);
+                    }
+                    Ok(false) => (),
+                    Err(_) => {
+                        tracing::debug!(
+                            context = "delivery_status",
+                            event = "error",
+                            account_id = account_id,
+                            document_id = document_id,
+                            "Failed to update email submission delivery status."
+                        );
+                    }
+                }
+            }
+
+            if !changes.is_empty() {
+                if let Ok(change_id) = self.commit_changes(account_id, changes).await {
+                    self.broadcast_state_change(
+                        StateChange::new(account_id)
+                            .with_change(TypeState::EmailSubmission, change_id),
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    pub async fn link_received_report(
+        &self,
+        account_id: u32,
+        report: &ReceivedReport,
+        blob_id: BlobId,
+    ) -> Result<Option<u64>, MethodError> {
+        let message_id = if let Some(message_id) = &report.message_id {
+            message_id
+        } else {
+            return Ok(None);
+        };
+
+        // Obtain the submissions of the original message
+        let email_ids = self
+            .filter(
+                account_id,
+                Collection::Email,
+                vec![Filter::eq(Property::MessageId, message_id.as_str())],
+            )
+            .await?
+            .results;
+        if email_ids.is_empty() {
+            return Ok(None);
+        }
+        let thread_ids = self
+            .get_properties::<u32>(
+                account_id,
+                Collection::Email,
+                email_ids.iter(),
+                Property::ThreadId,
+            )
+            .await?;
+        let mut filters = vec![Filter::Or];
+        for (document_id, thread_id) in email_ids.iter().zip(thread_ids) {
+            if let Some(thread_id) = thread_id {
+                filters.push(Filter::eq(
+                    Property::EmailId,
+                    Id::from_parts(thread_id, document_id).id(),
+                ));
+            }
+        }
+        if filters.len() == 1 {
+            return Ok(None);
+        }
+        filters.push(Filter::End);
+        let submission_ids = self
+            .filter(account_id, Collection::EmailSubmission, filters)
+            .await?
+            .results;
+
+        // Link report to submissions
+        let mut changes = ChangeLogBuilder::new();
+        for document_id in submission_ids {
+            if self
+                .update_submission(account_id, document_id, |submission| {
+                    if let Some(envelope_id) = &report.envelope_id {
+                        if submission_envelope_id(submission).map_or(false, |id| id != envelope_id)
+                        {
+                            return;
+                        }
+                    }
+
+                    let status = delivery_status_mut(submission);
+                    for rcpt in &report.recipients {
+                        let status = recipient_status_mut(status, &rcpt.address);
+                        if report.is_mdn {
+                            status.set(Property::Displayed, rcpt.status);
+                        } else {
+                            status.set(Property::Delivered, rcpt.status);
+                            if let Some(smtp_reply) = &rcpt.smtp_reply {
+                                status.set(Property::SmtpReply, smtp_reply.clone());
+                            }
+                        }
+                    }
+
+                    if let Value::List(blob_ids) = submission.properties.get_mut_or_insert_with(
+                        if report.is_mdn {
+                            Property::MdnBlobIds
+                        } else {
+                            Property::DsnBlobIds
+                        },
+                        || Value::List(vec![]),
+                    ) {
+                        blob_ids.push(Value::BlobId(blob_id.clone()));
+                    }
+                })
+                .await?
+            {
+                changes.log_update(Collection::EmailSubmission, document_id);
+            }
+        }
+
+        if !changes.is_empty() {
+            self.commit_changes(account_id, changes).await.map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn update_submission(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        update: impl Fn(&mut Object<Value>),
+    ) -> Result<bool, MethodError> {
+        let mut try_count = 0;
+
+        loop {
+            let current = if let Some(current) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::EmailSubmission,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                current
+            } else {
+                return Ok(false);
+            };
+
+            let mut submission = current.inner.clone();
+            update(&mut submission);
+            if submission == current.inner {
+                return Ok(false);
+            }
+
+            let mut changes = Object::with_capacity(3);
+            for property in [
+                Property::DeliveryStatus,
+                Property::DsnBlobIds,
+                Property::MdnBlobIds,
+            ] {
+                if let Some(value) = submission.properties.remove(&property) {
+                    changes.append(property, value);
+                }
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::EmailSubmission)
+                .update_document(document_id)
+                .custom(
+                    ObjectIndexBuilder::new(SCHEMA)
+                        .with_current(current)
+                        .with_changes(changes),
+                );
+
+            // Retry if the submission was modified concurrently
+            match self.store.write(batch.build()).await {
+                Ok(_) => return Ok(true),
+                Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                    try_count += 1;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        event = "error",
+                        context = "update_submission",
+                        account_id = account_id,
+                        document_id = document_id,
+                        error = ?err,
+                        "Failed to update email submission.");
+                    return Err(MethodError::ServerPartialFail);
+                }
+            }
+        }
+    }
+}
+
+impl ReceivedReport {
+    pub fn parse(message: &Message<'_>) -> Option<Self> {
+        if !message
+            .parts
+            .first()?
+            .is_content_type("multipart", "report")
+        {
+            return None;
+        }
+
+        let mut report = ReceivedReport::default();
+        let mut has_report = false;
+        for part in &message.parts {
+            let contents = match &part.body {
+                PartType::Text(text) => text.as_bytes(),
+                PartType::Binary(bytes) | PartType::InlineBinary(bytes) => bytes.as_ref(),
+                PartType::Message(nested) => {
+                    if report.message_id.is_none() {
+                        report.message_id = nested.message_id().map(|id| id.to_string());
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if part.is_content_type("message", "delivery-status")
+                || part.is_content_type("message", "global-delivery-status")
+            {
+                has_report = true;
+                report.parse_dsn(&String::from_utf8_lossy(contents));
+            } else if part.is_content_type("message", "disposition-notification")
+                || part.is_content_type("message", "global-disposition-notification")
+            {
+                has_report = true;
+                report.is_mdn = true;
+                report.parse_mdn(&String::from_utf8_lossy(contents));
+            } else if (part.is_content_type("text", "rfc822-headers")
+                || part.is_content_type("message", "global-headers"))
+                && report.message_id.is_none()
+            {
+                report.message_id = Message::parse(contents)
+                    .and_then(|headers| headers.message_id().map(|id| id.to_string()));
+            }
+        }
+
+        if has_report && !report.recipients.is_empty() {
+            Some(report)
+        } else {
+            None
+        }
+    }
+
+    fn parse_dsn(&mut self, text: &str) {
+        for fields in parse_fields(text) {
+            let mut address = None;
+            let mut status = None;
+            let mut smtp_reply = None;
+
+            for (name, value) in fields {
+                match name.as_str() {
+                    "original-envelope-id" => {
+                        self.envelope_id = value.into();
+                    }
+                    "original-recipient" => {
+                        address = parse_address(&value).into();
+                    }
+                    "final-recipient" if address.is_none() => {
+                        address = parse_address(&value).into();
+                    }
+                    "action" => {
+                        status = match value.to_ascii_lowercase().as_str() {
+                            "delivered" | "relayed" | "expanded" => "yes",
+                            "failed" => "no",
+                            "delayed" => "queued",
+                            _ => "unknown",
+                        }
+                        .into();
+                    }
+                    "diagnostic-code" => {
+                        smtp_reply = value
+                            .split_once(';')
+                            .map_or(value.as_str(), |(_, reply)| reply)
+                            .trim()
+                            .to_string()
+                            .into();
+                    }
+                    _ => (),
+                }
+            }
+
+            if let (Some(address), Some(status)) = (address, status) {
+                self.recipients.push(ReportRecipient {
+                    address,
+                    status,
+                    smtp_reply,
+                });
+            }
+        }
+    }
+
+    fn parse_mdn(&mut self, text: &str) {
+        let mut address = None;
+        let mut status = None;
+
+        for (name, value) in parse_fields(text).into_iter().flatten() {
+            match name.as_str() {
+                "original-message-id" => {
+                    self.message_id = value
+                        .trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_string()
+                        .into();
+                }
+                "original-recipient" => {
+                    address = parse_address(&value).into();
+                }
+                "final-recipient" if address.is_none() => {
+                    address = parse_address(&value).into();
+                }
+                "disposition" => {
+                    status = if value.split_once(';').map_or(false, |(_, disposition)| {
+                        disposition
+                            .trim()
+                            .to_ascii_lowercase()
+                            .starts_with("displayed")
+                    }) {
+                        "yes"
+                    } else {
+                        "unknown"
+                    }
+                    .into();
+                }
+                _ => (),
+            }
+        }
+
+        if let (Some(address), Some(status)) = (address, status) {
+            self.recipients.push(ReportRecipient {
+                address,
+                status,
+                smtp_reply: None,
+            });
+        }
+    }
+}
+
+pub fn parse_fields(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut blocks = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                blocks.push(std::mem::take(&mut fields));
+            }
+        } else if line.starts_with(|ch| ch == ' ' || ch == '\t') {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    if !fields.is_empty() {
+        blocks.push(fields);
+    }
+
+    blocks
+}
+
+fn parse_address(value: &str) -> String {
+    value
+        .split_once(';')
+        .map_or(value, |(_, address)| address)
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_lowercase()
+}
+
+fn submission_envelope_id(submission: &Object<Value>) -> Option<&str> {
+    submission
+        .get(&Property::Envelope)
+        .as_obj()?
+        .get(&Property::MailFrom)
+        .as_obj()?
+        .get(&Property::Parameters)
+        .as_obj()?
+        .properties
+        .iter()
+        .find_map(|(name, value)| match name {
+            Property::_T(name) if name.eq_ignore_ascii_case("envid") => value.as_string(),
+            _ => None,
+        })
+}
+
+fn delivery_status_mut(submission: &mut Object<Value>) -> &mut Object<Value> {
+    let status = submission
+        .properties
+        .get_mut_or_insert_with(Property::DeliveryStatus, || {
+            Value::Object(Object::with_capacity(1))
+        });
+    if !matches!(status, Value::Object(_)) {
+        *status = Value::Object(Object::with_capacity(1));
+    }
+    status.as_obj_mut().unwrap()
+}
+
+fn recipient_status_mut<'x>(status: &'x mut Object<Value>, address: &str) -> &'x mut Object<Value> {
+    let key = status
+        .properties
+        .keys()
+        .find(|key| matches!(key, Property::_T(addr) if addr.eq_ignore_ascii_case(address)))
+        .cloned()
+        .unwrap_or_else(|| Property::_T(address.to_string()));
+    let rcpt_status = status.properties.get_mut_or_insert_with(key, || {
+        Value::Object(
+            Object::with_capacity(3)
+                .with_property(Property::Delivered, "unknown")
+                .with_property(Property::SmtpReply, "")
+                .with_property(Property::Displayed, "unknown"),
+        )
+    });
+    if !matches!(rcpt_status, Value::Object(_)) {
+        *rcpt_status = Value::Object(Object::with_capacity(3));
+    }
+    rcpt_status.as_obj_mut().unwrap()
+}
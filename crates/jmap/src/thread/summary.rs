@@ -0,0 +1,311 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::{
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, BatchBuilder, F_CLEAR, F_VALUE},
+    BitmapKey, ValueKey,
+};
+
+use crate::{Bincode, JMAP};
+
+/// Aggregated thread properties, kept up to date as emails are added to or
+/// removed from a thread so that queries can sort by conversation without
+/// loading every message in the thread.
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct ThreadSummary {
+    /// Most recent receivedAt of the emails in the thread.
+    pub received_at: u64,
+    /// Number of emails in the thread.
+    pub size: u32,
+}
+
+/// Change made to the emails of a thread by the batch its summary is written with.
+#[derive(Debug)]
+pub enum ThreadSummaryChange {
+    /// An email received at `received_at` is added to the thread.
+    Add { received_at: u64 },
+    /// The email `document_id` received at `received_at` is removed from the thread.
+    Remove { document_id: u32, received_at: u64 },
+    /// The email `document_id` is replaced by an email received at `received_at`.
+    Replace { document_id: u32, received_at: u64 },
+    /// The thread is left with exactly `document_ids`.
+    Rebuild { document_ids: RoaringBitmap },
+}
+
+impl JMAP {
+    /// Writes `batch` together with the updated summary of `thread_id`, retrying
+    /// if the summary was modified by a concurrent write.
+    pub async fn write_batch_with_thread_summary(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+        batch: BatchBuilder,
+        change: ThreadSummaryChange,
+    ) -> store::Result<()> {
+        self.write_batch_with_thread_summaries(account_id, batch, vec![(thread_id, change)])
+            .await
+    }
+
+    /// Writes `batch` together with the updated summaries of several threads.
+    pub async fn write_batch_with_thread_summaries(
+        &self,
+        account_id: u32,
+        batch: BatchBuilder,
+        changes: Vec<(u32, ThreadSummaryChange)>,
+    ) -> store::Result<()> {
+        let mut try_count = 0;
+
+        loop {
+            let mut attempt = batch.clone();
+            for (thread_id, change) in &changes {
+                self.thread_summary_prepare(account_id, *thread_id, change, &mut attempt)
+                    .await?;
+            }
+
+            match self.store.write(attempt.build()).await {
+                Err(store::Error::AssertValueFailed) if try_count < 3 => {
+                    try_count += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Adds the new summary of `thread_id` to `batch`. The current summary is
+    /// asserted so the batch fails if the thread changed in the meantime.
+    pub async fn thread_summary_prepare(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+        change: &ThreadSummaryChange,
+        batch: &mut BatchBuilder,
+    ) -> store::Result<()> {
+        let current = self
+            .store
+            .get_value::<HashedValue<Bincode<ThreadSummary>>>(ValueKey::new(
+                account_id,
+                Collection::Thread,
+                thread_id,
+                Property::Value,
+            ))
+            .await?;
+
+        let summary = match (change, current.as_ref().map(|current| current.inner.inner)) {
+            (ThreadSummaryChange::Add { received_at }, summary) => {
+                // Summaries missing from the store are rebuilt from the emails in the thread
+                let summary = if let Some(summary) = summary {
+                    summary
+                } else {
+                    self.thread_summary_build(
+                        account_id,
+                        &self.thread_document_ids(account_id, thread_id).await?,
+                    )
+                    .await?
+                };
+                ThreadSummary {
+                    received_at: std::cmp::max(summary.received_at, *received_at),
+                    size: summary.size + 1,
+                }
+            }
+            (ThreadSummaryChange::Remove { received_at, .. }, Some(summary))
+                if summary.received_at > *received_at && summary.size > 1 =>
+            {
+                ThreadSummary {
+                    received_at: summary.received_at,
+                    size: summary.size - 1,
+                }
+            }
+            (ThreadSummaryChange::Remove { document_id, .. }, _) => {
+                // The most recent email was removed, rebuild from the remaining ones
+                let mut document_ids = self.thread_document_ids(account_id, thread_id).await?;
+                document_ids.remove(*document_id);
+                self.thread_summary_build(account_id, &document_ids).await?
+            }
+            (
+                ThreadSummaryChange::Replace {
+                    document_id,
+                    received_at,
+                },
+                _,
+            ) => {
+                let mut document_ids = self.thread_document_ids(account_id, thread_id).await?;
+                document_ids.remove(*document_id);
+                let summary = self.thread_summary_build(account_id, &document_ids).await?;
+                ThreadSummary {
+                    received_at: std::cmp::max(summary.received_at, *received_at),
+                    size: summary.size + 1,
+                }
+            }
+            (ThreadSummaryChange::Rebuild { document_ids }, _) => {
+                self.thread_summary_build(account_id, document_ids).await?
+            }
+        };
+
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Thread)
+            .update_document(thread_id);
+        if let Some(current) = &current {
+            batch.assert_value(Property::Value, current);
+        } else {
+            batch.assert_value(Property::Value, ());
+        }
+        if summary.size > 0 {
+            batch.value(Property::Value, Bincode::new(summary), F_VALUE);
+        } else {
+            batch.value(Property::Value, (), F_VALUE | F_CLEAR);
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds and stores a summary that is missing from the store, such as
+    /// those of threads created by earlier versions.
+    pub async fn thread_summary_refresh(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+    ) -> store::Result<ThreadSummary> {
+        let summary = self
+            .thread_summary_build(
+                account_id,
+                &self.thread_document_ids(account_id, thread_id).await?,
+            )
+            .await?;
+
+        if summary.size > 0 {
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Thread)
+                .update_document(thread_id)
+                .assert_value(Property::Value, ())
+                .value(Property::Value, Bincode::new(summary), F_VALUE);
+            match self.store.write(batch.build()).await {
+                // A concurrent write already stored the summary
+                Ok(_) | Err(store::Error::AssertValueFailed) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Returns the summaries of the requested threads, rebuilding the ones
+    /// missing from the store.
+    pub async fn thread_summaries(
+        &self,
+        account_id: u32,
+        thread_ids: &[u32],
+    ) -> store::Result<Vec<ThreadSummary>> {
+        let mut summaries = Vec::with_capacity(thread_ids.len());
+
+        for (thread_id, summary) in thread_ids.iter().zip(
+            self.store
+                .get_values::<Bincode<ThreadSummary>>(
+                    thread_ids
+                        .iter()
+                        .map(|thread_id| {
+                            ValueKey::new(
+                                account_id,
+                                Collection::Thread,
+                                *thread_id,
+                                Property::Value,
+                            )
+                        })
+                        .collect(),
+                )
+                .await?,
+        ) {
+            summaries.push(if let Some(summary) = summary {
+                summary.inner
+            } else {
+                self.thread_summary_refresh(account_id, *thread_id).await?
+            });
+        }
+
+        Ok(summaries)
+    }
+
+    async fn thread_document_ids(
+        &self,
+        account_id: u32,
+        thread_id: u32,
+    ) -> store::Result<RoaringBitmap> {
+        self.store
+            .get_bitmap(BitmapKey::value(
+                account_id,
+                Collection::Email,
+                Property::ThreadId,
+                thread_id,
+            ))
+            .await
+            .map(|document_ids| document_ids.unwrap_or_default())
+    }
+
+    async fn thread_summary_build(
+        &self,
+        account_id: u32,
+        document_ids: &RoaringBitmap,
+    ) -> store::Result<ThreadSummary> {
+        let mut summary = ThreadSummary {
+            received_at: 0,
+            size: document_ids.len() as u32,
+        };
+        for metadata in self
+            .store
+            .get_values::<Object<Value>>(
+                document_ids
+                    .iter()
+                    .map(|document_id| {
+                        ValueKey::new(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::BodyStructure,
+                        )
+                    })
+                    .collect(),
+            )
+            .await?
+            .into_iter()
+            .flatten()
+        {
+            if let Some(received_at) = metadata
+                .properties
+                .get(&Property::ReceivedAt)
+                .and_then(|value| value.as_date())
+            {
+                summary.received_at =
+                    std::cmp::max(summary.received_at, received_at.timestamp() as u64);
+            }
+        }
+
+        Ok(summary)
+    }
+}
@@ -24,7 +24,10 @@
 use std::time::Duration;
 
 use directory::config::ConfigDirectory;
-use imap::core::{ImapSessionManager, IMAP};
+use imap::{
+    core::{ImapSessionManager, IMAP},
+    op::urlauth::spawn_url_resolver,
+};
 use jmap::{api::JmapSessionManager, services::IPC_CHANNEL_BUFFER, JMAP};
 use managesieve::core::ManageSieveSessionManager;
 use smtp::core::{SmtpSessionManager, SMTP};
@@ -62,7 +65,8 @@ async fn main() -> std::io::Result<()> {
 
     // Init servers
     let (delivery_tx, delivery_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx)
+    let (url_tx, url_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
+    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx, url_tx)
         .await
         .failed("Invalid configuration file");
     let jmap = JMAP::init(&config, &directory, delivery_rx, smtp.clone())
@@ -71,6 +75,7 @@ async fn main() -> std::io::Result<()> {
     let imap = IMAP::init(&config)
         .await
         .failed("Invalid configuration file");
+    spawn_url_resolver(jmap.clone(), url_rx);
 
     // Spawn servers
     let shutdown_tx = servers.spawn(|server, shutdown_rx| {
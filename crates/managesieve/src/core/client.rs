@@ -21,7 +21,10 @@
  * for more details.
 */
 
-use imap::core::IMAP;
+use imap::core::{
+    compress::{Deflater, Inflater},
+    IMAP,
+};
 use imap_proto::receiver::{self, Request};
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::query::Filter;
@@ -76,6 +79,18 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
                 Command::DeleteScript => self.handle_deletescript(request).await,
                 Command::RenameScript => self.handle_renamescript(request).await,
                 Command::CheckScript => self.handle_checkscript(request).await,
+                Command::Compress => match self.handle_compress(request).await {
+                    Ok(response) => {
+                        // The response is sent uncompressed, all data
+                        // exchanged afterwards is compressed in both directions.
+                        self.write(&response).await?;
+                        self.deflater = Some(Deflater::new());
+                        self.inflater = Some(Inflater::new());
+                        tracing::debug!(parent: &self.span, event = "compress", "DEFLATE compression enabled.");
+                        continue;
+                    }
+                    Err(err) => Err(err),
+                },
                 Command::HaveSpace => self.handle_havespace(request).await,
                 Command::Capability => self.handle_capability("").await,
                 Command::Authenticate => self.handle_authenticate(request).await,
@@ -112,7 +127,19 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
 impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
     #[inline(always)]
     pub async fn write(&mut self, bytes: &[u8]) -> Result<(), ()> {
-        let err = match self.stream.write_all(bytes).await {
+        let compressed;
+        let output = if let Some(deflater) = &mut self.deflater {
+            if let Some(bytes) = deflater.deflate(bytes) {
+                compressed = bytes;
+                &compressed[..]
+            } else {
+                tracing::debug!(parent: &self.span, event = "error", "Failed to compress bytes.");
+                return Err(());
+            }
+        } else {
+            bytes
+        };
+        let err = match self.stream.write_all(output).await {
             Ok(_) => match self.stream.flush().await {
                 Ok(_) => {
                     tracing::trace!(parent: &self.span,
@@ -222,6 +249,7 @@ impl ValidateRequest for Request<Command> {
             | Command::DeleteScript
             | Command::RenameScript
             | Command::CheckScript
+            | Command::Compress
             | Command::Unauthenticate => {
                 if let State::Authenticated { access_token, .. } = state {
                     if imap
@@ -26,7 +26,10 @@ pub mod session;
 
 use std::{borrow::Cow, sync::Arc};
 
-use imap::core::IMAP;
+use imap::core::{
+    compress::{Deflater, Inflater},
+    IMAP,
+};
 use imap_proto::receiver::{CommandParser, Receiver};
 use jmap::{
     auth::{rate_limit::RemoteAddress, AccessToken},
@@ -47,6 +50,8 @@ pub struct Session<T: AsyncRead + AsyncWrite> {
     pub state: State,
     pub remote_addr: RemoteAddress,
     pub stream: T,
+    pub deflater: Option<Deflater>,
+    pub inflater: Option<Inflater>,
     pub span: tracing::Span,
     pub in_flight: InFlight,
 }
@@ -96,6 +101,7 @@ pub enum Command {
     DeleteScript,
     RenameScript,
     CheckScript,
+    Compress,
     #[default]
     Noop,
     Unauthenticate,
@@ -132,6 +138,7 @@ impl CommandParser for Command {
             b"DELETESCRIPT" => Some(Command::DeleteScript),
             b"RENAMESCRIPT" => Some(Command::RenameScript),
             b"CHECKSCRIPT" => Some(Command::CheckScript),
+            b"COMPRESS" => Some(Command::Compress),
             b"NOOP" => Some(Command::Noop),
             b"UNAUTHENTICATE" => Some(Command::Unauthenticate),
             _ => None,
@@ -21,6 +21,8 @@
  * for more details.
 */
 
+use std::borrow::Cow;
+
 use imap_proto::receiver::{self, Receiver};
 use jmap::auth::rate_limit::RemoteAddress;
 use tokio::{
@@ -44,6 +46,8 @@ impl SessionManager for ManageSieveSessionManager {
             state: State::NotAuthenticated { auth_failures: 0 },
             span: session.span,
             stream: session.stream,
+            deflater: None,
+            inflater: None,
             in_flight: session.in_flight,
             remote_addr: RemoteAddress::IpAddress(session.remote_ip),
             receiver: Receiver::with_max_request_size(self.imap.max_request_size)
@@ -93,7 +97,17 @@ impl<T: AsyncRead + AsyncWrite + IsTls + Unpin> Session<T> {
                         match result {
                             Ok(Ok(bytes_read)) => {
                                 if bytes_read > 0 {
-                                    match self.ingest(&buf[..bytes_read]).await {
+                                    let result = if let Some(bytes) = self.inflate(&buf[..bytes_read]) {
+                                        self.ingest(&bytes).await
+                                    } else {
+                                        tracing::debug!(
+                                            parent: &self.span,
+                                            event = "error",
+                                            "Failed to decompress data."
+                                        );
+                                        Err(())
+                                    };
+                                    match result {
                                         Ok(true) => (),
                                         Ok(false) => {
                                             return true;
@@ -145,6 +159,16 @@ impl<T: AsyncRead + AsyncWrite + IsTls + Unpin> Session<T> {
 
         false
     }
+
+    pub fn inflate<'x>(&mut self, bytes: &'x [u8]) -> Option<Cow<'x, [u8]>> {
+        if let Some(inflater) = &mut self.inflater {
+            inflater
+                .inflate(bytes, self.imap.max_request_size)
+                .map(Cow::Owned)
+        } else {
+            Some(Cow::Borrowed(bytes))
+        }
+    }
 }
 
 impl Session<TcpStream> {
@@ -152,6 +176,8 @@ impl Session<TcpStream> {
         let span = self.span;
         Ok(Session {
             stream: self.instance.tls_accept(self.stream, &span).await?,
+            deflater: self.deflater,
+            inflater: self.inflater,
             state: self.state,
             instance: self.instance,
             in_flight: self.in_flight,
@@ -38,6 +38,9 @@ impl<T: AsyncRead + AsyncWrite + IsTls> Session<T> {
             response.extend_from_slice(b"\"STARTTLS\"\r\n");
         } else {
             response.extend_from_slice(b"\"SASL\" \"PLAIN OAUTHBEARER\"\r\n");
+            if self.deflater.is_none() {
+                response.extend_from_slice(b"\"COMPRESS\" \"DEFLATE\"\r\n");
+            }
         };
         if let Some(sieve) =
             self.jmap
@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2020-2022, Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use imap_proto::receiver::Request;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::core::{Command, Session, StatusResponse};
+
+impl<T: AsyncRead + AsyncWrite> Session<T> {
+    pub async fn handle_compress(&mut self, request: Request<Command>) -> super::OpResult {
+        if self.deflater.is_some() {
+            return Err(StatusResponse::no("Compression is already active."));
+        }
+
+        let algorithm = request
+            .tokens
+            .into_iter()
+            .next()
+            .and_then(|s| s.unwrap_string().ok())
+            .ok_or_else(|| StatusResponse::no("Expected compression algorithm."))?;
+        if algorithm.eq_ignore_ascii_case("DEFLATE") {
+            Ok(StatusResponse::ok("DEFLATE active").into_bytes())
+        } else {
+            Err(StatusResponse::no("Unsupported compression algorithm."))
+        }
+    }
+}
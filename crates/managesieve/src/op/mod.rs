@@ -28,6 +28,7 @@ use crate::core::StatusResponse;
 pub mod authenticate;
 pub mod capability;
 pub mod checkscript;
+pub mod compress;
 pub mod deletescript;
 pub mod getscript;
 pub mod havespace;
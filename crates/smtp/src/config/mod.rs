@@ -211,6 +211,7 @@ pub struct Ehlo {
 pub struct Extensions {
     pub pipelining: IfBlock<bool>,
     pub chunking: IfBlock<bool>,
+    pub burl: IfBlock<bool>,
     pub requiretls: IfBlock<bool>,
     pub dsn: IfBlock<bool>,
     pub vrfy: IfBlock<bool>,
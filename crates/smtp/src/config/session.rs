@@ -197,6 +197,9 @@ impl ConfigSession for Config {
             chunking: self
                 .parse_if_block("session.extensions.chunking", ctx, &available_keys)?
                 .unwrap_or_else(|| IfBlock::new(true)),
+            burl: self
+                .parse_if_block("session.extensions.burl", ctx, &available_keys)?
+                .unwrap_or_default(),
             requiretls: self
                 .parse_if_block("session.extensions.requiretls", ctx, &available_keys)?
                 .unwrap_or_default(),
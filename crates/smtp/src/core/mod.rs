@@ -43,7 +43,7 @@ use tokio::{
 use tokio_rustls::TlsConnector;
 use tracing::Span;
 use utils::{
-    ipc::DeliveryEvent,
+    ipc::{DeliveryEvent, UrlFetchRequest},
     listener::{limiter::InFlight, ServerInstance},
 };
 
@@ -101,6 +101,8 @@ pub struct SMTP {
     pub sieve: SieveCore,
     #[cfg(feature = "local_delivery")]
     pub delivery_tx: mpsc::Sender<DeliveryEvent>,
+    #[cfg(feature = "local_delivery")]
+    pub url_tx: mpsc::Sender<UrlFetchRequest>,
 }
 
 pub struct SieveCore {
@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::oneshot,
+};
+use utils::{config::ServerProtocol, ipc::UrlFetchRequest};
+
+use crate::core::Session;
+
+use super::IsTls;
+
+impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
+    pub async fn handle_burl(&mut self, uri: String, is_last: bool) -> Result<bool, ()> {
+        if self.data.authenticated_as.is_empty()
+            || !*self.core.session.config.extensions.burl.eval(self).await
+        {
+            self.write(b"503 5.5.1 BURL not allowed.\r\n").await?;
+            return Ok(true);
+        } else if !self.can_send_data().await? {
+            self.data.message = Vec::with_capacity(0);
+            return Ok(true);
+        }
+
+        // Resolve URL against the local message store
+        let (result_tx, result_rx) = oneshot::channel();
+        let result = if self
+            .core
+            .url_tx
+            .send(UrlFetchRequest {
+                url: uri.clone(),
+                user: self.data.authenticated_as.clone(),
+                result_tx,
+            })
+            .await
+            .is_ok()
+        {
+            result_rx.await.ok()
+        } else {
+            None
+        };
+
+        match result {
+            Some(Some(bytes)) => {
+                if self.data.message.len() + bytes.len() < self.params.max_message_size {
+                    tracing::debug!(
+                        parent: &self.span,
+                        context = "burl",
+                        event = "success",
+                        url = &uri,
+                        size = bytes.len(),
+                    );
+
+                    if self.data.message.is_empty() {
+                        self.data.message = bytes;
+                    } else {
+                        self.data.message.extend_from_slice(&bytes);
+                    }
+                } else {
+                    tracing::debug!(
+                        parent: &self.span,
+                        context = "burl",
+                        event = "too-large",
+                        "Message is too large."
+                    );
+
+                    self.data.message = Vec::with_capacity(0);
+                    self.write(b"552 5.3.4 Message too big for system.\r\n")
+                        .await?;
+                    return Ok(true);
+                }
+            }
+            Some(None) => {
+                tracing::debug!(
+                    parent: &self.span,
+                    context = "burl",
+                    event = "invalid-url",
+                    url = &uri,
+                );
+
+                self.data.message = Vec::with_capacity(0);
+                self.write(b"554 5.6.6 IMAP URL resolution failed.\r\n")
+                    .await?;
+                return Ok(true);
+            }
+            None => {
+                tracing::debug!(
+                    parent: &self.span,
+                    context = "burl",
+                    event = "temp-fail",
+                    url = &uri,
+                );
+
+                self.data.message = Vec::with_capacity(0);
+                self.write(b"454 4.4.1 IMAP server unavailable.\r\n")
+                    .await?;
+                return Ok(true);
+            }
+        }
+
+        if is_last {
+            let num_rcpts = self.data.rcpt_to.len();
+            let message = self.queue_message().await;
+            if !message.is_empty() {
+                if self.instance.protocol == ServerProtocol::Smtp {
+                    self.write(message.as_ref()).await?;
+                } else {
+                    for _ in 0..num_rcpts {
+                        self.write(message.as_ref()).await?;
+                    }
+                }
+                self.reset();
+            } else {
+                // Disconnect requested
+                return Ok(false);
+            }
+        } else {
+            self.write(b"250 2.5.0 Waiting for additional BURL or BDAT commands.\r\n")
+                .await?;
+        }
+
+        Ok(true)
+    }
+}
@@ -45,7 +45,7 @@ use tokio::{
 use crate::{
     config::DNSBL_FROM,
     core::{scripts::ScriptResult, Session, SessionAddress, State},
-    queue::{self, DomainPart, Message, SimpleEnvelope},
+    queue::{self, DomainPart, Message, SimpleEnvelope, MAIL_AUTHENTICATED_SENDER},
     reporting::analysis::AnalyzeReport,
 };
 
@@ -204,6 +204,7 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
         }
 
         // Verify DMARC
+        let mut is_sender_authenticated = !self.data.authenticated_as.is_empty();
         match &self.data.spf_mail_from {
             Some(spf_output) if dmarc.verify() => {
                 let dmarc_output = self
@@ -232,6 +233,8 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
 
                 // Add to DMARC output to the Authentication-Results header
                 auth_results = auth_results.with_dmarc_result(&dmarc_output);
+                is_sender_authenticated |= matches!(dmarc_output.spf_result(), DmarcResult::Pass)
+                    || matches!(dmarc_output.dkim_result(), DmarcResult::Pass);
 
                 if !rejected {
                     tracing::debug!(parent: &self.span,
@@ -436,6 +439,9 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
         let mail_from = self.data.mail_from.clone().unwrap();
         let rcpt_to = std::mem::take(&mut self.data.rcpt_to);
         let mut message = self.build_message(mail_from, rcpt_to).await;
+        if is_sender_authenticated {
+            message.flags |= MAIL_AUTHENTICATED_SENDER;
+        }
 
         // Add Received header
         let mut headers = Vec::with_capacity(64);
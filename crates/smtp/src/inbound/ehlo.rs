@@ -142,6 +142,12 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
             response.capabilities |= EXT_CHUNKING;
         }
 
+        // Message submission from stored URLs
+        #[cfg(feature = "local_delivery")]
+        if !self.data.authenticated_as.is_empty() && *ec.burl.eval(self).await {
+            response.capabilities |= EXT_BURL;
+        }
+
         // Address Expansion
         if *ec.expn.eval(self).await {
             response.capabilities |= EXT_EXPN;
@@ -30,6 +30,8 @@ use tokio_rustls::server::TlsStream;
 use crate::config::{ArcSealer, DkimSigner};
 
 pub mod auth;
+#[cfg(feature = "local_delivery")]
+pub mod burl;
 pub mod data;
 pub mod ehlo;
 pub mod mail;
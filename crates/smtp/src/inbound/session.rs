@@ -178,7 +178,18 @@ impl<T: AsyncWrite + AsyncRead + IsTls + Unpin> Session<T> {
                                     self.write(b"502 5.5.1 Invalid command.\r\n").await?;
                                 }
                             }
-                            Request::Etrn { .. } | Request::Atrn { .. } | Request::Burl { .. } => {
+                            #[cfg(feature = "local_delivery")]
+                            Request::Burl { uri, is_last } => {
+                                if !self.handle_burl(uri, is_last).await? {
+                                    return Err(());
+                                }
+                            }
+                            #[cfg(not(feature = "local_delivery"))]
+                            Request::Burl { .. } => {
+                                self.write(b"502 5.5.1 Command not implemented.\r\n")
+                                    .await?;
+                            }
+                            Request::Etrn { .. } | Request::Atrn { .. } => {
                                 self.write(b"502 5.5.1 Command not implemented.\r\n")
                                     .await?;
                             }
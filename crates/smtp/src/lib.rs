@@ -57,6 +57,7 @@ impl SMTP {
         servers: &Servers,
         directory: &DirectoryConfig,
         #[cfg(feature = "local_delivery")] delivery_tx: mpsc::Sender<utils::ipc::DeliveryEvent>,
+        #[cfg(feature = "local_delivery")] url_tx: mpsc::Sender<utils::ipc::UrlFetchRequest>,
     ) -> Result<Arc<Self>, String> {
         // Read configuration parameters
         let mut config_ctx = ConfigContext::new(&servers.inner);
@@ -146,6 +147,8 @@ impl SMTP {
             sieve: sieve_config,
             #[cfg(feature = "local_delivery")]
             delivery_tx,
+            #[cfg(feature = "local_delivery")]
+            url_tx,
         });
 
         // Spawn queue manager
@@ -76,6 +76,12 @@ impl DeliveryAttempt {
             }
         } else {
             // All message recipients expired, do not re-queue. (DSN has been already sent)
+            #[cfg(feature = "local_delivery")]
+            if (self.message.flags & crate::queue::MAIL_TRACK_DELIVERY) != 0 {
+                self.message
+                    .report_delivery(&core.delivery_tx, &self.span)
+                    .await;
+            }
             self.message.remove().await;
             return;
         }
@@ -926,6 +932,12 @@ impl DeliveryAttempt {
                     inner: self.message,
                 })
             } else {
+                // Report final delivery status to the submitting account
+                #[cfg(feature = "local_delivery")]
+                if (self.message.flags & crate::queue::MAIL_TRACK_DELIVERY) != 0 {
+                    self.message.report_delivery(&core.delivery_tx, &span).await;
+                }
+
                 // Delete message from queue
                 self.message.remove().await;
 
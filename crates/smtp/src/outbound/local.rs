@@ -23,10 +23,11 @@
 
 use smtp_proto::Response;
 use tokio::sync::{mpsc, oneshot};
-use utils::ipc::{DeliveryEvent, DeliveryResult, IngestMessage};
+use utils::ipc::{DeliveryEvent, DeliveryReport, DeliveryResult, IngestMessage, RecipientReport};
 
 use crate::queue::{
-    Error, ErrorDetails, HostResponse, Message, Recipient, Status, RCPT_STATUS_CHANGED,
+    Error, ErrorDetails, HostResponse, Message, Recipient, Status, MAIL_AUTHENTICATED_SENDER,
+    RCPT_STATUS_CHANGED,
 };
 
 impl Message {
@@ -62,6 +63,7 @@ impl Message {
             .send(DeliveryEvent::Ingest {
                 message: IngestMessage {
                     sender_address: self.return_path_lcase.clone(),
+                    sender_authenticated: (self.flags & MAIL_AUTHENTICATED_SENDER) != 0,
                     recipients: recipient_addresses,
                     message_path: self.path.clone(),
                     message_size: self.size,
@@ -168,4 +170,52 @@ impl Message {
             Status::Scheduled
         }
     }
+
+    pub async fn report_delivery(
+        &self,
+        delivery_tx: &mpsc::Sender<DeliveryEvent>,
+        span: &tracing::Span,
+    ) {
+        let recipients = self
+            .recipients
+            .iter()
+            .map(|rcpt| {
+                let (delivered, smtp_reply) = match &rcpt.status {
+                    Status::Completed(response) => (true, response.response.to_string()),
+                    Status::TemporaryFailure(err) | Status::PermanentFailure(err) => {
+                        (false, err.response.to_string())
+                    }
+                    Status::Scheduled => (
+                        false,
+                        self.domains
+                            .get(rcpt.domain_idx)
+                            .map(|domain| domain.status.to_string())
+                            .unwrap_or_default(),
+                    ),
+                };
+                RecipientReport {
+                    address: rcpt.address_lcase.clone(),
+                    delivered,
+                    smtp_reply,
+                }
+            })
+            .collect();
+
+        if delivery_tx
+            .send(DeliveryEvent::Report(DeliveryReport {
+                queue_id: self.id,
+                sender_address: self.return_path_lcase.clone(),
+                recipients,
+            }))
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                parent: span,
+                context = "report_delivery",
+                event = "error",
+                reason = "tx channel closed",
+            );
+        }
+    }
 }
@@ -118,6 +118,9 @@ pub struct Recipient {
 pub const RCPT_DSN_SENT: u64 = 1 << 32;
 pub const RCPT_STATUS_CHANGED: u64 = 2 << 32;
 
+pub const MAIL_TRACK_DELIVERY: u64 = 1 << 32;
+pub const MAIL_AUTHENTICATED_SENDER: u64 = 2 << 32;
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Status<T, E> {
     #[serde(rename = "scheduled")]
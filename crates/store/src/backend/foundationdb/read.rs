@@ -28,7 +28,7 @@ use std::{
 
 use foundationdb::{
     options::{self, StreamingMode},
-    KeySelector, RangeOption,
+    KeySelector, RangeOption, Transaction,
 };
 use futures::StreamExt;
 use roaring::RoaringBitmap;
@@ -37,9 +37,11 @@ use crate::{
     query::Operator,
     write::key::{DeserializeBigEndian, KeySerializer},
     BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ReadTransaction, Serialize,
-    Store, SUBSPACE_INDEXES, SUBSPACE_QUOTAS,
+    Store, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_QUOTAS, SUBSPACE_VALUES,
 };
 
+const TRANSACTION_TOO_OLD: i32 = 1007;
+
 use super::bitmap::DeserializeBlock;
 
 impl ReadTransaction<'_> {
@@ -376,6 +378,116 @@ impl ReadTransaction<'_> {
         }
     }
 
+    pub(crate) async fn iterate_subspaces(
+        &self,
+        subspaces: &[u8],
+        account_id: Option<u32>,
+        mut cb: impl FnMut(u8, &[u8], &[u8]) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        // All subspaces are read at the version of this transaction, a newer
+        // version is only used once the transaction exceeds its maximum lifetime
+        let mut trx = None;
+        for &subspace in subspaces {
+            self.iterate_subspace(&mut trx, subspace, account_id, |key, value| {
+                cb(subspace, key, value)
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn iterate_subspace(
+        &self,
+        new_trx: &mut Option<Transaction>,
+        subspace: u8,
+        account_id: Option<u32>,
+        mut cb: impl FnMut(&[u8], &[u8]) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        let (mut begin, end) = if let Some(account_id) = account_id {
+            let mut begin = Vec::with_capacity(std::mem::size_of::<u32>() + 1);
+            begin.push(subspace);
+            begin.extend_from_slice(&account_id.to_be_bytes());
+            let end = if let Some(next_account_id) = account_id.checked_add(1) {
+                let mut end = Vec::with_capacity(std::mem::size_of::<u32>() + 1);
+                end.push(subspace);
+                end.extend_from_slice(&next_account_id.to_be_bytes());
+                end
+            } else {
+                // The last account has no upper bound
+                vec![subspace + 1]
+            };
+            (KeySelector::first_greater_or_equal(begin), end)
+        } else {
+            (
+                KeySelector::first_greater_or_equal(vec![subspace]),
+                vec![subspace + 1],
+            )
+        };
+
+        // Subspaces can take longer to read than the maximum transaction lifetime,
+        // start a new transaction from the last key read when the current one expires.
+        loop {
+            let mut is_expired = false;
+            let trx = new_trx.as_ref().unwrap_or(&self.trx);
+            let mut values = trx.get_ranges(
+                RangeOption {
+                    begin: begin.clone(),
+                    end: KeySelector::first_greater_or_equal(end.clone()),
+                    mode: StreamingMode::WantAll,
+                    reverse: false,
+                    ..RangeOption::default()
+                },
+                true,
+            );
+
+            while let Some(values) = values.next().await {
+                let values = match values {
+                    Ok(values) => values,
+                    Err(err) if err.code() == TRANSACTION_TOO_OLD => {
+                        is_expired = true;
+                        break;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                for value in values.iter() {
+                    let key = value.key();
+                    let (_, key_) = key.split_at(1);
+
+                    match subspace {
+                        SUBSPACE_BITMAPS => {
+                            let mut bm = RoaringBitmap::new();
+                            bm.deserialize_block(
+                                value.value(),
+                                key.deserialize_be_u32(key.len() - std::mem::size_of::<u32>())?,
+                            );
+                            if !bm.is_empty() {
+                                let mut bytes = Vec::with_capacity(bm.serialized_size());
+                                bm.serialize_into(&mut bytes)?;
+                                cb(key_, &bytes)?;
+                            }
+                        }
+                        // Change id counters are restored separately
+                        SUBSPACE_VALUES if key_.len() == std::mem::size_of::<u32>() => (),
+                        _ => {
+                            cb(key_, value.value())?;
+                        }
+                    }
+                    begin = KeySelector::first_greater_than(key.to_vec());
+                }
+            }
+            drop(values);
+
+            if is_expired {
+                *new_trx = Some(self.db.create_trx()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn refresh_if_old(&mut self) -> crate::Result<()> {
         if self.trx_age.elapsed() > Duration::from_millis(2000) {
             self.trx = self.db.create_trx()?;
@@ -396,7 +508,7 @@ impl Store {
 
     #[cfg(feature = "test_mode")]
     pub async fn assert_is_empty(&self) {
-        use crate::{SUBSPACE_BITMAPS, SUBSPACE_LOGS, SUBSPACE_VALUES};
+        use crate::SUBSPACE_LOGS;
 
         // Purge bitmaps
         self.purge_bitmaps().await.unwrap();
@@ -493,6 +493,25 @@ impl Store {
         }
     }
 
+    pub(crate) async fn restore_change_id(
+        &self,
+        account_id: u32,
+        change_id: u64,
+    ) -> crate::Result<()> {
+        let trx = self.db.create_trx()?;
+        trx.set(
+            &KeySerializer::new(std::mem::size_of::<u32>() + 2)
+                .write(SUBSPACE_VALUES)
+                .write(account_id)
+                .finalize(),
+            &change_id.serialize(),
+        );
+        trx.commit()
+            .await
+            .map(|_| ())
+            .map_err(|err| FdbError::from(err).into())
+    }
+
     #[cfg(feature = "test_mode")]
     pub async fn destroy(&self) {
         let trx = self.db.create_trx().unwrap();
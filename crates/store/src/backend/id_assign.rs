@@ -121,10 +121,19 @@ impl Store {
         unreachable!()
     }
 
+    pub(crate) async fn restore_change_id(
+        &self,
+        _account_id: u32,
+        _change_id: u64,
+    ) -> crate::Result<()> {
+        // Assigners are rebuilt from the restored data
+        self.id_assigner.lock().clear();
+        Ok(())
+    }
+
     async fn build_id_assigner(&self, key: IdCacheKey) -> crate::Result<()> {
-        let conn = self.read_transaction()?;
         let id_assigner = self.id_assigner.clone();
-        self.spawn_worker(move || {
+        self.spawn_read(move |conn| {
             let mut id_assigner = id_assigner.lock();
             // Make sure id assigner was not added by another thread
             if id_assigner.get_mut(&key).is_some() {
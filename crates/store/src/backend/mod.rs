@@ -23,6 +23,12 @@
 
 #[cfg(feature = "foundation")]
 pub mod foundationdb;
+#[cfg(any(feature = "sqlite", feature = "rocks"))]
+pub mod id_assign;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 #[cfg(feature = "rocks")]
 pub mod rocksdb;
 #[cfg(feature = "sqlite")]
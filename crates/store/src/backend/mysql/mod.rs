@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+// Isolation guarantees of this backend:
+// - Reads run on pooled connections outside of any transaction and do not share a
+//   snapshot, the only exception being `iterate_subspaces` used by backups.
+// - Writes run at the default isolation level and are only serializable when the
+//   batch contains an `AssertValue`, retrying on serialization failures.
+// - Document ids are reserved under a row lock on the collection's counter in `c`,
+//   reservations are stored in `i` at field `u8::MAX` with their creation timestamp
+//   and are reclaimed once older than `ID_ASSIGNMENT_EXPIRY`.
+
+pub mod main;
+pub mod purge;
+pub mod read;
+pub mod write;
+
+const WORD_SIZE_BITS: u32 = (WORD_SIZE * 8) as u32;
+const WORD_SIZE: usize = std::mem::size_of::<u64>();
+const WORDS_PER_BLOCK: u32 = 16;
+pub const BITS_PER_BLOCK: u32 = WORD_SIZE_BITS * WORDS_PER_BLOCK;
+const BITS_MASK: u32 = BITS_PER_BLOCK - 1;
+
+impl From<sqlx::Error> for crate::Error {
+    fn from(err: sqlx::Error) -> Self {
+        Self::InternalError(format!("MySQL error: {}", err))
+    }
+}
+
+// Transactions failing with a serialization error or deadlock can be retried
+fn is_serialization_failure(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|err| err.code())
+        .map_or(false, |code| matches!(&*code, "40001"))
+}
+
+// Row in the counters table used to serialize document id assignments of a collection,
+// negative keys never collide with the change id counters keyed by account id
+fn document_id_counter(account_id: u32, collection: u8) -> i64 {
+    -(((account_id as i64) << 8) | collection as i64) - 1
+}
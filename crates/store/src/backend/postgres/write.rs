@@ -0,0 +1,499 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::time::{Duration, Instant};
+
+use ahash::AHashSet;
+use futures::TryStreamExt;
+use roaring::RoaringBitmap;
+use sqlx::Row;
+
+use crate::{
+    write::{now, Batch, Operation, ValueClass},
+    AclKey, BitmapKey, IndexKey, LogKey, Serialize, Store, ValueKey,
+};
+
+use super::{document_id_counter, is_serialization_failure, BITS_MASK, BITS_PER_BLOCK};
+
+#[cfg(not(feature = "test_mode"))]
+pub const ID_ASSIGNMENT_EXPIRY: u64 = 60 * 60; // seconds
+#[cfg(not(feature = "test_mode"))]
+const MAX_COMMIT_ATTEMPTS: u32 = 10;
+#[cfg(not(feature = "test_mode"))]
+const MAX_COMMIT_TIME: Duration = Duration::from_secs(10);
+
+#[cfg(feature = "test_mode")]
+pub static ID_ASSIGNMENT_EXPIRY: std::sync::atomic::AtomicU64 =
+    std::sync::atomic::AtomicU64::new(60 * 60); // seconds
+#[cfg(feature = "test_mode")]
+const MAX_COMMIT_ATTEMPTS: u32 = 1000;
+#[cfg(feature = "test_mode")]
+const MAX_COMMIT_TIME: Duration = Duration::from_secs(3600);
+
+const SET_QUERIES: &[&str] = &[
+    "INSERT INTO b (z, a) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET a = b.a | EXCLUDED.a",
+    "INSERT INTO b (z, b) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET b = b.b | EXCLUDED.b",
+    "INSERT INTO b (z, c) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET c = b.c | EXCLUDED.c",
+    "INSERT INTO b (z, d) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET d = b.d | EXCLUDED.d",
+    "INSERT INTO b (z, e) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET e = b.e | EXCLUDED.e",
+    "INSERT INTO b (z, f) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET f = b.f | EXCLUDED.f",
+    "INSERT INTO b (z, g) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET g = b.g | EXCLUDED.g",
+    "INSERT INTO b (z, h) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET h = b.h | EXCLUDED.h",
+    "INSERT INTO b (z, i) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET i = b.i | EXCLUDED.i",
+    "INSERT INTO b (z, j) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET j = b.j | EXCLUDED.j",
+    "INSERT INTO b (z, k) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET k = b.k | EXCLUDED.k",
+    "INSERT INTO b (z, l) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET l = b.l | EXCLUDED.l",
+    "INSERT INTO b (z, m) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET m = b.m | EXCLUDED.m",
+    "INSERT INTO b (z, n) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET n = b.n | EXCLUDED.n",
+    "INSERT INTO b (z, o) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET o = b.o | EXCLUDED.o",
+    "INSERT INTO b (z, p) VALUES ($1, $2) ON CONFLICT (z) DO UPDATE SET p = b.p | EXCLUDED.p",
+];
+const CLEAR_QUERIES: &[&str] = &[
+    "UPDATE b SET a = a & $1 WHERE z = $2",
+    "UPDATE b SET b = b & $1 WHERE z = $2",
+    "UPDATE b SET c = c & $1 WHERE z = $2",
+    "UPDATE b SET d = d & $1 WHERE z = $2",
+    "UPDATE b SET e = e & $1 WHERE z = $2",
+    "UPDATE b SET f = f & $1 WHERE z = $2",
+    "UPDATE b SET g = g & $1 WHERE z = $2",
+    "UPDATE b SET h = h & $1 WHERE z = $2",
+    "UPDATE b SET i = i & $1 WHERE z = $2",
+    "UPDATE b SET j = j & $1 WHERE z = $2",
+    "UPDATE b SET k = k & $1 WHERE z = $2",
+    "UPDATE b SET l = l & $1 WHERE z = $2",
+    "UPDATE b SET m = m & $1 WHERE z = $2",
+    "UPDATE b SET n = n & $1 WHERE z = $2",
+    "UPDATE b SET o = o & $1 WHERE z = $2",
+    "UPDATE b SET p = p & $1 WHERE z = $2",
+];
+
+impl Store {
+    pub async fn write(&self, batch: Batch) -> crate::Result<()> {
+        let start = Instant::now();
+        let mut retry_count = 0;
+
+        loop {
+            match self.write_trx(&batch).await {
+                Ok(true) => return Ok(()),
+                Ok(false) => return Err(crate::Error::AssertValueFailed),
+                Err(err)
+                    if is_serialization_failure(&err)
+                        && retry_count < MAX_COMMIT_ATTEMPTS
+                        && start.elapsed() < MAX_COMMIT_TIME =>
+                {
+                    retry_count += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    async fn write_trx(&self, batch: &Batch) -> Result<bool, sqlx::Error> {
+        let mut account_id = u32::MAX;
+        let mut collection = u8::MAX;
+        let mut document_id = u32::MAX;
+        let mut bitmap_block_num = 0;
+        let mut bitmap_col_num = 0;
+        let mut bitmap_value_set = 0i64;
+        let mut bitmap_value_clear = 0i64;
+        let mut trx = self.conn_pool.begin().await?;
+
+        // Assertions on keys that do not exist yet are only safe under serializable isolation
+        if batch
+            .ops
+            .iter()
+            .any(|op| matches!(op, Operation::AssertValue { .. }))
+        {
+            sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+                .execute(&mut *trx)
+                .await?;
+        }
+
+        for op in &batch.ops {
+            match op {
+                Operation::AccountId {
+                    account_id: account_id_,
+                } => {
+                    account_id = *account_id_;
+                }
+                Operation::Collection {
+                    collection: collection_,
+                } => {
+                    collection = *collection_;
+                }
+                Operation::DocumentId {
+                    document_id: document_id_,
+                } => {
+                    document_id = *document_id_;
+                    bitmap_block_num = document_id / BITS_PER_BLOCK;
+                    let index = document_id & BITS_MASK;
+                    bitmap_col_num = (index / 64) as usize;
+                    bitmap_value_set = (1u64 << (index as u64 & 63)) as i64;
+                    bitmap_value_clear = (!(1u64 << (index as u64 & 63))) as i64;
+                }
+                Operation::Value { class, set } => {
+                    let key = match class {
+                        ValueClass::Property { field, family } => ValueKey {
+                            account_id,
+                            collection,
+                            document_id,
+                            family: *family,
+                            field: *field,
+                        }
+                        .serialize(),
+                        ValueClass::Acl { grant_account_id } => AclKey {
+                            grant_account_id: *grant_account_id,
+                            to_account_id: account_id,
+                            to_collection: collection,
+                            to_document_id: document_id,
+                        }
+                        .serialize(),
+                        ValueClass::Custom { bytes } => bytes.to_vec(),
+                    };
+
+                    if let Some(value) = set {
+                        sqlx::query(
+                            "INSERT INTO v (k, v) VALUES ($1, $2) ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v",
+                        )
+                        .bind(&key)
+                        .bind(value)
+                        .execute(&mut *trx)
+                        .await?;
+                    } else {
+                        sqlx::query("DELETE FROM v WHERE k = $1")
+                            .bind(&key)
+                            .execute(&mut *trx)
+                            .await?;
+                    }
+                }
+                Operation::Index { field, key, set } => {
+                    let key = IndexKey {
+                        account_id,
+                        collection,
+                        document_id,
+                        field: *field,
+                        key,
+                    }
+                    .serialize();
+
+                    if *set {
+                        sqlx::query("INSERT INTO i (k) VALUES ($1) ON CONFLICT (k) DO NOTHING")
+                            .bind(&key)
+                            .execute(&mut *trx)
+                            .await?;
+                    } else {
+                        sqlx::query("DELETE FROM i WHERE k = $1")
+                            .bind(&key)
+                            .execute(&mut *trx)
+                            .await?;
+                    }
+                }
+                Operation::Bitmap {
+                    family,
+                    field,
+                    key,
+                    set,
+                } => {
+                    let key = BitmapKey {
+                        account_id,
+                        collection,
+                        family: *family,
+                        field: *field,
+                        block_num: bitmap_block_num,
+                        key,
+                    }
+                    .serialize();
+
+                    if *set {
+                        sqlx::query(SET_QUERIES[bitmap_col_num])
+                            .bind(&key)
+                            .bind(bitmap_value_set)
+                            .execute(&mut *trx)
+                            .await?;
+                    } else {
+                        sqlx::query(CLEAR_QUERIES[bitmap_col_num])
+                            .bind(bitmap_value_clear)
+                            .bind(&key)
+                            .execute(&mut *trx)
+                            .await?;
+                    }
+                }
+                Operation::Log {
+                    collection,
+                    change_id,
+                    set,
+                } => {
+                    let key = LogKey {
+                        account_id,
+                        collection: *collection,
+                        change_id: *change_id,
+                    }
+                    .serialize();
+
+                    sqlx::query(
+                        "INSERT INTO l (k, v) VALUES ($1, $2) ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v",
+                    )
+                    .bind(&key)
+                    .bind(set)
+                    .execute(&mut *trx)
+                    .await?;
+                }
+                Operation::AssertValue {
+                    class,
+                    assert_value,
+                } => {
+                    let key = match class {
+                        ValueClass::Property { field, family } => ValueKey {
+                            account_id,
+                            collection,
+                            document_id,
+                            family: *family,
+                            field: *field,
+                        }
+                        .serialize(),
+                        ValueClass::Acl { grant_account_id } => AclKey {
+                            grant_account_id: *grant_account_id,
+                            to_account_id: account_id,
+                            to_collection: collection,
+                            to_document_id: document_id,
+                        }
+                        .serialize(),
+                        ValueClass::Custom { bytes } => bytes.to_vec(),
+                    };
+                    let matches = if let Some(row) =
+                        sqlx::query("SELECT v FROM v WHERE k = $1 FOR UPDATE")
+                            .bind(&key)
+                            .fetch_optional(&mut *trx)
+                            .await?
+                    {
+                        assert_value.matches(row.try_get::<&[u8], _>(0)?)
+                    } else {
+                        assert_value.is_none()
+                    };
+                    if !matches {
+                        return Ok(false);
+                    }
+                }
+                Operation::UpdateQuota { bytes } => {
+                    sqlx::query(
+                        "INSERT INTO q (k, v) VALUES ($1, $2) ON CONFLICT (k) DO UPDATE SET v = q.v + EXCLUDED.v",
+                    )
+                    .bind(account_id as i64)
+                    .bind(*bytes)
+                    .execute(&mut *trx)
+                    .await?;
+                }
+            }
+        }
+
+        trx.commit().await.map(|_| true)
+    }
+
+    pub async fn assign_document_id(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8>,
+    ) -> crate::Result<u32> {
+        let start = Instant::now();
+        let collection = collection.into();
+        let mut retry_count = 0;
+
+        loop {
+            // Ids in use are read before the reservations, documents created in
+            // the meantime still hold their reservation until it is released
+            let used_ids = self
+                .read_transaction()
+                .await?
+                .get_bitmap(BitmapKey::document_ids(account_id, collection))
+                .await?
+                .unwrap_or_default();
+
+            match self
+                .reserve_document_id(account_id, collection, &used_ids)
+                .await
+            {
+                Ok(Some(document_id)) => return Ok(document_id),
+                Ok(None) => {
+                    return Err(crate::Error::InternalError(
+                        "No document ids available".to_string(),
+                    ))
+                }
+                Err(err)
+                    if is_serialization_failure(&err)
+                        && retry_count < MAX_COMMIT_ATTEMPTS
+                        && start.elapsed() < MAX_COMMIT_TIME =>
+                {
+                    retry_count += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    async fn reserve_document_id(
+        &self,
+        account_id: u32,
+        collection: u8,
+        used_ids: &RoaringBitmap,
+    ) -> Result<Option<u32>, sqlx::Error> {
+        let begin = IndexKey {
+            account_id,
+            collection,
+            document_id: 0,
+            field: u8::MAX,
+            key: &[],
+        }
+        .serialize();
+        let end = IndexKey {
+            account_id,
+            collection,
+            document_id: u32::MAX,
+            field: u8::MAX,
+            key: &[],
+        }
+        .serialize();
+        #[cfg(not(feature = "test_mode"))]
+        let expired_timestamp = now() - ID_ASSIGNMENT_EXPIRY;
+        #[cfg(feature = "test_mode")]
+        let expired_timestamp =
+            now() - ID_ASSIGNMENT_EXPIRY.load(std::sync::atomic::Ordering::Relaxed);
+
+        let mut trx = self.conn_pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+            .execute(&mut *trx)
+            .await?;
+
+        // Lock the id counter of the collection, concurrent assignments wait here
+        sqlx::query(
+            "INSERT INTO c (k, v) VALUES ($1, 1) ON CONFLICT (k) DO UPDATE SET v = c.v + 1",
+        )
+        .bind(document_id_counter(account_id, collection))
+        .execute(&mut *trx)
+        .await?;
+
+        // Release expired reservations and those of documents already created
+        let mut reserved_ids = AHashSet::new();
+        let mut released_keys = Vec::new();
+        let mut rows = sqlx::query("SELECT k, v FROM i WHERE k >= $1 AND k <= $2")
+            .bind(&begin)
+            .bind(&end)
+            .fetch(&mut *trx);
+        while let Some(row) = rows.try_next().await? {
+            let key = row.try_get::<&[u8], _>(0)?;
+            if key.len() != begin.len() {
+                continue;
+            }
+            let mut document_id = [0u8; std::mem::size_of::<u32>()];
+            document_id.copy_from_slice(&key[key.len() - std::mem::size_of::<u32>()..]);
+            let document_id = u32::from_be_bytes(document_id);
+            if row.try_get::<i64, _>(1)? as u64 <= expired_timestamp
+                || used_ids.contains(document_id)
+            {
+                released_keys.push(key.to_vec());
+            } else {
+                reserved_ids.insert(document_id);
+            }
+        }
+        drop(rows);
+        for key in released_keys {
+            sqlx::query("DELETE FROM i WHERE k = $1")
+                .bind(&key)
+                .execute(&mut *trx)
+                .await?;
+        }
+
+        // Reserve the first id that is neither in use nor reserved
+        let document_id = if let Some(document_id) =
+            (0..u32::MAX).find(|id| !used_ids.contains(*id) && !reserved_ids.contains(id))
+        {
+            document_id
+        } else {
+            return Ok(None);
+        };
+        sqlx::query("INSERT INTO i (k, v) VALUES ($1, $2)")
+            .bind(
+                IndexKey {
+                    account_id,
+                    collection,
+                    document_id,
+                    field: u8::MAX,
+                    key: &[],
+                }
+                .serialize(),
+            )
+            .bind(now() as i64)
+            .execute(&mut *trx)
+            .await?;
+
+        trx.commit().await.map(|_| Some(document_id))
+    }
+
+    pub async fn assign_change_id(&self, account_id: u32) -> crate::Result<u64> {
+        sqlx::query(
+            "INSERT INTO c (k, v) VALUES ($1, 0) ON CONFLICT (k) DO UPDATE SET v = c.v + 1 RETURNING v",
+        )
+        .bind(account_id as i64)
+        .fetch_one(&self.conn_pool)
+        .await?
+        .try_get::<i64, _>(0)
+        .map(|id| id as u64)
+        .map_err(Into::into)
+    }
+
+    pub(crate) async fn restore_change_id(
+        &self,
+        account_id: u32,
+        change_id: u64,
+    ) -> crate::Result<()> {
+        sqlx::query(
+            "INSERT INTO c (k, v) VALUES ($1, $2) ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v",
+        )
+        .bind(account_id as i64)
+        .bind(change_id as i64)
+        .execute(&self.conn_pool)
+        .await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "test_mode")]
+    pub async fn destroy(&self) {
+        use crate::{
+            SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_QUOTAS, SUBSPACE_VALUES,
+        };
+
+        for table in [
+            SUBSPACE_VALUES,
+            SUBSPACE_LOGS,
+            SUBSPACE_BITMAPS,
+            SUBSPACE_INDEXES,
+            SUBSPACE_QUOTAS,
+            b'c',
+        ] {
+            sqlx::query(&format!("DROP TABLE IF EXISTS {}", char::from(table)))
+                .execute(&self.conn_pool)
+                .await
+                .unwrap();
+        }
+        self.create_tables().await.unwrap();
+    }
+}
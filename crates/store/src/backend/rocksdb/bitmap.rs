@@ -61,15 +61,19 @@ pub fn deserialize_bitmap(bytes: &[u8]) -> Option<RoaringBitmap> {
 }
 
 impl Deserialize for RoaringBitmap {
-    fn deserialize(bytes: &[u8]) -> Option<Self> {
-        match *bytes.first()? {
-            IS_BITMAP => deserialize_bitmap(bytes),
-            IS_BITLIST => {
+    fn deserialize(bytes: &[u8]) -> crate::Result<Self> {
+        match bytes.first() {
+            Some(&IS_BITMAP) => deserialize_bitmap(bytes).ok_or_else(|| {
+                crate::Error::InternalError("Failed to deserialize bitmap".to_string())
+            }),
+            Some(&IS_BITLIST) => {
                 let mut bm = RoaringBitmap::new();
                 deserialize_bitlist(&mut bm, bytes);
-                Some(bm)
+                Ok(bm)
             }
-            _ => None,
+            _ => Err(crate::Error::InternalError(
+                "Invalid bitmap header".to_string(),
+            )),
         }
     }
 }
@@ -201,7 +205,7 @@ pub fn bitmap_merge<'x>(
     operands: impl IntoIterator<Item = &'x [u8]>,
 ) -> Option<Vec<u8>> {
     let mut bm = match existing_val {
-        Some(existing_val) => RoaringBitmap::deserialize(existing_val)?,
+        Some(existing_val) => RoaringBitmap::deserialize(existing_val).ok()?,
         None if operands_len == 1 => {
             return Some(Vec::from(operands.into_iter().next().unwrap()));
         }
@@ -21,29 +21,27 @@
  * for more details.
 */
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
+use lru_cache::LruCache;
+use parking_lot::Mutex;
 use roaring::RoaringBitmap;
 use rocksdb::{ColumnFamilyDescriptor, MergeOperands, OptimisticTransactionDB, Options};
+use tokio::sync::oneshot;
+use utils::config::Config;
 
-use crate::{Deserialize, Error, Store};
+use crate::{blob::BlobStore, Deserialize, Error, ReadTransaction, Store};
 
-use super::{CF_BITMAPS, CF_BLOBS, CF_INDEXES, CF_LOGS, CF_VALUES};
+use super::{CF_BITMAPS, CF_INDEXES, CF_LOGS, CF_QUOTAS, CF_VALUES};
 
 impl Store {
-    pub fn open() -> crate::Result<Self> {
+    pub async fn open(config: &Config) -> crate::Result<Self> {
         // Create the database directory if it doesn't exist
-        let path = PathBuf::from(
-            "/tmp/rocksdb_test", /*&settings
-                                 .get("db-path")
-                                 .unwrap_or_else(|| "/usr/local/stalwart-jmap/data".to_string())*/
-        );
-        let mut idx_path = path;
-        idx_path.push("idx");
-        std::fs::create_dir_all(&idx_path).map_err(|err| {
+        let path = config.property_require::<PathBuf>("store.db.path")?;
+        std::fs::create_dir_all(&path).map_err(|err| {
             Error::InternalError(format!(
-                "Failed to create index directory {}: {:?}",
-                idx_path.display(),
+                "Failed to create database directory {}: {:?}",
+                path.display(),
                 err
             ))
         })?;
@@ -51,7 +49,6 @@ impl Store {
         // Bitmaps
         let cf_bitmaps = {
             let mut cf_opts = Options::default();
-            //cf_opts.set_max_write_buffer_number(16);
             cf_opts.set_merge_operator("merge", bitmap_merge, bitmap_partial_merge);
             cf_opts.set_compaction_filter("compact", bitmap_compact);
             ColumnFamilyDescriptor::new(CF_BITMAPS, cf_opts)
@@ -60,7 +57,9 @@ impl Store {
         // Stored values
         let cf_values = {
             let mut cf_opts = Options::default();
-            cf_opts.set_merge_operator_associative("merge", numeric_value_merge);
+            cf_opts.set_enable_blob_files(true);
+            cf_opts
+                .set_min_blob_size(config.property_or_static("store.db.min-blob-size", "16384")?);
             ColumnFamilyDescriptor::new(CF_VALUES, cf_opts)
         };
 
@@ -70,22 +69,19 @@ impl Store {
             ColumnFamilyDescriptor::new(CF_INDEXES, cf_opts)
         };
 
-        // Blobs
-        let cf_blobs = {
-            let mut cf_opts = Options::default();
-            cf_opts.set_enable_blob_files(true);
-            cf_opts.set_min_blob_size(
-                16834, /*settings.parse("blob-min-size").unwrap_or(16384) */
-            );
-            ColumnFamilyDescriptor::new(CF_BLOBS, cf_opts)
-        };
-
-        // Raft log and change log
-        let cf_log = {
+        // Change log
+        let cf_logs = {
             let cf_opts = Options::default();
             ColumnFamilyDescriptor::new(CF_LOGS, cf_opts)
         };
 
+        // Quotas
+        let cf_quotas = {
+            let mut cf_opts = Options::default();
+            cf_opts.set_merge_operator_associative("merge", numeric_value_merge);
+            ColumnFamilyDescriptor::new(CF_QUOTAS, cf_opts)
+        };
+
         let mut db_opts = Options::default();
         db_opts.create_missing_column_families(true);
         db_opts.create_if_missing(true);
@@ -93,20 +89,63 @@ impl Store {
         Ok(Store {
             db: OptimisticTransactionDB::open_cf_descriptors(
                 &db_opts,
-                idx_path,
-                vec![cf_bitmaps, cf_values, cf_indexes, cf_blobs, cf_log],
+                path,
+                vec![cf_bitmaps, cf_values, cf_indexes, cf_logs, cf_quotas],
             )
-            .map_err(|e| Error::InternalError(e.into_string()))?,
+            .map(Arc::new)?,
+            worker_pool: rayon::ThreadPoolBuilder::new()
+                .num_threads(
+                    config
+                        .property::<usize>("store.db.pool.workers")?
+                        .filter(|v| *v > 0)
+                        .unwrap_or_else(num_cpus::get),
+                )
+                .build()
+                .map_err(|err| {
+                    crate::Error::InternalError(format!("Failed to build worker pool: {}", err))
+                })?,
+            id_assigner: Arc::new(Mutex::new(LruCache::new(
+                config.property_or_static("store.db.cache.size", "1000")?,
+            ))),
+            blob: BlobStore::new(config).await?,
         })
     }
 
     pub fn close(&self) -> crate::Result<()> {
-        self.db
-            .flush()
-            .map_err(|e| Error::InternalError(e.to_string()))?;
+        self.db.flush()?;
         self.db.cancel_all_background_work(true);
         Ok(())
     }
+
+    pub async fn spawn_read<U, V>(&self, f: U) -> crate::Result<V>
+    where
+        U: FnOnce(&mut ReadTransaction<'_>) -> crate::Result<V> + Send + 'static,
+        V: Sync + Send + 'static,
+    {
+        let db = self.db.clone();
+        self.spawn_worker(move || f(&mut ReadTransaction::new(&db)))
+            .await
+    }
+
+    pub async fn spawn_worker<U, V>(&self, f: U) -> crate::Result<V>
+    where
+        U: FnOnce() -> crate::Result<V> + Send + 'static,
+        V: Sync + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        self.worker_pool.spawn(move || {
+            tx.send(f()).ok();
+        });
+
+        match rx.await {
+            Ok(result) => result,
+            Err(err) => Err(crate::Error::InternalError(format!(
+                "Worker thread failed: {}",
+                err
+            ))),
+        }
+    }
 }
 
 pub fn numeric_value_merge(
@@ -152,7 +191,7 @@ pub fn bitmap_compact(
     value: &[u8],
 ) -> rocksdb::compaction_filter::Decision {
     match RoaringBitmap::deserialize(value) {
-        Some(bm) if bm.is_empty() => rocksdb::compaction_filter::Decision::Remove,
+        Ok(bm) if bm.is_empty() => rocksdb::compaction_filter::Decision::Remove,
         _ => rocksdb::compaction_filter::Decision::Keep,
     }
 }
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use roaring::RoaringBitmap;
+use rocksdb::{Direction, IteratorMode};
+
+use crate::{write::key::KeySerializer, Deserialize, Store};
+
+use super::{CF_BITMAPS, CF_INDEXES, CF_LOGS, CF_QUOTAS, CF_VALUES};
+
+impl Store {
+    pub async fn purge_bitmaps(&self) -> crate::Result<()> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let cf = db.cf_handle(CF_BITMAPS).unwrap();
+
+            for row in db.iterator_cf(&cf, IteratorMode::Start) {
+                let (key, value) = row?;
+                if RoaringBitmap::deserialize(&value)?.is_empty() {
+                    // Make sure the bitmap was not modified since it was read
+                    let txn = db.transaction();
+                    if txn
+                        .get_pinned_for_update_cf(&cf, &key, true)?
+                        .map_or(false, |value| {
+                            RoaringBitmap::deserialize(&value).map_or(false, |bm| bm.is_empty())
+                        })
+                    {
+                        txn.delete_cf(&cf, &key)?;
+                        if let Err(err) = txn.commit() {
+                            tracing::debug!("Failed to purge bitmap {:?}: {}", key, err);
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn purge_account(&self, account_id: u32) -> crate::Result<()> {
+        let db = self.db.clone();
+        self.spawn_worker(move || {
+            let from_key = KeySerializer::new(std::mem::size_of::<u32>())
+                .write(account_id)
+                .finalize();
+            let to_key = account_id.checked_add(1).map(|account_id| {
+                KeySerializer::new(std::mem::size_of::<u32>())
+                    .write(account_id)
+                    .finalize()
+            });
+
+            for cf_name in [CF_BITMAPS, CF_VALUES, CF_LOGS, CF_INDEXES] {
+                let cf = db.cf_handle(cf_name).unwrap();
+                for row in db.iterator_cf(&cf, IteratorMode::From(&from_key, Direction::Forward)) {
+                    let (key, _) = row?;
+                    if to_key
+                        .as_ref()
+                        .map_or(true, |to_key| key.as_ref() < to_key.as_slice())
+                    {
+                        db.delete_cf(&cf, key)?;
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            db.delete_cf(&db.cf_handle(CF_QUOTAS).unwrap(), account_id.to_be_bytes())?;
+
+            Ok(())
+        })
+        .await
+    }
+}
@@ -21,122 +21,67 @@
  * for more details.
 */
 
-use std::ops::{BitAndAssign, BitOrAssign};
+use std::{ops::BitAndAssign, sync::Arc};
 
 use roaring::RoaringBitmap;
-use rocksdb::{Direction, IteratorMode};
+use rocksdb::{BoundColumnFamily, Direction, IteratorMode, MultiThreaded, OptimisticTransactionDB};
 
 use crate::{
-    query::Operator, write::key::DeserializeBigEndian, BitmapKey, Deserialize, Error, Serialize,
-    Store, BM_DOCUMENT_IDS,
+    query::Operator,
+    write::key::{DeserializeBigEndian, KeySerializer},
+    BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ReadTransaction, Serialize,
+    Store, SUBSPACE_BITMAPS,
 };
 
-use super::{CF_BITMAPS, CF_INDEXES, CF_VALUES, FIELD_PREFIX_LEN};
+use super::{subspace_to_cf, CF_BITMAPS, CF_INDEXES, CF_LOGS, CF_QUOTAS};
 
-impl Store {
-    #[inline(always)]
-    pub fn get_value<U>(&self, key: impl Serialize) -> crate::Result<Option<U>>
-    where
-        U: Deserialize,
-    {
-        let key = key.serialize();
-        if let Some(bytes) = self
-            .db
-            .get_pinned_cf(&self.db.cf_handle(CF_VALUES).unwrap(), &key)
-            .map_err(|err| Error::InternalError(format!("get_cf failed: {}", err)))?
-        {
-            Ok(Some(U::deserialize(&bytes).ok_or_else(|| {
-                Error::InternalError(format!("Failed to deserialize key: {:?}", key))
-            })?))
-        } else {
-            Ok(None)
+impl<'x> ReadTransaction<'x> {
+    /// Reads are served from a snapshot taken when the transaction is created.
+    pub(crate) fn new(db: &'x OptimisticTransactionDB<MultiThreaded>) -> Self {
+        ReadTransaction {
+            db,
+            snapshot: db.snapshot(),
         }
     }
 
     #[inline(always)]
-    pub fn get_values<U>(&self, keys: Vec<impl Serialize>) -> crate::Result<Vec<Option<U>>>
+    #[maybe_async::maybe_async]
+    pub async fn get_value<U>(&self, key: impl Key) -> crate::Result<Option<U>>
     where
         U: Deserialize,
     {
-        let cf_handle = self.db.cf_handle(CF_VALUES).unwrap();
-        let mut results = Vec::with_capacity(keys.len());
-        for value in self.db.multi_get_cf(
-            keys.into_iter()
-                .map(|key| (&cf_handle, key.serialize()))
-                .collect::<Vec<_>>(),
-        ) {
-            results.push(
-                if let Some(bytes) = value
-                    .map_err(|err| Error::InternalError(format!("multi_get_cf failed: {}", err)))?
-                {
-                    U::deserialize(&bytes)
-                        .ok_or_else(|| {
-                            Error::InternalError("Failed to deserialize keys.".to_string())
-                        })?
-                        .into()
-                } else {
-                    None
-                },
-            );
+        let cf = self.cf_handle(subspace_to_cf(key.subspace())?)?;
+        if let Some(bytes) = self.snapshot.get_cf(&cf, key.serialize())? {
+            U::deserialize(&bytes).map(Some)
+        } else {
+            Ok(None)
         }
-
-        Ok(results)
     }
 
-    #[inline(always)]
-    pub fn get_bitmap<T: AsRef<[u8]>>(
+    #[maybe_async::maybe_async]
+    pub async fn get_bitmap<T: AsRef<[u8]>>(
         &self,
         key: BitmapKey<T>,
     ) -> crate::Result<Option<RoaringBitmap>> {
-        let key = key.serialize();
         if let Some(bytes) = self
-            .db
-            .get_pinned_cf(&self.db.cf_handle(CF_BITMAPS).unwrap(), &key)
-            .map_err(|err| Error::InternalError(format!("get_cf failed: {}", err)))?
+            .snapshot
+            .get_cf(&self.cf_handle(CF_BITMAPS)?, key.serialize())?
         {
-            let bm = RoaringBitmap::deserialize(&bytes).ok_or_else(|| {
-                Error::InternalError(format!("Failed to deserialize key: {:?}", &key))
-            })?;
+            let bm = RoaringBitmap::deserialize(&bytes)?;
             Ok(if !bm.is_empty() { Some(bm) } else { None })
         } else {
             Ok(None)
         }
     }
 
-    #[inline(always)]
-    fn get_bitmaps<T: Serialize>(&self, keys: Vec<T>) -> crate::Result<Vec<Option<RoaringBitmap>>> {
-        let cf_handle = self.db.cf_handle(CF_BITMAPS).unwrap();
-        let mut results = Vec::with_capacity(keys.len());
-        for value in self.db.multi_get_cf(
-            keys.into_iter()
-                .map(|key| (&cf_handle, key.serialize()))
-                .collect::<Vec<_>>(),
-        ) {
-            results.push(
-                if let Some(bytes) = value
-                    .map_err(|err| Error::InternalError(format!("multi_get_cf failed: {}", err)))?
-                {
-                    RoaringBitmap::deserialize(&bytes)
-                        .ok_or_else(|| {
-                            Error::InternalError("Failed to deserialize keys.".to_string())
-                        })?
-                        .into()
-                } else {
-                    None
-                },
-            );
-        }
-
-        Ok(results)
-    }
-
-    pub(crate) fn get_bitmaps_intersection<T: Serialize>(
+    #[maybe_async::maybe_async]
+    pub(crate) async fn get_bitmaps_intersection<T: AsRef<[u8]>>(
         &self,
-        keys: Vec<T>,
+        keys: Vec<BitmapKey<T>>,
     ) -> crate::Result<Option<RoaringBitmap>> {
         let mut result: Option<RoaringBitmap> = None;
-        for bitmap in self.get_bitmaps(keys)? {
-            if let Some(bitmap) = bitmap {
+        for key in keys {
+            if let Some(bitmap) = self.get_bitmap(key).await? {
                 if let Some(result) = &mut result {
                     result.bitand_assign(&bitmap);
                     if result.is_empty() {
@@ -152,79 +97,374 @@ impl Store {
         Ok(result)
     }
 
-    pub(crate) fn get_bitmaps_union<T: Serialize>(
+    #[maybe_async::maybe_async]
+    pub(crate) async fn get_bitmaps_union<T: AsRef<[u8]>>(
         &self,
-        keys: Vec<T>,
+        keys: Vec<BitmapKey<T>>,
     ) -> crate::Result<Option<RoaringBitmap>> {
-        let mut result: Option<RoaringBitmap> = None;
-        for bitmap in (self.get_bitmaps(keys)?).into_iter().flatten() {
-            if let Some(result) = &mut result {
-                result.bitor_assign(&bitmap);
-            } else {
-                result = Some(bitmap);
+        let mut bm = RoaringBitmap::new();
+
+        for key in keys {
+            if let Some(bitmap) = self.get_bitmap(key).await? {
+                bm |= bitmap;
             }
         }
-        Ok(result)
+
+        Ok(if !bm.is_empty() { Some(bm) } else { None })
     }
 
-    pub(crate) fn range_to_bitmap(
+    #[maybe_async::maybe_async]
+    pub(crate) async fn range_to_bitmap(
         &self,
-        match_key: &[u8],
-        match_value: &[u8],
+        account_id: u32,
+        collection: u8,
+        field: u8,
+        value: Vec<u8>,
         op: Operator,
     ) -> crate::Result<Option<RoaringBitmap>> {
-        let mut bm = RoaringBitmap::new();
-        let match_prefix = &match_key[0..FIELD_PREFIX_LEN];
-        for result in self.db.iterator_cf(
-            &self.db.cf_handle(CF_INDEXES).unwrap(),
-            IteratorMode::From(
-                match_key,
-                match op {
-                    Operator::GreaterThan | Operator::GreaterEqualThan | Operator::Equal => {
-                        Direction::Forward
-                    }
-                    _ => Direction::Reverse,
-                },
+        let k1 = KeySerializer::new(
+            std::mem::size_of::<IndexKey<&[u8]>>() + value.len() + 1 + std::mem::size_of::<u32>(),
+        )
+        .write(account_id)
+        .write(collection)
+        .write(field);
+        let k2 = KeySerializer::new(
+            std::mem::size_of::<IndexKey<&[u8]>>() + value.len() + 1 + std::mem::size_of::<u32>(),
+        )
+        .write(account_id)
+        .write(collection)
+        .write(field + matches!(op, Operator::GreaterThan | Operator::GreaterEqualThan) as u8);
+
+        let (begin, end, begin_inclusive, end_inclusive) = match op {
+            Operator::LowerThan => (
+                k1.finalize(),
+                k2.write(&value[..]).write(0u32).finalize(),
+                true,
+                false,
+            ),
+            Operator::LowerEqualThan => (
+                k1.finalize(),
+                k2.write(&value[..]).write(u32::MAX).finalize(),
+                true,
+                true,
+            ),
+            Operator::GreaterThan => (
+                k1.write(&value[..]).write(u32::MAX).finalize(),
+                k2.finalize(),
+                false,
+                true,
+            ),
+            Operator::GreaterEqualThan => (
+                k1.write(&value[..]).write(0u32).finalize(),
+                k2.finalize(),
+                true,
+                true,
             ),
+            Operator::Equal => (
+                k1.write(&value[..]).write(0u32).finalize(),
+                k2.write(&value[..]).write(u32::MAX).finalize(),
+                true,
+                true,
+            ),
+        };
+
+        let mut bm = RoaringBitmap::new();
+        let key_len = begin.len();
+        for row in self.snapshot.iterator_cf(
+            &self.cf_handle(CF_INDEXES)?,
+            IteratorMode::From(&begin, Direction::Forward),
         ) {
-            let (key, _) = result
-                .map_err(|err| Error::InternalError(format!("iterator_cf failed: {}", err)))?;
-            if !key.starts_with(match_prefix) {
+            let (key, _) = row?;
+            let key = key.as_ref();
+
+            if key > end.as_slice() || (!end_inclusive && key == end.as_slice()) {
                 break;
+            } else if (!begin_inclusive && key == begin.as_slice())
+                || (op == Operator::Equal && key.len() != key_len)
+            {
+                continue;
             }
-            let doc_id_pos = key.len() - std::mem::size_of::<u32>();
-            let value = key.get(FIELD_PREFIX_LEN..doc_id_pos).ok_or_else(|| {
-                Error::InternalError("Invalid key found in 'indexes' column family.".to_string())
-            })?;
-
-            match op {
-                Operator::LowerThan if value >= match_value => {
-                    if value == match_value {
-                        continue;
-                    } else {
-                        break;
-                    }
+
+            bm.insert(key.deserialize_be_u32(key.len() - std::mem::size_of::<u32>())?);
+        }
+
+        Ok(Some(bm))
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn sort_index(
+        &self,
+        account_id: u32,
+        collection: u8,
+        field: u8,
+        ascending: bool,
+        mut cb: impl FnMut(&[u8], u32) -> bool,
+    ) -> crate::Result<()> {
+        let begin = IndexKeyPrefix {
+            account_id,
+            collection,
+            field,
+        }
+        .serialize();
+        let end = IndexKeyPrefix {
+            account_id,
+            collection,
+            field: field + 1,
+        }
+        .serialize();
+        let prefix_len = begin.len();
+
+        for row in self.snapshot.iterator_cf(
+            &self.cf_handle(CF_INDEXES)?,
+            if ascending {
+                IteratorMode::From(&begin, Direction::Forward)
+            } else {
+                IteratorMode::From(&end, Direction::Reverse)
+            },
+        ) {
+            let (key, _) = row?;
+            let key = key.as_ref();
+
+            if key < begin.as_slice() || key >= end.as_slice() {
+                if ascending || key < begin.as_slice() {
+                    break;
+                } else {
+                    continue;
                 }
-                Operator::LowerEqualThan if value > match_value => break,
-                Operator::GreaterThan if value <= match_value => {
-                    if value == match_value {
-                        continue;
-                    } else {
-                        break;
+            }
+
+            let id_pos = key.len() - std::mem::size_of::<u32>();
+            if !cb(
+                key.get(prefix_len..id_pos).ok_or_else(|| {
+                    crate::Error::InternalError("Invalid key found in index".to_string())
+                })?,
+                key.deserialize_be_u32(id_pos)?,
+            ) {
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn iterate<T>(
+        &self,
+        mut acc: T,
+        begin: impl Key,
+        end: impl Key,
+        first: bool,
+        ascending: bool,
+        cb: impl Fn(&mut T, &[u8], &[u8]) -> crate::Result<bool> + Sync + Send + 'static,
+    ) -> crate::Result<T> {
+        let cf = self.cf_handle(subspace_to_cf(begin.subspace())?)?;
+        let begin = begin.serialize();
+        let end = end.serialize();
+
+        for row in self.snapshot.iterator_cf(
+            &cf,
+            if ascending {
+                IteratorMode::From(&begin, Direction::Forward)
+            } else {
+                IteratorMode::From(&end, Direction::Reverse)
+            },
+        ) {
+            let (key, value) = row?;
+            let key = key.as_ref();
+
+            if key < begin.as_slice() || key > end.as_slice() {
+                if ascending == (key > end.as_slice()) {
+                    break;
+                } else {
+                    continue;
+                }
+            }
+
+            if !cb(&mut acc, key, &value)? || first {
+                return Ok(acc);
+            }
+        }
+
+        Ok(acc)
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn get_last_change_id(
+        &self,
+        account_id: u32,
+        collection: u8,
+    ) -> crate::Result<Option<u64>> {
+        let begin = LogKey {
+            account_id,
+            collection,
+            change_id: 0,
+        }
+        .serialize();
+        let end = LogKey {
+            account_id,
+            collection,
+            change_id: u64::MAX,
+        }
+        .serialize();
+
+        for row in self.snapshot.iterator_cf(
+            &self.cf_handle(CF_LOGS)?,
+            IteratorMode::From(&end, Direction::Reverse),
+        ) {
+            let (key, _) = row?;
+            let key = key.as_ref();
+
+            if key >= end.as_slice() {
+                continue;
+            } else if key >= begin.as_slice() {
+                return key
+                    .deserialize_be_u64(key.len() - std::mem::size_of::<u64>())
+                    .map(Some);
+            } else {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn get_quota(&self, account_id: u32) -> crate::Result<i64> {
+        if let Some(bytes) = self
+            .snapshot
+            .get_cf(&self.cf_handle(CF_QUOTAS)?, account_id.to_be_bytes())?
+        {
+            Ok(i64::from_le_bytes(bytes[..].try_into().map_err(|_| {
+                crate::Error::InternalError(format!(
+                    "Invalid quota value for account {}",
+                    account_id
+                ))
+            })?))
+        } else {
+            Ok(0)
+        }
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn iterate_subspaces(
+        &self,
+        subspaces: &[u8],
+        account_id: Option<u32>,
+        mut cb: impl FnMut(u8, &[u8], &[u8]) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        // The last account has no upper bound
+        let begin = account_id.map(u32::to_be_bytes);
+        let end = account_id
+            .and_then(|account_id| account_id.checked_add(1))
+            .map(u32::to_be_bytes);
+
+        for &subspace in subspaces {
+            let cf = self.cf_handle(subspace_to_cf(subspace)?)?;
+            let mode = begin
+                .as_ref()
+                .map(|begin| IteratorMode::From(begin, Direction::Forward))
+                .unwrap_or(IteratorMode::Start);
+
+            for row in self.snapshot.iterator_cf(&cf, mode) {
+                let (key, value) = row?;
+                if end
+                    .as_ref()
+                    .map_or(false, |end| key.as_ref() >= end.as_slice())
+                {
+                    break;
+                }
+
+                if subspace == SUBSPACE_BITMAPS {
+                    let bm = RoaringBitmap::deserialize(&value)?;
+                    if !bm.is_empty() {
+                        let mut bytes = Vec::with_capacity(bm.serialized_size());
+                        bm.serialize_into(&mut bytes)?;
+                        cb(subspace, &key, &bytes)?;
                     }
+                } else {
+                    cb(subspace, &key, &value)?;
                 }
-                Operator::GreaterEqualThan if value < match_value => break,
-                Operator::Equal if value != match_value => break,
-                _ => {
-                    bm.insert(key.as_ref().deserialize_be_u32(doc_id_pos).ok_or_else(|| {
-                        Error::InternalError(
-                            "Invalid key found in 'indexes' column family.".to_string(),
-                        )
-                    })?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[maybe_async::maybe_async]
+    pub async fn refresh_if_old(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn cf_handle(&self, cf_name: &str) -> crate::Result<Arc<BoundColumnFamily<'_>>> {
+        self.db.cf_handle(cf_name).ok_or_else(|| {
+            crate::Error::InternalError(format!("Column family {:?} not found", cf_name))
+        })
+    }
+}
+
+impl Store {
+    #[maybe_async::maybe_async]
+    pub async fn read_transaction(&self) -> crate::Result<ReadTransaction<'_>> {
+        Ok(ReadTransaction::new(&self.db))
+    }
+
+    #[cfg(feature = "test_mode")]
+    pub async fn assert_is_empty(&self) {
+        use super::CF_VALUES;
+
+        // Purge bitmaps
+        self.purge_bitmaps().await.unwrap();
+
+        let mut has_errors = false;
+        for cf_name in [CF_VALUES, CF_INDEXES, CF_BITMAPS, CF_QUOTAS] {
+            let cf = self.db.cf_handle(cf_name).unwrap();
+            for row in self.db.iterator_cf(&cf, IteratorMode::Start) {
+                let (key, value) = row.unwrap();
+
+                match cf_name {
+                    CF_VALUES | CF_BITMAPS if key[0..4] == u32::MAX.to_be_bytes() => (),
+                    CF_INDEXES => {
+                        eprintln!(
+                            "Table index is not empty, account {}, collection {}, document {}, property {}, value {:?}: {:?}",
+                            u32::from_be_bytes(key[0..4].try_into().unwrap()),
+                            key[4],
+                            u32::from_be_bytes(key[key.len()-4..].try_into().unwrap()),
+                            key[5],
+                            String::from_utf8_lossy(&key[6..key.len()-4]),
+                            key
+                        );
+                        has_errors = true;
+                    }
+                    CF_QUOTAS => {
+                        let value = i64::from_le_bytes(value[..].try_into().unwrap());
+                        if value != 0 {
+                            eprintln!(
+                                "Table quota is not empty, account {}, quota: {}",
+                                u32::from_be_bytes(key[0..4].try_into().unwrap()),
+                                value,
+                            );
+                            has_errors = true;
+                        }
+                    }
+                    _ => {
+                        eprintln!("Table {cf_name:?} is not empty: {key:?} {value:?}");
+                        has_errors = true;
+                    }
                 }
             }
         }
 
-        Ok(Some(bm))
+        // Delete logs
+        let cf = self.db.cf_handle(CF_LOGS).unwrap();
+        for row in self.db.iterator_cf(&cf, IteratorMode::Start) {
+            let (key, _) = row.unwrap();
+            self.db.delete_cf(&cf, key).unwrap();
+        }
+
+        if has_errors {
+            panic!("Database is not empty");
+        }
+
+        self.id_assigner.lock().clear();
     }
 }
@@ -30,7 +30,8 @@ use tokio::sync::oneshot;
 use utils::{config::Config, UnwrapFailure};
 
 use crate::{
-    blob::BlobStore, Store, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_VALUES,
+    blob::BlobStore, ReadTransaction, Store, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_LOGS,
+    SUBSPACE_VALUES,
 };
 
 use super::pool::SqliteConnectionManager;
@@ -138,6 +139,15 @@ impl Store {
         Ok(())
     }
 
+    pub async fn spawn_read<U, V>(&self, f: U) -> crate::Result<V>
+    where
+        U: FnOnce(&mut ReadTransaction<'_>) -> crate::Result<V> + Send + 'static,
+        V: Sync + Send + 'static,
+    {
+        let mut trx = self.read_transaction()?;
+        self.spawn_worker(move || f(&mut trx)).await
+    }
+
     pub async fn spawn_worker<U, V>(&self, f: U) -> crate::Result<V>
     where
         U: FnOnce() -> crate::Result<V> + Send + 'static,
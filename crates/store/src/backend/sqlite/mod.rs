@@ -21,7 +21,6 @@
  * for more details.
 */
 
-pub mod id_assign;
 pub mod main;
 pub mod pool;
 pub mod purge;
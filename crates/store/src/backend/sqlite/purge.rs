@@ -63,9 +63,11 @@ impl Store {
             let from_key = KeySerializer::new(std::mem::size_of::<u32>())
                 .write(account_id)
                 .finalize();
-            let to_key = KeySerializer::new(std::mem::size_of::<u32>())
-                .write(account_id + 1)
-                .finalize();
+            let to_key = account_id.checked_add(1).map(|account_id| {
+                KeySerializer::new(std::mem::size_of::<u32>())
+                    .write(account_id)
+                    .finalize()
+            });
 
             for (table, i) in [
                 (SUBSPACE_BITMAPS, 'z'),
@@ -73,13 +75,23 @@ impl Store {
                 (SUBSPACE_LOGS, 'k'),
                 (SUBSPACE_INDEXES, 'k'),
             ] {
-                conn.prepare_cached(&format!(
-                    "DELETE FROM {} WHERE {} >= ? AND {} < ?",
-                    char::from(table),
-                    i,
-                    i
-                ))?
-                .execute([&from_key, &to_key])?;
+                // The last account has no upper bound
+                if let Some(to_key) = &to_key {
+                    conn.prepare_cached(&format!(
+                        "DELETE FROM {} WHERE {} >= ? AND {} < ?",
+                        char::from(table),
+                        i,
+                        i
+                    ))?
+                    .execute([&from_key, to_key])?;
+                } else {
+                    conn.prepare_cached(&format!(
+                        "DELETE FROM {} WHERE {} >= ?",
+                        char::from(table),
+                        i
+                    ))?
+                    .execute([&from_key])?;
+                }
             }
             conn.prepare_cached("DELETE FROM q WHERE k = ?")?
                 .execute([account_id as i64])?;
@@ -30,7 +30,7 @@ use crate::{
     query::Operator,
     write::key::{DeserializeBigEndian, KeySerializer},
     BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, ReadTransaction, Serialize,
-    Store,
+    Store, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_QUOTAS,
 };
 
 use super::{BITS_PER_BLOCK, WORDS_PER_BLOCK, WORD_SIZE_BITS};
@@ -74,27 +74,12 @@ impl ReadTransaction<'_> {
                 let block_num = key.deserialize_be_u32(key.len() - std::mem::size_of::<u32>())?;
 
                 for word_num in 0..WORDS_PER_BLOCK {
-                    match row.get::<_, i64>((word_num + 1) as usize)? as u64 {
-                        0 => (),
-                        u64::MAX => {
-                            bm.insert_range(
-                                block_num * BITS_PER_BLOCK + word_num * WORD_SIZE_BITS
-                                    ..(block_num * BITS_PER_BLOCK + word_num * WORD_SIZE_BITS)
-                                        + WORD_SIZE_BITS,
-                            );
-                        }
-                        mut word => {
-                            while word != 0 {
-                                let trailing_zeros = word.trailing_zeros();
-                                bm.insert(
-                                    block_num * BITS_PER_BLOCK
-                                        + word_num * WORD_SIZE_BITS
-                                        + trailing_zeros,
-                                );
-                                word ^= 1 << trailing_zeros;
-                            }
-                        }
-                    }
+                    deserialize_word(
+                        bm,
+                        block_num,
+                        word_num,
+                        row.get::<_, i64>((word_num + 1) as usize)? as u64,
+                    );
                 }
             }
         }
@@ -347,6 +332,114 @@ impl ReadTransaction<'_> {
         }
     }
 
+    #[maybe_async::maybe_async]
+    pub(crate) async fn iterate_subspaces(
+        &self,
+        subspaces: &[u8],
+        account_id: Option<u32>,
+        mut cb: impl FnMut(u8, &[u8], &[u8]) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        // All subspaces are read from the same snapshot
+        let trx = self.conn.unchecked_transaction()?;
+        for &subspace in subspaces {
+            self.iterate_subspace(subspace, account_id, |key, value| cb(subspace, key, value))
+                .await?;
+        }
+        trx.commit().map_err(Into::into)
+    }
+
+    #[maybe_async::maybe_async]
+    async fn iterate_subspace(
+        &self,
+        subspace: u8,
+        account_id: Option<u32>,
+        mut cb: impl FnMut(&[u8], &[u8]) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        // Keys are prefixed by the account id, restrict the scan to its range if requested
+        let range = account_id
+            .map(|account_id| {
+                [Some(account_id), account_id.checked_add(1)]
+                    .into_iter()
+                    .flatten()
+                    .map(|account_id| account_id.to_be_bytes().to_vec())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let filter = |column: char| match range.len() {
+            2 => format!(" WHERE {column} >= ? AND {column} < ?"),
+            // The last account has no upper bound
+            1 => format!(" WHERE {column} >= ?"),
+            _ => String::new(),
+        };
+
+        match subspace {
+            SUBSPACE_BITMAPS => {
+                let mut query = self.conn.prepare_cached(&format!(
+                    "SELECT z, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p FROM b{}",
+                    filter('z')
+                ))?;
+                let mut rows = query.query(rusqlite::params_from_iter(range.iter()))?;
+                while let Some(row) = rows.next()? {
+                    let key = row.get_ref(0)?.as_bytes()?;
+                    let block_num =
+                        key.deserialize_be_u32(key.len() - std::mem::size_of::<u32>())?;
+                    let mut bm = RoaringBitmap::new();
+                    for word_num in 0..WORDS_PER_BLOCK {
+                        deserialize_word(
+                            &mut bm,
+                            block_num,
+                            word_num,
+                            row.get::<_, i64>((word_num + 1) as usize)? as u64,
+                        );
+                    }
+                    if !bm.is_empty() {
+                        let mut bytes = Vec::with_capacity(bm.serialized_size());
+                        bm.serialize_into(&mut bytes)?;
+                        cb(key, &bytes)?;
+                    }
+                }
+            }
+            SUBSPACE_INDEXES => {
+                let mut query = self
+                    .conn
+                    .prepare_cached(&format!("SELECT k FROM i{}", filter('k')))?;
+                let mut rows = query.query(rusqlite::params_from_iter(range.iter()))?;
+                while let Some(row) = rows.next()? {
+                    cb(row.get_ref(0)?.as_bytes()?, &[])?;
+                }
+            }
+            SUBSPACE_QUOTAS => {
+                let mut query = self.conn.prepare_cached(if account_id.is_some() {
+                    "SELECT k, v FROM q WHERE k = ?"
+                } else {
+                    "SELECT k, v FROM q"
+                })?;
+                let mut rows = query.query(rusqlite::params_from_iter(
+                    account_id.map(|account_id| account_id as i64),
+                ))?;
+                while let Some(row) = rows.next()? {
+                    cb(
+                        &(row.get::<_, i64>(0)? as u32).to_be_bytes(),
+                        &row.get::<_, i64>(1)?.to_le_bytes(),
+                    )?;
+                }
+            }
+            _ => {
+                let mut query = self.conn.prepare_cached(&format!(
+                    "SELECT k, v FROM {}{}",
+                    char::from(subspace),
+                    filter('k')
+                ))?;
+                let mut rows = query.query(rusqlite::params_from_iter(range.iter()))?;
+                while let Some(row) = rows.next()? {
+                    cb(row.get_ref(0)?.as_bytes()?, row.get_ref(1)?.as_bytes()?)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[maybe_async::maybe_async]
     pub async fn refresh_if_old(&mut self) -> crate::Result<()> {
         Ok(())
@@ -450,3 +543,23 @@ impl Store {
         self.id_assigner.lock().clear();
     }
 }
+
+#[inline(always)]
+fn deserialize_word(bm: &mut RoaringBitmap, block_num: u32, word_num: u32, word: u64) {
+    match word {
+        0 => (),
+        u64::MAX => {
+            bm.insert_range(
+                block_num * BITS_PER_BLOCK + word_num * WORD_SIZE_BITS
+                    ..(block_num * BITS_PER_BLOCK + word_num * WORD_SIZE_BITS) + WORD_SIZE_BITS,
+            );
+        }
+        mut word => {
+            while word != 0 {
+                let trailing_zeros = word.trailing_zeros();
+                bm.insert(block_num * BITS_PER_BLOCK + word_num * WORD_SIZE_BITS + trailing_zeros);
+                word ^= 1 << trailing_zeros;
+            }
+        }
+    }
+}
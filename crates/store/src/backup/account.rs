@@ -0,0 +1,70 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::path::Path;
+
+use super::{export::Exporter, ArchiveReader, ArchiveWriter, FAMILY_ACCOUNT, FAMILY_PRINCIPAL};
+use crate::Store;
+
+impl Store {
+    /// Writes all the data of an account, including its blobs, to an archive that
+    /// can be imported into any account with [`Store::import_account`]. Principals
+    /// are the ids and names of the accounts referenced by the account's ACLs.
+    pub async fn export_account(
+        &self,
+        account_id: u32,
+        path: impl AsRef<Path>,
+        principals: &[(u32, String)],
+    ) -> crate::Result<()> {
+        if account_id == u32::MAX {
+            return Err(crate::Error::InternalError(
+                "Invalid account id.".to_string(),
+            ));
+        }
+
+        let mut writer = ArchiveWriter::create(path)?;
+        writer.write(FAMILY_ACCOUNT, &account_id.to_be_bytes(), &[])?;
+        for (principal_id, name) in principals {
+            writer.write(
+                FAMILY_PRINCIPAL,
+                &principal_id.to_be_bytes(),
+                name.as_bytes(),
+            )?;
+        }
+
+        self.export_archive(Exporter::new(writer, account_id.into()))
+            .await
+    }
+
+    /// Imports an archive created with [`Store::export_account`] into an empty account.
+    /// Document ids are preserved, returns the principals listed in the archive so
+    /// ACLs can be mapped to the local accounts.
+    pub async fn import_account(
+        &self,
+        account_id: u32,
+        path: impl AsRef<Path>,
+    ) -> crate::Result<Vec<(u32, String)>> {
+        self.import_archive(ArchiveReader::open(path)?, account_id.into())
+            .await
+    }
+}
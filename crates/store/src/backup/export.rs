@@ -0,0 +1,140 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::path::Path;
+
+use ahash::AHashMap;
+
+use crate::{
+    write::key::DeserializeBigEndian, Store, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_VALUES,
+};
+
+use super::{serialize_blob_kind, ArchiveWriter, FAMILY_BLOB, FAMILY_CHANGE_ID, SUBSPACES};
+
+pub(super) struct Exporter {
+    pub writer: ArchiveWriter,
+    pub account_id: Option<u32>,
+    change_ids: AHashMap<u32, u64>,
+}
+
+impl Store {
+    /// Writes all the data in the store, including linked blobs, to a backend
+    /// independent archive that can be later imported with [`Store::restore`].
+    pub async fn backup(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        self.export_archive(Exporter::new(ArchiveWriter::create(path)?, None))
+            .await
+    }
+
+    pub(super) async fn export_archive(&self, exporter: Exporter) -> crate::Result<()> {
+        let mut exporter = self.backup_subspaces(exporter).await?;
+
+        // Change ids are backend specific, store the last id used by each account
+        for (account_id, change_id) in &exporter.change_ids {
+            exporter.writer.write(
+                FAMILY_CHANGE_ID,
+                &account_id.to_be_bytes(),
+                &change_id.to_be_bytes(),
+            )?;
+        }
+
+        // Write blobs
+        for kind in self.list_blobs(exporter.account_id).await? {
+            if let (Some(key), Some(blob)) = (
+                serialize_blob_kind(&kind),
+                self.get_blob(&kind, 0..u32::MAX).await?,
+            ) {
+                exporter.writer.write(FAMILY_BLOB, &key, &blob)?;
+            }
+        }
+
+        exporter.writer.finish()
+    }
+
+    async fn backup_subspaces(&self, mut exporter: Exporter) -> crate::Result<Exporter> {
+        let account_id = exporter.account_id;
+
+        #[cfg(not(feature = "is_sync"))]
+        {
+            self.read_transaction()
+                .await?
+                .iterate_subspaces(SUBSPACES, account_id, |subspace, key, value| {
+                    exporter.export(subspace, key, value)
+                })
+                .await?;
+            Ok(exporter)
+        }
+
+        #[cfg(feature = "is_sync")]
+        {
+            self.spawn_read(move |trx| {
+                trx.iterate_subspaces(SUBSPACES, account_id, |subspace, key, value| {
+                    exporter.export(subspace, key, value)
+                })?;
+                Ok(exporter)
+            })
+            .await
+        }
+    }
+}
+
+impl Exporter {
+    pub fn new(writer: ArchiveWriter, account_id: Option<u32>) -> Self {
+        Self {
+            writer,
+            account_id,
+            change_ids: AHashMap::new(),
+        }
+    }
+
+    fn export(&mut self, subspace: u8, key: &[u8], value: &[u8]) -> crate::Result<()> {
+        match subspace {
+            SUBSPACE_VALUES => {
+                // Skip ACLs granted to the exported account by other accounts
+                if self.account_id.is_some()
+                    && key.get(std::mem::size_of::<u32>()) == Some(&u8::MAX)
+                {
+                    return Ok(());
+                }
+            }
+            SUBSPACE_INDEXES => {
+                // Skip document id reservations
+                if key.len() == std::mem::size_of::<u32>() * 2 + 2
+                    && key[std::mem::size_of::<u32>() + 1] == u8::MAX
+                {
+                    return Ok(());
+                }
+            }
+            SUBSPACE_LOGS => {
+                let account_id = key.deserialize_be_u32(0)?;
+                let change_id = key.deserialize_be_u64(key.len() - std::mem::size_of::<u64>())?;
+                let last_change_id = self.change_ids.entry(account_id).or_insert(change_id);
+                if change_id > *last_change_id {
+                    *last_change_id = change_id;
+                }
+            }
+            _ => (),
+        }
+
+        self.writer.write(subspace, key, value)
+    }
+}
@@ -0,0 +1,259 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::path::Path;
+
+use roaring::RoaringBitmap;
+
+use crate::{
+    write::{key::DeserializeBigEndian, BatchBuilder, Operation, ValueClass},
+    BlobKind, Store, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_LOGS, SUBSPACE_QUOTAS,
+    SUBSPACE_VALUES,
+};
+
+use super::{
+    deserialize_blob_kind, ArchiveReader, FAMILY_ACCOUNT, FAMILY_BLOB, FAMILY_CHANGE_ID,
+    FAMILY_PRINCIPAL, SUBSPACES,
+};
+
+const MAX_BATCH_OPS: usize = 1000;
+const MAX_BATCH_SIZE: usize = 1024 * 1024;
+
+impl Store {
+    /// Imports an archive created with [`Store::backup`]. The archive can be restored
+    /// into any backend, stores that already contain data are rejected.
+    pub async fn restore(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        self.import_archive(ArchiveReader::open(path)?, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Imports an archive, replacing the account id of every record with the
+    /// provided one when set. Returns the principals listed in the archive.
+    pub(super) async fn import_archive(
+        &self,
+        reader: ArchiveReader,
+        account_id: Option<u32>,
+    ) -> crate::Result<Vec<(u32, String)>> {
+        self.assert_is_empty_for_import(account_id).await?;
+
+        let principals = self.import_records(reader, account_id).await;
+
+        // Document and change id assigners have to be rebuilt from the imported data
+        #[cfg(any(feature = "rocks", feature = "sqlite"))]
+        self.id_assigner.lock().clear();
+
+        principals
+    }
+
+    async fn import_records(
+        &self,
+        mut reader: ArchiveReader,
+        account_id: Option<u32>,
+    ) -> crate::Result<Vec<(u32, String)>> {
+        let mut batch = BatchBuilder::new();
+        let mut batch_size = 0;
+        let mut principals = Vec::new();
+        let mut is_first = true;
+
+        while let Some((family, mut key, value)) = reader.next_record()? {
+            // Account archives start with the id of the exported account
+            if is_first && account_id.is_some() != (family == FAMILY_ACCOUNT) {
+                return Err(crate::Error::InternalError(
+                    if account_id.is_some() {
+                        "Archive does not contain an account export."
+                    } else {
+                        "Archive contains an account export, it can only be imported into an account."
+                    }
+                    .to_string(),
+                ));
+            }
+            is_first = false;
+
+            let min_key_len = match family {
+                SUBSPACE_INDEXES => (std::mem::size_of::<u32>() * 2) + 2,
+                SUBSPACE_BITMAPS => (std::mem::size_of::<u32>() * 2) + 3,
+                SUBSPACE_LOGS => std::mem::size_of::<u32>() + std::mem::size_of::<u64>() + 1,
+                _ => std::mem::size_of::<u32>(),
+            };
+            if key.len() < min_key_len {
+                return Err(crate::Error::InternalError(format!(
+                    "Corrupted key {key:?} in backup archive."
+                )));
+            }
+            batch_size += key.len() + value.len();
+            if let Some(account_id) = account_id {
+                if !matches!(family, FAMILY_PRINCIPAL | FAMILY_BLOB) {
+                    key[..std::mem::size_of::<u32>()].copy_from_slice(&account_id.to_be_bytes());
+                }
+            }
+
+            match family {
+                SUBSPACE_VALUES => {
+                    batch.op(Operation::Value {
+                        class: ValueClass::Custom { bytes: key },
+                        set: Some(value),
+                    });
+                }
+                SUBSPACE_INDEXES => {
+                    let document_id =
+                        key.deserialize_be_u32(key.len() - std::mem::size_of::<u32>())?;
+                    batch
+                        .with_account_id(key.deserialize_be_u32(0)?)
+                        .with_collection(key[4])
+                        .update_document(document_id)
+                        .op(Operation::Index {
+                            field: key[5],
+                            key: key[6..key.len() - std::mem::size_of::<u32>()].to_vec(),
+                            set: true,
+                        });
+                }
+                SUBSPACE_BITMAPS => {
+                    let bitmap = RoaringBitmap::deserialize_from(&value[..])?;
+                    let bitmap_key = key[7..key.len() - std::mem::size_of::<u32>()].to_vec();
+                    batch
+                        .with_account_id(key.deserialize_be_u32(0)?)
+                        .with_collection(key[4]);
+                    for document_id in bitmap {
+                        batch.update_document(document_id).op(Operation::Bitmap {
+                            family: key[5],
+                            field: key[6],
+                            key: bitmap_key.clone(),
+                            set: true,
+                        });
+                    }
+                }
+                SUBSPACE_LOGS => {
+                    batch
+                        .with_account_id(key.deserialize_be_u32(0)?)
+                        .op(Operation::Log {
+                            change_id: key.deserialize_be_u64(5)?,
+                            collection: key[4],
+                            set: value,
+                        });
+                }
+                SUBSPACE_QUOTAS => {
+                    batch
+                        .with_account_id(key.deserialize_be_u32(0)?)
+                        .quota(i64::from_le_bytes(value.try_into().map_err(|_| {
+                            crate::Error::InternalError("Invalid quota value".to_string())
+                        })?));
+                }
+                FAMILY_CHANGE_ID => {
+                    self.restore_change_id(
+                        key.deserialize_be_u32(0)?,
+                        value.deserialize_be_u64(0)?,
+                    )
+                    .await?;
+                }
+                FAMILY_BLOB => {
+                    let mut kind = deserialize_blob_kind(&key)?;
+                    if let Some(account_id) = account_id {
+                        match &mut kind {
+                            BlobKind::Linked {
+                                account_id: blob_account_id,
+                                ..
+                            }
+                            | BlobKind::LinkedMaildir {
+                                account_id: blob_account_id,
+                                ..
+                            } => *blob_account_id = account_id,
+                            BlobKind::Temporary { .. } => (),
+                        }
+                    }
+                    self.put_blob(&kind, &value).await?;
+                }
+                FAMILY_ACCOUNT => (),
+                FAMILY_PRINCIPAL => {
+                    principals.push((
+                        key.deserialize_be_u32(0)?,
+                        String::from_utf8(value).map_err(|_| {
+                            crate::Error::InternalError("Invalid principal name".to_string())
+                        })?,
+                    ));
+                }
+                _ => {
+                    return Err(crate::Error::InternalError(format!(
+                        "Unknown record type {family} in backup archive."
+                    )));
+                }
+            }
+
+            if batch.ops.len() >= MAX_BATCH_OPS || batch_size >= MAX_BATCH_SIZE {
+                self.write(batch.build_batch()).await?;
+                batch_size = 0;
+            }
+        }
+
+        if !batch.is_empty() {
+            self.write(batch.build()).await?;
+        }
+
+        Ok(principals)
+    }
+    async fn assert_is_empty_for_import(&self, account_id: Option<u32>) -> crate::Result<()> {
+        let has_data = move |subspace: u8, key: &[u8], _: &[u8]| {
+            let is_data = match subspace {
+                // Server wide settings and ACLs granted by other accounts are not restored
+                SUBSPACE_VALUES => {
+                    key.get(..std::mem::size_of::<u32>()) != Some(&u32::MAX.to_be_bytes()[..])
+                        && (account_id.is_none()
+                            || key.get(std::mem::size_of::<u32>()) != Some(&u8::MAX))
+                }
+                // Document id reservations
+                SUBSPACE_INDEXES => {
+                    key.len() != std::mem::size_of::<u32>() * 2 + 2
+                        || key[std::mem::size_of::<u32>() + 1] != u8::MAX
+                }
+                _ => true,
+            };
+
+            if is_data {
+                Err(crate::Error::InternalError(
+                    if account_id.is_some() {
+                        "Archives can only be imported into an empty account."
+                    } else {
+                        "Archives can only be restored into an empty store."
+                    }
+                    .to_string(),
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
+        #[cfg(not(feature = "is_sync"))]
+        {
+            self.read_transaction()
+                .await?
+                .iterate_subspaces(SUBSPACES, account_id, has_data)
+                .await
+        }
+
+        #[cfg(feature = "is_sync")]
+        {
+            self.spawn_read(move |trx| trx.iterate_subspaces(SUBSPACES, account_id, has_data))
+                .await
+        }
+    }
+}
@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+pub mod account;
+pub mod export;
+pub mod import;
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use utils::codec::leb128::Leb128_;
+
+use crate::{
+    write::key::DeserializeBigEndian, BlobKind, SUBSPACE_BITMAPS, SUBSPACE_INDEXES, SUBSPACE_LOGS,
+    SUBSPACE_QUOTAS, SUBSPACE_VALUES,
+};
+
+/*
+
+ Archive format:
+
+   MAGIC VERSION (FAMILY LEB128(KEY_LEN) KEY LEB128(VALUE_LEN) VALUE)* FAMILY_END
+
+ Keys and values are stored exactly as returned by the backend without the
+ subspace prefix, with the following exceptions:
+
+   - Bitmaps: the value contains the serialized RoaringBitmap of the block.
+   - Quotas: the key is the big-endian account id, the value a little-endian i64.
+   - Change ids: the key is the big-endian account id, the value the big-endian
+     last change id.
+   - Blobs: the key is the serialized BlobKind, the value the blob contents.
+
+ Account archives start with an account record (big-endian account id, empty
+ value) and list the principals referenced by the account's ACLs as principal
+ records (big-endian account id, UTF-8 name).
+
+*/
+
+const MAGIC: &[u8] = b"STWBAK";
+const VERSION: u8 = 1;
+
+pub(crate) const FAMILY_END: u8 = 0;
+pub(crate) const FAMILY_CHANGE_ID: u8 = b'c';
+pub(crate) const FAMILY_BLOB: u8 = b'B';
+pub(crate) const FAMILY_ACCOUNT: u8 = b'A';
+pub(crate) const FAMILY_PRINCIPAL: u8 = b'P';
+
+pub(crate) const SUBSPACES: &[u8] = &[
+    SUBSPACE_VALUES,
+    SUBSPACE_INDEXES,
+    SUBSPACE_BITMAPS,
+    SUBSPACE_LOGS,
+    SUBSPACE_QUOTAS,
+];
+
+const BLOB_LINKED: u8 = 0;
+const BLOB_LINKED_MAILDIR: u8 = 1;
+
+pub(crate) struct ArchiveWriter {
+    file: BufWriter<File>,
+}
+
+pub(crate) struct ArchiveReader {
+    file: BufReader<File>,
+}
+
+impl ArchiveWriter {
+    pub fn create(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION])?;
+        Ok(Self { file })
+    }
+
+    pub fn write(&mut self, family: u8, key: &[u8], value: &[u8]) -> crate::Result<()> {
+        self.file.write_all(&[family])?;
+        key.len().to_leb128_writer(&mut self.file)?;
+        self.file.write_all(key)?;
+        value.len().to_leb128_writer(&mut self.file)?;
+        self.file.write_all(value)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> crate::Result<()> {
+        self.file.write_all(&[FAMILY_END])?;
+        self.file.flush().map_err(Into::into)
+    }
+}
+
+impl ArchiveReader {
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut header = [0u8; MAGIC.len() + 1];
+        file.read_exact(&mut header)?;
+        if !header.starts_with(MAGIC) {
+            Err(crate::Error::InternalError(
+                "File is not a backup archive.".to_string(),
+            ))
+        } else if header[MAGIC.len()] != VERSION {
+            Err(crate::Error::InternalError(format!(
+                "Unsupported backup archive version {}.",
+                header[MAGIC.len()]
+            )))
+        } else {
+            Ok(Self { file })
+        }
+    }
+
+    pub fn next_record(&mut self) -> crate::Result<Option<(u8, Vec<u8>, Vec<u8>)>> {
+        let mut family = [0u8; 1];
+        self.file.read_exact(&mut family)?;
+        if family[0] != FAMILY_END {
+            let key = self.read_bytes()?;
+            let value = self.read_bytes()?;
+            Ok(Some((family[0], key, value)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_bytes(&mut self) -> crate::Result<Vec<u8>> {
+        let len = usize::from_leb128_it((&mut self.file).bytes().map_while(Result::ok))
+            .ok_or_else(|| {
+                crate::Error::InternalError("Unexpected end of backup archive.".to_string())
+            })?;
+        let mut bytes = vec![0u8; len];
+        self.file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+pub(crate) fn serialize_blob_kind(kind: &BlobKind) -> Option<Vec<u8>> {
+    match kind {
+        BlobKind::Linked {
+            account_id,
+            collection,
+            document_id,
+        } => {
+            let mut bytes = Vec::with_capacity(10);
+            bytes.push(BLOB_LINKED);
+            bytes.extend_from_slice(&account_id.to_be_bytes());
+            bytes.push(*collection);
+            bytes.extend_from_slice(&document_id.to_be_bytes());
+            Some(bytes)
+        }
+        BlobKind::LinkedMaildir {
+            account_id,
+            document_id,
+        } => {
+            let mut bytes = Vec::with_capacity(9);
+            bytes.push(BLOB_LINKED_MAILDIR);
+            bytes.extend_from_slice(&account_id.to_be_bytes());
+            bytes.extend_from_slice(&document_id.to_be_bytes());
+            Some(bytes)
+        }
+        BlobKind::Temporary { .. } => None,
+    }
+}
+
+pub(crate) fn deserialize_blob_kind(bytes: &[u8]) -> crate::Result<BlobKind> {
+    match bytes.first() {
+        Some(&BLOB_LINKED) => Ok(BlobKind::Linked {
+            account_id: bytes.deserialize_be_u32(1)?,
+            collection: *bytes.get(5).ok_or_else(|| {
+                crate::Error::InternalError(format!("Corrupted blob key {bytes:?}"))
+            })?,
+            document_id: bytes.deserialize_be_u32(6)?,
+        }),
+        Some(&BLOB_LINKED_MAILDIR) => Ok(BlobKind::LinkedMaildir {
+            account_id: bytes.deserialize_be_u32(1)?,
+            document_id: bytes.deserialize_be_u32(5)?,
+        }),
+        _ => Err(crate::Error::InternalError(format!(
+            "Corrupted blob key {bytes:?}"
+        ))),
+    }
+}
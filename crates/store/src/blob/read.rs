@@ -21,7 +21,7 @@
  * for more details.
 */
 
-use std::{io::SeekFrom, ops::Range};
+use std::{io::SeekFrom, ops::Range, path::PathBuf};
 
 use tokio::{
     fs::{self, File},
@@ -98,4 +98,101 @@ impl Store {
             }
         }
     }
+
+    pub(crate) async fn list_blobs(&self, account_id: Option<u32>) -> crate::Result<Vec<BlobKind>> {
+        let mut blobs = Vec::new();
+        let filter = |id: &u32| account_id.map_or(true, |account_id| account_id == *id);
+
+        match &self.blob {
+            BlobStore::Local(base_path) => {
+                // Emails: {account_id}/Maildir/cur/{document_id}
+                for (account_id, mut path) in list_hex_dir(base_path.path_email.clone())
+                    .await?
+                    .into_iter()
+                    .filter(|(account_id, _)| filter(account_id))
+                {
+                    path.push("Maildir");
+                    path.push("cur");
+                    for (document_id, _) in list_hex_dir(path).await? {
+                        blobs.push(BlobKind::LinkedMaildir {
+                            account_id,
+                            document_id,
+                        });
+                    }
+                }
+
+                // Other blobs: {account_id}/{collection}/{document_id}
+                for (account_id, path) in list_hex_dir(base_path.path_other.clone())
+                    .await?
+                    .into_iter()
+                    .filter(|(account_id, _)| filter(account_id))
+                {
+                    for (collection, path) in list_hex_dir(path).await? {
+                        for (document_id, _) in list_hex_dir(path).await? {
+                            blobs.push(BlobKind::Linked {
+                                account_id,
+                                collection: collection as u8,
+                                document_id,
+                            });
+                        }
+                    }
+                }
+            }
+            BlobStore::Remote(bucket) => {
+                for object in bucket
+                    .list(String::new(), None)
+                    .await?
+                    .into_iter()
+                    .flat_map(|result| result.contents)
+                {
+                    let key = object.key.strip_prefix('/').unwrap_or(&object.key);
+                    if key.starts_with("tmp/") {
+                        continue;
+                    }
+                    let parts = key
+                        .split('/')
+                        .map(|part| u32::from_str_radix(part, 16).ok())
+                        .collect::<Option<Vec<_>>>();
+                    match parts.as_deref() {
+                        Some([account_id, ..]) if !filter(account_id) => (),
+                        Some([account_id, document_id]) => {
+                            blobs.push(BlobKind::LinkedMaildir {
+                                account_id: *account_id,
+                                document_id: *document_id,
+                            });
+                        }
+                        Some([account_id, collection, document_id]) => {
+                            blobs.push(BlobKind::Linked {
+                                account_id: *account_id,
+                                collection: *collection as u8,
+                                document_id: *document_id,
+                            });
+                        }
+                        _ => {
+                            tracing::debug!("Unexpected S3 object while listing: {}", object.key);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(blobs)
+    }
+}
+
+async fn list_hex_dir(path: PathBuf) -> crate::Result<Vec<(u32, PathBuf)>> {
+    let mut items = Vec::new();
+    if fs::metadata(&path).await.is_ok() {
+        let mut dir = fs::read_dir(&path).await?;
+        while let Some(item) = dir.next_entry().await? {
+            if let Some(id) = item
+                .file_name()
+                .to_str()
+                .and_then(|name| u32::from_str_radix(name, 16).ok())
+            {
+                items.push((id, item.path()));
+            }
+        }
+    }
+    Ok(items)
 }
@@ -152,4 +152,32 @@ impl ReadTransaction<'_> {
             Ok(Some(bitmaps))
         }
     }
+
+    /// Matches documents containing any of the terms in `text`, either exact or stemmed.
+    #[maybe_async::maybe_async]
+    pub(crate) async fn fts_query_fuzzy(
+        &mut self,
+        account_id: u32,
+        collection: u8,
+        field: u8,
+        text: &str,
+        language: Language,
+    ) -> crate::Result<Option<RoaringBitmap>> {
+        let mut bitmaps = RoaringBitmap::new();
+
+        for token in Tokenizer::new(text, language, MAX_TOKEN_LENGTH) {
+            if let Some(b) = self
+                .fts_query(account_id, collection, field, &token.word, language, false)
+                .await?
+            {
+                bitmaps |= b;
+            }
+        }
+
+        Ok(if !bitmaps.is_empty() {
+            Some(bitmaps)
+        } else {
+            None
+        })
+    }
 }
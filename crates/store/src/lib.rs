@@ -26,6 +26,7 @@ use std::fmt::Display;
 use blob::BlobStore;
 
 pub mod backend;
+pub mod backup;
 pub mod blob;
 pub mod fts;
 pub mod query;
@@ -39,7 +40,23 @@ pub use roaring;
 
 #[cfg(feature = "rocks")]
 pub struct Store {
-    db: rocksdb::OptimisticTransactionDB<rocksdb::MultiThreaded>,
+    db: std::sync::Arc<rocksdb::OptimisticTransactionDB<rocksdb::MultiThreaded>>,
+    id_assigner: std::sync::Arc<
+        parking_lot::Mutex<
+            lru_cache::LruCache<backend::id_assign::IdCacheKey, backend::id_assign::IdAssigner>,
+        >,
+    >,
+    worker_pool: rayon::ThreadPool,
+    blob: BlobStore,
+}
+
+#[cfg(feature = "rocks")]
+pub struct ReadTransaction<'x> {
+    db: &'x rocksdb::OptimisticTransactionDB<rocksdb::MultiThreaded>,
+    snapshot: rocksdb::SnapshotWithThreadMode<
+        'x,
+        rocksdb::OptimisticTransactionDB<rocksdb::MultiThreaded>,
+    >,
 }
 
 #[cfg(feature = "foundation")]
@@ -62,10 +79,7 @@ pub struct Store {
     conn_pool: r2d2::Pool<backend::sqlite::pool::SqliteConnectionManager>,
     id_assigner: std::sync::Arc<
         parking_lot::Mutex<
-            lru_cache::LruCache<
-                backend::sqlite::id_assign::IdCacheKey,
-                backend::sqlite::id_assign::IdAssigner,
-            >,
+            lru_cache::LruCache<backend::id_assign::IdCacheKey, backend::id_assign::IdAssigner>,
         >,
     >,
     worker_pool: rayon::ThreadPool,
@@ -78,6 +92,28 @@ pub struct ReadTransaction<'x> {
     _p: std::marker::PhantomData<&'x ()>,
 }
 
+#[cfg(feature = "postgres")]
+pub struct Store {
+    conn_pool: sqlx::PgPool,
+    blob: BlobStore,
+}
+
+#[cfg(feature = "postgres")]
+pub struct ReadTransaction<'x> {
+    conn_pool: &'x sqlx::PgPool,
+}
+
+#[cfg(feature = "mysql")]
+pub struct Store {
+    conn_pool: sqlx::MySqlPool,
+    blob: BlobStore,
+}
+
+#[cfg(feature = "mysql")]
+pub struct ReadTransaction<'x> {
+    conn_pool: &'x sqlx::MySqlPool,
+}
+
 #[cfg(not(feature = "backend"))]
 #[allow(dead_code)]
 pub struct Store {
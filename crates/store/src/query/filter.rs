@@ -80,6 +80,10 @@ impl ReadTransaction<'_> {
                         self.fts_query(account_id, collection, field, &text, language, false)
                             .await?
                     }
+                    TextMatch::Fuzzy(language) => {
+                        self.fts_query_fuzzy(account_id, collection, field, &text, language)
+                            .await?
+                    }
                     TextMatch::Tokenized => {
                         self.get_bitmaps_intersection(
                             SpaceTokenizer::new(&text, MAX_TOKEN_LENGTH)
@@ -172,8 +176,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let mut trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.filter(account_id, collection, filters))
+            self.spawn_read(move |trx| trx.filter(account_id, collection, filters))
                 .await
         }
     }
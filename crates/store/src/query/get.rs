@@ -37,8 +37,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.get_value(key)).await
+            self.spawn_read(move |trx| trx.get_value(key)).await
         }
     }
 
@@ -61,8 +60,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || {
+            self.spawn_read(move |trx| {
                 let mut results = Vec::with_capacity(key.len());
                 for key in key {
                     results.push(trx.get_value(key)?);
@@ -91,8 +89,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.get_last_change_id(account_id, collection))
+            self.spawn_read(move |trx| trx.get_last_change_id(account_id, collection))
                 .await
         }
     }
@@ -105,8 +102,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.get_quota(account_id)).await
+            self.spawn_read(move |trx| trx.get_quota(account_id)).await
         }
     }
 
@@ -121,8 +117,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.get_bitmap(key)).await
+            self.spawn_read(move |trx| trx.get_bitmap(key)).await
         }
     }
 
@@ -145,8 +140,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || trx.iterate(acc, begin, end, first, ascending, cb))
+            self.spawn_read(move |trx| trx.iterate(acc, begin, end, first, ascending, cb))
                 .await
         }
     }
@@ -179,8 +173,7 @@ impl Store {
 
         #[cfg(feature = "is_sync")]
         {
-            let trx = self.read_transaction()?;
-            self.spawn_worker(move || {
+            self.spawn_read(move |trx| {
                 trx.sort_index(
                     account_id,
                     collection,
@@ -26,6 +26,7 @@ pub mod get;
 pub mod log;
 pub mod sort;
 
+use ahash::AHashMap;
 use roaring::RoaringBitmap;
 
 use crate::{
@@ -69,14 +70,25 @@ pub enum Filter {
 pub enum TextMatch {
     Exact(Language),
     Stemmed(Language),
+    Fuzzy(Language),
     Tokenized,
     Raw,
 }
 
 #[derive(Debug)]
 pub enum Comparator {
-    Field { field: u8, ascending: bool },
-    DocumentSet { set: RoaringBitmap, ascending: bool },
+    Field {
+        field: u8,
+        ascending: bool,
+    },
+    DocumentSet {
+        set: RoaringBitmap,
+        ascending: bool,
+    },
+    DocumentScore {
+        scores: AHashMap<u32, u32>,
+        ascending: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -185,6 +197,18 @@ impl Filter {
         }
     }
 
+    pub fn has_fuzzy_text(
+        field: impl Into<u8>,
+        text: impl Into<String>,
+        language: Language,
+    ) -> Self {
+        Filter::HasText {
+            field: field.into(),
+            text: text.into(),
+            op: TextMatch::Fuzzy(language),
+        }
+    }
+
     pub fn has_raw_text(field: impl Into<u8>, text: impl Into<String>) -> Self {
         Filter::HasText {
             field: field.into(),
@@ -222,6 +246,10 @@ impl Comparator {
         Self::DocumentSet { set, ascending }
     }
 
+    pub fn score(scores: AHashMap<u32, u32>, ascending: bool) -> Self {
+        Self::DocumentScore { scores, ascending }
+    }
+
     pub fn ascending(field: impl Into<u8>) -> Self {
         Self::Field {
             field: field.into(),
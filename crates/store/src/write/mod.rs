@@ -46,12 +46,12 @@ pub struct Batch {
     pub ops: Vec<Operation>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BatchBuilder {
     pub ops: Vec<Operation>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Operation {
     AccountId {
         account_id: u32,
@@ -91,7 +91,7 @@ pub enum Operation {
     },
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ValueClass {
     Property { field: u8, family: u8 },
     Acl { grant_account_id: u32 },
@@ -31,17 +31,40 @@ pub enum DeliveryEvent {
         message: IngestMessage,
         result_tx: oneshot::Sender<Vec<DeliveryResult>>,
     },
+    Report(DeliveryReport),
     Stop,
 }
 
 #[derive(Debug)]
 pub struct IngestMessage {
     pub sender_address: String,
+    pub sender_authenticated: bool,
     pub recipients: Vec<String>,
     pub message_path: PathBuf,
     pub message_size: usize,
 }
 
+#[derive(Debug)]
+pub struct DeliveryReport {
+    pub queue_id: u64,
+    pub sender_address: String,
+    pub recipients: Vec<RecipientReport>,
+}
+
+#[derive(Debug)]
+pub struct RecipientReport {
+    pub address: String,
+    pub delivered: bool,
+    pub smtp_reply: String,
+}
+
+#[derive(Debug)]
+pub struct UrlFetchRequest {
+    pub url: String,
+    pub user: String,
+    pub result_tx: oneshot::Sender<Option<Vec<u8>>>,
+}
+
 #[derive(Debug, Clone)]
 pub enum DeliveryResult {
     Success,
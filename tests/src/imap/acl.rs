@@ -22,10 +22,16 @@
 */
 
 use imap_proto::ResponseType;
+use jmap::JMAP;
+use jmap_proto::types::collection::Collection;
 
 use super::{append::assert_append_message, AssertResult, ImapConnection, Type};
 
-pub async fn test(mut imap_john: &mut ImapConnection, _imap_check: &mut ImapConnection) {
+pub async fn test(
+    mut imap_john: &mut ImapConnection,
+    _imap_check: &mut ImapConnection,
+    jmap: &JMAP,
+) {
     // Connect to all test accounts
     let mut imap_jane = ImapConnection::connect(b"_w ").await;
     let mut imap_bill = ImapConnection::connect(b"_z ").await;
@@ -60,8 +66,18 @@ pub async fn test(mut imap_john: &mut ImapConnection, _imap_check: &mut ImapConn
         .assert_equals("* LISTRIGHTS \"INBOX\" \"jdoe@example.com\" r l ws i et k x p a");
 
     // Jane shares her Inbox to John, expect a Shared Folders item in John's list
+    // and a share notification in John's account
+    let john_id = jmap.get_account_id("jdoe@example.com").await.unwrap();
+    let share_notifications = || async {
+        jmap.get_document_ids(john_id, Collection::ShareNotification)
+            .await
+            .unwrap()
+            .map_or(0, |ids| ids.len())
+    };
+    let num_notifications = share_notifications().await;
     imap_jane.send("SETACL INBOX jdoe@example.com lr").await;
     imap_jane.assert_read(Type::Tagged, ResponseType::Ok).await;
+    assert_eq!(share_notifications().await, num_notifications + 1);
     imap_john.send("LIST \"\" \"*\"").await;
     imap_john
         .assert_read(Type::Tagged, ResponseType::Ok)
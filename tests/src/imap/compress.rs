@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::time::Duration;
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use mail_send::smtp::tls::build_tls_connector;
+use rustls::ServerName;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+pub async fn test() {
+    let mut stream = TcpStream::connect("127.0.0.1:9991").await.unwrap();
+    let mut deflater = Compress::new(Compression::default(), false);
+    let mut inflater = Decompress::new(false);
+
+    // Authenticate and enable compression
+    read_response(&mut stream, None, "* OK").await;
+    stream
+        .write_all(b"a AUTHENTICATE PLAIN {32+}\r\nAGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0\r\n")
+        .await
+        .unwrap();
+    assert_contains(read_response(&mut stream, None, "a ").await, "a OK");
+    stream.write_all(b"b COMPRESS DEFLATE\r\n").await.unwrap();
+    assert_contains(read_response(&mut stream, None, "b ").await, "b OK");
+
+    // All further traffic is compressed
+    for (command, expected) in [
+        ("c COMPRESS DEFLATE\r\n", "c NO [COMPRESSIONACTIVE]"),
+        ("d LIST \"\" \"*\"\r\n", "\"INBOX\""),
+        ("e NOOP\r\n", "e OK"),
+        ("f LOGOUT\r\n", "f OK"),
+    ] {
+        let mut bytes = Vec::with_capacity(command.len() + 1024);
+        deflater
+            .compress_vec(command.as_bytes(), &mut bytes, FlushCompress::Sync)
+            .unwrap();
+        stream.write_all(&bytes).await.unwrap();
+        assert_contains(
+            read_response(&mut stream, Some(&mut inflater), &command[..2]).await,
+            expected,
+        );
+    }
+
+    // Enable compression on a ManageSieve session
+    let mut stream = build_tls_connector(true)
+        .connect(
+            ServerName::try_from("imap.example.org").unwrap(),
+            TcpStream::connect("127.0.0.1:4190").await.unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut deflater = Compress::new(Compression::default(), false);
+    let mut inflater = Decompress::new(false);
+
+    assert_contains(
+        read_response(&mut stream, None, "OK").await,
+        "\"COMPRESS\" \"DEFLATE\"",
+    );
+    stream.write_all(b"COMPRESS \"DEFLATE\"\r\n").await.unwrap();
+    assert_contains(
+        read_response(&mut stream, None, "NO").await,
+        "Not authenticated",
+    );
+    stream
+        .write_all(b"AUTHENTICATE \"PLAIN\" \"AGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0\"\r\n")
+        .await
+        .unwrap();
+    read_response(&mut stream, None, "OK").await;
+    stream.write_all(b"COMPRESS \"DEFLATE\"\r\n").await.unwrap();
+    assert_contains(
+        read_response(&mut stream, None, "OK").await,
+        "DEFLATE active",
+    );
+
+    for (command, tag, expected) in [
+        ("COMPRESS \"DEFLATE\"\r\n", "NO", "already active"),
+        ("CAPABILITY\r\n", "OK", "IMPLEMENTATION"),
+        ("CHECKSCRIPT \"if true { keep; }\"\r\n", "OK", "OK"),
+        ("LOGOUT\r\n", "BYE", "BYE"),
+    ] {
+        let mut bytes = Vec::with_capacity(command.len() + 1024);
+        deflater
+            .compress_vec(command.as_bytes(), &mut bytes, FlushCompress::Sync)
+            .unwrap();
+        stream.write_all(&bytes).await.unwrap();
+        let response = read_response(&mut stream, Some(&mut inflater), tag).await;
+        if command.starts_with("CAPABILITY") {
+            assert!(
+                !response.contains("COMPRESS"),
+                "Unexpected COMPRESS capability: {:?}",
+                response
+            );
+        }
+        assert_contains(response, expected);
+    }
+}
+
+async fn read_response(
+    stream: &mut (impl AsyncRead + Unpin),
+    mut inflater: Option<&mut Decompress>,
+    tag: &str,
+) -> String {
+    let mut response = Vec::new();
+    let mut buf = vec![0; 4096];
+
+    loop {
+        let bytes_read = tokio::time::timeout(Duration::from_millis(1500), stream.read(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(bytes_read > 0, "Connection closed: {:?}", response);
+        if let Some(inflater) = &mut inflater {
+            let mut bytes = Vec::with_capacity(65536);
+            inflater
+                .decompress_vec(&buf[..bytes_read], &mut bytes, FlushDecompress::Sync)
+                .unwrap();
+            response.extend_from_slice(&bytes);
+        } else {
+            response.extend_from_slice(&buf[..bytes_read]);
+        }
+
+        let text = String::from_utf8(response.clone()).unwrap();
+        if text.lines().any(|line| line.starts_with(tag)) {
+            return text;
+        }
+    }
+}
+
+fn assert_contains(response: String, expected: &str) {
+    assert!(
+        response.contains(expected),
+        "Expected {:?} but got {:?}",
+        expected,
+        response
+    );
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use imap_proto::ResponseType;
+
+use super::{AssertResult, ImapConnection, Type};
+
+pub async fn test(imap: &mut ImapConnection, imap_check: &mut ImapConnection) {
+    // Set mailbox annotations
+    imap.send(
+        "SETMETADATA INBOX (/shared/comment \"Shared comment\" /private/comment \"My comment\")",
+    )
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    // Annotations should be visible from another session
+    imap_check
+        .send("GETMETADATA INBOX (/shared/comment /private/comment /shared/missing)")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\"/shared/comment\" \"Shared comment\"")
+        .assert_contains("\"/private/comment\" \"My comment\"")
+        .assert_contains("\"/shared/missing\" NIL");
+
+    // LIST return options
+    imap_check
+        .send("LIST \"\" INBOX RETURN (MYRIGHTS METADATA (/shared/comment) STATUS (APPENDLIMIT))")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* MYRIGHTS \"INBOX\" rliteswkxp")
+        .assert_contains("* METADATA \"INBOX\" (\"/shared/comment\" \"Shared comment\")")
+        .assert_equals("* STATUS \"INBOX\" (APPENDLIMIT 100000)");
+
+    // Shared mailboxes only return the rights granted by the owner
+    imap_check
+        .send("LIST \"\" \"Shared Folders/jane.smith@example.com/Inbox\" RETURN (MYRIGHTS)")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* MYRIGHTS \"Shared Folders/jane.smith@example.com/Inbox\" rl");
+
+    // Messages larger than the append limit are rejected
+    imap.send("STATUS INBOX (APPENDLIMIT)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* STATUS \"INBOX\" (APPENDLIMIT 100000)");
+    imap.send(&format!(
+        "APPEND INBOX {{100001+}}\r\n{}",
+        "a".repeat(100001)
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_contains("[TOOBIG]");
+
+    // Depth and size limits
+    imap.send("GETMETADATA (DEPTH infinity) INBOX /shared")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("/shared/comment")
+        .assert_count("/private/comment", 0);
+    imap.send("GETMETADATA (MAXSIZE 5) INBOX /shared/comment")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("[METADATA LONGENTRIES 14]")
+        .assert_count("Shared comment", 0);
+    imap.send(&format!(
+        "SETMETADATA INBOX (/private/large {{5000+}}\r\n{})",
+        "a".repeat(5000)
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_contains("[METADATA MAXSIZE 4096]");
+    imap.send("SETMETADATA INBOX (/private/a/b/c/d/e/f/g/h/i \"deep\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::No).await;
+
+    // Server annotations
+    imap.send("SETMETADATA \"\" (/shared/admin \"mailto:admin@example.org\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap_check.send("GETMETADATA \"\" /shared/admin").await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* METADATA \"\" (\"/shared/admin\" \"mailto:admin@example.org\")");
+
+    // Remove annotations
+    imap.send("SETMETADATA INBOX (/shared/comment NIL /private/comment NIL)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SETMETADATA \"\" (/shared/admin NIL)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap_check
+        .send("GETMETADATA (DEPTH infinity) INBOX /private")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_count("* METADATA", 0);
+
+    // Mailbox must exist
+    imap.send("GETMETADATA \"Does not exist\" /shared/comment")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::No).await;
+}
@@ -24,6 +24,7 @@
 pub mod acl;
 pub mod append;
 pub mod basic;
+pub mod compress;
 pub mod body_structure;
 pub mod condstore;
 pub mod copy_move;
@@ -31,15 +32,22 @@ pub mod fetch;
 pub mod idle;
 pub mod mailbox;
 pub mod managesieve;
+pub mod metadata;
+pub mod notify;
+pub mod replace;
 pub mod search;
 pub mod store;
 pub mod thread;
+pub mod urlauth;
 
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use ::managesieve::core::ManageSieveSessionManager;
 use directory::config::ConfigDirectory;
-use imap::core::{ImapSessionManager, IMAP};
+use imap::{
+    core::{ImapSessionManager, IMAP},
+    op::urlauth::spawn_url_resolver,
+};
 use imap_proto::ResponseType;
 use jmap::{api::JmapSessionManager, services::IPC_CHANNEL_BUFFER, JMAP};
 use smtp::core::SMTP;
@@ -73,6 +81,12 @@ protocol = "imap"
 max-connections = 81920
 tls.implicit = true
 
+[server.listener.jmap]
+bind = ["127.0.0.1:8898"]
+url = "https://127.0.0.1:8898"
+protocol = "jmap"
+max-connections = 81920
+
 [server.listener.sieve]
 bind = ["127.0.0.1:4190"]
 protocol = "managesieve"
@@ -159,6 +173,9 @@ ttl = "1m"
 files = 3
 size = 50000
 
+[jmap.email]
+max-size = 100000
+
 [jmap.rate-limit]
 account = "1000/1m"
 authentication = "100/2s"
@@ -238,7 +255,8 @@ async fn init_imap_tests(delete_if_exists: bool) -> IMAPTest {
     // Start JMAP and SMTP servers
     servers.bind(&config);
     let (delivery_tx, delivery_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx)
+    let (url_tx, url_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
+    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx, url_tx)
         .await
         .failed("Invalid configuration file");
     let jmap = JMAP::init(&config, &directory, delivery_rx, smtp.clone())
@@ -247,6 +265,7 @@ async fn init_imap_tests(delete_if_exists: bool) -> IMAPTest {
     let imap: Arc<IMAP> = IMAP::init(&config)
         .await
         .failed("Invalid configuration file");
+    spawn_url_resolver(jmap.clone(), url_rx);
     let shutdown_tx = servers.spawn(|server, shutdown_rx| {
         match &server.protocol {
             ServerProtocol::Jmap => {
@@ -350,7 +369,12 @@ pub async fn imap_tests() {
     thread::test(&mut imap, &mut imap_check).await;
     idle::test(&mut imap, &mut imap_check).await;
     condstore::test(&mut imap, &mut imap_check).await;
-    acl::test(&mut imap, &mut imap_check).await;
+    acl::test(&mut imap, &mut imap_check, &handle.jmap).await;
+    metadata::test(&mut imap, &mut imap_check).await;
+    notify::test(&mut imap, &mut imap_check).await;
+    urlauth::test(&mut imap, &mut imap_check).await;
+    replace::test(&mut imap, &mut imap_check, &handle.jmap).await;
+    compress::test().await;
 
     // Logout
     for imap in [&mut imap, &mut imap_check] {
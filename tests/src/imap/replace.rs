@@ -0,0 +1,228 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::time::Duration;
+
+use imap_proto::ResponseType;
+use jmap::JMAP;
+use jmap_proto::types::id::Id;
+use serde_json::json;
+
+use super::{append::assert_append_message, AssertResult, ImapConnection, Type};
+
+pub async fn test(imap: &mut ImapConnection, _imap_check: &mut ImapConnection, jmap: &JMAP) {
+    // Append a draft
+    imap.send("CREATE Drafts-Replace").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    assert_eq!(
+        assert_append_message(
+            imap,
+            "Drafts-Replace",
+            "From: jdoe@example.com\r\nSubject: Draft\r\n\r\nFirst version\r\n",
+            ResponseType::Ok,
+        )
+        .await
+        .into_append_uid(),
+        "1"
+    );
+
+    // Replace the draft
+    imap.send("SELECT Drafts-Replace").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    let message = "From: jdoe@example.com\r\nSubject: Draft\r\n\r\nSecond version\r\n";
+    imap.send(&format!(
+        "UID REPLACE 1 Drafts-Replace {{{}+}}\r\n{}",
+        message.len(),
+        message
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("[APPENDUID")
+        .assert_contains("* 1 EXPUNGE");
+
+    // Only the replacement should be present
+    imap.send("UID FETCH 1:* (UID SAVEDATE BODY.PEEK[TEXT])")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID 2")
+        .assert_contains("SAVEDATE \"")
+        .assert_contains("Second version")
+        .assert_count("FETCH (", 1);
+
+    // Search by save date
+    imap.send("UID SEARCH SAVEDATESUPPORTED SAVEDSINCE 1-Jan-2000")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* SEARCH 2");
+    imap.send("UID SEARCH SAVEDBEFORE 1-Jan-2000").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* SEARCH");
+
+    // Replacing a non-existent message should fail
+    imap.send(&format!(
+        "UID REPLACE 99 Drafts-Replace {{{}+}}\r\n{}",
+        message.len(),
+        message
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::No).await;
+
+    // Copy the draft, the copy has its own save date
+    imap.send("CREATE Drafts-Copy").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UID COPY 2 Drafts-Copy").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SELECT Drafts-Copy").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UID SEARCH SAVEDSINCE 1-Jan-2000").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* SEARCH 1");
+
+    // Replacing a message that belongs to other mailboxes only removes it from this one
+    let message = "From: jdoe@example.com\r\nSubject: Draft\r\n\r\nThird version\r\n";
+    imap.send(&format!(
+        "UID REPLACE 1 Drafts-Copy {{{}+}}\r\n{}",
+        message.len(),
+        message
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* 1 EXPUNGE");
+    imap.send("UID FETCH 1:* (UID BODY.PEEK[TEXT])").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID 2")
+        .assert_contains("Third version")
+        .assert_count("FETCH (", 1);
+
+    // The original mailbox keeps the message and its save date
+    imap.send("SELECT Drafts-Replace").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UID FETCH 1:* (UID SAVEDATE BODY.PEEK[TEXT])")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID 2")
+        .assert_contains("SAVEDATE \"")
+        .assert_contains("Second version")
+        .assert_count("FETCH (", 1);
+    imap.send("UID SEARCH SAVEDSINCE 1-Jan-2000").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("* SEARCH 2");
+
+    // Messages cannot be replaced in virtual mailboxes
+    let account_id = Id::from(jmap.get_account_id("jdoe@example.com").await.unwrap()).to_string();
+    let response = jmap_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": account_id,
+                "create": {
+                    "s1": {
+                        "name": "Saved Drafts",
+                        "filter": {"subject": "Draft"}
+                    }
+                }
+            }, "0"]
+        ]
+    }))
+    .await;
+    let search_id = response["methodResponses"][0][1]["created"]["s1"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Failed to create saved search: {response}"))
+        .to_string();
+    imap.send("LIST \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("Saved Drafts");
+    let message = "From: jdoe@example.com\r\nSubject: Draft\r\n\r\nFourth version\r\n";
+    for mailbox in ["All Mail", "Saved Drafts"] {
+        imap.send(&format!("SELECT \"{mailbox}\"")).await;
+        imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+        imap.send(&format!(
+            "REPLACE 1 Drafts-Replace {{{}+}}\r\n{}",
+            message.len(),
+            message
+        ))
+        .await;
+        imap.assert_read(Type::Tagged, ResponseType::No)
+            .await
+            .assert_contains("[CANNOT]");
+    }
+    imap.send("STATUS Drafts-Replace (MESSAGES)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("MESSAGES 1");
+    imap.send("UID SEARCH BODY \"Fourth version\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* SEARCH");
+
+    imap.send("UNSELECT").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    // Remove the saved search
+    let response = jmap_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": account_id,
+                "destroy": [search_id]
+            }, "0"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["destroyed"],
+        json!([search_id]),
+        "{response}"
+    );
+}
+
+async fn jmap_request(request: serde_json::Value) -> serde_json::Value {
+    serde_json::from_slice(
+        &reqwest::Client::builder()
+            .timeout(Duration::from_millis(1000))
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap_or_default()
+            .post("https://127.0.0.1:8898/jmap/")
+            .basic_auth("jdoe@example.com", Some("secret"))
+            .header("Content-Type", "application/json")
+            .body(request.to_string())
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+}
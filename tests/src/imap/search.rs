@@ -135,4 +135,76 @@ pub async fn test(imap: &mut ImapConnection, imap_check: &mut ImapConnection) {
     imap.assert_read(Type::Tagged, ResponseType::Ok)
         .await
         .assert_contains("COUNT 10 ALL 6,4:5,1,10,9,3,7:8,2");
+
+    // Multi-mailbox search
+    imap_check
+        .send("ESEARCH IN (mailboxes INBOX) FROM nathaniel")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("MAILBOX \"INBOX\" UIDVALIDITY ")
+        .assert_contains("UID ALL 1,4,6");
+
+    imap_check.send("ESEARCH RETURN (COUNT) ALL").await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("MAILBOX \"INBOX\"")
+        .assert_contains("UID COUNT 10");
+
+    imap_check
+        .send("ESEARCH IN (personal) RETURN (MIN) SUBJECT argentina")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("MAILBOX \"INBOX\"")
+        .assert_contains("UID MIN 3");
+
+    // Partial results
+    imap_check
+        .send("UID SEARCH RETURN (PARTIAL 1:3 COUNT) ALL")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID COUNT 10 PARTIAL (1:3 1:3)");
+    imap_check
+        .send("UID SEARCH RETURN (PARTIAL -1:-2) ALL")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID PARTIAL (-1:-2 9:10)");
+    imap_check
+        .send("UID SEARCH RETURN (PARTIAL 20:30) ALL")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID PARTIAL (20:30 NIL)");
+    imap_check.send("UID FETCH 1:* (UID) (PARTIAL -1:-2)").await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID 9")
+        .assert_contains("UID 10")
+        .assert_count("FETCH (", 2);
+
+    // Fuzzy search and relevancy
+    imap_check
+        .send("UID SEARCH RETURN (RELEVANCY ALL) FUZZY SUBJECT argentina")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("UID ALL 3 RELEVANCY (100)");
+    imap_check
+        .send("UID SORT (RELEVANCY) UTF-8 OR FROM nathaniel SUBJECT argentina")
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_equals("* SORT 3 1 4 6");
 }
@@ -0,0 +1,143 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use imap_proto::ResponseType;
+
+use super::{append::assert_append_message, AssertResult, ImapConnection, Type};
+
+pub async fn test(imap: &mut ImapConnection, imap_check: &mut ImapConnection) {
+    // Append a message to forward
+    imap.send("CREATE Forwarded").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    assert_eq!(
+        assert_append_message(
+            imap,
+            "Forwarded",
+            "From: jane@example.com\r\nSubject: Original\r\n\r\nOriginal text\r\n",
+            ResponseType::Ok,
+        )
+        .await
+        .into_append_uid(),
+        "1"
+    );
+
+    // Build a new message from text and URL parts
+    let header = "From: jdoe@example.com\r\nSubject: Fwd: Original\r\n\r\n";
+    imap.send(&format!(
+        "APPEND Forwarded CATENATE (TEXT {{{}+}}\r\n{} URL \"/Forwarded/;UID=1/;SECTION=TEXT\")",
+        header.len(),
+        header
+    ))
+    .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SELECT Forwarded").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UID FETCH 2 BODY.PEEK[]").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("Subject: Fwd: Original")
+        .assert_contains("Original text");
+
+    // Invalid URLs are rejected
+    imap.send("APPEND Forwarded CATENATE (URL \"/Forwarded/;UID=99\")")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::No)
+        .await
+        .assert_contains("[BADURL /Forwarded/;UID=99]");
+
+    // Generate an URLAUTH URL
+    let rump =
+        "imap://jdoe%40example.com@localhost/Forwarded/;UID=1;URLAUTH=user+jdoe%40example.com";
+    imap.send(&format!("GENURLAUTH \"{rump}\" INTERNAL")).await;
+    let url = imap
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .into_iter()
+        .find_map(|line| {
+            line.strip_prefix("* GENURLAUTH \"")
+                .and_then(|url| url.strip_suffix('"'))
+                .map(|url| url.to_string())
+        })
+        .unwrap();
+    assert!(url.starts_with(&format!("{rump}:internal:")), "{url}");
+
+    // URLs can only be generated for the user's own mailboxes
+    imap_check
+        .send("GENURLAUTH \"imap://jane@localhost/INBOX/;UID=1;URLAUTH=anonymous\" INTERNAL")
+        .await;
+    imap_check.assert_read(Type::Tagged, ResponseType::No).await;
+
+    // URLs without an authorization component are rejected
+    imap.send("GENURLAUTH \"imap://jdoe%40example.com@localhost/Forwarded/;UID=1\" INTERNAL")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Bad).await;
+
+    // Fetch the URL
+    imap_check
+        .send(&format!(
+            "URLFETCH \"{url}\" \"{rump}:internal:{}\"",
+            "0".repeat(64)
+        ))
+        .await;
+    imap_check
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("Original text")
+        .assert_contains(
+            ":internal:0000000000000000000000000000000000000000000000000000000000000000\" NIL",
+        );
+
+    // Submit URLs can't be fetched over IMAP
+    let submit_rump =
+        "imap://jdoe%40example.com@localhost/Forwarded/;UID=1;URLAUTH=submit+jdoe%40example.com";
+    imap.send(&format!("GENURLAUTH \"{submit_rump}\" INTERNAL"))
+        .await;
+    let submit_url = imap
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .into_iter()
+        .find_map(|line| {
+            line.strip_prefix("* GENURLAUTH \"")
+                .and_then(|url| url.strip_suffix('"'))
+                .map(|url| url.to_string())
+        })
+        .unwrap();
+    imap.send(&format!("URLFETCH \"{submit_url}\"")).await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\" NIL");
+
+    // Resetting the mailbox key invalidates the URL
+    imap.send("RESETKEY Forwarded").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send(&format!("URLFETCH \"{url}\"")).await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\" NIL")
+        .assert_count("Original text", 0);
+
+    imap.send("UNSELECT").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("DELETE Forwarded").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+}
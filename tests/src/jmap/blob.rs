@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::JMAP;
+use jmap_client::{client::Client, mailbox::Role};
+use jmap_proto::types::id::Id;
+use serde_json::json;
+
+use crate::jmap::{jmap_json_request, mailbox::destroy_all_mailboxes};
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Blob tests...");
+
+    let account_id = Id::new(1).to_string();
+
+    // Upload blobs built from text, base64 and ranges of other blobs
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:blob"],
+        "methodCalls": [
+            ["Blob/upload", {
+                "accountId": account_id,
+                "create": {
+                    "b1": {
+                        "data": [{"data:asText": "Hello, "}, {"data:asBase64": "d29ybGQh"}],
+                        "type": "text/plain"
+                    },
+                    "b2": {
+                        "data": [{"blobId": "#b1", "offset": 7, "length": 5}]
+                    },
+                    "b3": {
+                        "data": [{"data:asBase64": "//4="}]
+                    },
+                    "b4": {
+                        "data": [{"blobId": "#b1", "offset": 10, "length": 10}]
+                    },
+                    "b5": {
+                        "data": [{"blobId": "#b9"}]
+                    }
+                }
+            }, "0"]
+        ]
+    }))
+    .await;
+    let upload_response = &response["methodResponses"][0][1];
+    let created = &upload_response["created"];
+    assert_eq!(created["b1"]["size"], 13, "{response}");
+    assert_eq!(created["b1"]["type"], "text/plain");
+    assert_eq!(created["b2"]["size"], 5, "{response}");
+    assert_eq!(created["b3"]["size"], 2, "{response}");
+    assert_eq!(
+        upload_response["notCreated"]["b4"]["type"], "invalidProperties",
+        "{response}"
+    );
+    assert_eq!(
+        upload_response["notCreated"]["b5"]["type"], "invalidProperties",
+        "{response}"
+    );
+    let blob_1 = created["b1"]["id"].as_str().unwrap().to_string();
+    let blob_2 = created["b2"]["id"].as_str().unwrap().to_string();
+    let blob_3 = created["b3"]["id"].as_str().unwrap().to_string();
+
+    // Fetch full blobs and ranges
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:blob"],
+        "methodCalls": [
+            ["Blob/get", {
+                "accountId": account_id,
+                "ids": [blob_1, blob_2, blob_3]
+            }, "0"],
+            ["Blob/get", {
+                "accountId": account_id,
+                "ids": [blob_1, blob_2],
+                "properties": ["data:asText", "digest:sha", "size"],
+                "offset": 7,
+                "length": 100
+            }, "1"],
+            ["Blob/get", {
+                "accountId": account_id,
+                "ids": [blob_1],
+                "properties": ["data", "digest:sha-256"],
+                "length": 5
+            }, "2"],
+            ["Blob/get", {
+                "accountId": account_id,
+                "ids": [blob_3],
+                "properties": ["data:asText", "data:asBase64"]
+            }, "3"]
+        ]
+    }))
+    .await;
+    let list = &response["methodResponses"][0][1]["list"];
+    assert_eq!(list[0]["data:asText"], "Hello, world!", "{response}");
+    assert_eq!(list[0]["size"], 13);
+    assert_eq!(list[1]["data:asText"], "world");
+    assert_eq!(list[2]["data:asBase64"], "//4=");
+    assert_eq!(list[2]["size"], 2);
+
+    let list = &response["methodResponses"][1][1]["list"];
+    assert_eq!(list[0]["data:asText"], "world!", "{response}");
+    assert_eq!(list[0]["digest:sha"], "pnlMgxStausI7RSWYO4/77zaXmw=");
+    assert_eq!(list[0]["size"], 13);
+    assert_eq!(list[0]["isTruncated"], true);
+    assert_eq!(list[1]["data:asText"], "");
+    assert_eq!(list[1]["size"], 5);
+    assert_eq!(list[1]["isTruncated"], true);
+
+    let list = &response["methodResponses"][2][1]["list"];
+    assert_eq!(list[0]["data:asText"], "Hello", "{response}");
+    assert_eq!(
+        list[0]["digest:sha-256"],
+        "GF+NsyJx/iX1Yab8k4suJkMG7DBO2lGAB9F2SCY4GWk="
+    );
+    assert_eq!(list[0]["isTruncated"], serde_json::Value::Null);
+
+    let list = &response["methodResponses"][3][1]["list"];
+    assert_eq!(
+        list[0]["data:asText"],
+        serde_json::Value::Null,
+        "{response}"
+    );
+    assert_eq!(list[0]["isEncodingProblem"], true);
+    assert_eq!(list[0]["data:asBase64"], "//4=");
+
+    // Lookup the objects that reference a blob
+    let mailbox_id = client
+        .set_default_account_id(&account_id)
+        .mailbox_create("Blob Lookup", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let email = client
+        .email_import(
+            b"From: john@example.com\r\nSubject: Blob lookup\r\n\r\nTest.\r\n".to_vec(),
+            [&mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap();
+    let email_id = email.id().unwrap().to_string();
+    let email_blob_id = email.blob_id().unwrap().to_string();
+    let thread_id = email.thread_id().unwrap().to_string();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:blob"],
+        "methodCalls": [
+            ["Blob/lookup", {
+                "accountId": account_id,
+                "typeNames": ["Email", "Mailbox", "Thread"],
+                "ids": [email_blob_id, blob_1]
+            }, "0"]
+        ]
+    }))
+    .await;
+    let lookup_response = &response["methodResponses"][0][1];
+    assert_eq!(
+        lookup_response["list"],
+        json!([
+            {
+                "id": email_blob_id,
+                "matchedIds": {
+                    "Email": [email_id],
+                    "Mailbox": [mailbox_id],
+                    "Thread": [thread_id]
+                }
+            },
+            {
+                "id": blob_1,
+                "matchedIds": {
+                    "Email": [],
+                    "Mailbox": [],
+                    "Thread": []
+                }
+            }
+        ]),
+        "{response}"
+    );
+
+    // Destroyed emails are no longer found
+    client.email_destroy(&email_id).await.unwrap();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:blob"],
+        "methodCalls": [
+            ["Blob/lookup", {
+                "accountId": account_id,
+                "typeNames": ["Email"],
+                "ids": [email_blob_id]
+            }, "0"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notFound"],
+        json!([email_blob_id]),
+        "{response}"
+    );
+
+    destroy_all_mailboxes(client).await;
+    server.store.assert_is_empty().await;
+}
@@ -0,0 +1,546 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::{
+    calendar_event::{
+        ical::{jscalendar_to_ical, vevent_to_jscalendar, Component},
+        itip::ItipMessage,
+        organizer_address,
+        recurrence::{format_local_date_time, Recurrence},
+        JSCalendarObject,
+    },
+    JMAP,
+};
+use jmap_client::client::Client;
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
+use serde_json::json;
+use store::query::Filter;
+
+use crate::jmap::jmap_json_request;
+
+const UID: &str = "weekly-sync@remote.org";
+const EVENT: &str = concat!(
+    "DTSTART;TZID=Europe/Madrid:20230102T100000\r\n",
+    "DTEND;TZID=Europe/Madrid:20230102T103000\r\n",
+    "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4\r\n",
+    "EXDATE;TZID=Europe/Madrid:20230104T100000\r\n",
+    "SUMMARY:Weekly sync\r\n",
+    "ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jdoe@example.com\r\n",
+    "ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jane@remote.org\r\n",
+);
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Calendar tests...");
+    client.set_default_account_id(Id::new(1).to_string());
+
+    test_ical();
+    test_itip(&server).await;
+    test_set().await;
+
+    // Empty store
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:calendars"],
+        "methodCalls": [
+            ["Calendar/get", {
+                "accountId": Id::new(1).to_string(),
+                "ids": null,
+                "properties": ["id"]
+            }, "0"],
+            ["Calendar/set", {
+                "accountId": Id::new(1).to_string(),
+                "#destroy": {
+                    "resultOf": "0",
+                    "name": "Calendar/get",
+                    "path": "/list/*/id"
+                },
+                "onDestroyRemoveEvents": true
+            }, "1"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][1][1]["notDestroyed"],
+        serde_json::Value::Null,
+        "{response}"
+    );
+    server.store.assert_is_empty().await;
+}
+
+fn test_ical() {
+    // Parse an iMIP request
+    let itip = itip("REQUEST", "bill@remote.org", "bill@remote.org", EVENT);
+    assert_eq!(itip.calendar.method().as_deref(), Some("REQUEST"));
+    let event =
+        vevent_to_jscalendar(itip.calendar.events().next().unwrap(), &itip.calendar).unwrap();
+    assert_eq!(event.get(&Property::Uid).as_string(), Some(UID));
+    assert_eq!(event.get(&Property::Title).as_string(), Some("Weekly sync"));
+    assert_eq!(
+        event.get(&Property::Start).as_string(),
+        Some("2023-01-02T10:00:00")
+    );
+    assert_eq!(
+        event.get(&Property::TimeZone).as_string(),
+        Some("Europe/Madrid")
+    );
+    assert_eq!(event.get(&Property::Duration).as_string(), Some("PT30M"));
+    assert_eq!(
+        organizer_address(&event).as_deref(),
+        Some("bill@remote.org")
+    );
+    assert_eq!(
+        participation_status(&event, "jane@remote.org").as_deref(),
+        Some("needs-action")
+    );
+
+    // Expand recurrences, excluded instances are skipped but still count
+    let recurrence = Recurrence::from_event(&event, None).unwrap();
+    assert!(recurrence.is_recurrent());
+    assert!(!recurrence.is_infinite());
+    let instances = recurrence.instances(i64::MIN, i64::MAX, 100);
+    assert_eq!(
+        instances
+            .iter()
+            .map(|instance| format_local_date_time(&instance.recurrence_id))
+            .collect::<Vec<_>>(),
+        [
+            "2023-01-02T10:00:00",
+            "2023-01-09T10:00:00",
+            "2023-01-11T10:00:00"
+        ]
+    );
+    // Madrid is UTC+1 in winter
+    assert_eq!(instances[0].utc_start, 1672650000);
+    assert_eq!(instances[0].utc_end, 1672650000 + 1800);
+    assert_eq!(
+        recurrence.utc_range(100),
+        (1672650000, Some(instances[2].utc_end))
+    );
+    assert_eq!(
+        recurrence
+            .instances(1673222400, 1673308800, 100)
+            .into_iter()
+            .map(|instance| format_local_date_time(&instance.recurrence_id))
+            .collect::<Vec<_>>(),
+        ["2023-01-09T10:00:00"]
+    );
+
+    // Converting back to iCalendar preserves the event
+    let ical = jscalendar_to_ical(&event, "REQUEST", None, 0);
+    let calendar = Component::parse(&ical).unwrap();
+    assert_eq!(calendar.method().as_deref(), Some("REQUEST"));
+    let event_ = vevent_to_jscalendar(calendar.events().next().unwrap(), &calendar).unwrap();
+    for property in [
+        Property::Uid,
+        Property::Title,
+        Property::Start,
+        Property::TimeZone,
+        Property::Duration,
+        Property::RecurrenceRules,
+        Property::RecurrenceOverrides,
+        Property::ReplyTo,
+    ] {
+        assert_eq!(event_.get(&property), event.get(&property), "{property}");
+    }
+}
+
+async fn test_itip(server: &JMAP) {
+    // Messages from unauthenticated senders are ignored
+    assert_eq!(
+        server
+            .calendar_itip_ingest(
+                1,
+                &itip("REQUEST", "bill@remote.org", "bill@remote.org", EVENT),
+                "bill@remote.org",
+                false,
+            )
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(get_event(server).await.is_none());
+
+    // Invitations are only accepted from their organizer
+    assert_eq!(
+        server
+            .calendar_itip_ingest(
+                1,
+                &itip("REQUEST", "mallory@remote.org", "bill@remote.org", EVENT),
+                "mallory@remote.org",
+                true,
+            )
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(get_event(server).await.is_none());
+    assert!(server
+        .calendar_itip_ingest(
+            1,
+            &itip("REQUEST", "bill@remote.org", "bill@remote.org", EVENT),
+            "bill@remote.org",
+            true,
+        )
+        .await
+        .unwrap()
+        .is_some());
+    let event = get_event(server).await.unwrap();
+    assert_eq!(event.get(&Property::Title).as_string(), Some("Weekly sync"));
+
+    // Other senders cannot update or cancel the event by naming themselves organizer
+    for method in ["REQUEST", "CANCEL"] {
+        assert_eq!(
+            server
+                .calendar_itip_ingest(
+                    1,
+                    &itip(
+                        method,
+                        "mallory@remote.org",
+                        "mallory@remote.org",
+                        &format!("{EVENT}SEQUENCE:1\r\nSTATUS:CANCELLED\r\n")
+                    ),
+                    "mallory@remote.org",
+                    true,
+                )
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(get_event(server).await.unwrap(), event);
+    }
+
+    // Replies update the status of the sending participant only
+    assert!(server
+        .calendar_itip_ingest(
+            1,
+            &itip(
+                "REPLY",
+                "jane@remote.org",
+                "bill@remote.org",
+                &EVENT.replace(
+                    "PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jane",
+                    "PARTSTAT=ACCEPTED:mailto:jane"
+                )
+            ),
+            "jane@remote.org",
+            true,
+        )
+        .await
+        .unwrap()
+        .is_some());
+    let event = get_event(server).await.unwrap();
+    assert_eq!(
+        participation_status(&event, "jane@remote.org").as_deref(),
+        Some("accepted")
+    );
+    assert_eq!(
+        participation_status(&event, "jdoe@example.com").as_deref(),
+        Some("needs-action")
+    );
+
+    // Updates from the organizer are applied
+    assert!(server
+        .calendar_itip_ingest(
+            1,
+            &itip(
+                "REQUEST",
+                "bill@remote.org",
+                "bill@remote.org",
+                &format!(
+                    "{}SEQUENCE:1\r\n",
+                    EVENT.replace("Weekly sync", "Weekly sync (moved)")
+                )
+            ),
+            "bill@remote.org",
+            true,
+        )
+        .await
+        .unwrap()
+        .is_some());
+    let event = get_event(server).await.unwrap();
+    assert_eq!(
+        event.get(&Property::Title).as_string(),
+        Some("Weekly sync (moved)")
+    );
+    assert_eq!(event.get(&Property::Sequence).as_uint(), Some(1));
+
+    // Cancelled instances are excluded
+    assert!(server
+        .calendar_itip_ingest(
+            1,
+            &itip(
+                "CANCEL",
+                "bill@remote.org",
+                "bill@remote.org",
+                &format!(
+                    "{EVENT}SEQUENCE:1\r\nRECURRENCE-ID;TZID=Europe/Madrid:20230109T100000\r\n"
+                )
+            ),
+            "bill@remote.org",
+            true,
+        )
+        .await
+        .unwrap()
+        .is_some());
+    let event = get_event(server).await.unwrap();
+    assert_eq!(
+        Recurrence::from_event(&event, None)
+            .unwrap()
+            .instances(i64::MIN, i64::MAX, 100)
+            .into_iter()
+            .map(|instance| format_local_date_time(&instance.recurrence_id))
+            .collect::<Vec<_>>(),
+        ["2023-01-02T10:00:00", "2023-01-11T10:00:00"]
+    );
+
+    // Cancellations from the organizer are applied
+    assert!(server
+        .calendar_itip_ingest(
+            1,
+            &itip(
+                "CANCEL",
+                "bill@remote.org",
+                "bill@remote.org",
+                &format!("{EVENT}SEQUENCE:2\r\n")
+            ),
+            "bill@remote.org",
+            true,
+        )
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        get_event(server)
+            .await
+            .unwrap()
+            .get(&Property::Status)
+            .as_string(),
+        Some("cancelled")
+    );
+}
+
+async fn test_set() {
+    let account_id = Id::new(1).to_string();
+    let unknown_id = Id::new(9999).to_string();
+
+    // Create a calendar and a recurring event
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:calendars"],
+        "methodCalls": [
+            ["Calendar/set", {
+                "accountId": account_id,
+                "create": {
+                    "c1": {
+                        "name": "Work",
+                        "timeZone": "Europe/Madrid"
+                    }
+                }
+            }, "0"],
+            ["CalendarEvent/set", {
+                "accountId": account_id,
+                "create": {
+                    "e1": {
+                        "calendarIds": {"#c1": true},
+                        "title": "Standup",
+                        "start": "2023-01-02T10:00:00",
+                        "timeZone": "Europe/Madrid",
+                        "duration": "PT30M",
+                        "recurrenceRules": [{
+                            "@type": "RecurrenceRule",
+                            "frequency": "weekly",
+                            "byDay": [{"@type": "NDay", "day": "mo"}, {"@type": "NDay", "day": "we"}],
+                            "count": 4
+                        }],
+                        "recurrenceOverrides": {
+                            "2023-01-04T10:00:00": {"excluded": true}
+                        }
+                    },
+                    "e2": {
+                        "calendarIds": {"#c1": true},
+                        "title": "No start"
+                    },
+                    "e3": {
+                        "calendarIds": {"#c1": true},
+                        "title": "Invalid rule",
+                        "start": "2023-01-02T10:00:00",
+                        "recurrenceRules": [{"@type": "RecurrenceRule", "frequency": "fortnightly"}]
+                    },
+                    "e4": {
+                        "calendarIds": {unknown_id: true},
+                        "title": "Unknown calendar",
+                        "start": "2023-01-02T10:00:00"
+                    }
+                }
+            }, "1"]
+        ]
+    }))
+    .await;
+    let calendar_id = response["methodResponses"][0][1]["created"]["c1"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("{response}"))
+        .to_string();
+    let event_id = response["methodResponses"][1][1]["created"]["e1"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("{response}"))
+        .to_string();
+    for (id, property) in [
+        ("e2", "start"),
+        ("e3", "recurrenceRules"),
+        ("e4", "calendarIds"),
+    ] {
+        let error = &response["methodResponses"][1][1]["notCreated"][id];
+        assert_eq!(error["type"], "invalidProperties", "{response}");
+        assert_eq!(error["properties"][0], property, "{response}");
+    }
+
+    // Query instances within a range
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:calendars"],
+        "methodCalls": [
+            ["CalendarEvent/query", {
+                "accountId": account_id,
+                "filter": {
+                    "inCalendars": [calendar_id],
+                    "after": "2023-01-01T00:00:00Z",
+                    "before": "2023-01-15T00:00:00Z"
+                },
+                "expandRecurrences": true
+            }, "0"],
+            ["CalendarEvent/query", {
+                "accountId": account_id,
+                "filter": {
+                    "inCalendars": [calendar_id],
+                    "after": "2023-02-01T00:00:00Z"
+                }
+            }, "1"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["ids"]
+            .as_array()
+            .map(|ids| ids.len()),
+        Some(3),
+        "{response}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["ids"],
+        json!([]),
+        "{response}"
+    );
+
+    // Update and destroy the event
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:calendars"],
+        "methodCalls": [
+            ["CalendarEvent/set", {
+                "accountId": account_id,
+                "update": {
+                    event_id.as_str(): {
+                        "title": "Daily standup"
+                    }
+                }
+            }, "0"],
+            ["CalendarEvent/get", {
+                "accountId": account_id,
+                "ids": [event_id],
+                "properties": ["title", "start", "calendarIds"]
+            }, "1"],
+            ["CalendarEvent/set", {
+                "accountId": account_id,
+                "destroy": [event_id]
+            }, "2"]
+        ]
+    }))
+    .await;
+    let event = &response["methodResponses"][1][1]["list"][0];
+    assert_eq!(event["title"], "Daily standup", "{response}");
+    assert_eq!(event["start"], "2023-01-02T10:00:00", "{response}");
+    assert_eq!(
+        event["calendarIds"],
+        json!({calendar_id: true}),
+        "{response}"
+    );
+    assert_eq!(
+        response["methodResponses"][2][1]["destroyed"],
+        json!([event_id]),
+        "{response}"
+    );
+}
+
+fn itip(method: &str, from: &str, organizer: &str, event: &str) -> ItipMessage {
+    ItipMessage {
+        method: method.to_string(),
+        calendar: Component::parse(&format!(
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Test//EN\r\n",
+                "METHOD:{}\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:{}\r\n",
+                "DTSTAMP:20230101T000000Z\r\n",
+                "ORGANIZER:mailto:{}\r\n",
+                "{}",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n"
+            ),
+            method, UID, organizer, event
+        ))
+        .unwrap(),
+        from: Some(from.to_string()),
+    }
+}
+
+async fn get_event(server: &JMAP) -> Option<Object<Value>> {
+    let document_id = server
+        .filter(
+            1,
+            Collection::CalendarEvent,
+            vec![Filter::eq(Property::Uid, UID)],
+        )
+        .await
+        .unwrap()
+        .results
+        .min()?;
+    server
+        .get_property::<Object<Value>>(1, Collection::CalendarEvent, document_id, Property::Value)
+        .await
+        .unwrap()
+}
+
+fn participation_status(event: &Object<Value>, address: &str) -> Option<String> {
+    event
+        .get(&Property::Participants)
+        .as_obj()?
+        .properties
+        .values()
+        .filter_map(|participant| participant.as_obj())
+        .find(|participant| participant.get_key_str("email") == Some(address))?
+        .get_key_str("participationStatus")
+        .map(|status| status.to_string())
+}
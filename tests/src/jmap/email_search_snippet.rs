@@ -65,6 +65,11 @@ pub async fn test(server: Arc<JMAP>, client: &mut Client) {
         email_ids.insert(email_name, email_id);
     }
 
+    // Rebuild the full-text index, the searches below should return the same results
+    server.fts_reindex_schedule(Some(1)).await.unwrap();
+    server.fts_reindex_run().await;
+    assert!(server.fts_reindex_state().await.unwrap().is_none());
+
     // Run tests
     for (filter, email_name, snippet_subject, snippet_preview) in [
         (
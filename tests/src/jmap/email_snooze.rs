@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::JMAP;
+use jmap_client::{client::Client, mailbox::Role};
+use jmap_proto::types::id::Id;
+use serde_json::json;
+
+use crate::jmap::{jmap_json_request, mailbox::destroy_all_mailboxes};
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Email Snooze tests...");
+
+    // Import a message
+    let mailbox_id = client
+        .set_default_account_id(Id::new(1).to_string())
+        .mailbox_create("Snooze Test", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let email_id = client
+        .email_import(
+            concat!(
+                "From: bill@example.com\r\n",
+                "To: jdoe@example.com\r\n",
+                "Subject: TPS Report\r\n",
+                "\r\n",
+                "I'm going to need those TPS reports ASAP."
+            )
+            .as_bytes()
+            .to_vec(),
+            [&mailbox_id],
+            Some(["$seen"]),
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+
+    // Snoozing moves the message to the Snoozed mailbox and schedules a wake-up
+    let until = 4102444800; // 2100-01-01T00:00:00Z
+    snooze(
+        &email_id,
+        json!({"until": "2100-01-01T00:00:00Z", "moveToMailboxId": mailbox_id}),
+    )
+    .await;
+    let snoozed_id = Id::from(server.mailbox_get_or_create_snoozed(1).await.unwrap()).to_string();
+    let email = client
+        .email_get(&email_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(email.mailbox_ids(), &[&snoozed_id]);
+    let mut keywords = email.keywords();
+    keywords.sort_unstable();
+    assert_eq!(keywords, ["$seen", "$snoozed"]);
+    assert_eq!(server.snooze_next_due().await.unwrap(), Some(until));
+
+    // Wake-ups that are not due yet are left untouched
+    server.snooze_wake_due().await.unwrap();
+    let email = client
+        .email_get(&email_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(email.mailbox_ids(), &[&snoozed_id]);
+    assert_eq!(server.snooze_next_due().await.unwrap(), Some(until));
+
+    // Cancelling the snooze returns the message to its target mailbox
+    snooze(&email_id, serde_json::Value::Null).await;
+    let email = client
+        .email_get(&email_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(email.mailbox_ids(), &[&mailbox_id]);
+    assert_eq!(email.keywords(), ["$seen"]);
+    assert_eq!(server.snooze_next_due().await.unwrap(), None);
+
+    // Rescheduling replaces the pending wake-up
+    snooze(
+        &email_id,
+        json!({"until": "2100-01-01T00:00:00Z", "moveToMailboxId": mailbox_id}),
+    )
+    .await;
+    snooze(
+        &email_id,
+        json!({"until": "2000-01-01T00:00:00Z", "moveToMailboxId": mailbox_id}),
+    )
+    .await;
+    server.snooze_wake_due().await.unwrap();
+
+    // Woken up messages are moved back to their target mailbox as unread
+    let email = client
+        .email_get(&email_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(email.mailbox_ids(), &[&mailbox_id]);
+    assert!(email.keywords().is_empty());
+    assert_eq!(server.snooze_next_due().await.unwrap(), None);
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [["Email/get", {
+            "accountId": Id::new(1).to_string(),
+            "ids": [email_id],
+            "properties": ["snoozed"]
+        }, "0"]]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"][0]["snoozed"],
+        serde_json::Value::Null,
+        "{response}"
+    );
+
+    // The Snoozed mailbox is only created once
+    assert_eq!(
+        Id::from(server.mailbox_get_or_create_snoozed(1).await.unwrap()).to_string(),
+        snoozed_id
+    );
+
+    // Existing top-level mailboxes named "Snoozed" are not reused
+    client
+        .set_default_account_id(Id::new(2).to_string())
+        .mailbox_create("Snoozed", None::<String>, Role::None)
+        .await
+        .unwrap();
+    let snoozed_id = server.mailbox_get_or_create_snoozed(2).await.unwrap();
+    assert_eq!(
+        client
+            .mailbox_get(&Id::from(snoozed_id).to_string(), None::<Vec<_>>)
+            .await
+            .unwrap()
+            .unwrap()
+            .name()
+            .unwrap(),
+        "Snoozed 2"
+    );
+    assert_eq!(
+        server.mailbox_get_or_create_snoozed(2).await.unwrap(),
+        snoozed_id
+    );
+
+    // Empty store
+    destroy_all_mailboxes(client).await;
+    client.set_default_account_id(Id::new(1).to_string());
+    destroy_all_mailboxes(client).await;
+    server.store.assert_is_empty().await;
+}
+
+async fn snooze(email_id: &str, snoozed: serde_json::Value) {
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [["Email/set", {
+            "accountId": Id::new(1).to_string(),
+            "update": {
+                email_id: {
+                    "snoozed": snoozed
+                }
+            }
+        }, "0"]]
+    }))
+    .await;
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .map_or(false, |updated| updated.contains_key(email_id)),
+        "{response}"
+    );
+}
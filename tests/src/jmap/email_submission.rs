@@ -31,6 +31,7 @@ use jmap::JMAP;
 use jmap_client::{
     client::Client,
     core::set::{SetError, SetErrorType, SetObject},
+    email,
     email_submission::{query::Filter, Address, Delivered, DeliveryStatus, Displayed, UndoStatus},
     mailbox::Role,
     Error,
@@ -44,9 +45,14 @@ use tokio::{
     sync::mpsc,
 };
 
+use serde_json::json;
+
 use crate::{
     directory::sql::create_test_user_with_email,
-    jmap::{email_set::assert_email_properties, mailbox::destroy_all_mailboxes},
+    jmap::{
+        delivery::SmtpConnection, email_set::assert_email_properties, jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+    },
 };
 
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -261,20 +267,21 @@ pub async fn test(server: Arc<JMAP>, client: &mut Client) {
     assert_eq!(email_submission.undo_status().unwrap(), &UndoStatus::Final);
     assert_eq!(
         email_submission.delivery_status().unwrap(),
-        &AHashMap::from_iter([
-            (
-                "tim@foobar.com".to_string(),
-                DeliveryStatus::new("250 2.1.5 Queued", Delivered::Unknown, Displayed::Unknown)
-            ),
-            (
-                "secret_rcpt@test.com".to_string(),
-                DeliveryStatus::new("250 2.1.5 Queued", Delivered::Unknown, Displayed::Unknown)
-            ),
-            (
-                "james@other_domain.com".to_string(),
-                DeliveryStatus::new("250 2.1.5 Queued", Delivered::Unknown, Displayed::Unknown)
-            ),
-        ])
+        &AHashMap::from_iter(
+            [
+                "tim@foobar.com",
+                "secret_rcpt@test.com",
+                "james@other_domain.com"
+            ]
+            .map(|rcpt| (
+                rcpt.to_string(),
+                DeliveryStatus::new(
+                    "Code: 250, Enhanced code: 0.0.0, Message: OK",
+                    Delivered::Yes,
+                    Displayed::Unknown
+                )
+            ))
+        )
     );
 
     // SMTP rejects some of the recipients
@@ -422,6 +429,41 @@ pub async fn test(server: Arc<JMAP>, client: &mut Client) {
         ),])
     );
 
+    // Blob lookups include the submissions of an email
+    let email_blob_id = client
+        .email_get(&email_id, Some([email::Property::BlobId]))
+        .await
+        .unwrap()
+        .unwrap()
+        .take_blob_id();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:submission", "urn:ietf:params:jmap:blob"],
+        "methodCalls": [
+            ["Blob/lookup", {
+                "accountId": account_id,
+                "typeNames": ["Email", "EmailSubmission"],
+                "ids": [email_blob_id]
+            }, "0"]
+        ]
+    }))
+    .await;
+    let matched_ids = &response["methodResponses"][0][1]["list"][0]["matchedIds"];
+    assert_eq!(matched_ids["Email"], json!([email_id]), "{response}");
+    assert!(
+        matched_ids["EmailSubmission"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(email_submission_id)),
+        "{response}"
+    );
+
+    // Send and parse MDNs
+    test_mdn(client, &mut smtp_rx, &account_id, &identity_id, &mailbox_id).await;
+
+    // Link received DSNs and MDNs to their submissions
+    test_report_linking(client, &mut smtp_rx, &account_id, &identity_id, &mailbox_id).await;
+
     // Verify onSuccessUpdateEmail action
     let mut request = client.build();
     let set_request = request.set_email_submission();
@@ -474,6 +516,385 @@ pub async fn test(server: Arc<JMAP>, client: &mut Client) {
     server.store.assert_is_empty().await;
 }
 
+async fn test_mdn(
+    client: &mut Client,
+    smtp_rx: &mut mpsc::Receiver<MockMessage>,
+    account_id: &str,
+    identity_id: &str,
+    mailbox_id: &str,
+) {
+    let email_id = client
+        .email_import(
+            concat!(
+                "From: bill@remote.org\r\n",
+                "To: jdoe@example.com\r\n",
+                "Message-ID: <mdn-request@remote.org>\r\n",
+                "Disposition-Notification-To: Bill <bill@remote.org>\r\n",
+                "Subject: Please confirm\r\n",
+                "\r\n",
+                "Let me know when you read this.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let no_request_id = client
+        .email_import(
+            b"From: bill@remote.org\r\nSubject: No receipt\r\n\r\ntest\r\n".to_vec(),
+            [mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+
+    // Send an MDN and update the email on success
+    let disposition = json!({
+        "actionMode": "manual-action",
+        "sendingMode": "mdn-sent-manually",
+        "type": "displayed"
+    });
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:mdn"],
+        "methodCalls": [
+            ["MDN/send", {
+                "accountId": account_id,
+                "identityId": identity_id,
+                "send": {
+                    "k1": {
+                        "forEmailId": email_id,
+                        "disposition": disposition,
+                        "reportingUA": "test-client/1.0"
+                    },
+                    "k2": {
+                        "forEmailId": no_request_id,
+                        "disposition": disposition
+                    },
+                    "k3": {
+                        "forEmailId": email_id,
+                        "disposition": {
+                            "actionMode": "manual-action",
+                            "sendingMode": "mdn-sent-manually",
+                            "type": "read"
+                        }
+                    }
+                },
+                "onSuccessUpdateEmail": {"#k1": {"keywords/$seen": true}}
+            }, "0"],
+            ["Email/get", {
+                "accountId": account_id,
+                "ids": [email_id, no_request_id],
+                "properties": ["keywords"]
+            }, "1"]
+        ]
+    }))
+    .await;
+    let mdn_response = &response["methodResponses"][0][1];
+    assert_eq!(
+        mdn_response["sent"]["k1"]["finalRecipient"], "rfc822; jdoe@example.com",
+        "{response}"
+    );
+    assert_eq!(
+        mdn_response["sent"]["k1"]["originalMessageId"],
+        "mdn-request@remote.org"
+    );
+    assert_eq!(mdn_response["notSent"]["k2"]["type"], "forbiddenToSend");
+    assert_eq!(mdn_response["notSent"]["k3"]["type"], "invalidProperties");
+    let email_list = response["methodResponses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|response| response[0] == "Email/get")
+        .unwrap()[1]["list"]
+        .clone();
+    assert_eq!(
+        email_list[0]["keywords"],
+        json!({"$mdnsent": true, "$seen": true}),
+        "{response}"
+    );
+    assert_eq!(email_list[1]["keywords"], json!({}), "{response}");
+    let message = expect_message_delivery(smtp_rx).await;
+    assert_eq!(message.mail_from, "<>");
+    assert_eq!(message.rcpt_to, vec!["<bill@remote.org>".to_string()]);
+    for needle in [
+        "multipart/report",
+        "Reporting-UA: test-client/1.0",
+        "Final-Recipient: rfc822; jdoe@example.com",
+        "Original-Message-ID: <mdn-request@remote.org>",
+        "Disposition: manual-action/mdn-sent-manually; displayed",
+    ] {
+        assert!(message.message.contains(needle), "{needle}: {message:?}");
+    }
+    expect_nothing(smtp_rx).await;
+
+    // A second MDN for the same email is rejected
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:mdn"],
+        "methodCalls": [
+            ["MDN/send", {
+                "accountId": account_id,
+                "identityId": identity_id,
+                "send": {"k1": {"forEmailId": email_id, "disposition": disposition}}
+            }, "0"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["notSent"]["k1"]["type"], "mdnAlreadySent",
+        "{response}"
+    );
+    expect_nothing(smtp_rx).await;
+
+    // Parse the MDN that was sent
+    let mdn_email_id = client
+        .email_import(
+            message.message.into_bytes(),
+            [mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:mdn"],
+        "methodCalls": [
+            ["Email/get", {
+                "accountId": account_id,
+                "ids": [mdn_email_id, no_request_id],
+                "properties": ["blobId"]
+            }, "0"]
+        ]
+    }))
+    .await;
+    let mdn_blob_id = response["methodResponses"][0][1]["list"][0]["blobId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let other_blob_id = response["methodResponses"][0][1]["list"][1]["blobId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:mdn"],
+        "methodCalls": [
+            ["MDN/parse", {
+                "accountId": account_id,
+                "blobIds": [mdn_blob_id, other_blob_id]
+            }, "0"]
+        ]
+    }))
+    .await;
+    let parse_response = &response["methodResponses"][0][1];
+    let mdn = &parse_response["parsed"][&mdn_blob_id];
+    assert_eq!(mdn["forEmailId"], email_id.as_str(), "{response}");
+    assert_eq!(mdn["disposition"], disposition);
+    assert_eq!(mdn["reportingUA"], "test-client/1.0");
+    assert_eq!(mdn["finalRecipient"], "rfc822; jdoe@example.com");
+    assert_eq!(mdn["originalMessageId"], "mdn-request@remote.org");
+    assert_eq!(mdn["includeOriginalMessage"], false);
+    assert_eq!(
+        parse_response["notParsable"],
+        json!([other_blob_id]),
+        "{response}"
+    );
+
+    for email_id in [email_id, no_request_id, mdn_email_id] {
+        client.email_destroy(&email_id).await.unwrap();
+    }
+}
+
+async fn test_report_linking(
+    client: &mut Client,
+    smtp_rx: &mut mpsc::Receiver<MockMessage>,
+    account_id: &str,
+    identity_id: &str,
+    mailbox_id: &str,
+) {
+    let email_id = client
+        .email_import(
+            concat!(
+                "From: jdoe@example.com\r\n",
+                "To: bill@remote.org\r\n",
+                "Message-ID: <report-test@example.com>\r\n",
+                "Subject: Tracked message\r\n",
+                "\r\n",
+                "Please let me know.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [mailbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let submission_id = client
+        .email_submission_create(&email_id, identity_id)
+        .await
+        .unwrap()
+        .take_id();
+    assert_message_delivery(
+        smtp_rx,
+        MockMessage::new(
+            "<jdoe@example.com>",
+            ["<bill@remote.org>"],
+            "@Tracked message",
+        ),
+    )
+    .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Deliver a DSN and an MDN for the submitted message
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "",
+        &["jdoe@example.com"],
+        concat!(
+            "From: MAILER-DAEMON@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Delivery Status Notification\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/report; report-type=delivery-status; ",
+            "boundary=\"dsn\"\r\n",
+            "\r\n",
+            "--dsn\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Your message could not be delivered.\r\n",
+            "--dsn\r\n",
+            "Content-Type: message/delivery-status\r\n",
+            "\r\n",
+            "Reporting-MTA: dns; remote.org\r\n",
+            "\r\n",
+            "Final-Recipient: rfc822; bill@remote.org\r\n",
+            "Action: failed\r\n",
+            "Status: 5.1.1\r\n",
+            "Diagnostic-Code: smtp; 550 5.1.1 Mailbox unavailable\r\n",
+            "\r\n",
+            "--dsn\r\n",
+            "Content-Type: text/rfc822-headers\r\n",
+            "\r\n",
+            "From: jdoe@example.com\r\n",
+            "Message-ID: <report-test@example.com>\r\n",
+            "Subject: Tracked message\r\n",
+            "\r\n",
+            "--dsn--\r\n"
+        ),
+    )
+    .await;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Read: Tracked message\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/report; report-type=disposition-notification; ",
+            "boundary=\"mdn\"\r\n",
+            "\r\n",
+            "--mdn\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Your message was displayed.\r\n",
+            "--mdn\r\n",
+            "Content-Type: message/disposition-notification\r\n",
+            "\r\n",
+            "Final-Recipient: rfc822; bill@remote.org\r\n",
+            "Original-Message-ID: <report-test@example.com>\r\n",
+            "Disposition: manual-action/MDN-sent-manually; displayed\r\n",
+            "\r\n",
+            "--mdn--\r\n"
+        ),
+    )
+    .await;
+
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:submission"],
+        "methodCalls": [
+            ["EmailSubmission/get", {
+                "accountId": account_id,
+                "ids": [submission_id],
+                "properties": ["deliveryStatus", "dsnBlobIds", "mdnBlobIds"]
+            }, "0"]
+        ]
+    }))
+    .await;
+    let submission = &response["methodResponses"][0][1]["list"][0];
+    let status = &submission["deliveryStatus"]["bill@remote.org"];
+    assert_eq!(status["delivered"], "no", "{response}");
+    assert_eq!(status["displayed"], "yes", "{response}");
+    assert_eq!(status["smtpReply"], "550 5.1.1 Mailbox unavailable");
+    assert_eq!(
+        submission["dsnBlobIds"].as_array().map(|ids| ids.len()),
+        Some(1),
+        "{response}"
+    );
+    assert_eq!(
+        submission["mdnBlobIds"].as_array().map(|ids| ids.len()),
+        Some(1),
+        "{response}"
+    );
+
+    // Reports for unknown messages are stored without linking
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: bill@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Read: Unknown message\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/report; report-type=disposition-notification; ",
+            "boundary=\"mdn\"\r\n",
+            "\r\n",
+            "--mdn\r\n",
+            "Content-Type: message/disposition-notification\r\n",
+            "\r\n",
+            "Final-Recipient: rfc822; bill@remote.org\r\n",
+            "Original-Message-ID: <unknown@example.com>\r\n",
+            "Disposition: manual-action/MDN-sent-manually; displayed\r\n",
+            "\r\n",
+            "--mdn--\r\n"
+        ),
+    )
+    .await;
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:submission"],
+        "methodCalls": [
+            ["EmailSubmission/get", {
+                "accountId": account_id,
+                "ids": [submission_id],
+                "properties": ["mdnBlobIds"]
+            }, "0"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["list"][0]["mdnBlobIds"]
+            .as_array()
+            .map(|ids| ids.len()),
+        Some(1),
+        "{response}"
+    );
+
+    client.email_destroy(&email_id).await.unwrap();
+}
+
 pub fn spawn_mock_smtp_server() -> (mpsc::Receiver<MockMessage>, Arc<Mutex<MockSMTPSettings>>) {
     // Create channels
     let (event_tx, event_rx) = mpsc::channel::<MockMessage>(100);
@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+
+use jmap::{
+    email::reindex::{fts_reindex_backoff, ReindexState},
+    mailbox::INBOX_ID,
+    JMAP,
+};
+use jmap_client::{client::Client, email::query::Filter};
+use jmap_proto::types::{collection::Collection, id::Id};
+use reqwest::{header::AUTHORIZATION, StatusCode};
+use store::{fts::term_index::TokenIndex, write::BatchBuilder, BlobKind, ValueKey};
+
+use crate::{directory::sql::create_test_user_with_email, jmap::mailbox::destroy_all_mailboxes};
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running full-text reindex tests...");
+
+    // Create test account
+    let directory = server.directory.as_ref();
+    create_test_user_with_email(directory, "jdoe@example.com", "12345", "John Doe").await;
+    let account_id = server.get_account_id("jdoe@example.com").await.unwrap();
+    client.set_default_account_id(Id::from(account_id).to_string());
+    let mailbox_id = Id::from(INBOX_ID).to_string();
+
+    // Import test messages
+    let mut test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_dir.push("resources");
+    test_dir.push("jmap_mail_snippet");
+    let mut email_ids = Vec::new();
+    for email_name in [
+        "html",
+        "subpart",
+        "mixed",
+        "text_plain",
+        "text_plain_chinese",
+    ] {
+        let mut file_name = test_dir.clone();
+        file_name.push(format!("{}.eml", email_name));
+        let email_id = client
+            .email_import(
+                fs::read(&file_name).unwrap(),
+                [&mailbox_id],
+                None::<Vec<&str>>,
+                None,
+            )
+            .await
+            .unwrap()
+            .take_id();
+        email_ids.push(email_id);
+    }
+    let mut document_ids = email_ids
+        .iter()
+        .map(|id| Id::from_bytes(id.as_bytes()).unwrap().document_id())
+        .collect::<Vec<_>>();
+    document_ids.sort_unstable();
+
+    // Retries back off exponentially and give up after a few attempts
+    let mut last_backoff = Duration::ZERO;
+    for try_count in 1..=5 {
+        let backoff = fts_reindex_backoff(try_count).unwrap();
+        assert!(backoff > last_backoff, "{backoff:?} <= {last_backoff:?}");
+        last_backoff = backoff;
+    }
+    assert_eq!(fts_reindex_backoff(6), None);
+
+    // Reindexing is done in batches of two messages, and resumes from the
+    // last batch stored when interrupted
+    assert!(server.fts_reindex_state().await.unwrap().is_none());
+    server.fts_reindex_schedule(Some(account_id)).await.unwrap();
+    let state = fts_status().await.unwrap();
+    assert_eq!(state.accounts, vec![account_id]);
+    assert_eq!(state.last_document_id, None);
+    for (batch_num, last_document_id) in [(1, document_ids[1]), (2, document_ids[3])] {
+        assert!(server.fts_reindex_batch().await.unwrap());
+        let state = server.fts_reindex_state().await.unwrap().unwrap();
+        assert_eq!(state.accounts, vec![account_id]);
+        assert_eq!(state.last_document_id, Some(last_document_id));
+        assert_eq!(state.indexed, batch_num * 2);
+        assert_eq!(state.failed, 0);
+        assert_eq!(fts_status().await.unwrap().indexed, batch_num * 2);
+    }
+    assert!(!server.fts_reindex_batch().await.unwrap());
+    assert!(server.fts_reindex_state().await.unwrap().is_none());
+    assert!(fts_status().await.is_none());
+    for document_id in &document_ids {
+        assert_term_index(&server, account_id, *document_id, true).await;
+    }
+    assert!(client
+        .email_query(Filter::body("secretly").into(), None::<Vec<_>>)
+        .await
+        .unwrap()
+        .ids()
+        .contains(&email_ids[3]));
+
+    // A message deleted while it is being reindexed should not be indexed again,
+    // its blob is removed only after the deletion is committed
+    let document_id = document_ids.remove(0);
+    let blob_kind = BlobKind::LinkedMaildir {
+        account_id,
+        document_id,
+    };
+    let raw_message = server
+        .store
+        .get_blob(&blob_kind, 0..u32::MAX)
+        .await
+        .unwrap()
+        .unwrap();
+    server
+        .email_delete(account_id, document_id)
+        .await
+        .unwrap()
+        .unwrap();
+    server
+        .store
+        .put_blob(&blob_kind, &raw_message)
+        .await
+        .unwrap();
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email);
+    assert!(!server
+        .fts_reindex_message(&mut batch, account_id, document_id)
+        .await
+        .unwrap());
+    assert!(server
+        .fts_reindex_message(&mut batch, account_id, document_ids[0])
+        .await
+        .unwrap());
+    server.store.write(batch.build()).await.unwrap();
+    assert_term_index(&server, account_id, document_id, false).await;
+    assert_term_index(&server, account_id, document_ids[0], true).await;
+    server.store.delete_blob(&blob_kind).await.unwrap();
+
+    // Reindexing through the management API
+    assert_eq!(
+        fts_request("/admin/fts/reindex/unknown@example.com")
+            .await
+            .status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        fts_request("/admin/fts/reindex/jdoe@example.com")
+            .await
+            .status(),
+        StatusCode::OK
+    );
+    let mut is_done = false;
+    for _ in 0..50 {
+        if fts_status().await.is_none() {
+            is_done = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(is_done, "Reindex did not finish.");
+    for document_id in &document_ids {
+        assert_term_index(&server, account_id, *document_id, true).await;
+    }
+
+    // Destroy test data
+    destroy_all_mailboxes(client).await;
+    server.store.assert_is_empty().await;
+}
+
+async fn assert_term_index(server: &JMAP, account_id: u32, document_id: u32, exists: bool) {
+    assert_eq!(
+        server
+            .store
+            .get_value::<TokenIndex>(ValueKey::term_index(
+                account_id,
+                Collection::Email,
+                document_id,
+            ))
+            .await
+            .unwrap()
+            .is_some(),
+        exists,
+        "document {document_id}"
+    );
+}
+
+async fn fts_status() -> Option<ReindexState> {
+    let response = fts_request("/admin/fts/status").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice(&response.bytes().await.unwrap()).unwrap()
+}
+
+async fn fts_request(query: &str) -> reqwest::Response {
+    reqwest::Client::builder()
+        .timeout(Duration::from_millis(1000))
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap()
+        .get(format!("https://127.0.0.1:8899{query}"))
+        .header(AUTHORIZATION, "Basic YWRtaW46c2VjcmV0")
+        .send()
+        .await
+        .unwrap()
+}
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::JMAP;
+use jmap_client::{client::Client, mailbox::Role};
+use jmap_proto::types::id::Id;
+use serde_json::json;
+
+use crate::jmap::{jmap_json_request, mailbox::destroy_all_mailboxes};
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Mailbox saved search tests...");
+
+    let account_id = Id::new(1).to_string();
+    let mailbox_id = client
+        .set_default_account_id(&account_id)
+        .mailbox_create("Saved Search Source", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    let mut email_ids = Vec::new();
+    for (from, subject) in [
+        ("alice@example.com", "Quarterly report"),
+        ("bob@example.com", "Lunch plans"),
+        ("carol@example.com", "Holiday pictures"),
+    ] {
+        email_ids.push(
+            client
+                .email_import(
+                    format!("From: {from}\r\nSubject: {subject}\r\n\r\nTest.\r\n").into_bytes(),
+                    [&mailbox_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+
+    // Create saved searches with nested filters
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": account_id,
+                "create": {
+                    "s1": {
+                        "name": "Alice or lunch",
+                        "filter": {
+                            "operator": "AND",
+                            "conditions": [
+                                {"inMailbox": mailbox_id},
+                                {
+                                    "operator": "OR",
+                                    "conditions": [
+                                        {"from": "alice"},
+                                        {"subject": "lunch"}
+                                    ]
+                                }
+                            ]
+                        }
+                    },
+                    "s2": {
+                        "name": "Not from Bob",
+                        "filter": {
+                            "operator": "NOT",
+                            "conditions": [{"from": "bob"}]
+                        }
+                    },
+                    "s3": {
+                        "name": "Invalid condition",
+                        "filter": {"inMailbox": mailbox_id, "notACondition": true}
+                    },
+                    "s4": {
+                        "name": "Search with role",
+                        "role": "archive",
+                        "filter": {"from": "alice"}
+                    }
+                }
+            }, "0"]
+        ]
+    }))
+    .await;
+    let created = &response["methodResponses"][0][1]["created"];
+    let search_id = created["s1"]["id"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Unexpected response: {response}"))
+        .to_string();
+    let not_search_id = created["s2"]["id"].as_str().unwrap().to_string();
+    let not_created = &response["methodResponses"][0][1]["notCreated"];
+    assert_eq!(not_created["s3"]["type"], "invalidProperties", "{response}");
+    assert_eq!(not_created["s3"]["properties"], json!(["filter"]));
+    assert_eq!(not_created["s4"]["type"], "invalidProperties", "{response}");
+
+    // Counts and queries reflect the stored filter
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Mailbox/get", {
+                "accountId": account_id,
+                "ids": [search_id, not_search_id],
+                "properties": ["name", "totalEmails", "filter"]
+            }, "0"],
+            ["Email/query", {
+                "accountId": account_id,
+                "filter": {"inMailbox": search_id},
+                "sort": [{"property": "subject"}]
+            }, "1"],
+            ["Email/query", {
+                "accountId": account_id,
+                "filter": {"inMailbox": not_search_id, "text": "pictures"}
+            }, "2"]
+        ]
+    }))
+    .await;
+    let list = &response["methodResponses"][0][1]["list"];
+    assert_eq!(list[0]["totalEmails"], 2, "{response}");
+    assert_eq!(
+        list[0]["filter"]["conditions"][1]["conditions"][0]["from"],
+        "alice"
+    );
+    assert_eq!(list[1]["totalEmails"], 2, "{response}");
+    assert_eq!(
+        response["methodResponses"][1][1]["ids"],
+        json!([email_ids[1], email_ids[0]]),
+        "{response}"
+    );
+    assert_eq!(
+        response["methodResponses"][2][1]["ids"],
+        json!([email_ids[2]]),
+        "{response}"
+    );
+
+    // Updating the filter changes the mailbox contents
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": account_id,
+                "update": {search_id.as_str(): {"filter": {"from": "carol"}}}
+            }, "0"],
+            ["Email/query", {
+                "accountId": account_id,
+                "filter": {"inMailbox": search_id}
+            }, "1"]
+        ]
+    }))
+    .await;
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .unwrap()
+            .contains_key(&search_id),
+        "{response}"
+    );
+    assert_eq!(
+        response["methodResponses"][1][1]["ids"],
+        json!([email_ids[2]]),
+        "{response}"
+    );
+
+    destroy_all_mailboxes(client).await;
+    server.store.assert_is_empty().await;
+}
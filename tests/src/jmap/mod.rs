@@ -24,6 +24,7 @@
 use std::{sync::Arc, time::Duration};
 
 use directory::config::ConfigDirectory;
+use imap::op::urlauth::spawn_url_resolver;
 use jmap::{api::JmapSessionManager, services::IPC_CHANNEL_BUFFER, JMAP};
 use jmap_client::client::{Client, Credentials};
 use jmap_proto::types::id::Id;
@@ -40,6 +41,8 @@ use crate::{
 pub mod auth_acl;
 pub mod auth_limits;
 pub mod auth_oauth;
+pub mod blob;
+pub mod calendar;
 pub mod crypto;
 pub mod delivery;
 pub mod email_changes;
@@ -50,15 +53,21 @@ pub mod email_query;
 pub mod email_query_changes;
 pub mod email_search_snippet;
 pub mod email_set;
+pub mod email_snooze;
 pub mod email_submission;
 pub mod event_source;
+pub mod fts_reindex;
 pub mod mailbox;
+pub mod mailbox_saved_search;
 pub mod push_subscription;
 pub mod quota;
+pub mod share_notification;
 pub mod sieve_script;
+pub mod smime;
 pub mod stress_test;
 pub mod thread_get;
 pub mod thread_merge;
+pub mod thread_summary;
 pub mod vacation_response;
 pub mod websocket;
 
@@ -146,6 +155,10 @@ directory = "sql"
 [jmap.protocol]
 set.max-objects = 100000
 
+[jmap.fts.reindex]
+batch-size = 2
+throttle = "10ms"
+
 [jmap.protocol.request]
 max-concurrent = 8
 
@@ -232,15 +245,22 @@ pub async fn jmap_tests() {
     email_get::test(params.server.clone(), &mut params.client).await;
     email_set::test(params.server.clone(), &mut params.client).await;
     email_parse::test(params.server.clone(), &mut params.client).await;
+    blob::test(params.server.clone(), &mut params.client).await;
     email_search_snippet::test(params.server.clone(), &mut params.client).await;
+    fts_reindex::test(params.server.clone(), &mut params.client).await;
     email_changes::test(params.server.clone(), &mut params.client).await;
     email_query_changes::test(params.server.clone(), &mut params.client).await;
     email_copy::test(params.server.clone(), &mut params.client).await;
+    email_snooze::test(params.server.clone(), &mut params.client).await;
+    calendar::test(params.server.clone(), &mut params.client).await;
     thread_get::test(params.server.clone(), &mut params.client).await;
     thread_merge::test(params.server.clone(), &mut params.client).await;
+    thread_summary::test(params.server.clone(), &mut params.client).await;
     mailbox::test(params.server.clone(), &mut params.client).await;
+    mailbox_saved_search::test(params.server.clone(), &mut params.client).await;
     delivery::test(params.server.clone(), &mut params.client).await;
     auth_acl::test(params.server.clone(), &mut params.client).await;
+    share_notification::test(params.server.clone(), &mut params.client).await;
     auth_limits::test(params.server.clone(), &mut params.client).await;
     auth_oauth::test(params.server.clone(), &mut params.client).await;
     event_source::test(params.server.clone(), &mut params.client).await;
@@ -293,12 +313,14 @@ async fn init_jmap_tests(delete_if_exists: bool) -> JMAPTest {
     // Start JMAP and SMTP servers
     servers.bind(&config);
     let (delivery_tx, delivery_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
-    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx)
+    let (url_tx, url_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
+    let smtp = SMTP::init(&config, &servers, &directory, delivery_tx, url_tx)
         .await
         .failed("Invalid configuration file");
     let jmap = JMAP::init(&config, &directory, delivery_rx, smtp.clone())
         .await
         .failed("Invalid configuration file");
+    spawn_url_resolver(jmap.clone(), url_rx);
     let shutdown_tx = servers.spawn(|server, shutdown_rx| {
         match &server.protocol {
             ServerProtocol::Smtp | ServerProtocol::Lmtp => {
@@ -403,3 +425,24 @@ pub async fn test_account_login(login: &str, secret: &str) -> Client {
         .await
         .unwrap()
 }
+
+pub async fn jmap_json_request(request: serde_json::Value) -> serde_json::Value {
+    serde_json::from_slice(
+        &reqwest::Client::builder()
+            .timeout(Duration::from_millis(1000))
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap_or_default()
+            .post("https://127.0.0.1:8899/jmap/")
+            .basic_auth("admin", Some("secret"))
+            .header("Content-Type", "application/json")
+            .body(request.to_string())
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+}
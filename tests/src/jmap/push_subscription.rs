@@ -23,10 +23,10 @@
 
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use base64::{engine::general_purpose, Engine};
@@ -44,9 +44,9 @@ use jmap::{
     JMAP,
 };
 use jmap_client::{client::Client, mailbox::Role, push_subscription::Keys};
-use jmap_proto::types::{id::Id, type_state::TypeState};
-use reqwest::header::CONTENT_ENCODING;
-use store::ahash::AHashSet;
+use jmap_proto::types::{collection::Collection, date::UTCDate, id::Id, type_state::TypeState};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING};
+use store::{ahash::AHashSet, write::now};
 use tokio::{net::TcpStream, sync::mpsc};
 use utils::listener::SessionData;
 
@@ -100,7 +100,8 @@ pub async fn test(server: Arc<JMAP>, admin_client: &mut Client) {
         keypair: keypair.raw_components().unwrap(),
         auth_secret: auth_secret.to_vec(),
         tx: event_tx,
-        fail_requests: false.into(),
+        fail_requests: 0.into(),
+        failed_requests: Mutex::new(Vec::new()),
     });
 
     // Start mock push server
@@ -185,13 +186,13 @@ pub async fn test(server: Arc<JMAP>, admin_client: &mut Client) {
         .unwrap();
 
     // Failed deliveries should be re-attempted
-    push_server.fail_requests.store(true, Ordering::Relaxed);
+    push_server.fail_requests.store(429, Ordering::Relaxed);
     client
         .mailbox_update_sort_order(&mailbox_id, 101)
         .await
         .unwrap();
     tokio::time::sleep(Duration::from_millis(200)).await;
-    push_server.fail_requests.store(false, Ordering::Relaxed);
+    push_server.fail_requests.store(0, Ordering::Relaxed);
     assert_state(&mut event_rx, &account_id, &[TypeState::Mailbox]).await;
 
     // Make a mailbox change and expect state change
@@ -212,6 +213,92 @@ pub async fn test(server: Arc<JMAP>, admin_client: &mut Client) {
     assert_state(&mut event_rx, &account_id, &[TypeState::Mailbox]).await;
     expect_nothing(&mut event_rx).await;
 
+    // Transient failures are retried with an increasing delay until the
+    // maximum number of attempts is reached
+    push_server.failed_requests.lock().unwrap().clear();
+    push_server.fail_requests.store(503, Ordering::Relaxed);
+    client
+        .mailbox_update_sort_order(&mailbox_id, 201)
+        .await
+        .unwrap();
+    let failed_requests = wait_for_failed_requests(&push_server, 3).await;
+    for (num_attempt, delay) in failed_requests
+        .windows(2)
+        .map(|requests| requests[1] - requests[0])
+        .enumerate()
+    {
+        assert!(
+            delay >= Duration::from_millis(500 << num_attempt),
+            "attempt {num_attempt} was retried after {delay:?}"
+        );
+    }
+
+    // Once given up, pending changes are discarded and no more attempts are made
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    assert_eq!(push_server.failed_requests.lock().unwrap().len(), 3);
+    push_server.fail_requests.store(0, Ordering::Relaxed);
+    expect_nothing(&mut event_rx).await;
+
+    // The subscription is still active after giving up
+    client
+        .mailbox_update_sort_order(&mailbox_id, 202)
+        .await
+        .unwrap();
+    assert_state(&mut event_rx, &account_id, &[TypeState::Mailbox]).await;
+    client.push_subscription_destroy(&push_id).await.unwrap();
+
+    // Subscriptions are deleted when the push service reports that the endpoint is gone
+    for status in [404, 410] {
+        let push_id = create_verified_subscription(&client, &mut event_rx).await;
+        push_server.failed_requests.lock().unwrap().clear();
+        push_server.fail_requests.store(status, Ordering::Relaxed);
+        client
+            .mailbox_update_sort_order(&mailbox_id, status as u32)
+            .await
+            .unwrap();
+        wait_for_failed_requests(&push_server, 1).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(
+            !server
+                .get_document_ids(account_id.document_id(), Collection::PushSubscription)
+                .await
+                .unwrap()
+                .unwrap_or_default()
+                .contains(Id::from_bytes(push_id.as_bytes()).unwrap().document_id()),
+            "subscription was not deleted after a {status} response"
+        );
+
+        // No further requests are sent to the endpoint
+        push_server.fail_requests.store(0, Ordering::Relaxed);
+        client
+            .mailbox_update_sort_order(&mailbox_id, status as u32 + 1)
+            .await
+            .unwrap();
+        expect_nothing(&mut event_rx).await;
+        assert_eq!(push_server.failed_requests.lock().unwrap().len(), 1);
+    }
+
+    // Expired subscriptions are no longer used
+    let push_id = create_verified_subscription(&client, &mut event_rx).await;
+    let response = set_subscription_expires(&push_id, now() + 2).await;
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .map_or(false, |updated| updated.contains_key(&push_id)),
+        "{response}"
+    );
+    client
+        .mailbox_update_sort_order(&mailbox_id, 301)
+        .await
+        .unwrap();
+    assert_state(&mut event_rx, &account_id, &[TypeState::Mailbox]).await;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    client
+        .mailbox_update_sort_order(&mailbox_id, 302)
+        .await
+        .unwrap();
+    expect_nothing(&mut event_rx).await;
+
     // Destroy mailbox
     client.push_subscription_destroy(&push_id).await.unwrap();
     client.mailbox_destroy(&mailbox_id, true).await.unwrap();
@@ -236,7 +323,8 @@ pub struct PushServer {
     keypair: EcKeyComponents,
     auth_secret: Vec<u8>,
     tx: mpsc::Sender<PushMessage>,
-    fail_requests: AtomicBool,
+    fail_requests: AtomicU16,
+    failed_requests: Mutex<Vec<Instant>>,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -299,13 +387,22 @@ impl utils::listener::SessionManager for SessionManager {
                         let push = push.clone();
 
                         async move {
-                            if push.fail_requests.load(Ordering::Relaxed) {
+                            let fail_status = push.fail_requests.load(Ordering::Relaxed);
+                            if fail_status != 0 {
+                                push.failed_requests.lock().unwrap().push(Instant::now());
                                 return Ok(HtmlResponse::with_status(
-                                    StatusCode::TOO_MANY_REQUESTS,
-                                    "too many requests".to_string(),
+                                    StatusCode::from_u16(fail_status).unwrap(),
+                                    "request failed".to_string(),
                                 )
                                 .into_http_response());
                             }
+                            assert!(
+                                req.headers()
+                                    .get(AUTHORIZATION)
+                                    .and_then(|value| value.to_str().ok())
+                                    .map_or(false, |value| value.starts_with("vapid t=")),
+                                "Missing VAPID authorization header"
+                            );
                             let is_encrypted = req
                                 .headers()
                                 .get(CONTENT_ENCODING)
@@ -344,6 +441,73 @@ impl utils::listener::SessionManager for SessionManager {
     fn shutdown(&self) {}
 }
 
+async fn create_verified_subscription(
+    client: &Client,
+    event_rx: &mut mpsc::Receiver<PushMessage>,
+) -> String {
+    let push_id = client
+        .push_subscription_create("123", "https://127.0.0.1:9000/push?skip_checks=true", None)
+        .await
+        .unwrap()
+        .take_id();
+    let verification = expect_push(event_rx).await.unwrap_verification();
+    assert_eq!(verification.push_subscription_id, push_id);
+    client
+        .push_subscription_verify(&push_id, verification.verification_code)
+        .await
+        .unwrap();
+    push_id
+}
+
+async fn set_subscription_expires(push_id: &str, expires: u64) -> serde_json::Value {
+    serde_json::from_slice(
+        &reqwest::Client::builder()
+            .timeout(Duration::from_millis(1000))
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap_or_default()
+            .post("https://127.0.0.1:8899/jmap/")
+            .basic_auth("jdoe@example.com", Some("12345"))
+            .header("Content-Type", "application/json")
+            .body(
+                serde_json::json!({
+                    "using": ["urn:ietf:params:jmap:core"],
+                    "methodCalls": [[
+                        "PushSubscription/set",
+                        {
+                            "update": {
+                                push_id: {
+                                    "expires": UTCDate::from_timestamp(expires as i64).to_string()
+                                }
+                            }
+                        },
+                        "0"
+                    ]]
+                })
+                .to_string(),
+            )
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+async fn wait_for_failed_requests(push_server: &PushServer, num_requests: usize) -> Vec<Instant> {
+    for _ in 0..100 {
+        let failed_requests = push_server.failed_requests.lock().unwrap().clone();
+        if failed_requests.len() >= num_requests {
+            assert_eq!(failed_requests.len(), num_requests);
+            return failed_requests;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Timeout waiting for {num_requests} failed push requests");
+}
+
 async fn expect_push(event_rx: &mut mpsc::Receiver<PushMessage>) -> PushMessage {
     match tokio::time::timeout(Duration::from_millis(1500), event_rx.recv()).await {
         Ok(Some(push)) => {
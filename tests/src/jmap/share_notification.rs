@@ -0,0 +1,224 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::JMAP;
+use jmap_client::client::Client;
+use jmap_proto::types::id::Id;
+use serde_json::json;
+
+use crate::{
+    directory::sql::create_test_user_with_email,
+    jmap::{jmap_json_request, mailbox::destroy_all_mailboxes},
+};
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Share Notification tests...");
+
+    let directory = server.directory.as_ref();
+    create_test_user_with_email(directory, "alice.share@example.com", "12345", "Alice").await;
+    create_test_user_with_email(directory, "bob.share@example.com", "abcde", "Bob").await;
+    let alice_id = Id::from(
+        server
+            .get_account_id("alice.share@example.com")
+            .await
+            .unwrap(),
+    )
+    .to_string();
+    let bob_id = Id::from(
+        server
+            .get_account_id("bob.share@example.com")
+            .await
+            .unwrap(),
+    )
+    .to_string();
+
+    // Sharing a mailbox notifies the grantee
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": alice_id,
+                "create": {
+                    "m1": {
+                        "name": "Shared Folder",
+                        "acl": {"bob.share@example.com": ["read", "readItems"]}
+                    }
+                }
+            }, "0"],
+            ["ShareNotification/get", {"accountId": bob_id}, "1"],
+            ["ShareNotification/get", {"accountId": alice_id}, "2"]
+        ]
+    }))
+    .await;
+    let mailbox_id = response["methodResponses"][0][1]["created"]["m1"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let notifications = response["methodResponses"][1][1]["list"]
+        .as_array()
+        .unwrap();
+    assert_eq!(notifications.len(), 1, "{response}");
+    assert_eq!(notifications[0]["objectType"], "Mailbox");
+    assert_eq!(notifications[0]["objectAccountId"], alice_id.as_str());
+    assert_eq!(notifications[0]["objectId"], mailbox_id.as_str());
+    assert_eq!(notifications[0]["name"], "Shared Folder");
+    assert_eq!(notifications[0]["oldRights"], serde_json::Value::Null);
+    assert_eq!(notifications[0]["newRights"]["mayReadItems"], true);
+    assert_eq!(notifications[0]["newRights"]["mayDelete"], false);
+    assert_eq!(
+        response["methodResponses"][2][1]["list"]
+            .as_array()
+            .unwrap()
+            .len(),
+        0
+    );
+    let state = response["methodResponses"][1][1]["state"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Revoking access notifies the grantee again
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail",
+                  "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["Mailbox/set", {
+                "accountId": alice_id,
+                "update": {mailbox_id.as_str(): {"acl": {}}}
+            }, "0"],
+            ["ShareNotification/changes", {"accountId": bob_id, "sinceState": state}, "1"],
+            ["ShareNotification/get", {"accountId": bob_id, "#ids": {
+                "resultOf": "1",
+                "name": "ShareNotification/changes",
+                "path": "/created"
+            }}, "2"]
+        ]
+    }))
+    .await;
+    assert!(
+        response["methodResponses"][0][1]["updated"]
+            .as_object()
+            .unwrap()
+            .contains_key(&mailbox_id),
+        "{response}"
+    );
+    let notifications = response["methodResponses"][2][1]["list"]
+        .as_array()
+        .unwrap();
+    assert_eq!(notifications.len(), 1, "{response}");
+    assert_eq!(notifications[0]["oldRights"]["mayReadItems"], true);
+    assert_eq!(notifications[0]["newRights"], serde_json::Value::Null);
+
+    // Principal updates are visible through Principal/changes
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["Principal/get", {"accountId": alice_id, "ids": [alice_id]}, "0"]
+        ]
+    }))
+    .await;
+    let state = response["methodResponses"][0][1]["state"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["Principal/set", {
+                "accountId": alice_id,
+                "ifInState": state,
+                "update": {alice_id.as_str(): {"description": "Out of office"}}
+            }, "0"],
+            ["Principal/changes", {"accountId": alice_id, "sinceState": state}, "1"],
+            ["Principal/get", {"accountId": alice_id, "ids": [alice_id]}, "2"]
+        ]
+    }))
+    .await;
+    let new_state = response["methodResponses"][0][1]["newState"]
+        .as_str()
+        .unwrap();
+    assert_eq!(
+        response["methodResponses"][0][1]["oldState"],
+        state.as_str()
+    );
+    assert_ne!(new_state, state, "{response}");
+    assert_eq!(response["methodResponses"][1][1]["newState"], new_state);
+    assert_eq!(
+        response["methodResponses"][1][1]["updated"],
+        json!([alice_id]),
+        "{response}"
+    );
+    assert_eq!(response["methodResponses"][2][1]["state"], new_state);
+    assert_eq!(
+        response["methodResponses"][2][1]["list"][0]["description"],
+        "Out of office"
+    );
+
+    // Stale states are rejected
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["Principal/set", {
+                "accountId": alice_id,
+                "ifInState": state,
+                "update": {alice_id.as_str(): {"description": null}}
+            }, "0"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][0][1]["type"], "stateMismatch",
+        "{response}"
+    );
+
+    // Remove test data
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:principals"],
+        "methodCalls": [
+            ["ShareNotification/query", {"accountId": bob_id}, "0"],
+            ["ShareNotification/set", {"accountId": bob_id, "#destroy": {
+                "resultOf": "0",
+                "name": "ShareNotification/query",
+                "path": "/ids"
+            }}, "1"]
+        ]
+    }))
+    .await;
+    assert_eq!(
+        response["methodResponses"][1][1]["destroyed"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2,
+        "{response}"
+    );
+    for account_id in [&alice_id, &bob_id] {
+        client.set_default_account_id(account_id);
+        destroy_all_mailboxes(client).await;
+    }
+    client.set_default_account_id(Id::new(1).to_string());
+    server.store.assert_is_empty().await;
+}
@@ -0,0 +1,104 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::path::PathBuf;
+
+use jmap::email::smime::{parse_trust_store, SmimeStatus, VerifySmime};
+use mail_parser::Message;
+use utils::config::Config;
+
+// All test certificates are valid from 2020 until 2040, except for the expired
+// one which is only valid during 2020.
+const NOW: i64 = 1_700_000_000;
+const VALID_UNTIL: u64 = 2_208_988_800;
+
+#[test]
+fn smime_verify() {
+    let resources = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join("smime");
+    let trust_store = parse_trust_store(
+        &Config::parse(&format!(
+            "[jmap.email.smime]\ntrust-store = [\"file://{}\"]\n",
+            resources.join("root.pem").display()
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+
+    for (file_name, expected_status, expected_error) in [
+        ("valid.eml", SmimeStatus::Verified, None),
+        ("valid_ec.eml", SmimeStatus::Verified, None),
+        (
+            "forged.eml",
+            SmimeStatus::Failed,
+            Some("Certificate was not issued by a trusted authority"),
+        ),
+        (
+            "path_len.eml",
+            SmimeStatus::Failed,
+            Some("Certificate path length constraint exceeded"),
+        ),
+        (
+            "expired.eml",
+            SmimeStatus::Failed,
+            Some("Certificate has expired"),
+        ),
+        (
+            "tampered.eml",
+            SmimeStatus::Failed,
+            Some("Message digest mismatch"),
+        ),
+    ] {
+        let raw_message = std::fs::read(resources.join(file_name)).unwrap();
+        let result = Message::parse(&raw_message)
+            .unwrap()
+            .verify_smime(&trust_store, NOW)
+            .unwrap();
+
+        assert_eq!(
+            result.status, expected_status,
+            "{file_name}: {:?}",
+            result.errors
+        );
+        if let Some(expected_error) = expected_error {
+            assert_eq!(
+                result.errors,
+                vec![expected_error.to_string()],
+                "{file_name}"
+            );
+            assert_eq!(result.valid_until, None, "{file_name}");
+        } else {
+            assert_eq!(result.valid_until, Some(VALID_UNTIL), "{file_name}");
+        }
+    }
+
+    // Verified signatures are no longer valid once the chain expires
+    let raw_message = std::fs::read(resources.join("valid.eml")).unwrap();
+    let result = Message::parse(&raw_message)
+        .unwrap()
+        .verify_smime(&trust_store, VALID_UNTIL as i64 + 1)
+        .unwrap();
+    assert_eq!(result.status, SmimeStatus::Failed);
+    assert_eq!(result.errors, vec!["Certificate has expired".to_string()]);
+}
@@ -0,0 +1,284 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::sync::Arc;
+
+use jmap::{thread::summary::ThreadSummary, JMAP};
+use jmap_client::{client::Client, mailbox::Role};
+use jmap_proto::types::{collection::Collection, date::UTCDate, id::Id, property::Property};
+use serde_json::json;
+use store::write::{BatchBuilder, F_CLEAR, F_VALUE};
+
+use crate::jmap::{jmap_json_request, mailbox::destroy_all_mailboxes};
+
+const T: u64 = 1_600_000_000;
+
+pub async fn test(server: Arc<JMAP>, client: &mut Client) {
+    println!("Running Thread Summary tests...");
+
+    let mailbox_id = client
+        .set_default_account_id(Id::new(1).to_string())
+        .mailbox_create("Thread Summary", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+
+    // Thread A has three emails, B one and C two, one of them received after 2106
+    let (a1, thread_a) = import(client, &mailbox_id, "a1", &[], "Project A", T + 1000, true).await;
+    let (a2, _) = import(
+        client,
+        &mailbox_id,
+        "a2",
+        &["a1"],
+        "Project A",
+        T + 5000,
+        true,
+    )
+    .await;
+    let (a3, _) = import(
+        client,
+        &mailbox_id,
+        "a3",
+        &["a1"],
+        "Project A",
+        T + 2000,
+        true,
+    )
+    .await;
+    let (b1, thread_b) = import(client, &mailbox_id, "b1", &[], "Project B", T + 4000, false).await;
+    let (c1, thread_c) = import(client, &mailbox_id, "c1", &[], "Project C", T + 3000, false).await;
+    let (c2, _) = import(
+        client,
+        &mailbox_id,
+        "c2",
+        &["c1"],
+        "Project C",
+        4_400_000_000,
+        true,
+    )
+    .await;
+    assert_summary(&server, thread_a, T + 5000, 3).await;
+    assert_summary(&server, thread_b, T + 4000, 1).await;
+    assert_summary(&server, thread_c, 4_400_000_000, 2).await;
+
+    // Sort by thread aggregates
+    assert_eq!(
+        query(&mailbox_id, json!({}), "threadReceivedAt", true).await,
+        [c2.as_str(), a2.as_str(), b1.as_str()]
+    );
+    assert_eq!(
+        query(&mailbox_id, json!({}), "threadSize", true).await,
+        [a2.as_str(), c2.as_str(), b1.as_str()]
+    );
+
+    // Filter by thread aggregates
+    assert_eq!(
+        query(
+            &mailbox_id,
+            json!({"threadMinSize": 2}),
+            "receivedAt",
+            false
+        )
+        .await,
+        [
+            c2.as_str(),
+            a2.as_str(),
+            a3.as_str(),
+            c1.as_str(),
+            a1.as_str()
+        ]
+    );
+    assert_eq!(
+        query(
+            &mailbox_id,
+            json!({"threadMaxSize": 3}),
+            "receivedAt",
+            false
+        )
+        .await,
+        [c2.as_str(), b1.as_str(), c1.as_str()]
+    );
+    let date = UTCDate::from_timestamp((T + 4500) as i64).to_string();
+    assert_eq!(
+        query(
+            &mailbox_id,
+            json!({"threadReceivedAfter": date}),
+            "receivedAt",
+            false
+        )
+        .await,
+        [
+            c2.as_str(),
+            a2.as_str(),
+            a3.as_str(),
+            c1.as_str(),
+            a1.as_str()
+        ]
+    );
+    assert_eq!(
+        query(
+            &mailbox_id,
+            json!({"threadReceivedBefore": date}),
+            "receivedAt",
+            false
+        )
+        .await,
+        [b1.as_str()]
+    );
+
+    // Removing the most recent email rebuilds the summary
+    client.email_destroy(&a2).await.unwrap();
+    assert_summary(&server, thread_a, T + 2000, 2).await;
+    assert_eq!(
+        query(&mailbox_id, json!({}), "threadReceivedAt", true).await,
+        [c2.as_str(), b1.as_str(), a3.as_str()]
+    );
+    client.email_destroy(&a1).await.unwrap();
+    assert_summary(&server, thread_a, T + 2000, 1).await;
+
+    // Missing summaries are rebuilt before being updated
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(1)
+        .with_collection(Collection::Thread)
+        .update_document(thread_b)
+        .value(Property::Value, (), F_VALUE | F_CLEAR);
+    server.store.write(batch.build()).await.unwrap();
+    import(
+        client,
+        &mailbox_id,
+        "b2",
+        &["b1"],
+        "Project B",
+        T + 7000,
+        true,
+    )
+    .await;
+    assert_summary(&server, thread_b, T + 7000, 2).await;
+
+    // Merged threads are summarized as a whole
+    let (_, thread_x1) = import(client, &mailbox_id, "x1", &[], "Merge", T + 100, true).await;
+    let (_, thread_x2) = import(client, &mailbox_id, "x2", &[], "Merge", T + 200, true).await;
+    assert_ne!(thread_x1, thread_x2);
+    let (_, thread_x) = import(
+        client,
+        &mailbox_id,
+        "x3",
+        &["x1", "x2"],
+        "Merge",
+        T + 300,
+        true,
+    )
+    .await;
+    assert_summary(&server, thread_x, T + 300, 3).await;
+
+    destroy_all_mailboxes(client).await;
+    server.store.assert_is_empty().await;
+}
+
+async fn import(
+    client: &mut Client,
+    mailbox_id: &str,
+    message_id: &str,
+    references: &[&str],
+    subject: &str,
+    received_at: u64,
+    is_seen: bool,
+) -> (String, u32) {
+    let mut email = client
+        .email_import(
+            format!(
+                "From: john@example.com\r\nMessage-ID: <{message_id}@test>\r\n{}Subject: {}{subject}\r\n\r\nTest.\r\n",
+                if !references.is_empty() {
+                    format!(
+                        "References: {}\r\n",
+                        references
+                            .iter()
+                            .map(|id| format!("<{id}@test>"))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )
+                } else {
+                    String::new()
+                },
+                if !references.is_empty() { "Re: " } else { "" },
+            )
+            .into_bytes(),
+            [mailbox_id],
+            if is_seen { Some(["$seen"]) } else { None },
+            Some(received_at as i64),
+        )
+        .await
+        .unwrap();
+    let thread_id = Id::from_bytes(email.thread_id().unwrap().as_bytes())
+        .unwrap()
+        .document_id();
+
+    (email.take_id(), thread_id)
+}
+
+async fn query(
+    mailbox_id: &str,
+    filter: serde_json::Value,
+    sort: &str,
+    collapse: bool,
+) -> Vec<String> {
+    let mut filter = filter;
+    filter["inMailbox"] = mailbox_id.into();
+    let response = jmap_json_request(json!({
+        "using": ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        "methodCalls": [
+            ["Email/query", {
+                "accountId": Id::new(1).to_string(),
+                "filter": filter,
+                "sort": [
+                    {"property": sort, "isAscending": false},
+                    {"property": "receivedAt", "isAscending": false}
+                ],
+                "collapseThreads": collapse
+            }, "0"]
+        ]
+    }))
+    .await;
+
+    response["methodResponses"][0][1]["ids"]
+        .as_array()
+        .unwrap_or_else(|| panic!("Unexpected response: {response}"))
+        .iter()
+        .map(|id| id.as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn assert_summary(server: &JMAP, thread_id: u32, received_at: u64, size: u32) {
+    let summary: ThreadSummary = server
+        .thread_summaries(1, &[thread_id])
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(
+        (summary.received_at, summary.size),
+        (received_at, size),
+        "thread {thread_id}"
+    );
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use directory::config::ConfigDirectory;
+use tokio::sync::mpsc;
+use utils::{config::Config, ipc::UrlFetchRequest};
+
+use crate::smtp::{
+    inbound::{TestMessage, TestQueueEvent},
+    session::{TestSession, VerifyResponse},
+    ParseTestConfig, TestConfig, TestSMTP,
+};
+use smtp::{
+    config::{ConfigContext, IfBlock, MaybeDynValue},
+    core::{Session, SMTP},
+};
+
+const DIRECTORY: &str = r#"
+[directory."local"]
+type = "memory"
+
+[[directory."local".users]]
+name = "john"
+description = "John Doe"
+secret = "secret"
+email = "john@foobar.org"
+
+[[directory."local".users]]
+name = "bill"
+description = "Bill Foobar"
+secret = "p4ssw0rd"
+email = "bill@foobar.org"
+
+[directory."local".lookup]
+domains = ["foobar.org"]
+"#;
+
+const URL_HEADERS: &str = "imap://john@localhost/Sent;UIDVALIDITY=1/;UID=1/;SECTION=HEADER;URLAUTH=submit+john:internal:91354a473744909de610943775f92038";
+const URL_BODY: &str = "imap://john@localhost/Sent;UIDVALIDITY=1/;UID=1/;SECTION=TEXT;URLAUTH=submit+john:internal:91354a473744909de610943775f92038";
+const URL_INVALID: &str = "imap://john@localhost/Sent;UIDVALIDITY=1/;UID=2;URLAUTH=submit+john:internal:91354a473744909de610943775f92038";
+
+#[tokio::test]
+async fn burl() {
+    let mut core = SMTP::test();
+
+    // Create temp dir for queue
+    let mut qr = core.init_test_queue("smtp_burl_test");
+    let directory = Config::parse(DIRECTORY).unwrap().parse_directory().unwrap();
+    let mut config = &mut core.session.config.rcpt;
+    config.directory = IfBlock::new(Some(MaybeDynValue::Static(
+        directory.directories.get("local").unwrap().clone(),
+    )));
+    core.session.config.extensions.burl = r"[{if = 'authenticated-as', ne = '', then = true},
+    {else = false}]"
+        .parse_if(&ConfigContext::new(&[]));
+
+    // Spawn a dummy URL resolver
+    let (url_tx, mut url_rx) = mpsc::channel::<UrlFetchRequest>(128);
+    core.url_tx = url_tx;
+    tokio::spawn(async move {
+        while let Some(request) = url_rx.recv().await {
+            assert_eq!(request.user, "john");
+            let result = if request.url == URL_HEADERS {
+                Some(b"From: john@foobar.org\r\nSubject: BURL test\r\n\r\n".to_vec())
+            } else if request.url == URL_BODY {
+                Some(b"Hello from a stored message.\r\n".to_vec())
+            } else {
+                None
+            };
+            request.result_tx.send(result).unwrap();
+        }
+    });
+
+    // BURL should not be advertised nor allowed to unauthenticated users
+    let mut session = Session::test(core);
+    session.data.remote_ip = "10.0.0.1".parse().unwrap();
+    session.eval_session_params().await;
+    session
+        .ehlo("mx.foobar.org")
+        .await
+        .assert_not_contains("BURL");
+    session.mail_from("john@foobar.org", "250").await;
+    session.rcpt_to("bill@foobar.org", "250").await;
+    session
+        .cmd(&format!("BURL {URL_HEADERS} LAST"), "503 5.5.1")
+        .await;
+    session.rset().await;
+
+    // Authenticated users should see BURL advertised
+    session.data.authenticated_as = "john".to_string();
+    session.ehlo("mx.foobar.org").await.assert_contains("BURL");
+
+    // BURL requires RCPT
+    session.mail_from("john@foobar.org", "250").await;
+    session
+        .cmd(&format!("BURL {URL_HEADERS} LAST"), "503 5.5.1")
+        .await;
+
+    // Unresolvable URLs should be rejected
+    session.rcpt_to("bill@foobar.org", "250").await;
+    session
+        .cmd(&format!("BURL {URL_INVALID} LAST"), "554 5.6.6")
+        .await;
+    qr.assert_empty_queue();
+
+    // Assemble message from multiple URLs
+    session
+        .cmd(&format!("BURL {URL_HEADERS}"), "250 2.5.0")
+        .await;
+    session.cmd(&format!("BURL {URL_BODY} LAST"), "250").await;
+    qr.read_event()
+        .await
+        .unwrap_message()
+        .read_lines()
+        .assert_contains("Subject: BURL test")
+        .assert_contains("Hello from a stored message.");
+}
@@ -34,6 +34,7 @@ use super::{QueueReceiver, ReportReceiver};
 
 pub mod auth;
 pub mod basic;
+pub mod burl;
 pub mod data;
 pub mod dmarc;
 pub mod dnsrbl;
@@ -182,6 +182,7 @@ impl TestConfig for SMTP {
             report: ReportCore::test(),
             sieve: SieveCore::test(),
             delivery_tx: mpsc::channel(1).0,
+            url_tx: mpsc::channel(1).0,
         }
     }
 }
@@ -221,6 +222,7 @@ impl TestConfig for SessionConfig {
             extensions: Extensions {
                 pipelining: IfBlock::new(true),
                 chunking: IfBlock::new(true),
+                burl: IfBlock::new(false),
                 requiretls: IfBlock::new(true),
                 no_soliciting: IfBlock::new("domain.org".to_string().into()),
                 future_release: IfBlock::new(None),
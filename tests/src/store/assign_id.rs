@@ -30,16 +30,27 @@ use store::{write::BatchBuilder, Store};
 pub async fn test(db: Arc<Store>) {
     println!("Running Store ID assignment tests...");
 
-    store::backend::foundationdb::write::ID_ASSIGNMENT_EXPIRY
-        .store(2, std::sync::atomic::Ordering::Relaxed);
+    set_id_assignment_expiry(2);
 
     test_1(db.clone()).await;
     test_2(db.clone()).await;
     test_3(db.clone()).await;
-    test_4(db).await;
+    test_4(db.clone()).await;
+    test_5(db).await;
+
+    set_id_assignment_expiry(60 * 60);
+}
 
+fn set_id_assignment_expiry(expiry: u64) {
+    #[cfg(feature = "foundationdb")]
     store::backend::foundationdb::write::ID_ASSIGNMENT_EXPIRY
-        .store(60 * 60, std::sync::atomic::Ordering::Relaxed);
+        .store(expiry, std::sync::atomic::Ordering::Relaxed);
+    #[cfg(feature = "postgres")]
+    store::backend::postgres::write::ID_ASSIGNMENT_EXPIRY
+        .store(expiry, std::sync::atomic::Ordering::Relaxed);
+    #[cfg(feature = "mysql")]
+    store::backend::mysql::write::ID_ASSIGNMENT_EXPIRY
+        .store(expiry, std::sync::atomic::Ordering::Relaxed);
 }
 
 async fn test_1(db: Arc<Store>) {
@@ -158,3 +169,28 @@ async fn test_4(db: Arc<Store>) {
 
     db.destroy().await;
 }
+
+async fn test_5(db: Arc<Store>) {
+    // Reclaim stale reservations while keeping the fresh ones
+    assert_eq!(db.assign_document_id(0, 0).await.unwrap(), 0);
+    assert_eq!(db.assign_document_id(0, 0).await.unwrap(), 1);
+
+    // Create the second document and let both reservations expire
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .create_document(1);
+    db.write(batch.build()).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    // The stale reservation is reclaimed, the new one is not
+    assert_eq!(db.assign_document_id(0, 0).await.unwrap(), 0);
+    assert_eq!(db.assign_document_id(0, 0).await.unwrap(), 2);
+
+    // Reservations of other collections are not affected
+    assert_eq!(db.assign_document_id(0, 1).await.unwrap(), 0);
+    assert_eq!(db.assign_document_id(0, 1).await.unwrap(), 1);
+
+    db.destroy().await;
+}
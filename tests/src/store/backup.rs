@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2023 Stalwart Labs Ltd.
+ *
+ * This file is part of the Stalwart Mail Server.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of
+ * the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ * in the LICENSE file at the top-level directory of this distribution.
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * You can be released from the requirements of the AGPLv3 license by
+ * purchasing a commercial license. Please contact licensing@stalw.art
+ * for more details.
+*/
+
+use std::{path::Path, sync::Arc};
+
+use store::{BitmapKey, BlobKind, Store};
+
+pub async fn test(db: Arc<Store>, path: &Path) {
+    println!("Running Store backup tests...");
+
+    let blob_kind = BlobKind::Linked {
+        account_id: 0,
+        collection: 0,
+        document_id: 0,
+    };
+    let blob = b"this is a blob".to_vec();
+    db.put_blob(&blob_kind, &blob).await.unwrap();
+
+    // Backup, destroy and restore the database
+    let archive = path.join("backup.bin");
+    db.backup(&archive).await.unwrap();
+    db.destroy().await;
+    db.delete_blob(&blob_kind).await.unwrap();
+    assert!(db
+        .get_blob(&blob_kind, 0..u32::MAX)
+        .await
+        .unwrap()
+        .is_none());
+    db.restore(&archive).await.unwrap();
+
+    assert_eq!(
+        db.get_blob(&blob_kind, 0..u32::MAX).await.unwrap(),
+        Some(blob.clone())
+    );
+
+    // Archives can't be restored over existing data
+    assert!(db.restore(&archive).await.is_err());
+
+    // Export the account and import it into a different one
+    let account_archive = path.join("account.bin");
+    db.export_account(0, &account_archive, &[(1, "jdoe".to_string())])
+        .await
+        .unwrap();
+    assert!(db.restore(&account_archive).await.is_err());
+    assert_eq!(
+        db.import_account(1, &account_archive).await.unwrap(),
+        vec![(1, "jdoe".to_string())]
+    );
+    assert_eq!(
+        db.get_bitmap(BitmapKey::document_ids(0, super::query::COLLECTION_ID))
+            .await
+            .unwrap(),
+        db.get_bitmap(BitmapKey::document_ids(1, super::query::COLLECTION_ID))
+            .await
+            .unwrap(),
+    );
+    assert_eq!(
+        db.get_blob(
+            &BlobKind::Linked {
+                account_id: 1,
+                collection: 0,
+                document_id: 0,
+            },
+            0..u32::MAX
+        )
+        .await
+        .unwrap(),
+        Some(blob)
+    );
+
+    // Run the query tests against the restored data
+    super::query::test(db, false).await;
+}
@@ -61,7 +61,7 @@ pub const FIELDS: [&str; 20] = [
     "url",
 ];
 
-const COLLECTION_ID: u8 = 0;
+pub const COLLECTION_ID: u8 = 0;
 
 enum FieldType {
     Keyword,